@@ -1,8 +1,9 @@
 use criterion::{Criterion, criterion_group, criterion_main};
 use once_cell::sync::Lazy;
 use std::hint::black_box;
+use std::thread;
 use std::time::Duration;
-use string_pipeline::Template;
+use string_pipeline::{CacheScope, Template};
 
 // -----------------------------------------------------------------------------
 // Test data
@@ -11,8 +12,12 @@ use string_pipeline::Template;
 const SMALL_INPUT: &str = "apple,banana,cherry,date,elderberry,fig,grape,honeydew,kiwi,lemon";
 const PADDED_SMALL_INPUT: &str = " apple , banana , cherry , date , elderberry ";
 const USER_RECORD: &str = "john doe admin@example.com";
+const TV_LINE: &str = "src/pipeline/template.rs:1431:        let stripped = strip_ansi(input);";
+const TV_COLORED_LINE: &str = "\x1b[35msrc/pipeline/template.rs\x1b[0m:\x1b[32m1431\x1b[0m:        let stripped = strip_ansi(input);";
 static LARGE_INPUT: Lazy<String> = Lazy::new(|| SMALL_INPUT.repeat(1_000)); // ~600 KB
 static LARGE_MAP_INPUT: Lazy<String> = Lazy::new(|| PADDED_SMALL_INPUT.repeat(1_000));
+// ~6 KB and ~900 items: the largest input still kept in the split cache
+static CACHEABLE_INPUT: Lazy<String> = Lazy::new(|| SMALL_INPUT.repeat(100));
 
 // -----------------------------------------------------------------------------
 // 1. Parsing Benchmarks – How fast can we compile templates?
@@ -60,8 +65,25 @@ fn bench_execution(c: &mut Criterion) {
     // (id, template, input)
     let cases = [
         ("single_block_upper_small", "{upper}", SMALL_INPUT),
+        ("literal_only", "Hello, world!", SMALL_INPUT),
+        ("literal_upper_small", "Hello {upper}!", USER_RECORD),
         ("split_join_small", "{split:,:..|join: }", SMALL_INPUT),
         ("split_join_large", "{split:,:..|join: }", &LARGE_INPUT),
+        (
+            "split_multi_byte_large",
+            "{split:, :..|map:{upper}|join:,}",
+            &LARGE_MAP_INPUT,
+        ),
+        (
+            "split_cache_hit_slice",
+            "{split:,:0..2|join:-}",
+            &CACHEABLE_INPUT,
+        ),
+        (
+            "split_cache_hit_tail",
+            "{split:,:..|slice:-3..|join:-}",
+            &CACHEABLE_INPUT,
+        ),
         (
             "multi_section_format",
             "Name: {split: :0} Surname: {split: :1}",
@@ -78,6 +100,9 @@ fn bench_execution(c: &mut Criterion) {
             SMALL_INPUT,
         ),
         ("map_upper", "{split:,:..|map:{upper}|join:,}", SMALL_INPUT),
+        ("string_chain", "{trim|lower|append:!}", USER_RECORD),
+        ("strip_ansi_plain", "{strip_ansi}", TV_LINE),
+        ("strip_ansi_colored", "{strip_ansi}", TV_COLORED_LINE),
         (
             "map_trim_upper_large",
             "{split:,:..|map:{trim|upper}|join:,}",
@@ -89,6 +114,7 @@ fn bench_execution(c: &mut Criterion) {
             SMALL_INPUT,
         ),
         ("tv_path_last_segment", "{split:/:-1}", "/a/b/c/d.txt"),
+        ("tv_path_basename", "{basename}", "/a/b/c/d.txt"),
         (
             "tv_tabbed_display",
             "{split:\\t:0} ({split:\\t:2})",
@@ -137,6 +163,68 @@ fn bench_structured_inputs(c: &mut Criterion) {
     group.finish();
 }
 
+// -----------------------------------------------------------------------------
+// 4. Buffer reuse – format() against format_into() with a reused context
+// -----------------------------------------------------------------------------
+
+fn bench_format_into(c: &mut Criterion) {
+    let tpl = Template::parse("{split:\\t:0} ({split:\\t:2}) {split:\\t:0}").unwrap();
+    let input = "api\tnginx:latest\tUp 2 hours";
+
+    let mut group = c.benchmark_group("format_into");
+    group.bench_function("format", |b| {
+        b.iter(|| tpl.format(black_box(input)).unwrap())
+    });
+    group.bench_function("format_into", |b| {
+        let mut ctx = tpl.new_context();
+        let mut out = String::new();
+        b.iter(|| {
+            tpl.format_into(black_box(input), &mut ctx, &mut out)
+                .unwrap();
+            out.len()
+        })
+    });
+    group.finish();
+}
+
+// -----------------------------------------------------------------------------
+// 5. Cache scopes – contention on the regex and split caches under 16 threads
+// -----------------------------------------------------------------------------
+
+fn bench_cache_scopes(c: &mut Criterion) {
+    const THREADS: usize = 16;
+    const FORMATS_PER_THREAD: usize = 200;
+
+    let mut group = c.benchmark_group("cache_scope_16_threads");
+    group.sample_size(20);
+    for (name, scope) in [
+        ("global", CacheScope::Global),
+        ("thread", CacheScope::Thread),
+        ("context", CacheScope::Context),
+    ] {
+        let tpl = Template::parse("{split:,:..|filter:^[a-m]|map:{upper}|join:,} {split:,:0}")
+            .unwrap()
+            .with_cache_scope(scope);
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                thread::scope(|s| {
+                    for _ in 0..THREADS {
+                        s.spawn(|| {
+                            let mut ctx = tpl.new_context();
+                            let mut out = String::new();
+                            for _ in 0..FORMATS_PER_THREAD {
+                                tpl.format_into(black_box(SMALL_INPUT), &mut ctx, &mut out)
+                                    .unwrap();
+                            }
+                        });
+                    }
+                })
+            })
+        });
+    }
+    group.finish();
+}
+
 // -----------------------------------------------------------------------------
 // Criterion configuration & entry point
 // -----------------------------------------------------------------------------
@@ -147,6 +235,7 @@ criterion_group! {
         .configure_from_args()
         .sample_size(200)
         .measurement_time(Duration::from_secs(5));
-    targets = bench_parsing, bench_execution, bench_structured_inputs
+    targets = bench_parsing, bench_execution, bench_structured_inputs, bench_format_into,
+        bench_cache_scopes
 }
 criterion_main!(benches);
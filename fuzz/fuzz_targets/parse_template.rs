@@ -0,0 +1,22 @@
+//! Parses arbitrary templates and formats the ones that parse.
+//!
+//! Parsing must return `Ok` or `Err` for every input, never panic or
+//! exhaust the stack. Formatting runs under the untrusted limits so slow
+//! but valid templates do not stall the fuzzer.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use string_pipeline::{ParserConfig, SafetyProfile, Template};
+
+fuzz_target!(|template: &str| {
+    let config = ParserConfig {
+        max_template_len: 4096,
+        ..ParserConfig::default()
+    };
+    if let Ok(template) = Template::parse_with_config(template, &config) {
+        let template = template.with_limits(SafetyProfile::default().limits());
+        let _ = template.format("a,b,c");
+        let _ = template.format("");
+    }
+});
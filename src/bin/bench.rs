@@ -1,21 +1,48 @@
-use clap::{Arg, Command};
+use clap::{Arg, ArgAction, Command};
 use std::collections::HashMap;
-use std::time::{Duration, Instant};
-use string_pipeline::Template;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use string_pipeline::{ProfilingReport, Template};
+
+#[path = "../duration.rs"]
+mod duration;
+
+use duration::format_duration;
 
 #[derive(Debug, Clone)]
 struct BenchmarkResult {
     name: String,
     iterations: usize,
     average_time: Duration,
+    median_time: Duration,
     min_time: Duration,
     max_time: Duration,
+    outliers_removed: usize,
+    profile: Option<ProfilingReport>,
+    memory: Option<MemoryUsage>,
+}
+
+/// Heap and resident memory used by one benchmark, measured in a separate
+/// pass when built with the `alloc-tracking` feature.
+#[derive(Debug, Clone, Copy)]
+struct MemoryUsage {
+    /// Allocations per iteration, counting reallocations
+    allocations: u64,
+    /// Bytes allocated per iteration
+    allocated_bytes: u64,
+    /// Highest heap size reached above the size before the pass
+    peak_heap_bytes: usize,
+    /// Peak resident set size of the process during the pass, on Linux
+    peak_rss_bytes: Option<u64>,
 }
 
 impl BenchmarkResult {
     fn new(name: String, times: Vec<Duration>) -> Self {
         let iterations = times.len();
-        let filtered_times = remove_outliers(times.clone());
+        let mut filtered_times = remove_outliers(times.clone());
+        filtered_times.sort();
 
         let average_time = if filtered_times.is_empty() {
             Duration::from_nanos(0)
@@ -24,6 +51,14 @@ impl BenchmarkResult {
             sum / filtered_times.len() as u32
         };
 
+        // The median is far less sensitive to scheduler noise than the mean,
+        // which makes it the statistic used for baseline comparisons.
+        let median_time = match filtered_times.len() {
+            0 => Duration::from_nanos(0),
+            len if len % 2 == 1 => filtered_times[len / 2],
+            len => (filtered_times[len / 2 - 1] + filtered_times[len / 2]) / 2,
+        };
+
         // Calculate min and max from filtered data (after outlier removal)
         let min_time = filtered_times
             .iter()
@@ -40,8 +75,122 @@ impl BenchmarkResult {
             name,
             iterations,
             average_time,
+            median_time,
             min_time,
             max_time,
+            outliers_removed: iterations - filtered_times.len(),
+            profile: None,
+            memory: None,
+        }
+    }
+
+    fn with_profile(mut self, profile: Option<ProfilingReport>) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    fn with_memory(mut self, memory: Option<MemoryUsage>) -> Self {
+        self.memory = memory;
+        self
+    }
+}
+
+/* ------------------------------------------------------------------------ */
+/*  Allocation tracking                                                     */
+/* ------------------------------------------------------------------------ */
+
+/// A global allocator that counts allocations on top of the system
+/// allocator. Counting costs a few atomic operations per allocation, which
+/// the timings then include.
+#[cfg(feature = "alloc-tracking")]
+mod alloc_tracking {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+    use super::MemoryUsage;
+
+    static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+    static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+    static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+    static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+    struct CountingAllocator;
+
+    #[global_allocator]
+    static GLOBAL: CountingAllocator = CountingAllocator;
+
+    fn record_growth(size: usize) {
+        let live = LIVE_BYTES.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK_BYTES.fetch_max(live, Ordering::Relaxed);
+    }
+
+    // SAFETY: every call is forwarded unchanged to the system allocator
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+            record_growth(layout.size());
+            unsafe { System.alloc(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+            unsafe { System.dealloc(ptr, layout) }
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            ALLOCATED_BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+            if new_size >= layout.size() {
+                record_growth(new_size - layout.size());
+            } else {
+                LIVE_BYTES.fetch_sub(layout.size() - new_size, Ordering::Relaxed);
+            }
+            unsafe { System.realloc(ptr, layout, new_size) }
+        }
+    }
+
+    /// Peak resident set size of the process, from `/proc/self/status`.
+    #[cfg(target_os = "linux")]
+    fn peak_rss() -> Option<u64> {
+        let status = std::fs::read_to_string("/proc/self/status").ok()?;
+        let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+        let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+        Some(kib * 1024)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn peak_rss() -> Option<u64> {
+        None
+    }
+
+    /// Resets the peak resident set size to the current one, where the
+    /// kernel allows it.
+    fn reset_peak_rss() {
+        #[cfg(target_os = "linux")]
+        let _ = std::fs::write("/proc/self/clear_refs", "5");
+    }
+
+    /// Runs `f` `iterations` times and reports the memory it used.
+    pub(super) fn measure(iterations: usize, mut f: impl FnMut()) -> MemoryUsage {
+        reset_peak_rss();
+        let live_before = LIVE_BYTES.load(Ordering::Relaxed);
+        PEAK_BYTES.store(live_before, Ordering::Relaxed);
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+        let allocated_bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+
+        for _ in 0..iterations {
+            f();
+        }
+
+        let runs = iterations.max(1) as u64;
+        MemoryUsage {
+            allocations: (ALLOCATIONS.load(Ordering::Relaxed) - allocations) / runs,
+            allocated_bytes: (ALLOCATED_BYTES.load(Ordering::Relaxed) - allocated_bytes) / runs,
+            peak_heap_bytes: PEAK_BYTES
+                .load(Ordering::Relaxed)
+                .saturating_sub(live_before),
+            peak_rss_bytes: peak_rss(),
         }
     }
 }
@@ -70,10 +219,17 @@ struct BenchmarkSuite {
     test_data: String,
     formatting_test_data: String,
     quiet: bool,
+    profile: bool,
+    /// Template given with `--template` or `--template-file`, benchmarked
+    /// instead of the built-in set
+    custom_template: Option<String>,
+    /// `string-pipeline` binary that formats through its command line
+    /// instead of the library, for `--compare-bin`
+    cli: Option<PathBuf>,
 }
 
 impl BenchmarkSuite {
-    fn new(iterations: usize, quiet: bool) -> Self {
+    fn new(iterations: usize, quiet: bool, profile: bool) -> Self {
         // Create some realistic test data
         let test_data = "apple,banana,cherry,date,elderberry,fig,grape,honeydew,ice_fruit,jackfruit,kiwi,lemon,mango,nectarine,orange,papaya,quince,raspberry,strawberry,tomato,ugli_fruit,vanilla,watermelon,xigua,yellow_apple,zucchini".to_string();
         let formatting_test_data = "john doe admin@example.com".to_string();
@@ -87,9 +243,33 @@ impl BenchmarkSuite {
             test_data,
             formatting_test_data,
             quiet,
+            profile,
+            custom_template: None,
+            cli: None,
         }
     }
 
+    /// Formats by running the `string-pipeline` binary at `path` once per
+    /// iteration. Parsing benchmarks are skipped, as the command line does
+    /// not time parsing on its own.
+    fn with_cli(mut self, path: PathBuf) -> Self {
+        self.cli = Some(path);
+        self
+    }
+
+    /// Uses `input` instead of the built-in fruit list wherever the
+    /// benchmarks format the default test data.
+    fn with_test_data(mut self, input: String) -> Self {
+        self.test_data = input;
+        self
+    }
+
+    /// Benchmarks only `template` instead of the built-in set.
+    fn with_custom_template(mut self, template: Option<String>) -> Self {
+        self.custom_template = template;
+        self
+    }
+
     fn run_all_benchmarks(&self) -> Vec<BenchmarkResult> {
         let mut results = Vec::new();
 
@@ -101,8 +281,14 @@ impl BenchmarkSuite {
             println!();
         }
 
+        if let Some(template_str) = &self.custom_template {
+            return self.run_custom_benchmarks(template_str);
+        }
+
         // Parsing benchmarks
-        results.extend(self.run_parsing_benchmarks());
+        if self.cli.is_none() {
+            results.extend(self.run_parsing_benchmarks());
+        }
 
         // Single operation benchmarks
         results.extend(self.run_single_operation_benchmarks());
@@ -170,6 +356,7 @@ impl BenchmarkSuite {
         let benchmarks = vec![
             ("Single: split", "{split:,:..|join:,}"),
             ("Single: upper", "{upper}"),
+            ("Single: strip_ansi", "{strip_ansi}"),
             ("Single: lower", "{lower}"),
             ("Single: trim", "{trim}"),
             ("Single: reverse", "{reverse}"),
@@ -177,6 +364,8 @@ impl BenchmarkSuite {
             ("Single: unique", "{split:,:..|unique|join:,}"),
             ("Single: replace", "{replace:s/a/A/g}"),
             ("Single: filter", "{split:,:..|filter:^[a-m]|join:,}"),
+            ("Single: literal only", "Hello, world!"),
+            ("Single: literal + upper", "Hello {upper}!"),
         ];
 
         benchmarks
@@ -224,6 +413,7 @@ impl BenchmarkSuite {
                 "Name: {split: :0} Surname: {split: :1} Email: {split: :2}",
             ),
             ("Multi: tv path last segment", "{split:/:-1}"),
+            ("Multi: tv path basename", "{basename}"),
             ("Multi: tv tabbed display", "{split:\\t:0} ({split:\\t:2})"),
             (
                 "Multi: tv editor command",
@@ -234,6 +424,11 @@ impl BenchmarkSuite {
                 "Multi: split + sort + unique + join",
                 "{split:,:..|sort|unique|join:+}",
             ),
+            ("Multi: split all path components", "{split:/:..}"),
+            (
+                "Multi: uppercase all path components",
+                "{split:/:..|map:{upper}|join:/}",
+            ),
         ];
 
         benchmarks
@@ -265,6 +460,13 @@ impl BenchmarkSuite {
                     "Multi: tv display suffix" => {
                         self.benchmark_template_with_input(name, template_str, "entry_12345")
                     }
+                    "Multi: split all path components" | "Multi: uppercase all path components" => {
+                        self.benchmark_template_with_input(
+                            name,
+                            template_str,
+                            "/home/user/projects/string_pipeline/src/pipeline/template.rs",
+                        )
+                    }
                     _ => self.benchmark_template(name, template_str),
                 };
                 if !self.quiet {
@@ -385,6 +587,32 @@ impl BenchmarkSuite {
             .collect()
     }
 
+    fn run_custom_benchmarks(&self, template_str: &str) -> Vec<BenchmarkResult> {
+        if !self.quiet {
+            println!("🔸 Running custom template benchmarks...");
+        }
+
+        let mut results = Vec::new();
+        for name in ["Parse: custom template", "Custom: format"] {
+            if self.cli.is_some() && name.starts_with("Parse:") {
+                continue;
+            }
+            if !self.quiet {
+                print!("  {name} ... ");
+            }
+            let result = if name.starts_with("Parse:") {
+                self.benchmark_template_parsing(name, template_str)
+            } else {
+                self.benchmark_template(name, template_str)
+            };
+            if !self.quiet {
+                println!("✓ avg: {:?}", result.average_time);
+            }
+            results.push(result);
+        }
+        results
+    }
+
     fn benchmark_template(&self, name: &str, template_str: &str) -> BenchmarkResult {
         self.benchmark_template_with_input(name, template_str, &self.test_data)
     }
@@ -404,7 +632,9 @@ impl BenchmarkSuite {
             times.push(start.elapsed());
         }
 
-        BenchmarkResult::new(name.to_string(), times)
+        BenchmarkResult::new(name.to_string(), times).with_memory(self.measure_memory(|| {
+            let _ = Template::parse(template_str);
+        }))
     }
 
     fn benchmark_template_with_input(
@@ -413,6 +643,10 @@ impl BenchmarkSuite {
         template_str: &str,
         input: &str,
     ) -> BenchmarkResult {
+        if let Some(bin) = &self.cli {
+            return self.benchmark_cli(bin, name, template_str, input);
+        }
+
         let template = Template::parse(template_str)
             .unwrap_or_else(|e| panic!("Failed to parse template '{template_str}': {e}"));
 
@@ -436,19 +670,98 @@ impl BenchmarkSuite {
         }
 
         BenchmarkResult::new(name.to_string(), times)
+            .with_profile(self.profile_template(&template, input))
+            .with_memory(self.measure_memory(|| {
+                let _ = template.format(input);
+            }))
+    }
+
+    /// Times whole runs of the `string-pipeline` binary `bin`, process
+    /// startup included.
+    ///
+    /// A binary that fails on the template, such as an older version without
+    /// one of its operations, gives a result without measurements.
+    fn benchmark_cli(
+        &self,
+        bin: &Path,
+        name: &str,
+        template_str: &str,
+        input: &str,
+    ) -> BenchmarkResult {
+        let run = || -> Result<Duration, String> {
+            let start = Instant::now();
+            let output = std::process::Command::new(bin)
+                .args(["--", template_str, input])
+                .output()
+                .map_err(|e| format!("Failed to run '{}': {e}", bin.display()))?;
+            let elapsed = start.elapsed();
+            if output.status.success() {
+                Ok(elapsed)
+            } else {
+                Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+            }
+        };
+
+        let mut times = Vec::with_capacity(self.iterations);
+        let mut measure = || -> Result<(), String> {
+            for _ in 0..self.warmup_iterations {
+                run()?;
+            }
+            for _ in 0..self.iterations {
+                times.push(run()?);
+            }
+            Ok(())
+        };
+        if let Err(e) = measure() {
+            eprintln!("Warning: '{}' failed on {name}: {e}", bin.display());
+            times.clear();
+        }
+        BenchmarkResult::new(name.to_string(), times)
+    }
+
+    /// Runs a separate pass counting allocations when built with the
+    /// `alloc-tracking` feature.
+    #[cfg(feature = "alloc-tracking")]
+    fn measure_memory(&self, f: impl FnMut()) -> Option<MemoryUsage> {
+        Some(alloc_tracking::measure(self.iterations, f))
+    }
+
+    #[cfg(not(feature = "alloc-tracking"))]
+    fn measure_memory(&self, _f: impl FnMut()) -> Option<MemoryUsage> {
+        None
+    }
+
+    /// Runs a separate profiled pass so that instrumentation overhead does
+    /// not affect the timed measurements above.
+    fn profile_template(&self, template: &Template, input: &str) -> Option<ProfilingReport> {
+        if !self.profile {
+            return None;
+        }
+
+        let template = template.clone().with_profiling(true);
+        for _ in 0..self.iterations {
+            let _ = template.format(input);
+        }
+        template.profiling_report()
     }
 }
 
-fn format_duration(duration: Duration) -> String {
-    let nanos = duration.as_nanos();
-    if nanos < 1_000 {
-        format!("{nanos}ns")
-    } else if nanos < 1_000_000 {
-        format!("{:.2}μs", nanos as f64 / 1_000.0)
-    } else if nanos < 1_000_000_000 {
-        format!("{:.2}ms", nanos as f64 / 1_000_000.0)
+/// Returns the display name and JSON key of a benchmark's category.
+fn category_of(name: &str) -> (&'static str, &'static str) {
+    if name.starts_with("Single:") {
+        ("Single Operations", "single_operations")
+    } else if name.starts_with("Parse:") {
+        ("Parsing", "parsing")
+    } else if name.starts_with("Multi:") {
+        ("Multiple Simple Operations", "multiple_simple_operations")
+    } else if name.starts_with("Map:") {
+        ("Map Operations", "map_operations")
+    } else if name.starts_with("Complex:") {
+        ("Complex Operations", "complex_operations")
+    } else if name.starts_with("Custom:") {
+        ("Custom Template", "custom_template")
     } else {
-        format!("{:.2}s", duration.as_secs_f64())
+        ("Other", "other")
     }
 }
 
@@ -468,16 +781,17 @@ fn print_text_report(results: &[BenchmarkResult], total_time: Duration, warmup_i
 
     println!("\n📈 Detailed Results:");
     println!(
-        "{:<50} {:>12} {:>12} {:>12}",
-        "Benchmark", "Average", "Min", "Max"
+        "{:<50} {:>12} {:>12} {:>12} {:>12}",
+        "Benchmark", "Average", "Median", "Min", "Max"
     );
-    println!("{}", "-".repeat(88));
+    println!("{}", "-".repeat(101));
 
     for result in results {
         println!(
-            "{:<50} {:>12} {:>12} {:>12}",
+            "{:<50} {:>12} {:>12} {:>12} {:>12}",
             result.name,
             format_duration(result.average_time),
+            format_duration(result.median_time),
             format_duration(result.min_time),
             format_duration(result.max_time)
         );
@@ -486,20 +800,10 @@ fn print_text_report(results: &[BenchmarkResult], total_time: Duration, warmup_i
     // Category analysis
     let mut categories: HashMap<&str, Vec<&BenchmarkResult>> = HashMap::new();
     for result in results {
-        let category = if result.name.starts_with("Single:") {
-            "Single Operations"
-        } else if result.name.starts_with("Parse:") {
-            "Parsing"
-        } else if result.name.starts_with("Multi:") {
-            "Multiple Simple Operations"
-        } else if result.name.starts_with("Map:") {
-            "Map Operations"
-        } else if result.name.starts_with("Complex:") {
-            "Complex Operations"
-        } else {
-            "Other"
-        };
-        categories.entry(category).or_default().push(result);
+        categories
+            .entry(category_of(&result.name).0)
+            .or_default()
+            .push(result);
     }
 
     println!("\n📋 Performance by Category:");
@@ -533,6 +837,712 @@ fn print_text_report(results: &[BenchmarkResult], total_time: Duration, warmup_i
         );
         println!();
     }
+
+    if results.iter().any(|r| r.memory.is_some()) {
+        println!("🧠 Memory Usage (per iteration):");
+        println!("{}", "-".repeat(101));
+        println!(
+            "{:<50} {:>12} {:>12} {:>12} {:>12}",
+            "Benchmark", "Allocations", "Allocated", "Peak heap", "Peak RSS"
+        );
+        for result in results {
+            let Some(memory) = result.memory else {
+                continue;
+            };
+            println!(
+                "{:<50} {:>12} {:>12} {:>12} {:>12}",
+                result.name,
+                memory.allocations,
+                format_bytes(memory.allocated_bytes),
+                format_bytes(memory.peak_heap_bytes as u64),
+                memory
+                    .peak_rss_bytes
+                    .map_or_else(|| "-".to_string(), format_bytes)
+            );
+        }
+        println!();
+        println!("Note: peak heap is the most memory held at once during all iterations.");
+        println!();
+    }
+
+    let mut profile = ProfilingReport::default();
+    for report in results.iter().filter_map(|r| r.profile.as_ref()) {
+        profile.merge(report);
+    }
+    if !profile.is_empty() {
+        println!("🔬 Operation Profile (all benchmarks):");
+        println!("{}", "-".repeat(80));
+        println!(
+            "{:<16} {:>14} {:>14} {:>14}",
+            "Operation", "Calls", "Total", "Average"
+        );
+        for op in profile.operations() {
+            println!(
+                "{:<16} {:>14} {:>14} {:>14}",
+                op.operation,
+                op.invocations,
+                format_duration(op.total_time),
+                format_duration(op.average_time())
+            );
+        }
+        println!();
+        println!("Note: `map` totals include the time of their inner operations.");
+    }
+}
+
+/// Formats a byte count with binary units, like `1.5 KiB`.
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/* ------------------------------------------------------------------------ */
+/*  JSON output                                                             */
+/* ------------------------------------------------------------------------ */
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Formats the current time as an RFC 3339 UTC timestamp.
+fn utc_timestamp() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+
+    // Civil-from-days conversion (proleptic Gregorian calendar)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+fn push_duration_fields(out: &mut String, indent: &str, key: &str, duration: Duration) {
+    let _ = writeln!(out, "{indent}\"{key}_ns\": {},", duration.as_nanos());
+    let _ = writeln!(
+        out,
+        "{indent}\"{key}_formatted\": {},",
+        json_string(&format_duration(duration))
+    );
+}
+
+fn print_json_report(results: &[BenchmarkResult], total_time: Duration, warmup_iterations: usize) {
+    let mut out = String::new();
+    out.push_str("{\n  \"summary\": {\n");
+    let _ = writeln!(out, "    \"total_benchmarks\": {},", results.len());
+    push_duration_fields(&mut out, "    ", "total_execution_time", total_time);
+    let _ = writeln!(
+        out,
+        "    \"iterations_per_benchmark\": {},",
+        results.first().map(|r| r.iterations).unwrap_or(0)
+    );
+    out.push_str("    \"outlier_removal_method\": \"Top and bottom 5% removed\",\n");
+    let _ = writeln!(
+        out,
+        "    \"warmup_iterations_per_benchmark\": {warmup_iterations}"
+    );
+    out.push_str("  },\n  \"categories\": {");
+
+    // Preserve run order of categories and benchmarks
+    let mut categories: Vec<(&str, Vec<&BenchmarkResult>)> = Vec::new();
+    for result in results {
+        let key = category_of(&result.name).1;
+        match categories.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, list)) => list.push(result),
+            None => categories.push((key, vec![result])),
+        }
+    }
+
+    for (ci, (key, list)) in categories.iter().enumerate() {
+        let _ = write!(out, "{}\n    \"{key}\": [", if ci == 0 { "" } else { "," });
+        for (ri, result) in list.iter().enumerate() {
+            out.push_str(if ri == 0 { "\n" } else { ",\n" });
+            out.push_str("      {\n");
+            let indent = "        ";
+            let _ = writeln!(out, "{indent}\"name\": {},", json_string(&result.name));
+            let _ = writeln!(out, "{indent}\"iterations\": {},", result.iterations);
+            push_duration_fields(&mut out, indent, "average_time", result.average_time);
+            push_duration_fields(&mut out, indent, "median_time", result.median_time);
+            push_duration_fields(&mut out, indent, "min_time", result.min_time);
+            push_duration_fields(&mut out, indent, "max_time", result.max_time);
+            let _ = writeln!(
+                out,
+                "{indent}\"outliers_removed\": {},",
+                result.outliers_removed
+            );
+            if let Some(memory) = result.memory {
+                let _ = writeln!(
+                    out,
+                    "{indent}\"allocations_per_iteration\": {},",
+                    memory.allocations
+                );
+                let _ = writeln!(
+                    out,
+                    "{indent}\"allocated_bytes_per_iteration\": {},",
+                    memory.allocated_bytes
+                );
+                let _ = writeln!(
+                    out,
+                    "{indent}\"peak_heap_bytes\": {},",
+                    memory.peak_heap_bytes
+                );
+                let _ = writeln!(
+                    out,
+                    "{indent}\"peak_rss_bytes\": {},",
+                    memory
+                        .peak_rss_bytes
+                        .map_or_else(|| "null".to_string(), |bytes| bytes.to_string())
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{indent}\"total_raw_measurements\": {}",
+                result.iterations
+            );
+            out.push_str("      }");
+        }
+        out.push_str("\n    ]");
+    }
+    out.push_str("\n  },\n");
+
+    let mut profile = ProfilingReport::default();
+    for report in results.iter().filter_map(|r| r.profile.as_ref()) {
+        profile.merge(report);
+    }
+    if !profile.is_empty() {
+        out.push_str("  \"operation_profile\": [");
+        for (i, op) in profile.operations().iter().enumerate() {
+            let _ = write!(
+                out,
+                "{}\n    {{\"operation\": {}, \"invocations\": {}, \"total_time_ns\": {}, \"average_time_ns\": {}}}",
+                if i == 0 { "" } else { "," },
+                json_string(&op.operation),
+                op.invocations,
+                op.total_time.as_nanos(),
+                op.average_time().as_nanos()
+            );
+        }
+        out.push_str("\n  ],\n");
+    }
+
+    let _ = writeln!(out, "  \"timestamp\": {},", json_string(&utc_timestamp()));
+    let _ = writeln!(
+        out,
+        "  \"version\": {}",
+        json_string(env!("CARGO_PKG_VERSION"))
+    );
+    out.push('}');
+    println!("{out}");
+}
+
+/* ------------------------------------------------------------------------ */
+/*  Baseline comparison                                                     */
+/* ------------------------------------------------------------------------ */
+
+/// Minimal JSON value, sufficient for reading baseline files.
+#[derive(Debug)]
+enum Json {
+    /// `true`, `false`, or `null`; their values are never needed here.
+    Literal,
+    Number(f64),
+    Str(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+struct JsonParser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn parse(text: &'a str) -> Result<Json, String> {
+        let mut parser = JsonParser {
+            bytes: text.as_bytes(),
+            pos: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_ws();
+        if parser.pos != parser.bytes.len() {
+            return Err(format!("trailing characters at byte {}", parser.pos));
+        }
+        Ok(value)
+    }
+
+    fn skip_ws(&mut self) {
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(|b| b.is_ascii_whitespace())
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        self.skip_ws();
+        if self.bytes.get(self.pos) == Some(&byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!("expected '{}' at byte {}", byte as char, self.pos))
+        }
+    }
+
+    fn literal(&mut self, word: &str) -> Result<Json, String> {
+        if self.bytes[self.pos..].starts_with(word.as_bytes()) {
+            self.pos += word.len();
+            Ok(Json::Literal)
+        } else {
+            Err(format!("invalid literal at byte {}", self.pos))
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_ws();
+        match self.bytes.get(self.pos) {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => self.string().map(Json::Str),
+            Some(b't') => self.literal("true"),
+            Some(b'f') => self.literal("false"),
+            Some(b'n') => self.literal("null"),
+            Some(_) => self.number(),
+            None => Err("unexpected end of input".to_string()),
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect(b'{')?;
+        let mut fields = Vec::new();
+        self.skip_ws();
+        if self.bytes.get(self.pos) == Some(&b'}') {
+            self.pos += 1;
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            self.skip_ws();
+            let key = self.string()?;
+            self.expect(b':')?;
+            fields.push((key, self.value()?));
+            self.skip_ws();
+            match self.bytes.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Json::Object(fields));
+                }
+                _ => return Err(format!("expected ',' or '}}' at byte {}", self.pos)),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        self.skip_ws();
+        if self.bytes.get(self.pos) == Some(&b']') {
+            self.pos += 1;
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_ws();
+            match self.bytes.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Json::Array(items));
+                }
+                _ => return Err(format!("expected ',' or ']' at byte {}", self.pos)),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if self.bytes.get(self.pos) != Some(&b'"') {
+            return Err(format!("expected string at byte {}", self.pos));
+        }
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let start = self.pos;
+            while self
+                .bytes
+                .get(self.pos)
+                .is_some_and(|b| *b != b'"' && *b != b'\\')
+            {
+                self.pos += 1;
+            }
+            out.push_str(
+                std::str::from_utf8(&self.bytes[start..self.pos]).map_err(|e| e.to_string())?,
+            );
+            match self.bytes.get(self.pos) {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                Some(b'\\') => {
+                    let escaped = *self
+                        .bytes
+                        .get(self.pos + 1)
+                        .ok_or("unterminated escape sequence")?;
+                    self.pos += 2;
+                    match escaped {
+                        b'n' => out.push('\n'),
+                        b't' => out.push('\t'),
+                        b'r' => out.push('\r'),
+                        b'b' => out.push('\u{8}'),
+                        b'f' => out.push('\u{c}'),
+                        b'u' => {
+                            let hex = self
+                                .bytes
+                                .get(self.pos..self.pos + 4)
+                                .and_then(|h| std::str::from_utf8(h).ok())
+                                .and_then(|h| u32::from_str_radix(h, 16).ok())
+                                .ok_or("invalid unicode escape")?;
+                            self.pos += 4;
+                            out.push(char::from_u32(hex).unwrap_or('\u{fffd}'));
+                        }
+                        other => out.push(other as char),
+                    }
+                }
+                _ => return Err("unterminated string".to_string()),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.pos;
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(|b| b.is_ascii_digit() || matches!(b, b'-' | b'+' | b'.' | b'e' | b'E'))
+        {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.bytes[start..self.pos])
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .map(Json::Number)
+            .ok_or_else(|| format!("invalid value at byte {start}"))
+    }
+}
+
+/// Loads per-benchmark median times (in nanoseconds) from a JSON report.
+///
+/// Reports written before medians were recorded fall back to the average.
+fn load_baseline(path: &str) -> Result<HashMap<String, f64>, String> {
+    let text =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read baseline '{path}': {e}"))?;
+    let json = JsonParser::parse(&text).map_err(|e| format!("Invalid baseline '{path}': {e}"))?;
+
+    let Some(Json::Object(categories)) = json.get("categories") else {
+        return Err(format!(
+            "Invalid baseline '{path}': missing \"categories\" object"
+        ));
+    };
+
+    let mut baseline = HashMap::new();
+    for (_, list) in categories {
+        let Json::Array(entries) = list else { continue };
+        for entry in entries {
+            let name = entry.get("name").and_then(Json::as_str);
+            let time = entry
+                .get("median_time_ns")
+                .or_else(|| entry.get("average_time_ns"))
+                .and_then(Json::as_f64);
+            if let (Some(name), Some(time)) = (name, time) {
+                baseline.insert(name.to_string(), time);
+            }
+        }
+    }
+    Ok(baseline)
+}
+
+/// Compares results against a baseline and reports changes.
+///
+/// Returns the number of benchmarks whose median time grew by more than
+/// `threshold` percent.
+fn compare_with_baseline(
+    results: &[BenchmarkResult],
+    baseline: &HashMap<String, f64>,
+    threshold: f64,
+    out: &mut dyn std::io::Write,
+) -> usize {
+    let mut regressions = 0;
+
+    let _ = writeln!(
+        out,
+        "\n🔁 Baseline Comparison (median, threshold {threshold}%):"
+    );
+    let _ = writeln!(
+        out,
+        "{:<50} {:>12} {:>12} {:>10}",
+        "Benchmark", "Baseline", "Current", "Change"
+    );
+    let _ = writeln!(out, "{}", "-".repeat(87));
+
+    for result in results {
+        let Some(&base_ns) = baseline.get(&result.name) else {
+            let _ = writeln!(
+                out,
+                "{:<50} {:>12} {:>12} {:>10}",
+                result.name,
+                "-",
+                format_duration(result.median_time),
+                "new"
+            );
+            continue;
+        };
+
+        let current_ns = result.median_time.as_nanos() as f64;
+        let change = if base_ns > 0.0 {
+            (current_ns - base_ns) / base_ns * 100.0
+        } else {
+            0.0
+        };
+        let marker = if change > threshold {
+            regressions += 1;
+            "❌"
+        } else if change < -threshold {
+            "🚀"
+        } else {
+            ""
+        };
+
+        let _ = writeln!(
+            out,
+            "{:<50} {:>12} {:>12} {:>+9.1}% {marker}",
+            result.name,
+            format_duration(Duration::from_nanos(base_ns as u64)),
+            format_duration(result.median_time),
+            change
+        );
+    }
+
+    let missing = baseline
+        .keys()
+        .filter(|name| !results.iter().any(|r| &r.name == *name))
+        .count();
+    if missing > 0 {
+        let _ = writeln!(out, "\n{missing} baseline benchmark(s) no longer exist");
+    }
+
+    let _ = writeln!(
+        out,
+        "\n{regressions} regression(s) above {threshold}% out of {} benchmarks",
+        results.len()
+    );
+
+    regressions
+}
+
+/* ------------------------------------------------------------------------ */
+/*  Binary comparison                                                       */
+/* ------------------------------------------------------------------------ */
+
+/// Returns the `string-pipeline` binary built next to this one.
+fn sibling_cli() -> Result<PathBuf, String> {
+    let exe = std::env::current_exe()
+        .map_err(|e| format!("Failed to locate the benchmark binary: {e}"))?;
+    let path = exe.with_file_name(format!("string-pipeline{}", std::env::consts::EXE_SUFFIX));
+    if path.is_file() {
+        Ok(path)
+    } else {
+        Err(format!(
+            "No string-pipeline binary at '{}'; build it with the same profile first",
+            path.display()
+        ))
+    }
+}
+
+/// Speedup of the current binary over the other one for a benchmark, or
+/// `None` when either binary failed on it.
+fn speedup(current: &BenchmarkResult, other: &BenchmarkResult) -> Option<f64> {
+    if current.iterations == 0 || other.iterations == 0 || current.median_time.is_zero() {
+        return None;
+    }
+    Some(other.median_time.as_secs_f64() / current.median_time.as_secs_f64())
+}
+
+/// Geometric mean of the speedups of the benchmarks both binaries ran.
+fn mean_speedup(current: &[BenchmarkResult], other: &[BenchmarkResult]) -> Option<f64> {
+    let speedups: Vec<f64> = current
+        .iter()
+        .zip(other)
+        .filter_map(|(c, o)| speedup(c, o))
+        .collect();
+    if speedups.is_empty() {
+        return None;
+    }
+    let log_sum: f64 = speedups.iter().map(|s| s.ln()).sum();
+    Some((log_sum / speedups.len() as f64).exp())
+}
+
+/// Prints the side-by-side table of `--compare-bin`.
+///
+/// Both result lists come from the same suite, so they hold the same
+/// benchmarks in the same order.
+fn print_comparison_report(
+    current_bin: &Path,
+    other_bin: &Path,
+    current: &[BenchmarkResult],
+    other: &[BenchmarkResult],
+) {
+    let median = |result: &BenchmarkResult| {
+        if result.iterations == 0 {
+            "failed".to_string()
+        } else {
+            format_duration(result.median_time)
+        }
+    };
+
+    println!("\n{}", "=".repeat(80));
+    println!("                         BINARY COMPARISON");
+    println!("{}", "=".repeat(80));
+    println!("\n• Current: {}", current_bin.display());
+    println!("• Other:   {}", other_bin.display());
+    println!(
+        "• Median time per run, process startup included; speedup above 1.00x means current is faster"
+    );
+
+    println!(
+        "\n{:<50} {:>12} {:>12} {:>10}",
+        "Benchmark", "Current", "Other", "Speedup"
+    );
+    println!("{}", "-".repeat(87));
+    for (c, o) in current.iter().zip(other) {
+        println!(
+            "{:<50} {:>12} {:>12} {:>10}",
+            c.name,
+            median(c),
+            median(o),
+            speedup(c, o).map_or_else(|| "-".to_string(), |s| format!("{s:.2}x"))
+        );
+    }
+
+    if let Some(mean) = mean_speedup(current, other) {
+        println!("\nGeometric mean speedup: {mean:.2}x");
+    }
+}
+
+/// Prints the `--compare-bin` results as JSON.
+fn print_comparison_json(
+    current_bin: &Path,
+    other_bin: &Path,
+    current: &[BenchmarkResult],
+    other: &[BenchmarkResult],
+) {
+    let median_ns = |result: &BenchmarkResult| {
+        if result.iterations == 0 {
+            "null".to_string()
+        } else {
+            result.median_time.as_nanos().to_string()
+        }
+    };
+    let float =
+        |value: Option<f64>| value.map_or_else(|| "null".to_string(), |v| format!("{v:.4}"));
+
+    let mut out = String::from("{\n");
+    let _ = writeln!(
+        out,
+        "  \"current_bin\": {},",
+        json_string(&current_bin.display().to_string())
+    );
+    let _ = writeln!(
+        out,
+        "  \"other_bin\": {},",
+        json_string(&other_bin.display().to_string())
+    );
+    out.push_str("  \"benchmarks\": [");
+    for (i, (c, o)) in current.iter().zip(other).enumerate() {
+        let _ = write!(
+            out,
+            "{}\n    {{\"name\": {}, \"current_median_time_ns\": {}, \"other_median_time_ns\": {}, \"speedup\": {}}}",
+            if i == 0 { "" } else { "," },
+            json_string(&c.name),
+            median_ns(c),
+            median_ns(o),
+            float(speedup(c, o))
+        );
+    }
+    out.push_str("\n  ],\n");
+    let _ = writeln!(
+        out,
+        "  \"mean_speedup\": {},",
+        float(mean_speedup(current, other))
+    );
+    let _ = writeln!(out, "  \"timestamp\": {},", json_string(&utc_timestamp()));
+    let _ = writeln!(
+        out,
+        "  \"version\": {}",
+        json_string(env!("CARGO_PKG_VERSION"))
+    );
+    out.push('}');
+    println!("{out}");
 }
 
 fn main() {
@@ -547,6 +1557,72 @@ fn main() {
                 .help("Number of iterations to run for each benchmark")
                 .default_value("1000"),
         )
+        .arg(
+            Arg::new("format")
+                .short('f')
+                .long("format")
+                .value_name("FORMAT")
+                .help("Output format")
+                .value_parser(["text", "json"])
+                .default_value("text"),
+        )
+        .arg(
+            Arg::new("baseline")
+                .short('b')
+                .long("baseline")
+                .value_name("FILE")
+                .help("Compare median times against a JSON report saved with --format json"),
+        )
+        .arg(
+            Arg::new("check")
+                .long("check")
+                .help("Exit with a non-zero status if any benchmark regressed beyond the threshold")
+                .requires("baseline")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("threshold")
+                .long("threshold")
+                .value_name("PERCENT")
+                .help("Allowed median slowdown before a benchmark counts as a regression")
+                .value_parser(clap::value_parser!(f64))
+                .default_value("10"),
+        )
+        .arg(
+            Arg::new("template")
+                .short('t')
+                .long("template")
+                .value_name("TEMPLATE")
+                .help("Benchmark this template instead of the built-in set"),
+        )
+        .arg(
+            Arg::new("template_file")
+                .long("template-file")
+                .value_name("FILE")
+                .help("Benchmark the template in FILE instead of the built-in set")
+                .conflicts_with("template"),
+        )
+        .arg(
+            Arg::new("input_file")
+                .short('i')
+                .long("input-file")
+                .value_name("FILE")
+                .help("Format the contents of FILE instead of the built-in test data"),
+        )
+        .arg(
+            Arg::new("compare_bin")
+                .long("compare-bin")
+                .value_name("PATH")
+                .help("Run the suite through the string-pipeline CLI next to this binary and through PATH, and compare them")
+                .conflicts_with_all(["baseline", "profile"]),
+        )
+        .arg(
+            Arg::new("profile")
+                .short('p')
+                .long("profile")
+                .help("Record per-operation timings in a separate pass and report them")
+                .action(ArgAction::SetTrue),
+        )
         .get_matches();
 
     let iterations: usize = matches
@@ -559,10 +1635,86 @@ fn main() {
         eprintln!("Warning: Running with less than 10 iterations may produce unreliable results");
     }
 
-    let suite = BenchmarkSuite::new(iterations, false);
+    let profile = matches.get_flag("profile");
+    let json = matches.get_one::<String>("format").map(String::as_str) == Some("json");
+    let threshold = *matches.get_one::<f64>("threshold").unwrap();
+
+    // Load the baseline up front so a bad path fails before the long run
+    let baseline = matches.get_one::<String>("baseline").map(|path| {
+        load_baseline(path).unwrap_or_else(|e| {
+            eprintln!("{e}");
+            std::process::exit(2);
+        })
+    });
+
+    // Read and check the custom template and input before the long run
+    let read = |path: &String, what: &str| {
+        fs::read_to_string(path).unwrap_or_else(|e| {
+            eprintln!("Failed to read {what} '{path}': {e}");
+            std::process::exit(2);
+        })
+    };
+    let custom_template = matches.get_one::<String>("template").cloned().or_else(|| {
+        matches
+            .get_one::<String>("template_file")
+            .map(|path| read(path, "template file").trim().to_string())
+    });
+    if let Some(template) = &custom_template
+        && let Err(e) = Template::parse(template)
+    {
+        eprintln!("Error parsing template: {e}");
+        std::process::exit(2);
+    }
+
+    let mut suite =
+        BenchmarkSuite::new(iterations, json, profile).with_custom_template(custom_template);
+    if let Some(path) = matches.get_one::<String>("input_file") {
+        suite = suite.with_test_data(read(path, "input file").trim_end().to_string());
+    }
+
+    if let Some(other_bin) = matches.get_one::<String>("compare_bin") {
+        let other_bin = PathBuf::from(other_bin);
+        let current_bin = sibling_cli().unwrap_or_else(|e| {
+            eprintln!("{e}");
+            std::process::exit(2);
+        });
+        if !other_bin.is_file() {
+            eprintln!("No binary at '{}'", other_bin.display());
+            std::process::exit(2);
+        }
+
+        let suite = suite.with_cli(current_bin.clone());
+        let current = suite.run_all_benchmarks();
+        let suite = suite.with_cli(other_bin.clone());
+        let other = suite.run_all_benchmarks();
+        if json {
+            print_comparison_json(&current_bin, &other_bin, &current, &other);
+        } else {
+            print_comparison_report(&current_bin, &other_bin, &current, &other);
+        }
+        return;
+    }
+
     let start_time = Instant::now();
     let results = suite.run_all_benchmarks();
     let total_time = start_time.elapsed();
 
-    print_text_report(&results, total_time, suite.warmup_iterations);
+    if json {
+        print_json_report(&results, total_time, suite.warmup_iterations);
+    } else {
+        print_text_report(&results, total_time, suite.warmup_iterations);
+    }
+
+    if let Some(baseline) = baseline {
+        // Keep stdout valid JSON by sending the comparison to stderr
+        let regressions = if json {
+            compare_with_baseline(&results, &baseline, threshold, &mut std::io::stderr())
+        } else {
+            compare_with_baseline(&results, &baseline, threshold, &mut std::io::stdout())
+        };
+
+        if matches.get_flag("check") && regressions > 0 {
+            std::process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,19 @@
+//! Duration formatting shared by the `string-pipeline` and
+//! `string-pipeline-bench` binaries.
+
+use std::time::Duration;
+
+/// Format a duration with the largest unit that keeps it above one, from
+/// nanoseconds to seconds
+pub fn format_duration(duration: Duration) -> String {
+    let nanos = duration.as_nanos();
+    if nanos < 1_000 {
+        format!("{nanos}ns")
+    } else if nanos < 1_000_000 {
+        format!("{:.2}μs", nanos as f64 / 1_000.0)
+    } else if nanos < 1_000_000_000 {
+        format!("{:.2}ms", nanos as f64 / 1_000_000.0)
+    } else {
+        format!("{:.2}s", duration.as_secs_f64())
+    }
+}
@@ -32,32 +32,74 @@
 //! ### Core Operations (20+ Available)
 //!
 //! **🔪 Text Splitting & Joining**
-//! - **`split:sep:range`** - Split text and optionally select range
-//! - **`join:sep`** - Join list items with separator
+//! - **`split:sep:range[:max_splits]`** - Split text and optionally select range
+//! - **`split_any:chars[:range]`** - Split text on any of several characters
+//! - **`partition:sep`**, **`rpartition:sep`** - Split around the first or last separator into three items
+//! - **`words`**, **`sentences`** - Split text at Unicode word or sentence boundaries
+//! - **`extract_emails`**, **`extract_urls`**, **`extract_uuids`** - Lists of the email addresses, URLs or UUIDs in text
+//! - **`extract_between`** - Text inside balanced delimiters, with nesting
+//! - **`join:sep[:last]`** - Join list items with separator, optionally a different last one
+//! - **`output_sep:sep`** - Separator used to render a list left at the end of the pipeline
 //! - **`slice:range`** - Select list elements by range
+//! - **`clamp:range`** - Select list elements by range, clamped even with [`Template::with_strict_ranges`]
 //!
 //! **✨ Text Transformation**
-//! - **`upper`**, **`lower`** - Case conversion
+//! - **`upper`**, **`lower`** - Case conversion, with optional Turkic rules (`upper:tr`)
+//! - **`capitalize[:lower]`**, **`swapcase`** - Titlecase the first character, or swap the case of every letter
 //! - **`trim[:chars][:direction]`** - Remove whitespace or custom characters
+//! - **`squeeze[:chars]`** - Collapse runs of whitespace or custom characters
+//! - **`trim_prefix:text`**, **`trim_suffix:text`** - Remove a prefix or suffix once
+//! - **`trim_regex:pattern[:direction]`** - Remove a regex match at the ends
 //! - **`append:text`**, **`prepend:text`** - Add text to ends
-//! - **`surround:chars`**, **`quote:chars`** - Add characters to both ends
-//! - **`pad:width[:char][:direction]`** - Pad string to width
+//! - **`surround:chars[:suffix]`**, **`quote:chars[:suffix]`** - Add characters to both ends
+//! - **`pad:width[:fill][:direction][:truncate]`**, **`center:width[:fill][:truncate]`** - Pad string to width
 //! - **`substring:range`** - Extract characters from string
 //!
 //! **🔍 Pattern Matching & Replacement**
-//! - **`replace:s/pattern/replacement/flags`** - Regex find/replace (sed-like)
+//! - **`replace:s/pattern/replacement/flags[;...]`** - Regex find/replace (sed-like), several rules separated by `;`
+//! - **`replace_literal:from:to[:count]`** - Plain text find/replace
 //! - **`regex_extract:pattern[:group]`** - Extract with regex pattern
+//! - **`kv:key[:pair_sep[:kv_sep]]`** - Value of a key in `key=value` pairs (`kv_keys`/`kv_values` list them)
 //! - **`filter:pattern`** - Keep items matching regex
 //! - **`filter_not:pattern`** - Remove items matching regex
+//! - **`filter_prefix:text`**, **`filter_suffix:text`**, **`filter_contains:text`** - Keep items starting with, ending with, or containing literal text
+//! - **`filter_valid_utf8`**, **`filter_luhn`**, **`filter_checksum:crc32|adler32:HEX`** - Drop corrupt records
+//! - **`filter_by:{ops}[:pattern]`** - Keep items whose result from `ops` matches regex or is non-empty
 //!
 //! **🗂️ List Processing**
 //! - **`sort[:asc|desc]`** - Sort items alphabetically
+//! - **`sort_by:{ops}[:num][:asc|desc]`** - Sort items by a key computed with `ops`
 //! - **`reverse`** - Reverse string or list order
 //! - **`unique`** - Remove duplicate list items
+//! - **`unique_by:{ops}`** - Remove items whose key computed with `ops` was already seen
+//! - **`duplicates`** - Keep one copy of each item that appears more than once
+//! - **`histogram[:top]`** - Count items into `item<TAB>count` lines, most frequent first
+//! - **`remove_empty`** - Remove empty and whitespace-only list items
+//! - **`intersect:a,b`**, **`diff:a,b`**, **`union:a,b`** - Set operations with a second list (`@file` reads its lines)
+//! - **`shuffle[:seed]`** - Put list items in random order
+//! - **`sample:N[:seed]`** - Pick N random list items
+//! - **`table[:sep[:lrc][:header]]`** - Align delimiter-separated rows into columns
+//! - **`md_table[:sep[:lrc][:header]]`**, **`html_table[:sep[:lrc][:header]]`** - Write delimiter-separated rows as a Markdown or HTML table
 //! - **`map:{operations}`** - Apply sub-pipeline to each list item
+//! - **`map_fallible:{operations}`** - Like `map`, dropping the items it fails on
+//! - **`try:{operations}[:fallback]`** - Keep the input, or use a fallback, when the operations fail
+//! - **`{each:{operations}:template}`** - Format a template for each list item
 //!
 //! **🧹 Utility Operations**
 //! - **`strip_ansi`** - Remove ANSI escape sequences
+//! - **`deaccent`**, **`ascii`** - Remove accents or transliterate to ASCII
+//! - **`slugify[:sep]`** - Build a lowercase, URL-safe slug
+//! - **`normalize:nfc|nfd|nfkc|nfkd`** - Unicode normalization
+//! - **`basename`**, **`dirname`**, **`extname`**, **`strip_ext`**, **`with_ext:EXT`**, **`normalize_path`** - Path manipulation, with `:windows` for `\` separators and drives
+//! - **`url_scheme`**, **`url_host`**, **`url_path`**, **`url_query:KEY`** - Parts of a URL
+//! - **`yaml_get:PATH`**, **`toml_get:PATH`** - Scalar at a dotted path of a YAML or TOML document (`yaml` and `toml` features)
+//! - **`color:COLOR`**, **`bg:COLOR`**, **`bold`**, **`underline`**, **`style_reset`** - ANSI styles for terminal output (see [`Template::with_color`])
+//! - **`highlight:PATTERN[:COLOR]`** - Color every regex match, or put it between markers with `:mark:OPEN:CLOSE`
+//! - **`ordinal`**, **`to_words`**, **`roman`** - Integers as `1st`, `forty-two` or `XIV`; `:parse` converts back
+//! - **`len[:graphemes|chars|bytes]`** - Length of a string or list
+//! - **`json_array`**, **`json_object:KEY,...`** - Items as a JSON array, or as an object with the given keys
+//! - **`read_file`** - Replace a path with the file's contents (requires [`Template::with_fs_access`])
+//! - **`exec:command`** - Pipe the value through a shell command (requires [`Template::with_exec_access`])
 //!
 //! ### Range Syntax
 //!
@@ -69,6 +111,8 @@
 //! - **`N..`** - From N to end
 //! - **`..M`** - From start to M-1
 //! - **`..`** - All items
+//! - **`RANGE;STEP`** - Every STEP-th item (`..;2`), backwards when negative (`..;-1`)
+//! - **`A,B,...`** - Concatenated selections (`0,2..4,-1`)
 //!
 //! Negative indices count from end (`-1` = last item).
 //!
@@ -85,6 +129,10 @@
 //! assert_eq!(result, "a,b,c");
 //! ```
 //!
+//! Over large inputs, [`Template::with_debug_level`] leaves out per-item
+//! traces and value previews, and [`Template::with_debug_preview_chars`]
+//! sets how much of each value is shown.
+//!
 //! ## Templates With Literal Text
 //!
 //! Mixed literal/template strings support automatic caching for repeated
@@ -114,6 +162,9 @@
 //!
 //! Use `map:{operation}` to apply string operations to each item in a list.
 //!
+//! Templates are type-checked when parsed, so chains like `{split:,:..|upper}`
+//! fail in `Template::parse` rather than on the first `format` call.
+//!
 //! ## Structured Templates
 //!
 //! **Added in v0.13.0**: Apply multiple inputs to different template sections with individual separators.
@@ -188,6 +239,38 @@
 //! assert_eq!(result.template_output(1), Some("file1.txt,file2.txt"));
 //! ```
 //!
+//! ## Building Templates in Code
+//!
+//! `TemplateBuilder` constructs templates from typed method calls instead of
+//! formatted strings, and writes the equivalent template syntax:
+//!
+//! ```rust
+//! use string_pipeline::{Range, SortDirection, TemplateBuilder};
+//!
+//! let template = TemplateBuilder::new()
+//!     .split(",", Range::all())
+//!     .map(|item| item.trim().upper())
+//!     .sort(SortDirection::Asc)
+//!     .join("-")
+//!     .build()
+//!     .unwrap();
+//!
+//! assert_eq!(template.template_string(), "{split:,:..|map:{trim|upper}|sort|join:-}");
+//! assert_eq!(template.format("b, a ,c").unwrap(), "A-B-C");
+//! ```
+//!
+//! ## Template Packs
+//!
+//! `TemplateSet` loads a file of named templates, one `NAME = TEMPLATE` per
+//! line, so a shared library of pipelines can be used by name:
+//!
+//! ```rust
+//! use string_pipeline::TemplateSet;
+//!
+//! let set = TemplateSet::parse("shout = {upper}\nwhisper = {lower}").unwrap();
+//! assert_eq!(set.get("shout").unwrap().format("hi").unwrap(), "HI");
+//! ```
+//!
 //! ## Error Handling
 //!
 //! All operations return `Result<String, String>` for comprehensive error handling:
@@ -199,11 +282,14 @@
 //! let result = Template::parse("{split:}");
 //! assert!(result.is_err());
 //!
-//! // Type mismatch errors are clear and helpful
-//! let template = Template::parse("{sort}").unwrap();
-//! let result = template.format("not_a_list");
+//! // Type mismatches are rejected at parse time and point at the operation
+//! let result = Template::parse("{upper|sort}");
 //! assert!(result.is_err());
-//! // Error: "Sort operation can only be applied to lists"
+//! // Error: "Type error in operation 2 (`sort`): `sort` can only be applied to lists, ..."
+//!
+//! // Opt out of static checking; the mismatch then surfaces at format time
+//! let template = Template::parse_unchecked("{sort}").unwrap();
+//! assert!(template.format("not_a_list").is_err());
 //! ```
 //!
 //! ## Common Use Cases
@@ -320,7 +406,15 @@
 
 mod pipeline;
 
+pub use pipeline::builder::Range;
 #[allow(deprecated)]
 pub use pipeline::{
-    MultiTemplate, RichFormatResult, SectionInfo, SectionType, Template, TemplateOutput,
+    CacheScope, CacheStats, CaseLocale, ChecksumAlgorithm, ColumnAlign, DebugFormat, DebugLevel,
+    DebugSink, ErrorPolicy, FormatContext, HighlightStyle, InputRef, LengthUnit, Limits,
+    LineEndings, LintKind, LintWarning, ListSource, MemoStats, MultiTemplate, NormalForm,
+    OperationInfo, OperationProfile, PadDirection, ParserConfig, PathStyle, Pipeline, Placeholder,
+    ProfilingReport, RangeSpec, RichFormatResult, SafetyProfile, Section, SectionInfo,
+    SectionOutput, SectionType, SedRule, SortDirection, StringOp, Template, TemplateBuilder,
+    TemplateOutput, TemplateSet, TermColor, Token, TokenKind, TrimDirection, ValueKind, Variable,
+    cache_stats, operation, operations, operations_json, register_alias, tokenize,
 };
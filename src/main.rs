@@ -1,8 +1,16 @@
-use clap::{CommandFactory, Parser};
+use clap::{CommandFactory, Parser, ValueEnum};
 use std::fs;
-use std::io::{self, Read};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
 use std::path::PathBuf;
-use string_pipeline::Template;
+use std::time::{Duration, Instant};
+use string_pipeline::{
+    DebugFormat, DebugLevel, FormatContext, LineEndings, ParserConfig, Template, TemplateSet,
+    ValueKind, cache_stats, operations, operations_json,
+};
+
+mod duration;
+
+use duration::format_duration;
 
 #[derive(Parser)]
 #[command(
@@ -27,6 +35,24 @@ struct Cli {
     #[arg(short = 't', long = "template-file", value_name = "FILE")]
     template_file: Option<PathBuf>,
 
+    /// Use the template named NAME from the pack given with --templates-file;
+    /// a single positional argument is then the input
+    #[arg(
+        long = "template-name",
+        value_name = "NAME",
+        requires = "templates_file",
+        conflicts_with = "template_file"
+    )]
+    template_name: Option<String>,
+
+    /// Template pack with one NAME = TEMPLATE per line, or TOML for .toml files
+    #[arg(
+        long = "templates-file",
+        value_name = "FILE",
+        requires = "template_name"
+    )]
+    templates_file: Option<PathBuf>,
+
     /// Read input from file instead of stdin/argument
     #[arg(short = 'f', long = "input-file", value_name = "FILE")]
     input_file: Option<PathBuf>,
@@ -35,30 +61,206 @@ struct Cli {
     #[arg(short = 'd', long = "debug")]
     debug: bool,
 
+    /// Output format for debug traces
+    #[arg(long = "debug-format", value_name = "FORMAT", value_enum, default_value_t = DebugFormatArg::Text)]
+    debug_format: DebugFormatArg,
+
+    /// How much debug traces report: summary, ops, or full
+    #[arg(long = "debug-level", value_name = "LEVEL", value_enum, default_value_t = DebugLevelArg::Full)]
+    debug_level: DebugLevelArg,
+
+    /// Maximum number of characters kept in debug value previews
+    #[arg(long = "debug-preview", value_name = "CHARS", default_value_t = 40)]
+    debug_preview: usize,
+
+    /// Enclose template sections in OPEN and CLOSE instead of braces (e.g. '<< >>')
+    #[arg(long = "delim", value_name = "OPEN CLOSE")]
+    delim: Option<String>,
+
+    /// Ignore whitespace and # comments in every template section (like {!#...})
+    #[arg(long = "verbose-syntax")]
+    verbose_syntax: bool,
+
+    /// Read Windows line endings (\r\n) in the input as \n
+    #[arg(long = "crlf")]
+    crlf: bool,
+
+    /// Like --crlf, and also write the output with \r\n line endings
+    #[arg(long = "crlf-output")]
+    crlf_output: bool,
+
+    /// Join lists left at the end of a template section with SEP instead of
+    /// the last split or join separator (\n, \t and \r are unescaped)
+    #[arg(long = "ors", visible_alias = "join", value_name = "SEP")]
+    ors: Option<String>,
+
+    /// Replace invalid UTF-8 in the input instead of failing; {strip_ansi}
+    /// templates pass the raw bytes through
+    #[arg(long = "lossy")]
+    lossy: bool,
+
+    /// Allow operations that read files (read_file, @FILE lists)
+    #[arg(long = "allow-fs")]
+    allow_fs: bool,
+
+    /// Allow the exec operation to run external commands
+    #[arg(long = "allow-exec")]
+    allow_exec: bool,
+
+    /// Run a second template on the same input and show a diff of both outputs
+    #[arg(long = "diff", value_name = "TEMPLATE2")]
+    diff: Option<String>,
+
+    /// Check the output against EXPECTED, showing a diff and failing on mismatch
+    #[arg(long = "assert", value_name = "EXPECTED", conflicts_with_all = ["assert_file", "diff"])]
+    assert: Option<String>,
+
+    /// Check the output against the contents of FILE, like --assert
+    #[arg(long = "assert-file", value_name = "FILE", conflicts_with = "diff")]
+    assert_file: Option<PathBuf>,
+
+    /// Apply the template to each input line on its own, streaming the input
+    /// instead of reading it all first
+    #[arg(long = "per-line", conflicts_with_all = ["diff", "assert", "assert_file"])]
+    per_line: bool,
+
+    /// Report bytes and lines processed, throughput and ETA on stderr while
+    /// running with --per-line
+    #[arg(long = "progress", requires = "per_line")]
+    progress: bool,
+
+    /// Re-run the template from --template-file whenever it or the input
+    /// file changes, clearing the screen between runs
+    #[arg(
+        long = "watch",
+        requires = "template_file",
+        conflicts_with_all = ["per_line", "diff", "assert", "assert_file", "validate"]
+    )]
+    watch: bool,
+
     /// Validate template syntax without processing input
     #[arg(long = "validate")]
     validate: bool,
 
+    /// Leave out the ANSI styles of color, bg, bold, underline and
+    /// style_reset, and diff colors (also when NO_COLOR is set)
+    #[arg(long = "no-color")]
+    no_color: bool,
+
+    /// Fail on indices and ranges that reach past the value in split, slice
+    /// and substring, instead of clamping them (see the clamp operation)
+    #[arg(long = "strict-ranges")]
+    strict_ranges: bool,
+
     /// Suppress all output except the final result
     #[arg(short = 'q', long = "quiet")]
     quiet: bool,
 
-    /// Show available operations and exit
-    #[arg(long = "list-operations")]
-    list_operations: bool,
+    /// Print regex and split cache statistics to stderr after formatting
+    #[arg(long = "cache-stats")]
+    cache_stats: bool,
+
+    /// Show available operations and exit (FORMAT: text or json)
+    #[arg(
+        long = "list-operations",
+        value_name = "FORMAT",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "text"
+    )]
+    list_operations: Option<ListFormatArg>,
 
     /// Show template syntax help and exit
     #[arg(long = "syntax-help")]
     syntax_help: bool,
 }
 
+/// Output formats accepted by `--list-operations`
+#[derive(Clone, Copy, ValueEnum)]
+enum ListFormatArg {
+    /// Aligned help text
+    Text,
+    /// JSON array with one object per operation
+    Json,
+}
+
+/// Debug trace formats accepted by `--debug-format`
+#[derive(Clone, Copy, ValueEnum)]
+enum DebugFormatArg {
+    /// Human-readable tree
+    Text,
+    /// Line-delimited JSON events
+    Json,
+}
+
+impl From<DebugFormatArg> for DebugFormat {
+    fn from(arg: DebugFormatArg) -> Self {
+        match arg {
+            DebugFormatArg::Text => DebugFormat::Text,
+            DebugFormatArg::Json => DebugFormat::Json,
+        }
+    }
+}
+
+/// Debug levels accepted by `--debug-level`
+#[derive(Clone, Copy, ValueEnum)]
+enum DebugLevelArg {
+    /// Sessions, sections, and pipeline timings
+    Summary,
+    /// Adds each operation with its input and output
+    Ops,
+    /// Adds map items and sub-pipelines
+    Full,
+}
+
+impl From<DebugLevelArg> for DebugLevel {
+    fn from(arg: DebugLevelArg) -> Self {
+        match arg {
+            DebugLevelArg::Summary => DebugLevel::Summary,
+            DebugLevelArg::Ops => DebugLevel::Ops,
+            DebugLevelArg::Full => DebugLevel::Full,
+        }
+    }
+}
+
+/// Where `--per-line` reads its lines from
+enum LineSource {
+    Text(String),
+    File(PathBuf),
+    Stdin,
+}
+
 /// Processed configuration from CLI arguments
 struct Config {
     template: String,
-    input: Option<String>,
+    /// Raw input bytes, only checked to be valid UTF-8 without `--lossy`
+    input: Option<Vec<u8>>,
+    /// Input read line by line with `--per-line`, instead of `input`
+    lines: Option<LineSource>,
+    /// Template file and input file re-read on changes with `--watch`
+    watch: Option<(PathBuf, Option<PathBuf>)>,
+    progress: bool,
+    lossy: bool,
+    diff: Option<String>,
+    /// Label and text of the output expected by `--assert` or `--assert-file`
+    expected: Option<(String, String)>,
     validate: bool,
     quiet: bool,
+    cache_stats: bool,
     debug: bool,
+    debug_format: DebugFormat,
+    debug_level: DebugLevel,
+    debug_preview: usize,
+    delimiters: Option<(String, String)>,
+    verbose_syntax: bool,
+    line_endings: LineEndings,
+    output_separator: Option<String>,
+    allow_fs: bool,
+    allow_exec: bool,
+    /// Whether styling operations and diffs use ANSI colors
+    color: bool,
+    strict_ranges: bool,
 }
 
 /// Read content from a file with proper error handling
@@ -66,23 +268,82 @@ fn read_file(path: &PathBuf) -> Result<String, String> {
     fs::read_to_string(path).map_err(|e| format!("Failed to read file '{}': {}", path.display(), e))
 }
 
-/// Read from stdin with proper error handling
-fn read_stdin() -> Result<String, String> {
-    let mut buffer = String::new();
+/// Read the raw bytes of an input file with proper error handling
+fn read_file_bytes(path: &PathBuf) -> Result<Vec<u8>, String> {
+    fs::read(path).map_err(|e| format!("Failed to read file '{}': {}", path.display(), e))
+}
+
+/// Read raw bytes from stdin with proper error handling
+fn read_stdin() -> Result<Vec<u8>, String> {
+    let mut buffer = Vec::new();
     io::stdin()
-        .read_to_string(&mut buffer)
+        .read_to_end(&mut buffer)
         .map_err(|e| format!("Failed to read from stdin: {e}"))?;
     Ok(buffer)
 }
 
+/// Remove trailing whitespace from the input, rejecting invalid UTF-8 unless
+/// `lossy` is set
+fn finish_input(mut input: Vec<u8>, lossy: bool, source: &str) -> Result<Vec<u8>, String> {
+    let len = match std::str::from_utf8(&input) {
+        Ok(text) => text.trim_end().len(),
+        // Invalid bytes decode to U+FFFD, which is not whitespace, so only the
+        // text after the last of them is trimmed, as in the decoded string
+        Err(_) if lossy => match input.utf8_chunks().last() {
+            Some(chunk) if chunk.invalid().is_empty() => {
+                input.len() - chunk.valid().len() + chunk.valid().trim_end().len()
+            }
+            _ => input.len(),
+        },
+        Err(e) => {
+            return Err(format!(
+                "Failed to read from {source}: {e} (pass --lossy to replace invalid bytes)"
+            ));
+        }
+    };
+    input.truncate(len);
+    Ok(input)
+}
+
 /// Check if stdin is available (not a terminal)
 fn is_stdin_available() -> bool {
     use std::io::IsTerminal;
     !io::stdin().is_terminal()
 }
 
+/// Parser options from CLI arguments
+fn parser_config(cli: &Cli) -> Result<ParserConfig, String> {
+    Ok(ParserConfig {
+        verbose_syntax: cli.verbose_syntax,
+        delimiters: cli.delim.as_deref().map(parse_delimiters).transpose()?,
+        ..ParserConfig::default()
+    })
+}
+
+/// Get the template named by `--template-name` from the `--templates-file` pack
+fn get_pack_template(file: &PathBuf, name: &str, cli: &Cli) -> Result<String, String> {
+    let set = TemplateSet::load_with_config(file, &parser_config(cli)?)
+        .map_err(|e| format!("Error reading templates file: {e}"))?;
+    match set.get(name) {
+        Some(template) => Ok(template.template_string().to_string()),
+        None => Err(format!(
+            "Error: No template named '{name}' in '{}' (available: {})",
+            file.display(),
+            set.names().collect::<Vec<_>>().join(", ")
+        )),
+    }
+}
+
 /// Get template string from CLI arguments
 fn get_template(cli: &Cli) -> Result<String, String> {
+    if let (Some(name), Some(file)) = (&cli.template_name, &cli.templates_file) {
+        if cli.template.is_some() {
+            return Err(
+                "Error: Cannot specify both template argument and --template-name".to_string(),
+            );
+        }
+        return get_pack_template(file, name, cli);
+    }
     match (&cli.template, &cli.template_file) {
         (Some(template), None) => Ok(template.clone()),
         (None, Some(file)) => read_file(file)
@@ -97,69 +358,437 @@ fn get_template(cli: &Cli) -> Result<String, String> {
     }
 }
 
-/// Get input string from CLI arguments
-fn get_input(cli: &Cli) -> Result<String, String> {
+/// Get input bytes from CLI arguments
+fn get_input(cli: &Cli) -> Result<Vec<u8>, String> {
     match (&cli.input, &cli.input_file) {
-        (Some(input), None) => Ok(input.clone()),
-        (None, Some(file)) => read_file(file)
-            .map(|content| content.trim_end().to_string())
+        (Some(input), None) => Ok(input.clone().into_bytes()),
+        (None, Some(file)) => read_file_bytes(file)
+            .and_then(|content| {
+                finish_input(content, cli.lossy, &format!("file '{}'", file.display()))
+            })
             .map_err(|e| format!("Error reading input file: {e}")),
-        (None, None) => read_stdin().map(|input| input.trim_end().to_string()),
+        (None, None) => read_stdin().and_then(|input| finish_input(input, cli.lossy, "stdin")),
         (Some(_), Some(_)) => {
             Err("Error: Cannot specify both input argument and input file".to_string())
         }
     }
 }
 
+/// Get the `--per-line` input source from CLI arguments
+fn get_line_source(cli: &Cli) -> Result<LineSource, String> {
+    match (&cli.input, &cli.input_file) {
+        (Some(input), None) => Ok(LineSource::Text(input.clone())),
+        (None, Some(file)) => Ok(LineSource::File(file.clone())),
+        (None, None) => Ok(LineSource::Stdin),
+        (Some(_), Some(_)) => {
+            Err("Error: Cannot specify both input argument and input file".to_string())
+        }
+    }
+}
+
+/// Split the `--delim` value into its opening and closing delimiters
+fn parse_delimiters(delim: &str) -> Result<(String, String), String> {
+    match delim.split_whitespace().collect::<Vec<_>>()[..] {
+        [open, close] => Ok((open.to_string(), close.to_string())),
+        _ => Err(format!(
+            "Error: --delim expects an opening and a closing delimiter separated by a space, got '{delim}'"
+        )),
+    }
+}
+
+/// Resolve the `\n`, `\t` and `\r` escapes of an `--ors` separator, like in
+/// template arguments
+fn unescape_separator(sep: &str) -> String {
+    let mut result = String::with_capacity(sep.len());
+    let mut chars = sep.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
 /// Build configuration from CLI arguments
-fn build_config(cli: Cli) -> Result<Config, String> {
+fn build_config(mut cli: Cli) -> Result<Config, String> {
+    // With a named template there is no positional template, only the input
+    if cli.template_name.is_some() && cli.input.is_none() {
+        cli.input = cli.template.take();
+    }
     let template = get_template(&cli)?;
+    let delimiters = cli.delim.as_deref().map(parse_delimiters).transpose()?;
 
-    // Skip input collection if we're only validating the template
-    let input = if cli.validate {
+    let expected = match (&cli.assert, &cli.assert_file) {
+        (Some(text), _) => Some(("expected".to_string(), text.clone())),
+        (None, Some(file)) => {
+            // Files usually end with a newline that the output does not have
+            let text = read_file(file)
+                .map(|content| {
+                    let content = content.strip_suffix('\n').unwrap_or(&content);
+                    content.strip_suffix('\r').unwrap_or(content).to_string()
+                })
+                .map_err(|e| format!("Error reading expected output file: {e}"))?;
+            Some((file.display().to_string(), text))
+        }
+        (None, None) => None,
+    };
+
+    // Skip input collection if we're only validating the template, and
+    // leave it to the line reader with --per-line
+    let input = if cli.validate || cli.per_line {
         None
     } else {
         Some(get_input(&cli)?)
     };
+    let lines = if cli.per_line && !cli.validate {
+        Some(get_line_source(&cli)?)
+    } else {
+        None
+    };
+
+    let watch = cli
+        .template_file
+        .clone()
+        .filter(|_| cli.watch)
+        .map(|file| (file, cli.input_file.clone()));
 
     Ok(Config {
         template,
         input,
+        lines,
+        watch,
+        progress: cli.progress && !cli.quiet,
+        lossy: cli.lossy,
+        diff: cli.diff,
+        expected,
         validate: cli.validate,
         quiet: cli.quiet,
+        cache_stats: cli.cache_stats,
         debug: cli.debug,
+        debug_format: cli.debug_format.into(),
+        debug_level: cli.debug_level.into(),
+        debug_preview: cli.debug_preview,
+        delimiters,
+        verbose_syntax: cli.verbose_syntax,
+        line_endings: if cli.crlf_output {
+            LineEndings::Crlf
+        } else if cli.crlf {
+            LineEndings::Lf
+        } else {
+            LineEndings::Keep
+        },
+        output_separator: cli.ors.as_deref().map(unescape_separator),
+        allow_fs: cli.allow_fs,
+        allow_exec: cli.allow_exec,
+        color: !cli.no_color && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()),
+        strict_ranges: cli.strict_ranges,
     })
 }
 
-fn show_operations_help() {
-    println!("Available Operations:");
-    println!(
-        "
-  split:SEP:RANGE          - Split text into parts
-  slice:RANGE              - Extract range of items
-  join:SEP                 - Combine items with separator
-  substring:RANGE          - Extract characters from string
-  trim[:CHARS][:DIR]       - Remove characters from ends
-  pad:WIDTH[:CHAR][:DIR]   - Add padding to reach width
-  upper                    - Convert to uppercase
-  lower                    - Convert to lowercase
-  append:TEXT              - Add text to end
-  prepend:TEXT             - Add text to beginning
-  surround:CHARS           - Add characters to both ends
-  quote:CHARS              - Add characters to both ends (alias)
-  replace:s/PAT/REP/FLAGS  - Find and replace with regex
-  regex_extract:PAT[:GRP]  - Extract with regex pattern
-  sort[:DIR]               - Sort items alphabetically
-  reverse                  - Reverse order or characters
-  unique                   - Remove duplicates
-  filter:PATTERN           - Keep items matching pattern
-  filter_not:PATTERN       - Remove items matching pattern
-  strip_ansi               - Remove ANSI color codes
-  map:{{operations}}       - Apply operations to each item
-
-Use 'string-pipeline --syntax-help' for detailed syntax information.
-"
+/// One line of a line-based diff between two outputs
+enum DiffLine<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Unchanged lines shown around each change in `--diff` output
+const DIFF_CONTEXT: usize = 3;
+
+/// Steps of the diff search allowed before [`diff_lines`] gives up, which
+/// bounds its time on outputs that share little
+const DIFF_MAX_COST: usize = 20_000_000;
+
+/// Compute a minimal line diff from `old` to `new`.
+///
+/// Uses Myers' algorithm in linear space, so memory grows with the number of
+/// lines rather than with their product. Returns `None` when the outputs are
+/// too different to diff within [`DIFF_MAX_COST`] steps.
+fn diff_lines<'a>(old: &'a str, new: &'a str) -> Option<Vec<DiffLine<'a>>> {
+    let old: Vec<&str> = old.split('\n').collect();
+    let new: Vec<&str> = new.split('\n').collect();
+    let mut diff = Vec::with_capacity(old.len().max(new.len()));
+    let mut budget = DIFF_MAX_COST;
+    diff_range(&old, &new, &mut diff, &mut budget)?;
+
+    // Within each run of changes, show the removed lines before the added ones
+    let mut start = 0;
+    while start < diff.len() {
+        let end = start
+            + diff[start..]
+                .iter()
+                .take_while(|line| !matches!(line, DiffLine::Same(_)))
+                .count();
+        diff[start..end].sort_by_key(|line| matches!(line, DiffLine::Added(_)));
+        start = end + 1;
+    }
+    Some(diff)
+}
+
+/// Append the diff from `a` to `b` to `diff`, spending `budget`
+fn diff_range<'a>(
+    a: &[&'a str],
+    b: &[&'a str],
+    diff: &mut Vec<DiffLine<'a>>,
+    budget: &mut usize,
+) -> Option<()> {
+    // Keep the common prefix and suffix out of the search
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    diff.extend(a[..prefix].iter().map(|line| DiffLine::Same(line)));
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    if a_mid.is_empty() {
+        diff.extend(b_mid.iter().map(|line| DiffLine::Added(line)));
+    } else if b_mid.is_empty() {
+        diff.extend(a_mid.iter().map(|line| DiffLine::Removed(line)));
+    } else {
+        let (x, y) = middle_snake(a_mid, b_mid, budget)?;
+        diff_range(&a_mid[..x], &b_mid[..y], diff, budget)?;
+        diff_range(&a_mid[x..], &b_mid[y..], diff, budget)?;
+    }
+
+    diff.extend(
+        a[a.len() - suffix..]
+            .iter()
+            .map(|line| DiffLine::Same(line)),
     );
+    Some(())
+}
+
+/// Find a point on a shortest edit path from `a` to `b`, splitting it into
+/// two smaller diffs.
+///
+/// Searches forward from the start and backward from the end at once until
+/// both searches meet, keeping only the furthest point reached on each
+/// diagonal. `a` and `b` must be non-empty and differ in their first and
+/// last lines, so the point is never at either end.
+fn middle_snake(a: &[&str], b: &[&str], budget: &mut usize) -> Option<(usize, usize)> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max_d = (n + m + 1) / 2;
+    let offset = max_d;
+    let delta = n - m;
+    let front = delta % 2 != 0;
+    // Furthest x reached on each diagonal k = x - y, forward and backward
+    let mut forward = vec![-1isize; 2 * max_d as usize + 2];
+    let mut backward = forward.clone();
+    forward[offset as usize + 1] = 0;
+    backward[offset as usize + 1] = 0;
+    // Diagonals that ran off the grid on either side are not searched again
+    let (mut k1_start, mut k1_end, mut k2_start, mut k2_end) = (0, 0, 0, 0);
+
+    for d in 0..=max_d {
+        let mut k1 = -d + k1_start;
+        while k1 <= d - k1_end {
+            let i = (offset + k1) as usize;
+            let mut x = if k1 == -d || (k1 != d && forward[i - 1] < forward[i + 1]) {
+                forward[i + 1]
+            } else {
+                forward[i - 1] + 1
+            };
+            let mut y = x - k1;
+            let start = x;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            *budget = budget.checked_sub(1 + (x - start) as usize)?;
+            forward[i] = x;
+            if x > n {
+                k1_end += 2;
+            } else if y > m {
+                k1_start += 2;
+            } else if front {
+                let j = offset + delta - k1;
+                if (0..backward.len() as isize).contains(&j)
+                    && backward[j as usize] != -1
+                    && x >= n - backward[j as usize]
+                {
+                    return Some((x as usize, y as usize));
+                }
+            }
+            k1 += 2;
+        }
+
+        let mut k2 = -d + k2_start;
+        while k2 <= d - k2_end {
+            let i = (offset + k2) as usize;
+            let mut x = if k2 == -d || (k2 != d && backward[i - 1] < backward[i + 1]) {
+                backward[i + 1]
+            } else {
+                backward[i - 1] + 1
+            };
+            let mut y = x - k2;
+            let start = x;
+            while x < n && y < m && a[(n - x - 1) as usize] == b[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            *budget = budget.checked_sub(1 + (x - start) as usize)?;
+            backward[i] = x;
+            if x > n {
+                k2_end += 2;
+            } else if y > m {
+                k2_start += 2;
+            } else if !front {
+                let j = offset + delta - k2;
+                if (0..forward.len() as isize).contains(&j) && forward[j as usize] != -1 {
+                    let x1 = forward[j as usize];
+                    if x1 >= n - x {
+                        return Some((x1 as usize, (x1 - (j - offset)) as usize));
+                    }
+                }
+            }
+            k2 += 2;
+        }
+    }
+    // The searches always meet within `max_d` steps
+    None
+}
+
+/// Marker printed in place of unchanged lines collapsed by [`print_diff`]
+fn skipped_marker(count: usize) -> String {
+    let plural = if count == 1 { "" } else { "s" };
+    format!("@@ {count} unchanged line{plural} @@")
+}
+
+/// Print the diff between two outputs to stdout, headed by their labels.
+///
+/// Unchanged lines further than [`DIFF_CONTEXT`] lines from a change are
+/// collapsed into a single marker line. Colors are used when stdout is a
+/// terminal and `color` is set.
+fn print_diff(old_label: &str, new_label: &str, diff: &[DiffLine], color: bool) {
+    use std::io::IsTerminal;
+    let color = color && io::stdout().is_terminal();
+    let paint = |code: &str, text: &str| {
+        if color {
+            format!("\x1b[{code}m{text}\x1b[0m")
+        } else {
+            text.to_string()
+        }
+    };
+
+    println!("{}", paint("1", &format!("--- {old_label}")));
+    println!("{}", paint("1", &format!("+++ {new_label}")));
+
+    let changed: Vec<usize> = (0..diff.len())
+        .filter(|&i| !matches!(diff[i], DiffLine::Same(_)))
+        .collect();
+    let near_change = |i: usize| {
+        let next = changed.partition_point(|&c| c < i);
+        changed.get(next).is_some_and(|&c| c - i <= DIFF_CONTEXT)
+            || next > 0 && i - changed[next - 1] <= DIFF_CONTEXT
+    };
+
+    let mut skipped = 0;
+    for (i, line) in diff.iter().enumerate() {
+        match line {
+            DiffLine::Same(_) if !near_change(i) => {
+                skipped += 1;
+                continue;
+            }
+            _ if skipped > 0 => {
+                println!("{}", paint("36", &skipped_marker(skipped)));
+                skipped = 0;
+            }
+            _ => {}
+        }
+        match line {
+            DiffLine::Same(text) => println!(" {text}"),
+            DiffLine::Removed(text) => println!("{}", paint("31", &format!("-{text}"))),
+            DiffLine::Added(text) => println!("{}", paint("32", &format!("+{text}"))),
+        }
+    }
+    if skipped > 0 {
+        println!("{}", paint("36", &skipped_marker(skipped)));
+    }
+}
+
+/// Print the diff between two outputs if they differ, returning whether they do.
+///
+/// Outputs too different to diff are reported by their first differing line.
+fn compare_outputs(old_label: &str, new_label: &str, old: &str, new: &str, color: bool) -> bool {
+    if old == new {
+        return false;
+    }
+    match diff_lines(old, new) {
+        Some(diff) => print_diff(old_label, new_label, &diff, color),
+        None => print_first_difference(old_label, new_label, old, new),
+    }
+    true
+}
+
+/// Line number, starting at 1, and text of the first line where `old` and
+/// `new` differ, or `None` for a line one of them does not have
+fn first_difference<'a>(old: &'a str, new: &'a str) -> (usize, Option<&'a str>, Option<&'a str>) {
+    let mut old_lines = old.split('\n');
+    let mut new_lines = new.split('\n');
+    let mut number = 1;
+    loop {
+        match (old_lines.next(), new_lines.next()) {
+            (Some(a), Some(b)) if a == b => number += 1,
+            (a, b) => return (number, a, b),
+        }
+    }
+}
+
+/// Print the first differing line of two outputs that are too different to diff
+fn print_first_difference(old_label: &str, new_label: &str, old: &str, new: &str) {
+    let (number, old_line, new_line) = first_difference(old, new);
+    println!("--- {old_label}");
+    println!("+++ {new_label}");
+    println!("@@ outputs differ from line {number} on, too much for a diff @@");
+    if let Some(line) = old_line {
+        println!("-{line}");
+    }
+    if let Some(line) = new_line {
+        println!("+{line}");
+    }
+}
+
+/// Width of the syntax column in `--list-operations` output
+const SYNTAX_COLUMN: usize = 24;
+
+fn show_operations_help(format: ListFormatArg) {
+    if let ListFormatArg::Json = format {
+        println!("{}", operations_json());
+        return;
+    }
+
+    println!("Available Operations:");
+    println!();
+    for op in operations() {
+        print_operation_line(op.syntax, op.summary);
+        for alias in op.aliases {
+            let syntax = format!("{alias}{}", &op.syntax[op.name.len()..]);
+            print_operation_line(&syntax, &format!("Same as {}", op.name));
+        }
+    }
+    println!();
+    println!("Use 'string-pipeline --syntax-help' for detailed syntax information.");
+}
+
+/// Print one `--list-operations` entry, moving long syntax onto its own line
+fn print_operation_line(syntax: &str, summary: &str) {
+    if syntax.len() > SYNTAX_COLUMN {
+        println!("  {syntax}");
+        println!("  {:SYNTAX_COLUMN$} - {summary}", "");
+    } else {
+        println!("  {syntax:SYNTAX_COLUMN$} - {summary}");
+    }
 }
 
 fn show_syntax_help() {
@@ -179,6 +808,8 @@ RANGE SYNTAX:
   N..      - From N to end (2.. = from 3rd item)
   ..M      - From start to M-1 (..3 = first 3 items)
   ..       - All items
+  RANGE;S  - Every S-th item of RANGE, backwards if S < 0 (..;-1 = reversed)
+  A,B,...  - Items selected by each range in turn (0,3,7 = items 0,3,7)
 
 OPERATION-ONLY EXAMPLES:
   {{split:,:..|map:{{upper}}|join:-}}
@@ -190,30 +821,393 @@ MIXED TEXT EXAMPLES:
   'First: {{split:,:0}} Second: {{split:,:1}}'
   'some string {{split:,:1}} some string {{split:,:2}}'
 
+CUSTOM DELIMITERS:
+  --delim '<< >>' encloses sections in << and >>, leaving braces literal:
+  'fn <<lower>>() {{ return <<split:,:..|map:{{upper}}|join:;>>; }}'
+
+VERBOSE SYNTAX:
+  Sections starting with {{!# (or every section with --verbose-syntax)
+  ignore whitespace, and # starts a comment to the end of the line:
+  {{!#
+    split:,:..    # one item per field
+    | map:{{trim|upper}}
+    | join:\\ -\\   # escape spaces that belong to arguments
+  }}
+
 CACHING:
   Templates automatically cache split results for efficiency.
   In 'A: {{split:,:0}} B: {{split:,:1}} C: {{split:,:0}}', the input is
   split only once, with subsequent operations reusing the cached split result.
 
+QUOTING:
+  Text and split arguments can be quoted as a whole to avoid escaping:
+  {{split:': | ':..}}      - Single quotes: taken literally
+  {{append:\"a: b | c\\n\"}} - Double quotes: \\\" and the escapes below
+  Quotes inside an argument, and empty quotes like {{quote:''}}, stay literal.
+
 ESCAPING:
   \\:  - Literal colon
   \\|  - Literal pipe
   \\}} - Literal closing brace
   \\n  - Newline
   \\t  - Tab
+  \\   - Literal space (verbose syntax)
+  \\#  - Literal # (verbose syntax)
+"
+    );
+
+    println!("OPERATIONS BY INPUT TYPE (details: string-pipeline --list-operations):");
+    for kind in [ValueKind::String, ValueKind::List, ValueKind::Any] {
+        let names: Vec<&str> = operations()
+            .iter()
+            .filter(|op| op.input == kind)
+            .map(|op| op.name)
+            .collect();
+        print_wrapped(&format!("  {:8}", format!("{}:", kind.as_str())), &names);
+    }
 
+    println!(
+        "
 For complete documentation, visit:
-https://github.com/lalvarezt/string_pipeline/blob/main/docs/template-system.md
-"
+https://github.com/lalvarezt/string_pipeline/blob/main/docs/template-system.md"
     );
 }
 
+/// Print comma-separated words after a label, wrapping lines at 80 columns
+fn print_wrapped(label: &str, words: &[&str]) {
+    let mut line = label.to_string();
+    for (i, word) in words.iter().enumerate() {
+        let piece = if i + 1 < words.len() {
+            format!("{word},")
+        } else {
+            word.to_string()
+        };
+        if line.len() + 1 + piece.len() > 80 && line.len() > label.len() {
+            println!("{line}");
+            line = " ".repeat(label.len());
+        }
+        if line.len() > label.len() {
+            line.push(' ');
+        }
+        line.push_str(&piece);
+    }
+    println!("{line}");
+}
+
+/// Parse a template and apply the debug and access settings from the CLI
+fn prepare_template(text: &str, config: &Config) -> Template {
+    try_prepare_template(text, config).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1);
+    })
+}
+
+/// Like [`prepare_template`], returning parse errors instead of exiting
+fn try_prepare_template(text: &str, config: &Config) -> Result<Template, String> {
+    let parser_config = ParserConfig {
+        verbose_syntax: config.verbose_syntax,
+        delimiters: config.delimiters.clone(),
+        ..ParserConfig::default()
+    };
+    let template = Template::parse_with_config(text, &parser_config)
+        .map_err(|e| format!("Error parsing template: {e}"))?;
+
+    // Enable debug if either the template has ! prefix OR the CLI debug flag is set
+    // Disable debug only if quiet mode is enabled
+    let should_debug = (template.is_debug() || config.debug) && !config.quiet;
+    let mut template = template
+        .with_debug(should_debug)
+        .with_debug_format(config.debug_format)
+        .with_debug_level(config.debug_level)
+        .with_debug_preview_chars(config.debug_preview)
+        .with_line_endings(config.line_endings)
+        .with_fs_access(config.allow_fs)
+        .with_exec_access(config.allow_exec)
+        .with_color(config.color)
+        .with_strict_ranges(config.strict_ranges);
+    template.set_output_separator(config.output_separator.as_deref());
+    Ok(template)
+}
+
+/// Interval between two `--progress` reports
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Format a byte count with binary units, like `1.5 MiB`
+fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{value:.0} {}", UNITS[0])
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// `--progress` reporting on stderr for `--per-line`.
+///
+/// Reports overwrite each other when stderr is a terminal and are printed on
+/// their own lines otherwise.
+struct Progress {
+    start: Instant,
+    last_report: Instant,
+    /// Size of the input, when known, for the percentage and ETA
+    total: Option<u64>,
+    bytes: u64,
+    lines: u64,
+    terminal: bool,
+}
+
+impl Progress {
+    fn new(total: Option<u64>) -> Self {
+        use std::io::IsTerminal;
+        let now = Instant::now();
+        Self {
+            start: now,
+            last_report: now,
+            total,
+            bytes: 0,
+            lines: 0,
+            terminal: io::stderr().is_terminal(),
+        }
+    }
+
+    /// Count one line of `bytes` bytes, reporting if the interval has passed
+    fn record(&mut self, bytes: usize) {
+        self.bytes += bytes as u64;
+        self.lines += 1;
+        if self.last_report.elapsed() >= PROGRESS_INTERVAL {
+            self.last_report = Instant::now();
+            self.report(false);
+        }
+    }
+
+    fn report(&self, done: bool) {
+        let elapsed = self.start.elapsed();
+        let seconds = elapsed.as_secs_f64().max(f64::EPSILON);
+        let mut line = format_bytes(self.bytes as f64);
+        if let Some(total) = self.total.filter(|_| !done) {
+            let percent = self.bytes as f64 * 100.0 / total.max(1) as f64;
+            line.push_str(&format!(
+                " of {} ({percent:.1}%)",
+                format_bytes(total as f64)
+            ));
+        }
+        line.push_str(&format!(
+            ", {} lines, {:.0} lines/s, {}/s",
+            self.lines,
+            self.lines as f64 / seconds,
+            format_bytes(self.bytes as f64 / seconds)
+        ));
+        if done {
+            line.push_str(&format!(", done in {}", format_duration(elapsed)));
+        } else if let Some(total) = self.total.filter(|_| self.bytes > 0) {
+            let remaining = total.saturating_sub(self.bytes) as f64 / self.bytes as f64;
+            line.push_str(&format!(
+                ", ETA {}",
+                format_duration(elapsed.mul_f64(remaining))
+            ));
+        }
+
+        if self.terminal {
+            eprint!("\r{line}\x1b[K");
+            if done {
+                eprintln!();
+            }
+        } else {
+            eprintln!("{line}");
+        }
+    }
+}
+
+/// Format each line of `source` on its own and write the results to stdout,
+/// one per line.
+///
+/// Lines are read without their terminator, and `{line_no}` and, for input
+/// files, `{file_name}` read where they come from.
+fn run_per_line(template: &Template, source: &LineSource, config: &Config) -> Result<(), String> {
+    let (mut reader, total): (Box<dyn BufRead>, Option<u64>) = match source {
+        LineSource::Text(text) => (
+            Box::new(io::Cursor::new(text.as_bytes())),
+            Some(text.len() as u64),
+        ),
+        LineSource::File(path) => {
+            let file = fs::File::open(path).map_err(|e| {
+                format!(
+                    "Error reading input file: Failed to read file '{}': {e}",
+                    path.display()
+                )
+            })?;
+            let total = file.metadata().ok().map(|metadata| metadata.len());
+            (Box::new(BufReader::new(file)), total)
+        }
+        LineSource::Stdin => (Box::new(io::stdin().lock()), None),
+    };
+
+    let mut ctx = FormatContext::new();
+    if let LineSource::File(path) = source {
+        ctx.set_file_name(&path.display().to_string());
+    }
+    let terminator = match config.line_endings {
+        LineEndings::Crlf => "\r\n",
+        _ => "\n",
+    };
+    let mut progress = config.progress.then(|| Progress::new(total));
+    let mut out = BufWriter::new(io::stdout().lock());
+    let mut line = Vec::new();
+    let mut result = String::new();
+    let mut line_no = 0;
+
+    loop {
+        line.clear();
+        let read = reader
+            .read_until(b'\n', &mut line)
+            .map_err(|e| format!("Error reading input: {e}"))?;
+        if read == 0 {
+            break;
+        }
+        line_no += 1;
+
+        let mut end = line.len();
+        if line[..end].ends_with(b"\n") {
+            end -= 1;
+        }
+        if config.line_endings != LineEndings::Keep && line[..end].ends_with(b"\r") {
+            end -= 1;
+        }
+        if !config.lossy
+            && let Err(e) = std::str::from_utf8(&line[..end])
+        {
+            return Err(format!(
+                "Error reading input: line {line_no}: {e} (pass --lossy to replace invalid bytes)"
+            ));
+        }
+
+        ctx.set_line_no(line_no);
+        template
+            .format_lossy_into(&line[..end], &mut ctx, &mut result)
+            .map_err(|e| format!("Error formatting line {line_no}: {e}"))?;
+        let written = out
+            .write_all(result.as_bytes())
+            .and_then(|()| out.write_all(terminator.as_bytes()));
+        match written {
+            Ok(()) => {}
+            // The reader went away, as with `| head`
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            Err(e) => return Err(format!("Error writing output: {e}")),
+        }
+
+        if let Some(progress) = &mut progress {
+            progress.record(read);
+        }
+    }
+
+    match out.flush() {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+            return Err(format!("Error writing output: {e}"));
+        }
+        _ => {}
+    }
+    if let Some(progress) = &progress {
+        progress.report(true);
+    }
+    Ok(())
+}
+
+/// Exit with an error if the template needs access that was not granted
+fn check_access(template: &Template, config: &Config) {
+    if let Err(e) = access_allowed(template, config) {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
+
+/// Check that the access a template needs was granted
+fn access_allowed(template: &Template, config: &Config) -> Result<(), String> {
+    if template.reads_files() && !config.allow_fs {
+        return Err("Error: Template reads files; pass --allow-fs to allow it".to_string());
+    }
+    if template.runs_commands() && !config.allow_exec {
+        return Err("Error: Template runs commands; pass --allow-exec to allow it".to_string());
+    }
+    Ok(())
+}
+
+/// Interval between two checks for changes with `--watch`
+const WATCH_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Modification time and size of a file, to notice when it changes
+fn file_stamp(path: &PathBuf) -> Option<(std::time::SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Read the template file and input, and write the formatted output to stdout
+fn run_watched(
+    template_file: &PathBuf,
+    input_file: Option<&PathBuf>,
+    config: &Config,
+) -> Result<(), String> {
+    let text = read_file(template_file).map_err(|e| format!("Error reading template file: {e}"))?;
+    let template = try_prepare_template(text.trim(), config)?;
+    access_allowed(&template, config)?;
+    let input = match input_file {
+        Some(file) => read_file_bytes(file)
+            .and_then(|content| {
+                finish_input(content, config.lossy, &format!("file '{}'", file.display()))
+            })
+            .map_err(|e| format!("Error reading input file: {e}"))?,
+        None => config.input.clone().unwrap_or_default(),
+    };
+    let result = template
+        .format_bytes(&input)
+        .map_err(|e| format!("Error formatting input: {e}"))?;
+    let mut out = io::stdout().lock();
+    out.write_all(&result)
+        .and_then(|()| out.flush())
+        .map_err(|e| format!("Error writing output: {e}"))
+}
+
+/// Run the template again whenever the template file or input file changes.
+///
+/// Files are polled every [`WATCH_INTERVAL`]. Errors are reported without
+/// stopping, so the template can be fixed and saved again. The screen is
+/// cleared before each run when stdout is a terminal.
+fn run_watch(template_file: &PathBuf, input_file: Option<&PathBuf>, config: &Config) -> ! {
+    use std::io::IsTerminal;
+    let clear = io::stdout().is_terminal();
+    let mut last = None;
+    loop {
+        let stamps = (file_stamp(template_file), input_file.and_then(file_stamp));
+        if last.as_ref() != Some(&stamps) {
+            last = Some(stamps);
+            if clear {
+                print!("\x1b[2J\x1b[H");
+            }
+            if let Err(e) = run_watched(template_file, input_file, config) {
+                eprintln!("{e}");
+            }
+            if !config.quiet {
+                eprintln!(
+                    "\nWatching '{}' for changes (Ctrl-C to stop)",
+                    template_file.display()
+                );
+            }
+        }
+        std::thread::sleep(WATCH_INTERVAL);
+    }
+}
+
 fn main() {
     let cli = Cli::parse();
 
     // Handle help commands first
-    if cli.list_operations {
-        show_operations_help();
+    if let Some(format) = cli.list_operations {
+        show_operations_help(format);
         return;
     }
 
@@ -223,7 +1217,11 @@ fn main() {
     }
 
     // Show help if no arguments and no stdin
-    if cli.template.is_none() && cli.template_file.is_none() && !is_stdin_available() {
+    if cli.template.is_none()
+        && cli.template_file.is_none()
+        && cli.template_name.is_none()
+        && !is_stdin_available()
+    {
         Cli::command().print_help().unwrap();
         return;
     }
@@ -234,21 +1232,39 @@ fn main() {
         std::process::exit(1);
     });
 
-    // Parse template and handle debug mode from both template prefix and CLI flag
-    let template = Template::parse_with_debug(&config.template, None).unwrap_or_else(|e| {
-        eprintln!("Error parsing template: {e}");
-        std::process::exit(1);
-    });
+    if let Some((template_file, input_file)) = &config.watch {
+        run_watch(template_file, input_file.as_ref(), &config);
+    }
 
-    // Enable debug if either the template has ! prefix OR the CLI debug flag is set
-    // Disable debug only if quiet mode is enabled
-    let should_debug = (template.is_debug() || config.debug) && !config.quiet;
-    let template = template.with_debug(should_debug);
+    let template = prepare_template(&config.template, &config);
+    let other = config
+        .diff
+        .as_deref()
+        .map(|text| prepare_template(text, &config));
 
     // If just validating, exit here
     if config.validate {
         if !config.quiet {
             println!("Template syntax is valid");
+            for warning in std::iter::once(&template)
+                .chain(&other)
+                .flat_map(Template::lint)
+            {
+                eprintln!("Warning: {warning}");
+            }
+        }
+        return;
+    }
+
+    check_access(&template, &config);
+
+    if let Some(source) = &config.lines {
+        if let Err(e) = run_per_line(&template, source, &config) {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        if config.cache_stats && !config.quiet {
+            eprintln!("{}", cache_stats());
         }
         return;
     }
@@ -256,14 +1272,53 @@ fn main() {
     // For non-validation, input is required
     let input = config
         .input
+        .as_deref()
         .expect("Input should be available for non-validation operations");
 
+    if let Some(other) = &other {
+        check_access(other, &config);
+    }
+
     // Process input with template
-    let result = template.format(&input).unwrap_or_else(|e| {
+    let fail = |e: String| -> ! {
         eprintln!("Error formatting input: {e}");
         std::process::exit(1);
-    });
+    };
+    let format = |template: &Template| template.format_lossy(input).unwrap_or_else(|e| fail(e));
 
-    // Output result as string
-    print!("{result}");
+    // Output result as bytes, or its diff against the expected output or the
+    // second template's output
+    let differs = if let Some((label, expected)) = &config.expected {
+        // Report the mismatch itself before rendering any diff of it
+        let actual = format(&template);
+        if actual == *expected {
+            false
+        } else {
+            let (line, _, _) = first_difference(expected, &actual);
+            eprintln!("Assertion failed: output differs from {label} at line {line}");
+            compare_outputs(label, "actual", expected, &actual, config.color)
+        }
+    } else if let (Some(other), Some(other_text)) = (&other, &config.diff) {
+        compare_outputs(
+            &config.template,
+            other_text,
+            &format(&template),
+            &format(other),
+            config.color,
+        )
+    } else {
+        let result = template.format_bytes(input).unwrap_or_else(|e| fail(e));
+        if let Err(e) = io::stdout().write_all(&result) {
+            eprintln!("Error writing output: {e}");
+            std::process::exit(1);
+        }
+        false
+    };
+
+    if config.cache_stats && !config.quiet {
+        eprintln!("{}", cache_stats());
+    }
+    if differs {
+        std::process::exit(1);
+    }
 }
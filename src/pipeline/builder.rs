@@ -0,0 +1,1279 @@
+//! Typed construction of templates.
+//!
+//! [`TemplateBuilder`] assembles a template from literal text and operation
+//! chains written as method calls, so Rust callers never format template
+//! syntax by hand. Arguments are passed as values and escaped when the
+//! template string is written, and sub-pipelines such as `map` are closures
+//! over a [`Pipeline`], which cannot hold literal text or another
+//! sub-pipeline.
+
+use smallvec::SmallVec;
+
+use super::parser::{ParsedSection, duplicate_key};
+use super::template::{InputRef, Placeholder, TemplateSection, Variable};
+use super::{
+    CaseLocale, ChecksumAlgorithm, ColumnAlign, HighlightStyle, LengthUnit, ListSource, NormalForm,
+    PadDirection, PathStyle, RangeSpec, SedRule, SortDirection, StringOp, Template, TermColor,
+    TrimDirection, canonical,
+};
+
+/// Defines the operation methods shared by [`Pipeline`] and
+/// [`TemplateBuilder`], which both provide a `push` method.
+macro_rules! operation_methods {
+    () => {
+        /// Splits on `sep` and selects `range` of the parts, like `split:SEP:RANGE`.
+        pub fn split(self, sep: &str, range: Range) -> Self {
+            self.push(StringOp::Split {
+                sep: sep.to_string(),
+                range: range.0,
+                max_splits: None,
+                keep_sep: false,
+                skip_empty: false,
+            })
+        }
+
+        /// Splits on at most `max_splits` separators, like `split:SEP:RANGE:MAX`.
+        pub fn split_max(self, sep: &str, range: Range, max_splits: usize) -> Self {
+            self.push(StringOp::Split {
+                sep: sep.to_string(),
+                range: range.0,
+                max_splits: Some(max_splits),
+                keep_sep: false,
+                skip_empty: false,
+            })
+        }
+
+        /// Splits on `sep`, keeping each separator at the end of the part
+        /// before it, like `split:SEP:RANGE:keep_sep`.
+        pub fn split_keep_sep(self, sep: &str, range: Range) -> Self {
+            self.push(StringOp::Split {
+                sep: sep.to_string(),
+                range: range.0,
+                max_splits: None,
+                keep_sep: true,
+                skip_empty: false,
+            })
+        }
+
+        /// Splits on `sep` and leaves out empty parts, like
+        /// `split:SEP:RANGE:skip_empty`.
+        pub fn split_skip_empty(self, sep: &str, range: Range) -> Self {
+            self.push(StringOp::Split {
+                sep: sep.to_string(),
+                range: range.0,
+                max_splits: None,
+                keep_sep: false,
+                skip_empty: true,
+            })
+        }
+
+        /// Splits on each of `chars` and selects `range` of the parts, like
+        /// `split_any:CHARS:RANGE`.
+        pub fn split_any(self, chars: &str, range: Range) -> Self {
+            self.push(StringOp::SplitAny {
+                chars: chars.to_string(),
+                range: range.0,
+            })
+        }
+
+        /// Splits around the first `sep`, like `partition:SEP`.
+        pub fn partition(self, sep: &str) -> Self {
+            self.push(StringOp::Partition {
+                sep: sep.to_string(),
+            })
+        }
+
+        /// Splits around the last `sep`, like `rpartition:SEP`.
+        pub fn rpartition(self, sep: &str) -> Self {
+            self.push(StringOp::RPartition {
+                sep: sep.to_string(),
+            })
+        }
+
+        /// Joins a list with `sep`, like `join:SEP`.
+        pub fn join(self, sep: &str) -> Self {
+            self.push(StringOp::Join {
+                sep: sep.to_string(),
+                last_sep: None,
+            })
+        }
+
+        /// Joins a list with `sep`, and `last_sep` before the last item, like
+        /// `join:SEP:LAST`.
+        pub fn join_last(self, sep: &str, last_sep: &str) -> Self {
+            self.push(StringOp::Join {
+                sep: sep.to_string(),
+                last_sep: Some(last_sep.to_string()),
+            })
+        }
+
+        /// Sets the separator used to render a final list, like `output_sep:SEP`.
+        pub fn output_sep(self, sep: &str) -> Self {
+            self.push(StringOp::OutputSep {
+                sep: sep.to_string(),
+            })
+        }
+
+        /// Replaces regex matches, like `replace:s/PATTERN/REPLACEMENT/FLAGS`.
+        ///
+        /// Digits in `flags` select the occurrence to replace, as in the
+        /// template syntax, and `l` matches `pattern` as plain text. A `/` in
+        /// `pattern` or `replacement` is escaped when the template string is
+        /// written.
+        pub fn replace(self, pattern: &str, replacement: &str, flags: &str) -> Self {
+            let (digits, flags): (String, String) = flags.chars().partition(char::is_ascii_digit);
+            self.push(StringOp::Replace {
+                rules: vec![SedRule {
+                    pattern: pattern.to_string(),
+                    replacement: replacement.to_string(),
+                    flags,
+                    occurrence: digits.parse().ok(),
+                }],
+            })
+        }
+
+        /// Replaces every occurrence of `from`, like `replace_literal:FROM:TO`.
+        pub fn replace_literal(self, from: &str, to: &str) -> Self {
+            self.push(StringOp::ReplaceLiteral {
+                from: from.to_string(),
+                to: to.to_string(),
+                count: None,
+            })
+        }
+
+        /// Replaces the first `count` occurrences of `from`, like
+        /// `replace_literal:FROM:TO:COUNT`.
+        pub fn replace_literal_n(self, from: &str, to: &str, count: usize) -> Self {
+            self.push(StringOp::ReplaceLiteral {
+                from: from.to_string(),
+                to: to.to_string(),
+                count: Some(count),
+            })
+        }
+
+        /// Converts to uppercase, like `upper`.
+        pub fn upper(self) -> Self {
+            self.upper_locale(CaseLocale::Root)
+        }
+
+        /// Converts to uppercase with the rules of `locale`, like `upper:tr`.
+        pub fn upper_locale(self, locale: CaseLocale) -> Self {
+            self.push(StringOp::Upper { locale })
+        }
+
+        /// Converts to lowercase, like `lower`.
+        pub fn lower(self) -> Self {
+            self.lower_locale(CaseLocale::Root)
+        }
+
+        /// Converts to lowercase with the rules of `locale`, like `lower:tr`.
+        pub fn lower_locale(self, locale: CaseLocale) -> Self {
+            self.push(StringOp::Lower { locale })
+        }
+
+        /// Converts the first grapheme to titlecase, and the rest to lowercase
+        /// if `lower_rest` is set, like `capitalize:lower`.
+        pub fn capitalize(self, lower_rest: bool) -> Self {
+            self.push(StringOp::Capitalize {
+                lower_rest,
+                locale: CaseLocale::Root,
+            })
+        }
+
+        /// Capitalizes with the rules of `locale`, like `capitalize:lower:tr`.
+        pub fn capitalize_locale(self, lower_rest: bool, locale: CaseLocale) -> Self {
+            self.push(StringOp::Capitalize { lower_rest, locale })
+        }
+
+        /// Swaps the case of every letter, like `swapcase`.
+        pub fn swapcase(self) -> Self {
+            self.swapcase_locale(CaseLocale::Root)
+        }
+
+        /// Swaps case with the rules of `locale`, like `swapcase:tr`.
+        pub fn swapcase_locale(self, locale: CaseLocale) -> Self {
+            self.push(StringOp::SwapCase { locale })
+        }
+
+        /// Trims whitespace from both ends, like `trim`.
+        pub fn trim(self) -> Self {
+            self.trim_chars("", TrimDirection::Both)
+        }
+
+        /// Trims any of `chars`, or whitespace if empty, from `direction`,
+        /// like `trim:CHARS:DIRECTION`.
+        pub fn trim_chars(self, chars: &str, direction: TrimDirection) -> Self {
+            self.push(StringOp::Trim {
+                chars: chars.to_string(),
+                direction,
+            })
+        }
+
+        /// Collapses whitespace runs to one space, like `squeeze`.
+        pub fn squeeze(self) -> Self {
+            self.squeeze_chars("")
+        }
+
+        /// Collapses runs of any of `chars`, like `squeeze:CHARS`.
+        pub fn squeeze_chars(self, chars: &str) -> Self {
+            self.push(StringOp::Squeeze {
+                chars: chars.to_string(),
+            })
+        }
+
+        /// Removes `prefix` if present, like `trim_prefix:PREFIX`.
+        pub fn trim_prefix(self, prefix: &str) -> Self {
+            self.push(StringOp::TrimPrefix {
+                prefix: prefix.to_string(),
+            })
+        }
+
+        /// Removes `suffix` if present, like `trim_suffix:SUFFIX`.
+        pub fn trim_suffix(self, suffix: &str) -> Self {
+            self.push(StringOp::TrimSuffix {
+                suffix: suffix.to_string(),
+            })
+        }
+
+        /// Trims matches of `pattern`, like `trim_regex:PATTERN:DIRECTION`.
+        pub fn trim_regex(self, pattern: &str, direction: TrimDirection) -> Self {
+            self.push(StringOp::TrimRegex {
+                pattern: pattern.to_string(),
+                direction,
+            })
+        }
+
+        /// Selects characters, like `substring:RANGE`.
+        pub fn substring(self, range: Range) -> Self {
+            self.push(StringOp::Substring { range: range.0 })
+        }
+
+        /// Selects list items, like `slice:RANGE`.
+        pub fn slice(self, range: Range) -> Self {
+            self.push(StringOp::Slice { range: range.0 })
+        }
+
+        /// Selects list items, clamping the range to the list even with
+        /// strict ranges, like `clamp:RANGE`.
+        pub fn clamp(self, range: Range) -> Self {
+            self.push(StringOp::Clamp { range: range.0 })
+        }
+
+        /// Adds `suffix` at the end, like `append:SUFFIX`.
+        pub fn append(self, suffix: &str) -> Self {
+            self.push(StringOp::Append {
+                suffix: suffix.to_string(),
+            })
+        }
+
+        /// Adds `prefix` at the start, like `prepend:PREFIX`.
+        pub fn prepend(self, prefix: &str) -> Self {
+            self.push(StringOp::Prepend {
+                prefix: prefix.to_string(),
+            })
+        }
+
+        /// Adds `prefix` and `suffix` around the value, like
+        /// `surround:PREFIX:SUFFIX`.
+        pub fn surround(self, prefix: &str, suffix: &str) -> Self {
+            self.push(StringOp::Surround {
+                prefix: prefix.to_string(),
+                suffix: suffix.to_string(),
+            })
+        }
+
+        /// Adds `quote` on both sides, like `quote:QUOTE`.
+        pub fn quote(self, quote: &str) -> Self {
+            self.surround(quote, quote)
+        }
+
+        /// Removes ANSI escape sequences, like `strip_ansi`.
+        pub fn strip_ansi(self) -> Self {
+            self.push(StringOp::StripAnsi)
+        }
+
+        /// Removes accents and other combining marks, like `deaccent`.
+        pub fn deaccent(self) -> Self {
+            self.push(StringOp::Deaccent)
+        }
+
+        /// Transliterates to ASCII, like `ascii`.
+        pub fn ascii(self) -> Self {
+            self.push(StringOp::Ascii)
+        }
+
+        /// Converts to a URL slug joined with `-`, like `slugify`.
+        pub fn slugify(self) -> Self {
+            self.slugify_with("-")
+        }
+
+        /// Converts to a URL slug joined with `separator`, like
+        /// `slugify:SEPARATOR`.
+        pub fn slugify_with(self, separator: &str) -> Self {
+            self.push(StringOp::Slugify {
+                separator: separator.to_string(),
+            })
+        }
+
+        /// Applies a Unicode normalization form, like `normalize:nfc`.
+        pub fn normalize(self, form: NormalForm) -> Self {
+            self.push(StringOp::Normalize { form })
+        }
+
+        /// Formats an integer as an ordinal, or parses one back if `parse`
+        /// is set, like `ordinal:parse`.
+        pub fn ordinal(self, parse: bool) -> Self {
+            self.push(StringOp::Ordinal { parse })
+        }
+
+        /// Spells out an integer in words, or parses words back if `parse`
+        /// is set, like `to_words:parse`.
+        pub fn to_words(self, parse: bool) -> Self {
+            self.push(StringOp::ToWords { parse })
+        }
+
+        /// Formats an integer as a Roman numeral, or parses one back if
+        /// `parse` is set, like `roman:parse`.
+        pub fn roman(self, parse: bool) -> Self {
+            self.push(StringOp::Roman { parse })
+        }
+
+        /// Takes the last component of a path, like `basename`.
+        pub fn basename(self, style: PathStyle) -> Self {
+            self.push(StringOp::Basename { style })
+        }
+
+        /// Removes the last component of a path, like `dirname`.
+        pub fn dirname(self, style: PathStyle) -> Self {
+            self.push(StringOp::Dirname { style })
+        }
+
+        /// Takes the extension of a path, with its dot, like `extname`.
+        pub fn extname(self, style: PathStyle) -> Self {
+            self.push(StringOp::Extname { style })
+        }
+
+        /// Removes the extension of a path, like `strip_ext`.
+        pub fn strip_ext(self, style: PathStyle) -> Self {
+            self.push(StringOp::StripExt { style })
+        }
+
+        /// Replaces the extension of a path, like `with_ext:md`.
+        pub fn with_ext(self, ext: &str, style: PathStyle) -> Self {
+            self.push(StringOp::WithExt {
+                ext: ext.to_string(),
+                style,
+            })
+        }
+
+        /// Normalizes a path lexically, like `normalize_path`.
+        pub fn normalize_path(self, style: PathStyle) -> Self {
+            self.push(StringOp::NormalizePath { style })
+        }
+
+        /// Takes the scheme of a URL, like `url_scheme`.
+        pub fn url_scheme(self) -> Self {
+            self.push(StringOp::UrlScheme)
+        }
+
+        /// Takes the host of a URL, like `url_host`.
+        pub fn url_host(self) -> Self {
+            self.push(StringOp::UrlHost)
+        }
+
+        /// Takes the path of a URL, like `url_path`.
+        pub fn url_path(self) -> Self {
+            self.push(StringOp::UrlPath)
+        }
+
+        /// Takes the value of a URL query parameter, like `url_query:KEY`.
+        pub fn url_query(self, key: &str) -> Self {
+            self.push(StringOp::UrlQuery {
+                key: key.to_string(),
+            })
+        }
+
+        /// Sets the foreground color for terminal output, like `color:COLOR`.
+        pub fn color(self, color: TermColor) -> Self {
+            self.push(StringOp::Color { color })
+        }
+
+        /// Sets the background color for terminal output, like `bg:COLOR`.
+        pub fn bg(self, color: TermColor) -> Self {
+            self.push(StringOp::Background { color })
+        }
+
+        /// Makes the value bold for terminal output, like `bold`.
+        pub fn bold(self) -> Self {
+            self.push(StringOp::Bold)
+        }
+
+        /// Underlines the value for terminal output, like `underline`.
+        pub fn underline(self) -> Self {
+            self.push(StringOp::Underline)
+        }
+
+        /// Resets every terminal style after the value, like `style_reset`.
+        pub fn style_reset(self) -> Self {
+            self.push(StringOp::StyleReset)
+        }
+
+        /// Highlights matches of `pattern`, like `highlight:PATTERN:COLOR` or
+        /// `highlight:PATTERN:mark:OPEN:CLOSE`.
+        pub fn highlight(self, pattern: &str, style: HighlightStyle) -> Self {
+            self.push(StringOp::Highlight {
+                pattern: pattern.to_string(),
+                style,
+            })
+        }
+
+        /// Reads the file named by the value, like `read_file`.
+        pub fn read_file(self) -> Self {
+            self.push(StringOp::ReadFile)
+        }
+
+        /// Runs `command` with the value as its input, like `exec:COMMAND`.
+        pub fn exec(self, command: &str) -> Self {
+            self.push(StringOp::Exec {
+                command: command.to_string(),
+            })
+        }
+
+        /// Keeps items matching `pattern`, like `filter:PATTERN`.
+        pub fn filter(self, pattern: &str) -> Self {
+            self.push(StringOp::Filter {
+                pattern: pattern.to_string(),
+            })
+        }
+
+        /// Drops items matching `pattern`, like `filter_not:PATTERN`.
+        pub fn filter_not(self, pattern: &str) -> Self {
+            self.push(StringOp::FilterNot {
+                pattern: pattern.to_string(),
+            })
+        }
+
+        /// Keeps items starting with `prefix`, like `filter_prefix:PREFIX`.
+        pub fn filter_prefix(self, prefix: &str) -> Self {
+            self.push(StringOp::FilterPrefix {
+                prefix: prefix.to_string(),
+            })
+        }
+
+        /// Keeps items ending with `suffix`, like `filter_suffix:SUFFIX`.
+        pub fn filter_suffix(self, suffix: &str) -> Self {
+            self.push(StringOp::FilterSuffix {
+                suffix: suffix.to_string(),
+            })
+        }
+
+        /// Keeps items containing `needle`, like `filter_contains:TEXT`.
+        pub fn filter_contains(self, needle: &str) -> Self {
+            self.push(StringOp::FilterContains {
+                needle: needle.to_string(),
+            })
+        }
+
+        /// Drops items that had invalid UTF-8 before lossy reading, like
+        /// `filter_valid_utf8`.
+        pub fn filter_valid_utf8(self) -> Self {
+            self.push(StringOp::FilterValidUtf8)
+        }
+
+        /// Keeps items that pass the Luhn check, like `filter_luhn`.
+        pub fn filter_luhn(self) -> Self {
+            self.push(StringOp::FilterLuhn)
+        }
+
+        /// Keeps items whose checksum is `expected`, like
+        /// `filter_checksum:ALGORITHM:EXPECTED`.
+        pub fn filter_checksum(self, algorithm: ChecksumAlgorithm, expected: u32) -> Self {
+            self.push(StringOp::FilterChecksum {
+                algorithm,
+                expected,
+            })
+        }
+
+        /// Keeps items whose key matches `pattern`, or is non-empty without
+        /// one, like `filter_by:{...}:PATTERN`.
+        pub fn filter_by(
+            self,
+            key: impl FnOnce(Pipeline) -> Pipeline,
+            pattern: Option<&str>,
+        ) -> Self {
+            self.push(StringOp::FilterBy {
+                operations: sub_pipeline(key),
+                pattern: pattern.map(str::to_string),
+            })
+        }
+
+        /// Applies a pipeline to every item, like `map:{...}`.
+        pub fn map(self, ops: impl FnOnce(Pipeline) -> Pipeline) -> Self {
+            self.push(StringOp::Map {
+                operations: sub_pipeline(ops),
+            })
+        }
+
+        /// Applies a pipeline to every item and drops the items it fails on,
+        /// like `map_fallible:{...}`.
+        pub fn map_fallible(self, ops: impl FnOnce(Pipeline) -> Pipeline) -> Self {
+            self.push(StringOp::MapFallible {
+                operations: sub_pipeline(ops),
+            })
+        }
+
+        /// Applies a pipeline to a string, keeping the string or using
+        /// `fallback` if it fails, like `try:{...}:FALLBACK`.
+        pub fn try_ops(
+            self,
+            ops: impl FnOnce(Pipeline) -> Pipeline,
+            fallback: Option<&str>,
+        ) -> Self {
+            self.push(StringOp::Try {
+                operations: sub_pipeline(ops),
+                fallback: fallback.map(str::to_string),
+            })
+        }
+
+        /// Sorts a list, like `sort:DIRECTION`.
+        pub fn sort(self, direction: SortDirection) -> Self {
+            self.push(StringOp::Sort { direction })
+        }
+
+        /// Sorts a list by a key, compared as numbers if `numeric`, like
+        /// `sort_by:{...}:num:DIRECTION`.
+        pub fn sort_by(
+            self,
+            key: impl FnOnce(Pipeline) -> Pipeline,
+            numeric: bool,
+            direction: SortDirection,
+        ) -> Self {
+            self.push(StringOp::SortBy {
+                operations: sub_pipeline(key),
+                numeric,
+                direction,
+            })
+        }
+
+        /// Reverses a string or list, like `reverse`.
+        pub fn reverse(self) -> Self {
+            self.push(StringOp::Reverse)
+        }
+
+        /// Removes repeated items, like `unique`.
+        pub fn unique(self) -> Self {
+            self.push(StringOp::Unique)
+        }
+
+        /// Removes items with a repeated key, like `unique_by:{...}`.
+        pub fn unique_by(self, key: impl FnOnce(Pipeline) -> Pipeline) -> Self {
+            self.push(StringOp::UniqueBy {
+                operations: sub_pipeline(key),
+            })
+        }
+
+        /// Keeps the first copy of repeated items, like `duplicates`.
+        pub fn duplicates(self) -> Self {
+            self.push(StringOp::Duplicates)
+        }
+
+        /// Counts each item into `item<TAB>count` lines, most frequent first,
+        /// keeping the `top` ones if set, like `histogram:TOP`.
+        pub fn histogram(self, top: Option<usize>) -> Self {
+            self.push(StringOp::Histogram { top })
+        }
+
+        /// Drops blank items, like `remove_empty`.
+        pub fn remove_empty(self) -> Self {
+            self.push(StringOp::RemoveEmpty)
+        }
+
+        /// Keeps items also in `other`, like `intersect:LIST`.
+        pub fn intersect(self, other: ListSource) -> Self {
+            self.push(StringOp::Intersect { other })
+        }
+
+        /// Drops items also in `other`, like `diff:LIST`.
+        pub fn diff(self, other: ListSource) -> Self {
+            self.push(StringOp::Diff { other })
+        }
+
+        /// Adds the items of `other` not yet present, like `union:LIST`.
+        pub fn union(self, other: ListSource) -> Self {
+            self.push(StringOp::Union { other })
+        }
+
+        /// Shuffles a list, reproducibly with a `seed`, like `shuffle:SEED`.
+        pub fn shuffle(self, seed: Option<u64>) -> Self {
+            self.push(StringOp::Shuffle { seed })
+        }
+
+        /// Picks `count` random items, reproducibly with a `seed`, like
+        /// `sample:COUNT:SEED`.
+        pub fn sample(self, count: usize, seed: Option<u64>) -> Self {
+            self.push(StringOp::Sample { count, seed })
+        }
+
+        /// Extracts the value of `key` from `key=value` pairs, like
+        /// `kv:KEY:PAIR_SEP:KV_SEP`. An empty `pair_sep` stands for whitespace.
+        pub fn kv(self, key: &str, pair_sep: &str, kv_sep: &str) -> Self {
+            self.push(StringOp::Kv {
+                key: key.to_string(),
+                pair_sep: pair_sep.to_string(),
+                kv_sep: kv_sep.to_string(),
+            })
+        }
+
+        /// Lists the keys of `key=value` pairs, like `kv_keys:PAIR_SEP:KV_SEP`.
+        pub fn kv_keys(self, pair_sep: &str, kv_sep: &str) -> Self {
+            self.push(StringOp::KvKeys {
+                pair_sep: pair_sep.to_string(),
+                kv_sep: kv_sep.to_string(),
+            })
+        }
+
+        /// Lists the values of `key=value` pairs, like `kv_values:PAIR_SEP:KV_SEP`.
+        pub fn kv_values(self, pair_sep: &str, kv_sep: &str) -> Self {
+            self.push(StringOp::KvValues {
+                pair_sep: pair_sep.to_string(),
+                kv_sep: kv_sep.to_string(),
+            })
+        }
+
+        /// Splits into words at Unicode word boundaries, like `words`.
+        pub fn words(self) -> Self {
+            self.push(StringOp::Words)
+        }
+
+        /// Splits into trimmed sentences at Unicode sentence boundaries, like
+        /// `sentences`.
+        pub fn sentences(self) -> Self {
+            self.push(StringOp::Sentences)
+        }
+
+        /// Finds all email addresses, like `extract_emails`.
+        pub fn extract_emails(self) -> Self {
+            self.push(StringOp::ExtractEmails)
+        }
+
+        /// Finds all URLs, like `extract_urls`.
+        pub fn extract_urls(self) -> Self {
+            self.push(StringOp::ExtractUrls)
+        }
+
+        /// Finds all UUIDs, like `extract_uuids`.
+        pub fn extract_uuids(self) -> Self {
+            self.push(StringOp::ExtractUuids)
+        }
+
+        /// Aligns rows split on `sep` into columns, like `table:SEP:ALIGN:header`.
+        pub fn table(self, sep: &str, align: &[ColumnAlign], header: bool) -> Self {
+            self.push(StringOp::Table {
+                sep: sep.to_string(),
+                align: align.to_vec(),
+                header,
+            })
+        }
+
+        /// Writes rows split on `sep` as a Markdown table, like
+        /// `md_table:SEP:ALIGN:header`.
+        pub fn md_table(self, sep: &str, align: &[ColumnAlign], header: bool) -> Self {
+            self.push(StringOp::MarkdownTable {
+                sep: sep.to_string(),
+                align: align.to_vec(),
+                header,
+            })
+        }
+
+        /// Writes rows split on `sep` as an HTML table, like
+        /// `html_table:SEP:ALIGN:header`.
+        pub fn html_table(self, sep: &str, align: &[ColumnAlign], header: bool) -> Self {
+            self.push(StringOp::HtmlTable {
+                sep: sep.to_string(),
+                align: align.to_vec(),
+                header,
+            })
+        }
+
+        /// Pads to `width` with `fill`, like `pad:WIDTH:FILL:DIRECTION`.
+        pub fn pad(self, width: usize, fill: &str, direction: PadDirection) -> Self {
+            self.push(StringOp::Pad {
+                width,
+                fill: fill.to_string(),
+                direction,
+                truncate: false,
+            })
+        }
+
+        /// Pads to `width` and cuts longer values down to it, like
+        /// `pad:WIDTH:FILL:DIRECTION:truncate`.
+        pub fn pad_truncate(self, width: usize, fill: &str, direction: PadDirection) -> Self {
+            self.push(StringOp::Pad {
+                width,
+                fill: fill.to_string(),
+                direction,
+                truncate: true,
+            })
+        }
+
+        /// Pads both sides to `width`, like `center:WIDTH:FILL`.
+        pub fn center(self, width: usize, fill: &str) -> Self {
+            self.pad(width, fill, PadDirection::Both)
+        }
+
+        /// Extracts the first match of `pattern`, or of its capture `group`,
+        /// like `regex_extract:PATTERN:GROUP`.
+        pub fn regex_extract(self, pattern: &str, group: Option<usize>) -> Self {
+            self.push(StringOp::RegexExtract {
+                pattern: pattern.to_string(),
+                group,
+            })
+        }
+
+        /// Extracts the contents of group `nth` between balanced `open` and
+        /// `close` delimiters, like `extract_between:OPEN:CLOSE:NTH`.
+        pub fn extract_between(self, open: &str, close: &str, nth: isize) -> Self {
+            self.push(StringOp::ExtractBetween {
+                open: open.to_string(),
+                close: close.to_string(),
+                nth,
+            })
+        }
+
+        /// Counts the length of the value in `unit`, like `len:UNIT`.
+        pub fn len(self, unit: LengthUnit) -> Self {
+            self.push(StringOp::Len { unit })
+        }
+
+        /// Writes the value as a JSON array of strings, like `json_array`.
+        pub fn json_array(self) -> Self {
+            self.push(StringOp::JsonArray)
+        }
+
+        /// Writes the value as a JSON object with the given keys, like
+        /// `json_object:KEY,KEY`.
+        pub fn json_object(self, keys: &[&str]) -> Self {
+            self.push(StringOp::JsonObject {
+                keys: keys.iter().map(|key| key.to_string()).collect(),
+            })
+        }
+
+        /// Gets a scalar from a YAML document, like `yaml_get:PATH`. Each
+        /// key of `path` is one step, so keys may contain dots.
+        pub fn yaml_get(self, path: &[&str]) -> Self {
+            self.push(StringOp::YamlGet {
+                path: path.iter().map(|key| key.to_string()).collect(),
+            })
+        }
+
+        /// Gets a scalar from a TOML document, like `toml_get:PATH`. Each
+        /// key of `path` is one step, so keys may contain dots.
+        pub fn toml_get(self, path: &[&str]) -> Self {
+            self.push(StringOp::TomlGet {
+                path: path.iter().map(|key| key.to_string()).collect(),
+            })
+        }
+    };
+}
+
+/// Items or characters selected by a [`TemplateBuilder`] operation.
+///
+/// Indices count from `0`, and negative indices count back from the end,
+/// as in template range syntax.
+///
+/// # Examples
+///
+/// ```rust
+/// use string_pipeline::{Range, TemplateBuilder};
+///
+/// let template = TemplateBuilder::new()
+///     .split(",", Range::between(1, -1))
+///     .join("+")
+///     .build()
+///     .unwrap();
+/// assert_eq!(template.template_string(), "{split:,:1..-1|join:+}");
+/// assert_eq!(template.format("a,b,c,d").unwrap(), "b+c");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Range(RangeSpec);
+
+impl Range {
+    /// Selects everything, written `..`.
+    pub fn all() -> Self {
+        Self(RangeSpec::Range(None, None, false))
+    }
+
+    /// Selects a single item, written `N`.
+    pub fn index(index: isize) -> Self {
+        Self(RangeSpec::Index(index))
+    }
+
+    /// Selects from `start` to the end, written `N..`.
+    pub fn from(start: isize) -> Self {
+        Self(RangeSpec::Range(Some(start), None, false))
+    }
+
+    /// Selects up to `end`, exclusive, written `..N`.
+    pub fn to(end: isize) -> Self {
+        Self(RangeSpec::Range(None, Some(end), false))
+    }
+
+    /// Selects from `start` up to `end`, exclusive, written `N..M`.
+    pub fn between(start: isize, end: isize) -> Self {
+        Self(RangeSpec::Range(Some(start), Some(end), false))
+    }
+
+    /// Selects from `start` up to `end`, inclusive, written `N..=M`.
+    pub fn inclusive(start: isize, end: isize) -> Self {
+        Self(RangeSpec::Range(Some(start), Some(end), true))
+    }
+}
+
+/// A chain of operations without literal text, used for sub-pipelines.
+///
+/// The closures given to [`TemplateBuilder::map`] and the other operations
+/// with a sub-pipeline receive an empty `Pipeline` and return it with the
+/// operations to apply. A pipeline can also be built on its own and added
+/// to a template section with [`TemplateBuilder::pipeline`].
+///
+/// # Examples
+///
+/// ```rust
+/// use string_pipeline::{Pipeline, Range, TemplateBuilder};
+///
+/// let tidy = Pipeline::new().trim().lower();
+/// let template = TemplateBuilder::new()
+///     .split(",", Range::all())
+///     .map(|item| item.pipeline(tidy.clone()))
+///     .join(",")
+///     .build()
+///     .unwrap();
+/// assert_eq!(template.format(" A ,B").unwrap(), "a,b");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pipeline {
+    ops: Vec<StringOp>,
+}
+
+impl Pipeline {
+    /// Creates an empty pipeline.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends the operations of another pipeline.
+    pub fn pipeline(mut self, other: Pipeline) -> Self {
+        self.ops.extend(other.ops);
+        self
+    }
+
+    /// Returns the operations of the pipeline.
+    pub fn ops(&self) -> &[StringOp] {
+        &self.ops
+    }
+
+    /// Converts the pipeline into its operations, as taken by
+    /// [`Template::from_ops`].
+    pub fn into_ops(self) -> Vec<StringOp> {
+        self.ops
+    }
+
+    fn push(mut self, op: StringOp) -> Self {
+        self.ops.push(op);
+        self
+    }
+
+    operation_methods!();
+}
+
+/// A section of a template assembled from operations.
+///
+/// Used with [`Template::from_sections`] to build templates mixing literal
+/// text and operation sections without writing template syntax.
+///
+/// # Examples
+///
+/// ```rust
+/// use string_pipeline::{Pipeline, Section, Template};
+///
+/// let template = Template::from_sections(vec![
+///     Section::Literal("Name: ".to_string()),
+///     Section::Template {
+///         ops: Pipeline::new().trim().upper().into_ops(),
+///         input: None,
+///         variable: None,
+///         placeholder: None,
+///     },
+/// ])
+/// .unwrap();
+/// assert_eq!(template.template_string(), "Name: {trim|upper}");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Section {
+    /// Text copied to the output unchanged.
+    Literal(String),
+    /// Operations applied to the input, like `{...}` in template syntax.
+    Template {
+        /// The operations to apply, in order.
+        ops: Vec<StringOp>,
+        /// Input read in structured formatting, like `input=`.
+        input: Option<InputRef>,
+        /// Variable stored or read, like `set:` and `get:`.
+        variable: Option<Variable>,
+        /// Value read instead of the input, like `{input_len}`.
+        placeholder: Option<Placeholder>,
+    },
+}
+
+/// Builds a template from sections, merging adjacent literal text as the
+/// parser does.
+pub(crate) fn build_template(sections: Vec<Section>) -> Result<Template, String> {
+    let mut raw = String::new();
+    let mut built: Vec<TemplateSection> = Vec::with_capacity(sections.len());
+    for section in sections {
+        match section {
+            Section::Literal(text) => {
+                raw.push_str(&text);
+                match built.last_mut() {
+                    Some(TemplateSection::Literal(literal)) => literal.push_str(&text),
+                    _ if text.is_empty() => {}
+                    _ => built.push(TemplateSection::Literal(text)),
+                }
+            }
+            Section::Template {
+                ops,
+                input,
+                variable,
+                placeholder,
+            } => {
+                validate(&ops, None)?;
+                if placeholder.is_some() && (input.is_some() || variable.is_some()) {
+                    return Err(
+                        "A placeholder section cannot use `input=`, `set:` or `get:`".to_string(),
+                    );
+                }
+                if (input.is_some() || variable.is_some() || placeholder.is_some())
+                    && matches!(ops.last(), Some(StringOp::Each { .. }))
+                {
+                    return Err(
+                        "The list of `each:{...}` cannot use `input=`, `set:`, `get:` or a placeholder"
+                            .to_string(),
+                    );
+                }
+                let spans = canonical::write_section(
+                    &mut raw,
+                    &ops,
+                    input.as_ref(),
+                    variable.as_ref(),
+                    placeholder,
+                );
+                built.push(TemplateSection::from_parsed(ParsedSection {
+                    ops,
+                    spans,
+                    debug: false,
+                    input,
+                    variable,
+                    placeholder,
+                }));
+            }
+        }
+    }
+    let template = Template::new(raw, built, false);
+    template.check_variables()?;
+    template.check_types()?;
+    Ok(template)
+}
+
+#[derive(Debug, Clone, Default)]
+struct OpenSection {
+    ops: Vec<StringOp>,
+    input: Option<InputRef>,
+    variable: Option<Variable>,
+    placeholder: Option<Placeholder>,
+}
+
+impl From<OpenSection> for Section {
+    fn from(section: OpenSection) -> Self {
+        Section::Template {
+            ops: section.ops,
+            input: section.input,
+            variable: section.variable,
+            placeholder: section.placeholder,
+        }
+    }
+}
+
+/// Builds a [`Template`] from typed operations instead of template syntax.
+///
+/// Operation methods append to the current template section, starting one
+/// if the template ends with literal text. [`literal`](Self::literal) adds
+/// text between sections and [`section`](Self::section) starts a new section
+/// right after the current one. [`build`](Self::build) checks the result as
+/// [`Template::parse`] would and writes the equivalent template string,
+/// available from [`Template::template_string`].
+///
+/// # Examples
+///
+/// ```rust
+/// use string_pipeline::{Range, TemplateBuilder};
+///
+/// let template = TemplateBuilder::new()
+///     .literal("Tags: ")
+///     .split(",", Range::all())
+///     .map(|tag| tag.trim().upper())
+///     .join("-")
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(template.template_string(), "Tags: {split:,:..|map:{trim|upper}|join:-}");
+/// assert_eq!(template.format("a, b ,c").unwrap(), "Tags: A-B-C");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TemplateBuilder {
+    sections: Vec<Section>,
+    /// Section that operations are appended to, unless the template ends
+    /// with literal text.
+    current: Option<OpenSection>,
+}
+
+impl TemplateBuilder {
+    /// Creates an empty template builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends literal text, ending the current section.
+    pub fn literal(mut self, text: &str) -> Self {
+        self.end_section();
+        self.sections.push(Section::Literal(text.to_string()));
+        self
+    }
+
+    /// Starts a new, empty section.
+    ///
+    /// Only needed for adjacent sections, or for a section without
+    /// operations, which outputs its input unchanged.
+    pub fn section(mut self) -> Self {
+        self.end_section();
+        self.current = Some(OpenSection::default());
+        self
+    }
+
+    /// Selects the input of the current section in structured formatting,
+    /// like `input=` in template syntax.
+    pub fn input(mut self, input: InputRef) -> Self {
+        self.current.get_or_insert_with(OpenSection::default).input = Some(input);
+        self
+    }
+
+    /// Stores the result of the current section under `name`, like `set:`
+    /// in template syntax.
+    pub fn set(mut self, name: &str) -> Self {
+        self.current
+            .get_or_insert_with(OpenSection::default)
+            .variable = Some(Variable::Set(name.to_string()));
+        self
+    }
+
+    /// Runs the current section on the value stored under `name` instead of
+    /// the input, like `get:` in template syntax.
+    pub fn get(mut self, name: &str) -> Self {
+        self.current
+            .get_or_insert_with(OpenSection::default)
+            .variable = Some(Variable::Get(name.to_string()));
+        self
+    }
+
+    /// Runs the current section on a value provided by the formatter instead
+    /// of the input, like `{input_len}` in template syntax.
+    pub fn placeholder(mut self, placeholder: Placeholder) -> Self {
+        self.current
+            .get_or_insert_with(OpenSection::default)
+            .placeholder = Some(placeholder);
+        self
+    }
+
+    /// Appends the operations of a pipeline to the current section.
+    pub fn pipeline(mut self, pipeline: Pipeline) -> Self {
+        let section = self.current.get_or_insert_with(OpenSection::default);
+        section.ops.extend(pipeline.ops);
+        self
+    }
+
+    /// Builds the template.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error [`Template::parse`] would give for the equivalent
+    /// template string, such as an empty `partition` separator or a type
+    /// error like `sort` on a string.
+    pub fn build(mut self) -> Result<Template, String> {
+        self.end_section();
+        build_template(self.sections)
+    }
+
+    /// Writes the template string [`build`](Self::build) would produce,
+    /// without checking it.
+    ///
+    /// Values that template syntax cannot express, such as a `{` in literal
+    /// text or a `/` in a `replace` replacement, are written as they are,
+    /// so the string may not parse back to the same template.
+    pub fn to_template_string(&self) -> String {
+        let mut out = String::new();
+        for section in &self.sections {
+            match section {
+                Section::Literal(text) => out.push_str(text),
+                Section::Template {
+                    ops,
+                    input,
+                    variable,
+                    placeholder,
+                } => {
+                    canonical::write_section(
+                        &mut out,
+                        ops,
+                        input.as_ref(),
+                        variable.as_ref(),
+                        *placeholder,
+                    );
+                }
+            }
+        }
+        if let Some(OpenSection {
+            ops,
+            input,
+            variable,
+            placeholder,
+        }) = &self.current
+        {
+            canonical::write_section(
+                &mut out,
+                ops,
+                input.as_ref(),
+                variable.as_ref(),
+                *placeholder,
+            );
+        }
+        out
+    }
+
+    fn end_section(&mut self) {
+        if let Some(section) = self.current.take() {
+            self.sections.push(section.into());
+        }
+    }
+
+    fn push(mut self, op: StringOp) -> Self {
+        self.current
+            .get_or_insert_with(OpenSection::default)
+            .ops
+            .push(op);
+        self
+    }
+
+    /// Formats `body` once for each item of the current section's result
+    /// and joins the outputs, like `{each:{OPS}:TEMPLATE}`. Ends the section.
+    ///
+    /// A string result counts as a single item. Whether `body` may read
+    /// files or run commands is decided by the built template.
+    pub fn each(self, body: Template) -> Self {
+        let body = body.with_fs_access(true).with_exec_access(true);
+        let mut builder = self.push(StringOp::Each {
+            body: Box::new(body),
+        });
+        builder.end_section();
+        builder
+    }
+
+    operation_methods!();
+}
+
+/// Rejects what the template grammar or parser would reject.
+///
+/// `parent` is the operation whose sub-pipeline `ops` is, if any.
+fn validate(ops: &[StringOp], parent: Option<&str>) -> Result<(), String> {
+    for (i, op) in ops.iter().enumerate() {
+        match op {
+            StringOp::Each { .. } if parent.is_some() || i + 1 < ops.len() => {
+                return Err("`each` must end its section: {each:{OPS}:TEMPLATE}".to_string());
+            }
+            StringOp::Try { operations, .. } => {
+                if operations.is_empty() {
+                    return Err("`try` needs at least one operation".to_string());
+                }
+                validate(operations, Some(op.keyword()))?;
+            }
+            StringOp::Map { operations }
+            | StringOp::MapFallible { operations }
+            | StringOp::SortBy { operations, .. }
+            | StringOp::UniqueBy { operations }
+            | StringOp::FilterBy { operations, .. } => {
+                if let Some(parent) = parent {
+                    return Err(format!(
+                        "`{}` cannot be used inside `{parent}`",
+                        op.keyword()
+                    ));
+                }
+                if operations.is_empty() {
+                    return Err(format!("`{}` needs at least one operation", op.keyword()));
+                }
+                validate(operations, Some(op.keyword()))?;
+            }
+            StringOp::YamlGet { path } | StringOp::TomlGet { path }
+                if path.is_empty() || path.iter().any(String::is_empty) =>
+            {
+                return Err(format!("Empty key in {} path", op.keyword()));
+            }
+            StringOp::Partition { sep } | StringOp::RPartition { sep } if sep.is_empty() => {
+                return Err("Empty separator in partition".to_string());
+            }
+            StringOp::Replace { rules } if rules.iter().any(|rule| rule.pattern.is_empty()) => {
+                return Err("Empty pattern in sed string".to_string());
+            }
+            StringOp::Replace { rules } if rules.iter().any(|rule| rule.occurrence == Some(0)) => {
+                return Err("Invalid occurrence '0' in sed flags".to_string());
+            }
+            StringOp::ReplaceLiteral { from, .. } if from.is_empty() => {
+                return Err("Empty text to replace in replace_literal".to_string());
+            }
+            StringOp::JsonObject { keys } if duplicate_key(keys).is_some() => {
+                return Err(format!(
+                    "Duplicate key '{}' in json_object",
+                    duplicate_key(keys).unwrap_or_default()
+                ));
+            }
+            StringOp::Exec { command } if command.is_empty() => {
+                return Err("Exec command cannot be empty".to_string());
+            }
+            StringOp::ExtractBetween { open, close, .. } if open.is_empty() || close.is_empty() => {
+                return Err("extract_between delimiters cannot be empty".to_string());
+            }
+            StringOp::SplitAny { chars, .. } if chars.is_empty() => {
+                return Err("split_any requires at least one separator character".to_string());
+            }
+            StringOp::Split { range, .. }
+            | StringOp::SplitAny { range, .. }
+            | StringOp::Substring { range }
+            | StringOp::Slice { range }
+            | StringOp::Clamp { range }
+                if has_zero_step(range) =>
+            {
+                return Err("Range step cannot be 0".to_string());
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn has_zero_step(range: &RangeSpec) -> bool {
+    match range {
+        RangeSpec::Stepped(.., step) => *step == 0,
+        RangeSpec::Multi(parts) => parts.iter().any(has_zero_step),
+        RangeSpec::Index(_) | RangeSpec::Range(..) => false,
+    }
+}
+
+fn sub_pipeline(build: impl FnOnce(Pipeline) -> Pipeline) -> Box<SmallVec<[StringOp; 8]>> {
+    Box::new(SmallVec::from_vec(build(Pipeline::new()).ops))
+}
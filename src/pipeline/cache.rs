@@ -0,0 +1,242 @@
+//! Cache scopes and statistics for the regex and split caches.
+//!
+//! By default, compiled regexes and split results are cached process-wide
+//! and shared by every template. The global caches count their hits and
+//! misses with relaxed atomics, and [`cache_stats`] takes a snapshot of
+//! those counters together with the current cache sizes.
+//!
+//! A template can instead keep its caches per thread or per
+//! [`FormatContext`](crate::FormatContext), selected with [`CacheScope`].
+//! The formatter records the scope of the running call in a thread-local,
+//! which the cache lookups read.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use regex::Regex;
+
+use super::{
+    LIMITED_REGEX_CACHE, LimitedRegexCacheKey, REGEX_CACHE, SPLIT_CACHE, SplitCacheKey,
+    SplitCacheValue,
+};
+
+/// Where a template caches compiled regexes and split results.
+///
+/// The global caches are shared by every thread, so busy multithreaded
+/// programs contend on them, and templates formatting data for different
+/// callers see each other's entries. The other scopes keep the caches apart
+/// and need no locking.
+///
+/// # Examples
+///
+/// ```rust
+/// use string_pipeline::{CacheScope, Template};
+///
+/// let template = Template::parse("{split:,:..|filter:^a|join:-}")
+///     .unwrap()
+///     .with_cache_scope(CacheScope::Thread);
+/// assert_eq!(template.format("ab,b,ac").unwrap(), "ab-ac");
+/// assert_eq!(template.cache_scope(), CacheScope::Thread);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CacheScope {
+    /// One cache for the whole process, shared by every template and
+    /// thread. Reported by [`cache_stats`].
+    #[default]
+    Global,
+    /// One cache per thread, shared by the templates formatted on it.
+    Thread,
+    /// One cache per [`FormatContext`](crate::FormatContext) passed to
+    /// [`Template::format_into`](crate::Template::format_into), or per call
+    /// for the methods that take no context.
+    Context,
+}
+
+/// Regex and split caches owned by a thread or a format context.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LocalCaches {
+    pub(crate) regex: HashMap<String, Regex>,
+    pub(crate) limited_regex: HashMap<LimitedRegexCacheKey, Regex>,
+    pub(crate) split: HashMap<SplitCacheKey, SplitCacheValue>,
+}
+
+impl LocalCaches {
+    pub(crate) fn clear(&mut self) {
+        self.regex.clear();
+        self.limited_regex.clear();
+        self.split.clear();
+    }
+}
+
+thread_local! {
+    /// Scope of the format call running on this thread.
+    static ACTIVE_SCOPE: Cell<CacheScope> = const { Cell::new(CacheScope::Global) };
+    static THREAD_CACHES: RefCell<LocalCaches> = RefCell::new(LocalCaches::default());
+    /// Caches of the format call running with [`CacheScope::Context`].
+    static CONTEXT_CACHES: RefCell<LocalCaches> = RefCell::new(LocalCaches::default());
+}
+
+/// Restores the scope and context caches of the enclosing call, and hands
+/// the caches used by the call back to their context.
+struct ScopeGuard<'a> {
+    scope: CacheScope,
+    /// Context caches of the enclosing call, if this call replaced them.
+    caches: Option<LocalCaches>,
+    owned: Option<&'a mut LocalCaches>,
+}
+
+impl Drop for ScopeGuard<'_> {
+    fn drop(&mut self) {
+        ACTIVE_SCOPE.set(self.scope);
+        if let Some(previous) = self.caches.take() {
+            let used = CONTEXT_CACHES.replace(previous);
+            if let Some(owned) = self.owned.take() {
+                *owned = used;
+            }
+        }
+    }
+}
+
+/// Runs a format call `f` with the caches of `scope`.
+///
+/// With [`CacheScope::Context`], `owned` are the caches of the context
+/// being formatted. Without one, a call nested in another context-scoped
+/// call, such as the template of an `each` section, shares its caches, and
+/// any other call starts with empty ones.
+pub(crate) fn with_scope<R>(
+    scope: CacheScope,
+    mut owned: Option<&mut LocalCaches>,
+    f: impl FnOnce() -> R,
+) -> R {
+    let previous = ACTIVE_SCOPE.replace(scope);
+    let mut guard = ScopeGuard {
+        scope: previous,
+        caches: None,
+        owned: None,
+    };
+    if scope == CacheScope::Context && (owned.is_some() || previous != CacheScope::Context) {
+        let caches = owned.as_deref_mut().map(std::mem::take).unwrap_or_default();
+        guard.caches = Some(CONTEXT_CACHES.replace(caches));
+        guard.owned = owned;
+    }
+    f()
+}
+
+/// Runs `f` on the caches of the running call, or returns `None` if it uses
+/// the global caches.
+pub(crate) fn with_local<R>(f: impl FnOnce(&mut LocalCaches) -> R) -> Option<R> {
+    match ACTIVE_SCOPE.get() {
+        CacheScope::Global => None,
+        CacheScope::Thread => Some(THREAD_CACHES.with_borrow_mut(f)),
+        CacheScope::Context => Some(CONTEXT_CACHES.with_borrow_mut(f)),
+    }
+}
+
+static REGEX_HITS: AtomicU64 = AtomicU64::new(0);
+static REGEX_MISSES: AtomicU64 = AtomicU64::new(0);
+static SPLIT_HITS: AtomicU64 = AtomicU64::new(0);
+static SPLIT_MISSES: AtomicU64 = AtomicU64::new(0);
+
+/// Records a regex cache lookup.
+pub(crate) fn record_regex_lookup(hit: bool) {
+    let counter = if hit { &REGEX_HITS } else { &REGEX_MISSES };
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Records a split cache lookup.
+pub(crate) fn record_split_lookup(hit: bool) {
+    let counter = if hit { &SPLIT_HITS } else { &SPLIT_MISSES };
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Snapshot of the global regex and split caches.
+///
+/// Counters cover the whole process since it started, across all templates
+/// and threads. Regexes compiled under [`Limits`](crate::Limits) are counted
+/// with the other regexes. Caches of templates using
+/// [`CacheScope::Thread`] or [`CacheScope::Context`] are not included.
+///
+/// # Examples
+///
+/// ```rust
+/// use string_pipeline::{Template, cache_stats};
+///
+/// let before = cache_stats();
+/// let template = Template::parse("{split:,:..|filter:^a|join:-}").unwrap();
+/// template.format("ab,b,ac").unwrap();
+///
+/// let after = cache_stats();
+/// assert!(after.regex_hits + after.regex_misses > before.regex_hits + before.regex_misses);
+/// assert!(after.split_entries >= 1);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CacheStats {
+    /// Number of compiled regexes in the cache.
+    pub regex_entries: usize,
+    /// Number of split results in the cache.
+    pub split_entries: usize,
+    /// Regex lookups answered from the cache.
+    pub regex_hits: u64,
+    /// Regex lookups that compiled the pattern.
+    pub regex_misses: u64,
+    /// Split lookups answered from the cache.
+    pub split_hits: u64,
+    /// Split lookups that split the input.
+    ///
+    /// Inputs too large to cache are counted here on every lookup.
+    pub split_misses: u64,
+    /// Approximate size of the cached text, in bytes.
+    ///
+    /// Counts regex patterns, separators and split parts, but not compiled
+    /// regex programs or the bookkeeping of the caches themselves.
+    pub bytes: usize,
+}
+
+/// Returns a snapshot of the global regex and split caches.
+///
+/// See [`CacheStats`] for an example.
+pub fn cache_stats() -> CacheStats {
+    let regex_bytes: usize = REGEX_CACHE
+        .iter()
+        .map(|entry| entry.key().len())
+        .sum::<usize>()
+        + LIMITED_REGEX_CACHE
+            .iter()
+            .map(|entry| entry.key().0.len())
+            .sum::<usize>();
+    let split_bytes: usize = SPLIT_CACHE
+        .iter()
+        .map(|entry| {
+            entry.key().1.len() + entry.value().iter().map(|part| part.len()).sum::<usize>()
+        })
+        .sum();
+
+    CacheStats {
+        regex_entries: REGEX_CACHE.len() + LIMITED_REGEX_CACHE.len(),
+        split_entries: SPLIT_CACHE.len(),
+        regex_hits: REGEX_HITS.load(Ordering::Relaxed),
+        regex_misses: REGEX_MISSES.load(Ordering::Relaxed),
+        split_hits: SPLIT_HITS.load(Ordering::Relaxed),
+        split_misses: SPLIT_MISSES.load(Ordering::Relaxed),
+        bytes: regex_bytes + split_bytes,
+    }
+}
+
+impl fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "regex cache: {} entries, {} hits, {} misses",
+            self.regex_entries, self.regex_hits, self.regex_misses
+        )?;
+        writeln!(
+            f,
+            "split cache: {} entries, {} hits, {} misses",
+            self.split_entries, self.split_hits, self.split_misses
+        )?;
+        write!(f, "cached text: {} bytes", self.bytes)
+    }
+}
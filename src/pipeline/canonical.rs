@@ -0,0 +1,653 @@
+//! Canonical template syntax for parsed operations.
+//!
+//! Templates that differ only in spelling, such as `{1}` and `{split: :1}`,
+//! or `{trim:both}` and `{trim}`, parse to the same operations. This module
+//! writes operations back as template syntax in one normalized form: every
+//! operation under its own keyword, default arguments left out and argument
+//! values escaped so that parsing the output yields the same operations
+//! again. See [`Template::canonical_string`](super::Template::canonical_string).
+//!
+//! Templates built with [`TemplateBuilder`](super::TemplateBuilder) use the
+//! same writer for their template string, along with the spans it records.
+
+use super::parser::OpSpan;
+use super::template::{InputRef, Placeholder, Variable};
+use super::{
+    CaseLocale, ColumnAlign, HighlightStyle, LengthUnit, ListSource, NormalForm, PadDirection,
+    PathStyle, RangeSpec, SedRule, SortDirection, StringOp, TermColor, TrimDirection,
+};
+
+/// Writes a template section with its input reference, variable or
+/// placeholder, and operations.
+///
+/// Returns the spans of the operations in `out`.
+pub(crate) fn write_section(
+    out: &mut String,
+    ops: &[StringOp],
+    input: Option<&InputRef>,
+    variable: Option<&Variable>,
+    placeholder: Option<Placeholder>,
+) -> Vec<OpSpan> {
+    out.push('{');
+    if let Some(input) = input {
+        match input {
+            InputRef::Index(index) => out.push_str(&format!("input={index}")),
+            InputRef::Name(name) => out.push_str(&format!("input={name}")),
+        }
+        if !ops.is_empty() || variable.is_some() {
+            out.push('|');
+        }
+    }
+    match variable {
+        Some(Variable::Set(name)) => out.push_str(&format!("set:{name}:")),
+        Some(Variable::Get(name)) => {
+            out.push_str(&format!("get:{name}"));
+            if !ops.is_empty() {
+                out.push('|');
+            }
+        }
+        None => {}
+    }
+    if let Some(placeholder) = placeholder {
+        out.push_str(placeholder.name());
+        if !ops.is_empty() {
+            out.push('|');
+        }
+    }
+    let spans = match ops.split_last() {
+        // `each` takes the whole section, with the other operations as its source
+        Some((StringOp::Each { body }, source)) => {
+            let start = out.len();
+            out.push_str("each:{");
+            let mut spans = write_ops(out, source);
+            out.push_str("}:");
+            out.push_str(&body.canonical_string());
+            spans.push(OpSpan {
+                span: start..out.len(),
+                inner: Vec::new(),
+            });
+            spans
+        }
+        _ => write_ops(out, ops),
+    };
+    out.push('}');
+    spans
+}
+
+/// Formats a range specification as written in templates.
+pub(crate) fn format_range(range: &RangeSpec) -> String {
+    match range {
+        RangeSpec::Index(i) => i.to_string(),
+        RangeSpec::Range(s, e, inc) => match (s, e) {
+            (None, None) => "..".into(),
+            (Some(s), None) => format!("{s}.."),
+            (None, Some(e)) => {
+                if *inc {
+                    format!("..={e}")
+                } else {
+                    format!("..{e}")
+                }
+            }
+            (Some(s), Some(e)) => {
+                let dots = if *inc { "..=" } else { ".." };
+                format!("{s}{dots}{e}")
+            }
+        },
+        RangeSpec::Stepped(s, e, inc, step) => {
+            format!("{};{step}", format_range(&RangeSpec::Range(*s, *e, *inc)))
+        }
+        RangeSpec::Multi(ranges) => ranges
+            .iter()
+            .map(format_range)
+            .collect::<Vec<_>>()
+            .join(","),
+    }
+}
+
+fn write_ops(out: &mut String, ops: &[StringOp]) -> Vec<OpSpan> {
+    let mut spans = Vec::with_capacity(ops.len());
+    for (i, op) in ops.iter().enumerate() {
+        if i > 0 {
+            out.push('|');
+        }
+        let start = out.len();
+        let inner = write_op(out, op);
+        spans.push(OpSpan {
+            span: start..out.len(),
+            inner,
+        });
+    }
+    spans
+}
+
+/// Writes one operation, returning the spans of its sub-pipeline.
+fn write_op(out: &mut String, op: &StringOp) -> Vec<OpSpan> {
+    let mut inner = Vec::new();
+    out.push_str(match op {
+        StringOp::Pad {
+            direction: PadDirection::Both,
+            ..
+        } => "center",
+        op => op.keyword(),
+    });
+    match op {
+        StringOp::Split {
+            sep,
+            range,
+            max_splits,
+            keep_sep,
+            skip_empty,
+        } => {
+            arg(out, sep);
+            out.push(':');
+            out.push_str(&format_range(range));
+            if let Some(max) = max_splits {
+                out.push_str(&format!(":{max}"));
+            }
+            if *keep_sep {
+                out.push_str(":keep_sep");
+            }
+            if *skip_empty {
+                out.push_str(":skip_empty");
+            }
+        }
+        StringOp::SplitAny { chars, range } => {
+            arg(out, chars);
+            out.push(':');
+            out.push_str(&format_range(range));
+        }
+        StringOp::Partition { sep } | StringOp::RPartition { sep } => arg(out, sep),
+        StringOp::Join { sep, last_sep } => {
+            arg(out, sep);
+            if let Some(last) = last_sep {
+                arg(out, last);
+            }
+        }
+        StringOp::OutputSep { sep } => arg(out, sep),
+        // Only expressible as a whole section, see `write_section`
+        StringOp::Each { body } => {
+            out.push_str(":{}:");
+            out.push_str(&body.canonical_string());
+        }
+        StringOp::Replace { rules } => {
+            for (i, rule) in rules.iter().enumerate() {
+                out.push(if i == 0 { ':' } else { ';' });
+                write_sed_rule(out, rule);
+            }
+        }
+        StringOp::ReplaceLiteral { from, to, count } => {
+            arg(out, from);
+            arg(out, to);
+            if let Some(n) = count {
+                out.push_str(&format!(":{n}"));
+            }
+        }
+        StringOp::Upper { locale } | StringOp::Lower { locale } | StringOp::SwapCase { locale } => {
+            if *locale == CaseLocale::Turkic {
+                out.push_str(":tr");
+            }
+        }
+        StringOp::Capitalize { lower_rest, locale } => {
+            if *lower_rest {
+                out.push_str(":lower");
+            }
+            if *locale == CaseLocale::Turkic {
+                out.push_str(":tr");
+            }
+        }
+        StringOp::Trim { chars, direction } => {
+            if !chars.is_empty() {
+                out.push(':');
+                push_raw_or_quoted(out, chars);
+            }
+            // A lone direction word would be read as the direction
+            let is_direction = matches!(chars.as_str(), "left" | "right" | "both");
+            if *direction != TrimDirection::Both || is_direction {
+                push_direction(out, *direction);
+            }
+        }
+        StringOp::Squeeze { chars } => {
+            if !chars.is_empty() {
+                arg(out, chars);
+            }
+        }
+        StringOp::TrimPrefix { prefix: value }
+        | StringOp::TrimSuffix { suffix: value }
+        | StringOp::Append { suffix: value }
+        | StringOp::Prepend { prefix: value }
+        | StringOp::FilterPrefix { prefix: value }
+        | StringOp::FilterSuffix { suffix: value }
+        | StringOp::FilterContains { needle: value }
+        | StringOp::Exec { command: value } => arg(out, value),
+        StringOp::TrimRegex { pattern, direction } => {
+            out.push(':');
+            out.push_str(pattern);
+            if *direction != TrimDirection::Both {
+                push_direction(out, *direction);
+            }
+        }
+        StringOp::Substring { range } | StringOp::Slice { range } | StringOp::Clamp { range } => {
+            out.push(':');
+            out.push_str(&format_range(range));
+        }
+        StringOp::Surround { prefix, suffix } => {
+            arg(out, prefix);
+            if suffix != prefix {
+                arg(out, suffix);
+            }
+        }
+        StringOp::Slugify { separator } => {
+            if separator != "-" {
+                arg(out, separator);
+            }
+        }
+        StringOp::Normalize { form } => out.push_str(match form {
+            NormalForm::Nfc => ":nfc",
+            NormalForm::Nfd => ":nfd",
+            NormalForm::Nfkc => ":nfkc",
+            NormalForm::Nfkd => ":nfkd",
+        }),
+        StringOp::Ordinal { parse } | StringOp::ToWords { parse } | StringOp::Roman { parse } => {
+            if *parse {
+                out.push_str(":parse");
+            }
+        }
+        StringOp::Basename { style }
+        | StringOp::Dirname { style }
+        | StringOp::Extname { style }
+        | StringOp::StripExt { style }
+        | StringOp::NormalizePath { style } => write_path_style(out, *style),
+        StringOp::WithExt { ext, style } => {
+            arg(out, ext);
+            write_path_style(out, *style);
+        }
+        StringOp::UrlQuery { key } => arg(out, key),
+        StringOp::Highlight { pattern, style } => {
+            out.push(':');
+            out.push_str(pattern);
+            match style {
+                HighlightStyle::Color(TermColor::Red) => {}
+                HighlightStyle::Color(color) => {
+                    out.push(':');
+                    out.push_str(&color.to_string());
+                }
+                HighlightStyle::Marker { open, close } => {
+                    out.push_str(":mark");
+                    arg(out, open);
+                    if close != open {
+                        arg(out, close);
+                    }
+                }
+            }
+        }
+        StringOp::Color { color } | StringOp::Background { color } => {
+            out.push(':');
+            out.push_str(&color.to_string());
+        }
+        StringOp::FilterChecksum {
+            algorithm,
+            expected,
+        } => {
+            out.push(':');
+            out.push_str(algorithm.name());
+            out.push_str(&format!(":{expected:08x}"));
+        }
+        StringOp::Filter { pattern } | StringOp::FilterNot { pattern } => {
+            out.push(':');
+            out.push_str(pattern);
+        }
+        StringOp::ExtractBetween { open, close, nth } => {
+            arg(out, open);
+            arg(out, close);
+            if *nth != 0 {
+                out.push_str(&format!(":{nth}"));
+            }
+        }
+        StringOp::RegexExtract { pattern, group } => {
+            out.push(':');
+            out.push_str(pattern);
+            if let Some(group) = group {
+                out.push_str(&format!(":{group}"));
+            }
+        }
+        StringOp::FilterBy {
+            operations,
+            pattern,
+        } => {
+            inner = push_sub_pipeline(out, operations);
+            if let Some(pattern) = pattern {
+                out.push(':');
+                out.push_str(pattern);
+            }
+        }
+        StringOp::Map { operations }
+        | StringOp::MapFallible { operations }
+        | StringOp::UniqueBy { operations } => {
+            inner = push_sub_pipeline(out, operations);
+        }
+        StringOp::Try {
+            operations,
+            fallback,
+        } => {
+            inner = push_sub_pipeline(out, operations);
+            if let Some(fallback) = fallback {
+                arg(out, fallback);
+            }
+        }
+        StringOp::Sort { direction } => {
+            if *direction == SortDirection::Desc {
+                out.push_str(":desc");
+            }
+        }
+        StringOp::SortBy {
+            operations,
+            numeric,
+            direction,
+        } => {
+            inner = push_sub_pipeline(out, operations);
+            if *numeric {
+                out.push_str(":num");
+            }
+            if *direction == SortDirection::Desc {
+                out.push_str(":desc");
+            }
+        }
+        StringOp::Intersect { other } | StringOp::Diff { other } | StringOp::Union { other } => {
+            out.push(':');
+            push_list_source(out, other);
+        }
+        StringOp::Shuffle { seed } => {
+            if let Some(seed) = seed {
+                out.push_str(&format!(":{seed}"));
+            }
+        }
+        StringOp::Histogram { top } => {
+            if let Some(top) = top {
+                out.push_str(&format!(":{top}"));
+            }
+        }
+        StringOp::Sample { count, seed } => {
+            out.push_str(&format!(":{count}"));
+            if let Some(seed) = seed {
+                out.push_str(&format!(":{seed}"));
+            }
+        }
+        StringOp::Kv {
+            key,
+            pair_sep,
+            kv_sep,
+        } => {
+            arg(out, key);
+            push_kv_separators(out, pair_sep, kv_sep);
+        }
+        StringOp::KvKeys { pair_sep, kv_sep } | StringOp::KvValues { pair_sep, kv_sep } => {
+            push_kv_separators(out, pair_sep, kv_sep);
+        }
+        StringOp::Table { sep, align, header }
+        | StringOp::MarkdownTable { sep, align, header }
+        | StringOp::HtmlTable { sep, align, header } => {
+            if !sep.is_empty() || !align.is_empty() || *header {
+                arg(out, sep);
+            }
+            if !align.is_empty() {
+                out.push(':');
+                out.extend(align.iter().map(|align| match align {
+                    ColumnAlign::Left => 'l',
+                    ColumnAlign::Right => 'r',
+                    ColumnAlign::Center => 'c',
+                }));
+            }
+            if *header {
+                out.push_str(":header");
+            }
+        }
+        StringOp::Pad {
+            width,
+            fill,
+            direction,
+            truncate,
+        } => {
+            out.push_str(&format!(":{width}"));
+            // A fill must precede the direction, or the direction is read
+            // as the fill
+            if fill != " " || *direction == PadDirection::Left {
+                out.push(':');
+                if fill == "truncate" {
+                    out.push_str("truncat\\e");
+                } else {
+                    push_escaped(out, fill, &[]);
+                }
+            }
+            match direction {
+                PadDirection::Left => out.push_str(":left"),
+                PadDirection::Right | PadDirection::Both => {}
+            }
+            if *truncate {
+                out.push_str(":truncate");
+            }
+        }
+        StringOp::YamlGet { path } | StringOp::TomlGet { path } => {
+            out.push(':');
+            for (i, key) in path.iter().enumerate() {
+                if i > 0 {
+                    out.push('.');
+                }
+                push_escaped(out, key, &['.']);
+            }
+        }
+        StringOp::JsonObject { keys } => {
+            out.push(':');
+            for (i, key) in keys.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                push_escaped(out, key, &[',']);
+            }
+        }
+        StringOp::Len { unit } => out.push_str(match unit {
+            LengthUnit::Bytes => ":bytes",
+            LengthUnit::Chars => ":chars",
+            LengthUnit::Graphemes => "",
+        }),
+        StringOp::StripAnsi
+        | StringOp::Words
+        | StringOp::Sentences
+        | StringOp::ExtractEmails
+        | StringOp::ExtractUrls
+        | StringOp::ExtractUuids
+        | StringOp::UrlScheme
+        | StringOp::UrlHost
+        | StringOp::UrlPath
+        | StringOp::Bold
+        | StringOp::Underline
+        | StringOp::StyleReset
+        | StringOp::JsonArray
+        | StringOp::FilterValidUtf8
+        | StringOp::FilterLuhn
+        | StringOp::Deaccent
+        | StringOp::Ascii
+        | StringOp::ReadFile
+        | StringOp::Reverse
+        | StringOp::Unique
+        | StringOp::Duplicates
+        | StringOp::RemoveEmpty => {}
+    }
+    inner
+}
+
+/// Delimiters tried, in order, when a sed string cannot use `/`.
+const SED_DELIMITERS: [char; 7] = ['|', '#', '!', '@', '%', ',', '~'];
+
+/// Writes one rule of a `replace` as a sed string.
+fn write_sed_rule(out: &mut String, rule: &SedRule) {
+    let SedRule {
+        pattern,
+        replacement,
+        flags,
+        occurrence,
+    } = rule;
+    // A `\/` kept in the replacement only reads back with another delimiter
+    let delimiter = if replacement.contains("\\/") {
+        SED_DELIMITERS
+            .into_iter()
+            .find(|&d| !pattern.contains(d) && !replacement.contains(d))
+            .unwrap_or('/')
+    } else {
+        '/'
+    };
+    out.push('s');
+    out.push(delimiter);
+    push_sed_part(out, pattern, delimiter, flags.contains('l'));
+    out.push(delimiter);
+    push_sed_part(out, replacement, delimiter, false);
+    out.push(delimiter);
+    out.push_str(flags);
+    if let Some(n) = occurrence {
+        out.push_str(&n.to_string());
+    }
+}
+
+/// Writes the pattern or replacement of a sed string, escaping `delimiter`.
+///
+/// Escapes are kept as they are, except in a literal pattern, where a
+/// backslash is plain text and is escaped too.
+fn push_sed_part(out: &mut String, text: &str, delimiter: char, literal: bool) {
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' && !literal {
+            out.push(c);
+            out.extend(chars.next());
+        } else {
+            if c == delimiter || c == '\\' {
+                out.push('\\');
+            }
+            out.push(c);
+        }
+    }
+}
+
+/// Writes `:` and an escaped argument value.
+fn arg(out: &mut String, value: &str) {
+    out.push(':');
+    push_escaped(out, value, &[]);
+}
+
+/// Writes the option of a path operation, which is left out for POSIX paths.
+fn write_path_style(out: &mut String, style: PathStyle) {
+    if style == PathStyle::Windows {
+        out.push_str(":windows");
+    }
+}
+
+/// Escapes the characters that end or change the meaning of an argument.
+fn push_escaped(out: &mut String, value: &str, extra: &[char]) {
+    for (i, c) in value.chars().enumerate() {
+        match c {
+            '\\' | ':' | '|' | '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            // A leading quote would start a quoted argument
+            '\'' | '"' if i == 0 => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if extra.contains(&c) => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+}
+
+/// Writes trim characters, which are kept as written unless they were quoted.
+fn push_raw_or_quoted(out: &mut String, chars: &str) {
+    if is_plain_arg(chars) {
+        out.push_str(chars);
+    } else if !chars.contains('\'') {
+        out.push('\'');
+        out.push_str(chars);
+        out.push('\'');
+    } else {
+        out.push('"');
+        for c in chars.chars() {
+            match c {
+                '\\' | '"' => {
+                    out.push('\\');
+                    out.push(c);
+                }
+                '\n' => out.push_str("\\n"),
+                '\t' => out.push_str("\\t"),
+                '\r' => out.push_str("\\r"),
+                c => out.push(c),
+            }
+        }
+        out.push('"');
+    }
+}
+
+/// Returns `true` if `raw` parses back as itself in an unquoted argument.
+fn is_plain_arg(raw: &str) -> bool {
+    if raw.starts_with(['\'', '"']) {
+        return false;
+    }
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            // Skips the escaped character; a trailing backslash is not plain
+            '\\' if chars.next().is_none() => return false,
+            ':' | '|' | '{' | '}' => return false,
+            _ => {}
+        }
+    }
+    true
+}
+
+fn push_direction(out: &mut String, direction: TrimDirection) {
+    out.push_str(match direction {
+        TrimDirection::Both => ":both",
+        TrimDirection::Left => ":left",
+        TrimDirection::Right => ":right",
+    });
+}
+
+/// Writes the separators of a `kv` operation, leaving out the defaults.
+fn push_kv_separators(out: &mut String, pair_sep: &str, kv_sep: &str) {
+    if kv_sep != "=" {
+        arg(out, pair_sep);
+        arg(out, kv_sep);
+    } else if !pair_sep.is_empty() {
+        arg(out, pair_sep);
+    }
+}
+
+fn push_sub_pipeline(out: &mut String, ops: &[StringOp]) -> Vec<OpSpan> {
+    out.push_str(":{");
+    let spans = write_ops(out, ops);
+    out.push('}');
+    spans
+}
+
+fn push_list_source(out: &mut String, source: &ListSource) {
+    match source {
+        ListSource::File(path) => {
+            out.push('@');
+            push_escaped(out, &path.to_string_lossy(), &[]);
+        }
+        ListSource::Items(items) => {
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                if i == 0 && item.starts_with('@') {
+                    out.push('\\');
+                }
+                push_escaped(out, item, &[',']);
+            }
+        }
+    }
+}
@@ -0,0 +1,1019 @@
+//! Catalog of template operations.
+//!
+//! Every operation keyword is described once here: its syntax, the value
+//! types it accepts and produces, and a one-line summary. The type checker,
+//! the linter, parse error hints and the CLI help all read from this table,
+//! so adding an operation means adding one entry instead of updating each
+//! of them by hand.
+
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+
+use super::debug::json_string;
+
+/// Type of value an operation accepts or produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueKind {
+    /// A single string.
+    String,
+    /// A list of strings.
+    List,
+    /// Either type. As an output, the type matches the input or depends on
+    /// the arguments, as for `split`.
+    Any,
+}
+
+impl ValueKind {
+    /// Name used in help and JSON output.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ValueKind::String => "string",
+            ValueKind::List => "list",
+            ValueKind::Any => "any",
+        }
+    }
+}
+
+/// Description of a template operation.
+///
+/// # Examples
+///
+/// ```rust
+/// use string_pipeline::{ValueKind, operation};
+///
+/// let info = operation("quote").unwrap();
+/// assert_eq!(info.name, "surround");
+/// assert_eq!(info.input, ValueKind::String);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct OperationInfo {
+    /// Keyword that starts the operation, like `regex_extract`.
+    pub name: &'static str,
+    /// Other keywords accepted for the same operation. Aliases added with
+    /// [`register_alias`] are not listed here.
+    pub aliases: &'static [&'static str],
+    /// Usage with argument placeholders, like `regex_extract:PAT[:GRP]`.
+    pub syntax: &'static str,
+    /// Type of value the operation accepts.
+    pub input: ValueKind,
+    /// Type of value the operation produces.
+    pub output: ValueKind,
+    /// Whether repeating the operation right after itself with the same
+    /// arguments has no effect.
+    pub idempotent: bool,
+    /// One-line description.
+    pub summary: &'static str,
+    /// A template using the operation.
+    pub example: &'static str,
+}
+
+impl OperationInfo {
+    const fn new(
+        name: &'static str,
+        syntax: &'static str,
+        input: ValueKind,
+        output: ValueKind,
+        summary: &'static str,
+        example: &'static str,
+    ) -> Self {
+        Self {
+            name,
+            aliases: &[],
+            syntax,
+            input,
+            output,
+            idempotent: false,
+            summary,
+            example,
+        }
+    }
+
+    const fn aliases(mut self, aliases: &'static [&'static str]) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    const fn idempotent(mut self) -> Self {
+        self.idempotent = true;
+        self
+    }
+
+    /// Encodes the description as a single-line JSON object.
+    fn to_json(self) -> String {
+        let aliases: Vec<String> = self.aliases.iter().map(|a| json_string(a)).collect();
+        format!(
+            "{{\"name\":{},\"aliases\":[{}],\"syntax\":{},\"input\":\"{}\",\"output\":\"{}\",\"idempotent\":{},\"summary\":{},\"example\":{}}}",
+            json_string(self.name),
+            aliases.join(","),
+            json_string(self.syntax),
+            self.input.as_str(),
+            self.output.as_str(),
+            self.idempotent,
+            json_string(self.summary),
+            json_string(self.example),
+        )
+    }
+}
+
+static OPERATIONS: &[OperationInfo] = &[
+    OperationInfo::new(
+        "split",
+        "split:SEP:RANGE[:MAX][:keep_sep][:skip_empty]",
+        ValueKind::Any,
+        ValueKind::Any,
+        "Split text into parts (at most MAX splits)",
+        "{split:,:..}",
+    )
+    .aliases(&["s"]),
+    OperationInfo::new(
+        "split_any",
+        "split_any:CHARS[:RANGE]",
+        ValueKind::Any,
+        ValueKind::Any,
+        "Split text on each of the characters in CHARS",
+        "{split_any:,;:..}",
+    ),
+    OperationInfo::new(
+        "partition",
+        "partition:SEP",
+        ValueKind::String,
+        ValueKind::List,
+        "Split at first SEP into [before, SEP, after]",
+        "{partition:=}",
+    ),
+    OperationInfo::new(
+        "rpartition",
+        "rpartition:SEP",
+        ValueKind::String,
+        ValueKind::List,
+        "Split at last SEP into [before, SEP, after]",
+        "{rpartition:.}",
+    ),
+    OperationInfo::new(
+        "slice",
+        "slice:RANGE",
+        ValueKind::List,
+        ValueKind::List,
+        "Extract range of items",
+        "{split:,:..|slice:1..}",
+    ),
+    OperationInfo::new(
+        "clamp",
+        "clamp:RANGE",
+        ValueKind::List,
+        ValueKind::List,
+        "Extract range of items, clamped to the list even with strict ranges",
+        "{split:,:..|clamp:1..10}",
+    ),
+    OperationInfo::new(
+        "join",
+        "join:SEP[:LAST]",
+        ValueKind::Any,
+        ValueKind::String,
+        "Combine items with separator (LAST before final item)",
+        "{split:,:..|join:-}",
+    )
+    .aliases(&["j"]),
+    OperationInfo::new(
+        "output_sep",
+        "output_sep:SEP",
+        ValueKind::Any,
+        ValueKind::Any,
+        "Separator for rendering a final list result",
+        "{split:,:..|output_sep:;}",
+    ),
+    OperationInfo::new(
+        "substring",
+        "substring:RANGE",
+        ValueKind::String,
+        ValueKind::String,
+        "Extract characters from string",
+        "{substring:..3}",
+    ),
+    OperationInfo::new(
+        "trim",
+        "trim[:CHARS][:DIR]",
+        ValueKind::String,
+        ValueKind::String,
+        "Remove characters from ends",
+        "{trim}",
+    ),
+    OperationInfo::new(
+        "squeeze",
+        "squeeze[:CHARS]",
+        ValueKind::String,
+        ValueKind::String,
+        "Collapse whitespace (or CHARS) runs",
+        "{squeeze}",
+    ),
+    OperationInfo::new(
+        "trim_prefix",
+        "trim_prefix:TEXT",
+        ValueKind::String,
+        ValueKind::String,
+        "Remove prefix once",
+        "{trim_prefix:v}",
+    ),
+    OperationInfo::new(
+        "trim_suffix",
+        "trim_suffix:TEXT",
+        ValueKind::String,
+        ValueKind::String,
+        "Remove suffix once",
+        "{trim_suffix:.txt}",
+    ),
+    OperationInfo::new(
+        "trim_regex",
+        "trim_regex:PAT[:DIR]",
+        ValueKind::String,
+        ValueKind::String,
+        "Remove regex match from ends",
+        "{trim_regex:[0-9]+}",
+    ),
+    OperationInfo::new(
+        "pad",
+        "pad:WIDTH[:FILL][:DIR][:truncate]",
+        ValueKind::String,
+        ValueKind::String,
+        "Add padding to reach width (truncate cuts longer text)",
+        "{pad:8:0:left}",
+    ),
+    OperationInfo::new(
+        "center",
+        "center:WIDTH[:FILL]",
+        ValueKind::String,
+        ValueKind::String,
+        "Pad both sides to reach width",
+        "{center:10:*}",
+    ),
+    OperationInfo::new(
+        "upper",
+        "upper[:LOCALE]",
+        ValueKind::String,
+        ValueKind::String,
+        "Convert to uppercase (LOCALE: tr, az)",
+        "{upper}",
+    )
+    .aliases(&["uppercase"])
+    .idempotent(),
+    OperationInfo::new(
+        "lower",
+        "lower[:LOCALE]",
+        ValueKind::String,
+        ValueKind::String,
+        "Convert to lowercase (LOCALE: tr, az)",
+        "{lower}",
+    )
+    .aliases(&["lowercase"])
+    .idempotent(),
+    OperationInfo::new(
+        "capitalize",
+        "capitalize[:lower][:LOCALE]",
+        ValueKind::String,
+        ValueKind::String,
+        "Titlecase the first character (lower: lowercase the rest)",
+        "{capitalize:lower}",
+    )
+    .idempotent(),
+    OperationInfo::new(
+        "swapcase",
+        "swapcase[:LOCALE]",
+        ValueKind::String,
+        ValueKind::String,
+        "Swap uppercase and lowercase letters",
+        "{swapcase}",
+    ),
+    OperationInfo::new(
+        "append",
+        "append:TEXT",
+        ValueKind::String,
+        ValueKind::String,
+        "Add text to end",
+        "{append:!}",
+    ),
+    OperationInfo::new(
+        "prepend",
+        "prepend:TEXT",
+        ValueKind::String,
+        ValueKind::String,
+        "Add text to beginning",
+        "{prepend:> }",
+    ),
+    OperationInfo::new(
+        "surround",
+        "surround:CHARS[:SUFFIX]",
+        ValueKind::String,
+        ValueKind::String,
+        "Add characters to both ends",
+        "{surround:\"}",
+    )
+    .aliases(&["quote"]),
+    OperationInfo::new(
+        "replace",
+        "replace:s/PAT/REP/FLAGS[;...]",
+        ValueKind::String,
+        ValueKind::String,
+        "Find and replace with regex (N flag: Nth match)",
+        "{replace:s/a/b/g}",
+    ),
+    OperationInfo::new(
+        "replace_literal",
+        "replace_literal:FROM:TO[:COUNT]",
+        ValueKind::String,
+        ValueKind::String,
+        "Find and replace plain text",
+        "{replace_literal:.:/}",
+    ),
+    OperationInfo::new(
+        "regex_extract",
+        "regex_extract:PAT[:GRP]",
+        ValueKind::String,
+        ValueKind::String,
+        "Extract with regex pattern",
+        "{regex_extract:[0-9]+}",
+    ),
+    OperationInfo::new(
+        "kv",
+        "kv:KEY[:PSEP[:KSEP]]",
+        ValueKind::String,
+        ValueKind::String,
+        "Value of KEY in key=value pairs",
+        "{kv:user}",
+    ),
+    OperationInfo::new(
+        "kv_keys",
+        "kv_keys[:PSEP[:KSEP]]",
+        ValueKind::String,
+        ValueKind::List,
+        "List keys of key=value pairs",
+        "{kv_keys}",
+    ),
+    OperationInfo::new(
+        "kv_values",
+        "kv_values[:PSEP[:KSEP]]",
+        ValueKind::String,
+        ValueKind::List,
+        "List values of key=value pairs",
+        "{kv_values:&}",
+    ),
+    OperationInfo::new(
+        "words",
+        "words",
+        ValueKind::String,
+        ValueKind::List,
+        "Split into words at Unicode word boundaries",
+        "{words}",
+    ),
+    OperationInfo::new(
+        "sentences",
+        "sentences",
+        ValueKind::String,
+        ValueKind::List,
+        "Split into sentences at Unicode sentence boundaries",
+        "{sentences}",
+    ),
+    OperationInfo::new(
+        "extract_emails",
+        "extract_emails",
+        ValueKind::String,
+        ValueKind::List,
+        "List of the email addresses in the text",
+        "{extract_emails}",
+    ),
+    OperationInfo::new(
+        "extract_urls",
+        "extract_urls",
+        ValueKind::String,
+        ValueKind::List,
+        "List of the http, https, ftp and www. URLs in the text",
+        "{extract_urls}",
+    ),
+    OperationInfo::new(
+        "extract_uuids",
+        "extract_uuids",
+        ValueKind::String,
+        ValueKind::List,
+        "List of the UUIDs in the text",
+        "{extract_uuids}",
+    ),
+    OperationInfo::new(
+        "extract_between",
+        "extract_between:OPEN:CLOSE[:NTH]",
+        ValueKind::String,
+        ValueKind::String,
+        "Text inside the NTH group of balanced OPEN and CLOSE delimiters",
+        "{extract_between:(:)}",
+    ),
+    OperationInfo::new(
+        "sort",
+        "sort[:DIR]",
+        ValueKind::List,
+        ValueKind::List,
+        "Sort items alphabetically",
+        "{split:,:..|sort}",
+    ),
+    OperationInfo::new(
+        "sort_by",
+        "sort_by:{OPS}[:num][:DIR]",
+        ValueKind::List,
+        ValueKind::List,
+        "Sort items by a key computed with OPS",
+        "{split:,:..|sort_by:{len}:num}",
+    ),
+    OperationInfo::new(
+        "reverse",
+        "reverse",
+        ValueKind::Any,
+        ValueKind::Any,
+        "Reverse order or characters",
+        "{reverse}",
+    ),
+    OperationInfo::new(
+        "unique",
+        "unique",
+        ValueKind::List,
+        ValueKind::List,
+        "Remove duplicates",
+        "{split:,:..|unique}",
+    )
+    .idempotent(),
+    OperationInfo::new(
+        "unique_by",
+        "unique_by:{OPS}",
+        ValueKind::List,
+        ValueKind::List,
+        "Remove items with an already seen key from OPS",
+        "{split:,:..|unique_by:{lower}}",
+    ),
+    OperationInfo::new(
+        "duplicates",
+        "duplicates",
+        ValueKind::List,
+        ValueKind::List,
+        "Keep items that appear more than once",
+        "{split:,:..|duplicates}",
+    ),
+    OperationInfo::new(
+        "histogram",
+        "histogram[:TOP]",
+        ValueKind::List,
+        ValueKind::List,
+        "Count items into item<TAB>count lines, most frequent first",
+        "{split:\\n:..|histogram:10}",
+    )
+    .aliases(&["count_by"]),
+    OperationInfo::new(
+        "remove_empty",
+        "remove_empty",
+        ValueKind::List,
+        ValueKind::List,
+        "Remove blank items",
+        "{split:,:..|remove_empty}",
+    )
+    .idempotent(),
+    OperationInfo::new(
+        "intersect",
+        "intersect:ITEMS|@FILE",
+        ValueKind::List,
+        ValueKind::List,
+        "Keep items also in the second list",
+        "{split:,:..|intersect:a,b}",
+    ),
+    OperationInfo::new(
+        "diff",
+        "diff:ITEMS|@FILE",
+        ValueKind::List,
+        ValueKind::List,
+        "Keep items not in the second list",
+        "{split:,:..|diff:a,b}",
+    ),
+    OperationInfo::new(
+        "union",
+        "union:ITEMS|@FILE",
+        ValueKind::List,
+        ValueKind::List,
+        "Add missing items of the second list",
+        "{split:,:..|union:a,b}",
+    ),
+    OperationInfo::new(
+        "shuffle",
+        "shuffle[:SEED]",
+        ValueKind::List,
+        ValueKind::List,
+        "Put items in random order",
+        "{split:,:..|shuffle:7}",
+    ),
+    OperationInfo::new(
+        "sample",
+        "sample:N[:SEED]",
+        ValueKind::List,
+        ValueKind::List,
+        "Pick N random items",
+        "{split:,:..|sample:2:7}",
+    ),
+    OperationInfo::new(
+        "table",
+        "table[:SEP[:ALIGN][:header]]",
+        ValueKind::List,
+        ValueKind::List,
+        "Align rows into columns (ALIGN: l, r, c per column)",
+        "{split:\\n:..|table:,:lr:header}",
+    ),
+    OperationInfo::new(
+        "md_table",
+        "md_table[:SEP[:ALIGN][:header]]",
+        ValueKind::List,
+        ValueKind::List,
+        "Write rows as a Markdown table",
+        "{split:\\n:..|md_table:,:lr:header}",
+    ),
+    OperationInfo::new(
+        "html_table",
+        "html_table[:SEP[:ALIGN][:header]]",
+        ValueKind::List,
+        ValueKind::List,
+        "Write rows as an HTML table",
+        "{split:\\n:..|html_table:,:header}",
+    ),
+    OperationInfo::new(
+        "filter",
+        "filter:PATTERN",
+        ValueKind::Any,
+        ValueKind::Any,
+        "Keep items matching pattern",
+        "{split:,:..|filter:^a}",
+    ),
+    OperationInfo::new(
+        "filter_not",
+        "filter_not:PATTERN",
+        ValueKind::Any,
+        ValueKind::Any,
+        "Remove items matching pattern",
+        "{split:,:..|filter_not:^a}",
+    ),
+    OperationInfo::new(
+        "filter_prefix",
+        "filter_prefix:TEXT",
+        ValueKind::Any,
+        ValueKind::Any,
+        "Keep items starting with TEXT",
+        "{split:,:..|filter_prefix:ERR}",
+    ),
+    OperationInfo::new(
+        "filter_suffix",
+        "filter_suffix:TEXT",
+        ValueKind::Any,
+        ValueKind::Any,
+        "Keep items ending with TEXT",
+        "{split:,:..|filter_suffix:.rs}",
+    ),
+    OperationInfo::new(
+        "filter_contains",
+        "filter_contains:TEXT",
+        ValueKind::Any,
+        ValueKind::Any,
+        "Keep items containing TEXT",
+        "{split:,:..|filter_contains:(beta)}",
+    ),
+    OperationInfo::new(
+        "filter_valid_utf8",
+        "filter_valid_utf8",
+        ValueKind::Any,
+        ValueKind::Any,
+        "Drop items that had invalid UTF-8 (read with --lossy)",
+        "{split:,:..|filter_valid_utf8}",
+    ),
+    OperationInfo::new(
+        "filter_luhn",
+        "filter_luhn",
+        ValueKind::Any,
+        ValueKind::Any,
+        "Keep numbers that pass the Luhn check",
+        "{split:,:..|filter_luhn}",
+    ),
+    OperationInfo::new(
+        "filter_checksum",
+        "filter_checksum:ALGO:HEX",
+        ValueKind::Any,
+        ValueKind::Any,
+        "Keep items whose crc32 or adler32 checksum is HEX",
+        "{split:,:..|filter_checksum:crc32:cbf43926}",
+    ),
+    OperationInfo::new(
+        "filter_by",
+        "filter_by:{OPS}[:PAT]",
+        ValueKind::List,
+        ValueKind::List,
+        "Keep items whose OPS result matches (or is non-empty)",
+        "{split:,:..|filter_by:{len}:^3$}",
+    ),
+    OperationInfo::new(
+        "map_fallible",
+        "map_fallible:{OPS}",
+        ValueKind::List,
+        ValueKind::List,
+        "Apply OPS to each item, dropping items it fails on",
+        "{split:,:..|map_fallible:{roman}}",
+    ),
+    OperationInfo::new(
+        "try",
+        "try:{OPS}[:FALLBACK]",
+        ValueKind::String,
+        ValueKind::String,
+        "Apply OPS, keeping the input or FALLBACK on error",
+        "{try:{ordinal}:?}",
+    ),
+    OperationInfo::new(
+        "strip_ansi",
+        "strip_ansi",
+        ValueKind::String,
+        ValueKind::String,
+        "Remove ANSI color codes",
+        "{strip_ansi}",
+    )
+    .aliases(&["rm_ansi"])
+    .idempotent(),
+    OperationInfo::new(
+        "deaccent",
+        "deaccent",
+        ValueKind::String,
+        ValueKind::String,
+        "Remove accents (é -> e)",
+        "{deaccent}",
+    )
+    .idempotent(),
+    OperationInfo::new(
+        "ascii",
+        "ascii",
+        ValueKind::String,
+        ValueKind::String,
+        "Transliterate to ASCII (ß -> ss)",
+        "{ascii}",
+    )
+    .idempotent(),
+    OperationInfo::new(
+        "slugify",
+        "slugify[:SEP]",
+        ValueKind::String,
+        ValueKind::String,
+        "Make a lowercase URL slug (default SEP: -)",
+        "{slugify}",
+    ),
+    OperationInfo::new(
+        "normalize",
+        "normalize:FORM",
+        ValueKind::String,
+        ValueKind::String,
+        "Unicode normalization (nfc, nfd, nfkc, nfkd)",
+        "{normalize:nfc}",
+    ),
+    OperationInfo::new(
+        "ordinal",
+        "ordinal[:parse]",
+        ValueKind::String,
+        ValueKind::String,
+        "Integer to ordinal, 1 -> 1st (parse: back to integer)",
+        "{ordinal}",
+    ),
+    OperationInfo::new(
+        "to_words",
+        "to_words[:parse]",
+        ValueKind::String,
+        ValueKind::String,
+        "Integer to English words, 42 -> forty-two (parse: back)",
+        "{to_words}",
+    ),
+    OperationInfo::new(
+        "roman",
+        "roman[:parse]",
+        ValueKind::String,
+        ValueKind::String,
+        "Integer to Roman numeral, 14 -> XIV (parse: back)",
+        "{roman}",
+    ),
+    OperationInfo::new(
+        "basename",
+        "basename[:windows]",
+        ValueKind::String,
+        ValueKind::String,
+        "Last component of a path",
+        "{basename}",
+    )
+    .idempotent(),
+    OperationInfo::new(
+        "dirname",
+        "dirname[:windows]",
+        ValueKind::String,
+        ValueKind::String,
+        "Path without its last component",
+        "{dirname}",
+    ),
+    OperationInfo::new(
+        "extname",
+        "extname[:windows]",
+        ValueKind::String,
+        ValueKind::String,
+        "Extension of a path, with its dot",
+        "{extname}",
+    ),
+    OperationInfo::new(
+        "strip_ext",
+        "strip_ext[:windows]",
+        ValueKind::String,
+        ValueKind::String,
+        "Remove the extension of a path",
+        "{strip_ext}",
+    ),
+    OperationInfo::new(
+        "with_ext",
+        "with_ext:EXT[:windows]",
+        ValueKind::String,
+        ValueKind::String,
+        "Replace the extension of a path",
+        "{with_ext:md}",
+    ),
+    OperationInfo::new(
+        "normalize_path",
+        "normalize_path[:windows]",
+        ValueKind::String,
+        ValueKind::String,
+        "Collapse ., .. and duplicate separators in a path",
+        "{normalize_path}",
+    )
+    .idempotent(),
+    OperationInfo::new(
+        "url_scheme",
+        "url_scheme",
+        ValueKind::String,
+        ValueKind::String,
+        "Scheme of a URL, in lowercase",
+        "{url_scheme}",
+    ),
+    OperationInfo::new(
+        "url_host",
+        "url_host",
+        ValueKind::String,
+        ValueKind::String,
+        "Host of a URL, in lowercase, without port",
+        "{url_host}",
+    ),
+    OperationInfo::new(
+        "url_path",
+        "url_path",
+        ValueKind::String,
+        ValueKind::String,
+        "Path of a URL, without query or fragment",
+        "{url_path}",
+    ),
+    OperationInfo::new(
+        "url_query",
+        "url_query:KEY",
+        ValueKind::String,
+        ValueKind::String,
+        "Decoded value of a URL query parameter",
+        "{url_query:q}",
+    ),
+    OperationInfo::new(
+        "yaml_get",
+        "yaml_get:PATH",
+        ValueKind::String,
+        ValueKind::String,
+        "Scalar at a dotted path of a YAML document (yaml feature)",
+        "{yaml_get:server.port}",
+    ),
+    OperationInfo::new(
+        "toml_get",
+        "toml_get:PATH",
+        ValueKind::String,
+        ValueKind::String,
+        "Scalar at a dotted path of a TOML document (toml feature)",
+        "{toml_get:package.version}",
+    ),
+    OperationInfo::new(
+        "color",
+        "color:COLOR",
+        ValueKind::String,
+        ValueKind::String,
+        "Set the terminal foreground color (name, 0-255 or #rrggbb)",
+        "{color:red}",
+    ),
+    OperationInfo::new(
+        "bg",
+        "bg:COLOR",
+        ValueKind::String,
+        ValueKind::String,
+        "Set the terminal background color (name, 0-255 or #rrggbb)",
+        "{bg:blue}",
+    ),
+    OperationInfo::new(
+        "bold",
+        "bold",
+        ValueKind::String,
+        ValueKind::String,
+        "Make text bold on a terminal",
+        "{bold}",
+    ),
+    OperationInfo::new(
+        "underline",
+        "underline",
+        ValueKind::String,
+        ValueKind::String,
+        "Underline text on a terminal",
+        "{underline}",
+    ),
+    OperationInfo::new(
+        "style_reset",
+        "style_reset",
+        ValueKind::String,
+        ValueKind::String,
+        "Reset all terminal styles after the value",
+        "{style_reset}",
+    ),
+    OperationInfo::new(
+        "highlight",
+        "highlight:PATTERN[:COLOR|:mark:OPEN[:CLOSE]]",
+        ValueKind::String,
+        ValueKind::String,
+        "Color or mark every regex match",
+        "{highlight:ERROR:red}",
+    ),
+    OperationInfo::new(
+        "len",
+        "len[:UNIT]",
+        ValueKind::Any,
+        ValueKind::String,
+        "Count graphemes, chars, bytes or items",
+        "{len}",
+    )
+    .aliases(&["count"]),
+    OperationInfo::new(
+        "json_array",
+        "json_array",
+        ValueKind::Any,
+        ValueKind::String,
+        "Write items as a JSON array of strings",
+        "{split:,:..|json_array}",
+    ),
+    OperationInfo::new(
+        "json_object",
+        "json_object:KEY,KEY,...",
+        ValueKind::Any,
+        ValueKind::String,
+        "Write items as a JSON object with the given keys",
+        "{split:,:..|json_object:name,size}",
+    ),
+    OperationInfo::new(
+        "read_file",
+        "read_file",
+        ValueKind::String,
+        ValueKind::String,
+        "Replace a path with the file's contents (--allow-fs)",
+        "{read_file}",
+    ),
+    OperationInfo::new(
+        "exec",
+        "exec:COMMAND",
+        ValueKind::String,
+        ValueKind::String,
+        "Pipe the value through a shell command (--allow-exec)",
+        "{exec:sort}",
+    ),
+    OperationInfo::new(
+        "map",
+        "map:{OPS}",
+        ValueKind::List,
+        ValueKind::List,
+        "Apply operations to each item",
+        "{split:,:..|map:{upper}}",
+    ),
+    OperationInfo::new(
+        "each",
+        "each:{OPS}:TEMPLATE",
+        ValueKind::Any,
+        ValueKind::String,
+        "Format a template for each item and join the results",
+        "{each:{split:,:..}:- {upper};}",
+    ),
+];
+
+/// Returns the descriptions of all template operations.
+///
+/// # Examples
+///
+/// ```rust
+/// use string_pipeline::operations;
+///
+/// assert!(operations().iter().any(|op| op.name == "regex_extract"));
+/// ```
+pub fn operations() -> &'static [OperationInfo] {
+    OPERATIONS
+}
+
+/// Aliases added with [`register_alias`], mapped to operation keywords.
+static REGISTERED_ALIASES: Lazy<RwLock<HashMap<String, &'static str>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Looks up an operation by its keyword or one of its aliases, including
+/// aliases added with [`register_alias`].
+pub fn operation(name: &str) -> Option<&'static OperationInfo> {
+    OPERATIONS
+        .iter()
+        .find(|op| op.name == name || op.aliases.contains(&name))
+        .or_else(|| {
+            let keyword = *REGISTERED_ALIASES.read().get(name)?;
+            OPERATIONS.iter().find(|op| op.name == keyword)
+        })
+}
+
+/// Adds an alias for an operation, accepted by every template parsed
+/// afterwards.
+///
+/// Aliases are replaced by the operation keyword while parsing, so
+/// [`Template::canonical_string`](crate::Template::canonical_string) and
+/// error messages use the keyword. An alias written right after a regex
+/// argument, as in `filter:a|up`, is read as part of the pattern; use the
+/// keyword there.
+///
+/// # Returns
+///
+/// * `Ok(())` - The alias was added, or already named the same operation
+/// * `Err(String)` - The alias is not a lowercase word, already names
+///   another operation, or `operation` is not an operation keyword
+///
+/// # Examples
+///
+/// ```rust
+/// use string_pipeline::{Template, register_alias};
+///
+/// register_alias("up", "upper").unwrap();
+/// let template = Template::parse("{split:,:..|map:{up}|j:-}").unwrap();
+/// assert_eq!(template.format("a,b").unwrap(), "A-B");
+/// assert_eq!(template.canonical_string(), "{split:,:..|map:{upper}|join:-}");
+/// ```
+pub fn register_alias(alias: &str, operation: &str) -> Result<(), String> {
+    let valid = alias.starts_with(|c: char| c.is_ascii_lowercase())
+        && alias
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if !valid {
+        return Err(format!(
+            "Invalid alias `{alias}`: use lowercase letters, digits and underscores, starting with a letter"
+        ));
+    }
+    let Some(target) = OPERATIONS.iter().find(|op| op.name == operation) else {
+        return Err(format!("Unknown operation `{operation}`"));
+    };
+
+    let mut registered = REGISTERED_ALIASES.write();
+    let existing = OPERATIONS
+        .iter()
+        .find(|op| op.name == alias || op.aliases.contains(&alias))
+        .map(|op| op.name)
+        .or_else(|| registered.get(alias).copied());
+    match existing {
+        Some(keyword) if keyword == target.name && registered.contains_key(alias) => Ok(()),
+        Some(keyword) => Err(format!("`{alias}` already names the `{keyword}` operation")),
+        None => {
+            registered.insert(alias.to_string(), target.name);
+            Ok(())
+        }
+    }
+}
+
+/// Encodes [`operations`] as a JSON array with one object per line.
+pub fn operations_json() -> String {
+    let entries: Vec<String> = OPERATIONS.iter().map(|op| op.to_json()).collect();
+    format!("[\n{}\n]", entries.join(",\n"))
+}
+
+/// Returns the operation keyword closest to `word`, for "did you mean" hints.
+///
+/// Only keywords within a small edit distance are suggested.
+pub(crate) fn suggest(word: &str) -> Option<&'static str> {
+    OPERATIONS
+        .iter()
+        .flat_map(|op| std::iter::once(&op.name).chain(op.aliases))
+        .map(|name| (edit_distance(word, name), *name))
+        .filter(|&(distance, _)| distance <= 2 && distance < word.len())
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, name)| name)
+}
+
+/// Levenshtein distance between two strings, counted in chars.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
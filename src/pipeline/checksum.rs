@@ -0,0 +1,134 @@
+//! Validity checks for the `filter_valid_utf8`, `filter_luhn` and
+//! `filter_checksum` operations.
+//!
+//! Checksums are computed over the UTF-8 bytes of a value, so they match
+//! what tools such as `crc32` report for the same text without a trailing
+//! newline.
+
+use std::cell::Cell;
+
+use super::ChecksumAlgorithm;
+
+/// The CRC-32 lookup table for the reflected polynomial `0xEDB88320`, as used
+/// by zip, gzip and PNG.
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, &b| {
+        CRC32_TABLE[((crc ^ u32::from(b)) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    const MOD: u32 = 65_521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 bytes is the most that can be summed before `b` could overflow
+    for chunk in bytes.chunks(5552) {
+        for &byte in chunk {
+            a += u32::from(byte);
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+impl ChecksumAlgorithm {
+    /// Returns the checksum of `bytes`.
+    pub(crate) fn checksum(self, bytes: &[u8]) -> u32 {
+        match self {
+            ChecksumAlgorithm::Crc32 => crc32(bytes),
+            ChecksumAlgorithm::Adler32 => adler32(bytes),
+        }
+    }
+
+    /// Returns the name of the algorithm as written in templates.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Crc32 => "crc32",
+            ChecksumAlgorithm::Adler32 => "adler32",
+        }
+    }
+}
+
+thread_local! {
+    /// Whether the input of the format call running on this thread was read
+    /// lossily and had invalid UTF-8 replaced with U+FFFD.
+    static LOSSY_INPUT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Restores the input state of the enclosing call.
+struct LossyInputGuard(bool);
+
+impl Drop for LossyInputGuard {
+    fn drop(&mut self) {
+        LOSSY_INPUT.set(self.0);
+    }
+}
+
+/// Runs a format call `f` on input that had invalid UTF-8 replaced, or not.
+pub(crate) fn with_lossy_input<R>(lossy: bool, f: impl FnOnce() -> R) -> R {
+    let _guard = LossyInputGuard(LOSSY_INPUT.replace(lossy));
+    f()
+}
+
+/// Returns `true` if the running format call's input had invalid UTF-8
+/// replaced.
+pub(crate) fn lossy_input() -> bool {
+    LOSSY_INPUT.get()
+}
+
+/// Returns `true` if `s` holds no U+FFFD replacement character left by lossy
+/// decoding.
+///
+/// Input that was valid UTF-8 may hold U+FFFD itself, so nothing counts as
+/// invalid unless decoding the input replaced some bytes.
+pub(crate) fn is_valid_utf8(s: &str) -> bool {
+    !lossy_input() || !s.contains(char::REPLACEMENT_CHARACTER)
+}
+
+/// Returns `true` if `s` is a number that passes the Luhn check used by
+/// payment card and IMEI numbers.
+///
+/// Spaces and dashes between digits are ignored. Anything else, or fewer than
+/// two digits, fails the check.
+pub(crate) fn is_luhn(s: &str) -> bool {
+    let mut sum = 0;
+    let mut digits = 0;
+    for c in s.trim().chars().rev() {
+        let digit = match c {
+            ' ' | '-' => continue,
+            c => match c.to_digit(10) {
+                Some(digit) => digit,
+                None => return false,
+            },
+        };
+        sum += if digits % 2 == 1 {
+            let doubled = digit * 2;
+            if doubled > 9 { doubled - 9 } else { doubled }
+        } else {
+            digit
+        };
+        digits += 1;
+    }
+    digits >= 2 && sum % 10 == 0
+}
@@ -0,0 +1,89 @@
+//! Value lookup in YAML and TOML documents for the `yaml_get` and `toml_get`
+//! operations.
+//!
+//! Each format sits behind the cargo feature of the same name, so its parser
+//! is only compiled in when asked for. A path is a list of keys, and a key
+//! made of digits also indexes a sequence or array. Lookups give a scalar as
+//! text, an empty string for a missing key or a null, and an error for a
+//! mapping or list, which has no single value to give.
+
+/// Returns the error for a path that leads to a mapping or list.
+#[cfg(any(feature = "yaml", feature = "toml"))]
+fn not_scalar(path: &[String]) -> String {
+    format!("'{}' is not a scalar value", path.join("."))
+}
+
+/// Returns the error for an operation whose feature is not compiled in.
+pub(crate) fn disabled(op: &str, feature: &str) -> String {
+    format!("{op} requires string_pipeline to be built with the '{feature}' feature")
+}
+
+/// Looks up `path` in the YAML document `text`. Numbers and booleans are
+/// written the way YAML reads them, so `0x10` gives `16`.
+#[cfg(feature = "yaml")]
+pub(crate) fn yaml_get(text: &str, path: &[String]) -> Result<String, String> {
+    use yaml_rust2::{Yaml, YamlLoader};
+
+    let docs = YamlLoader::load_from_str(text).map_err(|e| format!("Invalid YAML: {e}"))?;
+    let mut node = docs.first().unwrap_or(&Yaml::Null);
+    for key in path {
+        let next = match node {
+            Yaml::Hash(map) => map
+                .get(&Yaml::String(key.clone()))
+                .or_else(|| key.parse().ok().and_then(|n| map.get(&Yaml::Integer(n)))),
+            Yaml::Array(items) => key.parse().ok().and_then(|i: usize| items.get(i)),
+            _ => None,
+        };
+        match next {
+            Some(next) => node = next,
+            None => return Ok(String::new()),
+        }
+    }
+    match node {
+        Yaml::String(s) | Yaml::Real(s) => Ok(s.clone()),
+        Yaml::Integer(n) => Ok(n.to_string()),
+        Yaml::Boolean(b) => Ok(b.to_string()),
+        Yaml::Null | Yaml::BadValue => Ok(String::new()),
+        _ => Err(not_scalar(path)),
+    }
+}
+
+#[cfg(not(feature = "yaml"))]
+pub(crate) fn yaml_get(_text: &str, _path: &[String]) -> Result<String, String> {
+    Err(disabled("yaml_get", "yaml"))
+}
+
+/// Looks up `path` in the TOML document `text`. Numbers, booleans and dates
+/// are written as they appear in the document, strings without quotes.
+#[cfg(feature = "toml")]
+pub(crate) fn toml_get(text: &str, path: &[String]) -> Result<String, String> {
+    use toml_edit::{DocumentMut, Value};
+
+    let doc: DocumentMut = text
+        .parse()
+        .map_err(|e: toml_edit::TomlError| format!("Invalid TOML: {}", e.message().trim()))?;
+    let mut item = doc.as_item();
+    for key in path {
+        let next = match key.parse::<usize>() {
+            Ok(i) if item.is_array() || item.is_array_of_tables() => item.get(i),
+            _ => item.get(key.as_str()),
+        };
+        match next {
+            Some(next) => item = next,
+            None => return Ok(String::new()),
+        }
+    }
+    match item.as_value() {
+        Some(Value::String(s)) => Ok(s.value().clone()),
+        Some(Value::Integer(n)) => Ok(n.display_repr().into_owned()),
+        Some(Value::Float(f)) => Ok(f.display_repr().into_owned()),
+        Some(Value::Boolean(b)) => Ok(b.display_repr().into_owned()),
+        Some(Value::Datetime(d)) => Ok(d.display_repr().into_owned()),
+        _ => Err(not_scalar(path)),
+    }
+}
+
+#[cfg(not(feature = "toml"))]
+pub(crate) fn toml_get(_text: &str, _path: &[String]) -> Result<String, String> {
+    Err(disabled("toml_get", "toml"))
+}
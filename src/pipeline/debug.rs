@@ -2,10 +2,193 @@
 //!
 //! This module contains the debug context implementation that provides
 //! detailed logging and tracing capabilities for pipeline execution.
+//!
+//! Traces are written either as a human-readable tree
+//! ([`DebugFormat::Text`]) or as line-delimited JSON events
+//! ([`DebugFormat::Json`]) for consumption by editors and tooling. Lines go
+//! to stderr unless a [`DebugSink`] is attached. With the `tracing` feature,
+//! `DebugFormat::Tracing` reports the same steps as `tracing` spans and
+//! events instead of lines.
+//!
+//! A [`DebugLevel`] limits how much of the execution is reported, and value
+//! previews are cut to a configurable number of characters, which keeps
+//! traces over large inputs readable.
 
 use crate::pipeline::{REGEX_CACHE, SPLIT_CACHE, StringOp, Value};
+use parking_lot::Mutex;
+use std::fmt;
+use std::fmt::Write as _;
+use std::io;
+use std::sync::Arc;
 use std::time::Duration;
 
+/// Default maximum number of characters kept in value previews.
+pub(crate) const DEFAULT_PREVIEW_CHARS: usize = 40;
+
+/// Maximum number of list items kept in JSON value previews.
+const JSON_PREVIEW_ITEMS: usize = 3;
+
+/// Output format for debug traces.
+///
+/// # Examples
+///
+/// ```rust
+/// use string_pipeline::{DebugFormat, Template};
+///
+/// let template = Template::parse_with_debug("{upper}", Some(true))
+///     .unwrap()
+///     .with_debug_format(DebugFormat::Json);
+/// // Emits one JSON object per line on stderr
+/// assert_eq!(template.format("hi").unwrap(), "HI");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DebugFormat {
+    /// Human-readable tree output (default).
+    #[default]
+    Text,
+    /// Line-delimited JSON, one event object per line.
+    ///
+    /// Every event has an `event` field (`session_start`, `section`, `cache`,
+    /// `pipeline_start`, `operation`, `map_item_start`, `map_item_end`,
+    /// `map_complete`, `pipeline_end`, `session_end`). Operation events carry
+    /// `operation`, `input`, `output`, and `duration_ns`.
+    Json,
+    /// Spans and events of the [`tracing`](https://docs.rs/tracing) crate,
+    /// delivered to the subscriber of the embedding application.
+    ///
+    /// A `session` span covers a whole template, with a `pipeline` span per
+    /// template section, an `operation` span per step, and a `map_item` span
+    /// per item of a `map`. Each span holds `debug` level events for the
+    /// steps that text and JSON traces report as lines. Nothing is written to
+    /// stderr or to a [`DebugSink`].
+    ///
+    /// Needs the `tracing` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::{DebugFormat, Template};
+    ///
+    /// let template = Template::parse("{split:,:..|map:{upper}}")
+    ///     .unwrap()
+    ///     .with_debug(true)
+    ///     .with_debug_format(DebugFormat::Tracing);
+    /// // Spans and events go to the current `tracing` subscriber
+    /// assert_eq!(template.format("a,b").unwrap(), "A,B");
+    /// ```
+    #[cfg(feature = "tracing")]
+    Tracing,
+}
+
+/// How much of the execution debug traces report.
+///
+/// Levels are ordered, and each one reports everything the previous one
+/// does.
+///
+/// # Examples
+///
+/// ```rust
+/// use string_pipeline::{DebugLevel, DebugSink, Template};
+/// use std::sync::{Arc, Mutex};
+///
+/// let lines = Arc::new(Mutex::new(Vec::new()));
+/// let captured = Arc::clone(&lines);
+///
+/// let template = Template::parse("{split:,:..|map:{upper}}")
+///     .unwrap()
+///     .with_debug(true)
+///     .with_debug_level(DebugLevel::Ops)
+///     .with_debug_sink(DebugSink::from_fn(move |line| {
+///         captured.lock().unwrap().push(line.to_string());
+///     }));
+///
+/// assert_eq!(template.format("a,b").unwrap(), "A,B");
+/// let lines = lines.lock().unwrap();
+/// assert!(lines.iter().any(|line| line.contains("Step 2: Map")));
+/// assert!(!lines.iter().any(|line| line.contains("Item 1/2")));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DebugLevel {
+    /// Sessions, sections, cache use, and the start and end of the main
+    /// pipeline with its timing, without operation steps or value previews.
+    Summary,
+    /// Adds every operation of the main pipeline with its input and output.
+    Ops,
+    /// Adds the items of `map` and the sub-pipelines run for them and by
+    /// `try` (default).
+    #[default]
+    Full,
+}
+
+/// Destination for debug trace lines.
+///
+/// By default debug output is printed to stderr. A sink redirects every trace
+/// line to a callback or writer instead, which lets embedding applications
+/// show traces in their own UI. Each line is delivered without a trailing
+/// newline, exactly as it would otherwise appear on stderr.
+///
+/// Sinks are cheap to clone and shared between clones of a [`Template`](crate::Template).
+///
+/// # Examples
+///
+/// ```rust
+/// use std::sync::{Arc, Mutex};
+/// use string_pipeline::{DebugSink, Template};
+///
+/// let lines = Arc::new(Mutex::new(Vec::new()));
+/// let captured = Arc::clone(&lines);
+///
+/// let template = Template::parse("{upper}")
+///     .unwrap()
+///     .with_debug(true)
+///     .with_debug_sink(DebugSink::from_fn(move |line| {
+///         captured.lock().unwrap().push(line.to_string());
+///     }));
+///
+/// assert_eq!(template.format("hi").unwrap(), "HI");
+/// assert!(!lines.lock().unwrap().is_empty());
+/// ```
+#[derive(Clone)]
+pub struct DebugSink {
+    callback: Arc<dyn Fn(&str) + Send + Sync>,
+}
+
+impl DebugSink {
+    /// Creates a sink that passes each trace line to `callback`.
+    pub fn from_fn<F>(callback: F) -> Self
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        Self {
+            callback: Arc::new(callback),
+        }
+    }
+
+    /// Creates a sink that writes each trace line, followed by a newline, to `writer`.
+    ///
+    /// Write errors are ignored so that tracing never affects formatting results.
+    pub fn from_writer<W>(writer: W) -> Self
+    where
+        W: io::Write + Send + 'static,
+    {
+        let writer = Mutex::new(writer);
+        Self::from_fn(move |line| {
+            let mut writer = writer.lock();
+            let _ = writeln!(writer, "{line}");
+        })
+    }
+
+    fn write_line(&self, line: &str) {
+        (self.callback)(line);
+    }
+}
+
+impl fmt::Debug for DebugSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DebugSink").finish_non_exhaustive()
+    }
+}
+
 /// Debug tracer that provides hierarchical execution logging for pipeline operations.
 ///
 /// The `DebugTracer` outputs detailed information about pipeline execution including
@@ -16,6 +199,10 @@ use std::time::Duration;
 pub struct DebugTracer {
     enabled: bool,
     is_sub_pipeline: bool,
+    format: DebugFormat,
+    level: DebugLevel,
+    preview_chars: usize,
+    sink: Option<DebugSink>,
 }
 
 impl DebugTracer {
@@ -28,6 +215,10 @@ impl DebugTracer {
         Self {
             enabled,
             is_sub_pipeline: false,
+            format: DebugFormat::Text,
+            level: DebugLevel::Full,
+            preview_chars: DEFAULT_PREVIEW_CHARS,
+            sink: None,
         }
     }
 
@@ -43,9 +234,60 @@ impl DebugTracer {
         Self {
             enabled,
             is_sub_pipeline: true,
+            format: DebugFormat::Text,
+            level: DebugLevel::Full,
+            preview_chars: DEFAULT_PREVIEW_CHARS,
+            sink: None,
         }
     }
 
+    /// Creates a tracer for a sub-pipeline that inherits this tracer's
+    /// format, level, preview length, and sink.
+    pub fn nested(&self) -> Self {
+        Self {
+            enabled: self.enabled,
+            is_sub_pipeline: true,
+            format: self.format,
+            level: self.level,
+            preview_chars: self.preview_chars,
+            sink: self.sink.clone(),
+        }
+    }
+
+    /// Sets the output format of this tracer.
+    pub fn with_format(mut self, format: DebugFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets how much of the execution this tracer reports.
+    pub fn with_level(mut self, level: DebugLevel) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Sets the maximum number of characters kept in value previews.
+    pub fn with_preview_chars(mut self, chars: usize) -> Self {
+        self.preview_chars = chars;
+        self
+    }
+
+    /// Redirects trace lines to `sink`, or back to stderr with `None`.
+    pub fn with_sink(mut self, sink: Option<DebugSink>) -> Self {
+        self.sink = sink;
+        self
+    }
+
+    /// Returns the output format of this tracer.
+    pub fn format(&self) -> DebugFormat {
+        self.format
+    }
+
+    /// Returns how much of the execution this tracer reports.
+    pub fn level(&self) -> DebugLevel {
+        self.level
+    }
+
     /// Logs the start of a session (template or template processing).
     ///
     /// This marks the beginning of a complete processing session, showing
@@ -68,10 +310,34 @@ impl DebugTracer {
             return;
         }
 
+        if self.uses_tracing() {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                session = session_type,
+                template,
+                input = %self.preview(input),
+                info,
+                "session start"
+            );
+            return;
+        }
+
+        if self.format == DebugFormat::Json {
+            let mut event = JsonEvent::new("session_start");
+            event.str("session", session_type);
+            event.str("template", template);
+            event.str("input", &self.preview(input));
+            if let Some(info) = info {
+                event.str("info", info);
+            }
+            self.emit(event);
+            return;
+        }
+
         self.line(format!("📂 {session_type}"));
         self.line_with_prefix(format!("🏁 {session_type} START"), 1);
         self.line_with_prefix(format!("Template: {template:?}"), 1);
-        self.line_with_prefix(format!("➡️ Input: {input:?}"), 1);
+        self.line_with_prefix(format!("➡️ Input: {:?}", self.preview(input)), 1);
         if let Some(info) = info {
             self.line_with_prefix(info.to_string(), 1);
         }
@@ -93,8 +359,32 @@ impl DebugTracer {
             return;
         }
 
+        if self.uses_tracing() {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                session = session_type,
+                result = %self.preview(result),
+                elapsed = ?elapsed,
+                regex_cache_entries = REGEX_CACHE.len(),
+                split_cache_entries = SPLIT_CACHE.len(),
+                "session end"
+            );
+            return;
+        }
+
+        if self.format == DebugFormat::Json {
+            let mut event = JsonEvent::new("session_end");
+            event.str("session", session_type);
+            event.str("result", &self.preview(result));
+            event.num("duration_ns", elapsed.as_nanos());
+            event.num("regex_cache_entries", REGEX_CACHE.len() as u128);
+            event.num("split_cache_entries", SPLIT_CACHE.len() as u128);
+            self.emit(event);
+            return;
+        }
+
         self.line_with_prefix(format!("🏁 ✅ {session_type} COMPLETE"), 1);
-        self.line_with_prefix(format!("🎯 Final result: {result:?}"), 1);
+        self.line_with_prefix(format!("🎯 Final result: {:?}", self.preview(result)), 1);
         self.line_with_prefix(format!("Total execution time: {elapsed:?}"), 1);
 
         self.line_with_ending_prefix(
@@ -116,8 +406,31 @@ impl DebugTracer {
     ///
     /// * `ops` - The sequence of operations to be executed
     /// * `input` - The initial input value for the pipeline
-    pub fn pipeline_start(&self, ops: &[StringOp], input: &Value) {
-        if !self.enabled {
+    pub fn pipeline_start(&self, ops: &[StringOp], input: &Value<'_>) {
+        if !self.reports(DebugLevel::Summary) {
+            return;
+        }
+
+        if self.uses_tracing() {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                sub_pipeline = self.is_sub_pipeline,
+                operations = ops.len(),
+                input = self.value_preview(input).as_deref(),
+                "pipeline start"
+            );
+            return;
+        }
+
+        if self.format == DebugFormat::Json {
+            let names: Vec<String> = ops.iter().map(|op| json_string(op.keyword())).collect();
+            let mut event = JsonEvent::new("pipeline_start");
+            event.bool("sub_pipeline", self.is_sub_pipeline);
+            event.raw("operations", &format!("[{}]", names.join(",")));
+            if self.shows_values() {
+                event.raw("input", &json_value_preview(input, self.preview_chars));
+            }
+            self.emit(event);
             return;
         }
 
@@ -144,10 +457,9 @@ impl DebugTracer {
             format!("{} {} START: {} operations", icon, label, ops.len()),
             depth + 1,
         );
-        self.line_with_prefix(
-            format!("➡️ Input: {}", Self::format_value(input)),
-            depth + 1,
-        );
+        if let Some(input) = self.value_preview(input) {
+            self.line_with_prefix(format!("➡️ Input: {input}"), depth + 1);
+        }
 
         if ops.len() > 1 {
             for (i, op) in ops.iter().enumerate() {
@@ -166,9 +478,37 @@ impl DebugTracer {
     /// # Arguments
     ///
     /// * `result` - The final result value from the pipeline
+    /// * `join_sep` - Separator used to render a list result
     /// * `elapsed` - Total execution time for the pipeline
-    pub fn pipeline_end(&self, result: &Value, elapsed: Duration) {
-        if !self.enabled {
+    pub fn pipeline_end(&self, result: &Value<'_>, join_sep: &str, elapsed: Duration) {
+        if !self.reports(DebugLevel::Summary) {
+            return;
+        }
+
+        let is_list = matches!(result, Value::List(_));
+        if self.uses_tracing() {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                sub_pipeline = self.is_sub_pipeline,
+                result = self.value_preview(result).as_deref(),
+                join_sep = is_list.then_some(join_sep),
+                elapsed = ?elapsed,
+                "pipeline end"
+            );
+            return;
+        }
+
+        if self.format == DebugFormat::Json {
+            let mut event = JsonEvent::new("pipeline_end");
+            event.bool("sub_pipeline", self.is_sub_pipeline);
+            if self.shows_values() {
+                event.raw("result", &json_value_preview(result, self.preview_chars));
+            }
+            if is_list {
+                event.str("join_sep", join_sep);
+            }
+            event.num("duration_ns", elapsed.as_nanos());
+            self.emit(event);
             return;
         }
 
@@ -180,10 +520,15 @@ impl DebugTracer {
         };
 
         self.line_with_prefix(format!("✅ {label} COMPLETE"), depth + 1);
-        self.line_with_prefix(
-            format!("🎯 Result: {}", Self::format_value(result)),
-            depth + 1,
-        );
+        if let Some(result) = self.value_preview(result) {
+            self.line_with_prefix(format!("🎯 Result: {result}"), depth + 1);
+        }
+        if is_list {
+            self.line_with_prefix(
+                format!("🔗 Rendered with separator: {join_sep:?}"),
+                depth + 1,
+            );
+        }
         self.line_with_ending_prefix(format!("Time: {elapsed:?}"), depth + 1);
 
         if !self.is_sub_pipeline {
@@ -209,11 +554,36 @@ impl DebugTracer {
         step: usize,
         _total: usize,
         op: &StringOp,
-        input: &Value,
-        result: &Value,
+        input: &Value<'_>,
+        result: &Value<'_>,
         elapsed: Duration,
     ) {
-        if !self.enabled {
+        if !self.reports(DebugLevel::Ops) {
+            return;
+        }
+
+        if self.uses_tracing() {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                step,
+                operation = op.keyword(),
+                input = %self.format_value(input),
+                output = %self.format_value(result),
+                elapsed = ?elapsed,
+                "operation"
+            );
+            return;
+        }
+
+        if self.format == DebugFormat::Json {
+            let mut event = JsonEvent::new("operation");
+            event.bool("sub_pipeline", self.is_sub_pipeline);
+            event.num("step", step as u128);
+            event.str("operation", op.keyword());
+            event.raw("input", &json_value_preview(input, self.preview_chars));
+            event.raw("output", &json_value_preview(result, self.preview_chars));
+            event.num("duration_ns", elapsed.as_nanos());
+            self.emit(event);
             return;
         }
 
@@ -223,12 +593,9 @@ impl DebugTracer {
             format!("⚙️ Step {}: {}", step, Self::format_operation_name(op)),
             depth,
         );
+        self.line_with_prefix(format!("➡️ Input: {}", self.format_value(input)), depth + 1);
         self.line_with_prefix(
-            format!("➡️ Input: {}", Self::format_value(input)),
-            depth + 1,
-        );
-        self.line_with_prefix(
-            format!("🎯 Result: {}", Self::format_value(result)),
+            format!("🎯 Result: {}", self.format_value(result)),
             depth + 1,
         );
         self.line_with_ending_prefix(format!("Time: {elapsed:?}"), depth + 1);
@@ -245,12 +612,32 @@ impl DebugTracer {
     /// * `total_items` - The total number of items being processed
     /// * `input` - The input string for this item
     pub fn map_item_start(&self, item_idx: usize, total_items: usize, input: &str) {
-        if !self.enabled {
+        if !self.reports(DebugLevel::Full) {
+            return;
+        }
+
+        if self.uses_tracing() {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                item = item_idx,
+                total = total_items,
+                input = %self.preview(input),
+                "map item start"
+            );
+            return;
+        }
+
+        if self.format == DebugFormat::Json {
+            let mut event = JsonEvent::new("map_item_start");
+            event.num("item", item_idx as u128);
+            event.num("total", total_items as u128);
+            event.str("input", &truncate_chars(input, self.preview_chars));
+            self.emit(event);
             return;
         }
 
         self.line_with_prefix(format!("🗂️ Item {item_idx}/{total_items}"), 3);
-        self.line_with_prefix(format!("➡️ Input: {input:?}"), 4);
+        self.line_with_prefix(format!("➡️ Input: {:?}", self.preview(input)), 4);
     }
 
     /// Logs the end of processing a map operation item.
@@ -262,12 +649,34 @@ impl DebugTracer {
     ///
     /// * `output` - The result of processing the item, either success or error
     pub fn map_item_end(&self, output: Result<&str, &str>) {
-        if !self.enabled {
+        if !self.reports(DebugLevel::Full) {
+            return;
+        }
+
+        if self.uses_tracing() {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                output = output.ok().map(|result| self.preview(result)).as_deref(),
+                error = output.err(),
+                "map item end"
+            );
+            return;
+        }
+
+        if self.format == DebugFormat::Json {
+            let mut event = JsonEvent::new("map_item_end");
+            match output {
+                Ok(result) => event.str("output", &truncate_chars(result, self.preview_chars)),
+                Err(error) => event.str("error", error),
+            }
+            self.emit(event);
             return;
         }
 
         match output {
-            Ok(result) => self.line_with_ending_prefix(format!("Output: {result:?}"), 4),
+            Ok(result) => {
+                self.line_with_ending_prefix(format!("Output: {:?}", self.preview(result)), 4)
+            }
             Err(error) => self.line_with_ending_prefix(format!("❌ ERROR: {error}"), 4),
         }
     }
@@ -282,7 +691,25 @@ impl DebugTracer {
     /// * `input_count` - Number of input items processed
     /// * `output_count` - Number of output items produced
     pub fn map_complete(&self, input_count: usize, output_count: usize) {
-        if !self.enabled {
+        if !self.reports(DebugLevel::Ops) {
+            return;
+        }
+
+        if self.uses_tracing() {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                input_items = input_count,
+                output_items = output_count,
+                "map complete"
+            );
+            return;
+        }
+
+        if self.format == DebugFormat::Json {
+            let mut event = JsonEvent::new("map_complete");
+            event.num("input_items", input_count as u128);
+            event.num("output_items", output_count as u128);
+            self.emit(event);
             return;
         }
 
@@ -306,10 +733,53 @@ impl DebugTracer {
             return;
         }
 
+        if self.uses_tracing() {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(kind = operation, details, "cache");
+            return;
+        }
+
+        if self.format == DebugFormat::Json {
+            let mut event = JsonEvent::new("cache");
+            event.str("kind", operation);
+            event.str("details", details);
+            self.emit(event);
+            return;
+        }
+
         self.line_with_prefix(format!("💾 {operation} {details}"), 1);
         self.separator();
     }
 
+    /// Logs an error that stopped template formatting.
+    ///
+    /// The message already names the failing operation and where it appears
+    /// in the template.
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - The error reported to the caller
+    pub fn error(&self, error: &str) {
+        if !self.enabled {
+            return;
+        }
+
+        if self.uses_tracing() {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(error, "error");
+            return;
+        }
+
+        if self.format == DebugFormat::Json {
+            let mut event = JsonEvent::new("error");
+            event.str("message", error);
+            self.emit(event);
+            return;
+        }
+
+        self.line_with_prefix(format!("❌ ERROR: {error}"), 1);
+    }
+
     /// Logs section processing information for template operations.
     ///
     /// This shows progress through different sections of a template,
@@ -332,6 +802,28 @@ impl DebugTracer {
             return;
         }
 
+        if self.uses_tracing() {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                section = section_num,
+                total = total_sections,
+                kind = section_type,
+                content,
+                "section"
+            );
+            return;
+        }
+
+        if self.format == DebugFormat::Json {
+            let mut event = JsonEvent::new("section");
+            event.num("section", section_num as u128);
+            event.num("total", total_sections as u128);
+            event.str("kind", section_type);
+            event.str("content", content);
+            self.emit(event);
+            return;
+        }
+
         self.line_with_prefix(
             format!(
                 "📊 SECTION {section_num}/{total_sections}: [{section_type}{}]",
@@ -345,11 +837,117 @@ impl DebugTracer {
         );
     }
 
+    /// Enters a span covering a whole session.
+    pub(crate) fn session_span(&self, session_type: &str, template: &str) -> SpanGuard {
+        #[cfg(feature = "tracing")]
+        if self.uses_tracing() {
+            return SpanGuard::enter(tracing::debug_span!(
+                "session",
+                session = session_type,
+                template
+            ));
+        }
+        let _ = (session_type, template);
+        SpanGuard::none()
+    }
+
+    /// Enters a span covering a pipeline of `op_count` operations.
+    pub(crate) fn pipeline_span(&self, op_count: usize) -> SpanGuard {
+        #[cfg(feature = "tracing")]
+        if self.uses_tracing() && self.reports(DebugLevel::Summary) {
+            return SpanGuard::enter(tracing::debug_span!(
+                "pipeline",
+                sub_pipeline = self.is_sub_pipeline,
+                operations = op_count
+            ));
+        }
+        let _ = op_count;
+        SpanGuard::none()
+    }
+
+    /// Enters a span covering one operation of a pipeline.
+    pub(crate) fn operation_span(&self, step: usize, op: &StringOp) -> SpanGuard {
+        #[cfg(feature = "tracing")]
+        if self.uses_tracing() && self.reports(DebugLevel::Ops) {
+            return SpanGuard::enter(tracing::debug_span!(
+                "operation",
+                step,
+                operation = op.keyword()
+            ));
+        }
+        let _ = (step, op);
+        SpanGuard::none()
+    }
+
+    /// Enters a span covering one item of a map operation.
+    pub(crate) fn map_item_span(&self, item_idx: usize, total_items: usize) -> SpanGuard {
+        #[cfg(feature = "tracing")]
+        if self.uses_tracing() && self.reports(DebugLevel::Full) {
+            return SpanGuard::enter(tracing::debug_span!(
+                "map_item",
+                item = item_idx,
+                total = total_items
+            ));
+        }
+        let _ = (item_idx, total_items);
+        SpanGuard::none()
+    }
+
     // PRIVATE HELPERS
 
+    /// Returns whether steps that need `level` are reported.
+    ///
+    /// Sub-pipelines run once per `map` item, so they are only reported at
+    /// [`DebugLevel::Full`].
+    fn reports(&self, level: DebugLevel) -> bool {
+        let level = if self.is_sub_pipeline {
+            DebugLevel::Full
+        } else {
+            level
+        };
+        self.enabled && self.level >= level
+    }
+
+    /// Returns whether pipeline values are previewed.
+    fn shows_values(&self) -> bool {
+        self.level >= DebugLevel::Ops
+    }
+
+    /// Previews a pipeline value, or `None` when values are not shown.
+    fn value_preview(&self, val: &Value<'_>) -> Option<String> {
+        self.shows_values().then(|| self.format_value(val))
+    }
+
+    /// Cuts a string to the preview length.
+    fn preview(&self, s: &str) -> String {
+        truncate_chars(s, self.preview_chars)
+    }
+
+    /// Returns whether steps are reported as `tracing` spans and events.
+    fn uses_tracing(&self) -> bool {
+        #[cfg(feature = "tracing")]
+        if self.format == DebugFormat::Tracing {
+            return self.enabled;
+        }
+        false
+    }
+
+    /// Writes a finished line to the sink, or to stderr when none is attached.
+    fn write(&self, line: &str) {
+        match &self.sink {
+            Some(sink) => sink.write_line(line),
+            None => eprintln!("{line}"),
+        }
+    }
+
+    /// Writes a finished JSON event.
+    fn emit(&self, event: JsonEvent) {
+        self.write(&event.finish());
+    }
+
     /// Outputs a debug line without indentation prefix.
     fn line(&self, msg: String) {
-        eprintln!("DEBUG: {msg}");
+        self.write(&format!("DEBUG: {msg}"));
     }
 
     /// Outputs a debug line with hierarchical indentation prefix.
@@ -370,7 +968,7 @@ impl DebugTracer {
             6 => "│   │   │   │   │   ├── ".to_string(),
             _ => "│   ".repeat(depth.saturating_sub(1)) + "├── ",
         };
-        eprintln!("DEBUG: {prefix}{msg}");
+        self.write(&format!("DEBUG: {prefix}{msg}"));
     }
 
     /// Outputs a debug line with ending hierarchical prefix.
@@ -391,12 +989,17 @@ impl DebugTracer {
             6 => "│   │   │   │   │   └── ".to_string(),
             _ => "│   ".repeat(depth.saturating_sub(1)) + "└── ",
         };
-        eprintln!("DEBUG: {prefix}{msg}");
+        self.write(&format!("DEBUG: {prefix}{msg}"));
     }
 
     /// Outputs a visual separator line.
+    ///
+    /// Separators only exist in text output and are skipped for other formats.
     pub fn separator(&self) {
-        eprintln!("DEBUG: │");
+        if self.format != DebugFormat::Text {
+            return;
+        }
+        self.write("DEBUG: │");
     }
 
     /// Formats a value for display in debug output.
@@ -411,22 +1014,25 @@ impl DebugTracer {
     /// # Returns
     ///
     /// A formatted string representation of the value
-    fn format_value(val: &Value) -> String {
+    fn format_value(&self, val: &Value<'_>) -> String {
         match val {
-            Value::Str(s) => {
-                if s.len() > 40 {
-                    format!("String({}..)", &s[..40])
-                } else {
-                    format!("String({s})")
-                }
-            }
+            Value::Str(s) => match clip_chars(s, self.preview_chars) {
+                Some(head) => format!("String({head}..)"),
+                None => format!("String({s})"),
+            },
             Value::List(list) => {
                 if list.is_empty() {
                     "List(empty)".to_string()
                 } else if list.len() <= 3 {
-                    format!("List{list:?}")
+                    let items: Vec<String> = list.iter().map(|item| self.preview(item)).collect();
+                    format!("List{items:?}")
                 } else {
-                    format!("List[{}, {}, ...+{}]", list[0], list[1], list.len() - 2)
+                    format!(
+                        "List[{}, {}, ...+{}]",
+                        self.preview(&list[0]),
+                        self.preview(&list[1]),
+                        list.len() - 2
+                    )
                 }
             }
         }
@@ -439,8 +1045,19 @@ impl DebugTracer {
     fn format_operation(op: &StringOp) -> String {
         match op {
             StringOp::Split { sep, .. } => format!("Split('{sep}')"),
-            StringOp::Join { sep } => format!("Join('{sep}')"),
+            StringOp::SplitAny { chars, .. } => format!("SplitAny('{chars}')"),
+            StringOp::Join {
+                sep,
+                last_sep: Some(last_sep),
+            } => format!("Join('{sep}', '{last_sep}')"),
+            StringOp::Join { sep, .. } => format!("Join('{sep}')"),
+            StringOp::OutputSep { sep } => format!("OutputSep('{sep}')"),
             StringOp::Map { operations } => format!("Map({})", operations.len()),
+            StringOp::MapFallible { operations } => format!("MapFallible({})", operations.len()),
+            StringOp::Try { operations, .. } => format!("Try({})", operations.len()),
+            StringOp::SortBy { operations, .. } => format!("SortBy({})", operations.len()),
+            StringOp::UniqueBy { operations } => format!("UniqueBy({})", operations.len()),
+            StringOp::FilterBy { operations, .. } => format!("FilterBy({})", operations.len()),
             _ => Self::format_operation_name(op),
         }
     }
@@ -449,25 +1066,222 @@ impl DebugTracer {
     fn format_operation_name(op: &StringOp) -> String {
         match op {
             StringOp::Split { .. } => "Split".to_string(),
+            StringOp::SplitAny { .. } => "SplitAny".to_string(),
+            StringOp::Partition { .. } => "Partition".to_string(),
+            StringOp::RPartition { .. } => "RPartition".to_string(),
             StringOp::Join { .. } => "Join".to_string(),
+            StringOp::OutputSep { .. } => "OutputSep".to_string(),
             StringOp::Map { .. } => "Map".to_string(),
-            StringOp::Upper => "Upper".to_string(),
-            StringOp::Lower => "Lower".to_string(),
+            StringOp::MapFallible { .. } => "MapFallible".to_string(),
+            StringOp::Try { .. } => "Try".to_string(),
+            StringOp::Upper { .. } => "Upper".to_string(),
+            StringOp::Lower { .. } => "Lower".to_string(),
+            StringOp::Capitalize { .. } => "Capitalize".to_string(),
+            StringOp::SwapCase { .. } => "SwapCase".to_string(),
             StringOp::Trim { .. } => "Trim".to_string(),
+            StringOp::Squeeze { .. } => "Squeeze".to_string(),
+            StringOp::TrimPrefix { .. } => "TrimPrefix".to_string(),
+            StringOp::TrimSuffix { .. } => "TrimSuffix".to_string(),
+            StringOp::TrimRegex { .. } => "TrimRegex".to_string(),
             StringOp::Replace { .. } => "Replace".to_string(),
+            StringOp::ReplaceLiteral { .. } => "ReplaceLiteral".to_string(),
             StringOp::Filter { .. } => "Filter".to_string(),
             StringOp::FilterNot { .. } => "FilterNot".to_string(),
+            StringOp::FilterPrefix { .. } => "FilterPrefix".to_string(),
+            StringOp::FilterSuffix { .. } => "FilterSuffix".to_string(),
+            StringOp::FilterContains { .. } => "FilterContains".to_string(),
+            StringOp::FilterValidUtf8 => "FilterValidUtf8".to_string(),
+            StringOp::FilterLuhn => "FilterLuhn".to_string(),
+            StringOp::FilterChecksum { .. } => "FilterChecksum".to_string(),
+            StringOp::FilterBy { .. } => "FilterBy".to_string(),
             StringOp::Sort { .. } => "Sort".to_string(),
+            StringOp::SortBy { .. } => "SortBy".to_string(),
             StringOp::Reverse => "Reverse".to_string(),
             StringOp::Unique => "Unique".to_string(),
+            StringOp::UniqueBy { .. } => "UniqueBy".to_string(),
+            StringOp::Duplicates => "Duplicates".to_string(),
+            StringOp::Histogram { .. } => "Histogram".to_string(),
+            StringOp::RemoveEmpty => "RemoveEmpty".to_string(),
+            StringOp::Intersect { .. } => "Intersect".to_string(),
+            StringOp::Diff { .. } => "Diff".to_string(),
+            StringOp::Union { .. } => "Union".to_string(),
+            StringOp::Shuffle { .. } => "Shuffle".to_string(),
+            StringOp::Sample { .. } => "Sample".to_string(),
+            StringOp::Table { .. } => "Table".to_string(),
+            StringOp::MarkdownTable { .. } => "MarkdownTable".to_string(),
+            StringOp::HtmlTable { .. } => "HtmlTable".to_string(),
             StringOp::Substring { .. } => "Substring".to_string(),
             StringOp::Append { .. } => "Append".to_string(),
             StringOp::Prepend { .. } => "Prepend".to_string(),
             StringOp::Surround { .. } => "Surround".to_string(),
             StringOp::Pad { .. } => "Pad".to_string(),
             StringOp::RegexExtract { .. } => "RegexExtract".to_string(),
+            StringOp::ExtractBetween { .. } => "ExtractBetween".to_string(),
+            StringOp::Kv { .. } => "Kv".to_string(),
+            StringOp::KvKeys { .. } => "KvKeys".to_string(),
+            StringOp::KvValues { .. } => "KvValues".to_string(),
+            StringOp::Words => "Words".to_string(),
+            StringOp::Sentences => "Sentences".to_string(),
+            StringOp::ExtractEmails => "ExtractEmails".to_string(),
+            StringOp::ExtractUrls => "ExtractUrls".to_string(),
+            StringOp::ExtractUuids => "ExtractUuids".to_string(),
             StringOp::Slice { .. } => "Slice".to_string(),
+            StringOp::Clamp { .. } => "Clamp".to_string(),
             StringOp::StripAnsi => "StripAnsi".to_string(),
+            StringOp::Deaccent => "Deaccent".to_string(),
+            StringOp::Ascii => "Ascii".to_string(),
+            StringOp::Slugify { .. } => "Slugify".to_string(),
+            StringOp::Normalize { .. } => "Normalize".to_string(),
+            StringOp::Ordinal { .. } => "Ordinal".to_string(),
+            StringOp::ToWords { .. } => "ToWords".to_string(),
+            StringOp::Roman { .. } => "Roman".to_string(),
+            StringOp::Basename { .. } => "Basename".to_string(),
+            StringOp::Dirname { .. } => "Dirname".to_string(),
+            StringOp::Extname { .. } => "Extname".to_string(),
+            StringOp::StripExt { .. } => "StripExt".to_string(),
+            StringOp::WithExt { .. } => "WithExt".to_string(),
+            StringOp::NormalizePath { .. } => "NormalizePath".to_string(),
+            StringOp::UrlScheme => "UrlScheme".to_string(),
+            StringOp::UrlHost => "UrlHost".to_string(),
+            StringOp::UrlPath => "UrlPath".to_string(),
+            StringOp::UrlQuery { .. } => "UrlQuery".to_string(),
+            StringOp::Color { .. } => "Color".to_string(),
+            StringOp::Background { .. } => "Background".to_string(),
+            StringOp::Bold => "Bold".to_string(),
+            StringOp::Underline => "Underline".to_string(),
+            StringOp::StyleReset => "StyleReset".to_string(),
+            StringOp::Highlight { .. } => "Highlight".to_string(),
+            StringOp::ReadFile => "ReadFile".to_string(),
+            StringOp::Exec { .. } => "Exec".to_string(),
+            StringOp::Each { .. } => "Each".to_string(),
+            StringOp::Len { .. } => "Len".to_string(),
+            StringOp::JsonArray => "JsonArray".to_string(),
+            StringOp::JsonObject { .. } => "JsonObject".to_string(),
+            StringOp::YamlGet { .. } => "YamlGet".to_string(),
+            StringOp::TomlGet { .. } => "TomlGet".to_string(),
+        }
+    }
+}
+
+/// Keeps a `tracing` span entered until dropped.
+///
+/// Empty unless the tracer reports to `tracing`, so holding one costs
+/// nothing for text and JSON traces.
+#[must_use]
+pub(crate) struct SpanGuard {
+    #[cfg(feature = "tracing")]
+    _span: Option<tracing::span::EnteredSpan>,
+}
+
+impl SpanGuard {
+    fn none() -> Self {
+        Self {
+            #[cfg(feature = "tracing")]
+            _span: None,
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    fn enter(span: tracing::Span) -> Self {
+        Self {
+            _span: Some(span.entered()),
+        }
+    }
+}
+
+/* ------------------------------------------------------------------------ */
+/*  JSON event helpers                                                      */
+/* ------------------------------------------------------------------------ */
+
+/// Builder for a single line-delimited JSON debug event.
+struct JsonEvent {
+    buf: String,
+}
+
+impl JsonEvent {
+    fn new(event: &str) -> Self {
+        let mut buf = String::with_capacity(128);
+        buf.push_str("{\"event\":");
+        buf.push_str(&json_string(event));
+        Self { buf }
+    }
+
+    fn raw(&mut self, key: &str, json: &str) {
+        let _ = write!(self.buf, ",{}:{json}", json_string(key));
+    }
+
+    fn str(&mut self, key: &str, value: &str) {
+        self.raw(key, &json_string(value));
+    }
+
+    fn num(&mut self, key: &str, value: u128) {
+        self.raw(key, &value.to_string());
+    }
+
+    fn bool(&mut self, key: &str, value: bool) {
+        self.raw(key, if value { "true" } else { "false" });
+    }
+
+    fn finish(mut self) -> String {
+        self.buf.push('}');
+        self.buf
+    }
+}
+
+/// Encodes a string as a JSON string literal, including the quotes.
+pub(crate) fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Returns the first `max` characters of `s`, or `None` when it is not longer.
+fn clip_chars(s: &str, max: usize) -> Option<&str> {
+    s.char_indices().nth(max).map(|(idx, _)| &s[..idx])
+}
+
+/// Truncates a string to at most `max` characters on a char boundary.
+fn truncate_chars(s: &str, max: usize) -> String {
+    match clip_chars(s, max) {
+        Some(head) => format!("{head}..."),
+        None => s.to_string(),
+    }
+}
+
+/// Renders a compact JSON preview of a pipeline value, with strings cut to
+/// `max_chars` characters.
+fn json_value_preview(val: &Value<'_>, max_chars: usize) -> String {
+    match val {
+        Value::Str(s) => format!(
+            "{{\"type\":\"string\",\"len\":{},\"preview\":{}}}",
+            s.len(),
+            json_string(&truncate_chars(s, max_chars))
+        ),
+        Value::List(list) => {
+            let items: Vec<String> = list
+                .iter()
+                .take(JSON_PREVIEW_ITEMS)
+                .map(|item| json_string(&truncate_chars(item, max_chars)))
+                .collect();
+            format!(
+                "{{\"type\":\"list\",\"len\":{},\"preview\":[{}]}}",
+                list.len(),
+                items.join(",")
+            )
         }
     }
 }
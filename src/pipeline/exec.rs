@@ -0,0 +1,175 @@
+//! Running external commands for the `exec` operation.
+//!
+//! Commands run through the platform shell (`sh -c` on Unix, `cmd /C` on
+//! Windows) with the current value on stdin. Their stdout becomes the new
+//! value once they exit successfully within the time allowed.
+
+use std::io::{self, Read, Write};
+use std::process::{Child, Command, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use super::limits::LIMIT_ERROR_PREFIX;
+
+/// Longest pause between two checks for the command's exit.
+const MAX_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Runs `command` with `input` on stdin and returns its stdout.
+///
+/// Trailing newlines are removed from the output, as shell command
+/// substitution does. The command and every process it started are killed
+/// if it runs longer than `timeout`, or if a process it left behind keeps
+/// its output open past that. Output longer than `max_output` bytes fails
+/// the command.
+pub(crate) fn run_command(
+    command: &str,
+    input: &str,
+    timeout: Duration,
+    max_output: usize,
+) -> Result<String, String> {
+    let deadline = Instant::now() + timeout;
+    let mut child = shell(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run command `{command}`: {e}"))?;
+
+    // Feed stdin and drain both outputs concurrently so that a command
+    // blocked on a full pipe can never deadlock us.
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = input.to_string();
+    let writer = thread::spawn(move || {
+        // Commands may exit without reading all of their input.
+        let _ = stdin.write_all(input.as_bytes());
+    });
+    let stdout = drain(child.stdout.take(), max_output);
+    let stderr = drain(child.stderr.take(), max_output);
+
+    let failed = |e: io::Error| format!("Failed to run command `{command}`: {e}");
+    let mut status = None;
+    let exited = poll_until(deadline, || {
+        status = child.try_wait()?;
+        Ok(status.is_some())
+    })
+    .map_err(failed)?;
+    // Processes started in the background may hold the pipes open after
+    // the shell exits
+    let drained = exited
+        && poll_until(deadline, || {
+            Ok(writer.is_finished() && stdout.is_finished() && stderr.is_finished())
+        })
+        .map_err(failed)?;
+    let Some(status) = status.filter(|_| drained) else {
+        kill_process_group(&mut child);
+        let _ = child.wait();
+        // The threads are left to finish on their own: their pipes close
+        // once the killed processes are gone, and nothing waits for them.
+        return Err(format!("Command `{command}` timed out after {timeout:?}"));
+    };
+
+    let _ = writer.join();
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+
+    // A command cut off by the size cap usually fails on the closed pipe,
+    // so the cap is reported first
+    if stdout.len() > max_output || stderr.len() > max_output {
+        return Err(format!(
+            "{LIMIT_ERROR_PREFIX} command `{command}` wrote more than {max_output} bytes"
+        ));
+    }
+
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&stderr);
+        let stderr = stderr.trim();
+        return Err(if stderr.is_empty() {
+            format!("Command `{command}` failed ({status})")
+        } else {
+            format!("Command `{command}` failed ({status}): {stderr}")
+        });
+    }
+
+    let mut output = String::from_utf8(stdout)
+        .map_err(|_| format!("Command `{command}` produced output that is not valid UTF-8"))?;
+    let len = output.trim_end_matches(['\n', '\r']).len();
+    output.truncate(len);
+    Ok(output)
+}
+
+/// Builds the shell command, in a process group of its own so that a
+/// timeout can kill everything it started.
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    use std::os::unix::process::CommandExt;
+
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command).process_group(0);
+    shell
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+/// Kills `child` and every process in its process group.
+#[cfg(not(windows))]
+fn kill_process_group(child: &mut Child) {
+    // The shell leads its own group, whose id is its process id. The id
+    // stays reserved while the shell is unreaped or any process of the
+    // group still runs, so no unrelated process can be hit.
+    let group = -(child.id() as libc::pid_t);
+    // Safety: `kill` only sends a signal and touches no memory
+    unsafe {
+        libc::kill(group, libc::SIGKILL);
+    }
+}
+
+/// Kills `child` and the processes it started.
+#[cfg(windows)]
+fn kill_process_group(child: &mut Child) {
+    let killed = Command::new("taskkill")
+        .args(["/F", "/T", "/PID", &child.id().to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if !killed {
+        let _ = child.kill();
+    }
+}
+
+/// Reads `pipe` to the end on a background thread, keeping at most one byte
+/// more than `max`.
+///
+/// The pipe is closed as soon as the output is known to be too long, which
+/// stops a command writing without end.
+fn drain(pipe: Option<impl Read + Send + 'static>, max: usize) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(pipe) = pipe {
+            let _ = pipe.take(max as u64 + 1).read_to_end(&mut buffer);
+        }
+        buffer
+    })
+}
+
+/// Polls `done` with growing pauses until it returns `true`, or returns
+/// `false` once `deadline` has passed.
+fn poll_until(deadline: Instant, mut done: impl FnMut() -> io::Result<bool>) -> io::Result<bool> {
+    let mut interval = Duration::from_micros(100);
+    loop {
+        if done()? {
+            return Ok(true);
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(false);
+        }
+        thread::sleep(interval.min(deadline - now));
+        interval = (interval * 2).min(MAX_POLL_INTERVAL);
+    }
+}
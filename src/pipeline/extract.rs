@@ -0,0 +1,100 @@
+//! Patterns for the `extract_emails`, `extract_urls` and `extract_uuids`
+//! operations, and the delimiter matching of `extract_between`.
+//!
+//! The patterns aim at what people write in running text and logs rather
+//! than at every address the standards allow: quoted local parts, IP address
+//! domains and URLs without a scheme other than `www.` are not found.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+static EMAIL: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)\b[a-z0-9](?:[a-z0-9._%+-]*[a-z0-9_%+-])?@(?:[a-z0-9](?:[a-z0-9-]{0,61}[a-z0-9])?\.)+[a-z]{2,63}\b",
+    )
+    .expect("email pattern is valid")
+});
+
+static URL: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)\b(?:(?:https?|ftp)://|www\.)[^\s<>"'`]+"#).expect("URL pattern is valid")
+});
+
+static UUID: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\b[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}\b")
+        .expect("UUID pattern is valid")
+});
+
+/// Returns the email addresses in `text`, in order.
+pub(crate) fn emails(text: &str) -> impl Iterator<Item = &str> {
+    EMAIL.find_iter(text).map(|m| m.as_str())
+}
+
+/// Returns the `http`, `https`, `ftp` and `www.` URLs in `text`, in order.
+///
+/// Punctuation that ends a sentence is not part of a URL, and neither is a
+/// closing bracket without a matching opening one inside the URL, so
+/// `(see https://a.org/x).` gives `https://a.org/x`.
+pub(crate) fn urls(text: &str) -> impl Iterator<Item = &str> {
+    URL.find_iter(text).map(|m| trim_url(m.as_str()))
+}
+
+/// Returns the UUIDs in `text`, in order, as written.
+pub(crate) fn uuids(text: &str) -> impl Iterator<Item = &str> {
+    UUID.find_iter(text).map(|m| m.as_str())
+}
+
+/// Returns the contents of the outermost groups enclosed by balanced `open`
+/// and `close` delimiters in `text`, in order.
+///
+/// Nested groups stay part of the group around them, so `f(a, g(b))` with
+/// `(` and `)` gives `a, g(b)`. When both delimiters are the same, as with
+/// quotes, groups cannot nest. A backslash escapes the character after it,
+/// a group left open at the end of the text is not returned, and closing
+/// delimiters without an opening one are ignored.
+pub(crate) fn between<'a>(text: &'a str, open: &str, close: &str) -> Vec<&'a str> {
+    let mut groups = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut i = 0;
+    while let Some(c) = text[i..].chars().next() {
+        let rest = &text[i..];
+        if c == '\\' {
+            i += 1 + rest[1..].chars().next().map_or(0, char::len_utf8);
+        } else if depth > 0 && rest.starts_with(close) {
+            depth -= 1;
+            if depth == 0 {
+                groups.push(&text[start..i]);
+            }
+            i += close.len();
+        } else if rest.starts_with(open) {
+            if depth == 0 {
+                start = i + open.len();
+            }
+            depth += 1;
+            i += open.len();
+        } else {
+            i += c.len_utf8();
+        }
+    }
+    groups
+}
+
+fn trim_url(mut url: &str) -> &str {
+    loop {
+        let Some(last) = url.chars().next_back() else {
+            return url;
+        };
+        let unbalanced = |open: char| url.matches(open).count() < url.matches(last).count();
+        let trailing = match last {
+            '.' | ',' | ';' | ':' | '!' | '?' => true,
+            ')' => unbalanced('('),
+            ']' => unbalanced('['),
+            '}' => unbalanced('{'),
+            _ => false,
+        };
+        if !trailing {
+            return url;
+        }
+        url = &url[..url.len() - 1];
+    }
+}
@@ -0,0 +1,542 @@
+//! Resource limits for evaluating untrusted templates.
+//!
+//! Services that format user-supplied templates can attach [`Limits`] to a
+//! [`Template`](crate::Template) to bound the memory spent compiling regular
+//! expressions and the wall time spent in a single format call. Exceeding a
+//! limit aborts formatting with an error starting with `Limit exceeded:`.
+//!
+//! The regex engine itself runs in linear time, so patterns such as `(a+)+$`
+//! cannot backtrack catastrophically; the remaining risks are huge compiled
+//! programs and large inputs, which these limits cover.
+//!
+//! A [`SafetyProfile`] bundles these runtime limits with static checks that
+//! [`Template::parse_untrusted`](crate::Template::parse_untrusted) applies
+//! before a template is ever executed.
+//!
+//! [`ParserConfig`] bounds the parser itself: template length, brace nesting
+//! and operation count.
+
+use regex::{Regex, RegexBuilder};
+use std::io::Read;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use super::{StringOp, Value, replace_regex_source};
+
+/// Prefix of every error reported when a [`Limits`] bound is exceeded.
+pub(crate) const LIMIT_ERROR_PREFIX: &str = "Limit exceeded:";
+
+/// Returns `true` if `message` reports an exceeded limit, which operations
+/// catching errors such as `try` must let through.
+pub(crate) fn is_limit_error(message: &str) -> bool {
+    message.starts_with(LIMIT_ERROR_PREFIX)
+}
+
+/// Size cap of files read by operations when [`Limits::max_file_size`] is unset.
+const DEFAULT_MAX_FILE_SIZE: usize = 10 << 20;
+
+/// Time allowed to each `exec` command when [`Limits::exec_timeout`] is unset.
+const DEFAULT_EXEC_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Size cap of the output of `exec` commands when [`Limits::max_output_len`] is unset.
+const DEFAULT_MAX_EXEC_OUTPUT: usize = 64 << 20;
+
+/// Resource limits applied while formatting a template.
+///
+/// Every limit is optional; `None` keeps the default behaviour. Use
+/// struct update syntax to set only the limits you need.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use string_pipeline::{Limits, Template};
+///
+/// let template = Template::parse("{replace:s/a+/b/g}")
+///     .unwrap()
+///     .with_limits(Limits {
+///         max_regex_size: Some(64 * 1024),
+///         step_timeout: Some(Duration::from_millis(50)),
+///         ..Limits::default()
+///     });
+///
+/// assert_eq!(template.format("caaat").unwrap(), "cbt");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum size in bytes of a compiled regular expression.
+    ///
+    /// Patterns that compile to a larger program are rejected instead of
+    /// being built. The regex crate's own default is 10 MiB.
+    pub max_regex_size: Option<usize>,
+    /// Maximum size in bytes of the lazy DFA cache of each regular expression.
+    ///
+    /// Searches that outgrow the cache fall back to slower engines rather
+    /// than failing.
+    pub max_dfa_size: Option<usize>,
+    /// Maximum wall time of one format call.
+    ///
+    /// Checked before every operation and between list items, so a single
+    /// operation on a huge input can overrun it by the time that operation
+    /// takes.
+    pub step_timeout: Option<Duration>,
+    /// Maximum number of items a single `split` may produce.
+    pub max_split_items: Option<usize>,
+    /// Maximum length in bytes of the output and of every intermediate value.
+    ///
+    /// Lists count the total length of their items. Also caps the output of
+    /// each `exec` command, which is 64 MiB when unset.
+    pub max_output_len: Option<usize>,
+    /// Maximum size in bytes of a file read by `read_file` or a `@FILE` list.
+    ///
+    /// Defaults to 10 MiB when unset. Reading files also requires
+    /// [`Template::with_fs_access`](crate::Template::with_fs_access).
+    pub max_file_size: Option<usize>,
+    /// Maximum wall time of each command run by `exec`.
+    ///
+    /// Defaults to 10 seconds when unset, and never exceeds the time left
+    /// under [`step_timeout`](Limits::step_timeout). Commands that run longer
+    /// are killed. Running commands also requires
+    /// [`Template::with_exec_access`](crate::Template::with_exec_access).
+    pub exec_timeout: Option<Duration>,
+}
+
+impl Limits {
+    fn limits_regex(&self) -> bool {
+        self.max_regex_size.is_some() || self.max_dfa_size.is_some()
+    }
+}
+
+/// Restrictions for templates written by untrusted users.
+///
+/// Used by [`Template::parse_untrusted`](crate::Template::parse_untrusted),
+/// which rejects templates exceeding the static caps when parsing and
+/// enforces the remaining ones as [`Limits`] on every format call. The
+/// default profile suits interactive use: small templates, inputs up to
+/// about a megabyte and a one second budget per call.
+///
+/// # Examples
+///
+/// ```rust
+/// use string_pipeline::{SafetyProfile, Template};
+///
+/// let profile = SafetyProfile {
+///     max_operations: 4,
+///     ..SafetyProfile::default()
+/// };
+///
+/// assert!(Template::parse_untrusted("{split:,:..|map:{upper}|join:-}", &profile).is_ok());
+///
+/// let err = Template::parse_untrusted("{trim|upper|append:!|prepend:>|pad:9}", &profile)
+///     .unwrap_err();
+/// assert!(err.starts_with("Limit exceeded:"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SafetyProfile {
+    /// Maximum number of operations in the whole template, counting the
+    /// operations inside `map`.
+    pub max_operations: usize,
+    /// Maximum nesting depth of `map` and `each`; `0` forbids both.
+    pub max_map_depth: usize,
+    /// Maximum number of items a single `split` may produce.
+    pub max_split_items: usize,
+    /// Maximum length in bytes of the output and of every intermediate value.
+    ///
+    /// Also caps `pad` widths when parsing.
+    pub max_output_len: usize,
+    /// Maximum size in bytes of each compiled regular expression.
+    ///
+    /// Every pattern is compiled when parsing, so oversized patterns are
+    /// rejected before any input is seen.
+    pub max_regex_size: usize,
+    /// Maximum wall time of one format call.
+    pub step_timeout: Duration,
+}
+
+impl Default for SafetyProfile {
+    fn default() -> Self {
+        Self {
+            max_operations: 64,
+            max_map_depth: 1,
+            max_split_items: 100_000,
+            max_output_len: 1 << 20,
+            max_regex_size: 1 << 20,
+            step_timeout: Duration::from_secs(1),
+        }
+    }
+}
+
+impl SafetyProfile {
+    /// Returns the limits enforced on every format call.
+    pub fn limits(&self) -> Limits {
+        Limits {
+            max_regex_size: Some(self.max_regex_size),
+            max_dfa_size: None,
+            step_timeout: Some(self.step_timeout),
+            max_split_items: Some(self.max_split_items),
+            max_output_len: Some(self.max_output_len),
+            max_file_size: None,
+            exec_timeout: None,
+        }
+    }
+
+    /// Checks the static caps against the operations of every template section.
+    pub(crate) fn check<'a>(
+        &self,
+        sections: impl IntoIterator<Item = &'a [StringOp]>,
+    ) -> Result<(), String> {
+        let guard = ExecGuard::start(&self.limits());
+        let mut operations = 0;
+        for ops in sections {
+            self.check_ops(ops, 0, &mut operations, &guard)?;
+        }
+        Ok(())
+    }
+
+    fn check_ops(
+        &self,
+        ops: &[StringOp],
+        depth: usize,
+        operations: &mut usize,
+        guard: &ExecGuard,
+    ) -> Result<(), String> {
+        for op in ops {
+            *operations += 1;
+            if *operations > self.max_operations {
+                return Err(format!(
+                    "{LIMIT_ERROR_PREFIX} template has more than {} operations",
+                    self.max_operations
+                ));
+            }
+            if op.reads_files() {
+                return Err(format!(
+                    "{LIMIT_ERROR_PREFIX} reading files is not allowed (`{}`)",
+                    op.keyword()
+                ));
+            }
+            if op.runs_commands() {
+                return Err(format!(
+                    "{LIMIT_ERROR_PREFIX} running commands is not allowed (`{}`)",
+                    op.keyword()
+                ));
+            }
+
+            match op {
+                StringOp::Map { operations: inner }
+                | StringOp::MapFallible { operations: inner } => {
+                    if depth + 1 > self.max_map_depth {
+                        return Err(format!(
+                            "{LIMIT_ERROR_PREFIX} `map` nested deeper than {} level(s)",
+                            self.max_map_depth
+                        ));
+                    }
+                    self.check_ops(inner, depth + 1, operations, guard)?;
+                }
+                StringOp::Each { body } => {
+                    if depth + 1 > self.max_map_depth {
+                        return Err(format!(
+                            "{LIMIT_ERROR_PREFIX} `each` nested deeper than {} level(s)",
+                            self.max_map_depth
+                        ));
+                    }
+                    for (_, inner) in body.get_template_sections() {
+                        self.check_ops(inner, depth + 1, operations, guard)?;
+                    }
+                }
+                StringOp::SortBy {
+                    operations: inner, ..
+                }
+                | StringOp::UniqueBy { operations: inner }
+                | StringOp::Try {
+                    operations: inner, ..
+                } => {
+                    self.check_ops(inner, depth, operations, guard)?;
+                }
+                StringOp::FilterBy {
+                    operations: inner,
+                    pattern,
+                } => {
+                    if let Some(pattern) = pattern {
+                        guard.compile_regex(pattern)?;
+                    }
+                    self.check_ops(inner, depth, operations, guard)?;
+                }
+                StringOp::Pad { width, .. } if *width > self.max_output_len => {
+                    return Err(format!(
+                        "{LIMIT_ERROR_PREFIX} pad width {width} is longer than the output limit of {} bytes",
+                        self.max_output_len
+                    ));
+                }
+                StringOp::Replace { rules } => {
+                    for rule in rules {
+                        guard.compile_regex(&replace_regex_source(&rule.pattern, &rule.flags))?;
+                    }
+                }
+                StringOp::Filter { pattern }
+                | StringOp::FilterNot { pattern }
+                | StringOp::TrimRegex { pattern, .. }
+                | StringOp::Highlight { pattern, .. }
+                | StringOp::RegexExtract { pattern, .. } => {
+                    guard.compile_regex(pattern)?;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Options of the template parser.
+///
+/// Selects the template syntax and bounds the templates accepted. Parsing
+/// time and memory grow with the size of a template, so callers that parse
+/// templates from untrusted sources can cap them with
+/// [`Template::parse_with_config`](crate::Template::parse_with_config).
+/// Templates over a bound are rejected with an error starting with
+/// `Limit exceeded:` before they are fully parsed. The other parse
+/// functions use [`ParserConfig::default`], which only rejects templates far
+/// larger than any written by hand.
+///
+/// # Examples
+///
+/// ```rust
+/// use string_pipeline::{ParserConfig, Template};
+///
+/// let config = ParserConfig {
+///     max_operations: 2,
+///     ..ParserConfig::default()
+/// };
+///
+/// assert!(Template::parse_with_config("{trim|upper}", &config).is_ok());
+///
+/// let err = Template::parse_with_config("{trim|upper|append:!}", &config).unwrap_err();
+/// assert!(err.starts_with("Limit exceeded:"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParserConfig {
+    /// Maximum length in bytes of the template.
+    pub max_template_len: usize,
+    /// Maximum nesting depth of braces, counting the braces of each
+    /// template section and of `${...}` literals. With custom
+    /// [`delimiters`](ParserConfig::delimiters), counts nested delimiters.
+    pub max_nesting_depth: usize,
+    /// Maximum number of operations in the whole template, counting the
+    /// operations inside `map` and the other sub-pipelines.
+    pub max_operations: usize,
+    /// Parse every template section in verbose syntax, as if it started
+    /// with `{!#`.
+    ///
+    /// Verbose sections ignore unescaped whitespace, and `#` starts a
+    /// comment that runs to the end of the line. Write `\ ` and `\#` for a
+    /// literal space or `#`.
+    pub verbose_syntax: bool,
+    /// Opening and closing delimiters of template sections, in place of
+    /// `{` and `}`.
+    ///
+    /// Braces outside of sections are then literal text. Operations inside
+    /// a section still use braces, as in `<<split:,:..|map:{upper}>>`.
+    pub delimiters: Option<(String, String)>,
+}
+
+impl Default for ParserConfig {
+    fn default() -> Self {
+        Self {
+            max_template_len: 1 << 20,
+            max_nesting_depth: 64,
+            max_operations: 1024,
+            verbose_syntax: false,
+            delimiters: None,
+        }
+    }
+}
+
+impl ParserConfig {
+    /// Returns the section delimiters if they differ from braces.
+    pub(crate) fn custom_delimiters(&self) -> Option<(&str, &str)> {
+        self.delimiters
+            .as_ref()
+            .map(|(open, close)| (open.as_str(), close.as_str()))
+            .filter(|&delimiters| delimiters != ("{", "}"))
+    }
+
+    /// Fails if the template is longer than allowed.
+    pub(crate) fn check_len(&self, template: &str) -> Result<(), String> {
+        if template.len() > self.max_template_len {
+            return Err(format!(
+                "{LIMIT_ERROR_PREFIX} template is longer than {} bytes",
+                self.max_template_len
+            ));
+        }
+        Ok(())
+    }
+
+    /// Fails if braces are nested deeper than allowed.
+    #[inline]
+    pub(crate) fn check_depth(&self, depth: usize) -> Result<(), String> {
+        if depth > self.max_nesting_depth {
+            return Err(format!(
+                "{LIMIT_ERROR_PREFIX} template braces nest deeper than {} level(s)",
+                self.max_nesting_depth
+            ));
+        }
+        Ok(())
+    }
+
+    /// Adds the operations of a parsed section to `operations` and fails if
+    /// the template now has more than allowed.
+    pub(crate) fn check_operations(
+        &self,
+        ops: &[StringOp],
+        operations: &mut usize,
+    ) -> Result<(), String> {
+        *operations += ops.iter().map(StringOp::operation_count).sum::<usize>();
+        if *operations > self.max_operations {
+            return Err(format!(
+                "{LIMIT_ERROR_PREFIX} template has more than {} operations",
+                self.max_operations
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Limits enforced during one format call.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ExecGuard {
+    limits: Limits,
+    deadline: Option<Instant>,
+}
+
+impl ExecGuard {
+    /// Starts enforcing `limits`; the timeout runs from now.
+    pub(crate) fn start(limits: &Limits) -> Self {
+        Self {
+            limits: *limits,
+            deadline: limits.step_timeout.map(|timeout| Instant::now() + timeout),
+        }
+    }
+
+    /// Fails once the format call has run out of time.
+    #[inline]
+    pub(crate) fn check_deadline(&self) -> Result<(), String> {
+        match (self.deadline, self.limits.step_timeout) {
+            (Some(deadline), Some(timeout)) if Instant::now() >= deadline => Err(format!(
+                "{LIMIT_ERROR_PREFIX} formatting took longer than {timeout:?}"
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns `true` if intermediate values must be inspected, which the
+    /// fused fast paths never materialize.
+    pub(crate) fn checks_values(&self) -> bool {
+        self.limits.max_split_items.is_some() || self.limits.max_output_len.is_some()
+    }
+
+    /// Fails if a `split` produced more items than allowed.
+    #[inline]
+    pub(crate) fn check_split(&self, items: usize) -> Result<(), String> {
+        match self.limits.max_split_items {
+            Some(max) if items > max => Err(format!(
+                "{LIMIT_ERROR_PREFIX} split produced {items} items, more than the allowed {max}"
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails if an intermediate value is longer than the output limit.
+    #[inline]
+    pub(crate) fn check_value(&self, value: &Value<'_>) -> Result<(), String> {
+        let Some(max) = self.limits.max_output_len else {
+            return Ok(());
+        };
+        let len = match value {
+            Value::Str(s) => s.len(),
+            Value::List(list) => list.iter().map(|item| item.len()).sum(),
+        };
+        if len > max {
+            return Err(format!(
+                "{LIMIT_ERROR_PREFIX} intermediate value of {len} bytes is longer than the allowed {max}"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Fails if the rendered output is longer than the output limit.
+    pub(crate) fn check_output(&self, output: &str) -> Result<(), String> {
+        match self.limits.max_output_len {
+            Some(max) if output.len() > max => Err(format!(
+                "{LIMIT_ERROR_PREFIX} output of {} bytes is longer than the allowed {max}",
+                output.len()
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the regex size limits as a cache key, if any are set.
+    pub(crate) fn regex_limits(&self) -> Option<(Option<usize>, Option<usize>)> {
+        self.limits
+            .limits_regex()
+            .then_some((self.limits.max_regex_size, self.limits.max_dfa_size))
+    }
+
+    /// Compiles `pattern` within the configured regex size limits.
+    pub(crate) fn compile_regex(&self, pattern: &str) -> Result<Regex, String> {
+        let mut builder = RegexBuilder::new(pattern);
+        if let Some(size) = self.limits.max_regex_size {
+            builder.size_limit(size);
+        }
+        if let Some(size) = self.limits.max_dfa_size {
+            builder.dfa_size_limit(size);
+        }
+        builder.build().map_err(|e| match e {
+            regex::Error::CompiledTooBig(limit) => format!(
+                "{LIMIT_ERROR_PREFIX} regex `{pattern}` compiles to more than {limit} bytes"
+            ),
+            e => format!("Invalid regex: {e}"),
+        })
+    }
+}
+
+/// Reads a UTF-8 file for an operation, within the file size limit of the
+/// running format call.
+pub(crate) fn read_limited_file(path: &Path, guard: Option<&ExecGuard>) -> Result<String, String> {
+    let max = guard
+        .and_then(|guard| guard.limits.max_file_size)
+        .unwrap_or(DEFAULT_MAX_FILE_SIZE);
+    let failed = |e: std::io::Error| format!("Failed to read '{}': {e}", path.display());
+
+    let mut content = String::new();
+    std::fs::File::open(path)
+        .map_err(failed)?
+        .take(max as u64 + 1)
+        .read_to_string(&mut content)
+        .map_err(failed)?;
+    if content.len() > max {
+        return Err(format!(
+            "{LIMIT_ERROR_PREFIX} file '{}' is larger than {max} bytes",
+            path.display()
+        ));
+    }
+    Ok(content)
+}
+
+/// Returns how many bytes an `exec` command may write to stdout or stderr
+/// in a format call.
+pub(crate) fn exec_output_limit(guard: Option<&ExecGuard>) -> usize {
+    guard
+        .and_then(|guard| guard.limits.max_output_len)
+        .unwrap_or(DEFAULT_MAX_EXEC_OUTPUT)
+}
+
+/// Returns the time an `exec` command may run for in a format call.
+pub(crate) fn exec_timeout(guard: Option<&ExecGuard>) -> Duration {
+    let Some(guard) = guard else {
+        return DEFAULT_EXEC_TIMEOUT;
+    };
+    let timeout = guard.limits.exec_timeout.unwrap_or(DEFAULT_EXEC_TIMEOUT);
+    match guard.deadline {
+        Some(deadline) => timeout.min(deadline.saturating_duration_since(Instant::now())),
+        None => timeout,
+    }
+}
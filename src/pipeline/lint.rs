@@ -0,0 +1,350 @@
+//! Static lints for template pipelines.
+//!
+//! Unlike type errors, the patterns reported here never make formatting fail;
+//! they flag operations that do nothing, or whose effect a later operation
+//! throws away, which usually hints at a mistake in the template. See
+//! [`Template::lint`](super::Template::lint).
+
+use std::fmt;
+
+use super::typecheck::{ValueType, step_type};
+use super::{RangeSpec, SortDirection, StringOp};
+
+/// Kind of issue reported by [`Template::lint`](crate::Template::lint).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LintKind {
+    /// The operation repeats the previous one and has no further effect,
+    /// as in `upper|upper` or `sort|unique|sort`.
+    Redundant,
+    /// A later operation discards the effect of this one, as in
+    /// `upper|lower` or `shuffle|sort`.
+    Overridden,
+    /// The operation undoes the previous one, as in `reverse|reverse`.
+    Cancelling,
+    /// The operation never changes its input, as in `append:` or `slice:..`.
+    NoOp,
+    /// A `map` whose operations never change the items.
+    NoOpMap,
+    /// A `join` applied to a string, which passes it through unchanged.
+    UselessJoin,
+    /// A `filter` or `filter_not` after `join`, which tests the whole joined
+    /// string instead of individual items.
+    FilterAfterJoin,
+}
+
+/// A suspicious operation found by [`Template::lint`](crate::Template::lint).
+///
+/// Displays as `template section 1, operation 2 (`join`): MESSAGE`, where
+/// nested operations are numbered through their enclosing operations, such
+/// as `operation 2.1` for the first operation inside a `map` in second place.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintWarning {
+    /// Kind of issue.
+    pub kind: LintKind,
+    /// Position of the section among template sections only, from 0.
+    pub template_position: usize,
+    /// Positions of the flagged operation, from 0, outermost first: its
+    /// position in the section, then inside each enclosing sub-pipeline.
+    pub operation_path: Vec<usize>,
+    /// Keyword of the flagged operation, such as `join`.
+    pub operation: &'static str,
+    /// Description of the issue.
+    pub message: String,
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "template section {}, operation ",
+            self.template_position + 1
+        )?;
+        for (i, position) in self.operation_path.iter().enumerate() {
+            if i > 0 {
+                f.write_str(".")?;
+            }
+            write!(f, "{}", position + 1)?;
+        }
+        write!(f, " (`{}`): {}", self.operation, self.message)
+    }
+}
+
+/// Lints one template section, whose pipeline receives a string.
+pub(crate) fn lint_section(template_position: usize, ops: &[StringOp]) -> Vec<LintWarning> {
+    let mut linter = Linter {
+        template_position,
+        path: Vec::new(),
+        warnings: Vec::new(),
+    };
+    linter.lint_ops(ops, ValueType::Str);
+    linter.warnings
+}
+
+struct Linter {
+    template_position: usize,
+    path: Vec<usize>,
+    warnings: Vec<LintWarning>,
+}
+
+impl Linter {
+    fn warn(&mut self, kind: LintKind, index: usize, op: &StringOp, message: String) {
+        let mut operation_path = self.path.clone();
+        operation_path.push(index);
+        self.warnings.push(LintWarning {
+            kind,
+            template_position: self.template_position,
+            operation_path,
+            operation: op.keyword(),
+            message,
+        });
+    }
+
+    fn lint_ops(&mut self, ops: &[StringOp], input: ValueType) {
+        let mut current = input;
+        // Whether the value is a string holding a whole joined list.
+        let mut joined = false;
+
+        for (i, op) in ops.iter().enumerate() {
+            let previous = i.checked_sub(1).map(|p| &ops[p]);
+
+            if let Some(previous) = previous {
+                self.lint_pair(i, previous, op);
+            }
+            if let StringOp::Sort { direction } = op
+                && let Some(sorted) = sorted_before(&ops[..i])
+                && same_direction(*sorted, *direction)
+            {
+                self.warn(
+                    LintKind::Redundant,
+                    i,
+                    op,
+                    "the list is already sorted in this order".to_string(),
+                );
+            }
+
+            let no_op_map =
+                matches!(op, StringOp::Map { operations } if operations.iter().all(is_no_op));
+            match op {
+                StringOp::Join { .. } if current == ValueType::Str => {
+                    let message = match previous {
+                        Some(
+                            StringOp::Split {
+                                range: RangeSpec::Index(_),
+                                ..
+                            }
+                            | StringOp::SplitAny {
+                                range: RangeSpec::Index(_),
+                                ..
+                            },
+                        ) => {
+                            "a split with a single index already yields a string; `join` has no effect"
+                        }
+                        _ => "the input is a string, which `join` passes through unchanged",
+                    };
+                    self.warn(LintKind::UselessJoin, i, op, message.to_string());
+                }
+                StringOp::Filter { .. }
+                | StringOp::FilterNot { .. }
+                | StringOp::FilterPrefix { .. }
+                | StringOp::FilterSuffix { .. }
+                | StringOp::FilterContains { .. }
+                | StringOp::FilterValidUtf8
+                | StringOp::FilterLuhn
+                | StringOp::FilterChecksum { .. }
+                    if joined =>
+                {
+                    self.warn(
+                        LintKind::FilterAfterJoin,
+                        i,
+                        op,
+                        format!(
+                            "`{}` after `join` tests the whole joined string; filter the list before joining",
+                            op.keyword()
+                        ),
+                    );
+                }
+                StringOp::Map { .. } if no_op_map => {
+                    self.warn(
+                        LintKind::NoOpMap,
+                        i,
+                        op,
+                        "the operations inside `map` never change the items".to_string(),
+                    );
+                }
+                _ if is_no_op(op) => {
+                    self.warn(
+                        LintKind::NoOp,
+                        i,
+                        op,
+                        format!("`{}` with these arguments has no effect", op.keyword()),
+                    );
+                }
+                _ => {}
+            }
+
+            if let StringOp::Map { operations }
+            | StringOp::MapFallible { operations }
+            | StringOp::Try { operations, .. }
+            | StringOp::SortBy { operations, .. }
+            | StringOp::UniqueBy { operations }
+            | StringOp::FilterBy { operations, .. } = op
+                && !no_op_map
+            {
+                self.path.push(i);
+                self.lint_ops(operations, ValueType::Str);
+                self.path.pop();
+            }
+
+            // Lints assume a well-typed pipeline; stop at the first type error.
+            let Some(next) = step_type(op, current) else {
+                return;
+            };
+            joined = match op {
+                StringOp::Join { .. } => current == ValueType::List,
+                _ => joined && next == ValueType::Str && preserves_join(op),
+            };
+            current = next;
+        }
+    }
+
+    /// Checks an operation against the one right before it.
+    fn lint_pair(&mut self, i: usize, previous: &StringOp, op: &StringOp) {
+        let (kind, index, flagged, message) = match (previous, op) {
+            // Only an exact repeat is redundant, `with_ext:a|with_ext:b` is not
+            _ if previous == op && op.info().idempotent => (
+                LintKind::Redundant,
+                i,
+                op,
+                format!("repeating `{}` has no effect", op.keyword()),
+            ),
+            (
+                StringOp::Upper { locale: a }
+                | StringOp::Capitalize { locale: a, .. }
+                | StringOp::SwapCase { locale: a },
+                StringOp::Lower { locale: b },
+            )
+            | (
+                StringOp::Lower { locale: a }
+                | StringOp::Capitalize { locale: a, .. }
+                | StringOp::SwapCase { locale: a },
+                StringOp::Upper { locale: b },
+            ) if a == b => (
+                LintKind::Overridden,
+                i - 1,
+                previous,
+                format!(
+                    "the following `{}` discards the case conversion",
+                    op.keyword()
+                ),
+            ),
+            (
+                StringOp::Sort { .. }
+                | StringOp::SortBy { .. }
+                | StringOp::Shuffle { .. }
+                | StringOp::Reverse,
+                StringOp::Sort { .. } | StringOp::Shuffle { .. },
+            ) => {
+                // `sort|sort` with the same direction is reported as redundant.
+                if let (StringOp::Sort { direction: a }, StringOp::Sort { direction: b }) =
+                    (previous, op)
+                    && same_direction(*a, *b)
+                {
+                    return;
+                }
+                (
+                    LintKind::Overridden,
+                    i - 1,
+                    previous,
+                    format!("the following `{}` discards this order", op.keyword()),
+                )
+            }
+            (StringOp::Reverse, StringOp::Reverse) => (
+                LintKind::Cancelling,
+                i,
+                op,
+                "a second `reverse` restores the original order".to_string(),
+            ),
+            _ => return,
+        };
+        self.warn(kind, index, flagged, message);
+    }
+}
+
+/// Returns the direction of an earlier `sort` whose order is still intact.
+///
+/// Walks back over operations that keep the relative order of the items
+/// they keep, stopping at anything else.
+fn sorted_before(ops: &[StringOp]) -> Option<&SortDirection> {
+    for op in ops.iter().rev() {
+        match op {
+            StringOp::Sort { direction } => return Some(direction),
+            StringOp::Unique
+            | StringOp::UniqueBy { .. }
+            | StringOp::Duplicates
+            | StringOp::RemoveEmpty
+            | StringOp::Filter { .. }
+            | StringOp::FilterNot { .. }
+            | StringOp::FilterPrefix { .. }
+            | StringOp::FilterSuffix { .. }
+            | StringOp::FilterContains { .. }
+            | StringOp::FilterValidUtf8
+            | StringOp::FilterLuhn
+            | StringOp::FilterChecksum { .. }
+            | StringOp::FilterBy { .. }
+            | StringOp::Intersect { .. }
+            | StringOp::Diff { .. }
+            | StringOp::Sample { .. } => {}
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// Returns `true` if the string left by a `join` still contains the whole
+/// joined list after `op`.
+fn preserves_join(op: &StringOp) -> bool {
+    matches!(
+        op,
+        StringOp::Upper { .. }
+            | StringOp::Lower { .. }
+            | StringOp::Capitalize { .. }
+            | StringOp::SwapCase { .. }
+            | StringOp::Trim { .. }
+            | StringOp::Append { .. }
+            | StringOp::Prepend { .. }
+            | StringOp::Surround { .. }
+            | StringOp::StripAnsi
+            | StringOp::Filter { .. }
+            | StringOp::FilterNot { .. }
+            | StringOp::FilterPrefix { .. }
+            | StringOp::FilterSuffix { .. }
+            | StringOp::FilterContains { .. }
+            | StringOp::FilterValidUtf8
+            | StringOp::FilterLuhn
+            | StringOp::FilterChecksum { .. }
+    )
+}
+
+fn same_direction(a: SortDirection, b: SortDirection) -> bool {
+    matches!(
+        (a, b),
+        (SortDirection::Asc, SortDirection::Asc) | (SortDirection::Desc, SortDirection::Desc)
+    )
+}
+
+/// Returns `true` if `op` never changes its input.
+fn is_no_op(op: &StringOp) -> bool {
+    match op {
+        StringOp::Append { suffix: affix }
+        | StringOp::Prepend { prefix: affix }
+        | StringOp::TrimPrefix { prefix: affix }
+        | StringOp::TrimSuffix { suffix: affix } => affix.is_empty(),
+        StringOp::Surround { prefix, suffix } => prefix.is_empty() && suffix.is_empty(),
+        StringOp::ReplaceLiteral { from, to, .. } => from == to,
+        StringOp::Substring { range } | StringOp::Slice { range } | StringOp::Clamp { range } => {
+            matches!(range, RangeSpec::Range(None | Some(0), None, _))
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,154 @@
+//! Memo of final formatted strings for [`Template::with_memo`].
+//!
+//! The memo maps inputs to the output of the whole template and evicts the
+//! least recently used entry when full. Recency is a counter bumped on every
+//! lookup, with an ordered index from counter values to inputs, so lookups
+//! and evictions take logarithmic time.
+//!
+//! [`Template::with_memo`]: crate::Template::with_memo
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+/// Statistics of a template's memo, returned by
+/// [`Template::memo_stats`](crate::Template::memo_stats).
+///
+/// # Examples
+///
+/// ```rust
+/// use string_pipeline::Template;
+///
+/// let template = Template::parse("{upper}").unwrap().with_memo(2);
+/// for input in ["a", "b", "a", "c", "b"] {
+///     template.format(input).unwrap();
+/// }
+///
+/// let stats = template.memo_stats().unwrap();
+/// assert_eq!((stats.hits, stats.misses, stats.evictions), (1, 4, 2));
+/// assert_eq!((stats.entries, stats.capacity), (2, 2));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MemoStats {
+    /// Number of memoized outputs.
+    pub entries: usize,
+    /// Maximum number of memoized outputs.
+    pub capacity: usize,
+    /// Format calls answered from the memo.
+    pub hits: u64,
+    /// Format calls that ran the template.
+    pub misses: u64,
+    /// Outputs dropped to make room for newer ones.
+    pub evictions: u64,
+}
+
+impl fmt::Display for MemoStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "memo: {}/{} entries, {} hits, {} misses, {} evictions",
+            self.entries, self.capacity, self.hits, self.misses, self.evictions
+        )
+    }
+}
+
+#[derive(Default)]
+struct MemoEntries {
+    /// Output and last use of each memoized input.
+    outputs: HashMap<Arc<str>, (String, u64)>,
+    /// Memoized inputs by last use, oldest first.
+    recency: BTreeMap<u64, Arc<str>>,
+    clock: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+/// Shared memo attached to a template.
+///
+/// Clones share the same entries.
+#[derive(Clone)]
+pub(crate) struct Memo {
+    capacity: usize,
+    entries: Arc<Mutex<MemoEntries>>,
+}
+
+impl Memo {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Arc::default(),
+        }
+    }
+
+    /// Returns an empty memo of the same capacity, not shared with this one.
+    pub(crate) fn detached(&self) -> Self {
+        Self::new(self.capacity)
+    }
+
+    /// Returns the memoized output for `input`, recording a hit or a miss.
+    pub(crate) fn get(&self, input: &str) -> Option<String> {
+        let mut guard = self.entries.lock();
+        let entries = &mut *guard;
+        let Some((output, last_use)) = entries.outputs.get_mut(input) else {
+            entries.misses += 1;
+            return None;
+        };
+        entries.hits += 1;
+        entries.clock += 1;
+        let key = entries
+            .recency
+            .remove(last_use)
+            .expect("memoized inputs are in the recency index");
+        entries.recency.insert(entries.clock, key);
+        *last_use = entries.clock;
+        Some(output.clone())
+    }
+
+    /// Memoizes `output` for `input`, evicting the least recently used
+    /// entry if the memo is full.
+    pub(crate) fn insert(&self, input: &str, output: &str) {
+        let mut entries = self.entries.lock();
+        if entries.outputs.contains_key(input) {
+            return;
+        }
+        if entries.outputs.len() >= self.capacity
+            && let Some((_, oldest)) = entries.recency.pop_first()
+        {
+            entries.outputs.remove(&oldest);
+            entries.evictions += 1;
+        }
+        entries.clock += 1;
+        let clock = entries.clock;
+        let key: Arc<str> = Arc::from(input);
+        entries.recency.insert(clock, Arc::clone(&key));
+        entries.outputs.insert(key, (output.to_string(), clock));
+    }
+
+    pub(crate) fn stats(&self) -> MemoStats {
+        let entries = self.entries.lock();
+        MemoStats {
+            entries: entries.outputs.len(),
+            capacity: self.capacity,
+            hits: entries.hits,
+            misses: entries.misses,
+            evictions: entries.evictions,
+        }
+    }
+
+    /// Discards all entries and statistics.
+    pub(crate) fn clear(&self) {
+        *self.entries.lock() = MemoEntries::default();
+    }
+}
+
+impl fmt::Debug for Memo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Memo")
+            .field("capacity", &self.capacity)
+            .finish_non_exhaustive()
+    }
+}
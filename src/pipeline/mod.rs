@@ -41,22 +41,71 @@
 use regex::Regex;
 use smallvec::SmallVec;
 
+pub(crate) mod builder;
+mod cache;
+mod canonical;
+mod catalog;
+mod checksum;
+mod config;
 mod debug;
+mod exec;
+mod extract;
+mod limits;
+mod lint;
+mod memo;
+mod numeral;
 mod parser;
+mod paths;
+mod profiling;
+mod random;
+#[cfg(feature = "tokio")]
+mod stream;
+mod style;
 mod template;
+mod template_set;
+mod typecheck;
+mod url;
 
+use compact_str::CompactString;
 use dashmap::DashMap;
 use fast_strip_ansi::strip_ansi_string;
-use memchr::memchr_iter;
+use memchr::{memchr, memchr_iter, memmem};
 use once_cell::sync::Lazy;
+use std::borrow::Cow;
+use std::cell::Cell;
 use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
+use unicode_normalization::{
+    UnicodeNormalization, char::is_combining_mark, is_nfc, is_nfd, is_nfkc, is_nfkd,
+};
+use unicode_segmentation::UnicodeSegmentation;
 
 #[allow(deprecated)]
 pub use crate::pipeline::template::{
-    MultiTemplate, RichFormatResult, SectionInfo, SectionType, Template, TemplateOutput,
+    FormatContext, InputRef, LineEndings, MultiTemplate, Placeholder, RichFormatResult,
+    SectionInfo, SectionOutput, SectionType, Template, TemplateOutput, Variable,
+};
+// The builder's `Range` is re-exported from the crate root only, as this
+// module uses `std::ops::Range`
+pub use builder::{Pipeline, Section, TemplateBuilder};
+pub use cache::{CacheScope, CacheStats, cache_stats};
+pub use catalog::{
+    OperationInfo, ValueKind, operation, operations, operations_json, register_alias,
 };
-pub use debug::DebugTracer;
+use debug::json_string;
+pub use debug::{DebugFormat, DebugLevel, DebugSink, DebugTracer};
+pub(crate) use limits::{ExecGuard, exec_output_limit, exec_timeout, read_limited_file};
+pub use limits::{Limits, ParserConfig, SafetyProfile};
+pub use lint::{LintKind, LintWarning};
+pub(crate) use memo::Memo;
+pub use memo::MemoStats;
+pub use parser::{Token, TokenKind, tokenize};
+pub(crate) use profiling::Profiler;
+pub use profiling::{OperationProfile, ProfilingReport};
+pub use template_set::TemplateSet;
 
 /* ------------------------------------------------------------------------ */
 /*  Global regex / split caches                                             */
@@ -68,10 +117,22 @@ pub use debug::DebugTracer;
 /// when the same patterns are used repeatedly across operations.
 static REGEX_CACHE: Lazy<DashMap<String, Regex>> = Lazy::new(DashMap::new);
 
-/// Type alias for split cache keys combining input hash and separator.
-type SplitCacheKey = (u64, String);
+/// Type alias for limited regex cache keys: pattern, size and DFA limits.
+type LimitedRegexCacheKey = (String, Option<usize>, Option<usize>);
+
+/// Global cache for regex patterns compiled under [`Limits`].
+///
+/// Kept apart from [`REGEX_CACHE`] so that a pattern compiled with the
+/// default limits is never reused by a template with stricter ones.
+static LIMITED_REGEX_CACHE: Lazy<DashMap<LimitedRegexCacheKey, Regex>> = Lazy::new(DashMap::new);
+
+/// Type alias for split cache keys combining input hash, separator and options.
+type SplitCacheKey = (u64, String, SplitOptions);
 /// Type alias for split cache values containing the split result.
-type SplitCacheValue = Vec<String>;
+///
+/// Entries are shared with the pipeline values they produce, so a cache hit
+/// is a reference-count increment rather than a copy of every part.
+type SplitCacheValue = List;
 
 /// Global cache for string splitting operations.
 ///
@@ -123,6 +184,24 @@ fn get_interned_separator(sep: &str) -> String {
         .unwrap_or_else(|| sep.to_string())
 }
 
+/// Joins `items` with `sep`, using `last_sep` between the final two items.
+fn join_items(items: &[Item], sep: &str, last_sep: Option<&str>) -> String {
+    match (last_sep, items) {
+        (Some(last_sep), [head @ .., second_last, last]) => {
+            let mut result = String::new();
+            for item in head {
+                result.push_str(item);
+                result.push_str(sep);
+            }
+            result.push_str(second_last);
+            result.push_str(last_sep);
+            result.push_str(last);
+            result
+        }
+        _ => items.join(sep),
+    }
+}
+
 /// Fast ASCII-only whitespace trimming optimization.
 ///
 /// Provides optimized whitespace trimming for ASCII-only strings by using
@@ -175,7 +254,7 @@ fn ascii_trim(s: &str) -> Option<&str> {
 /// by avoiding Unicode grapheme cluster boundary detection.
 #[inline(always)]
 fn ascii_reverse(s: &str) -> Option<String> {
-    if s.is_ascii() {
+    if ascii_graphemes(s) {
         // For ASCII, we can safely reverse bytes
         let mut bytes: Vec<u8> = s.bytes().collect();
         bytes.reverse();
@@ -186,6 +265,527 @@ fn ascii_reverse(s: &str) -> Option<String> {
     }
 }
 
+/// Returns `true` if every byte of `s` is its own grapheme cluster.
+///
+/// Holds for ASCII text without carriage returns, since `\r\n` is a single
+/// cluster. Such strings can be indexed and reversed byte by byte.
+#[inline]
+fn ascii_graphemes(s: &str) -> bool {
+    s.is_ascii() && memchr(b'\r', s.as_bytes()).is_none()
+}
+
+/// Counts the grapheme clusters in a string.
+#[inline]
+fn grapheme_len(s: &str) -> usize {
+    if ascii_graphemes(s) {
+        s.len()
+    } else {
+        s.graphemes(true).count()
+    }
+}
+
+/// Removes combining marks after canonical decomposition, so `é` becomes `e`.
+fn deaccent_str(s: &str) -> Cow<'_, str> {
+    if s.is_ascii() {
+        Cow::Borrowed(s)
+    } else {
+        Cow::Owned(s.nfd().filter(|c| !is_combining_mark(*c)).nfc().collect())
+    }
+}
+
+/// Converts `s` to the normalization form `form`, borrowing it when it
+/// already is.
+fn normalize_str(s: &str, form: NormalForm) -> Cow<'_, str> {
+    let normalized = match form {
+        NormalForm::Nfc => is_nfc(s),
+        NormalForm::Nfd => is_nfd(s),
+        NormalForm::Nfkc => is_nfkc(s),
+        NormalForm::Nfkd => is_nfkd(s),
+    };
+    if normalized {
+        return Cow::Borrowed(s);
+    }
+    Cow::Owned(match form {
+        NormalForm::Nfc => s.nfc().collect(),
+        NormalForm::Nfd => s.nfd().collect(),
+        NormalForm::Nfkc => s.nfkc().collect(),
+        NormalForm::Nfkd => s.nfkd().collect(),
+    })
+}
+
+/// Collapses runs of `chars`, or of whitespace when `chars` is empty.
+///
+/// Whitespace runs become a single space; other runs keep their first
+/// character. The input is borrowed when nothing changes.
+fn squeeze_str<'a>(s: &'a str, chars: &str) -> Cow<'a, str> {
+    if s.is_ascii() {
+        squeeze_chars(s, s.bytes().map(char::from).enumerate(), chars)
+    } else {
+        squeeze_chars(s, s.char_indices(), chars)
+    }
+}
+
+fn squeeze_chars<'a>(
+    s: &'a str,
+    chars_with_offsets: impl Iterator<Item = (usize, char)>,
+    chars: &str,
+) -> Cow<'a, str> {
+    let whitespace = chars.is_empty();
+    let mut out: Option<String> = None;
+    let mut in_run = false;
+    for (i, c) in chars_with_offsets {
+        let member = if whitespace {
+            c.is_whitespace()
+        } else {
+            chars.contains(c)
+        };
+        let replacement = if !member {
+            Some(c)
+        } else if in_run {
+            None
+        } else if whitespace {
+            Some(' ')
+        } else {
+            Some(c)
+        };
+        in_run = member;
+        if out.is_none() && replacement != Some(c) {
+            out = Some(s[..i].to_string());
+        }
+        if let (Some(out), Some(r)) = (out.as_mut(), replacement) {
+            out.push(r);
+        }
+    }
+    out.map_or(Cow::Borrowed(s), Cow::Owned)
+}
+
+/// Transliterates to ASCII, dropping characters that have no transliteration.
+fn ascii_str(s: &str) -> Cow<'_, str> {
+    deunicode::deunicode_with_tofu_cow(s, "")
+}
+
+/// Builds a lowercase ASCII slug, joining runs of alphanumerics with `separator`.
+fn slugify_str(s: &str, separator: &str) -> String {
+    let ascii = ascii_str(s);
+    let mut out = String::with_capacity(ascii.len());
+    let mut pending = false;
+    for c in ascii.chars() {
+        if c.is_ascii_alphanumeric() {
+            if pending && !out.is_empty() {
+                out.push_str(separator);
+            }
+            pending = false;
+            out.push(c.to_ascii_lowercase());
+        } else {
+            pending = true;
+        }
+    }
+    out
+}
+
+/// Appends `count` grapheme clusters of `fill`, repeating it as needed.
+fn push_fill(out: &mut String, fill: &str, count: usize) {
+    if ascii_graphemes(fill) {
+        for &b in fill.as_bytes().iter().cycle().take(count) {
+            out.push(b as char);
+        }
+    } else {
+        out.extend(fill.graphemes(true).cycle().take(count));
+    }
+}
+
+/// Parses `key=value` pairs, honoring quoted values.
+///
+/// Pairs are separated by `pair_sep`, or by runs of whitespace when it is
+/// empty. Keys and unquoted values are trimmed, and words without `kv_sep`
+/// are skipped.
+fn kv_pairs<'a>(s: &'a str, pair_sep: &str, kv_sep: &str) -> Vec<(&'a str, Cow<'a, str>)> {
+    let at_pair_sep = |t: &str| {
+        if pair_sep.is_empty() {
+            t.starts_with(char::is_whitespace)
+        } else {
+            t.starts_with(pair_sep)
+        }
+    };
+
+    let mut pairs = Vec::new();
+    let mut rest = s;
+    while let Some(c) = rest.chars().next() {
+        if at_pair_sep(rest) {
+            let skip = if pair_sep.is_empty() {
+                c.len_utf8()
+            } else {
+                pair_sep.len()
+            };
+            rest = &rest[skip..];
+            continue;
+        }
+
+        let key_end = find_boundary(rest, |t| t.starts_with(kv_sep) || at_pair_sep(t));
+        let key = rest[..key_end].trim();
+        let Some(after_sep) = rest[key_end..].strip_prefix(kv_sep) else {
+            rest = &rest[key_end..];
+            continue;
+        };
+        rest = after_sep;
+        if !pair_sep.is_empty() {
+            rest = rest.trim_start();
+        }
+
+        let value = match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let (value, len) = unquote(&rest[1..], quote);
+                rest = &rest[1 + len..];
+                // Text between the closing quote and the next pair is dropped
+                rest = &rest[find_boundary(rest, at_pair_sep)..];
+                value
+            }
+            _ => {
+                let end = find_boundary(rest, at_pair_sep);
+                let value = rest[..end].trim();
+                rest = &rest[end..];
+                Cow::Borrowed(value)
+            }
+        };
+        if !key.is_empty() {
+            pairs.push((key, value));
+        }
+    }
+    pairs
+}
+
+/// Returns the offset of the first position in `s` where `at` holds, or the
+/// length of `s`.
+fn find_boundary(s: &str, at: impl Fn(&str) -> bool) -> usize {
+    s.char_indices()
+        .map(|(i, _)| i)
+        .find(|&i| at(&s[i..]))
+        .unwrap_or(s.len())
+}
+
+/// Reads a quoted value up to its closing `quote`, resolving `\` and
+/// escaped quotes.
+///
+/// Returns the value and the length read, including the closing quote. An
+/// unterminated value runs to the end of `s`.
+fn unquote(s: &str, quote: char) -> (Cow<'_, str>, usize) {
+    // Allocated at the first escape only
+    let mut unescaped: Option<String> = None;
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c == quote {
+            let value = unescaped.map_or(Cow::Borrowed(&s[..i]), Cow::Owned);
+            return (value, i + c.len_utf8());
+        }
+        if c == '\\'
+            && let Some(&(_, next)) = chars.peek()
+            && (next == quote || next == '\\')
+        {
+            unescaped
+                .get_or_insert_with(|| s[..i].to_string())
+                .push(next);
+            chars.next();
+            continue;
+        }
+        if let Some(unescaped) = &mut unescaped {
+            unescaped.push(c);
+        }
+    }
+    (unescaped.map_or(Cow::Borrowed(s), Cow::Owned), s.len())
+}
+
+/// Text written between the columns of a table.
+const TABLE_GAP: &str = "  ";
+
+/// Splits delimiter-separated rows into trimmed cells.
+///
+/// An empty `sep` splits rows on runs of whitespace.
+fn table_cells<'a>(rows: &'a [Item], sep: &str) -> Vec<Vec<&'a str>> {
+    rows.iter()
+        .map(|row| {
+            if sep.is_empty() {
+                row.split_whitespace().collect()
+            } else {
+                row.split(sep).map(str::trim).collect()
+            }
+        })
+        .collect()
+}
+
+/// Splits `padding` spaces into those before and after an aligned cell.
+fn align_padding(align: Option<&ColumnAlign>, padding: usize) -> (usize, usize) {
+    match align {
+        Some(ColumnAlign::Right) => (padding, 0),
+        Some(ColumnAlign::Center) => (padding / 2, padding - padding / 2),
+        Some(ColumnAlign::Left) | None => (0, padding),
+    }
+}
+
+/// Aligns the cells of delimiter-separated rows into columns.
+///
+/// An empty `sep` splits rows on runs of whitespace.
+fn table_rows(rows: &[Item], sep: &str, align: &[ColumnAlign], header: bool) -> Vec<Item> {
+    let cells = table_cells(rows, sep);
+
+    let mut widths: Vec<usize> = Vec::new();
+    for row in &cells {
+        if widths.len() < row.len() {
+            widths.resize(row.len(), 0);
+        }
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(grapheme_len(cell));
+        }
+    }
+
+    let mut lines = Vec::with_capacity(cells.len() + usize::from(header));
+    for (i, row) in cells.iter().enumerate() {
+        let mut line = String::new();
+        for (column, cell) in row.iter().enumerate() {
+            if column > 0 {
+                line.push_str(TABLE_GAP);
+            }
+            let (left, right) =
+                align_padding(align.get(column), widths[column] - grapheme_len(cell));
+            push_fill(&mut line, " ", left);
+            line.push_str(cell);
+            push_fill(&mut line, " ", right);
+        }
+        line.truncate(line.trim_end().len());
+        lines.push(Item::from(line));
+
+        if header && i == 0 {
+            let underline: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+            lines.push(Item::from(underline.join(TABLE_GAP)));
+        }
+    }
+    lines
+}
+
+/// Writes delimiter-separated rows as a Markdown table, one line per item.
+///
+/// Columns are padded to line up, and rows with fewer cells get empty ones.
+/// Without `header`, the table gets an empty header row, as Markdown tables
+/// always have one.
+fn markdown_table_rows(rows: &[Item], sep: &str, align: &[ColumnAlign], header: bool) -> Vec<Item> {
+    let cells: Vec<Vec<String>> = table_cells(rows, sep)
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|cell| cell.replace('|', "\\|"))
+                .collect()
+        })
+        .collect();
+    let columns = cells.iter().map(Vec::len).max().unwrap_or(0);
+    if columns == 0 {
+        return Vec::new();
+    }
+    let mut widths = vec![3; columns];
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(grapheme_len(cell));
+        }
+    }
+
+    let line = |row: &[String]| {
+        let mut line = String::from("|");
+        for (column, width) in widths.iter().enumerate() {
+            let cell = row.get(column).map_or("", String::as_str);
+            let (left, right) = align_padding(align.get(column), width - grapheme_len(cell));
+            line.push(' ');
+            push_fill(&mut line, " ", left);
+            line.push_str(cell);
+            push_fill(&mut line, " ", right);
+            line.push_str(" |");
+        }
+        Item::from(line)
+    };
+    let mut delimiter = String::from("|");
+    for (column, &width) in widths.iter().enumerate() {
+        let (start, end) = match align.get(column) {
+            None => ("-", "-"),
+            Some(ColumnAlign::Left) => (":", "-"),
+            Some(ColumnAlign::Right) => ("-", ":"),
+            Some(ColumnAlign::Center) => (":", ":"),
+        };
+        delimiter.push(' ');
+        delimiter.push_str(start);
+        push_fill(&mut delimiter, "-", width - 2);
+        delimiter.push_str(end);
+        delimiter.push_str(" |");
+    }
+
+    let (head, body) = if header {
+        cells.split_at(1)
+    } else {
+        (&[][..], &cells[..])
+    };
+    let mut lines = Vec::with_capacity(body.len() + 2);
+    lines.push(line(head.first().map_or(&[], Vec::as_slice)));
+    lines.push(Item::from(delimiter));
+    lines.extend(body.iter().map(|row| line(row)));
+    lines
+}
+
+/// Escapes the characters that HTML gives a meaning in text and attributes.
+fn escape_html(s: &str) -> Cow<'_, str> {
+    if !s.contains(['&', '<', '>', '"']) {
+        return Cow::Borrowed(s);
+    }
+    let mut out = String::with_capacity(s.len() + 8);
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Writes delimiter-separated rows as an HTML table, one line per item.
+///
+/// With `header`, the first row goes in a `<thead>` of `<th>` cells. Rows
+/// with fewer cells get empty ones, and aligned columns get a `text-align`
+/// style.
+fn html_table_rows(rows: &[Item], sep: &str, align: &[ColumnAlign], header: bool) -> Vec<Item> {
+    let cells = table_cells(rows, sep);
+    let columns = cells.iter().map(Vec::len).max().unwrap_or(0);
+    if columns == 0 {
+        return Vec::new();
+    }
+
+    let row_line = |row: &[&str], tag: &str| {
+        let mut line = String::from("    <tr>");
+        for column in 0..columns {
+            line.push('<');
+            line.push_str(tag);
+            match align.get(column) {
+                Some(ColumnAlign::Left) => line.push_str(r#" style="text-align: left""#),
+                Some(ColumnAlign::Right) => line.push_str(r#" style="text-align: right""#),
+                Some(ColumnAlign::Center) => line.push_str(r#" style="text-align: center""#),
+                None => {}
+            }
+            line.push('>');
+            line.push_str(&escape_html(row.get(column).copied().unwrap_or("")));
+            line.push_str("</");
+            line.push_str(tag);
+            line.push('>');
+        }
+        line.push_str("</tr>");
+        Item::from(line)
+    };
+
+    let (head, body) = if header {
+        cells.split_at(1)
+    } else {
+        (&[][..], &cells[..])
+    };
+    let mut lines = Vec::with_capacity(cells.len() + 7);
+    lines.push(Item::from("<table>"));
+    if let Some(row) = head.first() {
+        lines.push(Item::from("  <thead>"));
+        lines.push(row_line(row, "th"));
+        lines.push(Item::from("  </thead>"));
+    }
+    lines.push(Item::from("  <tbody>"));
+    lines.extend(body.iter().map(|row| row_line(row, "td")));
+    lines.push(Item::from("  </tbody>"));
+    lines.push(Item::from("</table>"));
+    lines
+}
+
+/* ------------------------------------------------------------------------ */
+/*  Separator search                                                        */
+/* ------------------------------------------------------------------------ */
+
+/// Byte offsets of the non-overlapping occurrences of a non-empty separator.
+///
+/// One-byte separators are located with `memchr` and longer ones with
+/// `memmem`, both of which are vectorized.
+pub(crate) enum SeparatorMatches<'h, 'n> {
+    Byte(memchr::Memchr<'h>),
+    // The memmem searcher is large; boxing keeps one-byte splits lean
+    Bytes(Box<memmem::FindIter<'h, 'n>>),
+}
+
+impl Iterator for SeparatorMatches<'_, '_> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        match self {
+            SeparatorMatches::Byte(it) => it.next(),
+            SeparatorMatches::Bytes(it) => it.next(),
+        }
+    }
+}
+
+/// Finds every occurrence of `separator` in `input`, left to right.
+///
+/// Matches are the same as the ones [`str::split`] splits on. The separator
+/// must not be empty.
+pub(crate) fn separator_matches<'h, 'n>(
+    input: &'h str,
+    separator: &'n str,
+) -> SeparatorMatches<'h, 'n> {
+    debug_assert!(!separator.is_empty());
+    if let [byte] = separator.as_bytes() {
+        SeparatorMatches::Byte(memchr_iter(*byte, input.as_bytes()))
+    } else {
+        SeparatorMatches::Bytes(Box::new(memmem::find_iter(
+            input.as_bytes(),
+            separator.as_bytes(),
+        )))
+    }
+}
+
+/// Byte ranges of the parts of a string split by a non-empty separator.
+///
+/// Yields the same parts as [`str::split`] without allocating them; slice the
+/// input with each range to get the part.
+pub(crate) struct SplitRanges<'h, 'n> {
+    matches: SeparatorMatches<'h, 'n>,
+    start: usize,
+    sep_len: usize,
+    len: usize,
+    finished: bool,
+}
+
+impl Iterator for SplitRanges<'_, '_> {
+    type Item = Range<usize>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Range<usize>> {
+        if self.finished {
+            return None;
+        }
+        match self.matches.next() {
+            Some(idx) => {
+                let part = self.start..idx;
+                self.start = idx + self.sep_len;
+                Some(part)
+            }
+            None => {
+                self.finished = true;
+                Some(self.start..self.len)
+            }
+        }
+    }
+}
+
+/// Splits `input` by a non-empty `separator` into byte ranges.
+pub(crate) fn split_ranges<'h, 'n>(input: &'h str, separator: &'n str) -> SplitRanges<'h, 'n> {
+    SplitRanges {
+        matches: separator_matches(input, separator),
+        start: 0,
+        sep_len: separator.len(),
+        len: input.len(),
+        finished: false,
+    }
+}
+
 /* ------------------------------------------------------------------------ */
 /*  PUBLIC – split cache helper                                             */
 /* ------------------------------------------------------------------------ */
@@ -207,10 +807,11 @@ fn ascii_reverse(s: &str) -> Option<String> {
 ///
 /// * `input` - The string to split
 /// * `separator` - The separator to split on
+/// * `options` - Whether to keep separators and skip empty parts
 ///
 /// # Returns
 ///
-/// A vector of string parts from the split operation.
+/// The string parts from the split operation, shared with the cache entry.
 ///
 /// # Performance
 ///
@@ -218,7 +819,7 @@ fn ascii_reverse(s: &str) -> Option<String> {
 /// - Templates with multiple split operations on the same input
 /// - Repeated template applications with identical inputs
 /// - Pipeline operations that split the same data multiple times
-pub(crate) fn get_cached_split(input: &str, separator: &str) -> Vec<String> {
+pub(crate) fn get_cached_split(input: &str, separator: &str, options: SplitOptions) -> List {
     use std::collections::hash_map::DefaultHasher;
     use std::hash::{Hash, Hasher};
 
@@ -226,38 +827,118 @@ pub(crate) fn get_cached_split(input: &str, separator: &str) -> Vec<String> {
     let mut hasher = DefaultHasher::new();
     input.hash(&mut hasher);
     let input_hash = hasher.finish();
-    let cache_key = (input_hash, separator.to_string());
+    /* Do not grow indefinitely for huge data */
+    let cacheable = |parts: &List| input.len() <= 10_000 && parts.len() <= 1_000;
+
+    // Thread and context caches are not shared, so they are not counted
+    if let Some(parts) = cache::with_local(|caches| {
+        let cache_key = (input_hash, separator.to_string(), options);
+        if let Some(parts) = caches.split.get(&cache_key) {
+            return Arc::clone(parts);
+        }
+        let parts = split_uncached(input, separator, options);
+        if cacheable(&parts) {
+            caches.split.insert(cache_key, Arc::clone(&parts));
+        }
+        parts
+    }) {
+        return parts;
+    }
+
+    let cache_key = (input_hash, separator.to_string(), options);
 
     // Try to get from cache first
     if let Some(cached_split) = SPLIT_CACHE.get(&cache_key) {
-        return cached_split.value().clone();
+        cache::record_split_lookup(true);
+        return Arc::clone(cached_split.value());
     }
+    cache::record_split_lookup(false);
 
-    // Not in cache, compute it with fast path for 1-byte separators
-    let parts: Vec<String> = if separator.len() == 1 {
-        let sep_byte = separator.as_bytes()[0];
-        let mut parts = Vec::with_capacity(16);
-        let mut start = 0usize;
-        for idx in memchr_iter(sep_byte, input.as_bytes()) {
-            // Safety: idx is on UTF-8 boundary due to ASCII separator assumption
-            parts.push(input[start..idx].to_string());
-            start = idx + 1;
-        }
-        parts.push(input[start..].to_string());
-        parts
-    } else {
-        input.split(separator).map(str::to_string).collect()
-    };
+    let parts = split_uncached(input, separator, options);
 
     // Add to cache
-    /* Do not grow indefinitely for huge data */
-    if input.len() <= 10_000 && parts.len() <= 1_000 {
-        SPLIT_CACHE.insert(cache_key, parts.clone());
+    if cacheable(&parts) {
+        SPLIT_CACHE.insert(cache_key, Arc::clone(&parts));
     }
 
     parts
 }
 
+/// The `keep_sep` and `skip_empty` options of a split, and whether it is a
+/// `split_any`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub(crate) struct SplitOptions {
+    /// Split on each character of the separator, as `split_any` does.
+    pub(crate) any_char: bool,
+    /// Keep each separator at the end of the part before it.
+    pub(crate) keep_sep: bool,
+    /// Leave out parts that are empty apart from a kept separator.
+    pub(crate) skip_empty: bool,
+}
+
+/// Splits `input` on `separator` with the vectorized separator search.
+fn split_uncached(input: &str, separator: &str, options: SplitOptions) -> List {
+    if options.any_char {
+        let chars: Vec<char> = separator.chars().collect();
+        return Arc::new(input.split(chars.as_slice()).map(Item::from).collect());
+    }
+    split_limited(input, separator, None, options)
+}
+
+/// Splits `input` on `separator`, stopping after `max_splits` splits if set.
+///
+/// Unlimited splits go through the shared split cache.
+fn split_parts(
+    input: &str,
+    separator: &str,
+    max_splits: Option<usize>,
+    options: SplitOptions,
+) -> List {
+    match max_splits {
+        None => get_cached_split(input, separator, options),
+        Some(_) => split_limited(input, separator, max_splits, options),
+    }
+}
+
+/// Splits `input` on `separator` into at most `max_splits + 1` parts.
+///
+/// An empty separator splits like [`str::split`], so there is no separator
+/// for `keep_sep` to keep.
+fn split_limited(
+    input: &str,
+    separator: &str,
+    max_splits: Option<usize>,
+    options: SplitOptions,
+) -> List {
+    if separator.is_empty() {
+        let limit = max_splits.map_or(usize::MAX, |max| max.saturating_add(1));
+        let parts = input
+            .splitn(limit, separator)
+            .filter(|part| !(options.skip_empty && part.is_empty()))
+            .map(Item::from)
+            .collect();
+        return Arc::new(parts);
+    }
+
+    let mut parts = Vec::new();
+    for (index, part) in split_ranges(input, separator).enumerate() {
+        let last = max_splits == Some(index);
+        let part = if last { part.start..input.len() } else { part };
+        if !(options.skip_empty && part.is_empty()) {
+            let end = if options.keep_sep && part.end < input.len() {
+                part.end + separator.len()
+            } else {
+                part.end
+            };
+            parts.push(Item::from(&input[part.start..end]));
+        }
+        if last {
+            break;
+        }
+    }
+    Arc::new(parts)
+}
+
 /// Get a compiled regex from cache or compile and cache it.
 ///
 /// This function provides cached regex compilation to avoid the overhead of
@@ -269,11 +950,13 @@ pub(crate) fn get_cached_split(input: &str, separator: &str) -> Vec<String> {
 /// - Thread-safe access using mutex protection
 /// - Double-checked locking to prevent race conditions
 /// - Unbounded cache size (patterns are typically small and finite)
-/// - Global cache shared across all pipeline operations
+/// - Global cache shared across all pipeline operations, unless the running
+///   template uses another [`CacheScope`]
 ///
 /// # Arguments
 ///
 /// * `pattern` - The regex pattern string to compile
+/// * `guard` - Limits of the running format call; size limits select a separate cache
 ///
 /// # Returns
 ///
@@ -286,11 +969,49 @@ pub(crate) fn get_cached_split(input: &str, separator: &str) -> Vec<String> {
 /// - Templates with multiple regex operations using the same patterns
 /// - Repeated template applications with identical regex patterns
 /// - Filter operations that repeatedly use the same matching logic
-fn get_cached_regex(pattern: &str) -> Result<Regex, String> {
+fn get_cached_regex(pattern: &str, guard: Option<&ExecGuard>) -> Result<Regex, String> {
+    let limits = guard.and_then(|guard| Some((guard, guard.regex_limits()?)));
+
+    if let Some(regex) = cache::with_local(|caches| match limits {
+        Some((guard, (size, dfa_size))) => {
+            let key = (pattern.to_string(), size, dfa_size);
+            if let Some(regex) = caches.limited_regex.get(&key) {
+                return Ok(regex.clone());
+            }
+            let regex = guard.compile_regex(pattern)?;
+            caches.limited_regex.insert(key, regex.clone());
+            Ok(regex)
+        }
+        None => {
+            if let Some(regex) = caches.regex.get(pattern) {
+                return Ok(regex.clone());
+            }
+            let regex = Regex::new(pattern).map_err(|e| format!("Invalid regex: {e}"))?;
+            caches.regex.insert(pattern.to_string(), regex.clone());
+            Ok(regex)
+        }
+    }) {
+        return regex;
+    }
+
+    if let Some((guard, (size, dfa_size))) = limits {
+        let key = (pattern.to_string(), size, dfa_size);
+        if let Some(regex) = LIMITED_REGEX_CACHE.get(&key) {
+            cache::record_regex_lookup(true);
+            return Ok(regex.value().clone());
+        }
+        cache::record_regex_lookup(false);
+        let regex = guard.compile_regex(pattern)?;
+        LIMITED_REGEX_CACHE.entry(key).or_insert(regex.clone());
+        return Ok(regex);
+    }
+
     // Try to get from cache first
     if let Some(regex) = REGEX_CACHE.get(pattern) {
+        cache::record_regex_lookup(true);
         return Ok(regex.value().clone());
     }
+    cache::record_regex_lookup(false);
 
     // Not in cache, compile it
     let regex = Regex::new(pattern).map_err(|e| format!("Invalid regex: {e}"))?;
@@ -304,68 +1025,588 @@ fn get_cached_regex(pattern: &str) -> Result<Regex, String> {
     Ok(regex)
 }
 
+/// Builds the regex compiled by a `replace` operation.
+///
+/// The `i`, `m`, `s` and `x` sed flags become inline regex flags, and the `l`
+/// flag escapes the pattern so it matches as plain text; other flags only
+/// affect how replacements are applied.
+pub(crate) fn replace_regex_source<'a>(pattern: &'a str, flags: &str) -> Cow<'a, str> {
+    let pattern = if flags.contains('l') {
+        Cow::Owned(regex::escape(pattern))
+    } else {
+        Cow::Borrowed(pattern)
+    };
+    let mut inline_flags = String::with_capacity(4);
+    for flag in ['i', 'm', 's', 'x'] {
+        if flags.contains(flag) {
+            inline_flags.push(flag);
+        }
+    }
+    if inline_flags.is_empty() {
+        pattern
+    } else {
+        Cow::Owned(format!("(?{inline_flags}){pattern}"))
+    }
+}
+
 /// Internal representation of values during pipeline processing.
 ///
 /// Values can be either single strings or lists of strings, allowing operations
 /// to work on both individual items and collections efficiently.
+///
+/// Strings borrow from the pipeline input until an operation actually has to
+/// produce new text, so selecting operations such as `trim` or `substring`
+/// only narrow the borrowed slice. Lists are reference-counted: cloning them
+/// (for example for debug tracing) is O(1), and in-place operations like
+/// `sort` only copy when the list is shared.
 #[derive(Debug, Clone)]
-pub(crate) enum Value {
+pub(crate) enum Value<'a> {
     /// A single string value.
-    Str(String),
+    Str(Cow<'a, str>),
     /// A list of string values.
-    List(Vec<String>),
+    List(List),
 }
 
-/// Enumeration of all supported string transformation operations.
-///
-/// Each variant represents a specific transformation that can be applied to strings
-/// or lists of strings. Operations are designed to be composable and efficient,
-/// supporting both functional-style transformations and imperative-style mutations.
+/// A list item.
 ///
-/// # Operation Categories
+/// Items of up to 24 bytes, such as most fields and path components, are
+/// stored inline instead of in a heap allocation of their own.
+pub(crate) type Item = CompactString;
+
+/// A shared list of items, as held by [`Value::List`].
+pub(crate) type List = Arc<Vec<Item>>;
+
+impl<'a> Value<'a> {
+    fn owned_str(s: String) -> Self {
+        Value::Str(Cow::Owned(s))
+    }
+
+    fn list(items: Vec<Item>) -> Self {
+        Value::List(Arc::new(items))
+    }
+
+    /// Returns the items of a list, or a string as a single item.
+    fn items(&self) -> Vec<&str> {
+        match self {
+            Value::Str(s) => vec![s.as_ref()],
+            Value::List(list) => list.iter().map(Item::as_str).collect(),
+        }
+    }
+}
+
+/// Narrows a string to a subslice of itself without copying borrowed input.
 ///
-/// - **🔪 Text Splitting & Joining**: [`Split`], [`Join`], [`Slice`]
-/// - **✨ Text Transformation**: [`Upper`], [`Lower`], [`Trim`], [`Append`], [`Prepend`], [`Pad`], [`Substring`]
-/// - **🔍 Pattern Matching & Replacement**: [`Replace`], [`RegexExtract`], [`Filter`], [`FilterNot`]
-/// - **🗂️ List Processing**: [`Sort`], [`Reverse`], [`Unique`], [`Map`]
-/// - **🧹 Utility**: [`StripAnsi`]
+/// Owned strings are narrowed in place, keeping their allocation.
+fn narrow_str<'a, F>(s: Cow<'a, str>, select: F) -> Cow<'a, str>
+where
+    F: for<'x> FnOnce(&'x str) -> &'x str,
+{
+    match s {
+        Cow::Borrowed(b) => Cow::Borrowed(select(b)),
+        Cow::Owned(mut o) => {
+            let selected = select(&o);
+            let offset = (selected.as_ptr() as usize).wrapping_sub(o.as_ptr() as usize);
+            if offset
+                .checked_add(selected.len())
+                .is_some_and(|end| end <= o.len())
+            {
+                let end = offset + selected.len();
+                o.truncate(end);
+                o.drain(..offset);
+                Cow::Owned(o)
+            } else {
+                // Not a subslice of the input (e.g. a static empty string)
+                Cow::Owned(selected.to_string())
+            }
+        }
+    }
+}
+
+/// Trims characters from one or both ends of a string.
 ///
-/// # Type System
+/// Empty or whitespace-only `chars` trims Unicode whitespace.
+fn trim_str<'a>(s: Cow<'a, str>, chars: &str, direction: &TrimDirection) -> Cow<'a, str> {
+    // Fast path for default whitespace trimming
+    if chars.is_empty() || chars.trim().is_empty() {
+        narrow_str(s, |s| match direction {
+            TrimDirection::Both => ascii_trim(s).unwrap_or_else(|| s.trim()),
+            TrimDirection::Left => s.trim_start(),
+            TrimDirection::Right => s.trim_end(),
+        })
+    } else {
+        // Custom character trimming with optimized character set
+        let chars_to_trim: Vec<char> = chars.chars().collect();
+        narrow_str(s, |s| match direction {
+            TrimDirection::Both => s.trim_matches(|c| chars_to_trim.contains(&c)),
+            TrimDirection::Left => s.trim_start_matches(|c| chars_to_trim.contains(&c)),
+            TrimDirection::Right => s.trim_end_matches(|c| chars_to_trim.contains(&c)),
+        })
+    }
+}
+
+/// Selects a range of grapheme clusters from a string.
+fn substring_str<'a>(s: Cow<'a, str>, range: &RangeSpec) -> Cow<'a, str> {
+    let range = &*ascending_range(range);
+    // Stepped and multi-ranges do not select one contiguous slice
+    if let RangeSpec::Stepped(..) | RangeSpec::Multi(_) = range {
+        let graphemes: Vec<&str> = s.graphemes(true).collect();
+        return Cow::Owned(apply_range(&graphemes, range).concat());
+    }
+
+    narrow_str(s, |s| {
+        if ascii_graphemes(s) {
+            // Optimized ASCII path - byte offsets are grapheme offsets
+            &s[range_indices(s.len(), range)]
+        } else {
+            // Map grapheme offsets to byte offsets
+            let starts: Vec<usize> = s.grapheme_indices(true).map(|(b, _)| b).collect();
+            let selected = range_indices(starts.len(), range);
+            let byte_at = |n: usize| starts.get(n).copied().unwrap_or(s.len());
+            &s[byte_at(selected.start)..byte_at(selected.end)]
+        }
+    })
+}
+
+/// Returns `true` if `ops` is a run of string operations that
+/// [`apply_string_chain`] can execute as one fused step.
 ///
-/// Operations are categorized by their input/output type requirements:
+/// Single operations gain nothing from fusion and are not reported.
+pub(crate) fn is_fusable_string_chain(ops: &[StringOp]) -> bool {
+    ops.len() > 1
+        && ops.iter().all(|op| {
+            matches!(
+                op,
+                StringOp::Upper { .. }
+                    | StringOp::Lower { .. }
+                    | StringOp::Trim { .. }
+                    | StringOp::TrimPrefix { .. }
+                    | StringOp::TrimSuffix { .. }
+                    | StringOp::Substring { .. }
+                    | StringOp::Append { .. }
+                    | StringOp::Prepend { .. }
+                    | StringOp::Surround { .. }
+            )
+        })
+}
+
+/// Executes a fused chain of string operations.
 ///
-/// - **String→String**: [`Upper`], [`Lower`], [`Trim`], [`Replace`], [`Append`], [`Prepend`], [`Pad`], [`Substring`], [`RegexExtract`], [`StripAnsi`]
-/// - **List→List**: [`Sort`], [`Unique`], [`Slice`], [`Map`]
-/// - **Type-preserving**: [`Filter`], [`FilterNot`], [`Reverse`]
-/// - **Type-converting**: [`Split`] (String→List), [`Join`] (List→String)
+/// Produces the same result as running the operations one by one, but
+/// narrowing operations never copy and every rewrite reuses one of two
+/// buffers, so a chain like `trim|lower|append:x` allocates at most twice
+/// instead of once per operation.
 ///
-/// Use `map:{operation}` to apply string operations to each item in a list.
+/// Callers must check the chain with [`is_fusable_string_chain`] first;
+/// other operations are skipped.
+pub(crate) fn apply_string_chain(input: &str, ops: &[StringOp]) -> String {
+    let mut value: Cow<'_, str> = Cow::Borrowed(input);
+    let mut scratch = String::new();
+
+    for op in ops {
+        match op {
+            StringOp::Trim { chars, direction } => {
+                value = trim_str(value, chars, direction);
+                continue;
+            }
+            StringOp::Substring { range } => {
+                value = substring_str(value, range);
+                continue;
+            }
+            StringOp::TrimPrefix { prefix } => {
+                value = narrow_str(value, |s| s.strip_prefix(prefix.as_str()).unwrap_or(s));
+                continue;
+            }
+            StringOp::TrimSuffix { suffix } => {
+                value = narrow_str(value, |s| s.strip_suffix(suffix.as_str()).unwrap_or(s));
+                continue;
+            }
+            StringOp::Append { suffix } => {
+                if let Cow::Owned(s) = &mut value {
+                    s.push_str(suffix);
+                    continue;
+                }
+                scratch.clear();
+                scratch.push_str(&value);
+                scratch.push_str(suffix);
+            }
+            StringOp::Prepend { prefix } => {
+                scratch.clear();
+                scratch.push_str(prefix);
+                scratch.push_str(&value);
+            }
+            StringOp::Surround { prefix, suffix } => {
+                scratch.clear();
+                scratch.push_str(prefix);
+                scratch.push_str(&value);
+                scratch.push_str(suffix);
+            }
+            StringOp::Upper { locale } | StringOp::Lower { locale }
+                if locale.is_ascii_compatible() && value.is_ascii() =>
+            {
+                scratch.clear();
+                scratch.push_str(&value);
+                if matches!(op, StringOp::Upper { .. }) {
+                    scratch.make_ascii_uppercase();
+                } else {
+                    scratch.make_ascii_lowercase();
+                }
+            }
+            StringOp::Upper { locale } => {
+                value = Cow::Owned(locale.to_upper(&value));
+                continue;
+            }
+            StringOp::Lower { locale } => {
+                value = Cow::Owned(locale.to_lower(&value));
+                continue;
+            }
+            _ => continue,
+        }
+
+        // The rewritten value now lives in `scratch`; recycle the previous
+        // buffer as the next scratch space.
+        let next = std::mem::take(&mut scratch);
+        if let Cow::Owned(previous) = std::mem::replace(&mut value, Cow::Owned(next)) {
+            scratch = previous;
+        }
+    }
+
+    value.into_owned()
+}
+
+/// Applies one rule of a `replace` operation to `s`.
+fn apply_sed_rule<'a>(
+    s: Cow<'a, str>,
+    rule: &SedRule,
+    guard: Option<&ExecGuard>,
+) -> Result<Cow<'a, str>, String> {
+    let SedRule {
+        pattern,
+        replacement,
+        flags,
+        occurrence,
+    } = rule;
+    let literal = flags.contains('l');
+    // Early exit for simple string patterns (not regex)
+    if !flags.contains('g')
+        && !flags.contains('i')
+        && (literal
+            || !pattern.contains([
+                '\\', '.', '*', '+', '?', '^', '$', '|', '[', ']', '(', ')', '{', '}',
+            ]))
+        && !s.contains(pattern.as_str())
+    {
+        return Ok(s);
+    }
+
+    let re = get_cached_regex(&replace_regex_source(pattern, flags), guard)?;
+    // `$` in a literal replacement is plain text too
+    let replacement = if literal {
+        Cow::Owned(replacement.replace('$', "$$"))
+    } else {
+        Cow::Borrowed(replacement.as_str())
+    };
+    let global = flags.contains('g');
+    Ok(rewrite_str(s, |s| match occurrence {
+        Some(nth) => replace_from_nth(&re, s, &replacement, *nth, global),
+        None if global => re.replace_all(s, replacement.as_ref()),
+        None => re.replace(s, replacement.as_ref()),
+    }))
+}
+
+/// Replaces the `nth` match of `re` (1-based), or every match from the
+/// `nth` on when `all` is set.
+fn replace_from_nth<'a>(
+    re: &Regex,
+    s: &'a str,
+    replacement: &str,
+    nth: usize,
+    all: bool,
+) -> Cow<'a, str> {
+    let mut result = String::new();
+    let mut last = 0;
+    let mut replaced = false;
+    for caps in re.captures_iter(s).skip(nth - 1) {
+        let m = caps.get(0).unwrap();
+        result.push_str(&s[last..m.start()]);
+        caps.expand(replacement, &mut result);
+        last = m.end();
+        replaced = true;
+        if !all {
+            break;
+        }
+    }
+    if !replaced {
+        return Cow::Borrowed(s);
+    }
+    result.push_str(&s[last..]);
+    Cow::Owned(result)
+}
+
+/// Rewrites a string with a function that may return its input unchanged.
+///
+/// Keeps the original string, borrowed or owned, when nothing changed.
+fn rewrite_str<'a, F>(s: Cow<'a, str>, rewrite: F) -> Cow<'a, str>
+where
+    F: for<'x> FnOnce(&'x str) -> Cow<'x, str>,
+{
+    match s {
+        Cow::Borrowed(b) => rewrite(b),
+        Cow::Owned(o) => {
+            let changed = match rewrite(&o) {
+                Cow::Owned(new) => Some(new),
+                Cow::Borrowed(_) => None,
+            };
+            Cow::Owned(changed.unwrap_or(o))
+        }
+    }
+}
+
+/// Removes ANSI escape sequences from `s`, borrowing it when there are none.
+///
+/// Most text holds no escape sequences, so the terminal parser only runs when
+/// [`has_ansi_controls`] finds something for it to remove.
+pub(crate) fn strip_ansi(s: &str) -> Cow<'_, str> {
+    if has_ansi_controls(s.as_bytes()) {
+        strip_ansi_string(s)
+    } else {
+        Cow::Borrowed(s)
+    }
+}
+
+/// Returns `true` if `bytes` holds a byte that stripping ANSI codes removes.
+///
+/// Besides the ESC that starts escape sequences, the terminal parser drops
+/// DEL and every control character other than tab, newline and carriage
+/// return, so those bytes also count. The scan works on fixed-size chunks
+/// without early exits inside them, which lets it vectorize like `memchr`.
+pub(crate) fn has_ansi_controls(bytes: &[u8]) -> bool {
+    bytes.chunks(32).any(|chunk| {
+        chunk.iter().fold(false, |found, &b| {
+            found | ((b < 0x20 && b != b'\t' && b != b'\n' && b != b'\r') || b == 0x7f)
+        })
+    })
+}
+
+/// Selects `range` of the parts produced by a split: a string for a single
+/// index, otherwise a list.
+fn select_parts<'a>(
+    parts: List,
+    range: &RangeSpec,
+    guard: Option<&ExecGuard>,
+) -> Result<Value<'a>, String> {
+    if let Some(guard) = guard {
+        guard.check_split(parts.len())?;
+    }
+    check_range_bounds(parts.len(), range, "item")?;
+
+    let range = &*ascending_range(range);
+    let selected = range_indices(parts.len(), range);
+    match range {
+        // If the range is a single index, return a string instead of a list
+        RangeSpec::Index(_) => Ok(Value::owned_str(
+            parts
+                .get(selected.start)
+                .map(Item::to_string)
+                .unwrap_or_default(),
+        )),
+        // Full ranges keep sharing the cached parts
+        RangeSpec::Range(..) if selected.len() == parts.len() => Ok(Value::List(parts)),
+        _ => Ok(Value::list(select_items(parts, range))),
+    }
+}
+
+/// Selects a range of list items, copying only the selected items when the
+/// list is shared.
+fn select_items(list: List, range: &RangeSpec) -> Vec<Item> {
+    match Arc::try_unwrap(list) {
+        Ok(owned) => take_range(owned, range),
+        Err(shared) => apply_range(&shared, range),
+    }
+}
+
+/// Keeps the list items matching `keep`, filtering in place when the list is
+/// not shared.
+/// Keeps the list items, or the single string, for which `keep` holds.
+///
+/// A string that fails the check becomes empty, as with `filter`.
+fn retain_items<F>(mut list: List, mut keep: F) -> List
+where
+    F: FnMut(&str) -> bool,
+{
+    match Arc::get_mut(&mut list) {
+        Some(items) => {
+            items.retain(|item| keep(item));
+            list
+        }
+        None => Arc::new(list.iter().filter(|item| keep(item)).cloned().collect()),
+    }
+}
+
+/// Keeps the list items matching `keep`, or keeps a string matching it and
+/// turns any other string into an empty one.
+fn retain_value<F>(val: Value<'_>, mut keep: F) -> Value<'_>
+where
+    F: FnMut(&str) -> bool,
+{
+    match val {
+        Value::List(list) => Value::List(retain_items(list, keep)),
+        Value::Str(s) if keep(&s) => Value::Str(s),
+        Value::Str(_) => Value::Str(Cow::Borrowed("")),
+    }
+}
+
+/// Enumeration of all supported string transformation operations.
+///
+/// Each variant represents a specific transformation that can be applied to strings
+/// or lists of strings. Operations are designed to be composable and efficient,
+/// supporting both functional-style transformations and imperative-style mutations.
+///
+/// # Operation Categories
+///
+/// - **🔪 Text Splitting & Joining**: [`Split`], [`SplitAny`], [`Partition`], [`RPartition`], [`Words`], [`Sentences`], [`Join`], [`Slice`], [`Clamp`]
+/// - **✨ Text Transformation**: [`Upper`], [`Lower`], [`Capitalize`], [`SwapCase`], [`Trim`], [`Squeeze`], [`TrimPrefix`], [`TrimSuffix`], [`Append`], [`Prepend`], [`Pad`], [`Substring`]
+/// - **🔍 Pattern Matching & Replacement**: [`Replace`], [`ReplaceLiteral`], [`RegexExtract`], [`ExtractEmails`], [`ExtractUrls`], [`ExtractUuids`], [`ExtractBetween`], [`Kv`], [`TrimRegex`], [`Filter`], [`FilterNot`], [`FilterPrefix`], [`FilterSuffix`], [`FilterContains`], [`FilterValidUtf8`], [`FilterLuhn`], [`FilterChecksum`]
+/// - **🎨 Terminal Styling**: [`Color`], [`Background`], [`Bold`], [`Underline`], [`StyleReset`], [`Highlight`]
+/// - **🗂️ List Processing**: [`Sort`], [`Reverse`], [`Unique`], [`Histogram`], [`Map`], [`Table`], [`MarkdownTable`], [`HtmlTable`], [`Each`]
+/// - **🧹 Utility**: [`StripAnsi`], [`Deaccent`], [`Ascii`], [`Slugify`], [`Normalize`], [`Ordinal`], [`ToWords`], [`Roman`], [`Basename`], [`Dirname`], [`Extname`], [`StripExt`], [`WithExt`], [`NormalizePath`], [`UrlScheme`], [`UrlHost`], [`UrlPath`], [`UrlQuery`], [`YamlGet`], [`TomlGet`], [`Len`], [`JsonArray`], [`JsonObject`], [`ReadFile`], [`Exec`]
+///
+/// # Type System
+///
+/// Operations are categorized by their input/output type requirements:
+///
+/// - **String→String**: [`Upper`], [`Lower`], [`Capitalize`], [`SwapCase`], [`Trim`], [`Squeeze`], [`TrimPrefix`], [`TrimSuffix`], [`TrimRegex`], [`Replace`], [`ReplaceLiteral`], [`Append`], [`Prepend`], [`Pad`], [`Substring`], [`RegexExtract`], [`ExtractBetween`], [`Kv`], [`StripAnsi`], [`Deaccent`], [`Ascii`], [`Slugify`], [`Normalize`], [`Ordinal`], [`ToWords`], [`Roman`], [`Basename`], [`Dirname`], [`Extname`], [`StripExt`], [`WithExt`], [`NormalizePath`], [`UrlScheme`], [`UrlHost`], [`UrlPath`], [`UrlQuery`], [`YamlGet`], [`TomlGet`], [`Color`], [`Background`], [`Bold`], [`Underline`], [`StyleReset`], [`Highlight`], [`ReadFile`], [`Exec`]
+/// - **List→List**: [`Sort`], [`SortBy`], [`Unique`], [`UniqueBy`], [`Duplicates`], [`Histogram`], [`RemoveEmpty`], [`FilterBy`], [`Intersect`], [`Diff`], [`Union`], [`Shuffle`], [`Sample`], [`Table`], [`MarkdownTable`], [`HtmlTable`], [`Slice`], [`Clamp`], [`Map`]
+/// - **Type-preserving**: [`Filter`], [`FilterNot`], [`FilterPrefix`], [`FilterSuffix`], [`FilterContains`], [`FilterValidUtf8`], [`FilterLuhn`], [`FilterChecksum`], [`Reverse`]
+/// - **Type-converting**: [`Split`], [`SplitAny`], [`Partition`], [`RPartition`], [`KvKeys`], [`KvValues`], [`Words`], [`Sentences`], [`ExtractEmails`], [`ExtractUrls`], [`ExtractUuids`] (String→List), [`Join`] (List→String), [`Len`], [`JsonArray`], [`JsonObject`], [`Each`] (any→String)
+///
+/// Use `map:{operation}` to apply string operations to each item in a list.
+///
+/// # Building Operations
+///
+/// Operations are usually parsed from templates. In code, the methods of
+/// [`Pipeline`] create them with their defaults filled in, including the
+/// sub-pipelines of [`Map`], [`SortBy`], [`UniqueBy`] and [`FilterBy`], and
+/// [`Template::from_ops`] turns them into a template. Variants may also be
+/// written out directly. New operations can be added in minor releases, so
+/// matches on this enum need a wildcard arm.
+///
+/// ```rust
+/// use string_pipeline::{Pipeline, StringOp, Template};
+///
+/// let mut ops = Pipeline::new().trim().into_ops();
+/// ops.push(StringOp::Append { suffix: "!".to_string() });
+///
+/// let template = Template::from_ops(ops).unwrap();
+/// assert_eq!(template.template_string(), "{trim|append:!}");
+/// assert_eq!(template.format(" hi ").unwrap(), "hi!");
+/// ```
 ///
 /// [`Upper`]: StringOp::Upper
+/// [`Capitalize`]: StringOp::Capitalize
+/// [`SwapCase`]: StringOp::SwapCase
 /// [`Lower`]: StringOp::Lower
 /// [`Trim`]: StringOp::Trim
+/// [`TrimPrefix`]: StringOp::TrimPrefix
+/// [`TrimSuffix`]: StringOp::TrimSuffix
+/// [`TrimRegex`]: StringOp::TrimRegex
 /// [`Replace`]: StringOp::Replace
+/// [`ReplaceLiteral`]: StringOp::ReplaceLiteral
 /// [`Split`]: StringOp::Split
+/// [`SplitAny`]: StringOp::SplitAny
+/// [`Partition`]: StringOp::Partition
+/// [`RPartition`]: StringOp::RPartition
 /// [`Join`]: StringOp::Join
+/// [`OutputSep`]: StringOp::OutputSep
 /// [`Sort`]: StringOp::Sort
+/// [`SortBy`]: StringOp::SortBy
 /// [`Unique`]: StringOp::Unique
+/// [`UniqueBy`]: StringOp::UniqueBy
+/// [`Duplicates`]: StringOp::Duplicates
+/// [`Histogram`]: StringOp::Histogram
+/// [`RemoveEmpty`]: StringOp::RemoveEmpty
+/// [`Squeeze`]: StringOp::Squeeze
+/// [`Intersect`]: StringOp::Intersect
+/// [`Diff`]: StringOp::Diff
+/// [`Union`]: StringOp::Union
+/// [`Shuffle`]: StringOp::Shuffle
+/// [`Sample`]: StringOp::Sample
+/// [`Table`]: StringOp::Table
+/// [`MarkdownTable`]: StringOp::MarkdownTable
+/// [`HtmlTable`]: StringOp::HtmlTable
 /// [`Filter`]: StringOp::Filter
 /// [`FilterNot`]: StringOp::FilterNot
+/// [`FilterPrefix`]: StringOp::FilterPrefix
+/// [`FilterSuffix`]: StringOp::FilterSuffix
+/// [`FilterContains`]: StringOp::FilterContains
+/// [`FilterValidUtf8`]: StringOp::FilterValidUtf8
+/// [`FilterLuhn`]: StringOp::FilterLuhn
+/// [`FilterChecksum`]: StringOp::FilterChecksum
+/// [`FilterBy`]: StringOp::FilterBy
 /// [`Substring`]: StringOp::Substring
 /// [`RegexExtract`]: StringOp::RegexExtract
+/// [`Kv`]: StringOp::Kv
+/// [`KvKeys`]: StringOp::KvKeys
+/// [`KvValues`]: StringOp::KvValues
+/// [`Words`]: StringOp::Words
+/// [`ExtractEmails`]: StringOp::ExtractEmails
+/// [`ExtractUrls`]: StringOp::ExtractUrls
+/// [`ExtractUuids`]: StringOp::ExtractUuids
+/// [`ExtractBetween`]: StringOp::ExtractBetween
+/// [`Sentences`]: StringOp::Sentences
 /// [`Slice`]: StringOp::Slice
+/// [`Clamp`]: StringOp::Clamp
 /// [`Map`]: StringOp::Map
 /// [`Reverse`]: StringOp::Reverse
 /// [`Pad`]: StringOp::Pad
 /// [`Append`]: StringOp::Append
 /// [`Prepend`]: StringOp::Prepend
 /// [`StripAnsi`]: StringOp::StripAnsi
-#[derive(Debug, Clone, Hash)]
+/// [`Deaccent`]: StringOp::Deaccent
+/// [`Ascii`]: StringOp::Ascii
+/// [`Slugify`]: StringOp::Slugify
+/// [`Normalize`]: StringOp::Normalize
+/// [`Ordinal`]: StringOp::Ordinal
+/// [`ToWords`]: StringOp::ToWords
+/// [`Roman`]: StringOp::Roman
+/// [`Basename`]: StringOp::Basename
+/// [`Dirname`]: StringOp::Dirname
+/// [`Extname`]: StringOp::Extname
+/// [`StripExt`]: StringOp::StripExt
+/// [`WithExt`]: StringOp::WithExt
+/// [`NormalizePath`]: StringOp::NormalizePath
+/// [`UrlScheme`]: StringOp::UrlScheme
+/// [`UrlHost`]: StringOp::UrlHost
+/// [`UrlPath`]: StringOp::UrlPath
+/// [`UrlQuery`]: StringOp::UrlQuery
+/// [`YamlGet`]: StringOp::YamlGet
+/// [`TomlGet`]: StringOp::TomlGet
+/// [`Color`]: StringOp::Color
+/// [`Background`]: StringOp::Background
+/// [`Bold`]: StringOp::Bold
+/// [`Underline`]: StringOp::Underline
+/// [`StyleReset`]: StringOp::StyleReset
+/// [`Highlight`]: StringOp::Highlight
+/// [`Len`]: StringOp::Len
+/// [`JsonArray`]: StringOp::JsonArray
+/// [`JsonObject`]: StringOp::JsonObject
+/// [`ReadFile`]: StringOp::ReadFile
+/// [`Exec`]: StringOp::Exec
+/// [`Each`]: StringOp::Each
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum StringOp {
     /// Split a string by separator and optionally select a range of parts.
     ///
+    /// **Syntax:** `split:SEPARATOR:RANGE[:MAX_SPLITS][:keep_sep][:skip_empty]`
+    ///
     /// This operation converts a string into a list by splitting on the specified
     /// separator, then optionally selects a subset using the range specification.
+    /// Like Python's `str.split(sep, maxsplit)`, a split limit stops after that
+    /// many splits, leaving the remainder unsplit in the last part.
+    ///
+    /// The `keep_sep` option keeps each separator at the end of the part before
+    /// it, so `join` with an empty separator rebuilds the input. The `skip_empty`
+    /// option leaves out empty parts before the range selects from them.
     ///
     /// **Performance Optimization:** Common separators are cached to reduce memory allocations.
     ///
@@ -373,6 +1614,9 @@ pub enum StringOp {
     ///
     /// * `sep` - The separator string to split on
     /// * `range` - Range specification for selecting parts
+    /// * `max_splits` - Maximum number of splits, or `None` to split everywhere
+    /// * `keep_sep` - Keep each separator at the end of the part before it
+    /// * `skip_empty` - Leave out empty parts
     ///
     /// # Examples
     ///
@@ -390,15 +1634,119 @@ pub enum StringOp {
     /// // Split and take range
     /// let template = Template::parse("{split:,:1..3}").unwrap();
     /// assert_eq!(template.format("a,b,c,d").unwrap(), "b,c");
+    ///
+    /// // Split on the first separator only
+    /// let template = Template::parse("{split:=:1:1}").unwrap();
+    /// assert_eq!(template.format("key=value=with=equals").unwrap(), "value=with=equals");
+    ///
+    /// // Drop the empty parts left by repeated separators
+    /// let template = Template::parse("{split:,:..:skip_empty}").unwrap();
+    /// assert_eq!(template.format("a,,b,").unwrap(), "a,b");
+    ///
+    /// // Keep the separators with their parts
+    /// let template = Template::parse("{split:,:..:keep_sep|map:{upper}|join:}").unwrap();
+    /// assert_eq!(template.format("a,b").unwrap(), "A,B");
+    /// ```
+    Split {
+        sep: String,
+        range: RangeSpec,
+        max_splits: Option<usize>,
+        keep_sep: bool,
+        skip_empty: bool,
+    },
+
+    /// Split a string on any of a set of characters and optionally select a
+    /// range of parts.
+    ///
+    /// **Syntax:** `split_any:CHARS[:RANGE]`
+    ///
+    /// Like C's `strtok`, every character of `CHARS` is a separator of its
+    /// own, which is simpler and faster than a regex for mixed delimiters.
+    /// Unlike `strtok`, adjacent separators leave empty parts; follow with
+    /// `remove_empty` to drop them. A list left at the end of the section is
+    /// joined with the first of the characters.
+    ///
+    /// # Fields
+    ///
+    /// * `chars` - The separator characters
+    /// * `range` - Range specification for selecting parts
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split_any:,;|:..|join:-}").unwrap();
+    /// assert_eq!(template.format("a,b;c|d").unwrap(), "a-b-c-d");
+    ///
+    /// let template = Template::parse("{split_any: \\t:1}").unwrap();
+    /// assert_eq!(template.format("key\tvalue rest").unwrap(), "value");
+    /// ```
+    SplitAny { chars: String, range: RangeSpec },
+
+    /// Split a string around the first occurrence of a separator.
+    ///
+    /// **Syntax:** `partition:SEPARATOR`
+    ///
+    /// Always produces exactly three items: the text before the separator,
+    /// the separator itself, and the text after it. When the separator is
+    /// missing, the result is the whole string followed by two empty items,
+    /// like Python's `str.partition`.
+    ///
+    /// # Fields
+    ///
+    /// * `sep` - The separator to look for (must not be empty)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// // Everything after the first colon
+    /// let template = Template::parse("{partition:\\:|slice:2..|join:}").unwrap();
+    /// assert_eq!(template.format("host:port:path").unwrap(), "port:path");
+    ///
+    /// let template = Template::parse("{partition:=|join:,}").unwrap();
+    /// assert_eq!(template.format("a=b=c").unwrap(), "a,=,b=c");
+    /// assert_eq!(template.format("abc").unwrap(), "abc,,");
     /// ```
-    Split { sep: String, range: RangeSpec },
+    Partition { sep: String },
+
+    /// Split a string around the last occurrence of a separator.
+    ///
+    /// **Syntax:** `rpartition:SEPARATOR`
+    ///
+    /// Like [`StringOp::Partition`], but searches from the end. When the
+    /// separator is missing, the result is two empty items followed by the
+    /// whole string, like Python's `str.rpartition`.
+    ///
+    /// # Fields
+    ///
+    /// * `sep` - The separator to look for (must not be empty)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// // File extension
+    /// let template = Template::parse("{rpartition:.|slice:-1|join:}").unwrap();
+    /// assert_eq!(template.format("archive.tar.gz").unwrap(), "gz");
+    ///
+    /// let template = Template::parse("{rpartition:/|join:,}").unwrap();
+    /// assert_eq!(template.format("a/b/c").unwrap(), "a/b,/,c");
+    /// assert_eq!(template.format("abc").unwrap(), ",,abc");
+    /// ```
+    RPartition { sep: String },
 
     /// Join a list of strings with the specified separator.
     ///
-    /// **Syntax:** `join:SEPARATOR`
+    /// **Syntax:** `join:SEPARATOR[:LAST_SEPARATOR]`
     ///
     /// This operation takes a list of strings and combines them into a single
-    /// string using the provided separator between each item.
+    /// string using the provided separator between each item. An optional last
+    /// separator goes between the final two items instead, for human-readable
+    /// lists such as `a, b and c`.
     ///
     /// **Behavior on Different Input Types:**
     /// - **List:** Joins items with the separator in their current order (no sorting applied)
@@ -409,6 +1757,7 @@ pub enum StringOp {
     /// # Fields
     ///
     /// * `sep` - The separator to insert between list items (empty string for no separator)
+    /// * `last_sep` - Separator between the final two items, if different from `sep`
     ///
     /// # Examples
     ///
@@ -426,15 +1775,51 @@ pub enum StringOp {
     /// // Join with no separator
     /// let template = Template::parse("{split:,:..|join:}").unwrap();
     /// assert_eq!(template.format("a,b,c").unwrap(), "abc");
+    ///
+    /// // Natural-language list
+    /// let template = Template::parse("{split:,:..|join:, : and }").unwrap();
+    /// assert_eq!(template.format("a,b,c").unwrap(), "a, b and c");
+    /// assert_eq!(template.format("a,b").unwrap(), "a and b");
+    /// ```
+    Join {
+        sep: String,
+        last_sep: Option<String>,
+    },
+
+    /// Set the separator used to render a final list result.
+    ///
+    /// A pipeline that ends with a list is rendered with the separator of
+    /// its last `split` or `join`. `output_sep` overrides that choice for the
+    /// whole pipeline, wherever it appears, and leaves the value unchanged.
+    ///
+    /// **Syntax:** `output_sep:separator`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:,:..|filter:^a|output_sep:\\n}").unwrap();
+    /// assert_eq!(template.format("ab,b,ac").unwrap(), "ab\nac");
+    ///
+    /// // Later splits do not change the output separator
+    /// let template = Template::parse("{output_sep:+|split:,:..|sort}").unwrap();
+    /// assert_eq!(template.format("b,a").unwrap(), "a+b");
     /// ```
-    Join { sep: String },
+    OutputSep { sep: String },
 
     /// Replace text using regex patterns with sed-like syntax.
     ///
     /// **Syntax:** `replace:s/PATTERN/REPLACEMENT/FLAGS`
     ///
     /// Supports full regex replacement with capture groups, flags for global/case-insensitive
-    /// matching, and other standard regex features.
+    /// matching, and other standard regex features. Like sed, any punctuation character
+    /// can replace `/` as the delimiter, as in `s|/usr/bin|/opt|`.
+    ///
+    /// Several sed strings separated by `;` make one `Replace` whose rules
+    /// apply in order, each to the result of the one before:
+    /// `replace:s/a/b/g;s/c/d/` gives the same output as
+    /// `replace:s/a/b/g|replace:s/c/d/`, but remains one operation.
     ///
     /// **Performance Optimization:** Regex patterns are compiled and cached internally for
     /// reuse across operations. For simple string patterns without regex metacharacters
@@ -442,9 +1827,7 @@ pub enum StringOp {
     ///
     /// # Fields
     ///
-    /// * `pattern` - The regex pattern to search for
-    /// * `replacement` - The replacement text (supports capture group references like `$1`, `$2`)
-    /// * `flags` - Regex flags: `g` (global), `i` (case-insensitive), `m` (multiline), `s` (dot-all)
+    /// * `rules` - The sed rules, applied in order (see [`SedRule`])
     ///
     /// # Examples
     ///
@@ -466,17 +1849,62 @@ pub enum StringOp {
     /// // Using capture groups
     /// let template = Template::parse("{replace:s/(.+)/[$1]/}").unwrap();
     /// assert_eq!(template.format("hello").unwrap(), "[hello]");
+    ///
+    /// // Only the second match
+    /// let template = Template::parse("{replace:s/o/0/2}").unwrap();
+    /// assert_eq!(template.format("foo boo").unwrap(), "fo0 boo");
+    ///
+    /// // Another delimiter, so `/` needs no escaping
+    /// let template = Template::parse("{replace:s|/usr/bin|/opt|}").unwrap();
+    /// assert_eq!(template.format("/usr/bin/ls").unwrap(), "/opt/ls");
+    ///
+    /// // Literal matching
+    /// let template = Template::parse("{replace:s/1.5/($1)/gl}").unwrap();
+    /// assert_eq!(template.format("1.5 125").unwrap(), "($1) 125");
+    ///
+    /// // Several rules in one operation
+    /// let template = Template::parse("{replace:s/&/and/g;s/ +/-/g}").unwrap();
+    /// assert_eq!(template.format("salt & pepper").unwrap(), "salt-and-pepper");
     /// ```
-    Replace {
-        pattern: String,
-        replacement: String,
-        flags: String,
+    Replace { rules: Vec<SedRule> },
+
+    /// Replace plain text, without regex interpretation.
+    ///
+    /// **Syntax:** `replace_literal:FROM:TO[:COUNT]`
+    ///
+    /// Every occurrence of `FROM` is replaced unless a count limits the
+    /// number of replacements, counted from the start. Regex metacharacters
+    /// have no special meaning; escape `:` and `|` as in other arguments.
+    ///
+    /// # Fields
+    ///
+    /// * `from` - Text to search for
+    /// * `to` - Replacement text
+    /// * `count` - Maximum number of replacements (`None` for all)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{replace_literal:.*:+}").unwrap();
+    /// assert_eq!(template.format("a.*b.*c").unwrap(), "a+b+c");
+    ///
+    /// let template = Template::parse("{replace_literal:-:_:1}").unwrap();
+    /// assert_eq!(template.format("a-b-c").unwrap(), "a_b-c");
+    /// ```
+    ReplaceLiteral {
+        from: String,
+        to: String,
+        count: Option<usize>,
     },
 
     /// Convert text to uppercase.
     ///
     /// Applies Unicode-aware uppercase conversion to the entire string,
     /// properly handling international characters and special cases.
+    /// An optional locale (`tr` or `az`) selects Turkic rules, where `i`
+    /// becomes `İ`.
     ///
     /// # Examples
     ///
@@ -486,13 +1914,18 @@ pub enum StringOp {
     /// let template = Template::parse("{upper}").unwrap();
     /// assert_eq!(template.format("hello world").unwrap(), "HELLO WORLD");
     /// assert_eq!(template.format("café").unwrap(), "CAFÉ");
+    ///
+    /// let template = Template::parse("{upper:tr}").unwrap();
+    /// assert_eq!(template.format("istanbul").unwrap(), "İSTANBUL");
     /// ```
-    Upper,
+    Upper { locale: CaseLocale },
 
     /// Convert text to lowercase.
     ///
     /// Applies Unicode-aware lowercase conversion to the entire string,
     /// properly handling international characters and special cases.
+    /// An optional locale (`tr` or `az`) selects Turkic rules, where `I`
+    /// becomes the dotless `ı`.
     ///
     /// # Examples
     ///
@@ -502,8 +1935,64 @@ pub enum StringOp {
     /// let template = Template::parse("{lower}").unwrap();
     /// assert_eq!(template.format("HELLO WORLD").unwrap(), "hello world");
     /// assert_eq!(template.format("CAFÉ").unwrap(), "café");
+    ///
+    /// let template = Template::parse("{lower:tr}").unwrap();
+    /// assert_eq!(template.format("DİYARBAKIR").unwrap(), "diyarbakır");
+    /// ```
+    Lower { locale: CaseLocale },
+
+    /// Convert the first character of text to titlecase.
+    ///
+    /// **Syntax:** `capitalize[:lower][:LOCALE]`
+    ///
+    /// The first grapheme cluster is converted to titlecase, which is its
+    /// uppercase except for ligatures and digraphs such as `ß`, `ﬁ` or `ǆ`
+    /// (giving `Ss`, `Fi` and `ǅ`). The rest of the text is kept as is, or
+    /// converted to lowercase with the `lower` option. The locale works as
+    /// for [`Upper`](StringOp::Upper).
+    ///
+    /// # Fields
+    ///
+    /// * `lower_rest` - Convert the text after the first grapheme to lowercase
+    /// * `locale` - Case mapping rules
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{capitalize}").unwrap();
+    /// assert_eq!(template.format("hello World").unwrap(), "Hello World");
+    ///
+    /// let template = Template::parse("{split: :..|map:{capitalize:lower}|join: }").unwrap();
+    /// assert_eq!(template.format("jOHN mcDONALD").unwrap(), "John Mcdonald");
+    ///
+    /// let template = Template::parse("{capitalize:tr}").unwrap();
+    /// assert_eq!(template.format("izmir").unwrap(), "İzmir");
     /// ```
-    Lower,
+    Capitalize {
+        lower_rest: bool,
+        locale: CaseLocale,
+    },
+
+    /// Swap the case of every letter.
+    ///
+    /// **Syntax:** `swapcase[:LOCALE]`
+    ///
+    /// Lowercase letters become uppercase and uppercase letters lowercase;
+    /// other characters, including titlecase digraphs such as `ǅ`, are kept.
+    /// The locale works as for [`Upper`](StringOp::Upper).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{swapcase}").unwrap();
+    /// assert_eq!(template.format("Hello World 42").unwrap(), "hELLO wORLD 42");
+    /// assert_eq!(template.format("Straße").unwrap(), "sTRASSE");
+    /// ```
+    SwapCase { locale: CaseLocale },
 
     /// Trim whitespace or custom characters from string ends.
     ///
@@ -548,355 +2037,2236 @@ pub enum StringOp {
         direction: TrimDirection,
     },
 
-    /// Extract substring by character index or range.
+    /// Collapse runs of whitespace or of given characters.
     ///
-    /// Supports Unicode-aware character indexing with negative indices
-    /// for counting from the end. Handles out-of-bounds gracefully.
+    /// Without characters, every run of whitespace becomes a single space,
+    /// like `replace:s/\s+/ /g` but without a regex. With characters, a run
+    /// of any of them is replaced by its first character.
+    ///
+    /// **Syntax:** `squeeze[:chars]`
+    ///
+    /// **Performance Optimization:** ASCII-only strings are scanned byte by byte,
+    /// and strings without runs to collapse are returned without copying.
     ///
     /// # Fields
     ///
-    /// * `range` - Character range specification
+    /// * `chars` - Characters to collapse (empty string means whitespace)
     ///
     /// # Examples
     ///
     /// ```rust
     /// use string_pipeline::Template;
     ///
-    /// // Single character
-    /// let template = Template::parse("{substring:1}").unwrap();
-    /// assert_eq!(template.format("hello").unwrap(), "e");
+    /// let template = Template::parse("{squeeze}").unwrap();
+    /// assert_eq!(template.format("a  b\t\tc").unwrap(), "a b c");
     ///
-    /// // Character range
-    /// let template = Template::parse("{substring:1..4}").unwrap();
-    /// assert_eq!(template.format("hello").unwrap(), "ell");
+    /// let template = Template::parse("{squeeze:-/}").unwrap();
+    /// assert_eq!(template.format("a--b//c").unwrap(), "a-b/c");
     /// ```
-    Substring { range: RangeSpec },
+    Squeeze { chars: String },
 
-    /// Append text to the end of a string.
+    /// Remove a prefix once, if present.
     ///
-    /// Adds the specified suffix to the end of the input string,
-    /// supporting escape sequences and Unicode text.
+    /// **Syntax:** `trim_prefix:PREFIX`
+    ///
+    /// Unlike `trim`, which removes any run of the given characters, this
+    /// removes the exact text a single time and leaves other strings unchanged.
     ///
     /// # Fields
     ///
-    /// * `suffix` - Text to append
+    /// * `prefix` - Text to remove from the beginning of the string
     ///
     /// # Examples
     ///
     /// ```rust
     /// use string_pipeline::Template;
     ///
-    /// let template = Template::parse("{append:!}").unwrap();
-    /// assert_eq!(template.format("hello").unwrap(), "hello!");
+    /// let template = Template::parse("{trim_prefix:v}").unwrap();
+    /// assert_eq!(template.format("v1.2.0").unwrap(), "1.2.0");
+    ///
+    /// // `trim:ab` would remove every leading `a` and `b`
+    /// let template = Template::parse("{trim_prefix:ab}").unwrap();
+    /// assert_eq!(template.format("abababc").unwrap(), "ababc");
     /// ```
-    Append { suffix: String },
+    TrimPrefix { prefix: String },
 
-    /// Prepend text to the beginning of a string.
+    /// Remove a suffix once, if present.
     ///
-    /// Adds the specified prefix to the beginning of the input string,
-    /// supporting escape sequences and Unicode text.
+    /// **Syntax:** `trim_suffix:SUFFIX`
     ///
     /// # Fields
     ///
-    /// * `prefix` - Text to prepend
+    /// * `suffix` - Text to remove from the end of the string
     ///
     /// # Examples
     ///
     /// ```rust
     /// use string_pipeline::Template;
     ///
-    /// let template = Template::parse("{prepend:>>}").unwrap();
-    /// assert_eq!(template.format("hello").unwrap(), ">>hello");
+    /// let template = Template::parse("{trim_suffix:.tar.gz}").unwrap();
+    /// assert_eq!(template.format("archive.tar.gz").unwrap(), "archive");
+    /// assert_eq!(template.format("archive.zip").unwrap(), "archive.zip");
     /// ```
-    Prepend { prefix: String },
+    TrimSuffix { suffix: String },
 
-    /// Surround text with the specified text on both sides.
+    /// Remove a regex match anchored at the string ends.
     ///
-    /// Adds the specified text to both the beginning and end of the input string,
-    /// supporting escape sequences and Unicode text. This operation has an alias `quote`.
+    /// **Syntax:** `trim_regex:PATTERN[:DIRECTION]`
+    ///
+    /// Removes one match of the pattern anchored at the start, the end, or
+    /// both. Use a repetition inside the pattern (`(?:ab)+`) to remove
+    /// repeated occurrences.
     ///
     /// # Fields
     ///
-    /// * `text` - Text to add to both sides of the string
+    /// * `pattern` - Regex pattern to remove
+    /// * `direction` - Which end(s) to trim from: `both` (default), `left`, `right`
     ///
     /// # Examples
     ///
     /// ```rust
     /// use string_pipeline::Template;
     ///
-    /// // Basic surrounding with quotes
-    /// let template = Template::parse("{surround:\"}").unwrap();
-    /// assert_eq!(template.format("hello").unwrap(), "\"hello\"");
-    ///
-    /// // Using the quote alias
-    /// let template = Template::parse("{quote:''}").unwrap();
-    /// assert_eq!(template.format("world").unwrap(), "''world''");
+    /// let template = Template::parse(r"{trim_regex:\d+}").unwrap();
+    /// assert_eq!(template.format("12abc34").unwrap(), "abc");
     ///
-    /// // Multiple characters
-    /// let template = Template::parse("{surround:**}").unwrap();
-    /// assert_eq!(template.format("text").unwrap(), "**text**");
+    /// let template = Template::parse(r"{trim_regex:\s*#+\s*:left}").unwrap();
+    /// assert_eq!(template.format("## Title ##").unwrap(), "Title ##");
     /// ```
-    Surround { text: String },
+    TrimRegex {
+        pattern: String,
+        direction: TrimDirection,
+    },
 
-    /// Remove ANSI escape sequences from text.
+    /// Extract substring by character index or range.
     ///
-    /// Strips color codes, cursor movement commands, and other ANSI escape
-    /// sequences while preserving the actual text content and Unicode characters.
+    /// Characters are grapheme clusters, so combining marks and emoji
+    /// sequences are never cut apart. Supports negative indices for counting
+    /// from the end and handles out-of-bounds gracefully.
+    ///
+    /// # Fields
+    ///
+    /// * `range` - Character range specification
     ///
     /// # Examples
     ///
     /// ```rust
     /// use string_pipeline::Template;
     ///
-    /// let template = Template::parse("{strip_ansi}").unwrap();
+    /// // Single character
+    /// let template = Template::parse("{substring:1}").unwrap();
+    /// assert_eq!(template.format("hello").unwrap(), "e");
+    ///
+    /// // Character range
+    /// let template = Template::parse("{substring:1..4}").unwrap();
+    /// assert_eq!(template.format("hello").unwrap(), "ell");
+    /// ```
+    Substring { range: RangeSpec },
+
+    /// Append text to the end of a string.
+    ///
+    /// Adds the specified suffix to the end of the input string,
+    /// supporting escape sequences and Unicode text.
+    ///
+    /// # Fields
+    ///
+    /// * `suffix` - Text to append
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{append:!}").unwrap();
+    /// assert_eq!(template.format("hello").unwrap(), "hello!");
+    /// ```
+    Append { suffix: String },
+
+    /// Prepend text to the beginning of a string.
+    ///
+    /// Adds the specified prefix to the beginning of the input string,
+    /// supporting escape sequences and Unicode text.
+    ///
+    /// # Fields
+    ///
+    /// * `prefix` - Text to prepend
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{prepend:>>}").unwrap();
+    /// assert_eq!(template.format("hello").unwrap(), ">>hello");
+    /// ```
+    Prepend { prefix: String },
+
+    /// Surround text with the specified text on both sides.
+    ///
+    /// **Syntax:** `surround:TEXT` or `surround:PREFIX:SUFFIX`
+    ///
+    /// Adds the specified text to both the beginning and end of the input string,
+    /// supporting escape sequences and Unicode text. A second argument gives a
+    /// different text for the end. This operation has an alias `quote`.
+    ///
+    /// # Fields
+    ///
+    /// * `prefix` - Text to add to the beginning of the string
+    /// * `suffix` - Text to add to the end of the string
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// // Basic surrounding with quotes
+    /// let template = Template::parse("{surround:\"}").unwrap();
+    /// assert_eq!(template.format("hello").unwrap(), "\"hello\"");
+    ///
+    /// // Using the quote alias
+    /// let template = Template::parse("{quote:''}").unwrap();
+    /// assert_eq!(template.format("world").unwrap(), "''world''");
+    ///
+    /// // Multiple characters
+    /// let template = Template::parse("{surround:**}").unwrap();
+    /// assert_eq!(template.format("text").unwrap(), "**text**");
+    ///
+    /// // Different prefix and suffix, applied to each item
+    /// let template = Template::parse("{split:,:..|map:{surround:[:]}|join:}").unwrap();
+    /// assert_eq!(template.format("a,b").unwrap(), "[a][b]");
+    /// ```
+    Surround { prefix: String, suffix: String },
+
+    /// Remove ANSI escape sequences from text.
+    ///
+    /// Strips color codes, cursor movement commands, and other ANSI escape
+    /// sequences while preserving the actual text content and Unicode characters.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{strip_ansi}").unwrap();
     /// let input = "\x1b[31mRed Text\x1b[0m";
     /// assert_eq!(template.format(input).unwrap(), "Red Text");
     /// ```
     StripAnsi,
 
-    /// Keep only list items matching a regex pattern.
+    /// Remove accents and other combining marks.
     ///
-    /// **Syntax:** `filter:PATTERN`
+    /// Decomposes the text (NFD), drops combining marks and recomposes it,
+    /// so letters keep their base form. Characters without a decomposition,
+    /// like `ß` or `ø`, are left unchanged.
     ///
-    /// Filters a list to retain only items that match the specified regex pattern.
-    /// When applied to a single string, keeps the string if it matches or returns empty.
+    /// # Examples
     ///
-    /// **Behavior on Different Input Types:**
-    /// - **List:** Keeps items that match the pattern
-    /// - **String:** Returns the string if it matches, empty string otherwise
+    /// ```rust
+    /// use string_pipeline::Template;
     ///
-    /// **Performance Optimization:** Regex patterns are compiled and cached internally
-    /// for improved performance in repeated operations.
+    /// let template = Template::parse("{deaccent}").unwrap();
+    /// assert_eq!(template.format("Crème brûlée").unwrap(), "Creme brulee");
+    /// ```
+    Deaccent,
+
+    /// Transliterate text to ASCII.
+    ///
+    /// Lossy: every character is replaced by its closest ASCII spelling
+    /// (`ß` becomes `ss`, `Ж` becomes `Zh`), and characters without one are
+    /// dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{ascii}").unwrap();
+    /// assert_eq!(template.format("Straße in Køln").unwrap(), "Strasse in Koln");
+    /// ```
+    Ascii,
+
+    /// Turn text into a URL-safe slug.
+    ///
+    /// Transliterates to ASCII, lowercases, and joins each run of letters and
+    /// digits with `separator` (`-` by default). Leading and trailing
+    /// punctuation is dropped.
+    ///
+    /// **Syntax:** `slugify[:separator]`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{slugify}").unwrap();
+    /// assert_eq!(template.format("  Héllo, Wörld! ").unwrap(), "hello-world");
+    ///
+    /// let template = Template::parse("{slugify:_}").unwrap();
+    /// assert_eq!(template.format("Café Society").unwrap(), "cafe_society");
+    /// ```
+    Slugify { separator: String },
+
+    /// Convert text to a Unicode normalization form.
+    ///
+    /// Canonically equivalent strings, like a precomposed `é` and `e`
+    /// followed by a combining acute accent, become identical after
+    /// normalization. The compatibility forms also fold variants such as
+    /// `ﬁ` into `fi`.
+    ///
+    /// **Syntax:** `normalize:nfc|nfd|nfkc|nfkd`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{normalize:nfc}").unwrap();
+    /// assert_eq!(template.format("cafe\u{301}").unwrap(), "café");
+    ///
+    /// let template = Template::parse("{normalize:nfkc}").unwrap();
+    /// assert_eq!(template.format("ﬁle").unwrap(), "file");
+    /// ```
+    Normalize { form: NormalForm },
+
+    /// Format an integer as an English ordinal, or parse one back.
+    ///
+    /// **Syntax:** `ordinal[:parse]`
+    ///
+    /// The value must be an integer, optionally surrounded by whitespace.
+    /// With `parse`, an ordinal such as `21st` is turned back into `21`; its
+    /// suffix must match the number, ignoring case.
     ///
     /// # Fields
     ///
-    /// * `pattern` - Regex pattern for matching items
+    /// * `parse` - Convert an ordinal back into an integer
     ///
     /// # Examples
     ///
     /// ```rust
     /// use string_pipeline::Template;
     ///
-    /// // Keep items starting with vowels
-    /// let template = Template::parse("{split:,:..|filter:^[aeiou]|join:,}").unwrap();
-    /// assert_eq!(template.format("apple,banana,orange,grape").unwrap(), "apple,orange");
+    /// let template = Template::parse("{split:,:..|map:{ordinal}|join:,}").unwrap();
+    /// assert_eq!(template.format("1,2,3,11,112").unwrap(), "1st,2nd,3rd,11th,112th");
     ///
-    /// // Keep items containing numbers
-    /// let template = Template::parse("{split:,:..|filter:\\d+|join:,}").unwrap();
-    /// assert_eq!(template.format("item1,test,file22,doc").unwrap(), "item1,file22");
+    /// let template = Template::parse("{ordinal:parse}").unwrap();
+    /// assert_eq!(template.format("42nd").unwrap(), "42");
+    /// ```
+    Ordinal { parse: bool },
+
+    /// Spell out an integer in English words, or parse words back.
     ///
-    /// // Filter .txt files
-    /// let template = Template::parse("{split:,:..|filter:\\.txt$|join:\\n}").unwrap();
-    /// assert_eq!(template.format("file.txt,readme.md,data.txt").unwrap(), "file.txt\ndata.txt");
+    /// **Syntax:** `to_words[:parse]`
+    ///
+    /// Tens and units are joined with a hyphen, negative numbers start with
+    /// `minus`, and no `and` is written after hundreds. With `parse`, words
+    /// are turned back into an integer; hyphens and spaces are
+    /// interchangeable, and `and` between words is allowed.
+    ///
+    /// # Fields
+    ///
+    /// * `parse` - Convert number words back into an integer
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{to_words}").unwrap();
+    /// assert_eq!(template.format("1042").unwrap(), "one thousand forty-two");
+    ///
+    /// let template = Template::parse("{to_words:parse}").unwrap();
+    /// assert_eq!(template.format("three hundred and five").unwrap(), "305");
     /// ```
-    Filter { pattern: String },
+    ToWords { parse: bool },
 
-    /// Remove list items matching a regex pattern.
+    /// Format an integer as a Roman numeral, or parse one back.
     ///
-    /// **Syntax:** `filter_not:PATTERN`
+    /// **Syntax:** `roman[:parse]`
     ///
-    /// Filters a list to remove items that match the specified regex pattern.
-    /// When applied to a single string, removes the string if it matches.
+    /// Numbers from 1 to 3999 are written in uppercase with the standard
+    /// subtractive form. With `parse`, a numeral in that form, in either
+    /// case, is turned back into an integer.
     ///
-    /// **Behavior on Different Input Types:**
-    /// - **List:** Removes items that match the pattern
-    /// - **String:** Returns empty string if it matches, original string otherwise
+    /// # Fields
+    ///
+    /// * `parse` - Convert a Roman numeral back into an integer
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{roman}").unwrap();
+    /// assert_eq!(template.format("2024").unwrap(), "MMXXIV");
+    ///
+    /// let template = Template::parse("{roman:parse}").unwrap();
+    /// assert_eq!(template.format("xiv").unwrap(), "14");
+    /// ```
+    Roman { parse: bool },
+
+    /// Get the last component of a path.
+    ///
+    /// **Syntax:** `basename[:windows]`
+    ///
+    /// Trailing separators are ignored, and a path made only of separators
+    /// gives the root. With `windows`, `\` also separates components and a
+    /// leading drive such as `C:` is not part of the name. Paths are handled
+    /// as text, without touching the file system.
     ///
     /// # Fields
     ///
-    /// * `pattern` - Regex pattern for matching items to remove
+    /// * `style` - Which separators and prefixes are recognized
     ///
     /// # Examples
     ///
     /// ```rust
     /// use string_pipeline::Template;
     ///
-    /// // Remove specific items
-    /// let template = Template::parse("{split:,:..|filter_not:banana|join:,}").unwrap();
-    /// assert_eq!(template.format("apple,banana,orange").unwrap(), "apple,orange");
+    /// let template = Template::parse("{basename}").unwrap();
+    /// assert_eq!(template.format("/var/log/syslog.1").unwrap(), "syslog.1");
+    /// assert_eq!(template.format("src/bin/").unwrap(), "bin");
     ///
-    /// // Remove comments (lines starting with #)
-    /// let template = Template::parse("{split:\\n:..|filter_not:^#|join:\\n}").unwrap();
-    /// let input = "line1\n# comment\nline2\n# another comment\nline3";
-    /// assert_eq!(template.format(input).unwrap(), "line1\nline2\nline3");
+    /// let template = Template::parse("{basename:windows}").unwrap();
+    /// assert_eq!(template.format("C:\\Users\\me\\notes.txt").unwrap(), "notes.txt");
+    /// ```
+    Basename { style: PathStyle },
+
+    /// Remove the last component of a path.
     ///
-    /// // Remove empty lines
-    /// let template = Template::parse("{split:\\n:..|filter_not:^$|join:\\n}").unwrap();
-    /// assert_eq!(template.format("line1\n\nline2\n\nline3").unwrap(), "line1\nline2\nline3");
+    /// **Syntax:** `dirname[:windows]`
+    ///
+    /// Works like the `dirname` command: a path without a directory gives
+    /// `.`, and the root is its own parent. See [`Basename`](StringOp::Basename)
+    /// for the `windows` option.
+    ///
+    /// # Fields
+    ///
+    /// * `style` - Which separators and prefixes are recognized
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{dirname}").unwrap();
+    /// assert_eq!(template.format("/var/log/syslog").unwrap(), "/var/log");
+    /// assert_eq!(template.format("notes.txt").unwrap(), ".");
+    /// assert_eq!(template.format("/etc").unwrap(), "/");
     /// ```
-    FilterNot { pattern: String },
+    Dirname { style: PathStyle },
 
-    /// Select a range of items from a list.
+    /// Get the extension of the last component of a path, with its dot.
     ///
-    /// Extracts a subset of items from a list using range syntax,
-    /// supporting negative indexing and various range types.
+    /// **Syntax:** `extname[:windows]`
+    ///
+    /// The extension starts at the last dot of the name. A name without a
+    /// dot, or whose only dot is its first character, has no extension and
+    /// gives an empty string.
     ///
     /// # Fields
     ///
-    /// * `range` - Range specification for item selection
+    /// * `style` - Which separators and prefixes are recognized
     ///
     /// # Examples
     ///
     /// ```rust
     /// use string_pipeline::Template;
     ///
-    /// let template = Template::parse("{split:,:..|slice:1..3|join:,}").unwrap();
-    /// assert_eq!(template.format("a,b,c,d,e").unwrap(), "b,c");
+    /// let template = Template::parse("{extname}").unwrap();
+    /// assert_eq!(template.format("dist/app.tar.gz").unwrap(), ".gz");
+    /// assert_eq!(template.format("~/.bashrc").unwrap(), "");
     /// ```
-    Slice { range: RangeSpec },
+    Extname { style: PathStyle },
 
-    /// Apply a sub-pipeline to each item in a list.
+    /// Remove the extension of the last component of a path.
     ///
-    /// Maps a sequence of operations over each item in a list, enabling
-    /// complex per-item transformations while maintaining list structure.
+    /// **Syntax:** `strip_ext[:windows]`
+    ///
+    /// The extension is found as for [`Extname`](StringOp::Extname); paths
+    /// without one are left unchanged.
     ///
     /// # Fields
     ///
-    /// * `operations` - List of operations to apply to each item
+    /// * `style` - Which separators and prefixes are recognized
     ///
     /// # Examples
     ///
     /// ```rust
     /// use string_pipeline::Template;
     ///
-    /// let template = Template::parse("{split:,:..|map:{trim|upper}|join:,}").unwrap();
-    /// assert_eq!(template.format(" a , b , c ").unwrap(), "A,B,C");
+    /// let template = Template::parse("{strip_ext}").unwrap();
+    /// assert_eq!(template.format("dist/app.tar.gz").unwrap(), "dist/app.tar");
     /// ```
-    Map {
-        operations: Box<SmallVec<[StringOp; 8]>>,
-    },
+    StripExt { style: PathStyle },
 
-    /// Sort list items alphabetically.
+    /// Replace the extension of the last component of a path.
     ///
-    /// Sorts a list of strings in ascending or descending alphabetical order
-    /// using lexicographic comparison with Unicode support.
+    /// **Syntax:** `with_ext:EXT[:windows]`
+    ///
+    /// A name without an extension gets one added. The leading dot of `EXT`
+    /// is optional, and an empty `EXT` removes the extension.
     ///
     /// # Fields
     ///
-    /// * `direction` - Sort direction (ascending or descending)
+    /// * `ext` - The new extension
+    /// * `style` - Which separators and prefixes are recognized
     ///
     /// # Examples
     ///
     /// ```rust
     /// use string_pipeline::Template;
     ///
-    /// let template = Template::parse("{split:,:..|sort|join:,}").unwrap();
-    /// assert_eq!(template.format("c,a,b").unwrap(), "a,b,c");
+    /// let template = Template::parse("{with_ext:md}").unwrap();
+    /// assert_eq!(template.format("docs/readme.txt").unwrap(), "docs/readme.md");
+    /// assert_eq!(template.format("Makefile").unwrap(), "Makefile.md");
+    /// ```
+    WithExt { ext: String, style: PathStyle },
+
+    /// Normalize a path lexically.
     ///
-    /// let template = Template::parse("{split:,:..|sort:desc|join:,}").unwrap();
-    /// assert_eq!(template.format("a,b,c").unwrap(), "c,b,a");
+    /// **Syntax:** `normalize_path[:windows]`
+    ///
+    /// Removes `.` components, duplicate and trailing separators, and `..`
+    /// components that follow a directory name. Leading `..` components are
+    /// kept in relative paths and dropped at the root, and an empty result
+    /// becomes `.`. With `windows`, components are joined with `\`.
+    /// Symbolic links are not resolved, so `a/link/..` becomes `a` even if
+    /// `link` points elsewhere.
+    ///
+    /// # Fields
+    ///
+    /// * `style` - Which separators and prefixes are recognized
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{normalize_path}").unwrap();
+    /// assert_eq!(template.format("./src//pipeline/../lib.rs").unwrap(), "src/lib.rs");
+    /// assert_eq!(template.format("/../etc/").unwrap(), "/etc");
+    ///
+    /// let template = Template::parse("{normalize_path:windows}").unwrap();
+    /// assert_eq!(template.format("C:/Users/./me").unwrap(), "C:\\Users\\me");
     /// ```
-    Sort { direction: SortDirection },
+    NormalizePath { style: PathStyle },
 
-    /// Reverse a string or list order.
+    /// Get the scheme of a URL, in lowercase.
     ///
-    /// For strings, reverses the character order. For lists, reverses the item order.
-    /// Properly handles Unicode characters and grapheme clusters.
+    /// **Syntax:** `url_scheme`
     ///
-    /// **Performance Optimization:** ASCII-only strings use optimized byte-level reversal.
+    /// URLs are split into parts without being validated, and a missing
+    /// part gives an empty string.
     ///
     /// # Examples
     ///
     /// ```rust
     /// use string_pipeline::Template;
     ///
-    /// // Reverse string
-    /// let template = Template::parse("{reverse}").unwrap();
-    /// assert_eq!(template.format("hello").unwrap(), "olleh");
+    /// let template = Template::parse("{url_scheme}").unwrap();
+    /// assert_eq!(template.format("HTTPS://example.com/").unwrap(), "https");
+    /// assert_eq!(template.format("/index.html").unwrap(), "");
+    /// ```
+    UrlScheme,
+
+    /// Get the host of a URL, in lowercase.
     ///
-    /// // Reverse list
-    /// let template = Template::parse("{split:,:..|reverse|join:,}").unwrap();
-    /// assert_eq!(template.format("a,b,c").unwrap(), "c,b,a");
+    /// **Syntax:** `url_host`
+    ///
+    /// User information and port are left out, and IPv6 addresses keep
+    /// their brackets. Input without a `//` authority has no host.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{url_host}").unwrap();
+    /// assert_eq!(template.format("https://me@Example.com:8443/a?b=c").unwrap(), "example.com");
+    ///
+    /// // Summarize the domains of a list of URLs
+    /// let template = Template::parse("{split:\\n:..|map:{url_host}|unique|sort|join:,}").unwrap();
+    /// let urls = "https://b.org/x\nhttp://a.com/\nhttps://b.org/y";
+    /// assert_eq!(template.format(urls).unwrap(), "a.com,b.org");
+    /// ```
+    UrlHost,
+
+    /// Get the path of a URL, without query or fragment.
+    ///
+    /// **Syntax:** `url_path`
+    ///
+    /// A URL with a host but no path has the path `/`. Input without a
+    /// scheme, such as the request target of an access log, is read as a
+    /// path. The path is not percent-decoded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{url_path}").unwrap();
+    /// assert_eq!(template.format("https://example.com/docs/intro?lang=en").unwrap(), "/docs/intro");
+    /// assert_eq!(template.format("/search?q=rust#top").unwrap(), "/search");
+    /// ```
+    UrlPath,
+
+    /// Get the value of a query parameter of a URL.
+    ///
+    /// **Syntax:** `url_query:KEY`
+    ///
+    /// Gives the value of the first parameter named `KEY`, with `%XX`
+    /// escapes and `+` decoded, or an empty string if there is none.
+    ///
+    /// # Fields
+    ///
+    /// * `key` - The parameter name, compared after decoding
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{url_query:q}").unwrap();
+    /// assert_eq!(template.format("/search?q=hello+w%C3%B6rld&page=2").unwrap(), "hello wörld");
+    /// assert_eq!(template.format("/search?page=2").unwrap(), "");
+    /// ```
+    UrlQuery { key: String },
+
+    /// Set the foreground color of the value for terminal output.
+    ///
+    /// **Syntax:** `color:COLOR`
+    ///
+    /// The value is wrapped between the ANSI escape sequence selecting the
+    /// color and the one restoring the default foreground, so other styles
+    /// are left as they are. Nothing is added when the template has styling
+    /// switched off with [`Template::with_color`].
+    ///
+    /// # Fields
+    ///
+    /// * `color` - A name such as `red` or `bright_red`, a palette index
+    ///   from 0 to 255, or `#rrggbb`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{color:red}").unwrap();
+    /// assert_eq!(template.format("error").unwrap(), "\x1b[31merror\x1b[39m");
+    ///
+    /// let template = Template::parse("{color:#ff8800}").unwrap();
+    /// assert_eq!(template.format("warn").unwrap(), "\x1b[38;2;255;136;0mwarn\x1b[39m");
+    ///
+    /// let plain = Template::parse("{color:red}").unwrap().with_color(false);
+    /// assert_eq!(plain.format("error").unwrap(), "error");
+    /// ```
+    Color { color: TermColor },
+
+    /// Set the background color of the value for terminal output.
+    ///
+    /// **Syntax:** `bg:COLOR`
+    ///
+    /// Like [`Color`](StringOp::Color), restoring the default background at
+    /// the end of the value.
+    ///
+    /// # Fields
+    ///
+    /// * `color` - A name, palette index or `#rrggbb`, as for `color`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{bg:blue}").unwrap();
+    /// assert_eq!(template.format("note").unwrap(), "\x1b[44mnote\x1b[49m");
+    /// ```
+    Background { color: TermColor },
+
+    /// Make the value bold for terminal output.
+    ///
+    /// **Syntax:** `bold`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{bold|color:green}").unwrap();
+    /// assert_eq!(template.format("ok").unwrap(), "\x1b[32m\x1b[1mok\x1b[22m\x1b[39m");
+    /// ```
+    Bold,
+
+    /// Underline the value for terminal output.
+    ///
+    /// **Syntax:** `underline`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{underline}").unwrap();
+    /// assert_eq!(template.format("link").unwrap(), "\x1b[4mlink\x1b[24m");
+    /// ```
+    Underline,
+
+    /// Reset every terminal style at the end of the value.
+    ///
+    /// **Syntax:** `style_reset`
+    ///
+    /// Appends the ANSI sequence that turns all styles off, so that styles
+    /// left open by the input, such as colored output of another program,
+    /// do not leak into the rest of the output.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{style_reset}").unwrap();
+    /// assert_eq!(template.format("\x1b[31mred").unwrap(), "\x1b[31mred\x1b[0m");
+    /// ```
+    StyleReset,
+
+    /// Highlight every match of a regex within the value.
+    ///
+    /// **Syntax:** `highlight:PATTERN[:COLOR]` or `highlight:PATTERN:mark:OPEN[:CLOSE]`
+    ///
+    /// Matches are colored like [`Color`](StringOp::Color), in red unless
+    /// another color is given, or put between the `OPEN` and `CLOSE`
+    /// markers. `CLOSE` defaults to `OPEN`. A trailing color or `mark:` is
+    /// never part of the pattern. Colors follow the styling switch of
+    /// [`Template::with_color`], while markers are always written. Empty
+    /// matches are not highlighted.
+    ///
+    /// # Fields
+    ///
+    /// * `pattern` - Regular expression to highlight
+    /// * `style` - How matches are highlighted
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{highlight:ERROR|WARN:yellow}").unwrap();
+    /// assert_eq!(
+    ///     template.format("WARN: disk").unwrap(),
+    ///     "\x1b[33mWARN\x1b[39m: disk"
+    /// );
+    ///
+    /// let template = Template::parse("{highlight:\\d+:mark:[:]}").unwrap();
+    /// assert_eq!(template.format("took 42 ms").unwrap(), "took [42] ms");
+    ///
+    /// let template = Template::parse("{split:\n:..|map:{highlight:ERROR:red}|join:\n}").unwrap();
+    /// assert_eq!(
+    ///     template.format("ok\nan ERROR").unwrap(),
+    ///     "ok\nan \x1b[31mERROR\x1b[39m"
+    /// );
+    /// ```
+    Highlight {
+        pattern: String,
+        style: HighlightStyle,
+    },
+
+    /// Replace a path with the contents of the file it names.
+    ///
+    /// **Syntax:** `read_file`
+    ///
+    /// Relative paths are resolved against the working directory and the
+    /// contents are returned unchanged, trailing newline included. Reading
+    /// files must be enabled with [`Template::with_fs_access`](crate::Template::with_fs_access),
+    /// and files larger than [`Limits::max_file_size`] (10 MiB by default)
+    /// are rejected.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{read_file|split:\n:0}")
+    ///     .unwrap()
+    ///     .with_fs_access(true);
+    /// assert_eq!(template.format("Cargo.toml").unwrap(), "[package]");
+    ///
+    /// // Read every file of a list
+    /// let template = Template::parse("{split:,:..|map:{read_file|len:bytes}|join:,}")
+    ///     .unwrap()
+    ///     .with_fs_access(true);
+    /// assert!(template.format("Cargo.toml,LICENSE").is_ok());
+    /// ```
+    ReadFile,
+
+    /// Pipe the value through an external command.
+    ///
+    /// **Syntax:** `exec:COMMAND`
+    ///
+    /// Runs `COMMAND` with the platform shell (`sh -c` on Unix, `cmd /C` on
+    /// Windows), writes the value to its stdin and replaces the value with
+    /// its stdout, minus trailing newlines. Running commands must be enabled
+    /// with [`Template::with_exec_access`](crate::Template::with_exec_access).
+    /// Commands that exit unsuccessfully fail the operation with their
+    /// stderr, and commands running longer than [`Limits::exec_timeout`]
+    /// (10 seconds by default) are killed along with every process they
+    /// started. Output longer than [`Limits::max_output_len`] (64 MiB by
+    /// default) fails the operation.
+    ///
+    /// # Fields
+    ///
+    /// * `command` - Shell command line to run
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(unix)] {
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{exec:tr a-z A-Z}")
+    ///     .unwrap()
+    ///     .with_exec_access(true);
+    /// assert_eq!(template.format("hello").unwrap(), "HELLO");
+    ///
+    /// // `|` and `:` must be escaped inside the command
+    /// let template = Template::parse("{split:,:..|map:{exec:rev \\| tr -d x}|join:,}")
+    ///     .unwrap()
+    ///     .with_exec_access(true);
+    /// assert_eq!(template.format("abx,xcd").unwrap(), "ba,dc");
+    /// # }
+    /// ```
+    Exec { command: String },
+
+    /// Keep only list items matching a regex pattern.
+    ///
+    /// **Syntax:** `filter:PATTERN`
+    ///
+    /// Filters a list to retain only items that match the specified regex pattern.
+    /// When applied to a single string, keeps the string if it matches or returns empty.
+    ///
+    /// **Behavior on Different Input Types:**
+    /// - **List:** Keeps items that match the pattern
+    /// - **String:** Returns the string if it matches, empty string otherwise
+    ///
+    /// **Performance Optimization:** Regex patterns are compiled and cached internally
+    /// for improved performance in repeated operations.
+    ///
+    /// # Fields
+    ///
+    /// * `pattern` - Regex pattern for matching items
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// // Keep items starting with vowels
+    /// let template = Template::parse("{split:,:..|filter:^[aeiou]|join:,}").unwrap();
+    /// assert_eq!(template.format("apple,banana,orange,grape").unwrap(), "apple,orange");
+    ///
+    /// // Keep items containing numbers
+    /// let template = Template::parse("{split:,:..|filter:\\d+|join:,}").unwrap();
+    /// assert_eq!(template.format("item1,test,file22,doc").unwrap(), "item1,file22");
+    ///
+    /// // Filter .txt files
+    /// let template = Template::parse("{split:,:..|filter:\\.txt$|join:\\n}").unwrap();
+    /// assert_eq!(template.format("file.txt,readme.md,data.txt").unwrap(), "file.txt\ndata.txt");
+    /// ```
+    Filter { pattern: String },
+
+    /// Remove list items matching a regex pattern.
+    ///
+    /// **Syntax:** `filter_not:PATTERN`
+    ///
+    /// Filters a list to remove items that match the specified regex pattern.
+    /// When applied to a single string, removes the string if it matches.
+    ///
+    /// **Behavior on Different Input Types:**
+    /// - **List:** Removes items that match the pattern
+    /// - **String:** Returns empty string if it matches, original string otherwise
+    ///
+    /// # Fields
+    ///
+    /// * `pattern` - Regex pattern for matching items to remove
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// // Remove specific items
+    /// let template = Template::parse("{split:,:..|filter_not:banana|join:,}").unwrap();
+    /// assert_eq!(template.format("apple,banana,orange").unwrap(), "apple,orange");
+    ///
+    /// // Remove comments (lines starting with #)
+    /// let template = Template::parse("{split:\\n:..|filter_not:^#|join:\\n}").unwrap();
+    /// let input = "line1\n# comment\nline2\n# another comment\nline3";
+    /// assert_eq!(template.format(input).unwrap(), "line1\nline2\nline3");
+    ///
+    /// // Remove empty lines
+    /// let template = Template::parse("{split:\\n:..|filter_not:^$|join:\\n}").unwrap();
+    /// assert_eq!(template.format("line1\n\nline2\n\nline3").unwrap(), "line1\nline2\nline3");
+    /// ```
+    FilterNot { pattern: String },
+
+    /// Keep list items starting with a literal prefix.
+    ///
+    /// **Syntax:** `filter_prefix:PREFIX`
+    ///
+    /// Like [`Filter`](StringOp::Filter) with `^PREFIX`, without having to
+    /// escape regex metacharacters. A single string is kept if it starts
+    /// with the prefix, and becomes empty otherwise.
+    ///
+    /// # Fields
+    ///
+    /// * `prefix` - Text that kept items start with
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:\\n:..|filter_prefix:ERR|join:,}").unwrap();
+    /// assert_eq!(template.format("ERR disk\nOK\nERR net").unwrap(), "ERR disk,ERR net");
+    ///
+    /// // No escaping needed for regex metacharacters
+    /// let template = Template::parse("{split:,:..|filter_prefix:[x]|join:,}").unwrap();
+    /// assert_eq!(template.format("[x] done,[ ] todo").unwrap(), "[x] done");
+    /// ```
+    FilterPrefix { prefix: String },
+
+    /// Keep list items ending with a literal suffix.
+    ///
+    /// **Syntax:** `filter_suffix:SUFFIX`
+    ///
+    /// Like [`Filter`](StringOp::Filter) with `SUFFIX$`, without having to
+    /// escape regex metacharacters. A single string is kept if it ends with
+    /// the suffix, and becomes empty otherwise.
+    ///
+    /// # Fields
+    ///
+    /// * `suffix` - Text that kept items end with
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:,:..|filter_suffix:.rs|join:,}").unwrap();
+    /// assert_eq!(template.format("main.rs,README.md,lib.rs").unwrap(), "main.rs,lib.rs");
+    /// ```
+    FilterSuffix { suffix: String },
+
+    /// Keep list items containing a literal substring.
+    ///
+    /// **Syntax:** `filter_contains:TEXT`
+    ///
+    /// Like [`Filter`](StringOp::Filter) with an escaped pattern. A single
+    /// string is kept if it contains the text, and becomes empty otherwise.
+    ///
+    /// # Fields
+    ///
+    /// * `needle` - Text that kept items contain
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:,:..|filter_contains:(beta)|join:,}").unwrap();
+    /// assert_eq!(template.format("v1,v2 (beta),v3").unwrap(), "v2 (beta)");
+    /// ```
+    FilterContains { needle: String },
+
+    /// Keep list items that were valid UTF-8 before being read.
+    ///
+    /// **Syntax:** `filter_valid_utf8`
+    ///
+    /// Text is always valid UTF-8 once read, but lossy reading, as with
+    /// [`Template::format_lossy`] or the CLI's `--lossy`, turns each invalid
+    /// byte sequence into U+FFFD. This filter drops the items holding that
+    /// replacement character, so corrupt records can be left out instead of
+    /// failing the whole input. A string is kept or becomes empty, like with
+    /// [`Filter`](StringOp::Filter).
+    ///
+    /// Input that was valid UTF-8 is kept whole, even where it holds U+FFFD
+    /// itself. Once lossy reading replaced some bytes, every item holding
+    /// U+FFFD is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:\\n:..|filter_valid_utf8}").unwrap();
+    /// assert_eq!(template.format_lossy(b"ok\nbad\xff\nfine").unwrap(), "ok\nfine");
+    /// ```
+    FilterValidUtf8,
+
+    /// Keep list items that pass the Luhn check.
+    ///
+    /// **Syntax:** `filter_luhn`
+    ///
+    /// The Luhn check catches most typing errors in payment card, IMEI and
+    /// similar identification numbers. Spaces and dashes between digits are
+    /// ignored, and items with other characters or fewer than two digits are
+    /// dropped. A string is kept or becomes empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:,:..|filter_luhn}").unwrap();
+    /// assert_eq!(
+    ///     template.format("4111 1111 1111 1111,4111 1111 1111 1112,79927398713").unwrap(),
+    ///     "4111 1111 1111 1111,79927398713"
+    /// );
+    /// ```
+    FilterLuhn,
+
+    /// Keep list items whose checksum has the expected value.
+    ///
+    /// **Syntax:** `filter_checksum:ALGORITHM:EXPECTED`
+    ///
+    /// The checksum is computed over the UTF-8 bytes of each item with
+    /// `crc32` or `adler32`, and compared with `EXPECTED`, written as up to
+    /// eight hexadecimal digits in any case. A string is kept or becomes
+    /// empty.
+    ///
+    /// # Fields
+    ///
+    /// * `algorithm` - The checksum to compute
+    /// * `expected` - The checksum an item must have to be kept
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:,:..|filter_checksum:crc32:CBF43926}").unwrap();
+    /// assert_eq!(template.format("123456789,12345678").unwrap(), "123456789");
+    /// ```
+    FilterChecksum {
+        algorithm: ChecksumAlgorithm,
+        expected: u32,
+    },
+
+    /// Keep list items for which a sub-pipeline result passes a check.
+    ///
+    /// **Syntax:** `filter_by:{operations}[:PATTERN]`
+    ///
+    /// Runs the operations on each item, like [`Map`](StringOp::Map), and
+    /// keeps the original item if the result matches the regex `PATTERN`,
+    /// or is non-empty when no pattern is given.
+    ///
+    /// # Fields
+    ///
+    /// * `operations` - Operations computing the value checked for an item
+    /// * `pattern` - Regex the result must match, or `None` to require a non-empty result
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// // Keep lines whose third field is at least 100
+    /// let template = Template::parse(r"{split:\n:..|filter_by:{split: :2}:^[1-9]\d{2,}$|join:,}").unwrap();
+    /// assert_eq!(template.format("a x 99\nb y 250\nc z 100").unwrap(), "b y 250,c z 100");
+    ///
+    /// // Keep items containing a digit, without writing the pattern twice
+    /// let template = Template::parse(r"{split:,:..|filter_by:{regex_extract:\d}|join:,}").unwrap();
+    /// assert_eq!(template.format("a1,b,c2").unwrap(), "a1,c2");
+    /// ```
+    FilterBy {
+        operations: Box<SmallVec<[StringOp; 8]>>,
+        pattern: Option<String>,
+    },
+
+    /// Select a range of items from a list.
+    ///
+    /// Extracts a subset of items from a list using range syntax,
+    /// supporting negative indexing and various range types.
+    ///
+    /// # Fields
+    ///
+    /// * `range` - Range specification for item selection
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:,:..|slice:1..3|join:,}").unwrap();
+    /// assert_eq!(template.format("a,b,c,d,e").unwrap(), "b,c");
+    /// ```
+    Slice { range: RangeSpec },
+
+    /// Select a range of items from a list, clamping the range to the list.
+    ///
+    /// Works like [`Slice`](StringOp::Slice), which also clamps by default.
+    /// Under [`Template::with_strict_ranges`], where `slice` rejects
+    /// out-of-bounds ranges, `clamp` marks the places where clamping is
+    /// wanted.
+    ///
+    /// # Fields
+    ///
+    /// * `range` - Range specification for item selection
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:,:..|clamp:1..10|join:,}")
+    ///     .unwrap()
+    ///     .with_strict_ranges(true);
+    /// assert_eq!(template.format("a,b,c").unwrap(), "b,c");
+    /// ```
+    Clamp { range: RangeSpec },
+
+    /// Apply a sub-pipeline to each item in a list.
+    ///
+    /// Maps a sequence of operations over each item in a list, enabling
+    /// complex per-item transformations while maintaining list structure.
+    ///
+    /// # Fields
+    ///
+    /// * `operations` - List of operations to apply to each item
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:,:..|map:{trim|upper}|join:,}").unwrap();
+    /// assert_eq!(template.format(" a , b , c ").unwrap(), "A,B,C");
+    /// ```
+    Map {
+        operations: Box<SmallVec<[StringOp; 8]>>,
+    },
+
+    /// Apply a sub-pipeline to each item in a list, dropping the items it
+    /// fails on.
+    ///
+    /// **Syntax:** `map_fallible:{operation1|operation2|...}`
+    ///
+    /// Works like [`Map`](StringOp::Map), except that an item for which one
+    /// of the operations returns an error is left out of the result instead
+    /// of failing the whole format. Errors from [`Limits`] still fail it.
+    ///
+    /// # Fields
+    ///
+    /// * `operations` - List of operations to apply to each item
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:,:..|map_fallible:{roman}|join:,}").unwrap();
+    /// assert_eq!(template.format("4,x,9").unwrap(), "IV,IX");
+    /// ```
+    MapFallible {
+        operations: Box<SmallVec<[StringOp; 8]>>,
+    },
+
+    /// Apply a sub-pipeline to a string, keeping the string or using a
+    /// fallback if it fails.
+    ///
+    /// **Syntax:** `try:{operation1|operation2|...}[:FALLBACK]`
+    ///
+    /// If one of the operations returns an error, the result is `FALLBACK`,
+    /// or the input unchanged without one. Errors from [`Limits`] are not
+    /// caught. Inside [`Map`](StringOp::Map), one bad item no longer aborts
+    /// the whole list.
+    ///
+    /// # Fields
+    ///
+    /// * `operations` - List of operations to apply to the string
+    /// * `fallback` - Value to use when an operation fails, instead of the input
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:,:..|map:{try:{ordinal}}|join:,}").unwrap();
+    /// assert_eq!(template.format("1,x,3").unwrap(), "1st,x,3rd");
+    ///
+    /// let template = Template::parse("{split:,:..|map:{try:{ordinal}:?}|join:,}").unwrap();
+    /// assert_eq!(template.format("1,x,3").unwrap(), "1st,?,3rd");
+    /// ```
+    Try {
+        operations: Box<SmallVec<[StringOp; 8]>>,
+        fallback: Option<String>,
+    },
+
+    /// Render a template once for each list item.
+    ///
+    /// **Syntax:** `{each:{OPS}:BODY}`
+    ///
+    /// Written as a whole template section: `OPS` produce the list and
+    /// `BODY`, a template with literal text and sections of its own, is
+    /// formatted with each item as its input. The results are concatenated
+    /// without a separator. A string is treated as a list with one item.
+    ///
+    /// # Fields
+    ///
+    /// * `body` - Template formatted for each item
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("Report:\n{each:{split:,:..}:- {upper}\n}").unwrap();
+    /// assert_eq!(template.format("a,b").unwrap(), "Report:\n- A\n- B\n");
+    /// ```
+    Each { body: Box<Template> },
+
+    /// Sort list items alphabetically.
+    ///
+    /// Sorts a list of strings in ascending or descending alphabetical order
+    /// using lexicographic comparison with Unicode support.
+    ///
+    /// # Fields
+    ///
+    /// * `direction` - Sort direction (ascending or descending)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:,:..|sort|join:,}").unwrap();
+    /// assert_eq!(template.format("c,a,b").unwrap(), "a,b,c");
+    ///
+    /// let template = Template::parse("{split:,:..|sort:desc|join:,}").unwrap();
+    /// assert_eq!(template.format("a,b,c").unwrap(), "c,b,a");
+    /// ```
+    Sort { direction: SortDirection },
+
+    /// Sort list items by a key computed with a sub-pipeline.
+    ///
+    /// **Syntax:** `sort_by:{operations}[:num][:DIRECTION]`
+    ///
+    /// Runs the operations on each item, like [`Map`](StringOp::Map), and
+    /// sorts the original items by the results. The sort is stable, so items
+    /// with equal keys keep their relative order in both directions.
+    ///
+    /// With `num`, keys are compared as numbers. Keys that are not numbers
+    /// compare below every number and are ordered as text among themselves.
+    ///
+    /// # Fields
+    ///
+    /// * `operations` - Operations computing the sort key of an item
+    /// * `numeric` - Whether keys are compared as numbers
+    /// * `direction` - Sort direction (ascending or descending)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// // Sort paths by file name
+    /// let template = Template::parse("{split:,:..|sort_by:{split:/:-1}|join:,}").unwrap();
+    /// assert_eq!(template.format("/b/z.txt,/a/y.txt,/c/x.txt").unwrap(), "/c/x.txt,/a/y.txt,/b/z.txt");
+    ///
+    /// // Sort by a numeric field, largest first
+    /// let template = Template::parse("{split:,:..|sort_by:{split:=:1}:num:desc|join:,}").unwrap();
+    /// assert_eq!(template.format("a=9,b=10,c=2").unwrap(), "b=10,a=9,c=2");
+    /// ```
+    SortBy {
+        operations: Box<SmallVec<[StringOp; 8]>>,
+        numeric: bool,
+        direction: SortDirection,
+    },
+
+    /// Reverse a string or list order.
+    ///
+    /// For strings, reverses the order of grapheme clusters, keeping combining
+    /// marks and emoji sequences intact. For lists, reverses the item order.
+    ///
+    /// **Performance Optimization:** ASCII-only strings use optimized byte-level reversal.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// // Reverse string
+    /// let template = Template::parse("{reverse}").unwrap();
+    /// assert_eq!(template.format("hello").unwrap(), "olleh");
+    ///
+    /// // Reverse list
+    /// let template = Template::parse("{split:,:..|reverse|join:,}").unwrap();
+    /// assert_eq!(template.format("a,b,c").unwrap(), "c,b,a");
+    /// ```
+    Reverse,
+
+    /// Remove duplicate items from a list.
+    ///
+    /// **Syntax:** `unique`
+    ///
+    /// Filters a list to keep only the first occurrence of each unique item,
+    /// preserving the original order of first appearances.
+    ///
+    /// **Order Preservation:** The first occurrence of each item is kept, maintaining
+    /// the original order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// // Basic deduplication
+    /// let template = Template::parse("{split:,:..|unique|join:,}").unwrap();
+    /// assert_eq!(template.format("a,b,a,c,b").unwrap(), "a,b,c");
+    ///
+    /// // Remove duplicate lines
+    /// let template = Template::parse("{split:\\n:..|unique|join:\\n}").unwrap();
+    /// let input = "line1\nline2\nline1\nline3\nline2";
+    /// assert_eq!(template.format(input).unwrap(), "line1\nline2\nline3");
+    ///
+    /// // Combine with sort for alphabetical unique list
+    /// let template = Template::parse("{split:,:..|unique|sort|join:,}").unwrap();
+    /// assert_eq!(template.format("c,a,b,a,c").unwrap(), "a,b,c");
+    /// ```
+    Unique,
+
+    /// Remove list items whose sub-pipeline key was already seen.
+    ///
+    /// **Syntax:** `unique_by:{operations}`
+    ///
+    /// Runs the operations on each item, like [`Map`](StringOp::Map), and
+    /// keeps the first original item for every distinct result.
+    ///
+    /// # Fields
+    ///
+    /// * `operations` - Operations computing the key of an item
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// // Case-insensitive deduplication
+    /// let template = Template::parse("{split:,:..|unique_by:{lower}|join:,}").unwrap();
+    /// assert_eq!(template.format("Apple,banana,APPLE,Banana").unwrap(), "Apple,banana");
+    ///
+    /// // Deduplicate by an extracted ID
+    /// let template = Template::parse("{split:,:..|unique_by:{split:#:0}|join:,}").unwrap();
+    /// assert_eq!(template.format("1#a,2#b,1#c").unwrap(), "1#a,2#b");
+    /// ```
+    UniqueBy {
+        operations: Box<SmallVec<[StringOp; 8]>>,
+    },
+
+    /// Keep only list items that appear more than once.
+    ///
+    /// **Syntax:** `duplicates`
+    ///
+    /// Like `uniq -d`, each repeated item is kept once, at the position of its
+    /// first occurrence.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:,:..|duplicates|join:,}").unwrap();
+    /// assert_eq!(template.format("b,a,c,a,b,a").unwrap(), "b,a");
+    /// ```
+    Duplicates,
+
+    /// Count how often each list item appears.
+    ///
+    /// **Syntax:** `histogram[:TOP]`
+    ///
+    /// Like `sort | uniq -c | sort -rn`, each distinct item becomes an
+    /// `item<TAB>count` line, most frequent first. Items with the same count
+    /// keep the order of their first appearance. `TOP` keeps only the most
+    /// frequent items.
+    ///
+    /// # Fields
+    ///
+    /// * `top` - Number of lines to keep, or `None` for all of them
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:,:..|histogram|join:;}").unwrap();
+    /// assert_eq!(template.format("b,a,c,a,b,a").unwrap(), "a\t3;b\t2;c\t1");
+    ///
+    /// let template = Template::parse("{split:,:..|histogram:1}").unwrap();
+    /// assert_eq!(template.format("b,a,c,a,b,a").unwrap(), "a\t3");
+    /// ```
+    Histogram { top: Option<usize> },
+
+    /// Remove empty and whitespace-only list items.
+    ///
+    /// **Syntax:** `remove_empty`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:,:..|remove_empty|join:,}").unwrap();
+    /// assert_eq!(template.format("a,, ,b,").unwrap(), "a,b");
+    /// ```
+    RemoveEmpty,
+
+    /// Keep the list items that also appear in a second list.
+    ///
+    /// **Syntax:** `intersect:ITEMS` or `intersect:@FILE`
+    ///
+    /// The second list is either comma-separated items or the non-empty
+    /// lines of a file, read on every format call. Like the other set operations, the
+    /// result holds each item once, in order of first appearance.
+    ///
+    /// # Fields
+    ///
+    /// * `other` - The second list
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:,:..|intersect:b,c,d|join:,}").unwrap();
+    /// assert_eq!(template.format("a,b,c,b").unwrap(), "b,c");
+    /// ```
+    Intersect { other: ListSource },
+
+    /// Keep the list items that do not appear in a second list.
+    ///
+    /// **Syntax:** `diff:ITEMS` or `diff:@FILE`
+    ///
+    /// Useful for finding entries missing from an allowlist. The second list
+    /// is given as for [`Intersect`](StringOp::Intersect).
+    ///
+    /// # Fields
+    ///
+    /// * `other` - The second list
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:,:..|diff:b,c|join:,}").unwrap();
+    /// assert_eq!(template.format("a,b,c,d,a").unwrap(), "a,d");
+    /// ```
+    Diff { other: ListSource },
+
+    /// Combine the list with a second list.
+    ///
+    /// **Syntax:** `union:ITEMS` or `union:@FILE`
+    ///
+    /// Returns the items of the list followed by the items of the second
+    /// list that are not already present. The second list is given as for
+    /// [`Intersect`](StringOp::Intersect).
+    ///
+    /// # Fields
+    ///
+    /// * `other` - The second list
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:,:..|union:c,d|join:,}").unwrap();
+    /// assert_eq!(template.format("a,b,c").unwrap(), "a,b,c,d");
+    /// ```
+    Union { other: ListSource },
+
+    /// Shuffle list items into a random order.
+    ///
+    /// **Syntax:** `shuffle[:SEED]`
+    ///
+    /// Without a seed every format call produces a new order. With a seed
+    /// the order is reproducible across runs and platforms.
+    ///
+    /// # Fields
+    ///
+    /// * `seed` - Optional seed for reproducible output
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:,:..|shuffle|sort|join:,}").unwrap();
+    /// assert_eq!(template.format("c,a,b").unwrap(), "a,b,c");
+    ///
+    /// // Seeded shuffles always produce the same order
+    /// let template = Template::parse("{split:,:..|shuffle:42|join:,}").unwrap();
+    /// assert_eq!(template.format("a,b,c,d").unwrap(), template.format("a,b,c,d").unwrap());
+    /// ```
+    Shuffle { seed: Option<u64> },
+
+    /// Pick a random subset of list items.
+    ///
+    /// **Syntax:** `sample:N[:SEED]`
+    ///
+    /// Selects `N` distinct items, keeping their original relative order.
+    /// Lists with at most `N` items are returned unchanged. Seeding works
+    /// as for [`Shuffle`](StringOp::Shuffle).
+    ///
+    /// # Fields
+    ///
+    /// * `count` - Number of items to pick
+    /// * `seed` - Optional seed for reproducible output
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:,:..|sample:2|len}").unwrap();
+    /// assert_eq!(template.format("a,b,c,d").unwrap(), "2");
+    ///
+    /// let template = Template::parse("{split:,:..|sample:5:7|join:,}").unwrap();
+    /// assert_eq!(template.format("a,b,c").unwrap(), "a,b,c");
+    /// ```
+    Sample { count: usize, seed: Option<u64> },
+
+    /// Align delimiter-separated rows into columns.
+    ///
+    /// **Syntax:** `table[:SEP[:ALIGN][:header]]`
+    ///
+    /// Splits every list item into cells on `SEP`, or on runs of whitespace
+    /// when `SEP` is empty or missing, and pads the cells so that columns
+    /// line up, like `column -t`. Cells are trimmed and columns are two
+    /// spaces apart. `ALIGN` holds one letter per column, `l`, `r` or `c`;
+    /// columns without a letter are left-aligned. `header` underlines the
+    /// first row. Trailing padding is dropped from every line.
+    ///
+    /// # Fields
+    ///
+    /// * `sep` - Cell separator, or empty for whitespace
+    /// * `align` - Alignment of the leading columns
+    /// * `header` - Whether to underline the first row
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:\\n:..|table}").unwrap();
+    /// assert_eq!(
+    ///     template.format("NAME SIZE\nsrc 12\ntarget 1024").unwrap(),
+    ///     "NAME    SIZE\nsrc     12\ntarget  1024"
+    /// );
+    ///
+    /// let template = Template::parse("{split:\\n:..|table:,:lr:header}").unwrap();
+    /// assert_eq!(
+    ///     template.format("name,size\nsrc,12\ntarget,1024").unwrap(),
+    ///     "name    size\n------  ----\nsrc       12\ntarget  1024"
+    /// );
+    /// ```
+    Table {
+        sep: String,
+        align: Vec<ColumnAlign>,
+        header: bool,
+    },
+
+    /// Write delimiter-separated rows as a Markdown table.
+    ///
+    /// **Syntax:** `md_table[:SEP[:ALIGN][:header]]`
+    ///
+    /// Splits rows into cells like [`Table`](StringOp::Table) and gives one
+    /// line per table row, padded so the columns line up in the source
+    /// too. `header` makes the first row the table header; otherwise the
+    /// header is left empty, as Markdown tables always have one. `ALIGN`
+    /// sets the alignment markers of the delimiter row. `|` in cells is
+    /// escaped.
+    ///
+    /// # Fields
+    ///
+    /// * `sep` - Cell separator, or empty for whitespace
+    /// * `align` - Alignment of the leading columns
+    /// * `header` - Whether the first row is the header
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:\\n:..|md_table:,:lr:header}").unwrap();
+    /// assert_eq!(
+    ///     template.format("name,size\nsrc,12\ntarget,1024").unwrap(),
+    ///     "| name   | size |\n| :----- | ---: |\n| src    |   12 |\n| target | 1024 |"
+    /// );
+    /// ```
+    MarkdownTable {
+        sep: String,
+        align: Vec<ColumnAlign>,
+        header: bool,
+    },
+
+    /// Write delimiter-separated rows as an HTML table.
+    ///
+    /// **Syntax:** `html_table[:SEP[:ALIGN][:header]]`
+    ///
+    /// Splits rows into cells like [`Table`](StringOp::Table) and gives one
+    /// line per line of HTML. `header` puts the first row in a `<thead>`
+    /// of `<th>` cells. Aligned columns get a `text-align` style, and cell
+    /// text is escaped.
+    ///
+    /// # Fields
+    ///
+    /// * `sep` - Cell separator, or empty for whitespace
+    /// * `align` - Alignment of the leading columns
+    /// * `header` - Whether the first row is the header
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:\\n:..|html_table:,:header|join:\\n}").unwrap();
+    /// assert_eq!(
+    ///     template.format("name,size\nsrc,12").unwrap(),
+    ///     "<table>\n  <thead>\n    <tr><th>name</th><th>size</th></tr>\n  </thead>\n  \
+    ///      <tbody>\n    <tr><td>src</td><td>12</td></tr>\n  </tbody>\n</table>"
+    /// );
+    /// ```
+    HtmlTable {
+        sep: String,
+        align: Vec<ColumnAlign>,
+        header: bool,
+    },
+
+    /// Pad a string to a specified width.
+    ///
+    /// **Syntax:** `pad:WIDTH[:FILL][:DIRECTION][:truncate]` or
+    /// `center:WIDTH[:FILL][:truncate]`
+    ///
+    /// Adds padding to reach the target width, supporting left, right, or
+    /// both-sides padding. Multi-character fills repeat and are cut to the
+    /// exact width. `center` is an alias for padding on both sides.
+    ///
+    /// # Fields
+    ///
+    /// * `width` - Target width in grapheme clusters
+    /// * `fill` - Text repeated to fill the padding
+    /// * `direction` - Where to add padding (left, right, or both)
+    /// * `truncate` - Whether longer strings are cut to their first `width` grapheme clusters
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// // Right padding (default)
+    /// let template = Template::parse("{pad:5}").unwrap();
+    /// assert_eq!(template.format("hi").unwrap(), "hi   ");
+    ///
+    /// // Left padding with custom character
+    /// let template = Template::parse("{pad:5:0:left}").unwrap();
+    /// assert_eq!(template.format("42").unwrap(), "00042");
+    ///
+    /// // Centered with a repeating fill
+    /// let template = Template::parse("{center:8:-=}").unwrap();
+    /// assert_eq!(template.format("hi").unwrap(), "-=-hi-=-");
+    ///
+    /// // Fixed-width column
+    /// let template = Template::parse("{pad:5: :right:truncate}").unwrap();
+    /// assert_eq!(template.format("overlong").unwrap(), "overl");
+    /// ```
+    Pad {
+        width: usize,
+        fill: String,
+        direction: PadDirection,
+        truncate: bool,
+    },
+
+    /// Extract text using regex patterns with optional capture groups.
+    ///
+    /// Extracts the first match of a regex pattern, optionally selecting
+    /// a specific capture group for more precise extraction.
+    ///
+    /// # Fields
+    ///
+    /// * `pattern` - Regex pattern to match
+    /// * `group` - Optional capture group number (0 = entire match)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// // Extract numbers
+    /// let template = Template::parse(r"{regex_extract:\d+}").unwrap();
+    /// assert_eq!(template.format("item123").unwrap(), "123");
+    ///
+    /// // Extract capture group
+    /// let template = Template::parse(r"{regex_extract:(\w+)@(\w+):1}").unwrap();
+    /// assert_eq!(template.format("user@domain.com").unwrap(), "user");
+    /// ```
+    RegexExtract {
+        pattern: String,
+        group: Option<usize>,
+    },
+
+    /// Extract the value of a key from `key=value` pairs.
+    ///
+    /// **Syntax:** `kv:KEY[:PAIR_SEP[:KV_SEP]]`
+    ///
+    /// Pairs are separated by `PAIR_SEP`, or by runs of whitespace when it is
+    /// empty or missing, and keys are separated from values by `KV_SEP`
+    /// (default `=`). Values may be wrapped in double or single quotes, which
+    /// can contain separators and escape their own quote with a backslash;
+    /// the quotes are removed. Words without `KV_SEP` are skipped. Returns
+    /// the value of the first pair named `KEY`, or an empty string.
+    ///
+    /// # Fields
+    ///
+    /// * `key` - Key to look up
+    /// * `pair_sep` - Separator between pairs, or empty for whitespace
+    /// * `kv_sep` - Separator between a key and its value
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{kv:msg}").unwrap();
+    /// assert_eq!(
+    ///     template.format(r#"level=info msg="disk almost full" used=93%"#).unwrap(),
+    ///     "disk almost full"
+    /// );
+    ///
+    /// let template = Template::parse("{kv:port:;:\\:}").unwrap();
+    /// assert_eq!(template.format("host: example.org; port: 8080").unwrap(), "8080");
+    /// ```
+    Kv {
+        key: String,
+        pair_sep: String,
+        kv_sep: String,
+    },
+
+    /// List the keys of `key=value` pairs.
+    ///
+    /// **Syntax:** `kv_keys[:PAIR_SEP[:KV_SEP]]`
+    ///
+    /// Pairs are read as for [`Kv`](StringOp::Kv). Keys are listed in order,
+    /// including repeated ones.
+    ///
+    /// # Fields
+    ///
+    /// * `pair_sep` - Separator between pairs, or empty for whitespace
+    /// * `kv_sep` - Separator between a key and its value
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{kv_keys|join:,}").unwrap();
+    /// assert_eq!(template.format(r#"a=1 b="x y" c=3"#).unwrap(), "a,b,c");
+    /// ```
+    KvKeys { pair_sep: String, kv_sep: String },
+
+    /// List the values of `key=value` pairs.
+    ///
+    /// **Syntax:** `kv_values[:PAIR_SEP[:KV_SEP]]`
+    ///
+    /// Pairs are read as for [`Kv`](StringOp::Kv), and values are listed in
+    /// the order of their keys, without quotes.
+    ///
+    /// # Fields
+    ///
+    /// * `pair_sep` - Separator between pairs, or empty for whitespace
+    /// * `kv_sep` - Separator between a key and its value
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{kv_values:&|join:,}").unwrap();
+    /// assert_eq!(template.format("q=rust&page=2").unwrap(), "rust,2");
+    /// ```
+    KvValues { pair_sep: String, kv_sep: String },
+
+    /// Split a string into its words.
+    ///
+    /// **Syntax:** `words`
+    ///
+    /// Words follow Unicode word boundaries (UAX #29), so punctuation and
+    /// whitespace are left out, contractions such as `don't` stay whole, and
+    /// each ideograph of CJK text is its own word.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{words|join:,}").unwrap();
+    /// assert_eq!(template.format("Hello, world! Don't panic.").unwrap(), "Hello,world,Don't,panic");
+    ///
+    /// let template = Template::parse("{words|map:{lower}|unique|len}").unwrap();
+    /// assert_eq!(template.format("The cat saw the other cat.").unwrap(), "4");
+    /// ```
+    Words,
+
+    /// Split a string into its sentences.
+    ///
+    /// **Syntax:** `sentences`
+    ///
+    /// Sentences follow Unicode sentence boundaries (UAX #29). Whitespace
+    /// around each sentence is trimmed, and blank sentences are left out.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{sentences|join:\\n}").unwrap();
+    /// assert_eq!(template.format("Hi there. How are you? Fine!").unwrap(), "Hi there.\nHow are you?\nFine!");
+    /// ```
+    Sentences,
+
+    /// Find all email addresses in a string.
+    ///
+    /// **Syntax:** `extract_emails`
+    ///
+    /// Returns a list of the addresses in order of appearance, which is
+    /// empty if there are none. Addresses need a domain with a top-level
+    /// domain of at least two letters; quoted local parts and IP address
+    /// domains are not found.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{extract_emails|join:,}").unwrap();
+    /// let text = "Mail ann.lee+news@example.co.uk or <bob@mail.example.org>.";
+    /// assert_eq!(template.format(text).unwrap(), "ann.lee+news@example.co.uk,bob@mail.example.org");
+    /// ```
+    ExtractEmails,
+
+    /// Find all URLs in a string.
+    ///
+    /// **Syntax:** `extract_urls`
+    ///
+    /// Finds `http`, `https` and `ftp` URLs, and ones starting with `www.`,
+    /// in order of appearance. Sentence punctuation after a URL and closing
+    /// brackets without a matching opening bracket are left out.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{extract_urls|join:,}").unwrap();
+    /// let text = "Docs (https://docs.rs/regex) and www.example.com.";
+    /// assert_eq!(template.format(text).unwrap(), "https://docs.rs/regex,www.example.com");
+    /// ```
+    ExtractUrls,
+
+    /// Find all UUIDs in a string.
+    ///
+    /// **Syntax:** `extract_uuids`
+    ///
+    /// Finds UUIDs in the usual hyphenated form, in either case, and
+    /// returns them as written.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{extract_uuids|join:,}").unwrap();
+    /// let text = "req=6F9619FF-8B86-D011-B42D-00C04FC964FF status=500";
+    /// assert_eq!(template.format(text).unwrap(), "6F9619FF-8B86-D011-B42D-00C04FC964FF");
+    /// ```
+    ExtractUuids,
+
+    /// Extract the text between balanced delimiters.
+    ///
+    /// **Syntax:** `extract_between:OPEN:CLOSE[:NTH]`
+    ///
+    /// Finds the outermost groups enclosed by `OPEN` and `CLOSE` and returns
+    /// the contents of group `NTH`, counted from `0`, or from the end when
+    /// negative. Unlike a regex, this matches nested delimiters correctly,
+    /// so nested groups stay inside the group around them. Delimiters that
+    /// are the same, such as quotes, do not nest. A backslash escapes the
+    /// character after it. Gives an empty string when there is no such
+    /// group.
+    ///
+    /// # Fields
+    ///
+    /// * `open` - The opening delimiter
+    /// * `close` - The closing delimiter
+    /// * `nth` - Which group to return
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{extract_between:(:)}").unwrap();
+    /// assert_eq!(template.format("call(a, f(b), c) + g(d)").unwrap(), "a, f(b), c");
+    ///
+    /// let template = Template::parse("{extract_between:\\\":\\\":-1}").unwrap();
+    /// assert_eq!(template.format(r#"say "hi" then "bye""#).unwrap(), "bye");
+    /// ```
+    ExtractBetween {
+        open: String,
+        close: String,
+        nth: isize,
+    },
+
+    /// Count the length of a string or list.
+    ///
+    /// Strings are measured in the given unit, grapheme clusters by default.
+    /// Lists report their number of items, whatever the unit.
+    ///
+    /// # Fields
+    ///
+    /// * `unit` - What to count in strings
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{len}").unwrap();
+    /// assert_eq!(template.format("héllo").unwrap(), "5");
+    ///
+    /// let template = Template::parse("{len:bytes}").unwrap();
+    /// assert_eq!(template.format("héllo").unwrap(), "6");
+    ///
+    /// // Count list items
+    /// let template = Template::parse("{split:,:..|len}").unwrap();
+    /// assert_eq!(template.format("a,b,c").unwrap(), "3");
+    /// ```
+    Len { unit: LengthUnit },
+
+    /// Write the value as a JSON array of strings.
+    ///
+    /// **Syntax:** `json_array`
+    ///
+    /// A string gives an array of one item. Items are escaped as JSON
+    /// strings, so the output can be read by any JSON parser.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:,:..|json_array}").unwrap();
+    /// assert_eq!(template.format("a,\"b\",c").unwrap(), r#"["a","\"b\"","c"]"#);
     /// ```
-    Reverse,
+    JsonArray,
 
-    /// Remove duplicate items from a list.
+    /// Write the value as a JSON object, pairing the given keys with the
+    /// items in order.
     ///
-    /// **Syntax:** `unique`
+    /// **Syntax:** `json_object:KEY,KEY,...`
     ///
-    /// Filters a list to keep only the first occurrence of each unique item,
-    /// preserving the original order of first appearances.
+    /// Keys without an item get `null`, and items after the last key are
+    /// left out. A string is paired with the first key. Keys are separated
+    /// by commas; `\,` is a comma within a key. Keys must be unique.
     ///
-    /// **Order Preservation:** The first occurrence of each item is kept, maintaining
-    /// the original order.
+    /// # Fields
+    ///
+    /// * `keys` - Names of the object members, in order
     ///
     /// # Examples
     ///
     /// ```rust
     /// use string_pipeline::Template;
     ///
-    /// // Basic deduplication
-    /// let template = Template::parse("{split:,:..|unique|join:,}").unwrap();
-    /// assert_eq!(template.format("a,b,a,c,b").unwrap(), "a,b,c");
-    ///
-    /// // Remove duplicate lines
-    /// let template = Template::parse("{split:\\n:..|unique|join:\\n}").unwrap();
-    /// let input = "line1\nline2\nline1\nline3\nline2";
-    /// assert_eq!(template.format(input).unwrap(), "line1\nline2\nline3");
-    ///
-    /// // Combine with sort for alphabetical unique list
-    /// let template = Template::parse("{split:,:..|unique|sort|join:,}").unwrap();
-    /// assert_eq!(template.format("c,a,b,a,c").unwrap(), "a,b,c");
+    /// let template = Template::parse("{split:\\t:..|json_object:name,size,owner}").unwrap();
+    /// assert_eq!(
+    ///     template.format("src\t12").unwrap(),
+    ///     r#"{"name":"src","size":"12","owner":null}"#
+    /// );
     /// ```
-    Unique,
+    JsonObject { keys: Vec<String> },
 
-    /// Pad a string to a specified width.
+    /// Get a scalar value from a YAML document.
+    ///
+    /// **Syntax:** `yaml_get:PATH`
     ///
-    /// Adds padding characters to reach the target width, supporting
-    /// left, right, or both-sides padding with customizable fill characters.
+    /// Requires the `yaml` feature. `PATH` is a list of keys separated by
+    /// dots, where `\.` is a dot within a key and a number also indexes a
+    /// sequence. Gives an empty string for a missing key or a null, and an
+    /// error for a mapping or sequence or for input that is not YAML.
     ///
     /// # Fields
     ///
-    /// * `width` - Target width in characters
-    /// * `char` - Character to use for padding
-    /// * `direction` - Where to add padding (left, right, or both)
+    /// * `path` - The keys leading to the value, outermost first
     ///
     /// # Examples
     ///
     /// ```rust
+    /// # #[cfg(feature = "yaml")] {
     /// use string_pipeline::Template;
     ///
-    /// // Right padding (default)
-    /// let template = Template::parse("{pad:5}").unwrap();
-    /// assert_eq!(template.format("hi").unwrap(), "hi   ");
-    ///
-    /// // Left padding with custom character
-    /// let template = Template::parse("{pad:5:0:left}").unwrap();
-    /// assert_eq!(template.format("42").unwrap(), "00042");
+    /// let template = Template::parse("{yaml_get:server.ports.1}").unwrap();
+    /// assert_eq!(template.format("server:\n  ports: [80, 443]").unwrap(), "443");
+    /// # }
     /// ```
-    Pad {
-        width: usize,
-        char: char,
-        direction: PadDirection,
-    },
+    YamlGet { path: Vec<String> },
 
-    /// Extract text using regex patterns with optional capture groups.
+    /// Get a scalar value from a TOML document.
     ///
-    /// Extracts the first match of a regex pattern, optionally selecting
-    /// a specific capture group for more precise extraction.
+    /// **Syntax:** `toml_get:PATH`
+    ///
+    /// Requires the `toml` feature. `PATH` is a list of keys separated by
+    /// dots, where `\.` is a dot within a key and a number also indexes an
+    /// array. Values are written as they appear in the document, strings
+    /// without their quotes. Gives an empty string for a missing key, and an
+    /// error for a table or array or for input that is not TOML.
     ///
     /// # Fields
     ///
-    /// * `pattern` - Regex pattern to match
-    /// * `group` - Optional capture group number (0 = entire match)
+    /// * `path` - The keys leading to the value, outermost first
     ///
     /// # Examples
     ///
     /// ```rust
+    /// # #[cfg(feature = "toml")] {
     /// use string_pipeline::Template;
     ///
-    /// // Extract numbers
-    /// let template = Template::parse(r"{regex_extract:\d+}").unwrap();
-    /// assert_eq!(template.format("item123").unwrap(), "123");
-    ///
-    /// // Extract capture group
-    /// let template = Template::parse(r"{regex_extract:(\w+)@(\w+):1}").unwrap();
-    /// assert_eq!(template.format("user@domain.com").unwrap(), "user");
+    /// let template = Template::parse("{toml_get:package.version}").unwrap();
+    /// assert_eq!(template.format("[package]\nversion = \"1.2.0\"").unwrap(), "1.2.0");
+    /// # }
     /// ```
-    RegexExtract {
-        pattern: String,
-        group: Option<usize>,
-    },
+    TomlGet { path: Vec<String> },
+}
+
+impl StringOp {
+    /// Returns the template keyword used to write this operation.
+    ///
+    /// Used when reporting errors so messages refer to operations the way
+    /// users write them (`regex_extract`), not by their variant name.
+    pub(crate) fn keyword(&self) -> &'static str {
+        match self {
+            StringOp::Split { .. } => "split",
+            StringOp::SplitAny { .. } => "split_any",
+            StringOp::Partition { .. } => "partition",
+            StringOp::RPartition { .. } => "rpartition",
+            StringOp::Join { .. } => "join",
+            StringOp::OutputSep { .. } => "output_sep",
+            StringOp::Replace { .. } => "replace",
+            StringOp::ReplaceLiteral { .. } => "replace_literal",
+            StringOp::Upper { .. } => "upper",
+            StringOp::Lower { .. } => "lower",
+            StringOp::Capitalize { .. } => "capitalize",
+            StringOp::SwapCase { .. } => "swapcase",
+            StringOp::Trim { .. } => "trim",
+            StringOp::Squeeze { .. } => "squeeze",
+            StringOp::TrimPrefix { .. } => "trim_prefix",
+            StringOp::TrimSuffix { .. } => "trim_suffix",
+            StringOp::TrimRegex { .. } => "trim_regex",
+            StringOp::Substring { .. } => "substring",
+            StringOp::Append { .. } => "append",
+            StringOp::Prepend { .. } => "prepend",
+            StringOp::Surround { .. } => "surround",
+            StringOp::StripAnsi => "strip_ansi",
+            StringOp::Deaccent => "deaccent",
+            StringOp::Ascii => "ascii",
+            StringOp::Slugify { .. } => "slugify",
+            StringOp::Normalize { .. } => "normalize",
+            StringOp::Ordinal { .. } => "ordinal",
+            StringOp::ToWords { .. } => "to_words",
+            StringOp::Roman { .. } => "roman",
+            StringOp::Basename { .. } => "basename",
+            StringOp::Dirname { .. } => "dirname",
+            StringOp::Extname { .. } => "extname",
+            StringOp::StripExt { .. } => "strip_ext",
+            StringOp::WithExt { .. } => "with_ext",
+            StringOp::NormalizePath { .. } => "normalize_path",
+            StringOp::UrlScheme => "url_scheme",
+            StringOp::UrlHost => "url_host",
+            StringOp::UrlPath => "url_path",
+            StringOp::UrlQuery { .. } => "url_query",
+            StringOp::Color { .. } => "color",
+            StringOp::Background { .. } => "bg",
+            StringOp::Bold => "bold",
+            StringOp::Underline => "underline",
+            StringOp::StyleReset => "style_reset",
+            StringOp::Highlight { .. } => "highlight",
+            StringOp::ReadFile => "read_file",
+            StringOp::Exec { .. } => "exec",
+            StringOp::Filter { .. } => "filter",
+            StringOp::FilterNot { .. } => "filter_not",
+            StringOp::FilterPrefix { .. } => "filter_prefix",
+            StringOp::FilterSuffix { .. } => "filter_suffix",
+            StringOp::FilterContains { .. } => "filter_contains",
+            StringOp::FilterValidUtf8 => "filter_valid_utf8",
+            StringOp::FilterLuhn => "filter_luhn",
+            StringOp::FilterChecksum { .. } => "filter_checksum",
+            StringOp::FilterBy { .. } => "filter_by",
+            StringOp::Slice { .. } => "slice",
+            StringOp::Clamp { .. } => "clamp",
+            StringOp::Map { .. } => "map",
+            StringOp::MapFallible { .. } => "map_fallible",
+            StringOp::Try { .. } => "try",
+            StringOp::Each { .. } => "each",
+            StringOp::Sort { .. } => "sort",
+            StringOp::SortBy { .. } => "sort_by",
+            StringOp::Reverse => "reverse",
+            StringOp::Unique => "unique",
+            StringOp::UniqueBy { .. } => "unique_by",
+            StringOp::Duplicates => "duplicates",
+            StringOp::Histogram { .. } => "histogram",
+            StringOp::RemoveEmpty => "remove_empty",
+            StringOp::Intersect { .. } => "intersect",
+            StringOp::Diff { .. } => "diff",
+            StringOp::Union { .. } => "union",
+            StringOp::Shuffle { .. } => "shuffle",
+            StringOp::Sample { .. } => "sample",
+            StringOp::Table { .. } => "table",
+            StringOp::MarkdownTable { .. } => "md_table",
+            StringOp::HtmlTable { .. } => "html_table",
+            StringOp::Pad { .. } => "pad",
+            StringOp::RegexExtract { .. } => "regex_extract",
+            StringOp::Kv { .. } => "kv",
+            StringOp::KvKeys { .. } => "kv_keys",
+            StringOp::KvValues { .. } => "kv_values",
+            StringOp::Words => "words",
+            StringOp::Sentences => "sentences",
+            StringOp::ExtractEmails => "extract_emails",
+            StringOp::ExtractUrls => "extract_urls",
+            StringOp::ExtractUuids => "extract_uuids",
+            StringOp::ExtractBetween { .. } => "extract_between",
+            StringOp::Len { .. } => "len",
+            StringOp::JsonArray => "json_array",
+            StringOp::JsonObject { .. } => "json_object",
+            StringOp::YamlGet { .. } => "yaml_get",
+            StringOp::TomlGet { .. } => "toml_get",
+        }
+    }
+
+    /// Returns the catalog entry describing this operation.
+    pub(crate) fn info(&self) -> &'static OperationInfo {
+        operation(self.keyword()).expect("every operation keyword is in the catalog")
+    }
+
+    /// Returns `true` if this operation, or one nested in it, reads files.
+    pub(crate) fn reads_files(&self) -> bool {
+        self.any(&|op| {
+            matches!(
+                op,
+                StringOp::ReadFile
+                    | StringOp::Intersect {
+                        other: ListSource::File(_),
+                    }
+                    | StringOp::Diff {
+                        other: ListSource::File(_),
+                    }
+                    | StringOp::Union {
+                        other: ListSource::File(_),
+                    }
+            )
+        })
+    }
+
+    /// Returns `true` if this operation, or one nested in it, runs commands.
+    pub(crate) fn runs_commands(&self) -> bool {
+        self.any(&|op| matches!(op, StringOp::Exec { .. }))
+    }
+
+    /// Returns `true` if this operation, and every one nested in it, always
+    /// gives the same result for the same value.
+    ///
+    /// Reading files, running commands and unseeded `shuffle` or `sample`
+    /// are not repeatable.
+    pub(crate) fn is_repeatable(&self) -> bool {
+        !self.reads_files()
+            && !self.runs_commands()
+            && !self.any(&|op| match op {
+                StringOp::Shuffle { seed } | StringOp::Sample { seed, .. } => seed.is_none(),
+                // Depends on whether the input was read lossily
+                StringOp::FilterValidUtf8 => true,
+                StringOp::Each { body } => !body.is_repeatable(),
+                _ => false,
+            })
+    }
+
+    /// Returns the number of operations this one consists of, counting
+    /// itself and every operation nested in it.
+    pub(crate) fn operation_count(&self) -> usize {
+        1 + match self {
+            StringOp::Map { operations }
+            | StringOp::MapFallible { operations }
+            | StringOp::Try { operations, .. }
+            | StringOp::SortBy { operations, .. }
+            | StringOp::UniqueBy { operations }
+            | StringOp::FilterBy { operations, .. } => {
+                operations.iter().map(StringOp::operation_count).sum()
+            }
+            StringOp::Each { body } => body
+                .get_template_sections()
+                .into_iter()
+                .flat_map(|(_, ops)| ops)
+                .map(StringOp::operation_count)
+                .sum(),
+            _ => 0,
+        }
+    }
+
+    /// Returns how deeply `map`, `map_fallible` and `each` nest in this
+    /// operation, `0` if they do not appear in it.
+    pub(crate) fn map_depth(&self) -> usize {
+        let nested = |ops: &[StringOp]| ops.iter().map(StringOp::map_depth).max().unwrap_or(0);
+        match self {
+            StringOp::Map { operations } | StringOp::MapFallible { operations } => {
+                1 + nested(operations)
+            }
+            StringOp::Try { operations, .. }
+            | StringOp::SortBy { operations, .. }
+            | StringOp::UniqueBy { operations }
+            | StringOp::FilterBy { operations, .. } => nested(operations),
+            StringOp::Each { body } => 1 + body.max_map_depth(),
+            _ => 0,
+        }
+    }
+
+    /// Returns `true` if this operation, or one nested in it, matches a
+    /// regular expression.
+    pub(crate) fn uses_regex(&self) -> bool {
+        self.any(&|op| match op {
+            StringOp::FilterBy { pattern, .. } => pattern.is_some(),
+            StringOp::Replace { .. }
+            | StringOp::Filter { .. }
+            | StringOp::FilterNot { .. }
+            | StringOp::TrimRegex { .. }
+            | StringOp::Highlight { .. }
+            | StringOp::RegexExtract { .. } => true,
+            _ => false,
+        })
+    }
+
+    /// Returns `true` if `pred` holds for this operation or one nested in it.
+    fn any(&self, pred: &impl Fn(&StringOp) -> bool) -> bool {
+        pred(self)
+            || match self {
+                StringOp::Map { operations }
+                | StringOp::MapFallible { operations }
+                | StringOp::Try { operations, .. }
+                | StringOp::SortBy { operations, .. }
+                | StringOp::UniqueBy { operations }
+                | StringOp::FilterBy { operations, .. } => operations.iter().any(|op| op.any(pred)),
+                StringOp::Each { body } => body
+                    .get_template_sections()
+                    .into_iter()
+                    .any(|(_, ops)| ops.iter().any(|op| op.any(pred))),
+                _ => false,
+            }
+    }
 }
 
 /// Specification for selecting ranges of items or characters.
@@ -908,10 +4278,14 @@ pub enum StringOp {
 ///
 /// * [`Index`] - Single item selection
 /// * [`Range`] - Range-based selection with optional bounds
+/// * [`Stepped`] - Range-based selection taking every n-th item
+/// * [`Multi`] - Concatenation of several selections
 ///
 /// [`Index`]: RangeSpec::Index
 /// [`Range`]: RangeSpec::Range
-#[derive(Debug, Clone, Copy, Hash)]
+/// [`Stepped`]: RangeSpec::Stepped
+/// [`Multi`]: RangeSpec::Multi
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum RangeSpec {
     /// Select a single item by index.
     ///
@@ -942,13 +4316,47 @@ pub enum RangeSpec {
     /// - `(Some(1), Some(3), true)` - Items 1,2,3 (inclusive end)
     /// - `(Some(2), None, false)` - From item 2 to end
     /// - `(None, Some(3), false)` - First 3 items
+    /// - `(Some(3), Some(1), false)` - Items 3,2, in that order
+    /// - `(Some(-1), Some(-3), true)` - The last 3 items, last one first
+    ///
+    /// A start past the end selects in reverse order when both bounds count
+    /// from the same end of the value. Otherwise it selects nothing, so
+    /// `(Some(1), Some(-1), false)` drops the first and last item on any
+    /// length.
     Range(Option<isize>, Option<isize>, bool),
+
+    /// Select every n-th item of a range, written `START..END;STEP`.
+    ///
+    /// The first three fields match [`Range`](RangeSpec::Range). A positive
+    /// step walks the selected items from the first one, a negative step
+    /// walks them backwards from the last one. The step is never `0` or `1`;
+    /// a step of `1` parses as a plain range.
+    ///
+    /// # Examples
+    ///
+    /// - `(Some(0), Some(10), false, 2)` - Items 0,2,4,6,8
+    /// - `(None, None, false, -1)` - All items in reverse order
+    /// - `(Some(1), None, false, -2)` - From the last item back to item 1, every other one
+    Stepped(Option<isize>, Option<isize>, bool, isize),
+
+    /// Select several ranges, written as comma-separated specifications.
+    ///
+    /// Each part selects exactly what it would select on its own, and the
+    /// selections are concatenated in order, so items may repeat. Always
+    /// yields a list, even when every part is a single index. Parts are
+    /// never themselves [`Multi`](RangeSpec::Multi).
+    ///
+    /// # Examples
+    ///
+    /// - `0,3,7` - Items 0, 3 and 7
+    /// - `0,2..4,-1` - Items 0, 2, 3 and the last item
+    Multi(Vec<RangeSpec>),
 }
 
 /// Direction for trimming operations.
 ///
 /// Specifies which end(s) of a string to trim characters from.
-#[derive(Debug, Clone, Copy, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TrimDirection {
     /// Trim from both ends (default).
     Both,
@@ -961,7 +4369,7 @@ pub enum TrimDirection {
 /// Direction for sorting operations.
 ///
 /// Specifies the order for sorting list items.
-#[derive(Debug, Clone, Copy, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum SortDirection {
     /// Ascending order (A to Z).
     Asc,
@@ -969,10 +4377,248 @@ pub enum SortDirection {
     Desc,
 }
 
+/// Second list of the set operations `intersect`, `diff` and `union`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ListSource {
+    /// Items written in the template, separated by commas.
+    Items(Vec<String>),
+    /// Non-empty lines of a file, read on every format call.
+    File(PathBuf),
+}
+
+impl ListSource {
+    /// Returns the items of the list, reading the file if needed.
+    fn items(&self, guard: Option<&ExecGuard>) -> Result<Cow<'_, [String]>, String> {
+        match self {
+            ListSource::Items(items) => Ok(Cow::Borrowed(items)),
+            ListSource::File(path) => read_limited_file(path, guard).map(|content| {
+                Cow::Owned(
+                    content
+                        .lines()
+                        .filter(|line| !line.is_empty())
+                        .map(String::from)
+                        .collect(),
+                )
+            }),
+        }
+    }
+}
+
+/// Case mapping rules used by the `upper` and `lower` operations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CaseLocale {
+    /// Default Unicode case mapping.
+    #[default]
+    Root,
+    /// Turkish and Azerbaijani mapping, where dotted `i`/`İ` and dotless
+    /// `ı`/`I` are separate letters. Written `tr` or `az`.
+    Turkic,
+}
+
+impl CaseLocale {
+    /// Converts `s` to uppercase.
+    pub(crate) fn to_upper(self, s: &str) -> String {
+        match self {
+            CaseLocale::Root => s.to_uppercase(),
+            CaseLocale::Turkic => s.replace('i', "İ").to_uppercase(),
+        }
+    }
+
+    /// Converts `s` to lowercase.
+    pub(crate) fn to_lower(self, s: &str) -> String {
+        match self {
+            CaseLocale::Root => s.to_lowercase(),
+            CaseLocale::Turkic => {
+                // `I` followed by a combining dot above is a dotted `i`
+                s.replace("I\u{307}", "i")
+                    .replace('İ', "i")
+                    .replace('I', "ı")
+                    .to_lowercase()
+            }
+        }
+    }
+
+    /// Converts the first grapheme of `s` to titlecase, and the rest to
+    /// lowercase if `lower_rest` is set.
+    pub(crate) fn capitalize(self, s: &str, lower_rest: bool) -> String {
+        let first_len = s.graphemes(true).next().map_or(0, str::len);
+        let (first, rest) = s.split_at(first_len);
+        let mut out = self.to_title(first);
+        if lower_rest {
+            out.push_str(&self.to_lower(rest));
+        } else {
+            out.push_str(rest);
+        }
+        out
+    }
+
+    /// Converts a grapheme to titlecase, which only differs from uppercase
+    /// for ligatures and digraphs.
+    fn to_title(self, grapheme: &str) -> String {
+        let mut chars = grapheme.chars();
+        let Some(first) = chars.next() else {
+            return String::new();
+        };
+        let mut out = match first {
+            'ß' => "Ss".to_string(),
+            'ﬀ' => "Ff".to_string(),
+            'ﬁ' => "Fi".to_string(),
+            'ﬂ' => "Fl".to_string(),
+            'ﬃ' => "Ffi".to_string(),
+            'ﬄ' => "Ffl".to_string(),
+            'ﬅ' | 'ﬆ' => "St".to_string(),
+            'Ǆ' | 'ǅ' | 'ǆ' => "ǅ".to_string(),
+            'Ǉ' | 'ǈ' | 'ǉ' => "ǈ".to_string(),
+            'Ǌ' | 'ǋ' | 'ǌ' => "ǋ".to_string(),
+            'Ǳ' | 'ǲ' | 'ǳ' => "ǲ".to_string(),
+            c => self.to_upper(c.encode_utf8(&mut [0; 4])),
+        };
+        out.push_str(&self.to_upper(chars.as_str()));
+        out
+    }
+
+    /// Swaps the case of each letter in `s`.
+    pub(crate) fn swap_case(self, s: &str) -> String {
+        if self.is_ascii_compatible() && s.is_ascii() {
+            return s
+                .bytes()
+                .map(|b| {
+                    if b.is_ascii_alphabetic() {
+                        (b ^ 0x20) as char
+                    } else {
+                        b as char
+                    }
+                })
+                .collect();
+        }
+        let mut out = String::with_capacity(s.len());
+        let mut buf = [0; 4];
+        for c in s.chars() {
+            if c.is_lowercase() {
+                out.push_str(&self.to_upper(c.encode_utf8(&mut buf)));
+            } else if c.is_uppercase() {
+                out.push_str(&self.to_lower(c.encode_utf8(&mut buf)));
+            } else {
+                out.push(c);
+            }
+        }
+        out
+    }
+
+    /// Returns `true` if ASCII text maps like in the default mapping.
+    fn is_ascii_compatible(self) -> bool {
+        self == CaseLocale::Root
+    }
+}
+
+/// Unicode normalization form used by the `normalize` operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NormalForm {
+    /// Canonical composition.
+    Nfc,
+    /// Canonical decomposition.
+    Nfd,
+    /// Compatibility composition.
+    Nfkc,
+    /// Compatibility decomposition.
+    Nfkd,
+}
+
+/// One `s/PATTERN/REPLACEMENT/FLAGS` rule of a [`StringOp::Replace`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SedRule {
+    /// The regex pattern to search for, or plain text with the `l` flag.
+    pub pattern: String,
+    /// The replacement text (supports capture group references like `$1`, `$2`).
+    pub replacement: String,
+    /// Regex flags: `g` (global), `i` (case-insensitive), `m` (multiline),
+    /// `s` (dot-all), `x` (verbose), `l` (literal pattern and replacement).
+    pub flags: String,
+    /// Replace only the Nth match (`s/a/b/2`), or every match from the Nth on
+    /// when combined with `g` (`s/a/b/2g`).
+    pub occurrence: Option<usize>,
+}
+
+/// Checksum used by the `filter_checksum` operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChecksumAlgorithm {
+    /// CRC-32 as used by zip, gzip and PNG.
+    Crc32,
+    /// Adler-32 as used by zlib.
+    Adler32,
+}
+
+/// Path conventions used by the path operations such as `basename`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum PathStyle {
+    /// Components are separated by `/`.
+    #[default]
+    Posix,
+    /// Components are separated by `\` or `/`, and a path may start with a
+    /// drive such as `C:`. Written `windows`.
+    Windows,
+}
+
+/// Terminal color used by the `color` and `bg` operations.
+///
+/// Colors are written in templates by name, such as `red` or `bright_red`,
+/// as an index into the 256-color palette, or as `#rrggbb`, and are
+/// displayed the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TermColor {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+    /// A color of the 256-color palette.
+    Indexed(u8),
+    /// A 24-bit color.
+    Rgb(u8, u8, u8),
+}
+
+/// How the `highlight` operation marks matches.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HighlightStyle {
+    /// Matches get a foreground color. Written `COLOR`.
+    Color(TermColor),
+    /// Matches are put between two markers. Written `mark:OPEN:CLOSE`.
+    Marker { open: String, close: String },
+}
+
+impl Default for HighlightStyle {
+    fn default() -> Self {
+        HighlightStyle::Color(TermColor::Red)
+    }
+}
+
+/// Unit counted by the `len` operation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum LengthUnit {
+    /// UTF-8 bytes.
+    Bytes,
+    /// Unicode scalar values.
+    Chars,
+    /// Extended grapheme clusters, i.e. user-perceived characters.
+    #[default]
+    Graphemes,
+}
+
 /// Direction for padding operations.
 ///
 /// Specifies where to add padding characters to reach target width.
-#[derive(Debug, Clone, Copy, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PadDirection {
     /// Add padding to the left (right-align text).
     Left,
@@ -982,6 +4628,17 @@ pub enum PadDirection {
     Both,
 }
 
+/// Alignment of a column in `table` output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColumnAlign {
+    /// Align text to the left edge of the column.
+    Left,
+    /// Align text to the right edge of the column.
+    Right,
+    /// Center text in the column.
+    Center,
+}
+
 /// Resolves an index to a valid array position.
 ///
 /// Handles negative indexing and bounds clamping to ensure valid array access.
@@ -1032,26 +4689,103 @@ fn resolve_index(idx: isize, len: usize) -> usize {
 /// // let items = vec!["a", "b", "c", "d"];
 /// // apply_range(&items, &RangeSpec::Index(1)) -> vec!["b"]
 /// // apply_range(&items, &RangeSpec::Range(Some(1), Some(3), false)) -> vec!["b", "c"]
+/// // apply_range(&items, &RangeSpec::Stepped(None, None, false, -2)) -> vec!["d", "b"]
 /// ```
 fn apply_range<T: Clone>(items: &[T], range: &RangeSpec) -> Vec<T> {
-    let len = items.len();
+    if let Cow::Owned(range) = ascending_range(range) {
+        return apply_range(items, &range);
+    }
+    if let RangeSpec::Multi(ranges) = range {
+        return ranges
+            .iter()
+            .flat_map(|range| apply_range(items, range))
+            .collect();
+    }
+
+    let selected = &items[range_indices(items.len(), range)];
+    match *range {
+        RangeSpec::Stepped(.., step) => stride(selected.iter().cloned(), step),
+        // Use slice.to_vec() which is optimized for copying contiguous memory
+        _ => selected.to_vec(),
+    }
+}
+
+/// Applies a range specification to an owned vector without cloning items.
+///
+/// Selects exactly the same items as [`apply_range`].
+fn take_range<T: Clone>(mut items: Vec<T>, range: &RangeSpec) -> Vec<T> {
+    if let Cow::Owned(range) = ascending_range(range) {
+        return take_range(items, &range);
+    }
+    // Parts may select the same item more than once
+    if let RangeSpec::Multi(_) = range {
+        return apply_range(&items, range);
+    }
+
+    let selected = range_indices(items.len(), range);
+    items.truncate(selected.end);
+    items.drain(..selected.start);
+    match *range {
+        RangeSpec::Stepped(.., step) => stride(items.into_iter(), step),
+        _ => items,
+    }
+}
+
+/// Takes every `step`-th item, walking backwards from the last item when
+/// `step` is negative.
+fn stride<I: DoubleEndedIterator>(items: I, step: isize) -> Vec<I::Item> {
+    let n = step.unsigned_abs();
+    if step < 0 {
+        items.rev().step_by(n).collect()
+    } else {
+        items.step_by(n).collect()
+    }
+}
+
+/// Rewrites a descending range, whose start lies past its end as in `3..1`,
+/// as the ascending range it walks backwards, here `2..=3;-1`.
+///
+/// A range only descends when both bounds count from the same end, so
+/// `1..-1` keeps selecting all but the first and last item whatever the
+/// length. Other ranges are returned unchanged.
+pub(crate) fn ascending_range(range: &RangeSpec) -> Cow<'_, RangeSpec> {
+    let (start, end, inclusive, step) = match *range {
+        RangeSpec::Range(Some(start), Some(end), inclusive) => (start, end, inclusive, 1),
+        RangeSpec::Stepped(Some(start), Some(end), inclusive, step) => {
+            (start, end, inclusive, step)
+        }
+        _ => return Cow::Borrowed(range),
+    };
+    if start <= end || (start < 0) != (end < 0) {
+        return Cow::Borrowed(range);
+    }
+    let low = if inclusive { end } else { end + 1 };
+    Cow::Owned(RangeSpec::Stepped(Some(low), Some(start), true, -step))
+}
+
+/// Resolves a range specification into concrete indices for `len` items.
+///
+/// Single indices select one item (clamped to the last one), and empty
+/// ranges resolve to an empty range. Stepped and descending ranges resolve
+/// to the span their step walks over, and multi-ranges to the span from
+/// their first to their last selected item.
+pub(crate) fn range_indices(len: usize, range: &RangeSpec) -> Range<usize> {
     if len == 0 {
-        return Vec::new();
+        return 0..0;
+    }
+    if let Cow::Owned(range) = ascending_range(range) {
+        return range_indices(len, &range);
     }
 
     match range {
         RangeSpec::Index(idx) => {
             let i = resolve_index(*idx, len).min(len - 1);
-            if let Some(item) = items.get(i) {
-                vec![item.clone()]
-            } else {
-                Vec::new()
-            }
+            i..i + 1
         }
-        RangeSpec::Range(start, end, inclusive) => {
+        RangeSpec::Range(start, end, inclusive) | RangeSpec::Stepped(start, end, inclusive, _) => {
             let s_idx = start.map_or(0, |s| resolve_index(s, len));
             if s_idx >= len {
-                return Vec::new();
+                return 0..0;
             }
 
             let mut e_idx = end.map_or(len, |e| resolve_index(e, len));
@@ -1060,14 +4794,212 @@ fn apply_range<T: Clone>(items: &[T], range: &RangeSpec) -> Vec<T> {
             }
             let e_idx = e_idx.min(len);
 
-            if s_idx >= e_idx {
-                Vec::new()
-            } else {
-                // Use slice.to_vec() which is optimized for copying contiguous memory
-                items[s_idx..e_idx].to_vec()
-            }
+            if s_idx >= e_idx { 0..0 } else { s_idx..e_idx }
+        }
+        RangeSpec::Multi(ranges) => ranges
+            .iter()
+            .map(|range| range_indices(len, range))
+            .filter(|span| !span.is_empty())
+            .reduce(|a, b| a.start.min(b.start)..a.end.max(b.end))
+            .unwrap_or(0..0),
+    }
+}
+
+thread_local! {
+    /// Whether the format call running on this thread rejects out-of-bounds
+    /// ranges instead of clamping them.
+    static STRICT_RANGES: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Restores the range checking of the enclosing call.
+struct StrictRangesGuard(bool);
+
+impl Drop for StrictRangesGuard {
+    fn drop(&mut self) {
+        STRICT_RANGES.set(self.0);
+    }
+}
+
+/// Runs a format call `f` with out-of-bounds ranges rejected or clamped.
+pub(crate) fn with_strict_ranges<R>(strict: bool, f: impl FnOnce() -> R) -> R {
+    let _guard = StrictRangesGuard(STRICT_RANGES.replace(strict));
+    f()
+}
+
+/// Returns `true` if the running format call rejects out-of-bounds ranges.
+fn strict_ranges() -> bool {
+    STRICT_RANGES.get()
+}
+
+/// Fails if ranges are strict and `range` reaches past either end of `len`
+/// values of `unit`.
+///
+/// Starts and exclusive ends may point just past the last value, as with
+/// Rust slices, so `1..` still selects nothing from one item.
+fn check_range_bounds(len: usize, range: &RangeSpec, unit: &str) -> Result<(), String> {
+    if !strict_ranges() {
+        return Ok(());
+    }
+    let len_i = len as isize;
+    let ascending = ascending_range(range);
+    let in_bounds = |idx: isize, past_end: bool| {
+        let resolved = if idx < 0 { len_i + idx } else { idx };
+        resolved >= 0 && (resolved < len_i || past_end && resolved == len_i)
+    };
+    let valid = match &*ascending {
+        RangeSpec::Index(idx) => in_bounds(*idx, false),
+        RangeSpec::Range(start, end, inclusive) | RangeSpec::Stepped(start, end, inclusive, _) => {
+            start.is_none_or(|s| in_bounds(s, true)) && end.is_none_or(|e| in_bounds(e, !inclusive))
+        }
+        RangeSpec::Multi(ranges) => {
+            return ranges
+                .iter()
+                .try_for_each(|range| check_range_bounds(len, range, unit));
+        }
+    };
+    if valid {
+        return Ok(());
+    }
+    let kind = if let RangeSpec::Index(_) = range {
+        "Index"
+    } else {
+        "Range"
+    };
+    let plural = if len == 1 { "" } else { "s" };
+    Err(format!(
+        "{kind} {} is out of bounds for {len} {unit}{plural}",
+        canonical::format_range(range)
+    ))
+}
+
+/// Error of an operation pipeline, with the position of the failing operation.
+///
+/// Lets callers point at the operation in the template source; see
+/// [`Template`] for how it is reported.
+#[derive(Debug)]
+pub(crate) struct OpError {
+    /// Positions of the failing operation, outermost first: its position in
+    /// the pipeline, then inside each enclosing sub-pipeline. Empty when the
+    /// error is not tied to an operation.
+    pub(crate) path: Vec<usize>,
+    /// Description of the error.
+    pub(crate) message: String,
+}
+
+impl OpError {
+    /// Returns a mapper attributing errors to the operation at `index`.
+    fn at<E: Into<OpError>>(index: usize) -> impl Fn(E) -> OpError {
+        move |error| {
+            let mut error = error.into();
+            error.path.insert(0, index);
+            error
+        }
+    }
+}
+
+impl From<String> for OpError {
+    fn from(message: String) -> Self {
+        Self {
+            path: Vec::new(),
+            message,
+        }
+    }
+}
+
+/// How a template reports operations that fail on list items, such as an
+/// operation inside `map`.
+///
+/// # Examples
+///
+/// ```rust
+/// use string_pipeline::{ErrorPolicy, Template};
+///
+/// let template = Template::parse("{split:,:..|map:{ordinal}}").unwrap();
+/// assert_eq!(template.format("1,x,3,y").unwrap_err(), "Invalid integer: 'x' (in operation `ordinal` at chars 18..25)");
+///
+/// let template = template.with_error_policy(ErrorPolicy::CollectAll);
+/// assert!(template.format("1,x,3,y").unwrap_err().starts_with(
+///     "2 of 4 items failed:\n  item 2 (`ordinal`): Invalid integer: 'x'\n  item 4 (`ordinal`): Invalid integer: 'y'"
+/// ));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ErrorPolicy {
+    /// Stop at the first failing item and report its error.
+    #[default]
+    FailFast,
+    /// Run every item and report all the failing ones in a single error,
+    /// with their positions.
+    ///
+    /// The first ten failures are listed and the others counted. Exceeded
+    /// [`Limits`] still stop formatting right away.
+    CollectAll,
+}
+
+/// Most item failures that [`ErrorPolicy::CollectAll`] lists in its error.
+const MAX_LISTED_FAILURES: usize = 10;
+
+thread_local! {
+    /// The error policy of the format call running on this thread.
+    static ERROR_POLICY: Cell<ErrorPolicy> = const { Cell::new(ErrorPolicy::FailFast) };
+}
+
+/// Restores the error policy of the enclosing call.
+struct ErrorPolicyGuard(ErrorPolicy);
+
+impl Drop for ErrorPolicyGuard {
+    fn drop(&mut self) {
+        ERROR_POLICY.set(self.0);
+    }
+}
+
+/// Runs a format call `f` under the given error policy.
+pub(crate) fn with_error_policy<R>(policy: ErrorPolicy, f: impl FnOnce() -> R) -> R {
+    let _guard = ErrorPolicyGuard(ERROR_POLICY.replace(policy));
+    f()
+}
+
+/// Collects the results of running `operations` on each of `total` items.
+///
+/// Stops at the first error, or, under [`ErrorPolicy::CollectAll`], runs
+/// every item and reports all the failures at once, naming the failing item
+/// and operation of each.
+fn collect_item_results<T>(
+    results: impl Iterator<Item = Result<T, OpError>>,
+    total: usize,
+    operations: &[StringOp],
+) -> Result<Vec<T>, OpError> {
+    if ERROR_POLICY.get() == ErrorPolicy::FailFast {
+        return results.collect();
+    }
+    let mut values = Vec::with_capacity(total);
+    let mut failures = Vec::new();
+    for (index, result) in results.enumerate() {
+        match result {
+            Ok(value) => values.push(value),
+            Err(e) if limits::is_limit_error(&e.message) => return Err(e),
+            Err(e) => failures.push((index, e)),
+        }
+    }
+    if failures.is_empty() {
+        return Ok(values);
+    }
+
+    let mut message = format!("{} of {total} items failed:", failures.len());
+    for (index, error) in failures.iter().take(MAX_LISTED_FAILURES) {
+        message.push_str(&format!("\n  item {}", index + 1));
+        if let Some(op) = error.path.first().and_then(|&i| operations.get(i)) {
+            message.push_str(&format!(" (`{}`)", op.keyword()));
         }
+        message.push_str(": ");
+        message.push_str(&error.message);
     }
+    if failures.len() > MAX_LISTED_FAILURES {
+        message.push_str(&format!(
+            "\n  ... and {} more",
+            failures.len() - MAX_LISTED_FAILURES
+        ));
+    }
+    Err(OpError::from(message))
 }
 
 /// Applies a sequence of operations to an input string.
@@ -1080,12 +5012,16 @@ fn apply_range<T: Clone>(items: &[T], range: &RangeSpec) -> Vec<T> {
 ///
 /// * `input` - The input string to transform
 /// * `ops` - Slice of operations to apply in sequence
+/// * `final_sep` - Separator for a list result, instead of the last `split` or `join` one
 /// * `debug` - Whether to output detailed debug information with hierarchical tracing to stderr
+/// * `debug_tracer` - Tracer receiving the debug output
+/// * `profiler` - Recorder for per-operation timings, if profiling is enabled
+/// * `guard` - Resource limits of the running format call, if any
 ///
 /// # Returns
 ///
 /// * `Ok(String)` - The transformed result
-/// * `Err(String)` - Error description if any operation fails
+/// * `Err(OpError)` - Error description, with the position of the failing operation
 ///
 /// # Errors
 ///
@@ -1094,6 +5030,7 @@ fn apply_range<T: Clone>(items: &[T], range: &RangeSpec) -> Vec<T> {
 /// - Operations are applied to incompatible types
 /// - Nested map operations are attempted
 /// - Invalid arguments are provided to operations
+/// - A configured [`Limits`] bound is exceeded
 ///
 /// # Examples
 ///
@@ -1108,80 +5045,238 @@ fn apply_range<T: Clone>(items: &[T], range: &RangeSpec) -> Vec<T> {
 pub fn apply_ops_internal(
     input: &str,
     ops: &[StringOp],
+    final_sep: Option<&str>,
     debug: bool,
     debug_tracer: Option<DebugTracer>,
-) -> Result<String, String> {
-    let mut val = Value::Str(input.to_string());
+    profiler: Option<&Profiler>,
+    guard: Option<&ExecGuard>,
+) -> Result<String, OpError> {
+    let mut val = Value::Str(Cow::Borrowed(input));
     let mut default_sep = " ".to_string();
+    let mut output_sep: Option<&str> = final_sep;
     let start_time = if debug { Some(Instant::now()) } else { None };
+    let span_tracer = debug_tracer.as_ref().filter(|_| debug);
+    let _pipeline_span = span_tracer.map(|t| t.pipeline_span(ops.len()));
 
     if debug && let Some(ref tracer) = debug_tracer {
         tracer.pipeline_start(ops, &val);
     }
 
+    // JSON and `tracing` traces report `map` once it has finished, with its
+    // real output and duration; the text tree announces it up front instead.
+    let structured_trace = debug_tracer
+        .as_ref()
+        .is_some_and(|t| t.format() != DebugFormat::Text);
+
     for (i, op) in ops.iter().enumerate() {
-        let step_start = if debug { Some(Instant::now()) } else { None };
+        if let Some(guard) = guard {
+            guard.check_deadline().map_err(OpError::at(i))?;
+        }
+        let _op_span = span_tracer.map(|t| t.operation_span(i + 1, op));
+
+        let step_start = if debug || profiler.is_some() {
+            Some(Instant::now())
+        } else {
+            None
+        };
         let input_val = if debug { Some(val.clone()) } else { None };
 
         match op {
-            StringOp::Map { operations } => {
-                if debug && let Some(ref tracer) = debug_tracer {
+            StringOp::Map { operations } | StringOp::MapFallible { operations } => {
+                let fallible = matches!(op, StringOp::MapFallible { .. });
+                if debug
+                    && !structured_trace
+                    && let Some(ref tracer) = debug_tracer
+                {
                     tracer.operation_step(
                         i + 1,
                         ops.len(),
                         op,
                         input_val.as_ref().unwrap(),
-                        &Value::Str("processing...".to_string()),
+                        &Value::Str(Cow::Borrowed("processing...")),
                         Duration::from_nanos(0),
                     );
                 }
 
                 if let Value::List(list) = val {
+                    // Items are only traced at the full debug level
+                    let item_debug = debug
+                        && debug_tracer
+                            .as_ref()
+                            .is_none_or(|t| t.level() == DebugLevel::Full);
+                    // Traces and profiles report every inner operation, so
+                    // only plain runs use the fused string chain.
+                    let fused = !item_debug
+                        && profiler.is_none()
+                        && !strict_ranges()
+                        && is_fusable_string_chain(operations);
                     let mapped = list
                         .iter()
                         .enumerate()
                         .map(|(item_idx, item)| {
+                            let _item_span =
+                                span_tracer.map(|t| t.map_item_span(item_idx + 1, list.len()));
                             if debug && let Some(ref tracer) = debug_tracer {
                                 tracer.map_item_start(item_idx + 1, list.len(), item);
                             }
 
-                            let sub_tracer = DebugTracer::sub_pipeline(debug);
+                            if fused {
+                                if let Some(guard) = guard {
+                                    guard.check_deadline()?;
+                                }
+                                return Ok(apply_string_chain(item, operations));
+                            }
+
+                            let sub_tracer = debug_tracer.as_ref().map_or_else(
+                                || DebugTracer::sub_pipeline(item_debug),
+                                DebugTracer::nested,
+                            );
                             let result = apply_ops_internal(
                                 item,
                                 operations.as_slice(),
-                                debug,
+                                None,
+                                item_debug,
                                 Some(sub_tracer),
+                                profiler,
+                                guard,
                             );
 
                             if debug && let Some(ref tracer) = debug_tracer {
                                 match &result {
                                     Ok(output) => tracer.map_item_end(Ok(output)),
-                                    Err(e) => tracer.map_item_end(Err(e)),
+                                    Err(e) => tracer.map_item_end(Err(&e.message)),
                                 }
                             }
 
                             result
                         })
-                        .collect::<Result<Vec<_>, _>>()?;
+                        // `map_fallible` drops the items it fails on, but
+                        // never lets a limit be exceeded
+                        .filter(|result| match result {
+                            Err(e) => !fallible || limits::is_limit_error(&e.message),
+                            Ok(_) => true,
+                        })
+                        // Mapped items are already on the heap; keeping the
+                        // buffer is cheaper than copying short ones inline
+                        .map(|result| result.map(Item::from_string_buffer));
+                    let mapped = collect_item_results(mapped, list.len(), operations)
+                        .map_err(OpError::at(i))?;
 
                     if debug && let Some(ref tracer) = debug_tracer {
                         tracer.map_complete(list.len(), mapped.len());
                     }
-
-                    val = Value::List(mapped);
-                } else {
-                    return Err("Map operation can only be applied to lists".to_string());
+
+                    val = Value::list(mapped);
+                } else {
+                    let name = if fallible { "MapFallible" } else { "Map" };
+                    return Err(OpError::at(i)(format!(
+                        "{name} operation can only be applied to lists"
+                    )));
+                }
+            }
+            StringOp::Try {
+                operations,
+                fallback,
+            } => {
+                let Value::Str(s) = &val else {
+                    return Err(OpError::at(i)(
+                        "Try operation can only be applied to strings. Use map:{try:...} for lists."
+                            .to_string(),
+                    ));
+                };
+                let sub_tracer = debug_tracer.as_ref().map(DebugTracer::nested);
+                match apply_ops_internal(s, operations, None, debug, sub_tracer, profiler, guard) {
+                    Ok(output) => val = Value::owned_str(output),
+                    Err(e) if limits::is_limit_error(&e.message) => {
+                        return Err(OpError::at(i)(e));
+                    }
+                    Err(_) => {
+                        if let Some(fallback) = fallback {
+                            val = Value::owned_str(fallback.clone());
+                        }
+                    }
                 }
             }
 
+            StringOp::SortBy {
+                operations,
+                numeric,
+                direction,
+            } => {
+                let Value::List(list) = val else {
+                    return Err(OpError::at(i)(
+                        "SortBy operation can only be applied to lists".to_string(),
+                    ));
+                };
+                let keys =
+                    apply_to_items(&list, operations, profiler, guard).map_err(OpError::at(i))?;
+                let mut keyed: Vec<(SortKey, Item)> = keys
+                    .into_iter()
+                    .map(|key| SortKey::new(key, *numeric))
+                    .zip(Arc::unwrap_or_clone(list))
+                    .collect();
+                keyed.sort_by(|(a, _), (b, _)| match direction {
+                    SortDirection::Asc => a.cmp(b),
+                    SortDirection::Desc => b.cmp(a),
+                });
+                val = Value::list(keyed.into_iter().map(|(_, item)| item).collect());
+            }
+            StringOp::UniqueBy { operations } => {
+                let Value::List(list) = val else {
+                    return Err(OpError::at(i)(
+                        "UniqueBy operation can only be applied to lists".to_string(),
+                    ));
+                };
+                let keys =
+                    apply_to_items(&list, operations, profiler, guard).map_err(OpError::at(i))?;
+                let mut seen = std::collections::HashSet::with_capacity(keys.len());
+                // Items are visited in order, so each one meets its own key
+                let mut keep = keys.iter().map(|key| seen.insert(key.as_str()));
+                val = Value::List(retain_items(list, |_| keep.next().unwrap_or(false)));
+            }
+            StringOp::FilterBy {
+                operations,
+                pattern,
+            } => {
+                let Value::List(list) = val else {
+                    return Err(OpError::at(i)(
+                        "FilterBy operation can only be applied to lists".to_string(),
+                    ));
+                };
+                let re = pattern
+                    .as_deref()
+                    .map(|pattern| get_cached_regex(pattern, guard))
+                    .transpose()
+                    .map_err(OpError::at(i))?;
+                let results =
+                    apply_to_items(&list, operations, profiler, guard).map_err(OpError::at(i))?;
+                let mut keep = results.iter().map(|result| match &re {
+                    Some(re) => re.is_match(result),
+                    None => !result.is_empty(),
+                });
+                val = Value::List(retain_items(list, |_| keep.next().unwrap_or(false)));
+            }
+
+            StringOp::OutputSep { sep } => output_sep = Some(sep),
+
             // All other operations use the shared implementation
             _ => {
-                val = apply_single_operation(op, val, &mut default_sep)?;
+                val = apply_single_operation(op, val, &mut default_sep, guard)
+                    .map_err(OpError::at(i))?;
             }
         }
 
+        if let Some(guard) = guard {
+            guard.check_value(&val).map_err(OpError::at(i))?;
+        }
+
+        if let Some(profiler) = profiler {
+            profiler.record(op.keyword(), step_start.unwrap().elapsed());
+        }
+
         if debug
-            && !matches!(op, StringOp::Map { .. })
+            && (structured_trace
+                || !matches!(op, StringOp::Map { .. } | StringOp::MapFallible { .. }))
             && let Some(ref tracer) = debug_tracer
         {
             let elapsed = step_start.unwrap().elapsed();
@@ -1196,23 +5291,103 @@ pub fn apply_ops_internal(
         }
     }
 
+    let join_sep = output_sep.unwrap_or(&default_sep);
     if debug && let Some(ref tracer) = debug_tracer {
         let total_elapsed = start_time.unwrap().elapsed();
-        tracer.pipeline_end(&val, total_elapsed);
+        tracer.pipeline_end(&val, join_sep, total_elapsed);
     }
 
     Ok(match val {
-        Value::Str(s) => s,
+        Value::Str(s) => s.into_owned(),
         Value::List(list) => {
             if list.is_empty() {
                 String::new()
             } else {
-                list.join(&default_sep)
+                list.join(join_sep)
             }
         }
     })
 }
 
+/// Runs a sub-pipeline on every item of a list, as `map` does when neither
+/// tracing nor profiling is enabled.
+///
+/// Used by operations that derive a per-item key but keep the original items.
+fn apply_to_items(
+    list: &[Item],
+    operations: &[StringOp],
+    profiler: Option<&Profiler>,
+    guard: Option<&ExecGuard>,
+) -> Result<Vec<String>, OpError> {
+    let fused = profiler.is_none() && !strict_ranges() && is_fusable_string_chain(operations);
+    let results = list.iter().map(|item| {
+        if fused {
+            if let Some(guard) = guard {
+                guard.check_deadline()?;
+            }
+            return Ok(apply_string_chain(item, operations));
+        }
+        apply_ops_internal(item, operations, None, false, None, profiler, guard)
+    });
+    collect_item_results(results, list.len(), operations)
+}
+
+/// Sort key computed by `sort_by`.
+///
+/// Numeric keys order before text keys, so text compares below every number.
+#[derive(PartialEq, Eq)]
+struct SortKey {
+    number: Option<NumericKey>,
+    text: String,
+}
+
+impl SortKey {
+    fn new(text: String, numeric: bool) -> Self {
+        let number = if numeric {
+            text.trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|n| !n.is_nan())
+                .map(NumericKey)
+        } else {
+            None
+        };
+        Self { number, text }
+    }
+}
+
+impl Ord for SortKey {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.number
+            .cmp(&other.number)
+            .then_with(|| self.text.cmp(&other.text))
+    }
+}
+
+impl PartialOrd for SortKey {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Non-NaN number with a total order.
+#[derive(Clone, Copy, PartialEq)]
+struct NumericKey(f64);
+
+impl Eq for NumericKey {}
+
+impl Ord for NumericKey {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl PartialOrd for NumericKey {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 /// Apply a transformation function to a string value with type checking.
 ///
 /// This helper function ensures that string-only operations are only applied to
@@ -1234,12 +5409,16 @@ pub fn apply_ops_internal(
 ///
 /// This function enforces type safety by rejecting list inputs for string-only
 /// operations, guiding users to use `map:{{operation}}` syntax for list processing.
-fn apply_string_operation<F>(val: Value, transform: F, op_name: &str) -> Result<Value, String>
+fn apply_string_operation<'a, F>(
+    val: Value<'a>,
+    transform: F,
+    op_name: &str,
+) -> Result<Value<'a>, String>
 where
-    F: FnOnce(String) -> String,
+    F: FnOnce(&str) -> String,
 {
     if let Value::Str(s) = val {
-        Ok(Value::Str(transform(s)))
+        Ok(Value::owned_str(transform(&s)))
     } else {
         Err(format!(
             "{} operation can only be applied to strings. Use map:{{{}}} for lists.",
@@ -1249,6 +5428,47 @@ where
     }
 }
 
+/// Apply a string operation whose result may borrow from its input.
+///
+/// Borrowed input stays borrowed when the result is a slice of it, so
+/// operations such as `basename` and `url_host` copy nothing for template
+/// input.
+fn apply_borrowing_operation<'a, F>(
+    val: Value<'a>,
+    transform: F,
+    keyword: &str,
+) -> Result<Value<'a>, String>
+where
+    F: for<'x> FnOnce(&'x str) -> Cow<'x, str>,
+{
+    if let Value::Str(s) = val {
+        Ok(Value::Str(match s {
+            Cow::Borrowed(b) => transform(b),
+            Cow::Owned(o) => Cow::Owned(transform(&o).into_owned()),
+        }))
+    } else {
+        Err(format!(
+            "{keyword} operation can only be applied to strings. Use map:{{{keyword}}} for lists."
+        ))
+    }
+}
+
+/// Like [`apply_string_operation`], for transformations that can fail.
+fn try_apply_string_operation<'a, F>(
+    val: Value<'a>,
+    transform: F,
+    op_name: &str,
+) -> Result<Value<'a>, String>
+where
+    F: FnOnce(&str) -> Result<String, String>,
+{
+    if let Value::Str(s) = val {
+        Ok(Value::owned_str(transform(&s)?))
+    } else {
+        apply_string_operation(val, |_| String::new(), op_name)
+    }
+}
+
 /// Apply a transformation function to a list value with type checking.
 ///
 /// This helper function ensures that list-only operations are only applied to
@@ -1270,12 +5490,16 @@ where
 ///
 /// This function enforces type safety by rejecting string inputs for list-only
 /// operations, ensuring operations are applied to the correct data types.
-fn apply_list_operation<F>(val: Value, transform: F, op_name: &str) -> Result<Value, String>
+fn apply_list_operation<'a, F>(
+    val: Value<'a>,
+    transform: F,
+    op_name: &str,
+) -> Result<Value<'a>, String>
 where
-    F: FnOnce(Vec<String>) -> Vec<String>,
+    F: FnOnce(List) -> Vec<Item>,
 {
     if let Value::List(list) = val {
-        Ok(Value::List(transform(list)))
+        Ok(Value::list(transform(list)))
     } else {
         Err(format!("{op_name} operation can only be applied to lists"))
     }
@@ -1290,7 +5514,8 @@ where
 /// # Operation Categories
 ///
 /// - **Type-converting**: `Split` (String→List), `Join` (List→String)
-/// - **List operations**: `Slice`, `Sort`, `Unique`, `Filter`, `FilterNot`
+/// - **List operations**: `Slice`, `Sort`, `Unique`, `Filter`, `FilterNot`,
+///   `FilterPrefix`, `FilterSuffix`, `FilterContains`
 /// - **String operations**: `Upper`, `Lower`, `Trim`, `Replace`, `Append`, etc.
 /// - **Type-preserving**: `Reverse` (works on both strings and lists)
 ///
@@ -1318,76 +5543,130 @@ where
 /// - Type mismatches (applying string ops to lists, etc.)
 /// - Invalid regex patterns
 /// - Out-of-bounds access attempts
-fn apply_single_operation(
+fn apply_single_operation<'a>(
     op: &StringOp,
-    val: Value,
+    val: Value<'a>,
     default_sep: &mut String,
-) -> Result<Value, String> {
+    guard: Option<&ExecGuard>,
+) -> Result<Value<'a>, String> {
     match op {
         // List operations - work on lists
-        StringOp::Split { sep, range } => {
-            let parts: Vec<String> = match &val {
-                Value::Str(s) => {
-                    // Use cached split for string inputs
-                    get_cached_split(s, sep)
+        StringOp::Split {
+            sep,
+            range,
+            max_splits,
+            keep_sep,
+            skip_empty,
+        } => {
+            let options = SplitOptions {
+                any_char: false,
+                keep_sep: *keep_sep,
+                skip_empty: *skip_empty,
+            };
+            let parts: List = match &val {
+                Value::Str(s) => split_parts(s, sep, *max_splits, options),
+                Value::List(list) => {
+                    let mut flat = Vec::with_capacity(list.len());
+                    for item in list.iter() {
+                        flat.extend_from_slice(&split_parts(item, sep, *max_splits, options));
+                    }
+                    Arc::new(flat)
                 }
-                Value::List(list) => list.iter().flat_map(|s| get_cached_split(s, sep)).collect(),
             };
             *default_sep = get_interned_separator(sep);
-
-            let result = apply_range(&parts, range);
-
-            // If the range is a single index, return a string instead of a list
-            match range {
-                RangeSpec::Index(_) => {
-                    if result.len() == 1 {
-                        Ok(Value::Str(result[0].clone()))
-                    } else if result.is_empty() {
-                        Ok(Value::Str(String::new()))
-                    } else {
-                        // This shouldn't happen with a single index, but handle gracefully
-                        Ok(Value::List(result))
+            select_parts(parts, range, guard)
+        }
+        StringOp::SplitAny { chars, range } => {
+            let options = SplitOptions {
+                any_char: true,
+                ..SplitOptions::default()
+            };
+            let parts: List = match &val {
+                Value::Str(s) => get_cached_split(s, chars, options),
+                Value::List(list) => {
+                    let mut flat = Vec::with_capacity(list.len());
+                    for item in list.iter() {
+                        flat.extend_from_slice(&get_cached_split(item, chars, options));
                     }
+                    Arc::new(flat)
                 }
-                _ => Ok(Value::List(result)),
-            }
+            };
+            let first = chars.chars().next().map_or(0, char::len_utf8);
+            *default_sep = get_interned_separator(&chars[..first]);
+            select_parts(parts, range, guard)
+        }
+        StringOp::Partition { sep } | StringOp::RPartition { sep } => {
+            let from_end = matches!(op, StringOp::RPartition { .. });
+            let Value::Str(s) = val else {
+                return Err(format!(
+                    "{} operation can only be applied to strings. Use map:{{{}:...}} for lists.",
+                    if from_end { "RPartition" } else { "Partition" },
+                    op.keyword()
+                ));
+            };
+            let found = if from_end {
+                s.rfind(sep.as_str())
+            } else {
+                s.find(sep.as_str())
+            };
+            let parts = match found {
+                Some(at) => vec![
+                    Item::from(&s[..at]),
+                    Item::from(sep.as_str()),
+                    Item::from(&s[at + sep.len()..]),
+                ],
+                None if from_end => vec![Item::default(), Item::default(), Item::from(s)],
+                None => vec![Item::from(s), Item::default(), Item::default()],
+            };
+            // The separator is an item, so rendering the list as-is joins
+            // the parts back into the input.
+            default_sep.clear();
+            Ok(Value::list(parts))
         }
-        StringOp::Join { sep } => {
+        StringOp::Join { sep, last_sep } => {
             let result = match val {
-                Value::List(list) => Value::Str(list.join(sep)),
+                Value::List(list) => Value::owned_str(join_items(&list, sep, last_sep.as_deref())),
                 Value::Str(s) => Value::Str(s), // Pass through strings unchanged
             };
             *default_sep = get_interned_separator(sep);
             Ok(result)
         }
         StringOp::Slice { range } => {
-            apply_list_operation(val, |list| apply_range(&list, range), "Slice")
+            if let Value::List(list) = &val {
+                check_range_bounds(list.len(), range, "item")?;
+            }
+            apply_list_operation(val, |list| select_items(list, range), "Slice")
+        }
+        StringOp::Clamp { range } => {
+            apply_list_operation(val, |list| select_items(list, range), "Clamp")
         }
         StringOp::Filter { pattern } => {
-            let re = get_cached_regex(pattern)?;
-            match val {
-                Value::List(list) => Ok(Value::List(
-                    list.into_iter().filter(|s| re.is_match(s)).collect(),
-                )),
-                Value::Str(s) => Ok(Value::Str(if re.is_match(&s) { s } else { String::new() })),
-            }
+            let re = get_cached_regex(pattern, guard)?;
+            Ok(retain_value(val, |s| re.is_match(s)))
         }
         StringOp::FilterNot { pattern } => {
-            let re = get_cached_regex(pattern)?;
-            match val {
-                Value::List(list) => Ok(Value::List(
-                    list.into_iter().filter(|s| !re.is_match(s)).collect(),
-                )),
-                Value::Str(s) => Ok(Value::Str(if re.is_match(&s) { String::new() } else { s })),
-            }
+            let re = get_cached_regex(pattern, guard)?;
+            Ok(retain_value(val, |s| !re.is_match(s)))
         }
+        StringOp::FilterPrefix { prefix } => Ok(retain_value(val, |s| s.starts_with(prefix.as_str()))),
+        StringOp::FilterSuffix { suffix } => Ok(retain_value(val, |s| s.ends_with(suffix.as_str()))),
+        StringOp::FilterContains { needle } => Ok(retain_value(val, |s| s.contains(needle.as_str()))),
+        StringOp::FilterValidUtf8 => Ok(retain_value(val, checksum::is_valid_utf8)),
+        StringOp::FilterLuhn => Ok(retain_value(val, checksum::is_luhn)),
+        StringOp::FilterChecksum {
+            algorithm,
+            expected,
+        } => Ok(retain_value(val, |s| {
+            algorithm.checksum(s.as_bytes()) == *expected
+        })),
         StringOp::Sort { direction } => {
             if let Value::List(mut list) = val {
+                let items = Arc::make_mut(&mut list);
                 match direction {
-                    SortDirection::Asc => list.sort(),
+                    SortDirection::Asc => items.sort(),
                     SortDirection::Desc => {
-                        list.sort();
-                        list.reverse();
+                        items.sort();
+                        items.reverse();
                     }
                 }
                 Ok(Value::List(list))
@@ -1396,82 +5675,164 @@ fn apply_single_operation(
             }
         }
         StringOp::Reverse => match val {
-            Value::Str(s) => Ok(Value::Str(
-                ascii_reverse(&s).unwrap_or_else(|| s.chars().rev().collect()),
+            Value::Str(s) => Ok(Value::owned_str(
+                ascii_reverse(&s).unwrap_or_else(|| s.graphemes(true).rev().collect()),
             )),
             Value::List(mut list) => {
-                list.reverse();
+                Arc::make_mut(&mut list).reverse();
                 Ok(Value::List(list))
             }
         },
+        StringOp::Shuffle { seed } => {
+            if let Value::List(mut list) = val {
+                let items = Arc::make_mut(&mut list);
+                random::shuffle(items, &mut random::Rng::new(*seed));
+                Ok(Value::List(list))
+            } else {
+                Err("Shuffle operation can only be applied to lists".to_string())
+            }
+        }
+        StringOp::Sample { count, seed } => apply_list_operation(
+            val,
+            |list| {
+                random::sample_indices(list.len(), *count, &mut random::Rng::new(*seed))
+                    .into_iter()
+                    .map(|i| list[i].clone())
+                    .collect()
+            },
+            "Sample",
+        ),
+        StringOp::Table { sep, align, header } => apply_list_operation(
+            val,
+            |list| table_rows(&list, sep, align, *header),
+            "Table",
+        ),
+        StringOp::MarkdownTable { sep, align, header } => apply_list_operation(
+            val,
+            |list| markdown_table_rows(&list, sep, align, *header),
+            "MarkdownTable",
+        ),
+        StringOp::HtmlTable { sep, align, header } => apply_list_operation(
+            val,
+            |list| html_table_rows(&list, sep, align, *header),
+            "HtmlTable",
+        ),
+        StringOp::Intersect { other } | StringOp::Diff { other } | StringOp::Union { other } => {
+            let Value::List(list) = val else {
+                return Err(format!(
+                    "{} operation can only be applied to lists",
+                    match op {
+                        StringOp::Intersect { .. } => "Intersect",
+                        StringOp::Diff { .. } => "Diff",
+                        _ => "Union",
+                    }
+                ));
+            };
+            let other = other.items(guard)?;
+            let others: std::collections::HashSet<&str> =
+                other.iter().map(String::as_str).collect();
+            let mut seen = std::collections::HashSet::with_capacity(list.len());
+            let mut result: Vec<Item> = list
+                .iter()
+                .filter(|item| match op {
+                    StringOp::Intersect { .. } => others.contains(item.as_str()),
+                    StringOp::Diff { .. } => !others.contains(item.as_str()),
+                    _ => true,
+                })
+                .filter(|item| seen.insert(item.as_str()))
+                .cloned()
+                .collect();
+            if let StringOp::Union { .. } = op {
+                result.extend(
+                    other
+                        .iter()
+                        .filter(|item| seen.insert(item.as_str()))
+                        .map(|item| Item::from(item.as_str())),
+                );
+            }
+            Ok(Value::list(result))
+        }
+        StringOp::Duplicates => apply_list_operation(
+            val,
+            |list| {
+                let mut counts: HashMap<&str, usize> = HashMap::with_capacity(list.len());
+                for item in list.iter() {
+                    *counts.entry(item.as_str()).or_default() += 1;
+                }
+                list.iter()
+                    .filter(|item| counts.remove(item.as_str()).is_some_and(|n| n > 1))
+                    .cloned()
+                    .collect()
+            },
+            "Duplicates",
+        ),
+        StringOp::Histogram { top } => apply_list_operation(
+            val,
+            |list| {
+                let mut counts: Vec<(&str, usize)> = Vec::new();
+                let mut positions: HashMap<&str, usize> = HashMap::with_capacity(list.len());
+                for item in list.iter() {
+                    let position = *positions.entry(item.as_str()).or_insert_with(|| {
+                        counts.push((item.as_str(), 0));
+                        counts.len() - 1
+                    });
+                    counts[position].1 += 1;
+                }
+                // Stable, so equal counts keep their first-appearance order
+                counts.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+                counts
+                    .into_iter()
+                    .take(top.unwrap_or(usize::MAX))
+                    .map(|(item, count)| Item::from(format!("{item}\t{count}")))
+                    .collect()
+            },
+            "Histogram",
+        ),
+        StringOp::RemoveEmpty => apply_list_operation(
+            val,
+            |list| {
+                list.iter()
+                    .filter(|item| !item.trim().is_empty())
+                    .cloned()
+                    .collect()
+            },
+            "RemoveEmpty",
+        ),
+        StringOp::Squeeze { chars } => {
+            if let Value::Str(s) = val {
+                Ok(Value::Str(rewrite_str(s, |s| squeeze_str(s, chars))))
+            } else {
+                Err("Squeeze operation can only be applied to strings. Use map:{squeeze} for lists.".to_string())
+            }
+        }
         StringOp::Unique => apply_list_operation(
             val,
             |list| {
-                let mut seen = std::collections::HashSet::new();
-                list.into_iter()
-                    .filter(|item| seen.insert(item.clone()))
+                // Only the kept items are cloned, whether or not the list is shared
+                let mut seen = std::collections::HashSet::with_capacity(list.len());
+                list.iter()
+                    .filter(|item| seen.insert(item.as_str()))
+                    .cloned()
                     .collect()
             },
             "Unique",
         ),
         StringOp::Substring { range } => {
             if let Value::Str(s) = val {
-                if s.is_ascii() {
-                    // Optimized ASCII path - work directly with bytes
-                    let bytes = s.as_bytes();
-                    let result_bytes = apply_range(bytes, range);
-                    // Safety: ASCII input guarantees valid UTF-8 output
-                    let result = unsafe { String::from_utf8_unchecked(result_bytes) };
-                    Ok(Value::Str(result))
-                } else {
-                    // UTF-8 handling for Unicode strings
-                    let chars: Vec<char> = s.chars().collect();
-                    let result: String = apply_range(&chars, range).into_iter().collect();
-                    Ok(Value::Str(result))
+                if strict_ranges() {
+                    check_range_bounds(s.graphemes(true).count(), range, "character")?;
                 }
+                Ok(Value::Str(substring_str(s, range)))
             } else {
                 Err("Substring operation can only be applied to strings. Use map:{substring:...} for lists.".to_string())
             }
         }
-        StringOp::Replace {
-            pattern,
-            replacement,
-            flags,
-        } => {
-            if let Value::Str(s) = val {
-                // Early exit for simple string patterns (not regex)
-                if !flags.contains('g')
-                    && !pattern.contains([
-                        '\\', '.', '*', '+', '?', '^', '$', '|', '[', ']', '(', ')', '{', '}',
-                    ])
-                    && !s.contains(pattern)
-                {
-                    return Ok(Value::Str(s));
+        StringOp::Replace { rules } => {
+            if let Value::Str(mut s) = val {
+                for rule in rules {
+                    s = apply_sed_rule(s, rule, guard)?;
                 }
-
-                let pattern_to_use = if flags.is_empty() {
-                    pattern.clone()
-                } else {
-                    let mut inline_flags = String::with_capacity(4);
-                    for (flag, c) in [('i', 'i'), ('m', 'm'), ('s', 's'), ('x', 'x')] {
-                        if flags.contains(flag) {
-                            inline_flags.push(c);
-                        }
-                    }
-                    if inline_flags.is_empty() {
-                        pattern.clone()
-                    } else {
-                        format!("(?{inline_flags}){pattern}")
-                    }
-                };
-
-                let re = get_cached_regex(&pattern_to_use)?;
-                let result = if flags.contains('g') {
-                    re.replace_all(&s, replacement.as_str()).to_string()
-                } else {
-                    re.replace(&s, replacement.as_str()).to_string()
-                };
-                Ok(Value::Str(result))
+                Ok(Value::Str(s))
             } else {
                 Err(
                     "Replace operation can only be applied to strings. Use map:{replace:...} for lists."
@@ -1479,39 +5840,38 @@ fn apply_single_operation(
                 )
             }
         }
-        StringOp::Upper => apply_string_operation(val, |s| s.to_uppercase(), "Upper"),
-        StringOp::Lower => apply_string_operation(val, |s| s.to_lowercase(), "Lower"),
+        StringOp::ReplaceLiteral { from, to, count } => match val {
+            Value::Str(s) => Ok(Value::Str(rewrite_str(s, |s| {
+                if !s.contains(from.as_str()) {
+                    Cow::Borrowed(s)
+                } else if let Some(count) = count {
+                    Cow::Owned(s.replacen(from.as_str(), to, *count))
+                } else {
+                    Cow::Owned(s.replace(from.as_str(), to))
+                }
+            }))),
+            Value::List(_) => Err(
+                "ReplaceLiteral operation can only be applied to strings. Use map:{replace_literal:...} for lists."
+                    .to_string(),
+            ),
+        },
+        StringOp::Upper { locale } => {
+            apply_string_operation(val, |s| locale.to_upper(s), "Upper")
+        }
+        StringOp::Lower { locale } => {
+            apply_string_operation(val, |s| locale.to_lower(s), "Lower")
+        }
+        StringOp::Capitalize { lower_rest, locale } => apply_string_operation(
+            val,
+            |s| locale.capitalize(s, *lower_rest),
+            "Capitalize",
+        ),
+        StringOp::SwapCase { locale } => {
+            apply_string_operation(val, |s| locale.swap_case(s), "SwapCase")
+        }
         StringOp::Trim { chars, direction } => {
             if let Value::Str(s) = val {
-                // Fast path for default whitespace trimming
-                let result = if chars.is_empty() || chars.trim().is_empty() {
-                    match direction {
-                        TrimDirection::Both => {
-                            if let Some(trimmed) = ascii_trim(&s) {
-                                trimmed.to_string()
-                            } else {
-                                s.trim().to_string()
-                            }
-                        }
-                        TrimDirection::Left => s.trim_start().to_string(),
-                        TrimDirection::Right => s.trim_end().to_string(),
-                    }
-                } else {
-                    // Custom character trimming with optimized character set
-                    let chars_to_trim: Vec<char> = chars.chars().collect();
-                    match direction {
-                        TrimDirection::Both => {
-                            s.trim_matches(|c| chars_to_trim.contains(&c)).to_string()
-                        }
-                        TrimDirection::Left => s
-                            .trim_start_matches(|c| chars_to_trim.contains(&c))
-                            .to_string(),
-                        TrimDirection::Right => s
-                            .trim_end_matches(|c| chars_to_trim.contains(&c))
-                            .to_string(),
-                    }
-                };
-                Ok(Value::Str(result))
+                Ok(Value::Str(trim_str(s, chars, direction)))
             } else {
                 Err(
                     "Trim operation can only be applied to strings. Use map:{trim} for lists."
@@ -1519,6 +5879,49 @@ fn apply_single_operation(
                 )
             }
         }
+        StringOp::TrimPrefix { prefix } => match val {
+            Value::Str(s) => Ok(Value::Str(narrow_str(s, |s| {
+                s.strip_prefix(prefix.as_str()).unwrap_or(s)
+            }))),
+            Value::List(_) => Err(
+                "TrimPrefix operation can only be applied to strings. Use map:{trim_prefix:...} for lists."
+                    .to_string(),
+            ),
+        },
+        StringOp::TrimSuffix { suffix } => match val {
+            Value::Str(s) => Ok(Value::Str(narrow_str(s, |s| {
+                s.strip_suffix(suffix.as_str()).unwrap_or(s)
+            }))),
+            Value::List(_) => Err(
+                "TrimSuffix operation can only be applied to strings. Use map:{trim_suffix:...} for lists."
+                    .to_string(),
+            ),
+        },
+        StringOp::TrimRegex { pattern, direction } => {
+            let Value::Str(s) = val else {
+                return Err(
+                    "TrimRegex operation can only be applied to strings. Use map:{trim_regex:...} for lists."
+                        .to_string(),
+                );
+            };
+            let start = match direction {
+                TrimDirection::Right => None,
+                _ => Some(get_cached_regex(&format!(r"\A(?:{pattern})"), guard)?),
+            };
+            let end = match direction {
+                TrimDirection::Left => None,
+                _ => Some(get_cached_regex(&format!(r"(?:{pattern})\z"), guard)?),
+            };
+            Ok(Value::Str(narrow_str(s, |mut s| {
+                if let Some(m) = start.as_ref().and_then(|re| re.find(s)) {
+                    s = &s[m.end()..];
+                }
+                if let Some(m) = end.as_ref().and_then(|re| re.find(s)) {
+                    s = &s[..m.start()];
+                }
+                s
+            })))
+        }
 
         StringOp::Append { suffix } => {
             apply_string_operation(val, |s| format!("{s}{suffix}"), "Append")
@@ -1526,45 +5929,206 @@ fn apply_single_operation(
         StringOp::Prepend { prefix } => {
             apply_string_operation(val, |s| format!("{prefix}{s}"), "Prepend")
         }
-        StringOp::Surround { text } => {
-            apply_string_operation(val, |s| format!("{text}{s}{text}"), "Surround")
+        StringOp::Surround { prefix, suffix } => {
+            apply_string_operation(val, |s| format!("{prefix}{s}{suffix}"), "Surround")
         }
         StringOp::StripAnsi => {
             if let Value::Str(s) = val {
-                let result = strip_ansi_string(&s).into_owned();
-                Ok(Value::Str(result))
+                Ok(Value::Str(rewrite_str(s, strip_ansi)))
             } else {
                 Err("StripAnsi operation can only be applied to strings. Use map:{strip_ansi} for lists.".to_string())
             }
         }
+        StringOp::Deaccent => {
+            if let Value::Str(s) = val {
+                Ok(Value::Str(rewrite_str(s, deaccent_str)))
+            } else {
+                Err("Deaccent operation can only be applied to strings. Use map:{deaccent} for lists.".to_string())
+            }
+        }
+        StringOp::Ascii => {
+            if let Value::Str(s) = val {
+                Ok(Value::Str(rewrite_str(s, ascii_str)))
+            } else {
+                Err("Ascii operation can only be applied to strings. Use map:{ascii} for lists.".to_string())
+            }
+        }
+        StringOp::Slugify { separator } => {
+            apply_string_operation(val, |s| slugify_str(s, separator), "Slugify")
+        }
+        StringOp::Normalize { form } => {
+            if let Value::Str(s) = val {
+                Ok(Value::Str(rewrite_str(s, |s| normalize_str(s, *form))))
+            } else {
+                Err("Normalize operation can only be applied to strings. Use map:{normalize:...} for lists.".to_string())
+            }
+        }
+        StringOp::Ordinal { parse } => {
+            let format = if *parse {
+                numeral::parse_ordinal
+            } else {
+                numeral::ordinal
+            };
+            try_apply_string_operation(val, format, "Ordinal")
+        }
+        StringOp::ToWords { parse } => {
+            if let Value::Str(s) = val {
+                let words = if *parse {
+                    numeral::parse_words(&s)?
+                } else {
+                    numeral::to_words(&s)?
+                };
+                Ok(Value::owned_str(words))
+            } else {
+                Err("ToWords operation can only be applied to strings. Use map:{to_words} for lists.".to_string())
+            }
+        }
+        StringOp::Roman { parse } => {
+            let format = if *parse {
+                numeral::parse_roman
+            } else {
+                numeral::roman
+            };
+            try_apply_string_operation(val, format, "Roman")
+        }
+        StringOp::Basename { style } => {
+            apply_borrowing_operation(val, |s| Cow::Borrowed(paths::basename(s, *style)), "basename")
+        }
+        StringOp::Dirname { style } => {
+            apply_borrowing_operation(val, |s| Cow::Borrowed(paths::dirname(s, *style)), "dirname")
+        }
+        StringOp::Extname { style } => {
+            apply_borrowing_operation(val, |s| Cow::Borrowed(paths::extname(s, *style)), "extname")
+        }
+        StringOp::StripExt { style } => apply_borrowing_operation(
+            val,
+            |s| Cow::Owned(paths::with_ext(s, "", *style)),
+            "strip_ext",
+        ),
+        StringOp::WithExt { ext, style } => apply_borrowing_operation(
+            val,
+            |s| Cow::Owned(paths::with_ext(s, ext, *style)),
+            "with_ext",
+        ),
+        StringOp::NormalizePath { style } => apply_borrowing_operation(
+            val,
+            |s| Cow::Owned(paths::normalize_path(s, *style)),
+            "normalize_path",
+        ),
+        StringOp::UrlScheme => apply_borrowing_operation(val, url::scheme, "url_scheme"),
+        StringOp::UrlHost => apply_borrowing_operation(val, url::host, "url_host"),
+        StringOp::UrlPath => {
+            apply_borrowing_operation(val, |s| Cow::Borrowed(url::path(s)), "url_path")
+        }
+        StringOp::UrlQuery { key } => {
+            apply_borrowing_operation(val, |s| url::query_param(s, key), "url_query")
+        }
+        StringOp::YamlGet { path } => {
+            try_apply_string_operation(val, |s| config::yaml_get(s, path), "YamlGet")
+        }
+        StringOp::TomlGet { path } => {
+            try_apply_string_operation(val, |s| config::toml_get(s, path), "TomlGet")
+        }
+        StringOp::Color { color } => {
+            apply_string_operation(val, |s| style::color(s, *color), "Color")
+        }
+        StringOp::Background { color } => {
+            apply_string_operation(val, |s| style::background(s, *color), "Background")
+        }
+        StringOp::Bold => apply_string_operation(val, style::bold, "Bold"),
+        StringOp::Underline => apply_string_operation(val, style::underline, "Underline"),
+        StringOp::StyleReset => apply_string_operation(val, style::reset, "StyleReset"),
+        StringOp::Highlight { pattern, style } => {
+            let re = get_cached_regex(pattern, guard)?;
+            apply_borrowing_operation(val, |s| style::highlight(s, &re, style), "highlight")
+        }
+        StringOp::ReadFile => {
+            if let Value::Str(path) = val {
+                Ok(Value::owned_str(read_limited_file(
+                    Path::new(path.as_ref()),
+                    guard,
+                )?))
+            } else {
+                Err("ReadFile operation can only be applied to strings. Use map:{read_file} for lists.".to_string())
+            }
+        }
+        StringOp::Exec { command } => {
+            if let Value::Str(input) = val {
+                Ok(Value::owned_str(exec::run_command(
+                    command,
+                    &input,
+                    exec_timeout(guard),
+                    exec_output_limit(guard),
+                )?))
+            } else {
+                Err("Exec operation can only be applied to strings. Use map:{exec:...} for lists.".to_string())
+            }
+        }
+        StringOp::Len { unit } => {
+            let len = match &val {
+                Value::Str(s) => match unit {
+                    LengthUnit::Bytes => s.len(),
+                    LengthUnit::Chars => s.chars().count(),
+                    LengthUnit::Graphemes => grapheme_len(s),
+                },
+                Value::List(list) => list.len(),
+            };
+            Ok(Value::owned_str(len.to_string()))
+        }
+        StringOp::JsonArray => {
+            let mut out = String::from("[");
+            for (i, item) in val.items().into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&json_string(item));
+            }
+            out.push(']');
+            Ok(Value::owned_str(out))
+        }
+        StringOp::JsonObject { keys } => {
+            let mut items = val.items().into_iter();
+            let mut out = String::from("{");
+            for (i, key) in keys.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&json_string(key));
+                out.push(':');
+                match items.next() {
+                    Some(item) => out.push_str(&json_string(item)),
+                    None => out.push_str("null"),
+                }
+            }
+            out.push('}');
+            Ok(Value::owned_str(out))
+        }
         StringOp::Pad {
             width,
-            char,
+            fill,
             direction,
+            truncate,
         } => {
             if let Value::Str(s) = val {
-                let current_len = s.chars().count();
-                let result = if current_len >= *width {
+                let current_len = grapheme_len(&s);
+                let result = if current_len > *width && *truncate {
+                    substring_str(s, &RangeSpec::Range(None, Some(*width as isize), false))
+                } else if current_len >= *width {
                     s
                 } else {
                     let padding_needed = *width - current_len;
-                    match direction {
-                        PadDirection::Left => {
-                            format!("{}{s}", char.to_string().repeat(padding_needed))
-                        }
-                        PadDirection::Right => {
-                            format!("{s}{}", char.to_string().repeat(padding_needed))
-                        }
+                    let (left_pad, right_pad) = match direction {
+                        PadDirection::Left => (padding_needed, 0),
+                        PadDirection::Right => (0, padding_needed),
                         PadDirection::Both => {
-                            let left_pad = padding_needed / 2;
-                            let right_pad = padding_needed - left_pad;
-                            format!(
-                                "{}{s}{}",
-                                char.to_string().repeat(left_pad),
-                                char.to_string().repeat(right_pad)
-                            )
+                            (padding_needed / 2, padding_needed - padding_needed / 2)
                         }
-                    }
+                    };
+                    let mut padded = String::with_capacity(s.len() + padding_needed * fill.len());
+                    push_fill(&mut padded, fill, left_pad);
+                    padded.push_str(&s);
+                    push_fill(&mut padded, fill, right_pad);
+                    Cow::Owned(padded)
                 };
                 Ok(Value::Str(result))
             } else {
@@ -1574,24 +6138,133 @@ fn apply_single_operation(
                 )
             }
         }
-        StringOp::RegexExtract { pattern, group } => {
-            if let Value::Str(s) = val {
-                let re = get_cached_regex(pattern)?;
-                let result = if let Some(group_idx) = group {
-                    re.captures(&s)
-                        .and_then(|caps| caps.get(*group_idx))
-                        .map(|m| m.as_str().to_string())
-                        .unwrap_or_default()
+        StringOp::ExtractBetween { open, close, nth } => {
+            let Value::Str(s) = val else {
+                return Err("ExtractBetween operation can only be applied to strings. Use map:{extract_between:...} for lists.".to_string());
+            };
+            Ok(Value::Str(narrow_str(s, |s| {
+                let groups = extract::between(s, open, close);
+                let index = if *nth < 0 {
+                    groups.len().checked_sub(nth.unsigned_abs())
                 } else {
-                    re.find(&s)
-                        .map(|m| m.as_str().to_string())
-                        .unwrap_or_default()
+                    Some(nth.unsigned_abs())
                 };
-                Ok(Value::Str(result))
+                index.and_then(|i| groups.get(i).copied()).unwrap_or("")
+            })))
+        }
+        StringOp::RegexExtract { pattern, group } => {
+            if let Value::Str(s) = val {
+                let re = get_cached_regex(pattern, guard)?;
+                Ok(Value::Str(narrow_str(s, |s| {
+                    let found = if let Some(group_idx) = group {
+                        re.captures(s).and_then(|caps| caps.get(*group_idx))
+                    } else {
+                        re.find(s)
+                    };
+                    found.map_or("", |m| m.as_str())
+                })))
             } else {
                 Err("RegexExtract operation can only be applied to strings. Use map:{regex_extract:...} for lists.".to_string())
             }
         }
-        StringOp::Map { .. } => Err("Map operations should be handled separately".to_string()),
+        StringOp::Kv {
+            key,
+            pair_sep,
+            kv_sep,
+        } => {
+            if let Value::Str(s) = val {
+                let value = kv_pairs(&s, pair_sep, kv_sep)
+                    .into_iter()
+                    .find(|(k, _)| k == key)
+                    .map(|(_, v)| v.into_owned());
+                Ok(Value::owned_str(value.unwrap_or_default()))
+            } else {
+                Err("Kv operation can only be applied to strings. Use map:{kv:...} for lists.".to_string())
+            }
+        }
+        StringOp::KvKeys { pair_sep, kv_sep } | StringOp::KvValues { pair_sep, kv_sep } => {
+            let keys = matches!(op, StringOp::KvKeys { .. });
+            let Value::Str(s) = val else {
+                return Err(format!(
+                    "{} operation can only be applied to strings. Use map:{{{}}} for lists.",
+                    if keys { "KvKeys" } else { "KvValues" },
+                    op.keyword()
+                ));
+            };
+            let items = kv_pairs(&s, pair_sep, kv_sep)
+                .into_iter()
+                .map(|(k, v)| if keys { Item::from(k) } else { Item::from(v) })
+                .collect();
+            Ok(Value::list(items))
+        }
+        StringOp::Words | StringOp::Sentences => {
+            let Value::Str(s) = val else {
+                return Err(format!(
+                    "{} operation can only be applied to strings. Use map:{{{}}} for lists.",
+                    if matches!(op, StringOp::Words) { "Words" } else { "Sentences" },
+                    op.keyword()
+                ));
+            };
+            let items: Vec<Item> = if matches!(op, StringOp::Words) {
+                s.unicode_words().map(Item::from).collect()
+            } else {
+                s.unicode_sentences()
+                    .map(str::trim)
+                    .filter(|sentence| !sentence.is_empty())
+                    .map(Item::from)
+                    .collect()
+            };
+            Ok(Value::list(items))
+        }
+        StringOp::ExtractEmails | StringOp::ExtractUrls | StringOp::ExtractUuids => {
+            let Value::Str(s) = val else {
+                return Err(format!(
+                    "{} operation can only be applied to strings. Use map:{{{}}} for lists.",
+                    op.keyword(),
+                    op.keyword()
+                ));
+            };
+            let items: Vec<Item> = match op {
+                StringOp::ExtractEmails => extract::emails(&s).map(Item::from).collect(),
+                StringOp::ExtractUrls => extract::urls(&s).map(Item::from).collect(),
+                _ => extract::uuids(&s).map(Item::from).collect(),
+            };
+            Ok(Value::list(items))
+        }
+        StringOp::Map { .. } | StringOp::MapFallible { .. } => {
+            Err("Map operations should be handled separately".to_string())
+        }
+        StringOp::Try { .. } => Err("Try operations should be handled separately".to_string()),
+        StringOp::Each { body } => {
+            let mut output = String::new();
+            let mut render = |item: &str| {
+                if let Some(guard) = guard {
+                    guard.check_deadline()?;
+                }
+                output.push_str(&body.format(item)?);
+                Ok::<_, String>(())
+            };
+            match &val {
+                Value::Str(s) => render(s)?,
+                Value::List(list) => {
+                    for item in list.iter() {
+                        render(item)?;
+                    }
+                }
+            }
+            Ok(Value::Str(Cow::Owned(output)))
+        }
+        StringOp::OutputSep { .. } => {
+            Err("OutputSep operations should be handled separately".to_string())
+        }
+        StringOp::SortBy { .. } => {
+            Err("SortBy operations should be handled separately".to_string())
+        }
+        StringOp::UniqueBy { .. } => {
+            Err("UniqueBy operations should be handled separately".to_string())
+        }
+        StringOp::FilterBy { .. } => {
+            Err("FilterBy operations should be handled separately".to_string())
+        }
     }
 }
@@ -0,0 +1,282 @@
+//! Number formatting for the `ordinal`, `to_words` and `roman` operations.
+//!
+//! Each format has an inverse that turns the formatted text back into a
+//! plain integer. Inverses are strict: they accept what the forward
+//! direction writes, ignoring ASCII case and surrounding whitespace, and
+//! reject anything else instead of guessing.
+
+const ONES: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+/// Scale words and their values, largest first.
+const SCALES: [(&str, u64); 6] = [
+    ("quintillion", 1_000_000_000_000_000_000),
+    ("quadrillion", 1_000_000_000_000_000),
+    ("trillion", 1_000_000_000_000),
+    ("billion", 1_000_000_000),
+    ("million", 1_000_000),
+    ("thousand", 1_000),
+];
+
+const ROMAN: [(&str, u16); 13] = [
+    ("M", 1000),
+    ("CM", 900),
+    ("D", 500),
+    ("CD", 400),
+    ("C", 100),
+    ("XC", 90),
+    ("L", 50),
+    ("XL", 40),
+    ("X", 10),
+    ("IX", 9),
+    ("V", 5),
+    ("IV", 4),
+    ("I", 1),
+];
+
+fn parse_integer(s: &str) -> Result<i64, String> {
+    s.trim()
+        .parse()
+        .map_err(|_| format!("Invalid integer: '{s}'"))
+}
+
+fn ordinal_suffix(n: i64) -> &'static str {
+    let n = n.unsigned_abs();
+    match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    }
+}
+
+/// Formats an integer as an English ordinal: `"21"` becomes `"21st"`.
+pub(crate) fn ordinal(s: &str) -> Result<String, String> {
+    let n = parse_integer(s)?;
+    Ok(format!("{n}{}", ordinal_suffix(n)))
+}
+
+/// Parses an English ordinal such as `"21st"` back into `"21"`.
+pub(crate) fn parse_ordinal(s: &str) -> Result<String, String> {
+    let invalid = || format!("Invalid ordinal: '{s}'");
+    let text = s.trim();
+    let split = text
+        .char_indices()
+        .find(|&(i, c)| !(c.is_ascii_digit() || (i == 0 && c == '-')))
+        .map_or(text.len(), |(i, _)| i);
+    let (digits, suffix) = text.split_at(split);
+    let n: i64 = digits.parse().map_err(|_| invalid())?;
+    if suffix.eq_ignore_ascii_case(ordinal_suffix(n)) {
+        Ok(n.to_string())
+    } else {
+        Err(invalid())
+    }
+}
+
+/// Appends the words for `n`, which must be below one thousand and not zero.
+fn push_hundreds(out: &mut String, n: u64) {
+    let (hundreds, rest) = (n / 100, n % 100);
+    if hundreds > 0 {
+        out.push_str(ONES[hundreds as usize]);
+        out.push_str(" hundred");
+        if rest > 0 {
+            out.push(' ');
+        }
+    }
+    if rest >= 20 {
+        out.push_str(TENS[(rest / 10) as usize]);
+        if rest % 10 > 0 {
+            out.push('-');
+            out.push_str(ONES[(rest % 10) as usize]);
+        }
+    } else if rest > 0 {
+        out.push_str(ONES[rest as usize]);
+    }
+}
+
+/// Spells out an integer in English words: `"42"` becomes `"forty-two"`.
+///
+/// Negative numbers start with `minus`, and no `and` is written after
+/// hundreds.
+pub(crate) fn to_words(s: &str) -> Result<String, String> {
+    let n = parse_integer(s)?;
+    if n == 0 {
+        return Ok(ONES[0].to_string());
+    }
+    let mut out = String::new();
+    if n < 0 {
+        out.push_str("minus ");
+    }
+    let mut rest = n.unsigned_abs();
+    for (name, scale) in SCALES {
+        if rest >= scale {
+            push_hundreds(&mut out, rest / scale);
+            out.push(' ');
+            out.push_str(name);
+            rest %= scale;
+            if rest > 0 {
+                out.push(' ');
+            }
+        }
+    }
+    if rest > 0 {
+        push_hundreds(&mut out, rest);
+    }
+    Ok(out)
+}
+
+/// What the previous word of a group of number words was.
+#[derive(Clone, Copy, PartialEq)]
+enum Last {
+    Nothing,
+    Unit,
+    Tens,
+    Hundred,
+}
+
+/// Parses English number words such as `"forty-two"` back into `"42"`.
+///
+/// Hyphens and spaces are interchangeable, and `and` may appear between
+/// words, so `"one hundred and five"` is accepted.
+pub(crate) fn parse_words(s: &str) -> Result<String, String> {
+    let invalid = || format!("Invalid number words: '{s}'");
+    let lowered = s.to_ascii_lowercase().replace(['-', ','], " ");
+    let mut words = lowered.split_whitespace().peekable();
+    let negative = matches!(words.peek(), Some(&("minus" | "negative")));
+    if negative {
+        words.next();
+    }
+    let words: Vec<&str> = words.collect();
+    if words == ["zero"] {
+        return Ok("0".to_string());
+    }
+
+    let (mut total, mut group) = (0u64, 0u64);
+    let mut last = Last::Nothing;
+    let mut last_scale = u64::MAX;
+    for (i, &word) in words.iter().enumerate() {
+        if word == "and" && i > 0 && i + 1 < words.len() {
+            continue;
+        }
+        if let Some(value) = ONES[1..].iter().position(|&w| w == word) {
+            let value = value as u64 + 1;
+            let allowed = match last {
+                Last::Nothing | Last::Hundred => true,
+                Last::Tens => value < 10,
+                Last::Unit => false,
+            };
+            if !allowed {
+                return Err(invalid());
+            }
+            group += value;
+            last = Last::Unit;
+        } else if let Some(tens) = TENS.iter().position(|&w| !w.is_empty() && w == word) {
+            if !matches!(last, Last::Nothing | Last::Hundred) {
+                return Err(invalid());
+            }
+            group += tens as u64 * 10;
+            last = Last::Tens;
+        } else if word == "hundred" {
+            if last != Last::Unit || group >= 10 {
+                return Err(invalid());
+            }
+            group *= 100;
+            last = Last::Hundred;
+        } else if let Some(&(_, scale)) = SCALES.iter().find(|(name, _)| *name == word) {
+            if last == Last::Nothing || scale >= last_scale {
+                return Err(invalid());
+            }
+            total = group
+                .checked_mul(scale)
+                .and_then(|v| total.checked_add(v))
+                .ok_or_else(invalid)?;
+            group = 0;
+            last = Last::Nothing;
+            last_scale = scale;
+        } else {
+            return Err(invalid());
+        }
+    }
+    let magnitude = total.checked_add(group).ok_or_else(invalid)?;
+    if magnitude == 0 {
+        return Err(invalid());
+    }
+    let n = if negative {
+        0i64.checked_sub_unsigned(magnitude)
+    } else {
+        i64::try_from(magnitude).ok()
+    };
+    n.map(|n| n.to_string()).ok_or_else(invalid)
+}
+
+fn format_roman(mut n: u16) -> String {
+    let mut out = String::new();
+    for (symbol, value) in ROMAN {
+        while n >= value {
+            out.push_str(symbol);
+            n -= value;
+        }
+    }
+    out
+}
+
+/// Formats an integer from 1 to 3999 as a Roman numeral: `"14"` becomes
+/// `"XIV"`.
+pub(crate) fn roman(s: &str) -> Result<String, String> {
+    match parse_integer(s)? {
+        n @ 1..=3999 => Ok(format_roman(n as u16)),
+        _ => Err(format!(
+            "Roman numerals only cover 1 to 3999, got '{}'",
+            s.trim()
+        )),
+    }
+}
+
+/// Parses a Roman numeral such as `"XIV"` back into `"14"`.
+///
+/// Only the standard subtractive form is accepted, so `"IIII"` and `"IC"`
+/// are errors.
+pub(crate) fn parse_roman(s: &str) -> Result<String, String> {
+    let numeral = s.trim().to_ascii_uppercase();
+    let mut rest = numeral.as_str();
+    let mut n = 0u16;
+    for (symbol, value) in ROMAN {
+        while let Some(tail) = rest.strip_prefix(symbol) {
+            n += value;
+            rest = tail;
+            if n > 3999 {
+                break;
+            }
+        }
+    }
+    if rest.is_empty() && n > 0 && n <= 3999 && format_roman(n) == numeral {
+        Ok(n.to_string())
+    } else {
+        Err(format!("Invalid roman numeral: '{s}'"))
+    }
+}
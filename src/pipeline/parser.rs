@@ -12,11 +12,19 @@
 use pest::Parser;
 use pest_derive::Parser;
 use smallvec::SmallVec;
+use std::ops::Range;
+use std::path::PathBuf;
 
-use super::{PadDirection, RangeSpec, SortDirection, StringOp, TrimDirection};
+use super::catalog::{operation, suggest};
+use super::config;
+use super::{
+    CaseLocale, ChecksumAlgorithm, ColumnAlign, HighlightStyle, LengthUnit, ListSource, NormalForm,
+    PadDirection, ParserConfig, PathStyle, RangeSpec, SedRule, SortDirection, StringOp, TermColor,
+    TrimDirection,
+};
 
 // Import the new template section types
-use super::template::TemplateSection;
+use super::template::{InputRef, Placeholder, Template, TemplateSection, Variable};
 
 // Common separator constant to avoid repeated allocations
 const SPACE_SEP: &str = " ";
@@ -29,6 +37,82 @@ const SPACE_SEP: &str = " ";
 #[grammar = "pipeline/template.pest"]
 struct TemplateParser;
 
+/// Source location of a parsed operation.
+///
+/// Spans are byte ranges in the whole template text, so errors raised while
+/// formatting can point at the operation that failed.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct OpSpan {
+    /// Byte range of the operation, from its keyword to its last argument.
+    pub(crate) span: Range<usize>,
+    /// Spans of the operations of its sub-pipeline, if it has one.
+    pub(crate) inner: Vec<OpSpan>,
+}
+
+impl OpSpan {
+    /// Records the span of an operation node and of its sub-pipeline.
+    fn new(pair: &pest::iterators::Pair<Rule>, offset: usize) -> Self {
+        let inner = pair
+            .clone()
+            .into_inner()
+            .find(|arg| arg.as_rule() == Rule::map_operation)
+            .map(|map_op| {
+                map_op
+                    .into_inner()
+                    .next()
+                    .unwrap()
+                    .into_inner()
+                    .map(|op_pair| OpSpan::new(&op_pair.into_inner().next().unwrap(), offset))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            span: shifted(pair, offset),
+            inner,
+        }
+    }
+
+    /// Maps the span and the spans of its sub-pipeline through `position`.
+    fn remap(&mut self, position: &impl Fn(usize) -> usize) {
+        // Ends are exclusive, so map the last byte and step past it
+        self.span = position(self.span.start)..position(self.span.end - 1) + 1;
+        for inner in &mut self.inner {
+            inner.remap(position);
+        }
+    }
+}
+
+/// Marker at the start of a template section that enables verbose syntax.
+const VERBOSE_MARKER: &str = "!#";
+
+/// Start of a section that formats a template for each list item.
+const EACH_PREFIX: &str = "each:{";
+
+/// Opens a conditional group in literal text.
+const GROUP_OPEN: &str = "[?";
+
+/// Literal `[?` text that does not open a conditional group.
+const ESCAPED_GROUP_OPEN: &str = "\\[?";
+
+/// Closes a conditional group in literal text.
+const GROUP_CLOSE: char = ']';
+
+/// A parsed `{...}` template section.
+#[derive(Debug)]
+pub(crate) struct ParsedSection {
+    pub ops: Vec<StringOp>,
+    /// Source locations of `ops`.
+    pub spans: Vec<OpSpan>,
+    /// Whether the section starts with the `!` debug flag.
+    pub debug: bool,
+    /// Input selected with `input=`, if any.
+    pub input: Option<InputRef>,
+    /// Variable stored with `set:` or read with `get:`, if any.
+    pub variable: Option<Variable>,
+    /// Value provided by the formatter and read instead of the input, if any.
+    pub placeholder: Option<Placeholder>,
+}
+
 /// Parses a template string into operations and debug flag.
 ///
 /// This is the main entry point for template parsing. It processes the complete
@@ -37,10 +121,11 @@ struct TemplateParser;
 /// # Arguments
 ///
 /// * `template` - The template string to parse
+/// * `offset` - Byte position of `template` in the whole template text, used for spans
 ///
 /// # Returns
 ///
-/// * `Ok((Vec<StringOp>, bool))` - Operations and debug flag
+/// * `Ok(ParsedSection)` - Operations, their spans, debug flag, input reference and variable
 /// * `Err(String)` - Parse error with detailed description
 ///
 /// # Errors
@@ -56,15 +141,32 @@ struct TemplateParser;
 ///
 /// ```rust
 /// // This is an internal function used by Template::parse()
-/// // let (ops, debug) = parse_template("{upper|trim}").unwrap();
-/// // assert_eq!(ops.len(), 2);
-/// // assert!(!debug);
+/// // let parsed = parse_template("{upper|trim}", 0).unwrap();
+/// // assert_eq!(parsed.ops.len(), 2);
+/// // assert_eq!(parsed.spans[1].span, 7..11);
+/// // assert!(!parsed.debug);
 /// ```
-pub fn parse_template(template: &str) -> Result<(Vec<StringOp>, bool), String> {
-    let pairs = TemplateParser::parse(Rule::template, template)
-        .map_err(|e| format!("Parse error: {e}"))?
-        .next()
-        .unwrap();
+pub(crate) fn parse_template(template: &str, offset: usize) -> Result<ParsedSection, String> {
+    let pairs = match TemplateParser::parse(Rule::template, template) {
+        Ok(mut pairs) => pairs.next().unwrap(),
+        Err(e) => {
+            if let Some((rewritten, positions)) = rewrite_alias(template, &e) {
+                let mut parsed = parse_template(&rewritten, 0)?;
+                for span in &mut parsed.spans {
+                    span.remap(&|byte| offset + positions[byte]);
+                }
+                return Ok(parsed);
+            }
+            let mut message = format!("Parse error: {e}");
+            if let Some((_, word)) = failed_operation_word(template, &e)
+                && let Some(hint) = unknown_operation_hint(word)
+            {
+                message.push_str("\n  = ");
+                message.push_str(&hint);
+            }
+            return Err(message);
+        }
+    };
 
     let estimated_capacity = if template.len() < 50 {
         4 // Simple templates typically have 1-4 operations
@@ -75,13 +177,41 @@ pub fn parse_template(template: &str) -> Result<(Vec<StringOp>, bool), String> {
     };
 
     let mut ops = Vec::with_capacity(estimated_capacity);
+    let mut spans = Vec::with_capacity(estimated_capacity);
     let mut debug = false;
+    let mut input = None;
+    let mut variable = None;
+    let mut placeholder = None;
 
     for pair in pairs.into_inner() {
         match pair.as_rule() {
+            Rule::input_ref => {
+                let reference = pair.into_inner().next().unwrap();
+                input = Some(match reference.as_rule() {
+                    Rule::input_index => InputRef::Index(
+                        reference
+                            .as_str()
+                            .parse()
+                            .map_err(|_| format!("Invalid input index: {}", reference.as_str()))?,
+                    ),
+                    _ => InputRef::Name(reference.as_str().to_string()),
+                });
+            }
+            Rule::set_var => {
+                let name = pair.into_inner().next().unwrap().as_str().to_string();
+                variable = Some(Variable::Set(name));
+            }
+            Rule::get_var => {
+                let name = pair.into_inner().next().unwrap().as_str().to_string();
+                variable = Some(Variable::Get(name));
+            }
+            Rule::placeholder => {
+                placeholder = Placeholder::from_name(pair.into_inner().next().unwrap().as_str());
+            }
             Rule::operation_list => {
                 for op_pair in pair.into_inner() {
                     let inner = op_pair.into_inner().next().unwrap();
+                    spans.push(OpSpan::new(&inner, offset));
                     ops.push(parse_operation(inner)?);
                 }
             }
@@ -92,7 +222,153 @@ pub fn parse_template(template: &str) -> Result<(Vec<StringOp>, bool), String> {
         }
     }
 
-    Ok((ops, debug))
+    Ok(ParsedSection {
+        ops,
+        spans,
+        debug,
+        input,
+        variable,
+        placeholder,
+    })
+}
+
+/// Parses one `{...}` template section, in verbose syntax if enabled.
+///
+/// Verbose sections are enabled for the whole template by
+/// [`ParserConfig::verbose_syntax`] or for one section by starting it with
+/// `{!#`. Their whitespace and `#` comments are removed before parsing, and
+/// spans are mapped back to the original text.
+///
+/// # Arguments
+///
+/// * `section` - The section text, including its braces
+/// * `offset` - Byte position of `section` in the whole template text, used for spans
+/// * `config` - The parser configuration
+pub(crate) fn parse_section(
+    section: &str,
+    offset: usize,
+    config: &ParserConfig,
+) -> Result<ParsedSection, String> {
+    if let Some(parsed) = parse_each_section(section, offset, config)? {
+        return Ok(parsed);
+    }
+
+    let content = &section[1..section.len() - 1];
+    let marked = content.starts_with(VERBOSE_MARKER);
+    if !marked && !config.verbose_syntax {
+        return parse_template(section, offset);
+    }
+
+    let skip = if marked { 1 + VERBOSE_MARKER.len() } else { 1 };
+    let (stripped, positions) = strip_verbose(section, skip);
+    let mut parsed = parse_template(&stripped, 0)?;
+    for span in &mut parsed.spans {
+        span.remap(&|byte| offset + positions[byte]);
+    }
+    Ok(parsed)
+}
+
+/// Parses an `{each:{OPS}:BODY}` section, or returns `None` if `section` is
+/// not one.
+///
+/// `OPS` are parsed like a section of their own and followed by
+/// [`StringOp::Each`], whose body is parsed as a whole template. The body
+/// may read files and run commands; the enclosing template decides whether
+/// that is allowed.
+fn parse_each_section(
+    section: &str,
+    offset: usize,
+    config: &ParserConfig,
+) -> Result<Option<ParsedSection>, String> {
+    if !section[1..].starts_with(EACH_PREFIX) {
+        return Ok(None);
+    }
+
+    let open = EACH_PREFIX.len();
+    let close = matching_brace(section.as_bytes(), open)
+        .filter(|&close| close < section.len() - 1)
+        .ok_or_else(|| "Unclosed brace in `each:{...}`".to_string())?;
+    let body = section[close + 1..section.len() - 1]
+        .strip_prefix(':')
+        .ok_or_else(|| "Expected `:` and a template after `each:{...}`".to_string())?;
+
+    let mut parsed = parse_section(&section[open..=close], offset + open, config)?;
+    if parsed.input.is_some() || parsed.variable.is_some() || parsed.placeholder.is_some() {
+        return Err(
+            "The list of `each:{...}` cannot use `input=`, `set:`, `get:` or a placeholder"
+                .to_string(),
+        );
+    }
+    let body = Template::parse_with_config(body, config)
+        .map_err(|e| format!("Error in `each` template: {e}"))?
+        .with_fs_access(true)
+        .with_exec_access(true);
+
+    parsed.spans.push(OpSpan {
+        span: offset + 1..offset + section.len() - 1,
+        inner: Vec::new(),
+    });
+    parsed.ops.push(StringOp::Each {
+        body: Box::new(body),
+    });
+    Ok(Some(parsed))
+}
+
+/// Removes unescaped whitespace and `#` comments from a verbose section.
+///
+/// The first `skip` bytes are dropped except for the opening brace, which
+/// drops the verbose marker. Escape sequences are kept as written, so `\ `
+/// and `\#` still produce a space and a `#`, and so are quoted arguments.
+///
+/// # Returns
+///
+/// The stripped section and, for each of its bytes, the position of that
+/// byte in `section`.
+fn strip_verbose(section: &str, skip: usize) -> (String, Vec<usize>) {
+    let mut stripped = String::with_capacity(section.len());
+    let mut positions = Vec::with_capacity(section.len());
+    let mut keep = |stripped: &mut String, position: usize, ch: char| {
+        stripped.push(ch);
+        positions.extend(position..position + ch.len_utf8());
+    };
+
+    keep(&mut stripped, 0, '{');
+    let mut chars = section[skip..].char_indices().map(|(i, ch)| (i + skip, ch));
+    while let Some((position, ch)) = chars.next() {
+        match ch {
+            '\\' => {
+                keep(&mut stripped, position, ch);
+                if let Some((escaped_position, escaped)) = chars.next() {
+                    keep(&mut stripped, escaped_position, escaped);
+                }
+            }
+            '#' => {
+                for (_, comment_ch) in chars.by_ref() {
+                    if comment_ch == '\n' {
+                        break;
+                    }
+                }
+            }
+            '\'' | '"' if stripped.ends_with(':') => {
+                keep(&mut stripped, position, ch);
+                while let Some((quoted_position, quoted)) = chars.next() {
+                    keep(&mut stripped, quoted_position, quoted);
+                    if quoted == ch {
+                        break;
+                    }
+                    if quoted == '\\'
+                        && ch == '"'
+                        && let Some((escaped_position, escaped)) = chars.next()
+                    {
+                        keep(&mut stripped, escaped_position, escaped);
+                    }
+                }
+            }
+            ch if ch.is_whitespace() => {}
+            ch => keep(&mut stripped, position, ch),
+        }
+    }
+    (stripped, positions)
 }
 
 /// Parses a template string containing mixed literal text and template sections.
@@ -103,6 +379,7 @@ pub fn parse_template(template: &str) -> Result<(Vec<StringOp>, bool), String> {
 /// # Arguments
 ///
 /// * `template` - The template string to parse
+/// * `config` - Bounds on the template's length, nesting and operations
 ///
 /// # Returns
 ///
@@ -113,16 +390,26 @@ pub fn parse_template(template: &str) -> Result<(Vec<StringOp>, bool), String> {
 ///
 /// ```rust
 /// // This is an internal function used by Template::parse()
-/// // let (sections, debug) = parse_template_sections("Hello {upper} world").unwrap();
+/// // let config = ParserConfig::default();
+/// // let (sections, debug) = parse_template_sections("Hello {upper} world", &config).unwrap();
 /// // assert_eq!(sections.len(), 3); // "Hello ", upper operation, " world"
 /// ```
-pub fn parse_template_sections(template: &str) -> Result<(Vec<TemplateSection>, bool), String> {
+pub fn parse_template_sections(
+    template: &str,
+    config: &ParserConfig,
+) -> Result<(Vec<TemplateSection>, bool), String> {
+    config.check_len(template)?;
+    if let Some((open, close)) = config.custom_delimiters() {
+        return parse_delimited_sections(template, open, close, config);
+    }
+
     let mut sections = Vec::new();
     let mut current_literal = String::new();
-    let mut chars = template.chars().peekable();
+    let mut chars = template.char_indices();
     let mut debug = false;
+    let mut operations = 0;
 
-    while let Some(ch) = chars.next() {
+    while let Some((position, ch)) = chars.next() {
         if ch == '{' {
             // Check if this is a shell variable expansion ${...}
             if current_literal.ends_with('$') {
@@ -131,10 +418,11 @@ pub fn parse_template_sections(template: &str) -> Result<(Vec<TemplateSection>,
 
                 // Find the matching closing brace for the shell variable
                 let mut brace_count = 1;
-                for inner_ch in chars.by_ref() {
+                for (_, inner_ch) in chars.by_ref() {
                     current_literal.push(inner_ch);
                     if inner_ch == '{' {
                         brace_count += 1;
+                        config.check_depth(brace_count)?;
                     } else if inner_ch == '}' {
                         brace_count -= 1;
                         if brace_count == 0 {
@@ -160,9 +448,10 @@ pub fn parse_template_sections(template: &str) -> Result<(Vec<TemplateSection>,
                 let mut brace_count = 1;
                 let mut template_content = String::new();
 
-                for inner_ch in chars.by_ref() {
+                for (_, inner_ch) in chars.by_ref() {
                     if inner_ch == '{' {
                         brace_count += 1;
+                        config.check_depth(brace_count)?;
                         template_content.push(inner_ch);
                     } else if inner_ch == '}' {
                         brace_count -= 1;
@@ -182,12 +471,13 @@ pub fn parse_template_sections(template: &str) -> Result<(Vec<TemplateSection>,
 
                 // Parse the template content
                 let full_template = format!("{{{template_content}}}");
-                let (ops, section_debug) = parse_template(&full_template)?;
-                if section_debug {
+                let parsed = parse_section(&full_template, position, config)?;
+                config.check_operations(&parsed.ops, &mut operations)?;
+                if parsed.debug {
                     debug = true; // If any section has debug enabled, enable for the whole template
                 }
 
-                sections.push(TemplateSection::from_ops(ops));
+                sections.push(TemplateSection::from_parsed(parsed));
             }
         } else {
             // Regular character, add to current literal
@@ -205,6 +495,178 @@ pub fn parse_template_sections(template: &str) -> Result<(Vec<TemplateSection>,
     Ok((sections, debug))
 }
 
+/// Parses a template whose sections are enclosed in custom delimiters.
+///
+/// Works like [`parse_template_sections`], but sections start at `open` and
+/// end at the matching `close`, and all braces outside of them are literal
+/// text. A backslash escapes the character after it, so `\>` inside a
+/// section does not count towards a `>>` delimiter. Section content is
+/// parsed as if it were enclosed in braces.
+fn parse_delimited_sections(
+    template: &str,
+    open: &str,
+    close: &str,
+    config: &ParserConfig,
+) -> Result<(Vec<TemplateSection>, bool), String> {
+    if open.is_empty() || close.is_empty() {
+        return Err("Template delimiters cannot be empty".to_string());
+    }
+
+    let mut sections = Vec::new();
+    let mut debug = false;
+    let mut operations = 0;
+    let mut position = 0;
+
+    while let Some(found) = template[position..].find(open) {
+        let start = position + found;
+        if start > position {
+            sections.push(TemplateSection::Literal(
+                template[position..start].to_string(),
+            ));
+        }
+
+        // Find the matching closing delimiter. Braces opened inside the
+        // section close first, so `{{...}}` delimiters can enclose `map:{...}`
+        let content_start = start + open.len();
+        let mut depth = 1;
+        let mut braces = 0;
+        let mut index = content_start;
+        while depth > 0 {
+            let rest = &template[index..];
+            if braces > 0 && rest.starts_with('}') {
+                braces -= 1;
+                index += 1;
+            } else if rest.starts_with(close) {
+                depth -= 1;
+                index += close.len();
+            } else if rest.starts_with(open) {
+                depth += 1;
+                config.check_depth(depth + braces)?;
+                index += open.len();
+            } else {
+                let mut chars = rest.chars();
+                match chars.next() {
+                    Some('\\') => index += 1 + chars.next().map_or(0, char::len_utf8),
+                    Some('{') => {
+                        braces += 1;
+                        config.check_depth(depth + braces)?;
+                        index += 1;
+                    }
+                    Some(ch) => index += ch.len_utf8(),
+                    None => return Err(format!("Unclosed template delimiter `{open}`")),
+                }
+            }
+        }
+        let content_end = index - close.len();
+
+        // Parse the content as a braced section; its opening brace stands
+        // in for the last byte of `open`
+        let full_template = format!("{{{}}}", &template[content_start..content_end]);
+        let parsed = parse_section(&full_template, content_start - 1, config)?;
+        debug |= parsed.debug;
+        config.check_operations(&parsed.ops, &mut operations)?;
+        sections.push(TemplateSection::from_parsed(parsed));
+
+        position = index;
+    }
+
+    if position < template.len() {
+        sections.push(TemplateSection::Literal(template[position..].to_string()));
+    }
+
+    Ok((sections, debug))
+}
+
+/// Splits conditional groups `[?...]` out of the literal sections.
+///
+/// Groups are written in literal text, so the `[?` and `]` markers are
+/// removed from the literals and returned as ranges of section indices.
+/// A `]` outside of a group is literal text, and so is an escaped `\[?`,
+/// which is written as `[?`.
+///
+/// # Errors
+///
+/// Returns an error for a group without a template section, a nested
+/// group, or a group that is never closed.
+pub(crate) fn extract_groups(
+    sections: Vec<TemplateSection>,
+) -> Result<(Vec<TemplateSection>, Vec<Range<usize>>), String> {
+    let mut result = Vec::with_capacity(sections.len());
+    let mut groups = Vec::new();
+    let mut open = None;
+
+    let push_literal = |result: &mut Vec<TemplateSection>, text: &str| {
+        if !text.is_empty() {
+            result.push(TemplateSection::Literal(
+                text.replace(ESCAPED_GROUP_OPEN, GROUP_OPEN),
+            ));
+        }
+    };
+
+    for section in sections {
+        let TemplateSection::Literal(text) = section else {
+            result.push(section);
+            continue;
+        };
+        let mut rest = text.as_str();
+        loop {
+            match open {
+                None => {
+                    let Some(found) = find_group_open(rest) else {
+                        push_literal(&mut result, rest);
+                        break;
+                    };
+                    push_literal(&mut result, &rest[..found]);
+                    open = Some(result.len());
+                    rest = &rest[found + GROUP_OPEN.len()..];
+                }
+                Some(start) => {
+                    let close = rest.find(GROUP_CLOSE);
+                    if let Some(nested) = find_group_open(rest)
+                        && close.is_none_or(|close| nested < close)
+                    {
+                        return Err("Conditional groups `[?...]` cannot be nested".to_string());
+                    }
+                    let Some(close) = close else {
+                        push_literal(&mut result, rest);
+                        break;
+                    };
+                    push_literal(&mut result, &rest[..close]);
+                    if !result[start..]
+                        .iter()
+                        .any(|section| matches!(section, TemplateSection::Template { .. }))
+                    {
+                        return Err(
+                            "Conditional group `[?...]` needs a template section".to_string()
+                        );
+                    }
+                    groups.push(start..result.len());
+                    open = None;
+                    rest = &rest[close + GROUP_CLOSE.len_utf8()..];
+                }
+            }
+        }
+    }
+
+    if open.is_some() {
+        return Err("Unclosed conditional group `[?`".to_string());
+    }
+    Ok((result, groups))
+}
+
+/// Returns the position of the first `[?` in `text` that is not escaped.
+fn find_group_open(text: &str) -> Option<usize> {
+    let mut from = 0;
+    while let Some(found) = text[from..].find(GROUP_OPEN) {
+        let position = from + found;
+        if !text[..position].ends_with('\\') {
+            return Some(position);
+        }
+        from = position + GROUP_OPEN.len();
+    }
+    None
+}
+
 /// Deprecated compatibility wrapper for [`parse_template_sections`].
 #[allow(dead_code)]
 #[deprecated(
@@ -212,7 +674,7 @@ pub fn parse_template_sections(template: &str) -> Result<(Vec<TemplateSection>,
     note = "use `parse_template_sections` instead; `parse_multi_template` will be removed in the next major release"
 )]
 pub fn parse_multi_template(template: &str) -> Result<(Vec<TemplateSection>, bool), String> {
-    parse_template_sections(template)
+    parse_template_sections(template, &ParserConfig::default())
 }
 
 /// Parses a single operation from a parse tree node.
@@ -238,82 +700,174 @@ pub fn parse_multi_template(template: &str) -> Result<(Vec<TemplateSection>, boo
 /// - Required arguments are missing
 fn parse_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
     match pair.as_rule() {
-        Rule::shorthand_range => {
-            let range = parse_range_spec(pair)?;
-            Ok(StringOp::Split {
-                sep: SPACE_SEP.to_string(),
-                range,
-            })
-        }
+        Rule::shorthand_range => Ok(StringOp::Split {
+            sep: SPACE_SEP.to_string(),
+            range: extract_range_arg(pair)?,
+            max_splits: None,
+            keep_sep: false,
+            skip_empty: false,
+        }),
         Rule::shorthand_index => {
             let idx = pair.as_str().parse().unwrap();
             Ok(StringOp::Split {
                 sep: SPACE_SEP.to_string(),
                 range: RangeSpec::Index(idx),
+                max_splits: None,
+                keep_sep: false,
+                skip_empty: false,
             })
         }
-        Rule::split => {
-            let mut parts = pair.into_inner();
-            let sep_part = parts.next().unwrap();
-            let sep = process_arg(sep_part.as_str());
-            let range = if let Some(range_part) = parts.next() {
-                parse_range_spec(range_part)?
-            } else {
-                RangeSpec::Range(None, None, false)
-            };
-            Ok(StringOp::Split { sep, range })
-        }
-        Rule::join => Ok(StringOp::Join {
+        Rule::split_any => parse_split_any_operation(pair),
+        Rule::split => parse_split_operation(pair),
+        Rule::partition | Rule::rpartition => parse_partition_operation(pair),
+        Rule::join => Ok(parse_join_operation(pair)),
+        Rule::output_sep => Ok(StringOp::OutputSep {
             sep: extract_single_arg(pair)?,
         }),
         Rule::substring => Ok(StringOp::Substring {
             range: extract_range_arg(pair)?,
         }),
-        Rule::replace => {
-            let sed_parts = parse_sed_string(pair.into_inner().next().unwrap())?;
-            Ok(StringOp::Replace {
-                pattern: sed_parts.0,
-                replacement: sed_parts.1,
-                flags: sed_parts.2,
-            })
-        }
-        Rule::upper => Ok(StringOp::Upper),
-        Rule::lower => Ok(StringOp::Lower),
+        Rule::replace => parse_replace_operation(pair),
+        Rule::replace_literal => parse_replace_literal_operation(pair),
+        Rule::upper => Ok(StringOp::Upper {
+            locale: parse_case_locale(pair),
+        }),
+        Rule::lower => Ok(StringOp::Lower {
+            locale: parse_case_locale(pair),
+        }),
+        Rule::capitalize => Ok(parse_capitalize_operation(pair)),
+        Rule::swapcase => Ok(StringOp::SwapCase {
+            locale: parse_case_locale(pair),
+        }),
         Rule::trim => {
             let chars = parse_trim_chars(pair.clone());
             let direction = parse_trim_direction(pair);
             Ok(StringOp::Trim { chars, direction })
         }
+        Rule::squeeze => Ok(StringOp::Squeeze {
+            chars: pair
+                .into_inner()
+                .next()
+                .map(|p| arg_value(&p))
+                .unwrap_or_default(),
+        }),
+        Rule::trim_prefix => Ok(StringOp::TrimPrefix {
+            prefix: extract_single_arg(pair)?,
+        }),
+        Rule::trim_suffix => Ok(StringOp::TrimSuffix {
+            suffix: extract_single_arg(pair)?,
+        }),
         Rule::append => Ok(StringOp::Append {
             suffix: extract_single_arg(pair)?,
         }),
         Rule::prepend => Ok(StringOp::Prepend {
             prefix: extract_single_arg(pair)?,
         }),
-        Rule::surround => Ok(StringOp::Surround {
-            text: extract_single_arg(pair)?,
+        Rule::surround | Rule::quote => Ok(parse_surround_operation(pair)),
+        Rule::strip_ansi => Ok(StringOp::StripAnsi),
+        Rule::words => Ok(StringOp::Words),
+        Rule::sentences => Ok(StringOp::Sentences),
+        Rule::extract_emails => Ok(StringOp::ExtractEmails),
+        Rule::extract_urls => Ok(StringOp::ExtractUrls),
+        Rule::extract_uuids => Ok(StringOp::ExtractUuids),
+        Rule::extract_between => parse_extract_between_operation(pair),
+        Rule::deaccent => Ok(StringOp::Deaccent),
+        Rule::ascii => Ok(StringOp::Ascii),
+        Rule::slugify => Ok(parse_slugify_operation(pair)),
+        Rule::normalize => Ok(parse_normalize_operation(pair)),
+        Rule::ordinal => Ok(StringOp::Ordinal {
+            parse: has_numeral_parse(pair),
+        }),
+        Rule::to_words => Ok(StringOp::ToWords {
+            parse: has_numeral_parse(pair),
         }),
-        Rule::quote => Ok(StringOp::Surround {
-            text: extract_single_arg(pair)?,
+        Rule::roman => Ok(StringOp::Roman {
+            parse: has_numeral_parse(pair),
         }),
-        Rule::strip_ansi => Ok(StringOp::StripAnsi),
+        Rule::basename => Ok(StringOp::Basename {
+            style: parse_path_style(pair),
+        }),
+        Rule::dirname => Ok(StringOp::Dirname {
+            style: parse_path_style(pair),
+        }),
+        Rule::extname => Ok(StringOp::Extname {
+            style: parse_path_style(pair),
+        }),
+        Rule::strip_ext => Ok(StringOp::StripExt {
+            style: parse_path_style(pair),
+        }),
+        Rule::with_ext => Ok(parse_with_ext_operation(pair)),
+        Rule::json_array => Ok(StringOp::JsonArray),
+        Rule::json_object => parse_json_object_operation(pair),
+        Rule::yaml_get | Rule::toml_get => parse_config_get_operation(pair),
+        Rule::url_scheme => Ok(StringOp::UrlScheme),
+        Rule::url_host => Ok(StringOp::UrlHost),
+        Rule::url_path => Ok(StringOp::UrlPath),
+        Rule::url_query => Ok(StringOp::UrlQuery {
+            key: pair
+                .into_inner()
+                .next()
+                .map_or_else(String::new, |p| arg_value(&p)),
+        }),
+        Rule::color => Ok(StringOp::Color {
+            color: parse_term_color(pair)?,
+        }),
+        Rule::bg => Ok(StringOp::Background {
+            color: parse_term_color(pair)?,
+        }),
+        Rule::bold => Ok(StringOp::Bold),
+        Rule::underline => Ok(StringOp::Underline),
+        Rule::style_reset => Ok(StringOp::StyleReset),
+        Rule::normalize_path => Ok(StringOp::NormalizePath {
+            style: parse_path_style(pair),
+        }),
+        Rule::read_file => Ok(StringOp::ReadFile),
+        Rule::exec => parse_exec_operation(pair),
         Rule::filter => Ok(StringOp::Filter {
             pattern: extract_single_arg_raw(pair)?,
         }),
         Rule::filter_not => Ok(StringOp::FilterNot {
             pattern: extract_single_arg_raw(pair)?,
         }),
+        Rule::filter_prefix | Rule::filter_suffix | Rule::filter_contains => {
+            parse_filter_text_operation(pair)
+        }
+        Rule::filter_valid_utf8 => Ok(StringOp::FilterValidUtf8),
+        Rule::filter_luhn => Ok(StringOp::FilterLuhn),
+        Rule::filter_checksum => Ok(parse_filter_checksum_operation(pair)),
+        Rule::try_op => parse_try_operation(pair),
+        Rule::trim_regex => Ok(parse_trim_regex_operation(pair)),
+        Rule::highlight => parse_highlight_operation(pair),
         Rule::slice => Ok(StringOp::Slice {
             range: extract_range_arg(pair)?,
         }),
+        Rule::clamp => Ok(StringOp::Clamp {
+            range: extract_range_arg(pair)?,
+        }),
         Rule::sort => Ok(StringOp::Sort {
             direction: parse_sort_direction(pair),
         }),
         Rule::reverse => Ok(StringOp::Reverse),
         Rule::unique => Ok(StringOp::Unique),
-        Rule::pad => parse_pad_operation(pair),
+        Rule::unique_by => Ok(StringOp::UniqueBy {
+            operations: parse_sub_pipeline(pair.into_inner().next().unwrap())?,
+        }),
+        Rule::duplicates => Ok(StringOp::Duplicates),
+        Rule::histogram => parse_histogram_operation(pair),
+        Rule::remove_empty => Ok(StringOp::RemoveEmpty),
+        Rule::intersect | Rule::diff | Rule::union => Ok(parse_set_operation(pair)),
+        Rule::shuffle | Rule::sample => parse_random_operation(pair),
+        Rule::table | Rule::md_table | Rule::html_table => Ok(parse_table_operation(pair)),
+        Rule::kv | Rule::kv_keys | Rule::kv_values => parse_kv_operation(pair),
+        Rule::pad | Rule::center => parse_pad_operation(pair),
+        Rule::len => Ok(parse_len_operation(pair)),
         Rule::regex_extract | Rule::map_regex_extract => parse_regex_extract_operation(pair),
         Rule::map => parse_map_operation(pair),
+        Rule::map_fallible => Ok(StringOp::MapFallible {
+            operations: parse_sub_pipeline(pair.into_inner().next().unwrap())?,
+        }),
+        Rule::sort_by => parse_sort_by_operation(pair),
+        Rule::filter_by => parse_filter_by_operation(pair),
         _ => Err(format!("Unsupported operation: {:?}", pair.as_rule())),
     }
 }
@@ -332,7 +886,16 @@ fn parse_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String
 /// * `Err(String)` - Error if argument is missing
 fn extract_single_arg(pair: pest::iterators::Pair<Rule>) -> Result<String, String> {
     let inner = pair.into_inner().next().unwrap();
-    Ok(process_arg(inner.as_str()))
+    Ok(arg_value(&inner))
+}
+
+/// Parses an exec operation, rejecting an empty command.
+fn parse_exec_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
+    let command = extract_single_arg(pair)?;
+    if command.trim().is_empty() {
+        return Err("Exec command cannot be empty".to_string());
+    }
+    Ok(StringOp::Exec { command })
 }
 
 /// Extracts a single argument without escape sequence processing.
@@ -389,6 +952,11 @@ fn parse_trim_chars(pair: pest::iterators::Pair<Rule>) -> String {
         None => return String::new(),
     };
 
+    // Quoted characters are never a direction
+    if let Some(chars) = quoted_value(&first) {
+        return chars;
+    }
+
     // Check if there's a second argument
     if let Some(_second) = parts.next() {
         // If there are two arguments, first is chars, second is direction
@@ -464,181 +1032,979 @@ fn parse_sort_direction(pair: pest::iterators::Pair<Rule>) -> SortDirection {
     }
 }
 
-/// Parses a pad operation with width, character, and direction arguments.
-///
-/// Processes the padding operation arguments to extract width, padding character,
-/// and padding direction with appropriate defaults.
+/// Parses a split operation with its separator, range, split limit and options.
 ///
 /// # Arguments
 ///
-/// * `pair` - Parse tree node for the pad operation
+/// * `pair` - Parse tree node for the split operation
 ///
 /// # Returns
 ///
-/// * `Ok(StringOp::Pad)` - Parsed pad operation
-/// * `Err(String)` - Error if width is invalid
-fn parse_pad_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
+/// * `Ok(StringOp::Split)` - Parsed split operation
+/// * `Err(String)` - Error if the range or split limit is invalid
+fn parse_split_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
     let mut parts = pair.into_inner();
-    let width = parts
-        .next()
-        .unwrap()
-        .as_str()
-        .parse()
-        .map_err(|_| "Invalid padding width")?;
-
-    let char = if let Some(char_part) = parts.next() {
-        let processed = process_arg(char_part.as_str());
-        processed.chars().next().unwrap_or(' ')
-    } else {
-        ' '
+    let sep = arg_value(&parts.next().unwrap());
+    let range = match parts.next() {
+        Some(range_part) => parse_range_spec(range_part)?,
+        None => RangeSpec::Range(None, None, false),
     };
-
-    let direction = parts
-        .next()
-        .map(|p| match p.as_str() {
-            "left" => PadDirection::Left,
-            "right" => PadDirection::Right,
-            "both" => PadDirection::Both,
-            _ => PadDirection::Right,
-        })
-        .unwrap_or(PadDirection::Right);
-
-    Ok(StringOp::Pad {
-        width,
-        char,
-        direction,
+    let mut max_splits = None;
+    let (mut keep_sep, mut skip_empty) = (false, false);
+    for part in parts {
+        match part.as_rule() {
+            Rule::max_splits => {
+                max_splits = Some(
+                    part.as_str()
+                        .parse::<usize>()
+                        .map_err(|_| format!("Invalid split limit '{}'", part.as_str()))?,
+                );
+            }
+            _ if part.as_str() == "keep_sep" => keep_sep = true,
+            _ => skip_empty = true,
+        }
+    }
+    Ok(StringOp::Split {
+        sep,
+        range,
+        max_splits,
+        keep_sep,
+        skip_empty,
     })
 }
 
-/// Parses a regex extract operation with pattern and optional group.
-///
-/// Processes regex extraction arguments to extract the pattern and optional
-/// capture group number.
+/// Parses a split_any operation.
 ///
 /// # Arguments
 ///
-/// * `pair` - Parse tree node for the regex extract operation
+/// * `pair` - Parse tree node for the split_any operation
 ///
 /// # Returns
 ///
-/// * `Ok(StringOp::RegexExtract)` - Parsed regex extract operation
-/// * `Err(String)` - Error if arguments are invalid
-fn parse_regex_extract_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
+/// * `Ok(StringOp::SplitAny)` - Parsed split_any operation
+/// * `Err(String)` - Error if there are no separator characters or the range
+///   is invalid
+fn parse_split_any_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
     let mut parts = pair.into_inner();
-    let pattern = parts.next().unwrap().as_str().to_string();
-    let group = parts.next().and_then(|p| p.as_str().parse().ok());
-    Ok(StringOp::RegexExtract { pattern, group })
+    let chars = arg_value(&parts.next().unwrap());
+    if chars.is_empty() {
+        return Err("split_any requires at least one separator character".to_string());
+    }
+    let range = match parts.next() {
+        Some(range_part) => parse_range_spec(range_part)?,
+        None => RangeSpec::Range(None, None, false),
+    };
+    Ok(StringOp::SplitAny { chars, range })
 }
 
-/// Parses a map operation with nested operation list.
-///
-/// Processes the map operation to extract the nested operations that should
-/// be applied to each list item.
+/// Parses a partition or rpartition operation.
 ///
 /// # Arguments
 ///
-/// * `pair` - Parse tree node for the map operation
+/// * `pair` - Parse tree node for the partition or rpartition operation
 ///
 /// # Returns
 ///
-/// * `Ok(StringOp::Map)` - Parsed map operation with nested operations
-/// * `Err(String)` - Error if nested operations are invalid
-fn parse_map_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
-    let map_op_pair = pair.into_inner().next().unwrap();
-    let operation_list_pair = map_op_pair.into_inner().next().unwrap();
-
-    let mut operations: SmallVec<[StringOp; 8]> = SmallVec::new();
-    for op_pair in operation_list_pair.into_inner() {
-        let inner_op_pair = op_pair.into_inner().next().unwrap();
-        operations.push(parse_map_inner_operation(inner_op_pair)?);
+/// * `Ok(StringOp)` - `StringOp::Partition` or `StringOp::RPartition`
+/// * `Err(String)` - Error if the separator is empty
+fn parse_partition_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
+    let rule = pair.as_rule();
+    let sep = extract_single_arg(pair)?;
+    if sep.is_empty() {
+        return Err("Empty separator in partition".to_string());
     }
-
-    Ok(StringOp::Map {
-        operations: Box::new(operations),
+    Ok(if rule == Rule::partition {
+        StringOp::Partition { sep }
+    } else {
+        StringOp::RPartition { sep }
     })
 }
 
-/// Parses operations that can be used inside map blocks.
-///
-/// Handles the subset of operations that are valid within map contexts,
-/// including both string operations and list operations that work on individual items.
+/// Parses a shuffle or sample operation with its optional seed.
 ///
 /// # Arguments
 ///
-/// * `pair` - Parse tree node for the map inner operation
+/// * `pair` - Parse tree node for the shuffle or sample operation
 ///
 /// # Returns
 ///
-/// * `Ok(StringOp)` - Parsed operation valid for map context
-/// * `Err(String)` - Error if operation is invalid or unsupported in map
-fn parse_map_inner_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
-    match pair.as_rule() {
-        // String operations (existing)
-        Rule::substring => Ok(StringOp::Substring {
-            range: extract_range_arg(pair)?,
-        }),
-        Rule::replace => {
-            let sed_parts = parse_sed_string(pair.into_inner().next().unwrap())?;
-            Ok(StringOp::Replace {
-                pattern: sed_parts.0,
-                replacement: sed_parts.1,
-                flags: sed_parts.2,
-            })
-        }
-        Rule::append => Ok(StringOp::Append {
-            suffix: extract_single_arg(pair)?,
-        }),
-        Rule::prepend => Ok(StringOp::Prepend {
-            prefix: extract_single_arg(pair)?,
-        }),
-        Rule::surround => Ok(StringOp::Surround {
-            text: extract_single_arg(pair)?,
-        }),
-        Rule::quote => Ok(StringOp::Surround {
-            text: extract_single_arg(pair)?,
-        }),
-        Rule::upper => Ok(StringOp::Upper),
-        Rule::lower => Ok(StringOp::Lower),
-        Rule::trim => {
-            let chars = parse_trim_chars(pair.clone());
-            let direction = parse_trim_direction(pair);
-            Ok(StringOp::Trim { chars, direction })
-        }
-        Rule::pad => parse_pad_operation(pair),
-        Rule::reverse => Ok(StringOp::Reverse),
-        Rule::strip_ansi => Ok(StringOp::StripAnsi),
-        Rule::map_regex_extract => parse_regex_extract_operation(pair),
-
-        // List operations (new)
-        Rule::map_split => {
-            let mut parts = pair.into_inner();
-            let sep_part = parts.next().unwrap();
-            let sep = process_arg(sep_part.as_str());
-            let range = if let Some(range_part) = parts.next() {
-                parse_range_spec(range_part)?
-            } else {
-                RangeSpec::Range(None, None, false)
-            };
-            Ok(StringOp::Split { sep, range })
+/// * `Ok(StringOp)` - `StringOp::Shuffle` or `StringOp::Sample`
+/// * `Err(String)` - Error if the sample size or seed is not a valid number
+fn parse_random_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
+    let rule = pair.as_rule();
+    let mut count = 0;
+    let mut seed = None;
+    for part in pair.into_inner() {
+        let text = part.as_str();
+        match part.as_rule() {
+            Rule::number => {
+                count = text
+                    .parse()
+                    .map_err(|_| format!("Invalid sample size: {text}"))?;
+            }
+            Rule::seed => {
+                seed = Some(text.parse().map_err(|_| format!("Invalid seed: {text}"))?);
+            }
+            _ => {}
         }
-        Rule::map_join => Ok(StringOp::Join {
-            sep: extract_single_arg(pair)?,
-        }),
-        Rule::map_slice => Ok(StringOp::Slice {
-            range: extract_range_arg(pair)?,
-        }),
-        Rule::map_sort => Ok(StringOp::Sort {
-            direction: parse_sort_direction(pair),
-        }),
-        Rule::map_unique => Ok(StringOp::Unique),
-        Rule::map_filter => Ok(StringOp::Filter {
-            pattern: extract_single_arg_raw(pair)?,
-        }),
-        Rule::map_filter_not => Ok(StringOp::FilterNot {
-            pattern: extract_single_arg_raw(pair)?,
-        }),
-
-        _ => Err(format!("Unsupported map operation: {:?}", pair.as_rule())),
+    }
+    Ok(if rule == Rule::sample {
+        StringOp::Sample { count, seed }
+    } else {
+        StringOp::Shuffle { seed }
+    })
+}
+
+/// Parses a histogram operation with its optional number of lines.
+///
+/// # Arguments
+///
+/// * `pair` - Parse tree node for the histogram operation
+///
+/// # Returns
+///
+/// * `Ok(StringOp::Histogram)` - Parsed histogram operation
+/// * `Err(String)` - Error if the number of lines is not a valid number
+fn parse_histogram_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
+    let top = pair
+        .into_inner()
+        .next()
+        .map(|p| {
+            p.as_str()
+                .parse::<usize>()
+                .map_err(|_| format!("Invalid histogram size: {}", p.as_str()))
+        })
+        .transpose()?;
+    Ok(StringOp::Histogram { top })
+}
+
+/// Returns the word where parsing failed, if an operation was expected there.
+///
+/// Parse errors on an unknown operation point at its first character, right
+/// after `{`, `|` or `!`.
+fn failed_operation_word<'a>(
+    text: &'a str,
+    error: &pest::error::Error<Rule>,
+) -> Option<(usize, &'a str)> {
+    let pest::error::InputLocation::Pos(pos) = error.location else {
+        return None;
+    };
+    if !text[..pos].trim_end().ends_with(['{', '|', '!']) {
+        return None;
+    }
+    let rest = &text[pos..];
+    let end = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+    Some((pos, &rest[..end])).filter(|(_, word)| !word.is_empty())
+}
+
+/// Replaces the operation alias where parsing failed with its keyword.
+///
+/// Aliases are not part of the grammar, so a section using one fails to
+/// parse right at the alias. Callers parse the rewritten text again, which
+/// resolves one alias at a time.
+///
+/// # Returns
+///
+/// The rewritten text and, for each of its bytes, the position of the byte
+/// it came from, or `None` if parsing did not fail at an alias.
+fn rewrite_alias(text: &str, error: &pest::error::Error<Rule>) -> Option<(String, Vec<usize>)> {
+    let (pos, alias) = failed_operation_word(text, error)?;
+    let keyword = operation(alias)?.name;
+    if keyword == alias {
+        return None;
+    }
+
+    let end = pos + alias.len();
+    let rewritten = format!("{}{keyword}{}", &text[..pos], &text[end..]);
+    // The keyword's last byte maps to the alias's last byte, so spans that
+    // end with the keyword end with the alias
+    let keyword_positions = (0..keyword.len()).map(|i| {
+        if i + 1 == keyword.len() {
+            end - 1
+        } else {
+            pos + i.min(alias.len() - 1)
+        }
+    });
+    let positions = (0..pos)
+        .chain(keyword_positions)
+        .chain(end..text.len())
+        .collect();
+    Some((rewritten, positions))
+}
+
+/// Describes `word` if it is not an operation keyword or alias, naming the
+/// keyword that was meant when one is close.
+fn unknown_operation_hint(word: &str) -> Option<String> {
+    if word == "each" {
+        return Some("`each` must start a section: {each:{OPS}:TEMPLATE}".to_string());
+    }
+    if operation(word).is_some() {
+        return None;
+    }
+    Some(match suggest(word) {
+        Some(keyword) => format!("unknown operation `{word}`, did you mean `{keyword}`?"),
+        None => format!("unknown operation `{word}`"),
+    })
+}
+
+/// Parses a kv, kv_keys or kv_values operation.
+///
+/// An empty pair separator stands for whitespace and an empty key separator
+/// for the default `=`.
+///
+/// # Returns
+///
+/// * `Ok(StringOp)` - `StringOp::Kv`, `StringOp::KvKeys` or `StringOp::KvValues`
+/// * `Err(String)` - Error if the key of a kv operation is empty
+fn parse_kv_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
+    let rule = pair.as_rule();
+    let mut args = pair.into_inner().map(|p| arg_value(&p));
+    let key = if rule == Rule::kv {
+        let key = args.next().unwrap_or_default();
+        if key.is_empty() {
+            return Err("Empty key in kv".to_string());
+        }
+        key
+    } else {
+        String::new()
+    };
+    let pair_sep = args.next().unwrap_or_default();
+    let kv_sep = args
+        .next()
+        .filter(|sep| !sep.is_empty())
+        .unwrap_or_else(|| "=".to_string());
+
+    Ok(match rule {
+        Rule::kv => StringOp::Kv {
+            key,
+            pair_sep,
+            kv_sep,
+        },
+        Rule::kv_keys => StringOp::KvKeys { pair_sep, kv_sep },
+        _ => StringOp::KvValues { pair_sep, kv_sep },
+    })
+}
+
+/// Parses a table, md_table or html_table operation with its optional
+/// separator, alignments and header flag.
+fn parse_table_operation(pair: pest::iterators::Pair<Rule>) -> StringOp {
+    let rule = pair.as_rule();
+    let mut sep = String::new();
+    let mut align = Vec::new();
+    let mut header = false;
+    for part in pair.into_inner() {
+        match part.as_rule() {
+            Rule::table_align => {
+                align = part
+                    .as_str()
+                    .chars()
+                    .map(|c| match c {
+                        'r' => ColumnAlign::Right,
+                        'c' => ColumnAlign::Center,
+                        _ => ColumnAlign::Left,
+                    })
+                    .collect();
+            }
+            Rule::table_header => header = true,
+            _ => sep = arg_value(&part),
+        }
+    }
+    match rule {
+        Rule::md_table => StringOp::MarkdownTable { sep, align, header },
+        Rule::html_table => StringOp::HtmlTable { sep, align, header },
+        _ => StringOp::Table { sep, align, header },
+    }
+}
+
+/// Parses a trim_regex operation with its pattern and optional direction.
+///
+/// # Arguments
+///
+/// * `pair` - Parse tree node for the trim_regex operation
+///
+/// # Returns
+///
+/// * `StringOp::TrimRegex` - Configured regex trim operation
+fn parse_trim_regex_operation(pair: pest::iterators::Pair<Rule>) -> StringOp {
+    let mut parts = pair.into_inner();
+    let pattern = parts.next().unwrap().as_str().to_string();
+    let direction = match parts.next().map(|p| p.as_str()) {
+        Some("left") => TrimDirection::Left,
+        Some("right") => TrimDirection::Right,
+        _ => TrimDirection::Both,
+    };
+    StringOp::TrimRegex { pattern, direction }
+}
+
+/// Parses a highlight operation with its pattern and optional color or
+/// markers.
+fn parse_highlight_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
+    let mut parts = pair.into_inner();
+    let pattern = parts.next().unwrap().as_str().to_string();
+    let style = match parts.next() {
+        Some(p) if p.as_rule() == Rule::highlight_marker => {
+            let mut markers = p.into_inner().map(|m| arg_value(&m));
+            let open = markers.next().unwrap_or_default();
+            let close = markers.next().unwrap_or_else(|| open.clone());
+            HighlightStyle::Marker { open, close }
+        }
+        Some(p) => HighlightStyle::Color(TermColor::parse(p.as_str())?),
+        None => HighlightStyle::default(),
+    };
+    Ok(StringOp::Highlight { pattern, style })
+}
+
+/// Parses a json_object operation with its comma-separated keys.
+///
+/// Escaped commas (`\,`) are part of a key, and keys must be unique.
+fn parse_json_object_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
+    let raw = pair.into_inner().next().map_or("", |p| p.as_str());
+    let keys: Vec<String> = if raw.is_empty() {
+        Vec::new()
+    } else {
+        split_unescaped(raw, b',').map(process_arg).collect()
+    };
+    if let Some(key) = duplicate_key(&keys) {
+        return Err(format!("Duplicate key '{key}' in json_object"));
+    }
+    Ok(StringOp::JsonObject { keys })
+}
+
+/// Parses a yaml_get or toml_get operation with its dot-separated path.
+///
+/// Escaped dots (`\.`) are part of a key. The operation is an error if the
+/// feature for its format is not enabled.
+fn parse_config_get_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
+    let rule = pair.as_rule();
+    let (op, feature, enabled) = if rule == Rule::yaml_get {
+        ("yaml_get", "yaml", cfg!(feature = "yaml"))
+    } else {
+        ("toml_get", "toml", cfg!(feature = "toml"))
+    };
+    if !enabled {
+        return Err(config::disabled(op, feature));
+    }
+    let raw = pair.into_inner().next().map_or("", |p| p.as_str());
+    let path: Vec<String> = split_unescaped(raw, b'.').map(process_arg).collect();
+    if path.iter().any(String::is_empty) {
+        return Err(format!("Empty key in {op} path '{raw}'"));
+    }
+    Ok(if rule == Rule::yaml_get {
+        StringOp::YamlGet { path }
+    } else {
+        StringOp::TomlGet { path }
+    })
+}
+
+/// Returns the first key that appears earlier in `keys`, if any.
+pub(crate) fn duplicate_key(keys: &[String]) -> Option<&str> {
+    keys.iter()
+        .enumerate()
+        .find(|(i, key)| keys[..*i].contains(key))
+        .map(|(_, key)| key.as_str())
+}
+
+/// Parses a join operation with its separator and optional last separator.
+///
+/// # Arguments
+///
+/// * `pair` - Parse tree node for the join operation
+///
+/// # Returns
+///
+/// * `StringOp::Join` - Configured join operation
+fn parse_join_operation(pair: pest::iterators::Pair<Rule>) -> StringOp {
+    let mut parts = pair.into_inner();
+    let sep = arg_value(&parts.next().unwrap());
+    let last_sep = parts.next().map(|p| arg_value(&p));
+    StringOp::Join { sep, last_sep }
+}
+
+/// Parses surround operation arguments.
+///
+/// A single argument is added to both sides; a second argument, if present,
+/// replaces it on the right.
+///
+/// # Arguments
+///
+/// * `pair` - Parse tree node for surround or quote operation
+///
+/// # Returns
+///
+/// * `StringOp::Surround` - Configured surround operation
+fn parse_surround_operation(pair: pest::iterators::Pair<Rule>) -> StringOp {
+    let mut parts = pair.into_inner();
+    let prefix = arg_value(&parts.next().unwrap());
+    let suffix = parts
+        .next()
+        .map_or_else(|| prefix.clone(), |p| arg_value(&p));
+    StringOp::Surround { prefix, suffix }
+}
+
+/// Parses a pad or center operation with width, fill, direction, and truncate arguments.
+///
+/// Processes the padding operation arguments to extract width, fill text,
+/// padding direction, and the truncate flag with appropriate defaults.
+/// `center` always pads both sides.
+///
+/// # Arguments
+///
+/// * `pair` - Parse tree node for the pad or center operation
+///
+/// # Returns
+///
+/// * `Ok(StringOp::Pad)` - Parsed pad operation
+/// * `Err(String)` - Error if width is invalid
+fn parse_pad_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
+    let mut direction = if pair.as_rule() == Rule::center {
+        PadDirection::Both
+    } else {
+        PadDirection::Right
+    };
+    let mut parts = pair.into_inner();
+    let width = parts
+        .next()
+        .unwrap()
+        .as_str()
+        .parse()
+        .map_err(|_| "Invalid padding width")?;
+
+    let mut fill = " ".to_string();
+    let mut truncate = false;
+    for part in parts {
+        match part.as_rule() {
+            Rule::pad_char => {
+                let processed = process_arg(part.as_str());
+                if !processed.is_empty() {
+                    fill = processed;
+                }
+            }
+            Rule::direction => {
+                direction = match part.as_str() {
+                    "left" => PadDirection::Left,
+                    "both" => PadDirection::Both,
+                    _ => PadDirection::Right,
+                };
+            }
+            Rule::pad_truncate => truncate = true,
+            _ => {}
+        }
+    }
+
+    Ok(StringOp::Pad {
+        width,
+        fill,
+        direction,
+        truncate,
+    })
+}
+
+/// Parses a len operation with its optional unit.
+///
+/// The grammar only accepts known units; lengths default to grapheme clusters.
+fn parse_len_operation(pair: pest::iterators::Pair<Rule>) -> StringOp {
+    let unit = match pair.into_inner().next().map(|p| p.as_str()) {
+        Some("bytes") => LengthUnit::Bytes,
+        Some("chars") => LengthUnit::Chars,
+        _ => LengthUnit::Graphemes,
+    };
+    StringOp::Len { unit }
+}
+
+/// Parses a slugify operation with an optional separator.
+fn parse_slugify_operation(pair: pest::iterators::Pair<Rule>) -> StringOp {
+    let separator = pair
+        .into_inner()
+        .next()
+        .map_or_else(|| "-".to_string(), |p| arg_value(&p));
+    StringOp::Slugify { separator }
+}
+
+/// Parses a normalize operation with its normalization form.
+fn parse_normalize_operation(pair: pest::iterators::Pair<Rule>) -> StringOp {
+    let form = match pair.into_inner().next().map(|p| p.as_str()) {
+        Some("nfd") => NormalForm::Nfd,
+        Some("nfkc") => NormalForm::Nfkc,
+        Some("nfkd") => NormalForm::Nfkd,
+        _ => NormalForm::Nfc,
+    };
+    StringOp::Normalize { form }
+}
+
+/// Parses a filter_checksum operation with its algorithm and expected value.
+fn parse_filter_checksum_operation(pair: pest::iterators::Pair<Rule>) -> StringOp {
+    let mut inner = pair.into_inner();
+    let algorithm = match inner.next().map(|p| p.as_str()) {
+        Some("adler32") => ChecksumAlgorithm::Adler32,
+        _ => ChecksumAlgorithm::Crc32,
+    };
+    // The grammar allows at most eight hex digits, which always fit
+    let expected = inner
+        .next()
+        .and_then(|p| u32::from_str_radix(p.as_str(), 16).ok())
+        .unwrap_or_default();
+    StringOp::FilterChecksum {
+        algorithm,
+        expected,
+    }
+}
+
+/// Returns `true` if a number formatting operation has the `parse` option.
+fn has_numeral_parse(pair: pest::iterators::Pair<Rule>) -> bool {
+    pair.into_inner().next().is_some()
+}
+
+/// Returns the path style of a path operation, from its optional
+/// `windows` option.
+fn parse_path_style(pair: pest::iterators::Pair<Rule>) -> PathStyle {
+    if pair.into_inner().any(|p| p.as_rule() == Rule::path_style) {
+        PathStyle::Windows
+    } else {
+        PathStyle::Posix
+    }
+}
+
+/// Parses the color argument of a `color` or `bg` operation.
+fn parse_term_color(pair: pest::iterators::Pair<Rule>) -> Result<TermColor, String> {
+    TermColor::parse(pair.into_inner().next().map_or("", |p| p.as_str()))
+}
+
+/// Parses a with_ext operation with its extension and optional path style.
+fn parse_with_ext_operation(pair: pest::iterators::Pair<Rule>) -> StringOp {
+    let style = parse_path_style(pair.clone());
+    let ext = pair
+        .into_inner()
+        .next()
+        .map_or_else(String::new, |p| arg_value(&p));
+    StringOp::WithExt { ext, style }
+}
+
+/// Parses the optional locale of an upper or lower operation.
+///
+/// The grammar only accepts known locales; case mapping defaults to the
+/// Unicode rules.
+fn parse_case_locale(pair: pest::iterators::Pair<Rule>) -> CaseLocale {
+    match pair.into_inner().next().map(|p| p.as_str()) {
+        Some("tr" | "az") => CaseLocale::Turkic,
+        _ => CaseLocale::Root,
+    }
+}
+
+/// Parses a capitalize operation with its optional `lower` option and locale.
+fn parse_capitalize_operation(pair: pest::iterators::Pair<Rule>) -> StringOp {
+    let mut lower_rest = false;
+    let mut locale = CaseLocale::Root;
+    for part in pair.into_inner() {
+        match part.as_rule() {
+            Rule::capitalize_rest => lower_rest = true,
+            _ => locale = CaseLocale::Turkic,
+        }
+    }
+    StringOp::Capitalize { lower_rest, locale }
+}
+
+/// Parses a regex extract operation with pattern and optional group.
+///
+/// Processes regex extraction arguments to extract the pattern and optional
+/// capture group number.
+///
+/// # Arguments
+///
+/// * `pair` - Parse tree node for the regex extract operation
+///
+/// # Returns
+///
+/// * `Ok(StringOp::RegexExtract)` - Parsed regex extract operation
+/// * `Err(String)` - Error if arguments are invalid
+fn parse_regex_extract_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
+    let mut parts = pair.into_inner();
+    let pattern = parts.next().unwrap().as_str().to_string();
+    let group = parts.next().and_then(|p| p.as_str().parse().ok());
+    Ok(StringOp::RegexExtract { pattern, group })
+}
+
+/// Parses an extract_between operation.
+///
+/// # Arguments
+///
+/// * `pair` - Parse tree node for the extract_between operation
+///
+/// # Returns
+///
+/// * `Ok(StringOp::ExtractBetween)` - Parsed extract_between operation
+/// * `Err(String)` - Error if a delimiter is empty or the group number is
+///   invalid
+fn parse_extract_between_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
+    let mut parts = pair.into_inner();
+    let open = arg_value(&parts.next().unwrap());
+    let close = arg_value(&parts.next().unwrap());
+    if open.is_empty() || close.is_empty() {
+        return Err("extract_between delimiters cannot be empty".to_string());
+    }
+    let nth = match parts.next() {
+        Some(p) => p
+            .as_str()
+            .parse()
+            .map_err(|_| format!("Invalid group number '{}'", p.as_str()))?,
+        None => 0,
+    };
+    Ok(StringOp::ExtractBetween { open, close, nth })
+}
+
+/// Parses a map operation with nested operation list.
+///
+/// Processes the map operation to extract the nested operations that should
+/// be applied to each list item.
+///
+/// # Arguments
+///
+/// * `pair` - Parse tree node for the map operation
+///
+/// # Returns
+///
+/// * `Ok(StringOp::Map)` - Parsed map operation with nested operations
+/// * `Err(String)` - Error if nested operations are invalid
+fn parse_map_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
+    let map_op_pair = pair.into_inner().next().unwrap();
+    Ok(StringOp::Map {
+        operations: parse_sub_pipeline(map_op_pair)?,
+    })
+}
+
+/// Parses the `{...}` operation list of a map-like operation.
+///
+/// # Arguments
+///
+/// * `pair` - Parse tree node for the braced operation list
+///
+/// # Returns
+///
+/// * `Ok(operations)` - Operations to apply to each list item
+/// * `Err(String)` - Error if nested operations are invalid
+fn parse_sub_pipeline(
+    pair: pest::iterators::Pair<Rule>,
+) -> Result<Box<SmallVec<[StringOp; 8]>>, String> {
+    let operation_list_pair = pair.into_inner().next().unwrap();
+
+    let mut operations: SmallVec<[StringOp; 8]> = SmallVec::new();
+    for op_pair in operation_list_pair.into_inner() {
+        let inner_op_pair = op_pair.into_inner().next().unwrap();
+        operations.push(parse_map_inner_operation(inner_op_pair)?);
+    }
+    Ok(Box::new(operations))
+}
+
+/// Parses a sort_by operation with its key pipeline and sort options.
+///
+/// # Arguments
+///
+/// * `pair` - Parse tree node for the sort_by operation
+///
+/// # Returns
+///
+/// * `Ok(StringOp::SortBy)` - Parsed operation
+/// * `Err(String)` - Error if the key operations are invalid
+fn parse_sort_by_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
+    let mut operations = None;
+    let mut numeric = false;
+    let mut direction = SortDirection::Asc;
+    for part in pair.into_inner() {
+        match part.as_rule() {
+            Rule::map_operation => operations = Some(parse_sub_pipeline(part)?),
+            Rule::sort_numeric => numeric = true,
+            Rule::sort_direction if part.as_str() == "desc" => direction = SortDirection::Desc,
+            _ => {}
+        }
+    }
+    Ok(StringOp::SortBy {
+        operations: operations.unwrap(),
+        numeric,
+        direction,
+    })
+}
+
+/// Parses an intersect, diff or union operation with its second list.
+///
+/// An argument starting with `@` names a file whose lines form the list;
+/// otherwise the list is the comma-separated items of the argument. Escaped
+/// commas (`\,`) and a leading `\@` are taken literally.
+///
+/// # Arguments
+///
+/// * `pair` - Parse tree node for the set operation
+///
+/// # Returns
+///
+/// `StringOp::Intersect`, `StringOp::Diff` or `StringOp::Union`.
+fn parse_set_operation(pair: pest::iterators::Pair<Rule>) -> StringOp {
+    let rule = pair.as_rule();
+    let raw = pair.into_inner().next().unwrap().as_str();
+    let other = if let Some(path) = raw.strip_prefix('@') {
+        ListSource::File(PathBuf::from(process_arg(path)))
+    } else if raw.is_empty() {
+        ListSource::Items(Vec::new())
+    } else {
+        ListSource::Items(split_unescaped(raw, b',').map(process_arg).collect())
+    };
+    match rule {
+        Rule::intersect => StringOp::Intersect { other },
+        Rule::diff => StringOp::Diff { other },
+        _ => StringOp::Union { other },
+    }
+}
+
+/// Splits a raw argument at every `sep` byte that is not escaped.
+fn split_unescaped(raw: &str, sep: u8) -> impl Iterator<Item = &str> {
+    let bytes = raw.as_bytes();
+    let mut start = 0;
+    let mut i = 0;
+    std::iter::from_fn(move || {
+        if start > bytes.len() {
+            return None;
+        }
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' => i += 2,
+                b if b == sep => {
+                    let part = &raw[start..i];
+                    i += 1;
+                    start = i;
+                    return Some(part);
+                }
+                _ => i += 1,
+            }
+        }
+        let part = &raw[start..];
+        start = bytes.len() + 1;
+        Some(part)
+    })
+}
+
+/// Parses a try operation with its sub-pipeline and optional fallback.
+fn parse_try_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
+    let mut parts = pair.into_inner();
+    let operations = parse_sub_pipeline(parts.next().unwrap())?;
+    let fallback = parts.next().map(|p| arg_value(&p));
+    Ok(StringOp::Try {
+        operations,
+        fallback,
+    })
+}
+
+/// Parses a filter_by operation with its sub-pipeline and optional pattern.
+///
+/// # Arguments
+///
+/// * `pair` - Parse tree node for the filter_by operation
+///
+/// # Returns
+///
+/// * `Ok(StringOp::FilterBy)` - Parsed operation
+/// * `Err(String)` - Error if the sub-pipeline operations are invalid
+fn parse_filter_by_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
+    let mut parts = pair.into_inner();
+    let operations = parse_sub_pipeline(parts.next().unwrap())?;
+    let pattern = parts.next().map(|p| p.as_str().to_string());
+    Ok(StringOp::FilterBy {
+        operations,
+        pattern,
+    })
+}
+
+/// Parses operations that can be used inside map blocks.
+///
+/// Handles the subset of operations that are valid within map contexts,
+/// including both string operations and list operations that work on individual items.
+///
+/// # Arguments
+///
+/// * `pair` - Parse tree node for the map inner operation
+///
+/// # Returns
+///
+/// * `Ok(StringOp)` - Parsed operation valid for map context
+/// * `Err(String)` - Error if operation is invalid or unsupported in map
+fn parse_map_inner_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
+    match pair.as_rule() {
+        // String operations (existing)
+        Rule::substring => Ok(StringOp::Substring {
+            range: extract_range_arg(pair)?,
+        }),
+        Rule::replace => parse_replace_operation(pair),
+        Rule::replace_literal => parse_replace_literal_operation(pair),
+        Rule::append => Ok(StringOp::Append {
+            suffix: extract_single_arg(pair)?,
+        }),
+        Rule::prepend => Ok(StringOp::Prepend {
+            prefix: extract_single_arg(pair)?,
+        }),
+        Rule::surround | Rule::quote => Ok(parse_surround_operation(pair)),
+        Rule::upper => Ok(StringOp::Upper {
+            locale: parse_case_locale(pair),
+        }),
+        Rule::lower => Ok(StringOp::Lower {
+            locale: parse_case_locale(pair),
+        }),
+        Rule::capitalize => Ok(parse_capitalize_operation(pair)),
+        Rule::swapcase => Ok(StringOp::SwapCase {
+            locale: parse_case_locale(pair),
+        }),
+        Rule::trim => {
+            let chars = parse_trim_chars(pair.clone());
+            let direction = parse_trim_direction(pair);
+            Ok(StringOp::Trim { chars, direction })
+        }
+        Rule::squeeze => Ok(StringOp::Squeeze {
+            chars: pair
+                .into_inner()
+                .next()
+                .map(|p| arg_value(&p))
+                .unwrap_or_default(),
+        }),
+        Rule::trim_prefix => Ok(StringOp::TrimPrefix {
+            prefix: extract_single_arg(pair)?,
+        }),
+        Rule::trim_suffix => Ok(StringOp::TrimSuffix {
+            suffix: extract_single_arg(pair)?,
+        }),
+        Rule::pad | Rule::center => parse_pad_operation(pair),
+        Rule::reverse => Ok(StringOp::Reverse),
+        Rule::len => Ok(parse_len_operation(pair)),
+        Rule::strip_ansi => Ok(StringOp::StripAnsi),
+        Rule::words => Ok(StringOp::Words),
+        Rule::sentences => Ok(StringOp::Sentences),
+        Rule::extract_emails => Ok(StringOp::ExtractEmails),
+        Rule::extract_urls => Ok(StringOp::ExtractUrls),
+        Rule::extract_uuids => Ok(StringOp::ExtractUuids),
+        Rule::extract_between => parse_extract_between_operation(pair),
+        Rule::deaccent => Ok(StringOp::Deaccent),
+        Rule::ascii => Ok(StringOp::Ascii),
+        Rule::slugify => Ok(parse_slugify_operation(pair)),
+        Rule::normalize => Ok(parse_normalize_operation(pair)),
+        Rule::ordinal => Ok(StringOp::Ordinal {
+            parse: has_numeral_parse(pair),
+        }),
+        Rule::to_words => Ok(StringOp::ToWords {
+            parse: has_numeral_parse(pair),
+        }),
+        Rule::roman => Ok(StringOp::Roman {
+            parse: has_numeral_parse(pair),
+        }),
+        Rule::basename => Ok(StringOp::Basename {
+            style: parse_path_style(pair),
+        }),
+        Rule::dirname => Ok(StringOp::Dirname {
+            style: parse_path_style(pair),
+        }),
+        Rule::extname => Ok(StringOp::Extname {
+            style: parse_path_style(pair),
+        }),
+        Rule::strip_ext => Ok(StringOp::StripExt {
+            style: parse_path_style(pair),
+        }),
+        Rule::with_ext => Ok(parse_with_ext_operation(pair)),
+        Rule::yaml_get | Rule::toml_get => parse_config_get_operation(pair),
+        Rule::url_scheme => Ok(StringOp::UrlScheme),
+        Rule::url_host => Ok(StringOp::UrlHost),
+        Rule::url_path => Ok(StringOp::UrlPath),
+        Rule::url_query => Ok(StringOp::UrlQuery {
+            key: pair
+                .into_inner()
+                .next()
+                .map_or_else(String::new, |p| arg_value(&p)),
+        }),
+        Rule::color => Ok(StringOp::Color {
+            color: parse_term_color(pair)?,
+        }),
+        Rule::bg => Ok(StringOp::Background {
+            color: parse_term_color(pair)?,
+        }),
+        Rule::bold => Ok(StringOp::Bold),
+        Rule::underline => Ok(StringOp::Underline),
+        Rule::style_reset => Ok(StringOp::StyleReset),
+        Rule::normalize_path => Ok(StringOp::NormalizePath {
+            style: parse_path_style(pair),
+        }),
+        Rule::read_file => Ok(StringOp::ReadFile),
+        Rule::exec => parse_exec_operation(pair),
+        Rule::map_regex_extract => parse_regex_extract_operation(pair),
+
+        // List operations (new)
+        Rule::map_split_any => parse_split_any_operation(pair),
+        Rule::map_split => parse_split_operation(pair),
+        Rule::partition | Rule::rpartition => parse_partition_operation(pair),
+        Rule::map_join => Ok(parse_join_operation(pair)),
+        Rule::output_sep => Ok(StringOp::OutputSep {
+            sep: extract_single_arg(pair)?,
+        }),
+        Rule::map_slice => Ok(StringOp::Slice {
+            range: extract_range_arg(pair)?,
+        }),
+        Rule::map_clamp => Ok(StringOp::Clamp {
+            range: extract_range_arg(pair)?,
+        }),
+        Rule::map_sort => Ok(StringOp::Sort {
+            direction: parse_sort_direction(pair),
+        }),
+        Rule::map_unique => Ok(StringOp::Unique),
+        Rule::duplicates => Ok(StringOp::Duplicates),
+        Rule::histogram => parse_histogram_operation(pair),
+        Rule::remove_empty => Ok(StringOp::RemoveEmpty),
+        Rule::intersect | Rule::diff | Rule::union => Ok(parse_set_operation(pair)),
+        Rule::shuffle | Rule::sample => parse_random_operation(pair),
+        Rule::table | Rule::md_table | Rule::html_table => Ok(parse_table_operation(pair)),
+        Rule::kv | Rule::kv_keys | Rule::kv_values => parse_kv_operation(pair),
+        Rule::map_filter => Ok(StringOp::Filter {
+            pattern: extract_single_arg_raw(pair)?,
+        }),
+        Rule::map_filter_not => Ok(StringOp::FilterNot {
+            pattern: extract_single_arg_raw(pair)?,
+        }),
+        Rule::filter_prefix | Rule::filter_suffix | Rule::filter_contains => {
+            parse_filter_text_operation(pair)
+        }
+        Rule::filter_valid_utf8 => Ok(StringOp::FilterValidUtf8),
+        Rule::filter_luhn => Ok(StringOp::FilterLuhn),
+        Rule::filter_checksum => Ok(parse_filter_checksum_operation(pair)),
+        Rule::try_op => parse_try_operation(pair),
+        Rule::map_trim_regex => Ok(parse_trim_regex_operation(pair)),
+        Rule::map_highlight => parse_highlight_operation(pair),
+
+        _ => Err(format!("Unsupported map operation: {:?}", pair.as_rule())),
+    }
+}
+
+/// Parses the `filter_prefix`, `filter_suffix` and `filter_contains` operations.
+fn parse_filter_text_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
+    let rule = pair.as_rule();
+    let text = extract_single_arg(pair)?;
+    Ok(match rule {
+        Rule::filter_prefix => StringOp::FilterPrefix { prefix: text },
+        Rule::filter_suffix => StringOp::FilterSuffix { suffix: text },
+        _ => StringOp::FilterContains { needle: text },
+    })
+}
+
+/// Returns the value of a text argument, removing its quotes if it has them.
+///
+/// Unquoted arguments go through [`process_arg`].
+fn arg_value(pair: &pest::iterators::Pair<Rule>) -> String {
+    quoted_value(pair).unwrap_or_else(|| process_arg(pair.as_str()))
+}
+
+/// Returns the value of a quoted argument, or `None` if it is not quoted.
+///
+/// Single-quoted arguments are taken literally. Double-quoted arguments
+/// accept `\"` and the escape sequences of [`process_arg`].
+fn quoted_value(pair: &pest::iterators::Pair<Rule>) -> Option<String> {
+    let quoted = pair.clone().into_inner().next()?;
+    let text = quoted.as_str();
+    match quoted.as_rule() {
+        Rule::single_quoted => Some(text[1..text.len() - 1].to_string()),
+        Rule::double_quoted => Some(process_arg(&text[1..text.len() - 1])),
+        _ => None,
     }
 }
 
@@ -673,43 +2039,52 @@ fn process_arg(s: &str) -> String {
         return s.to_string();
     }
 
-    // Optimized path: pre-allocate with exact capacity and use efficient iteration
     let mut result = String::with_capacity(s.len());
-    let bytes = s.as_bytes();
-    let mut i = 0;
-
-    while i < bytes.len() {
-        if bytes[i] == b'\\' && i + 1 < bytes.len() {
-            // Handle escape sequence
-            match bytes[i + 1] {
-                b'n' => result.push('\n'),
-                b't' => result.push('\t'),
-                b'r' => result.push('\r'),
-                b':' => result.push(':'),
-                b'|' => result.push('|'),
-                b'\\' => result.push('\\'),
-                b'/' => result.push('/'),
-                b'{' => result.push('{'),
-                b'}' => result.push('}'),
-                other => result.push(other as char),
-            }
-            i += 2;
-        } else if bytes[i] == b'\\' {
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some(other) => result.push(other),
             // Backslash at end of string
-            result.push('\\');
-            i += 1;
-        } else {
-            // Regular character
-            result.push(bytes[i] as char);
-            i += 1;
+            None => result.push('\\'),
         }
     }
     result
 }
 
+/// Parses a replace operation with its `;`-separated sed strings.
+///
+/// # Arguments
+///
+/// * `pair` - Parse tree node for the replace operation
+///
+/// # Returns
+///
+/// * `Ok(StringOp::Replace)` - Parsed replace operation, one rule per sed string
+/// * `Err(String)` - Error if a sed string is invalid
+fn parse_replace_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
+    let rules = pair
+        .into_inner()
+        .map(parse_sed_string)
+        .collect::<Result<_, _>>()?;
+    Ok(StringOp::Replace { rules })
+}
+
 /// Parses sed-style replacement strings.
 ///
 /// Extracts pattern, replacement, and flags from sed-style syntax like `s/pattern/replacement/flags`.
+/// Any punctuation character can stand in for `/`, as in `s|/usr|/opt|`, and
+/// is written `\D` inside the pattern and replacement. Digits in the flags
+/// select the occurrence to replace, as in `s/a/b/2`.
+///
+/// The pattern is stored as regex source, or as plain text with the `l`
+/// flag, so an escaped delimiter reads the same whichever delimiter is used.
 ///
 /// # Arguments
 ///
@@ -717,15 +2092,17 @@ fn process_arg(s: &str) -> String {
 ///
 /// # Returns
 ///
-/// * `Ok((pattern, replacement, flags))` - Parsed sed components
+/// * `Ok(SedRule)` - Parsed sed rule
 /// * `Err(String)` - Error if sed syntax is invalid
 ///
 /// # Errors
 ///
-/// Returns an error if the pattern is empty (which would be invalid in regex).
-fn parse_sed_string(pair: pest::iterators::Pair<Rule>) -> Result<(String, String, String), String> {
+/// Returns an error if the pattern is empty (which would be invalid in regex)
+/// or the occurrence is zero.
+fn parse_sed_string(pair: pest::iterators::Pair<Rule>) -> Result<SedRule, String> {
     let mut parts = pair.into_inner();
 
+    let delimiter = parts.next().unwrap().as_str().chars().next().unwrap();
     let pattern_str = parts.next().unwrap().as_str();
     let replacement_str = parts.next().unwrap().as_str();
     let flags_opt = parts.next();
@@ -734,11 +2111,95 @@ fn parse_sed_string(pair: pest::iterators::Pair<Rule>) -> Result<(String, String
         return Err("Empty pattern in sed string".to_string());
     }
 
-    Ok((
-        pattern_str.to_string(),
-        replacement_str.to_string(),
-        flags_opt.map_or_else(String::new, |p| p.as_str().to_string()),
-    ))
+    let (digits, flags): (String, String) = flags_opt
+        .map_or("", |p| p.as_str())
+        .chars()
+        .partition(char::is_ascii_digit);
+    let occurrence = if digits.is_empty() {
+        None
+    } else {
+        match digits.parse::<usize>() {
+            Ok(n) if n > 0 => Some(n),
+            _ => return Err(format!("Invalid occurrence '{digits}' in sed flags")),
+        }
+    };
+
+    let literal = flags.contains('l');
+    let pattern = unescape_sed(pattern_str, |c| {
+        if literal {
+            (c == delimiter || c == '\\').then(|| c.to_string())
+        } else {
+            // `\/` means `/` whatever the delimiter, as it does in a regex
+            (c == delimiter || c == '/').then(|| regex::escape(&c.to_string()))
+        }
+    });
+    let replacement = unescape_sed(replacement_str, |c| {
+        (c == delimiter).then(|| match c {
+            '$' if !literal => "$$".to_string(),
+            c => c.to_string(),
+        })
+    });
+
+    Ok(SedRule {
+        pattern,
+        replacement,
+        flags,
+        occurrence,
+    })
+}
+
+/// Resolves the escapes of one part of a sed string.
+///
+/// `resolve` gives the text of an escaped character, or `None` to keep the
+/// backslash for the regex engine or the replacement.
+fn unescape_sed(text: &str, resolve: impl Fn(char) -> Option<String>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some(escaped) => match resolve(escaped) {
+                Some(resolved) => out.push_str(&resolved),
+                None => {
+                    out.push('\\');
+                    out.push(escaped);
+                }
+            },
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Parses a replace_literal operation with its optional count.
+///
+/// # Arguments
+///
+/// * `pair` - Parse tree node for the replace_literal operation
+///
+/// # Returns
+///
+/// * `Ok(StringOp::ReplaceLiteral)` - Parsed literal replacement
+/// * `Err(String)` - Error if the text to replace is empty or the count is invalid
+fn parse_replace_literal_operation(pair: pest::iterators::Pair<Rule>) -> Result<StringOp, String> {
+    let mut parts = pair.into_inner();
+    let from = arg_value(&parts.next().unwrap());
+    let to = arg_value(&parts.next().unwrap());
+    if from.is_empty() {
+        return Err("Empty text to replace in replace_literal".to_string());
+    }
+    let count = parts
+        .next()
+        .map(|p| {
+            p.as_str()
+                .parse::<usize>()
+                .map_err(|_| format!("Invalid replace_literal count '{}'", p.as_str()))
+        })
+        .transpose()?;
+    Ok(StringOp::ReplaceLiteral { from, to, count })
 }
 
 /// Parses range specifications from template syntax.
@@ -763,41 +2224,422 @@ fn parse_sed_string(pair: pest::iterators::Pair<Rule>) -> Result<(String, String
 /// - Open start: `..3`, `..=3`
 /// - Open end: `2..`
 /// - Full range: `..`
+/// - Stepped range: `0..10;2`, `..;-1`
+/// - Multiple ranges: `0,3,7`, `0,2..4,-1`
 fn parse_range_spec(pair: pest::iterators::Pair<Rule>) -> Result<RangeSpec, String> {
-    let inner = pair.into_inner().next().unwrap();
-    match inner.as_rule() {
+    let mut ranges = pair
+        .into_inner()
+        .map(parse_range_item)
+        .collect::<Result<Vec<_>, _>>()?;
+    if ranges.len() == 1 {
+        Ok(ranges.pop().unwrap())
+    } else {
+        Ok(RangeSpec::Multi(ranges))
+    }
+}
+
+/// Parses one comma-separated part of a range specification.
+fn parse_range_item(pair: pest::iterators::Pair<Rule>) -> Result<RangeSpec, String> {
+    let mut parts = pair.into_inner();
+    let inner = parts.next().unwrap();
+    let range = match inner.as_rule() {
         Rule::range_inclusive => {
             let mut parts = inner.into_inner();
             let start = parts.next().and_then(|p| p.as_str().parse().ok());
             let end = parts.next().and_then(|p| p.as_str().parse().ok());
-            Ok(RangeSpec::Range(start, end, true))
+            // Without an end, `..=` selects the same items as `..`
+            RangeSpec::Range(start, end, end.is_some())
         }
         Rule::range_exclusive => {
             let mut parts = inner.into_inner();
             let start = parts.next().and_then(|p| p.as_str().parse().ok());
             let end = parts.next().and_then(|p| p.as_str().parse().ok());
-            Ok(RangeSpec::Range(start, end, false))
+            RangeSpec::Range(start, end, false)
         }
         Rule::range_from => {
             let start = inner.into_inner().next().unwrap().as_str().parse().ok();
-            Ok(RangeSpec::Range(start, None, false))
+            RangeSpec::Range(start, None, false)
         }
         Rule::range_to => {
             let end = inner.into_inner().next().unwrap().as_str().parse().ok();
-            Ok(RangeSpec::Range(None, end, false))
+            RangeSpec::Range(None, end, false)
         }
         Rule::range_to_inclusive => {
             let end = inner.into_inner().next().unwrap().as_str().parse().ok();
-            Ok(RangeSpec::Range(None, end, true))
+            RangeSpec::Range(None, end, true)
         }
-        Rule::range_full => Ok(RangeSpec::Range(None, None, false)),
+        Rule::range_full => RangeSpec::Range(None, None, false),
         Rule::index => {
             let idx_str = inner.into_inner().next().unwrap().as_str();
             let idx = idx_str
                 .parse()
                 .map_err(|_| format!("Invalid index: {idx_str}"))?;
-            Ok(RangeSpec::Index(idx))
+            RangeSpec::Index(idx)
+        }
+        _ => return Err(format!("Unknown range spec: {:?}", inner.as_rule())),
+    };
+
+    let Some(step_pair) = parts.next() else {
+        return Ok(range);
+    };
+    let step_str = step_pair.into_inner().next().unwrap().as_str();
+    let step: isize = step_str
+        .parse()
+        .map_err(|_| format!("Invalid range step: {step_str}"))?;
+    if step == 0 {
+        return Err("Range step cannot be 0".to_string());
+    }
+
+    // A step of 1 is a plain range, which keeps the contiguous fast paths
+    match range {
+        RangeSpec::Range(start, end, inclusive) if step != 1 => {
+            Ok(RangeSpec::Stepped(start, end, inclusive, step))
+        }
+        _ => Ok(range),
+    }
+}
+
+/* ------------------------------------------------------------------------ */
+/*  Tokenization for editors                                                */
+/* ------------------------------------------------------------------------ */
+
+/// Syntactic class of a [`Token`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TokenKind {
+    /// Literal text outside template sections, including `${...}` shell variables.
+    Literal,
+    /// A `{` or `}` opening or closing a template section or an operation list.
+    Brace,
+    /// The `!` enabling debug mode at the start of a template section.
+    DebugFlag,
+    /// An `input=NAME` reference selecting the input of a template section.
+    InputRef,
+    /// A `set:NAME` or `get:NAME` variable at the start of a template section.
+    Variable,
+    /// A placeholder like `input_len` at the start of a template section.
+    Placeholder,
+    /// The `[?` or `]` around a conditional group in literal text.
+    Group,
+    /// The keyword of an operation, such as `split`.
+    OperationName,
+    /// An operation argument, such as a separator, pattern or range, written
+    /// as in the template with its escapes.
+    Argument,
+    /// A `:` between an operation and its arguments, or a `|` between operations.
+    Separator,
+    /// Text that cannot be parsed: the contents of an invalid template
+    /// section, or everything after an unclosed brace.
+    Error,
+}
+
+/// A classified piece of template text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    /// Syntactic class of the text.
+    pub kind: TokenKind,
+    /// Byte range of the text in the template.
+    pub span: Range<usize>,
+}
+
+/// Splits a template into classified tokens for syntax highlighting.
+///
+/// Tokens are returned in order, never overlap, and cover the whole template
+/// except empty arguments. Unlike [`Template::parse`](crate::Template::parse),
+/// tokenizing never fails: template sections that do not parse become a
+/// single [`TokenKind::Error`] token between their braces, so editors can
+/// still highlight the rest of the template and mark the faulty section.
+///
+/// Only the syntax is checked. Operations that parse but are rejected later,
+/// such as invalid regex patterns or type errors, are tokenized normally.
+///
+/// # Arguments
+///
+/// * `template` - The template text to tokenize
+///
+/// # Examples
+///
+/// ```rust
+/// use string_pipeline::{TokenKind, tokenize};
+///
+/// let template = "Hi {split:,:0|upper}";
+/// let tokens: Vec<(TokenKind, &str)> = tokenize(template)
+///     .into_iter()
+///     .map(|token| (token.kind, &template[token.span]))
+///     .collect();
+///
+/// assert_eq!(
+///     tokens,
+///     [
+///         (TokenKind::Literal, "Hi "),
+///         (TokenKind::Brace, "{"),
+///         (TokenKind::OperationName, "split"),
+///         (TokenKind::Separator, ":"),
+///         (TokenKind::Argument, ","),
+///         (TokenKind::Separator, ":"),
+///         (TokenKind::Argument, "0"),
+///         (TokenKind::Separator, "|"),
+///         (TokenKind::OperationName, "upper"),
+///         (TokenKind::Brace, "}"),
+///     ]
+/// );
+///
+/// // Invalid sections are marked without hiding the rest of the template
+/// let tokens = tokenize("{nope} {upper}");
+/// assert_eq!(tokens[1].kind, TokenKind::Error);
+/// assert_eq!(tokens[5].kind, TokenKind::OperationName);
+/// ```
+pub fn tokenize(template: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let bytes = template.as_bytes();
+    let mut literal_start = 0;
+    let mut in_group = false;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] != b'{' {
+            i += 1;
+            continue;
+        }
+
+        let close = matching_brace(bytes, i);
+        // Shell variable expansions `${...}` are literal text
+        if i > 0
+            && bytes[i - 1] == b'$'
+            && let Some(close) = close
+        {
+            i = close + 1;
+            continue;
+        }
+
+        tokenize_literal(template, literal_start..i, &mut in_group, &mut tokens);
+        let Some(close) = close else {
+            push_token(&mut tokens, TokenKind::Brace, i..i + 1);
+            push_token(&mut tokens, TokenKind::Error, i + 1..bytes.len());
+            return tokens;
+        };
+        tokenize_section(&template[i..=close], i, &mut tokens);
+        i = close + 1;
+        literal_start = i;
+    }
+
+    tokenize_literal(
+        template,
+        literal_start..bytes.len(),
+        &mut in_group,
+        &mut tokens,
+    );
+    tokens
+}
+
+/// Tokenizes literal text, splitting out the markers of conditional groups.
+fn tokenize_literal(
+    template: &str,
+    span: Range<usize>,
+    in_group: &mut bool,
+    tokens: &mut Vec<Token>,
+) {
+    let mut start = span.start;
+    loop {
+        let rest = &template[start..span.end];
+        let marker = if *in_group {
+            rest.find(GROUP_CLOSE)
+                .map(|found| (found, GROUP_CLOSE.len_utf8()))
+        } else {
+            find_group_open(rest).map(|found| (found, GROUP_OPEN.len()))
+        };
+        let Some((found, len)) = marker else {
+            push_token(tokens, TokenKind::Literal, start..span.end);
+            return;
+        };
+        push_token(tokens, TokenKind::Literal, start..start + found);
+        push_token(tokens, TokenKind::Group, start + found..start + found + len);
+        *in_group = !*in_group;
+        start += found + len;
+    }
+}
+
+/// Returns the index of the brace closing the one at `open`, if any.
+fn matching_brace(bytes: &[u8], open: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, &byte) in bytes.iter().enumerate().skip(open) {
+        match byte {
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Appends a token unless its span is empty.
+fn push_token(tokens: &mut Vec<Token>, kind: TokenKind, span: Range<usize>) {
+    if !span.is_empty() {
+        tokens.push(Token { kind, span });
+    }
+}
+
+/// Tokenizes one `{...}` template section starting at byte `offset`.
+fn tokenize_section(section: &str, offset: usize, tokens: &mut Vec<Token>) {
+    let end = offset + section.len();
+    if tokenize_each_section(section, offset, tokens) {
+        return;
+    }
+    let parsed = match TemplateParser::parse(Rule::template, section) {
+        Ok(mut pairs) => pairs.next(),
+        Err(e) => {
+            if let Some((rewritten, positions)) = rewrite_alias(section, &e) {
+                // Tokenize the section with the keyword and map the tokens back
+                let mut rewritten_tokens = Vec::new();
+                tokenize_section(&rewritten, 0, &mut rewritten_tokens);
+                tokens.extend(rewritten_tokens.into_iter().map(|token| Token {
+                    kind: token.kind,
+                    span: offset + positions[token.span.start]
+                        ..offset + positions[token.span.end - 1] + 1,
+                }));
+                return;
+            }
+            None
+        }
+    }
+    .filter(|pair| pair.as_span().end() == section.len());
+
+    push_token(tokens, TokenKind::Brace, offset..offset + 1);
+    let Some(pair) = parsed else {
+        push_token(tokens, TokenKind::Error, offset + 1..end - 1);
+        push_token(tokens, TokenKind::Brace, end - 1..end);
+        return;
+    };
+
+    for child in pair.into_inner() {
+        match child.as_rule() {
+            Rule::debug_flag => push_token(tokens, TokenKind::DebugFlag, shifted(&child, offset)),
+            Rule::input_ref => {
+                let span = shifted(&child, offset);
+                let reference_end = shifted(&child.into_inner().next().unwrap(), offset).end;
+                push_token(tokens, TokenKind::InputRef, span.start..reference_end);
+                push_token(tokens, TokenKind::Separator, reference_end..span.end);
+            }
+            Rule::set_var | Rule::get_var => {
+                let span = shifted(&child, offset);
+                let name_end = shifted(&child.into_inner().next().unwrap(), offset).end;
+                push_token(tokens, TokenKind::Variable, span.start..name_end);
+                push_token(tokens, TokenKind::Separator, name_end..span.end);
+            }
+            Rule::placeholder => {
+                let span = shifted(&child, offset);
+                let name_end = shifted(&child.into_inner().next().unwrap(), offset).end;
+                push_token(tokens, TokenKind::Placeholder, span.start..name_end);
+                push_token(tokens, TokenKind::Separator, name_end..span.end);
+            }
+            Rule::operation_list => tokenize_operation_list(child, offset, tokens),
+            _ => {}
         }
-        _ => Err(format!("Unknown range spec: {:?}", inner.as_rule())),
     }
+    push_token(tokens, TokenKind::Brace, end - 1..end);
+}
+
+/// Tokenizes an `{each:{OPS}:TEMPLATE}` section, returning `false` without
+/// adding tokens if `section` is not one.
+fn tokenize_each_section(section: &str, offset: usize, tokens: &mut Vec<Token>) -> bool {
+    if !section[1..].starts_with(EACH_PREFIX) {
+        return false;
+    }
+    let open = EACH_PREFIX.len();
+    let Some(close) = matching_brace(section.as_bytes(), open)
+        .filter(|&close| section[close + 1..].starts_with(':') && close + 2 < section.len())
+    else {
+        return false;
+    };
+
+    let end = offset + section.len();
+    push_token(tokens, TokenKind::Brace, offset..offset + 1);
+    push_token(
+        tokens,
+        TokenKind::OperationName,
+        offset + 1..offset + open - 1,
+    );
+    push_token(
+        tokens,
+        TokenKind::Separator,
+        offset + open - 1..offset + open,
+    );
+    // The list operations form a section of their own
+    tokenize_section(&section[open..=close], offset + open, tokens);
+    push_token(
+        tokens,
+        TokenKind::Separator,
+        offset + close + 1..offset + close + 2,
+    );
+    tokens.extend(
+        tokenize(&section[close + 2..section.len() - 1])
+            .into_iter()
+            .map(|token| Token {
+                kind: token.kind,
+                span: offset + close + 2 + token.span.start..offset + close + 2 + token.span.end,
+            }),
+    );
+    push_token(tokens, TokenKind::Brace, end - 1..end);
+    true
+}
+
+/// Tokenizes `|`-separated operations, top-level or inside `{...}`.
+fn tokenize_operation_list(
+    pair: pest::iterators::Pair<Rule>,
+    offset: usize,
+    tokens: &mut Vec<Token>,
+) {
+    let mut cursor = offset + pair.as_span().start();
+    for op_pair in pair.into_inner() {
+        let span = shifted(&op_pair, offset);
+        push_token(tokens, TokenKind::Separator, cursor..span.start);
+        tokenize_operation(op_pair.into_inner().next().unwrap(), offset, tokens);
+        cursor = span.end;
+    }
+}
+
+/// Tokenizes a single operation into its keyword, separators and arguments.
+fn tokenize_operation(pair: pest::iterators::Pair<Rule>, offset: usize, tokens: &mut Vec<Token>) {
+    let span = shifted(&pair, offset);
+    if matches!(
+        pair.as_rule(),
+        Rule::shorthand_index | Rule::shorthand_range
+    ) {
+        push_token(tokens, TokenKind::Argument, span);
+        return;
+    }
+
+    // Keywords are written in the grammar, so they are not pairs of their own
+    let keyword_len = pair
+        .as_str()
+        .bytes()
+        .take_while(|&b| b.is_ascii_lowercase() || b == b'_')
+        .count();
+    let mut cursor = span.start + keyword_len;
+    push_token(tokens, TokenKind::OperationName, span.start..cursor);
+
+    for arg in pair.into_inner() {
+        let arg_span = shifted(&arg, offset);
+        push_token(tokens, TokenKind::Separator, cursor..arg_span.start);
+        if arg.as_rule() == Rule::map_operation {
+            push_token(tokens, TokenKind::Brace, arg_span.start..arg_span.start + 1);
+            let list = arg.into_inner().next().unwrap();
+            tokenize_operation_list(list, offset, tokens);
+            push_token(tokens, TokenKind::Brace, arg_span.end - 1..arg_span.end);
+        } else {
+            push_token(tokens, TokenKind::Argument, arg_span.clone());
+        }
+        cursor = arg_span.end;
+    }
+}
+
+/// Returns the span of `pair` in the whole template.
+fn shifted(pair: &pest::iterators::Pair<Rule>, offset: usize) -> Range<usize> {
+    let span = pair.as_span();
+    offset + span.start()..offset + span.end()
 }
@@ -0,0 +1,165 @@
+//! Lexical path manipulation for the `basename`, `dirname`, `extname`,
+//! `strip_ext`, `with_ext` and `normalize_path` operations.
+//!
+//! Paths are treated as text and never touch the file system, so symbolic
+//! links are not resolved and the results are the same on every platform.
+//! POSIX paths only separate components with `/`. Windows paths accept both
+//! `/` and `\`, and keep a leading drive such as `C:` in front of the root.
+
+use super::PathStyle;
+
+impl PathStyle {
+    fn is_separator(self, c: char) -> bool {
+        c == '/' || (self == PathStyle::Windows && c == '\\')
+    }
+
+    fn separator(self) -> char {
+        match self {
+            PathStyle::Posix => '/',
+            PathStyle::Windows => '\\',
+        }
+    }
+
+    /// Returns the length of a leading drive such as `C:`.
+    fn drive_len(self, path: &str) -> usize {
+        match path.as_bytes() {
+            [letter, b':', ..] if self == PathStyle::Windows && letter.is_ascii_alphabetic() => 2,
+            _ => 0,
+        }
+    }
+}
+
+/// Returns the byte range of the last component of `path`, ignoring
+/// trailing separators, or `None` if the path has no components.
+fn name_range(path: &str, style: PathStyle) -> Option<(usize, usize)> {
+    let drive = style.drive_len(path);
+    let end = path.trim_end_matches(|c| style.is_separator(c)).len();
+    if end <= drive {
+        return None;
+    }
+    let start = path[..end]
+        .rfind(|c| style.is_separator(c))
+        .map_or(drive, |i| i + 1);
+    Some((start, end))
+}
+
+/// Returns the byte offset of the extension dot within `name`, if any.
+///
+/// A leading dot starts a hidden file's name rather than an extension.
+fn extension_dot(name: &str) -> Option<usize> {
+    match name.rfind('.') {
+        Some(0) | None => None,
+        Some(_) if name == ".." => None,
+        Some(i) => Some(i),
+    }
+}
+
+/// Returns the last component of `path`.
+///
+/// Trailing separators are ignored, and a path made only of separators is
+/// its own root: `basename` of `/` is `/`.
+pub(crate) fn basename(path: &str, style: PathStyle) -> &str {
+    match name_range(path, style) {
+        Some((start, end)) => &path[start..end],
+        None => {
+            let drive = style.drive_len(path);
+            let root = path[drive..].chars().next().map_or(0, char::len_utf8);
+            &path[drive..drive + root]
+        }
+    }
+}
+
+/// Returns `path` without its last component, like the `dirname` command.
+///
+/// A path without a directory gives `.`, and the root is its own parent.
+pub(crate) fn dirname(path: &str, style: PathStyle) -> &str {
+    let drive = style.drive_len(path);
+    let root = match path[drive..].chars().next() {
+        Some(c) if style.is_separator(c) => drive + 1,
+        _ => drive,
+    };
+    let Some((start, _)) = name_range(path, style) else {
+        return if root > 0 { &path[..root] } else { "." };
+    };
+    let dir = path[..start].trim_end_matches(|c| style.is_separator(c));
+    if dir.len() > drive {
+        dir
+    } else if root > 0 {
+        &path[..root]
+    } else {
+        "."
+    }
+}
+
+/// Returns the extension of the last component, including its dot.
+pub(crate) fn extname(path: &str, style: PathStyle) -> &str {
+    name_range(path, style)
+        .and_then(|(start, end)| {
+            extension_dot(&path[start..end]).map(|dot| &path[start + dot..end])
+        })
+        .unwrap_or("")
+}
+
+/// Replaces the extension of the last component with `ext`, or removes it
+/// if `ext` is empty. A leading dot in `ext` is optional.
+pub(crate) fn with_ext(path: &str, ext: &str, style: PathStyle) -> String {
+    let Some((start, end)) = name_range(path, style) else {
+        return path.to_string();
+    };
+    let name = &path[start..end];
+    if name == "." || name == ".." {
+        return path.to_string();
+    }
+    let stem_end = extension_dot(name).map_or(end, |dot| start + dot);
+    let ext = ext.strip_prefix('.').unwrap_or(ext);
+    let mut out = String::with_capacity(path.len() + ext.len() + 1);
+    out.push_str(&path[..stem_end]);
+    if !ext.is_empty() {
+        out.push('.');
+        out.push_str(ext);
+    }
+    out.push_str(&path[end..]);
+    out
+}
+
+/// Removes `.` components, duplicate and trailing separators, and `..`
+/// components that follow a directory name.
+///
+/// Leading `..` components of a relative path are kept, those of an
+/// absolute path are dropped, and an empty result becomes `.`.
+pub(crate) fn normalize_path(path: &str, style: PathStyle) -> String {
+    let drive = style.drive_len(path);
+    let rest = &path[drive..];
+    let rooted = rest.starts_with(|c| style.is_separator(c));
+    let mut parts: Vec<&str> = Vec::new();
+    for part in rest.split(|c| style.is_separator(c)) {
+        match part {
+            "" | "." => {}
+            ".." => match parts.last() {
+                Some(&last) if last != ".." => {
+                    parts.pop();
+                }
+                _ if rooted => {}
+                _ => parts.push(".."),
+            },
+            _ => parts.push(part),
+        }
+    }
+
+    let separator = style.separator();
+    let mut out = String::with_capacity(path.len());
+    out.push_str(&path[..drive]);
+    if rooted {
+        out.push(separator);
+    }
+    for (i, part) in parts.iter().enumerate() {
+        if i > 0 {
+            out.push(separator);
+        }
+        out.push_str(part);
+    }
+    if parts.is_empty() && !rooted && drive == 0 {
+        out.push('.');
+    }
+    out
+}
@@ -0,0 +1,179 @@
+//! Per-operation profiling for pipeline execution.
+//!
+//! When profiling is enabled on a [`Template`](crate::Template), the execution
+//! engine records the wall time and invocation count of every operation it
+//! runs. The collected data is exposed as a [`ProfilingReport`] snapshot that
+//! library users and the bench binary can inspect.
+//!
+//! Profiling is a runtime flag: templates without it pay only a branch per
+//! operation.
+
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Timing statistics for one kind of operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperationProfile {
+    /// Operation keyword as written in templates (e.g. `split`, `map`).
+    ///
+    /// Fused fast paths that execute several operations at once are reported
+    /// with their keywords joined by `+` (e.g. `split+join`).
+    pub operation: String,
+    /// Number of times the operation was executed.
+    pub invocations: u64,
+    /// Total wall time spent in the operation.
+    ///
+    /// For `map` this includes the time spent in its inner operations, which
+    /// are also reported individually.
+    pub total_time: Duration,
+}
+
+impl OperationProfile {
+    /// Returns the mean wall time per invocation.
+    pub fn average_time(&self) -> Duration {
+        if self.invocations == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_nanos((self.total_time.as_nanos() / self.invocations as u128) as u64)
+        }
+    }
+}
+
+/// Snapshot of per-operation timings collected while profiling.
+///
+/// Operations are ordered by descending total time.
+///
+/// # Examples
+///
+/// ```rust
+/// use string_pipeline::Template;
+///
+/// let template = Template::parse("{split:,:..|map:{upper}|join:-}")
+///     .unwrap()
+///     .with_profiling(true);
+///
+/// template.format("a,b,c").unwrap();
+///
+/// let report = template.profiling_report().unwrap();
+/// assert_eq!(report.get("split").unwrap().invocations, 1);
+/// assert_eq!(report.get("upper").unwrap().invocations, 3);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfilingReport {
+    operations: Vec<OperationProfile>,
+}
+
+impl ProfilingReport {
+    /// Returns all recorded operations, slowest first.
+    pub fn operations(&self) -> &[OperationProfile] {
+        &self.operations
+    }
+
+    /// Returns the statistics for a single operation keyword.
+    pub fn get(&self, operation: &str) -> Option<&OperationProfile> {
+        self.operations.iter().find(|p| p.operation == operation)
+    }
+
+    /// Returns `true` if no operation was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Adds the statistics of `other` into this report.
+    ///
+    /// Useful for aggregating reports from several templates.
+    pub fn merge(&mut self, other: &ProfilingReport) {
+        for profile in &other.operations {
+            match self
+                .operations
+                .iter_mut()
+                .find(|p| p.operation == profile.operation)
+            {
+                Some(existing) => {
+                    existing.invocations += profile.invocations;
+                    existing.total_time += profile.total_time;
+                }
+                None => self.operations.push(profile.clone()),
+            }
+        }
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        self.operations.sort_by(|a, b| {
+            b.total_time
+                .cmp(&a.total_time)
+                .then_with(|| a.operation.cmp(&b.operation))
+        });
+    }
+}
+
+impl fmt::Display for ProfilingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<16} {:>12} {:>14} {:>14}",
+            "Operation", "Calls", "Total", "Average"
+        )?;
+        for p in &self.operations {
+            writeln!(
+                f,
+                "{:<16} {:>12} {:>14?} {:>14?}",
+                p.operation,
+                p.invocations,
+                p.total_time,
+                p.average_time()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Shared recorder attached to a profiling template.
+///
+/// Clones share the same underlying data.
+#[derive(Clone, Default)]
+pub(crate) struct Profiler {
+    stats: Arc<Mutex<HashMap<&'static str, (u64, Duration)>>>,
+}
+
+impl Profiler {
+    /// Records one execution of `operation` taking `elapsed`.
+    pub(crate) fn record(&self, operation: &'static str, elapsed: Duration) {
+        let mut stats = self.stats.lock();
+        let entry = stats.entry(operation).or_insert((0, Duration::ZERO));
+        entry.0 += 1;
+        entry.1 += elapsed;
+    }
+
+    /// Builds a report from the data recorded so far.
+    pub(crate) fn report(&self) -> ProfilingReport {
+        let stats = self.stats.lock();
+        let mut report = ProfilingReport {
+            operations: stats
+                .iter()
+                .map(|(name, (invocations, total_time))| OperationProfile {
+                    operation: (*name).to_string(),
+                    invocations: *invocations,
+                    total_time: *total_time,
+                })
+                .collect(),
+        };
+        report.sort();
+        report
+    }
+
+    /// Discards all recorded data.
+    pub(crate) fn reset(&self) {
+        self.stats.lock().clear();
+    }
+}
+
+impl fmt::Debug for Profiler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Profiler").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,56 @@
+//! Seedable pseudo-random numbers for the `shuffle` and `sample` operations.
+//!
+//! A seeded generator yields the same sequence on every platform and release,
+//! so templates with an explicit seed produce reproducible output. Unseeded
+//! generators take their seed from the standard library's hash randomness,
+//! which differs between calls.
+//!
+//! The generator is not cryptographically secure; it only needs to be fast
+//! and well distributed for generating test data.
+
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+/// SplitMix64 pseudo-random number generator.
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    /// Creates a generator from `seed`, or from a fresh random seed.
+    pub(crate) fn new(seed: Option<u64>) -> Self {
+        Self(seed.unwrap_or_else(|| RandomState::new().hash_one(0u8)))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..n`; `n` must not be zero.
+    fn below(&mut self, n: usize) -> usize {
+        ((u128::from(self.next_u64()) * n as u128) >> 64) as usize
+    }
+}
+
+/// Shuffles `items` in place with the Fisher-Yates algorithm.
+pub(crate) fn shuffle<T>(items: &mut [T], rng: &mut Rng) {
+    for i in (1..items.len()).rev() {
+        items.swap(i, rng.below(i + 1));
+    }
+}
+
+/// Picks `count` distinct indices out of `0..len`, returned in increasing order.
+///
+/// Returns every index when `count` is at least `len`.
+pub(crate) fn sample_indices(len: usize, count: usize, rng: &mut Rng) -> Vec<usize> {
+    let count = count.min(len);
+    let mut indices: Vec<usize> = (0..len).collect();
+    for i in 0..count {
+        indices.swap(i, i + rng.below(len - i));
+    }
+    indices.truncate(count);
+    indices.sort_unstable();
+    indices
+}
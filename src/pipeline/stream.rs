@@ -0,0 +1,82 @@
+//! Asynchronous formatting of input streams, behind the `tokio` feature.
+
+use std::pin::pin;
+
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::{Stream, StreamExt};
+
+use super::Template;
+
+impl Template {
+    /// Format each item of `input` without blocking the async runtime.
+    ///
+    /// Items are formatted in order on a blocking thread of the Tokio
+    /// runtime, with a [`FormatContext`](crate::FormatContext) reused across
+    /// items as in [`Template::format_into`]. Each item gives one result, so
+    /// an item that fails to format does not end the stream.
+    ///
+    /// At most `buffer` items wait to be formatted and at most `buffer`
+    /// results wait to be read, so a slow reader slows down how fast `input`
+    /// is polled instead of letting results pile up. A `buffer` of 0 is
+    /// treated as 1. Dropping the returned stream stops the formatting.
+    ///
+    /// Available with the `tokio` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    /// use tokio_stream::StreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let template = Template::parse("{split:,:0|upper}").unwrap();
+    /// let lines = tokio_stream::iter(["a,b".to_string(), "c,d".to_string()]);
+    ///
+    /// let results: Vec<_> = template.format_stream(lines, 16).collect().await;
+    /// assert_eq!(results, [Ok("A".to_string()), Ok("C".to_string())]);
+    /// # }
+    /// ```
+    pub fn format_stream<S>(
+        &self,
+        input: S,
+        buffer: usize,
+    ) -> impl Stream<Item = Result<String, String>> + Send + 'static
+    where
+        S: Stream<Item = String> + Send + 'static,
+    {
+        let buffer = buffer.max(1);
+        let (input_tx, mut input_rx) = mpsc::channel::<String>(buffer);
+        let (output_tx, output_rx) = mpsc::channel(buffer);
+
+        tokio::spawn(async move {
+            let mut input = pin!(input);
+            while let Some(item) = input.next().await {
+                if input_tx.send(item).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let template = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut ctx = template.new_context();
+            while let Some(item) = input_rx.blocking_recv() {
+                let mut out = String::new();
+                let result = template
+                    .format_into(&item, &mut ctx, &mut out)
+                    .map(|()| out);
+                if output_tx.blocking_send(result).is_err() {
+                    break;
+                }
+            }
+        });
+
+        ReceiverStream::new(output_rx)
+    }
+}
@@ -0,0 +1,203 @@
+//! ANSI styling for the `color`, `bg`, `bold`, `underline`, `style_reset` and
+//! `highlight` operations.
+//!
+//! Each operation wraps the value between the SGR sequence that turns an
+//! attribute on and the one that turns only that attribute off again, so
+//! styles nest: in `{bold|prepend:> |color:red}` the prompt is red but not
+//! bold. A template can switch styling off, in which case the operations
+//! return the value unchanged. The formatter records the switch of the
+//! running call in a thread-local, which the operations read.
+
+use std::cell::Cell;
+use std::fmt;
+
+use std::borrow::Cow;
+
+use regex::Regex;
+
+use super::{HighlightStyle, TermColor};
+
+/// Names of the basic colors in SGR order, so a color's code is `30 +` its
+/// index.
+const NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// The basic colors followed by their bright variants.
+const NAMED: [TermColor; 16] = [
+    TermColor::Black,
+    TermColor::Red,
+    TermColor::Green,
+    TermColor::Yellow,
+    TermColor::Blue,
+    TermColor::Magenta,
+    TermColor::Cyan,
+    TermColor::White,
+    TermColor::BrightBlack,
+    TermColor::BrightRed,
+    TermColor::BrightGreen,
+    TermColor::BrightYellow,
+    TermColor::BrightBlue,
+    TermColor::BrightMagenta,
+    TermColor::BrightCyan,
+    TermColor::BrightWhite,
+];
+
+impl TermColor {
+    /// Parses a color as written in a template: a name such as `red` or
+    /// `bright_red`, a palette index from 0 to 255, or `#rrggbb`.
+    pub(crate) fn parse(text: &str) -> Result<Self, String> {
+        if let Some(hex) = text.strip_prefix('#') {
+            let channel = |i: usize| {
+                hex.get(i..i + 2)
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+            };
+            return match (hex.len(), channel(0), channel(2), channel(4)) {
+                (6, Some(r), Some(g), Some(b)) => Ok(TermColor::Rgb(r, g, b)),
+                _ => Err(format!("Invalid color: '{text}'")),
+            };
+        }
+        if text.starts_with(|c: char| c.is_ascii_digit()) {
+            return text
+                .parse()
+                .map(TermColor::Indexed)
+                .map_err(|_| format!("Color index must be between 0 and 255, got '{text}'"));
+        }
+        let (offset, name) = match text.strip_prefix("bright_") {
+            Some(name) => (NAMES.len(), name),
+            None => (0, text),
+        };
+        NAMES
+            .iter()
+            .position(|&n| n == name)
+            .map(|i| NAMED[offset + i])
+            .ok_or_else(|| format!("Unknown color: '{text}'"))
+    }
+
+    /// Returns the index of a named color in [`NAMES`] and whether it is
+    /// the bright variant.
+    fn name_index(self) -> (usize, bool) {
+        let i = NAMED.iter().position(|&c| c == self).unwrap_or_default();
+        (i % NAMES.len(), i >= NAMES.len())
+    }
+
+    /// Returns the SGR parameters selecting this color as the foreground,
+    /// or as the background if `background` is set.
+    fn sgr(self, background: bool) -> String {
+        let (base, extended) = if background { (40, 48) } else { (30, 38) };
+        match self {
+            TermColor::Rgb(r, g, b) => format!("{extended};2;{r};{g};{b}"),
+            TermColor::Indexed(n) => format!("{extended};5;{n}"),
+            named => {
+                let (i, bright) = named.name_index();
+                (base + i + if bright { 60 } else { 0 }).to_string()
+            }
+        }
+    }
+}
+
+/// Writes the color as it is written in templates, such as `bright_red`,
+/// `208` or `#ff8800`.
+impl fmt::Display for TermColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            TermColor::Rgb(r, g, b) => write!(f, "#{r:02x}{g:02x}{b:02x}"),
+            TermColor::Indexed(n) => write!(f, "{n}"),
+            named => {
+                let (i, bright) = named.name_index();
+                if bright {
+                    f.write_str("bright_")?;
+                }
+                f.write_str(NAMES[i])
+            }
+        }
+    }
+}
+
+thread_local! {
+    /// Whether the format call running on this thread writes styles.
+    static COLOR: Cell<bool> = const { Cell::new(true) };
+}
+
+/// Restores the color switch of the enclosing call.
+struct ColorGuard(bool);
+
+impl Drop for ColorGuard {
+    fn drop(&mut self) {
+        COLOR.set(self.0);
+    }
+}
+
+/// Runs a format call `f` with styling switched on or off.
+pub(crate) fn with_color<R>(enabled: bool, f: impl FnOnce() -> R) -> R {
+    let _guard = ColorGuard(COLOR.replace(enabled));
+    f()
+}
+
+/// Wraps `s` between `\x1b[{on}m` and `\x1b[{off}m`, or returns it
+/// unchanged when styling is off.
+fn wrap(s: &str, on: &str, off: &str) -> String {
+    if !COLOR.get() {
+        return s.to_string();
+    }
+    format!("\x1b[{on}m{s}\x1b[{off}m")
+}
+
+/// Sets the foreground color of `s`.
+pub(crate) fn color(s: &str, color: TermColor) -> String {
+    wrap(s, &color.sgr(false), "39")
+}
+
+/// Sets the background color of `s`.
+pub(crate) fn background(s: &str, color: TermColor) -> String {
+    wrap(s, &color.sgr(true), "49")
+}
+
+/// Makes `s` bold.
+pub(crate) fn bold(s: &str) -> String {
+    wrap(s, "1", "22")
+}
+
+/// Underlines `s`.
+pub(crate) fn underline(s: &str) -> String {
+    wrap(s, "4", "24")
+}
+
+/// Appends the sequence that resets every style, so styles left open in
+/// the value, such as those of colored input, end with it.
+pub(crate) fn reset(s: &str) -> String {
+    if COLOR.get() {
+        format!("{s}\x1b[0m")
+    } else {
+        s.to_string()
+    }
+}
+
+/// Highlights the non-empty matches of `re` in `s`, or returns `s` unchanged
+/// if nothing matches or colors are off.
+pub(crate) fn highlight<'a>(s: &'a str, re: &Regex, style: &HighlightStyle) -> Cow<'a, str> {
+    let (open, close) = match style {
+        HighlightStyle::Color(_) if !COLOR.get() => return Cow::Borrowed(s),
+        HighlightStyle::Color(color) => (
+            Cow::Owned(format!("\x1b[{}m", color.sgr(false))),
+            Cow::Borrowed("\x1b[39m"),
+        ),
+        HighlightStyle::Marker { open, close } => {
+            (Cow::Borrowed(open.as_str()), Cow::Borrowed(close.as_str()))
+        }
+    };
+    let mut out = String::new();
+    let mut last = 0;
+    for m in re.find_iter(s).filter(|m| !m.is_empty()) {
+        out.push_str(&s[last..m.start()]);
+        out.push_str(&open);
+        out.push_str(m.as_str());
+        out.push_str(&close);
+        last = m.end();
+    }
+    if last == 0 {
+        return Cow::Borrowed(s);
+    }
+    out.push_str(&s[last..]);
+    Cow::Owned(out)
+}
@@ -35,6 +35,8 @@
 //!
 //! - **Operation Caching**: Template section results are cached per input to avoid recomputation
 //! - **Fast Single Split**: Single split operations use an optimized code path
+//! - **Small Template Shortcut**: Templates with only literal text, or one section between
+//!   literal text, skip section caching and dispatch
 //! - **String Interning**: Common separators are interned to reduce memory allocations
 //! - **Regex Caching**: Compiled regex patterns are cached globally for reuse
 //!
@@ -46,14 +48,30 @@
 //! - Cache hit/miss statistics
 //! - Input/output values at each stage
 
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet, hash_map::DefaultHasher};
 use std::fmt::Display;
 use std::hash::{Hash, Hasher};
 use std::ops::Range;
+use std::time::Instant;
 
-use crate::pipeline::get_cached_split;
-use crate::pipeline::{DebugTracer, RangeSpec, StringOp, apply_ops_internal, apply_range, parser}; // ← use global split cache
-use memchr::memchr_iter;
+use crate::pipeline::cache::LocalCaches;
+use crate::pipeline::checksum::{lossy_input, with_lossy_input};
+use crate::pipeline::debug::DEFAULT_PREVIEW_CHARS;
+use crate::pipeline::parser::{OpSpan, ParsedSection};
+use crate::pipeline::{
+    CacheScope, CaseLocale, DebugFormat, DebugLevel, DebugSink, DebugTracer, ErrorPolicy,
+    ExecGuard, Limits, LintWarning, Memo, MemoStats, OpError, ParserConfig, Profiler,
+    ProfilingReport, RangeSpec, SafetyProfile, Section, StringOp, apply_ops_internal, apply_range,
+    apply_string_chain, ascending_range, builder, cache, canonical, grapheme_len,
+    is_fusable_string_chain, lint, parser, range_indices, style, typecheck, with_error_policy,
+    with_strict_ranges,
+}; // ← use global split cache
+use crate::pipeline::{
+    SplitOptions, get_cached_split, has_ansi_controls, separator_matches, split_ranges,
+};
+use fast_strip_ansi::strip_ansi_bytes;
+use memchr::{memchr, memchr_iter, memrchr_iter};
 
 /* ------------------------------------------------------------------------ */
 /*  Template implementation                                                 */
@@ -122,7 +140,85 @@ pub struct Template {
     raw: String,
     sections: Vec<TemplateSection>,
     compiled_sections: Vec<CompiledSectionPlan>,
+    /// Section ranges of the conditional groups `[?...]`, in order.
+    groups: Vec<Range<usize>>,
     debug: bool,
+    debug_format: DebugFormat,
+    debug_level: DebugLevel,
+    debug_preview_chars: usize,
+    debug_sink: Option<DebugSink>,
+    profiler: Option<Profiler>,
+    limits: Option<Limits>,
+    fs_access: bool,
+    exec_access: bool,
+    line_endings: LineEndings,
+    output_separator: Option<String>,
+    cache_scope: CacheScope,
+    color: bool,
+    error_policy: ErrorPolicy,
+    strict_ranges: bool,
+    memo: Option<Memo>,
+    fast_path: Option<FastPath>,
+}
+
+/// How a template treats Windows (`\r\n`) line endings.
+///
+/// # Examples
+///
+/// ```rust
+/// use string_pipeline::{LineEndings, Template};
+///
+/// let template = Template::parse("{split:\\n:..|filter:a$|join:\\n}").unwrap();
+/// assert_eq!(template.format("ba\r\nca\r\n").unwrap(), "");
+///
+/// let template = template.with_line_endings(LineEndings::Lf);
+/// assert_eq!(template.format("ba\r\nca\r\n").unwrap(), "ba\nca");
+///
+/// let template = template.with_line_endings(LineEndings::Crlf);
+/// assert_eq!(template.format("ba\r\nca\r\n").unwrap(), "ba\r\nca");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum LineEndings {
+    /// Leave line endings in the input and output as they are.
+    #[default]
+    Keep,
+    /// Turn `\r\n` in the input into `\n`, so splitting on `\n` leaves no
+    /// `\r` at the end of the items.
+    Lf,
+    /// Like [`LineEndings::Lf`], and also write every `\n` of the output,
+    /// including those of literal text, as `\r\n`.
+    Crlf,
+}
+
+impl LineEndings {
+    /// Replaces `\r\n` in an input with `\n`, unless line endings are kept.
+    fn normalize_input(self, input: &str) -> Cow<'_, str> {
+        if self == LineEndings::Keep || memchr(b'\r', input.as_bytes()).is_none() {
+            Cow::Borrowed(input)
+        } else {
+            Cow::Owned(input.replace("\r\n", "\n"))
+        }
+    }
+}
+
+/// Appends `text` to `out`, writing each `\n` not already preceded by `\r`
+/// as `\r\n`.
+fn push_crlf(out: &mut String, text: &str) {
+    let bytes = text.as_bytes();
+    let mut start = 0;
+    for i in memchr_iter(b'\n', bytes) {
+        let after_cr = match i {
+            0 => out.ends_with('\r'),
+            _ => bytes[i - 1] == b'\r',
+        };
+        out.push_str(&text[start..i]);
+        if !after_cr {
+            out.push('\r');
+        }
+        // The newline itself starts the next chunk
+        start = i;
+    }
+    out.push_str(&text[start..]);
 }
 
 /* ---------- helper enums ------------------------------------------------- */
@@ -145,8 +241,21 @@ struct TemplateExecutionPlan {
 #[derive(Debug, Clone)]
 enum TemplateExecutionKind {
     Passthrough,
-    SplitIndex { sep: String, idx: isize },
-    SplitJoinRewrite { split_sep: String, join_sep: String },
+    SplitIndex {
+        sep: String,
+        idx: isize,
+    },
+    SplitJoinRewrite {
+        split_sep: String,
+        join_sep: String,
+    },
+    SplitRangeJoin {
+        split_sep: String,
+        range: RangeSpec,
+        join_sep: String,
+        operation: &'static str,
+    },
+    StringChain,
     Generic,
 }
 
@@ -156,6 +265,20 @@ enum CachePolicy {
     PerCall,
 }
 
+/// Shortcut for templates with at most one template section, which skips
+/// the section cache and dispatch of a format call.
+#[derive(Debug, Clone)]
+enum FastPath {
+    /// Only literal text, which is the output for every input.
+    Literal(String),
+    /// One template section reading the input, between literal text.
+    Section {
+        prefix: String,
+        index: usize,
+        suffix: String,
+    },
+}
+
 /// Represents a section within a parsed template.
 ///
 /// Templates are decomposed into alternating literal and template sections,
@@ -165,13 +288,201 @@ pub enum TemplateSection {
     /// A literal text section that appears unchanged in the output.
     Literal(String),
     /// A template section containing a sequence of string operations to apply.
-    Template { ops: Vec<StringOp>, cache_key: u64 },
+    Template {
+        ops: Vec<StringOp>,
+        /// Source locations of `ops`, used to attribute formatting errors.
+        spans: Vec<OpSpan>,
+        /// Input selected with `input=`, used by structured formatting.
+        input: Option<InputRef>,
+        /// Variable stored with `set:` or read with `get:`.
+        variable: Option<Variable>,
+        /// Value provided by the formatter, read instead of the input.
+        placeholder: Option<Placeholder>,
+        cache_key: u64,
+    },
 }
 
 impl TemplateSection {
-    pub(crate) fn from_ops(ops: Vec<StringOp>) -> Self {
-        let cache_key = Template::hash_ops(&ops);
-        Self::Template { ops, cache_key }
+    pub(crate) fn from_parsed(parsed: ParsedSection) -> Self {
+        let cache_key = Template::hash_ops(&parsed.ops);
+        Self::Template {
+            ops: parsed.ops,
+            spans: parsed.spans,
+            input: parsed.input,
+            variable: parsed.variable,
+            placeholder: parsed.placeholder,
+            cache_key,
+        }
+    }
+}
+
+/// Sections compare by their literal text, or by their operations, input
+/// reference, variable and placeholder. Source spans and cache keys are
+/// ignored.
+impl PartialEq for TemplateSection {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Literal(a), Self::Literal(b)) => a == b,
+            (
+                Self::Template {
+                    ops,
+                    input,
+                    variable,
+                    placeholder,
+                    ..
+                },
+                Self::Template {
+                    ops: other_ops,
+                    input: other_input,
+                    variable: other_variable,
+                    placeholder: other_placeholder,
+                    ..
+                },
+            ) => {
+                ops == other_ops
+                    && input == other_input
+                    && variable == other_variable
+                    && placeholder == other_placeholder
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for TemplateSection {}
+
+impl Hash for TemplateSection {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Self::Literal(text) => {
+                0u8.hash(state);
+                text.hash(state);
+            }
+            Self::Template {
+                ops,
+                input,
+                variable,
+                placeholder,
+                ..
+            } => {
+                1u8.hash(state);
+                ops.hash(state);
+                input.hash(state);
+                variable.hash(state);
+                placeholder.hash(state);
+            }
+        }
+    }
+}
+
+/// Input read by a template section in structured formatting.
+///
+/// Written at the start of a section as `input=N` or `input=NAME`, as in
+/// `{input=1|upper}` or `{input=users|join:,}`. Sections without one read
+/// the input at their own position.
+///
+/// # Examples
+///
+/// ```rust
+/// use string_pipeline::{InputRef, Template};
+///
+/// let template = Template::parse("{input=users|upper} {input=0}").unwrap();
+/// let info = template.get_section_info();
+/// assert_eq!(info[0].input, Some(InputRef::Name("users".to_string())));
+/// assert_eq!(info[2].input, Some(InputRef::Index(0)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum InputRef {
+    /// Input at this position, as in [`Template::format_with_inputs`].
+    Index(usize),
+    /// Input with this name, as in [`Template::format_with_named_inputs`].
+    Name(String),
+}
+
+/// Variable shared between the template sections of one format call.
+///
+/// Written at the start of a section as `set:NAME:` to store the section
+/// result under `NAME`, or as `get:NAME` to run the section on the stored
+/// value instead of the input. This lets an expensive extraction run once
+/// per input and be reused by later sections.
+///
+/// # Examples
+///
+/// ```rust
+/// use string_pipeline::{Template, Variable};
+///
+/// let template =
+///     Template::parse(r"{set:user:regex_extract:^(\w+)@:1} <{get:user|upper}>").unwrap();
+/// assert_eq!(template.format("ada@example.com").unwrap(), "ada <ADA>");
+///
+/// let info = template.get_section_info();
+/// assert_eq!(info[0].variable, Some(Variable::Set("user".to_string())));
+/// assert_eq!(info[2].variable, Some(Variable::Get("user".to_string())));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Variable {
+    /// Store the section result under this name, as in `{set:NAME:...}`.
+    Set(String),
+    /// Read the value stored under this name, as in `{get:NAME}`.
+    Get(String),
+}
+
+/// Value provided by the formatter, read by a section instead of the input.
+///
+/// Written as the whole start of a section, as in `{input_len}` or
+/// `{file_name|upper}`. `{line_no}` and `{file_name}` read the values set on
+/// a [`FormatContext`] and are only available through
+/// [`Template::format_into`].
+///
+/// # Examples
+///
+/// ```rust
+/// use string_pipeline::{FormatContext, Template};
+///
+/// let template = Template::parse("{file_name}:{line_no}: {input} ({input_len})").unwrap();
+/// let mut ctx = FormatContext::new();
+/// ctx.set_file_name("notes.txt");
+/// let mut out = String::new();
+///
+/// for (index, line) in ["alpha", "béta"].into_iter().enumerate() {
+///     ctx.set_line_no(index + 1);
+///     template.format_into(line, &mut ctx, &mut out).unwrap();
+/// }
+/// assert_eq!(out, "notes.txt:2: béta (4)");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Placeholder {
+    /// The input as given, as in `{input}`.
+    Input,
+    /// Number of characters in the input, counted like `len`, as in
+    /// `{input_len}`.
+    InputLen,
+    /// Line number set with [`FormatContext::set_line_no`], as in `{line_no}`.
+    LineNo,
+    /// File name set with [`FormatContext::set_file_name`], as in `{file_name}`.
+    FileName,
+}
+
+impl Placeholder {
+    /// The name written in templates, like `input_len`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Placeholder::Input => "input",
+            Placeholder::InputLen => "input_len",
+            Placeholder::LineNo => "line_no",
+            Placeholder::FileName => "file_name",
+        }
+    }
+
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        [
+            Placeholder::Input,
+            Placeholder::InputLen,
+            Placeholder::LineNo,
+            Placeholder::FileName,
+        ]
+        .into_iter()
+        .find(|placeholder| placeholder.name() == name)
     }
 }
 
@@ -259,6 +570,12 @@ pub struct SectionInfo {
     pub content: Option<String>,
     /// Operations for template sections (None for literal sections).
     pub operations: Option<Vec<StringOp>>,
+    /// Input selected with `input=` (None for literal sections and sections without one).
+    pub input: Option<InputRef>,
+    /// Variable stored with `set:` or read with `get:` (None for literal sections and sections without one).
+    pub variable: Option<Variable>,
+    /// Value read instead of the input, like `{input_len}` (None for literal sections and sections without one).
+    pub placeholder: Option<Placeholder>,
 }
 
 /// Rich output for a single template section.
@@ -372,22 +689,180 @@ impl RichFormatResult {
             .get(index)
             .map(|output| output.as_str(&self.rendered))
     }
+
+    /// Split the rendered output into literal text and template section
+    /// outputs, in order.
+    ///
+    /// See [`Template::format_sections`].
+    pub fn section_outputs(&self) -> Vec<SectionOutput> {
+        let literal = |text: &str| SectionOutput {
+            section_type: SectionType::Literal,
+            template_position: None,
+            text: text.to_string(),
+        };
+        let mut outputs = Vec::with_capacity(self.template_outputs.len() * 2 + 1);
+        let mut at = 0;
+        for output in &self.template_outputs {
+            let range = output.rendered_range();
+            if range.start > at {
+                outputs.push(literal(&self.rendered[at..range.start]));
+            }
+            outputs.push(SectionOutput {
+                section_type: SectionType::Template,
+                template_position: Some(output.template_position),
+                text: self.rendered[range.clone()].to_string(),
+            });
+            at = at.max(range.end);
+        }
+        if at < self.rendered.len() {
+            outputs.push(literal(&self.rendered[at..]));
+        }
+        outputs
+    }
+}
+
+/// One piece of formatted output, as returned by
+/// [`Template::format_sections`].
+///
+/// Concatenating the text of all pieces gives the output of
+/// [`Template::format`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SectionOutput {
+    /// Whether the text is literal template text or the output of a
+    /// template section.
+    pub section_type: SectionType,
+    /// Position among template sections, for template section outputs.
+    pub template_position: Option<usize>,
+    /// The text as it appears in the output.
+    pub text: String,
+}
+
+impl SectionOutput {
+    /// Returns `true` if the text is the output of a template section.
+    pub fn is_template(&self) -> bool {
+        self.section_type == SectionType::Template
+    }
 }
 
 /* ---------- per-format call cache (operation results only) -------------- */
 
+/// Reusable state for [`Template::format_into`].
+///
+/// Keeps the results of repeated template sections between calls, so that
+/// formatting the same input again skips recomputing them. The cache is keyed
+/// by input and operations, which makes a context safe to share between
+/// templates, and is cleared once it holds 1024 results.
+///
+/// A context also carries the line number and file name read by the
+/// `{line_no}` and `{file_name}` placeholders, for callers formatting a file
+/// line by line, and the regex and split caches of templates using
+/// [`CacheScope::Context`].
+///
+/// # Examples
+///
+/// ```rust
+/// use string_pipeline::{FormatContext, Template};
+///
+/// let template = Template::parse("{upper} {upper}").unwrap();
+/// let mut ctx = FormatContext::new();
+/// let mut out = String::new();
+///
+/// for input in ["a", "b", "a"] {
+///     template.format_into(input, &mut ctx, &mut out).unwrap();
+///     assert_eq!(out, format!("{0} {0}", input.to_uppercase()));
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FormatContext {
+    operations: HashMap<CacheKey, String>,
+    caches: LocalCaches,
+    line_no: Option<usize>,
+    file_name: Option<String>,
+    /// Output buffer reused by [`Template::format_to`].
+    scratch: String,
+}
+
+impl FormatContext {
+    const MAX_ENTRIES: usize = 1024;
+
+    /// Create an empty context.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drop all cached section results, regexes and split results, keeping
+    /// the allocated memory.
+    ///
+    /// The line number and file name are kept.
+    pub fn clear(&mut self) {
+        self.operations.clear();
+        self.caches.clear();
+    }
+
+    /// Set the line number read by `{line_no}`.
+    pub fn set_line_no(&mut self, line_no: usize) {
+        self.line_no = Some(line_no);
+    }
+
+    /// Set the file name read by `{file_name}`.
+    pub fn set_file_name(&mut self, file_name: &str) {
+        self.file_name = Some(file_name.to_string());
+    }
+}
+
+/// Values read by placeholder sections during one format call.
+#[derive(Default)]
+struct PlaceholderValues<'a> {
+    /// The input, unless formatting structured inputs.
+    input: Option<&'a str>,
+    line_no: Option<usize>,
+    file_name: Option<&'a str>,
+}
+
+impl PlaceholderValues<'_> {
+    fn resolve(&self, placeholder: Placeholder) -> Result<Cow<'_, str>, String> {
+        let missing = || match placeholder {
+            Placeholder::Input | Placeholder::InputLen => format!(
+                "`{{{}}}` is not available in structured formatting",
+                placeholder.name()
+            ),
+            Placeholder::LineNo => {
+                "`{line_no}` needs a line number; set one with FormatContext::set_line_no"
+                    .to_string()
+            }
+            Placeholder::FileName => {
+                "`{file_name}` needs a file name; set one with FormatContext::set_file_name"
+                    .to_string()
+            }
+        };
+        match placeholder {
+            Placeholder::Input => self.input.map(Cow::Borrowed),
+            Placeholder::InputLen => self
+                .input
+                .map(|input| Cow::Owned(grapheme_len(input).to_string())),
+            Placeholder::LineNo => self.line_no.map(|line_no| Cow::Owned(line_no.to_string())),
+            Placeholder::FileName => self.file_name.map(Cow::Borrowed),
+        }
+        .ok_or_else(missing)
+    }
+}
+
 /// Per-template-instance cache for operation results.
 ///
 /// Caches the results of template section execution to avoid recomputing
 /// identical operations on the same input data within a single format call.
+/// Also carries the resource limits of that call, if any.
 struct TemplateCache {
     operations: HashMap<CacheKey, String>,
+    guard: Option<ExecGuard>,
 }
 
 impl TemplateCache {
-    fn new() -> Self {
+    fn new(limits: Option<&Limits>) -> Self {
         Self {
             operations: HashMap::new(),
+            guard: limits.map(ExecGuard::start),
         }
     }
 }
@@ -411,6 +886,8 @@ struct CacheKey {
 struct RenderBuffer {
     rendered: String,
     template_outputs: Option<Vec<TemplateOutput>>,
+    /// Whether newlines are written as `\r\n`.
+    crlf: bool,
 }
 
 impl RenderBuffer {
@@ -418,11 +895,20 @@ impl RenderBuffer {
         Self {
             rendered: String::with_capacity(rendered_capacity),
             template_outputs: rich_capacity.map(Vec::with_capacity),
+            crlf: false,
+        }
+    }
+
+    fn push_str(&mut self, text: &str) {
+        if self.crlf {
+            push_crlf(&mut self.rendered, text);
+        } else {
+            self.rendered.push_str(text);
         }
     }
 
     fn push_literal(&mut self, text: &str) {
-        self.rendered.push_str(text);
+        self.push_str(text);
     }
 
     fn push_template_output(
@@ -432,7 +918,7 @@ impl RenderBuffer {
         output: String,
     ) {
         let start = self.rendered.len();
-        self.rendered.push_str(&output);
+        self.push_str(&output);
         let end = self.rendered.len();
 
         if let Some(template_outputs) = &mut self.template_outputs {
@@ -444,6 +930,25 @@ impl RenderBuffer {
         }
     }
 
+    /// Returns the current end of the buffer, for [`RenderBuffer::rewind`].
+    fn mark(&self) -> (usize, usize) {
+        (
+            self.rendered.len(),
+            self.template_outputs.as_ref().map_or(0, Vec::len),
+        )
+    }
+
+    /// Removes everything written since `mark`. Template outputs written
+    /// since then are kept as empty ranges at the new end.
+    fn rewind(&mut self, (len, outputs): (usize, usize)) {
+        self.rendered.truncate(len);
+        if let Some(template_outputs) = &mut self.template_outputs {
+            for output in &mut template_outputs[outputs..] {
+                output.rendered_range = len..len;
+            }
+        }
+    }
+
     fn into_rendered(self) -> String {
         self.rendered
     }
@@ -461,13 +966,40 @@ impl RenderBuffer {
 /* ------------------------------------------------------------------------ */
 
 impl Template {
-    fn new(raw: String, sections: Vec<TemplateSection>, debug: bool) -> Self {
+    /// Builds a tracer honoring this template's debug settings and sink.
+    fn debug_tracer(&self) -> DebugTracer {
+        DebugTracer::new(true)
+            .with_format(self.debug_format)
+            .with_level(self.debug_level)
+            .with_preview_chars(self.debug_preview_chars)
+            .with_sink(self.debug_sink.clone())
+    }
+
+    pub(crate) fn new(raw: String, sections: Vec<TemplateSection>, debug: bool) -> Self {
         let compiled_sections = Self::compile_sections(&sections);
+        let fast_path = Self::compile_fast_path(&sections);
         Self {
             raw,
             sections,
             compiled_sections,
+            groups: Vec::new(),
             debug,
+            debug_format: DebugFormat::default(),
+            debug_level: DebugLevel::default(),
+            debug_preview_chars: DEFAULT_PREVIEW_CHARS,
+            debug_sink: None,
+            profiler: None,
+            limits: None,
+            fs_access: false,
+            exec_access: false,
+            line_endings: LineEndings::Keep,
+            output_separator: None,
+            cache_scope: CacheScope::Global,
+            color: true,
+            error_policy: ErrorPolicy::FailFast,
+            strict_ranges: false,
+            memo: None,
+            fast_path,
         }
     }
 
@@ -506,15 +1038,183 @@ impl Template {
     /// let template = Template::parse("{split:,:..|sort|join: - }").unwrap();
     /// ```
     pub fn parse(template: &str) -> Result<Self, String> {
+        let parsed = Self::parse_unchecked(template)?;
+        parsed.check_types()?;
+        Ok(parsed)
+    }
+
+    /// Parse a template string without static type checking.
+    ///
+    /// Behaves like [`Template::parse`] but skips the parse-time pass that
+    /// rejects impossible operation chains such as `{upper|sort}`. Type
+    /// mismatches are then only reported when the template is formatted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// assert!(Template::parse("{upper|sort}").is_err());
+    ///
+    /// let template = Template::parse_unchecked("{upper|sort}").unwrap();
+    /// assert!(template.format("hello").is_err());
+    /// ```
+    pub fn parse_unchecked(template: &str) -> Result<Self, String> {
+        Self::parse_unchecked_with_config(template, &ParserConfig::default())
+            .map(|(parsed, _)| parsed)
+    }
+
+    /// Create a template with a single section applying `ops`.
+    ///
+    /// The operations are checked as [`Template::parse`] would check the
+    /// equivalent template string, which becomes the
+    /// [`template_string`](Template::template_string).
+    ///
+    /// # Errors
+    ///
+    /// Returns the parse error of the equivalent template string, such as
+    /// an empty `partition` separator, or a type error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::{Pipeline, Range, Template};
+    ///
+    /// let ops = Pipeline::new().split(",", Range::all()).join("-").into_ops();
+    /// let template = Template::from_ops(ops).unwrap();
+    /// assert_eq!(template, Template::parse("{split:,:..|join:-}").unwrap());
+    /// ```
+    pub fn from_ops(ops: Vec<StringOp>) -> Result<Self, String> {
+        Self::from_sections(vec![Section::Template {
+            ops,
+            input: None,
+            variable: None,
+            placeholder: None,
+        }])
+    }
+
+    /// Create a template from literal text and operation sections.
+    ///
+    /// Like [`Template::from_ops`] for templates with several sections.
+    /// Adjacent literal sections are merged, as they are when parsing.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::{Pipeline, Section, Template};
+    ///
+    /// let template = Template::from_sections(vec![
+    ///     Section::Literal("<".to_string()),
+    ///     Section::Template {
+    ///         ops: Pipeline::new().upper().into_ops(),
+    ///         input: None,
+    ///         variable: None,
+    ///         placeholder: None,
+    ///     },
+    ///     Section::Literal(">".to_string()),
+    /// ])
+    /// .unwrap();
+    /// assert_eq!(template.template_string(), "<{upper}>");
+    /// assert_eq!(template.format("a").unwrap(), "<A>");
+    /// ```
+    pub fn from_sections(sections: Vec<Section>) -> Result<Self, String> {
+        builder::build_template(sections)
+    }
+
+    /// Parse a template string with custom parser options.
+    ///
+    /// Behaves like [`Template::parse_with_debug`] without an override, so
+    /// debug markers enable debug mode, but rejects templates that are
+    /// longer, nest braces deeper or contain more operations than `config`
+    /// allows. The bounds are checked while parsing, so oversized templates
+    /// fail early with an error starting with `Limit exceeded:`.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - The template string to parse
+    /// * `config` - The parser options to apply
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::{ParserConfig, Template};
+    ///
+    /// let config = ParserConfig {
+    ///     max_template_len: 16,
+    ///     ..ParserConfig::default()
+    /// };
+    ///
+    /// assert!(Template::parse_with_config("{upper}", &config).is_ok());
+    /// assert!(Template::parse_with_config("{split:,:..|map:{upper}}", &config).is_err());
+    ///
+    /// // Verbose syntax ignores whitespace and comments
+    /// let config = ParserConfig {
+    ///     verbose_syntax: true,
+    ///     ..ParserConfig::default()
+    /// };
+    /// let template = Template::parse_with_config("{ split:,:..  # items\n | join:- }", &config).unwrap();
+    /// assert_eq!(template.format("a,b").unwrap(), "a-b");
+    /// ```
+    pub fn parse_with_config(template: &str, config: &ParserConfig) -> Result<Self, String> {
+        let (mut parsed, markers) = Self::parse_unchecked_with_config(template, config)?;
+        parsed.debug = markers;
+        parsed.check_types()?;
+        Ok(parsed)
+    }
+
+    /// Parse a template whose sections use custom delimiters instead of braces.
+    ///
+    /// Braces outside of sections are literal text, which suits templates
+    /// that generate code. Operations inside a section still use braces for
+    /// `map` and the other sub-pipelines. Debug markers enable debug mode as
+    /// with [`Template::parse_with_config`], which accepts the delimiters as
+    /// [`ParserConfig::delimiters`] alongside other parser options.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - The template string to parse
+    /// * `open` - The delimiter that starts a section
+    /// * `close` - The delimiter that ends a section
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template =
+    ///     Template::parse_with_delimiters("fn <<lower>>() { <<split:,:..|map:{upper}|join:;>> }", "<<", ">>")
+    ///         .unwrap();
+    /// assert_eq!(template.format("A,b").unwrap(), "fn a,b() { A;B }");
+    /// ```
+    pub fn parse_with_delimiters(template: &str, open: &str, close: &str) -> Result<Self, String> {
+        let config = ParserConfig {
+            delimiters: Some((open.to_string(), close.to_string())),
+            ..ParserConfig::default()
+        };
+        Self::parse_with_config(template, &config)
+    }
+
+    /// Parses without type checking, returning the template and whether any
+    /// section carries a debug marker.
+    fn parse_unchecked_with_config(
+        template: &str,
+        config: &ParserConfig,
+    ) -> Result<(Self, bool), String> {
         // Fast-path: if the input is a *single* template block (no outer-level
         // literal text) we can skip the mixed-section scanner and directly
         // parse the operation list.
-        if let Some(single) = Self::try_single_block(template)? {
-            return Ok(single);
+        if let Some(single) = Self::try_single_block(template, config)? {
+            single.check_variables()?;
+            let debug = single.debug;
+            return Ok((single, debug));
         }
 
-        let (sections, _) = parser::parse_template_sections(template)?;
-        Ok(Self::new(template.to_string(), sections, false))
+        let (sections, inner_dbg) = parser::parse_template_sections(template, config)?;
+        let (sections, groups) = parser::extract_groups(sections)?;
+        let mut parsed = Self::new(template.to_string(), sections, false);
+        parsed.groups = groups;
+        parsed.check_variables()?;
+        Ok((parsed, inner_dbg))
     }
 
     /// Parse a template string into a `Template` instance.
@@ -557,20 +1257,60 @@ impl Template {
     /// let template = Template::parse_with_debug("{upper}", Some(true)).unwrap();
     /// ```
     pub fn parse_with_debug(template: &str, debug: Option<bool>) -> Result<Self, String> {
-        // Re-use the single-block shortcut when applicable.
-        if let Some(mut single) = Self::try_single_block(template)? {
-            if let Some(dbg_override) = debug {
-                single.debug = dbg_override;
-            }
-            return Ok(single);
-        }
+        let (mut parsed, markers) =
+            Self::parse_unchecked_with_config(template, &ParserConfig::default())?;
+        parsed.debug = debug.unwrap_or(markers);
+        parsed.check_types()?;
+        Ok(parsed)
+    }
 
-        let (sections, inner_dbg) = parser::parse_template_sections(template)?;
-        Ok(Self::new(
-            template.to_string(),
-            sections,
-            debug.unwrap_or(inner_dbg),
-        ))
+    /// Parse a template written by an untrusted user.
+    ///
+    /// Parses and type checks like [`Template::parse`], then rejects
+    /// templates that exceed the static caps of `profile`: too many
+    /// operations, `map` nested too deeply, `pad` widths beyond the output
+    /// limit, and regular expressions that compile too large. The returned
+    /// template enforces the remaining caps through
+    /// [`SafetyProfile::limits`] on every format call.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - The template string to parse
+    /// * `profile` - The restrictions to apply
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Self)` - A template that enforces the profile's limits
+    /// * `Err(String)` - Parse error, type error, or an error starting with
+    ///   `Limit exceeded:`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::{SafetyProfile, Template};
+    ///
+    /// let profile = SafetyProfile {
+    ///     max_map_depth: 0,
+    ///     max_split_items: 3,
+    ///     ..SafetyProfile::default()
+    /// };
+    ///
+    /// // Rejected when parsing
+    /// let err = Template::parse_untrusted("{split:,:..|map:{upper}|join:,}", &profile).unwrap_err();
+    /// assert!(err.starts_with("Limit exceeded:"));
+    ///
+    /// // Rejected when formatting
+    /// let template = Template::parse_untrusted("{split:,:..|sort|join:,}", &profile).unwrap();
+    /// assert_eq!(template.format("c,a,b").unwrap(), "a,b,c");
+    /// assert!(template.format("d,c,a,b").unwrap_err().starts_with("Limit exceeded:"));
+    /// ```
+    pub fn parse_untrusted(template: &str, profile: &SafetyProfile) -> Result<Self, String> {
+        let parsed = Self::parse(template)?;
+        profile.check(parsed.sections.iter().filter_map(|section| match section {
+            TemplateSection::Template { ops, .. } => Some(ops.as_slice()),
+            TemplateSection::Literal(_) => None,
+        }))?;
+        Ok(parsed.with_limits(profile.limits()))
     }
 
     /* -------- formatting ------------------------------------------------- */
@@ -619,43 +1359,332 @@ impl Template {
     /// assert_eq!(result, "Items: apple | banana | cherry");
     /// ```
     pub fn format(&self, input: &str) -> Result<String, String> {
-        self.render_single_input(input, false)
-            .map(RenderBuffer::into_rendered)
+        let memo = self.active_memo();
+        if let Some(output) = memo.and_then(|memo| memo.get(input)) {
+            return Ok(output);
+        }
+        let output = match self.active_fast_path() {
+            Some(fast_path) => {
+                let mut output = String::new();
+                self.render_fast_path(fast_path, input, &mut output)?;
+                output
+            }
+            None => {
+                let buffer = RenderBuffer::new(self.estimate_output_capacity(input), None);
+                self.render_single_input(
+                    input,
+                    buffer,
+                    &mut HashMap::new(),
+                    None,
+                    PlaceholderValues::default(),
+                )?
+                .into_rendered()
+            }
+        };
+        if let Some(memo) = memo {
+            memo.insert(input, &output);
+        }
+        Ok(output)
     }
 
-    /// Apply the template to input data, returning both the final string and
-    /// each rendered template section result.
-    ///
-    /// `rendered` is identical to the output of [`Template::format`], while
-    /// `template_outputs` captures the exact text inserted for each template
-    /// section in left-to-right order.
-    ///
-    /// The returned section metadata points into the final rendered string, so
-    /// the rich path avoids storing duplicate owned strings for each section.
+    /// Create a reusable context for [`Template::format_into`].
     ///
     /// # Examples
     ///
     /// ```rust
     /// use string_pipeline::Template;
     ///
-    /// let template = Template::parse("asd {upper} bsd {lower}").unwrap();
-    /// let result = template.format_rich("MiXeD").unwrap();
+    /// let template = Template::parse("{split:,:0}-{split:,:0}").unwrap();
+    /// let mut ctx = template.new_context();
+    /// let mut out = String::new();
     ///
-    /// assert_eq!(result.rendered(), "asd MIXED bsd mixed");
-    /// assert_eq!(result.template_output(0), Some("MIXED"));
-    /// assert_eq!(result.template_output(1), Some("mixed"));
+    /// template.format_into("a,b", &mut ctx, &mut out).unwrap();
+    /// assert_eq!(out, "a-a");
     /// ```
-    pub fn format_rich(&self, input: &str) -> Result<RichFormatResult, String> {
-        self.render_single_input(input, true)
-            .map(RenderBuffer::into_rich)
+    pub fn new_context(&self) -> FormatContext {
+        FormatContext::new()
     }
 
-    /* -------- public helpers ------------------------------------------- */
-
-    /// Get the original template string.
+    /// Apply the template to input data, writing the result into `out`.
     ///
-    /// Returns the raw template string that was used to create this instance,
-    /// useful for debugging, serialization, or displaying template definitions.
+    /// Produces the same result as [`Template::format`], but reuses the
+    /// allocation of `out` and the section results cached in `ctx`, which is
+    /// meant for callers formatting many inputs in a loop. The previous
+    /// contents of `out` are replaced. On error, `out` is left empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:/:-1|upper}").unwrap();
+    /// let mut ctx = template.new_context();
+    /// let mut out = String::new();
+    ///
+    /// for (path, expected) in [("a/b.txt", "B.TXT"), ("c/d.rs", "D.RS")] {
+    ///     template.format_into(path, &mut ctx, &mut out).unwrap();
+    ///     assert_eq!(out, expected);
+    /// }
+    /// ```
+    pub fn format_into(
+        &self,
+        input: &str,
+        ctx: &mut FormatContext,
+        out: &mut String,
+    ) -> Result<(), String> {
+        let memo = self.active_memo();
+        if let Some(output) = memo.and_then(|memo| memo.get(input)) {
+            *out = output;
+            return Ok(());
+        }
+        if let Some(fast_path) = self.active_fast_path() {
+            self.render_fast_path(fast_path, input, out)?;
+            if let Some(memo) = memo {
+                memo.insert(input, out);
+            }
+            return Ok(());
+        }
+        let mut rendered = std::mem::take(out);
+        rendered.clear();
+        rendered.reserve(self.estimate_output_capacity(input));
+        let buffer = RenderBuffer {
+            rendered,
+            template_outputs: None,
+            crlf: false,
+        };
+
+        if ctx.operations.len() >= FormatContext::MAX_ENTRIES {
+            ctx.operations.clear();
+        }
+        if ctx.caches.split.len() >= FormatContext::MAX_ENTRIES {
+            ctx.caches.split.clear();
+        }
+        *out = self
+            .render_single_input(
+                input,
+                buffer,
+                &mut ctx.operations,
+                Some(&mut ctx.caches),
+                PlaceholderValues {
+                    input: None,
+                    line_no: ctx.line_no,
+                    file_name: ctx.file_name.as_deref(),
+                },
+            )?
+            .into_rendered();
+        if let Some(memo) = memo {
+            memo.insert(input, out);
+        }
+        Ok(())
+    }
+
+    /// Apply the template to input data, appending the result to `out`.
+    ///
+    /// Works like [`Template::format_into`], but writes to any
+    /// [`fmt::Write`](std::fmt::Write) and keeps what `out` already holds,
+    /// which suits building one large output from many inputs. The result is
+    /// rendered into a buffer kept in `ctx`, so repeated calls do not
+    /// allocate once the buffer has grown. On error, nothing is written.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("- {split:/:-1}\n").unwrap();
+    /// let mut ctx = template.new_context();
+    /// let mut out = String::from("Files:\n");
+    ///
+    /// for path in ["src/main.rs", "src/lib.rs"] {
+    ///     template.format_to(path, &mut ctx, &mut out).unwrap();
+    /// }
+    /// assert_eq!(out, "Files:\n- main.rs\n- lib.rs\n");
+    /// ```
+    pub fn format_to<W: std::fmt::Write>(
+        &self,
+        input: &str,
+        ctx: &mut FormatContext,
+        out: &mut W,
+    ) -> Result<(), String> {
+        let mut scratch = std::mem::take(&mut ctx.scratch);
+        let result = self.format_into(input, ctx, &mut scratch).and_then(|()| {
+            out.write_str(&scratch)
+                .map_err(|_| "Failed to write the formatted output".to_string())
+        });
+        ctx.scratch = scratch;
+        result
+    }
+
+    /// Apply the template to input data, returning the output split into
+    /// literal text and template section outputs.
+    ///
+    /// Each template section gives one element, even when its output is
+    /// empty. Literal text between two template sections gives one element,
+    /// and none when it is empty or was dropped with its conditional group.
+    /// This lets callers style the transformed parts differently from the
+    /// literal text, as [`Template::format_rich`] does with byte ranges.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::{SectionType, Template};
+    ///
+    /// let template = Template::parse("[{split: :0}] {split: :1|upper}").unwrap();
+    /// let sections = template.format_sections("info started").unwrap();
+    ///
+    /// let styled: String = sections
+    ///     .iter()
+    ///     .map(|section| match section.section_type {
+    ///         SectionType::Template => format!("<b>{}</b>", section.text),
+    ///         SectionType::Literal => section.text.clone(),
+    ///     })
+    ///     .collect();
+    /// assert_eq!(styled, "[<b>info</b>] <b>STARTED</b>");
+    /// assert_eq!(sections[1].template_position, Some(0));
+    /// ```
+    pub fn format_sections(&self, input: &str) -> Result<Vec<SectionOutput>, String> {
+        self.format_rich(input)
+            .map(|result| result.section_outputs())
+    }
+
+    /// Apply the template to input data, returning both the final string and
+    /// each rendered template section result.
+    ///
+    /// `rendered` is identical to the output of [`Template::format`], while
+    /// `template_outputs` captures the exact text inserted for each template
+    /// section in left-to-right order.
+    ///
+    /// The returned section metadata points into the final rendered string, so
+    /// the rich path avoids storing duplicate owned strings for each section.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("asd {upper} bsd {lower}").unwrap();
+    /// let result = template.format_rich("MiXeD").unwrap();
+    ///
+    /// assert_eq!(result.rendered(), "asd MIXED bsd mixed");
+    /// assert_eq!(result.template_output(0), Some("MIXED"));
+    /// assert_eq!(result.template_output(1), Some("mixed"));
+    /// ```
+    pub fn format_rich(&self, input: &str) -> Result<RichFormatResult, String> {
+        let buffer = RenderBuffer::new(
+            self.estimate_output_capacity(input),
+            Some(self.template_section_count()),
+        );
+        self.render_single_input(
+            input,
+            buffer,
+            &mut HashMap::new(),
+            None,
+            PlaceholderValues::default(),
+        )
+        .map(RenderBuffer::into_rich)
+    }
+
+    /// Apply the template to input that may not be valid UTF-8.
+    ///
+    /// Invalid byte sequences are replaced with `U+FFFD` before formatting,
+    /// so binary junk in the input never makes formatting fail.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:/:-1}").unwrap();
+    /// assert_eq!(template.format_lossy(b"dir/file\xff").unwrap(), "file\u{FFFD}");
+    /// ```
+    pub fn format_lossy(&self, input: &[u8]) -> Result<String, String> {
+        match String::from_utf8_lossy(input) {
+            Cow::Borrowed(input) => self.format(input),
+            Cow::Owned(input) => with_lossy_input(true, || self.format(&input)),
+        }
+    }
+
+    /// Apply the template to input that may not be valid UTF-8, writing the
+    /// result into `out`.
+    ///
+    /// Replaces invalid byte sequences like [`Template::format_lossy`] and
+    /// reuses `ctx` and `out` like [`Template::format_into`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{filter_valid_utf8}").unwrap();
+    /// let mut ctx = template.new_context();
+    /// let mut out = String::new();
+    ///
+    /// template.format_lossy_into(b"ok \xef\xbf\xbd", &mut ctx, &mut out).unwrap();
+    /// assert_eq!(out, "ok \u{FFFD}");
+    /// template.format_lossy_into(b"bad \xff", &mut ctx, &mut out).unwrap();
+    /// assert_eq!(out, "");
+    /// ```
+    pub fn format_lossy_into(
+        &self,
+        input: &[u8],
+        ctx: &mut FormatContext,
+        out: &mut String,
+    ) -> Result<(), String> {
+        match String::from_utf8_lossy(input) {
+            Cow::Borrowed(input) => self.format_into(input, ctx, out),
+            Cow::Owned(input) => with_lossy_input(true, || self.format_into(&input, ctx, out)),
+        }
+    }
+
+    /// Apply the template to raw bytes, returning raw bytes.
+    ///
+    /// Valid UTF-8 input is formatted like [`Template::format`]. Invalid input
+    /// is formatted like [`Template::format_lossy`], except for templates whose
+    /// sections only strip ANSI codes (such as `{strip_ansi}`): those remove
+    /// escape sequences at the byte level and pass every other byte through
+    /// unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{strip_ansi}").unwrap();
+    /// let output = template.format_bytes(b"\x1b[31mred\x1b[0m \xff").unwrap();
+    /// assert_eq!(output, b"red \xff");
+    /// ```
+    pub fn format_bytes(&self, input: &[u8]) -> Result<Vec<u8>, String> {
+        if let Ok(input) = std::str::from_utf8(input) {
+            return self.format(input).map(String::into_bytes);
+        }
+        if !self.strips_ansi_only() {
+            return self.format_lossy(input).map(String::into_bytes);
+        }
+
+        let stripped = if has_ansi_controls(input) {
+            strip_ansi_bytes(input)
+        } else {
+            Cow::Borrowed(input)
+        };
+        let mut output = Vec::with_capacity(self.raw.len() + stripped.len());
+        for section in &self.sections {
+            match section {
+                TemplateSection::Literal(text) => output.extend_from_slice(text.as_bytes()),
+                TemplateSection::Template { ops, .. } if ops.is_empty() => {
+                    output.extend_from_slice(input)
+                }
+                TemplateSection::Template { .. } => output.extend_from_slice(&stripped),
+            }
+        }
+        Ok(output)
+    }
+
+    /* -------- public helpers ------------------------------------------- */
+
+    /// Get the original template string.
+    ///
+    /// Returns the raw template string that was used to create this instance,
+    /// useful for debugging, serialization, or displaying template definitions.
     ///
     /// # Examples
     ///
@@ -669,6 +1698,58 @@ impl Template {
         &self.raw
     }
 
+    /// Get the template in canonical syntax.
+    ///
+    /// Templates that parse to the same sections render to the same string:
+    /// shorthands such as `{1}` are written out as `{split: :1}`, aliases
+    /// such as `quote` use their operation's own keyword, default arguments
+    /// are left out and argument values are escaped in one consistent way.
+    /// Debug markers are dropped. Parsing the result yields a template equal
+    /// to this one.
+    ///
+    /// Sections are always written with braces, even for templates parsed
+    /// with custom delimiters, whose literal text may then need escaping
+    /// by hand.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{!1|trim:both|quote:\"}").unwrap();
+    /// assert_eq!(template.canonical_string(), r#"{split: :1|trim|surround:\"}"#);
+    /// ```
+    pub fn canonical_string(&self) -> String {
+        let mut out = String::with_capacity(self.raw.len());
+        for (position, section) in self.sections.iter().enumerate() {
+            if self.groups.iter().any(|group| group.start == position) {
+                out.push_str("[?");
+            }
+            match section {
+                TemplateSection::Literal(text) => out.push_str(&text.replace("[?", "\\[?")),
+                TemplateSection::Template {
+                    ops,
+                    input,
+                    variable,
+                    placeholder,
+                    ..
+                } => {
+                    canonical::write_section(
+                        &mut out,
+                        ops,
+                        input.as_ref(),
+                        variable.as_ref(),
+                        *placeholder,
+                    );
+                }
+            }
+            if self.groups.iter().any(|group| group.end == position + 1) {
+                out.push(']');
+            }
+        }
+        out
+    }
+
     /// Get the total number of sections in the template.
     ///
     /// Returns the count of all sections (both literal and template sections)
@@ -706,6 +1787,81 @@ impl Template {
             .count()
     }
 
+    /// Get the number of operations in the template.
+    ///
+    /// Counts the operations of every template section, including those
+    /// nested in `map` and the other sub-pipelines, the same way
+    /// [`SafetyProfile::max_operations`](crate::SafetyProfile::max_operations)
+    /// does.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:,:..|map:{trim|upper}|join:-} {lower}").unwrap();
+    /// assert_eq!(template.operation_count(), 6);
+    /// ```
+    pub fn operation_count(&self) -> usize {
+        self.template_ops()
+            .flatten()
+            .map(StringOp::operation_count)
+            .sum()
+    }
+
+    /// Get how deeply `map`, `map_fallible` and `each` nest in the template.
+    ///
+    /// Returns `0` for a template without them. This is the depth checked
+    /// against [`SafetyProfile::max_map_depth`](crate::SafetyProfile::max_map_depth).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{each:{split:,:..}:{split: :..|map:{upper}}}").unwrap();
+    /// assert_eq!(template.max_map_depth(), 2);
+    ///
+    /// let template = Template::parse("{upper}").unwrap();
+    /// assert_eq!(template.max_map_depth(), 0);
+    /// ```
+    pub fn max_map_depth(&self) -> usize {
+        self.template_ops()
+            .flatten()
+            .map(StringOp::map_depth)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Check if any operation of the template matches a regular expression.
+    ///
+    /// These are `filter`, `filter_not`, `filter_by` with a pattern,
+    /// `replace`, `trim_regex`, `highlight` and `regex_extract`, wherever
+    /// they are nested.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:,:..|map:{replace:s/a/b/}}").unwrap();
+    /// assert!(template.uses_regex());
+    ///
+    /// let template = Template::parse("{split:,:..|map:{upper}}").unwrap();
+    /// assert!(!template.uses_regex());
+    /// ```
+    pub fn uses_regex(&self) -> bool {
+        self.any_op(StringOp::uses_regex)
+    }
+
+    /// The operations of each template section.
+    fn template_ops(&self) -> impl Iterator<Item = &[StringOp]> {
+        self.sections.iter().filter_map(|section| match section {
+            TemplateSection::Template { ops, .. } => Some(ops.as_slice()),
+            TemplateSection::Literal(_) => None,
+        })
+    }
+
     /// Check if debug mode is enabled.
     ///
     /// Returns `true` if this template will output debug information during
@@ -737,25 +1893,897 @@ impl Template {
         self
     }
 
-    /// Set debug mode on this template instance.
+    /// Set debug mode on this template instance.
+    ///
+    /// Modifies this template's debug setting in place.
+    ///
+    /// # Arguments
+    ///
+    /// * `debug` - Whether to enable debug mode
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let mut template = Template::parse("{upper}").unwrap();
+    /// template.set_debug(true);
+    /// assert!(template.is_debug());
+    /// ```
+    pub fn set_debug(&mut self, debug: bool) {
+        self.debug = debug;
+    }
+
+    /// Get the output format used for debug traces.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::{DebugFormat, Template};
+    ///
+    /// let template = Template::parse("{upper}").unwrap();
+    /// assert_eq!(template.debug_format(), DebugFormat::Text);
+    /// ```
+    pub fn debug_format(&self) -> DebugFormat {
+        self.debug_format
+    }
+
+    /// Create a new template instance with the given debug output format.
+    ///
+    /// The format only takes effect when debug mode is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - [`DebugFormat::Text`] for the tree view, or
+    ///   [`DebugFormat::Json`] for line-delimited JSON events
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::{DebugFormat, Template};
+    ///
+    /// let template = Template::parse("{upper}")
+    ///     .unwrap()
+    ///     .with_debug(true)
+    ///     .with_debug_format(DebugFormat::Json);
+    /// assert_eq!(template.debug_format(), DebugFormat::Json);
+    /// ```
+    pub fn with_debug_format(mut self, format: DebugFormat) -> Self {
+        self.debug_format = format;
+        self
+    }
+
+    /// Set the debug output format on this template instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - The debug output format to use
+    pub fn set_debug_format(&mut self, format: DebugFormat) {
+        self.debug_format = format;
+    }
+
+    /// Get how much of the execution debug traces report.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::{DebugLevel, Template};
+    ///
+    /// let template = Template::parse("{upper}").unwrap();
+    /// assert_eq!(template.debug_level(), DebugLevel::Full);
+    /// ```
+    pub fn debug_level(&self) -> DebugLevel {
+        self.debug_level
+    }
+
+    /// Create a new template instance with the given debug level.
+    ///
+    /// Lower levels leave out per-operation steps, `map` items, and value
+    /// previews, which keeps traces over large inputs short. The level only
+    /// takes effect when debug mode is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - How much of the execution to report
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::{DebugLevel, Template};
+    ///
+    /// let template = Template::parse("{split:,:..|map:{upper}}")
+    ///     .unwrap()
+    ///     .with_debug(true)
+    ///     .with_debug_level(DebugLevel::Summary);
+    /// assert_eq!(template.debug_level(), DebugLevel::Summary);
+    /// ```
+    pub fn with_debug_level(mut self, level: DebugLevel) -> Self {
+        self.debug_level = level;
+        self
+    }
+
+    /// Set the debug level on this template instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - How much of the execution to report
+    pub fn set_debug_level(&mut self, level: DebugLevel) {
+        self.debug_level = level;
+    }
+
+    /// Get the number of characters kept in debug value previews.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{upper}").unwrap();
+    /// assert_eq!(template.debug_preview_chars(), 40);
+    /// ```
+    pub fn debug_preview_chars(&self) -> usize {
+        self.debug_preview_chars
+    }
+
+    /// Create a new template instance that cuts debug value previews to
+    /// `chars` characters.
+    ///
+    /// Inputs, results, and list items longer than that are shown with their
+    /// first `chars` characters followed by an ellipsis. Defaults to 40.
+    ///
+    /// # Arguments
+    ///
+    /// * `chars` - Maximum number of characters kept in each preview
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{upper}")
+    ///     .unwrap()
+    ///     .with_debug(true)
+    ///     .with_debug_preview_chars(200);
+    /// assert_eq!(template.debug_preview_chars(), 200);
+    /// ```
+    pub fn with_debug_preview_chars(mut self, chars: usize) -> Self {
+        self.debug_preview_chars = chars;
+        self
+    }
+
+    /// Set the number of characters kept in debug value previews on this
+    /// template instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `chars` - Maximum number of characters kept in each preview
+    pub fn set_debug_preview_chars(&mut self, chars: usize) {
+        self.debug_preview_chars = chars;
+    }
+
+    /// Create a new template instance that sends debug output to `sink`.
+    ///
+    /// Without a sink, debug output is printed to stderr. The sink only
+    /// receives lines while debug mode is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `sink` - Callback or writer receiving each debug line
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::{DebugFormat, DebugSink, Template};
+    ///
+    /// let template = Template::parse("{split:,:..|join:-}")
+    ///     .unwrap()
+    ///     .with_debug(true)
+    ///     .with_debug_format(DebugFormat::Json)
+    ///     .with_debug_sink(DebugSink::from_writer(std::io::sink()));
+    /// assert_eq!(template.format("a,b").unwrap(), "a-b");
+    /// ```
+    pub fn with_debug_sink(mut self, sink: DebugSink) -> Self {
+        self.debug_sink = Some(sink);
+        self
+    }
+
+    /// Set or clear the debug sink on this template instance.
+    ///
+    /// Passing `None` restores printing to stderr.
+    ///
+    /// # Arguments
+    ///
+    /// * `sink` - The new sink, or `None` for stderr
+    pub fn set_debug_sink(&mut self, sink: Option<DebugSink>) {
+        self.debug_sink = sink;
+    }
+
+    /// Create a new template instance with per-operation profiling enabled or disabled.
+    ///
+    /// While enabled, every operation executed by [`format`](Self::format) and
+    /// related methods records its wall time and invocation count. Enabling
+    /// profiling starts from an empty report; clones of the template share
+    /// the same recorded data.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to record operation timings
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{upper}").unwrap().with_profiling(true);
+    /// assert!(template.is_profiling());
+    /// ```
+    pub fn with_profiling(mut self, enabled: bool) -> Self {
+        self.profiler = enabled.then(Profiler::default);
+        self
+    }
+
+    /// Check if per-operation profiling is enabled.
+    pub fn is_profiling(&self) -> bool {
+        self.profiler.is_some()
+    }
+
+    /// Get a snapshot of the operation timings recorded so far.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(ProfilingReport)` - Timings recorded since profiling was enabled or last reset
+    /// * `None` - Profiling is disabled
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{trim|upper}").unwrap().with_profiling(true);
+    /// template.format(" a ").unwrap();
+    /// template.format(" b ").unwrap();
+    ///
+    /// let report = template.profiling_report().unwrap();
+    /// assert_eq!(report.get("trim").unwrap().invocations, 2);
+    /// assert!(Template::parse("{upper}").unwrap().profiling_report().is_none());
+    /// ```
+    pub fn profiling_report(&self) -> Option<ProfilingReport> {
+        self.profiler.as_ref().map(Profiler::report)
+    }
+
+    /// Discard the operation timings recorded so far.
+    ///
+    /// Does nothing when profiling is disabled.
+    pub fn reset_profiling(&self) {
+        if let Some(profiler) = &self.profiler {
+            profiler.reset();
+        }
+    }
+
+    /// Create a new template instance that enforces resource limits.
+    ///
+    /// Intended for services that evaluate untrusted templates: regex
+    /// compilation is capped in size and every format call is bounded in
+    /// time. Exceeding a limit makes [`format`](Self::format) and related
+    /// methods fail with an error starting with `Limit exceeded:`.
+    ///
+    /// # Arguments
+    ///
+    /// * `limits` - The limits to enforce
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::{Limits, Template};
+    ///
+    /// let template = Template::parse("{replace:s/\\w{50}/x/}")
+    ///     .unwrap()
+    ///     .with_limits(Limits {
+    ///         max_regex_size: Some(1024),
+    ///         ..Limits::default()
+    ///     });
+    ///
+    /// let err = template.format("hello").unwrap_err();
+    /// assert!(err.starts_with("Limit exceeded:"));
+    /// ```
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.set_limits(Some(limits));
+        self
+    }
+
+    /// Set or clear the resource limits on this template instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `limits` - The limits to enforce, or `None` for no limits
+    pub fn set_limits(&mut self, limits: Option<Limits>) {
+        self.limits = limits;
+        // `each` bodies are formatted as templates of their own
+        for section in &mut self.sections {
+            if let TemplateSection::Template { ops, .. } = section {
+                for op in ops {
+                    if let StringOp::Each { body } = op {
+                        body.set_limits(limits);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Get the resource limits enforced by this template, if any.
+    pub fn limits(&self) -> Option<&Limits> {
+        self.limits.as_ref()
+    }
+
+    /// Create a new template instance that keeps its regex and split caches
+    /// in `scope` instead of the global caches.
+    ///
+    /// See [`CacheScope`] for the available scopes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::{CacheScope, Template};
+    ///
+    /// let template = Template::parse("{split:,:0}-{split:,:1}")
+    ///     .unwrap()
+    ///     .with_cache_scope(CacheScope::Context);
+    /// let mut ctx = template.new_context();
+    /// let mut out = String::new();
+    ///
+    /// template.format_into("a,b", &mut ctx, &mut out).unwrap();
+    /// assert_eq!(out, "a-b");
+    /// ```
+    pub fn with_cache_scope(mut self, scope: CacheScope) -> Self {
+        self.set_cache_scope(scope);
+        self
+    }
+
+    /// Set where this template instance caches regexes and split results.
+    pub fn set_cache_scope(&mut self, scope: CacheScope) {
+        self.cache_scope = scope;
+        for section in &mut self.sections {
+            if let TemplateSection::Template { ops, .. } = section {
+                for op in ops {
+                    if let StringOp::Each { body } = op {
+                        body.set_cache_scope(scope);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Get where this template caches regexes and split results.
+    pub fn cache_scope(&self) -> CacheScope {
+        self.cache_scope
+    }
+
+    /// Create a new template instance that remembers the output of up to
+    /// `capacity` inputs.
+    ///
+    /// [`format`](Self::format) and [`format_into`](Self::format_into) return
+    /// the remembered output when called again with the same input, without
+    /// running the template. When the memo is full, the least recently used
+    /// output is dropped. Errors are not remembered, and debug mode bypasses
+    /// the memo so traces are still printed. Clones of the template share the
+    /// same memo.
+    ///
+    /// A `capacity` of 0 turns the memo off. Templates whose output can
+    /// change for the same input never use a memo: those reading files,
+    /// running commands, using an unseeded `shuffle` or `sample`, using
+    /// `filter_valid_utf8`, or reading `{line_no}` or `{file_name}`.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - Maximum number of remembered outputs
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:/:-1|upper}").unwrap().with_memo(256);
+    /// for _ in 0..3 {
+    ///     assert_eq!(template.format("src/main.rs").unwrap(), "MAIN.RS");
+    /// }
+    ///
+    /// let stats = template.memo_stats().unwrap();
+    /// assert_eq!((stats.hits, stats.misses), (2, 1));
+    /// ```
+    pub fn with_memo(mut self, capacity: usize) -> Self {
+        self.set_memo(capacity);
+        self
+    }
+
+    /// Set the number of outputs this template remembers, starting from an
+    /// empty memo. See [`Template::with_memo`].
+    pub fn set_memo(&mut self, capacity: usize) {
+        self.memo = (capacity > 0 && self.is_repeatable()).then(|| Memo::new(capacity));
+    }
+
+    /// Get the statistics of this template's memo.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(MemoStats)` - Entries, hits, misses and evictions so far
+    /// * `None` - The template has no memo
+    pub fn memo_stats(&self) -> Option<MemoStats> {
+        self.memo.as_ref().map(Memo::stats)
+    }
+
+    /// Discard the remembered outputs and the memo statistics.
+    ///
+    /// Does nothing when the template has no memo.
+    pub fn clear_memo(&self) {
+        if let Some(memo) = &self.memo {
+            memo.clear();
+        }
+    }
+
+    /// Create a new template instance that may read files.
+    ///
+    /// Templates using `read_file` or a `@FILE` list in `intersect`, `diff`
+    /// or `union` fail to format unless file access is enabled. Files are
+    /// read with the permissions of the running process and capped in size
+    /// by [`Limits::max_file_size`].
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether operations may read files
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{read_file}").unwrap();
+    /// assert!(template.reads_files());
+    /// assert!(template.format("Cargo.toml").is_err());
+    ///
+    /// let template = template.with_fs_access(true);
+    /// assert!(template.format("Cargo.toml").unwrap().contains("[package]"));
+    /// ```
+    pub fn with_fs_access(mut self, enabled: bool) -> Self {
+        self.fs_access = enabled;
+        self
+    }
+
+    /// Enable or disable file access on this template instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether operations may read files
+    pub fn set_fs_access(&mut self, enabled: bool) {
+        self.fs_access = enabled;
+    }
+
+    /// Create a new template instance that may run external commands.
+    ///
+    /// Templates using `exec` fail to format unless command execution is
+    /// enabled. Commands run with the permissions of the running process,
+    /// so only enable this for trusted templates. Each command is killed
+    /// after [`Limits::exec_timeout`].
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether operations may run commands
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(unix)] {
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{exec:wc -c}").unwrap();
+    /// assert!(template.runs_commands());
+    /// assert!(template.format("hello").is_err());
+    ///
+    /// let template = template.with_exec_access(true);
+    /// assert_eq!(template.format("hello").unwrap().trim(), "5");
+    /// # }
+    /// ```
+    pub fn with_exec_access(mut self, enabled: bool) -> Self {
+        self.exec_access = enabled;
+        self
+    }
+
+    /// Enable or disable command execution on this template instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether operations may run commands
+    pub fn set_exec_access(&mut self, enabled: bool) {
+        self.exec_access = enabled;
+    }
+
+    /// Create a new template instance with terminal styling switched on or
+    /// off.
+    ///
+    /// Styling is on by default. When it is off, the `color`, `bg`, `bold`,
+    /// `underline` and `style_reset` operations return their value
+    /// unchanged, so the same template can write to a terminal and to a
+    /// file or pipe. Programs usually switch it off when the `NO_COLOR`
+    /// environment variable is set.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether styling operations write ANSI escape sequences
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{bold|color:red}: {split:\\::1}").unwrap();
+    /// assert_eq!(
+    ///     template.format("error:disk full").unwrap(),
+    ///     "\x1b[31m\x1b[1merror:disk full\x1b[22m\x1b[39m: disk full"
+    /// );
+    ///
+    /// let template = template.with_color(false);
+    /// assert_eq!(template.format("error:disk full").unwrap(), "error:disk full: disk full");
+    /// ```
+    pub fn with_color(mut self, enabled: bool) -> Self {
+        self.set_color(enabled);
+        self
+    }
+
+    /// Switch terminal styling on or off on this template instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether styling operations write ANSI escape sequences
+    pub fn set_color(&mut self, enabled: bool) {
+        if self.color != enabled {
+            self.detach_memo();
+        }
+        self.color = enabled;
+        for section in &mut self.sections {
+            if let TemplateSection::Template { ops, .. } = section {
+                for op in ops {
+                    if let StringOp::Each { body } = op {
+                        body.set_color(enabled);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Check whether styling operations write ANSI escape sequences.
+    pub fn is_color(&self) -> bool {
+        self.color
+    }
+
+    /// Create a new template instance with the given error policy.
+    ///
+    /// By default formatting stops at the first list item an operation fails
+    /// on. With [`ErrorPolicy::CollectAll`] every item is processed and the
+    /// error lists each failing item, so a batch can be fixed in one pass.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - How failures on list items are reported
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::{ErrorPolicy, Template};
+    ///
+    /// let template = Template::parse("{split:,:..|map:{ordinal}}")
+    ///     .unwrap()
+    ///     .with_error_policy(ErrorPolicy::CollectAll);
+    /// let error = template.format("1,x,3,y").unwrap_err();
+    /// assert!(error.starts_with("2 of 4 items failed:"));
+    /// assert!(error.contains("item 4 (`ordinal`): Invalid integer: 'y'"));
+    /// ```
+    pub fn with_error_policy(mut self, policy: ErrorPolicy) -> Self {
+        self.set_error_policy(policy);
+        self
+    }
+
+    /// Set the error policy on this template instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - How failures on list items are reported
+    pub fn set_error_policy(&mut self, policy: ErrorPolicy) {
+        self.error_policy = policy;
+        for section in &mut self.sections {
+            if let TemplateSection::Template { ops, .. } = section {
+                for op in ops {
+                    if let StringOp::Each { body } = op {
+                        body.set_error_policy(policy);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Get the error policy of this template.
+    pub fn error_policy(&self) -> ErrorPolicy {
+        self.error_policy
+    }
+
+    /// Create a new template instance that rejects out-of-bounds ranges.
+    ///
+    /// By default `split`, `slice` and `substring` clamp indices and ranges
+    /// that reach past the value, so `{substring:5}` gives the last
+    /// character of `hi`. With strict ranges they fail instead, which
+    /// surfaces templates written for a different input shape. Use `clamp`
+    /// where clamping a list selection is wanted.
+    ///
+    /// # Arguments
+    ///
+    /// * `strict` - Whether out-of-bounds ranges are errors
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{substring:5}").unwrap();
+    /// assert_eq!(template.format("hi").unwrap(), "i");
+    ///
+    /// let template = template.with_strict_ranges(true);
+    /// assert_eq!(template.format("hello!").unwrap(), "!");
+    /// assert!(template.format("hi").unwrap_err().starts_with("Index 5 is out of bounds for 2 characters"));
+    /// ```
+    pub fn with_strict_ranges(mut self, strict: bool) -> Self {
+        self.set_strict_ranges(strict);
+        self
+    }
+
+    /// Switch strict range checking on or off on this template instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `strict` - Whether out-of-bounds ranges are errors
+    pub fn set_strict_ranges(&mut self, strict: bool) {
+        if self.strict_ranges != strict {
+            self.detach_memo();
+        }
+        self.strict_ranges = strict;
+        for section in &mut self.sections {
+            if let TemplateSection::Template { ops, .. } = section {
+                for op in ops {
+                    if let StringOp::Each { body } = op {
+                        body.set_strict_ranges(strict);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Check whether out-of-bounds ranges are errors.
+    pub fn is_strict_ranges(&self) -> bool {
+        self.strict_ranges
+    }
+
+    /// Create a new template instance with the given line ending handling.
+    ///
+    /// See [`LineEndings`]. With [`LineEndings::Lf`] or [`LineEndings::Crlf`],
+    /// `\r\n` in every input is read as `\n`.
+    ///
+    /// # Arguments
+    ///
+    /// * `line_endings` - How to treat `\r\n` line endings
+    pub fn with_line_endings(mut self, line_endings: LineEndings) -> Self {
+        self.line_endings = line_endings;
+        self.detach_memo();
+        self
+    }
+
+    /// Get the line ending handling of this template.
+    pub fn line_endings(&self) -> LineEndings {
+        self.line_endings
+    }
+
+    /// Create a new template instance that joins lists left at the end of a
+    /// section with `sep`.
+    ///
+    /// Without this setting, such lists are joined with the separator of
+    /// the last `split` or `join`, which depends on every operation of the
+    /// section. An `output_sep` operation in the section still takes
+    /// precedence, and nested pipelines such as those of `map` are not
+    /// affected.
+    ///
+    /// # Arguments
+    ///
+    /// * `sep` - The separator for lists at the end of a section
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{split:,:..|map:{split: :..|join:-}}").unwrap();
+    /// assert_eq!(template.format("a b,c d").unwrap(), "a-b,c-d");
+    ///
+    /// let template = template.with_output_separator("\n");
+    /// assert_eq!(template.format("a b,c d").unwrap(), "a-b\nc-d");
+    /// ```
+    pub fn with_output_separator(mut self, sep: &str) -> Self {
+        self.set_output_separator(Some(sep));
+        self
+    }
+
+    /// Set or clear the separator for lists left at the end of a section.
+    ///
+    /// # Arguments
+    ///
+    /// * `sep` - The separator, or `None` to use the last `split` or `join`
+    pub fn set_output_separator(&mut self, sep: Option<&str>) {
+        self.output_separator = sep.map(str::to_string);
+        self.detach_memo();
+    }
+
+    /// Get the separator for lists left at the end of a section, if set.
+    pub fn output_separator(&self) -> Option<&str> {
+        self.output_separator.as_deref()
+    }
+
+    /// Returns `true` if any operation of this template reads files.
+    pub fn reads_files(&self) -> bool {
+        self.any_op(StringOp::reads_files)
+    }
+
+    /// Returns `true` if any operation of this template runs commands.
+    pub fn runs_commands(&self) -> bool {
+        self.any_op(StringOp::runs_commands)
+    }
+
+    /// Returns `true` if the output only depends on the input, as required
+    /// by [`Template::with_memo`].
+    pub(crate) fn is_repeatable(&self) -> bool {
+        self.sections.iter().all(|section| match section {
+            TemplateSection::Template {
+                ops, placeholder, ..
+            } => {
+                !matches!(
+                    placeholder,
+                    Some(Placeholder::LineNo | Placeholder::FileName)
+                ) && ops.iter().all(StringOp::is_repeatable)
+            }
+            TemplateSection::Literal(_) => true,
+        })
+    }
+
+    /// The memo to use for a format call, if any.
+    fn active_memo(&self) -> Option<&Memo> {
+        self.memo.as_ref().filter(|_| !self.debug)
+    }
+
+    /// Gives the template an empty memo of its own after a setting that
+    /// changes its output, as clones may still use the old one.
+    fn detach_memo(&mut self) {
+        self.memo = self.memo.as_ref().map(Memo::detached);
+    }
+
+    fn any_op(&self, pred: impl Fn(&StringOp) -> bool) -> bool {
+        self.sections.iter().any(|section| match section {
+            TemplateSection::Template { ops, .. } => ops.iter().any(&pred),
+            TemplateSection::Literal(_) => false,
+        })
+    }
+
+    /// Fails if the template reads files or runs commands without the
+    /// matching access enabled.
+    fn check_access(&self) -> Result<(), String> {
+        if !self.fs_access && self.reads_files() {
+            return Err(
+                "Template reads files, but file access is disabled; enable it with `Template::with_fs_access`"
+                    .to_string(),
+            );
+        }
+        if !self.exec_access && self.runs_commands() {
+            return Err(
+                "Template runs commands, but command execution is disabled; enable it with `Template::with_exec_access`"
+                    .to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Statically check that every template section is well-typed.
+    ///
+    /// Every section starts from a string input, so operation chains such as
+    /// `{sort}` or `{split:,:..|upper}` can never succeed. This check runs
+    /// automatically in [`Template::parse`]; call it explicitly on templates
+    /// created with [`Template::parse_unchecked`].
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - Every section is well-typed
+    /// * `Err(String)` - Description of the first offending operation and its section
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse_unchecked("A: {upper} B: {upper|sort}").unwrap();
+    /// let error = template.check_types().unwrap_err();
+    /// assert!(error.contains("template section 2"));
+    /// assert!(error.contains("operation 2 (`sort`)"));
+    /// ```
+    pub fn check_types(&self) -> Result<(), String> {
+        for (position, ops) in self.get_template_sections() {
+            typecheck::check_pipeline(ops).map_err(|e| {
+                if self.template_section_count() > 1 {
+                    format!("{e} (in template section {})", position + 1)
+                } else {
+                    e
+                }
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Checks that every `get:` section reads a variable stored by an
+    /// earlier `set:` section, and that no variable is stored twice.
+    pub(crate) fn check_variables(&self) -> Result<(), String> {
+        let mut stored = HashSet::new();
+        let variables = self.sections.iter().filter_map(|section| match section {
+            TemplateSection::Template { variable, .. } => Some(variable),
+            TemplateSection::Literal(_) => None,
+        });
+        for (position, variable) in variables.enumerate() {
+            match variable {
+                Some(Variable::Set(name)) if !stored.insert(name.as_str()) => {
+                    return Err(format!(
+                        "Variable `{name}` is set again in template section {}",
+                        position + 1
+                    ));
+                }
+                Some(Variable::Get(name)) if !stored.contains(name.as_str()) => {
+                    return Err(format!(
+                        "Variable `{name}` is read in template section {} before any section sets it",
+                        position + 1
+                    ));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Look for operations that are useless or conflict with each other.
     ///
-    /// Modifies this template's debug setting in place.
+    /// Reports patterns that format without errors but usually hint at a
+    /// mistake: operations repeated for nothing (`upper|upper`), undone
+    /// (`reverse|reverse`) or discarded by a later one (`upper|lower`,
+    /// `shuffle|sort`), no-op arguments (`append:`, `slice:..`), a `map`
+    /// that changes nothing, `join` on a string, and `filter` after `join`.
     ///
-    /// # Arguments
+    /// # Returns
     ///
-    /// * `debug` - Whether to enable debug mode
+    /// The warnings of every template section, in template order; empty if
+    /// nothing looks suspicious.
     ///
     /// # Examples
     ///
     /// ```rust
-    /// use string_pipeline::Template;
+    /// use string_pipeline::{LintKind, Template};
     ///
-    /// let mut template = Template::parse("{upper}").unwrap();
-    /// template.set_debug(true);
-    /// assert!(template.is_debug());
+    /// let template = Template::parse("{split:,:0|join:-|upper|lower}").unwrap();
+    /// let warnings = template.lint();
+    ///
+    /// assert_eq!(warnings.len(), 2);
+    /// assert_eq!(warnings[0].kind, LintKind::UselessJoin);
+    /// assert_eq!(warnings[0].operation_path, [1]);
+    /// assert_eq!(warnings[1].kind, LintKind::Overridden);
+    /// assert_eq!(
+    ///     warnings[1].to_string(),
+    ///     "template section 1, operation 3 (`upper`): the following `lower` discards the case conversion"
+    /// );
+    ///
+    /// assert!(Template::parse("{split:,:..|sort|join:,}").unwrap().lint().is_empty());
     /// ```
-    pub fn set_debug(&mut self, debug: bool) {
-        self.debug = debug;
+    pub fn lint(&self) -> Vec<LintWarning> {
+        self.get_template_sections()
+            .into_iter()
+            .flat_map(|(position, ops)| lint::lint_section(position, ops))
+            .collect()
     }
 
     /* -------- structured template processing ----------------------------- */
@@ -785,6 +2813,10 @@ impl Template {
     /// - **Excess separators**: Extra separators beyond template section count are truncated/ignored
     /// - **Insufficient separators**: Missing separators default to space " " for remaining template sections
     ///
+    /// A section written as `{input=N|...}` reads input `N` and its separator
+    /// instead of the ones at its own position; see [`InputRef`]. Referencing
+    /// an input that was not given is an error.
+    ///
     /// # Template Section Ordering
     ///
     /// Template sections are numbered from left to right, starting at 0. Literal sections
@@ -844,7 +2876,7 @@ impl Template {
         inputs: &[&[&str]],
         separators: &[&str],
     ) -> Result<String, String> {
-        self.render_structured_inputs(inputs, separators, false)
+        self.render_structured_inputs(inputs, &[], separators, false)
             .map(RenderBuffer::into_rendered)
     }
 
@@ -881,10 +2913,50 @@ impl Template {
         inputs: &[&[&str]],
         separators: &[&str],
     ) -> Result<RichFormatResult, String> {
-        self.render_structured_inputs(inputs, separators, true)
+        self.render_structured_inputs(inputs, &[], separators, true)
             .map(RenderBuffer::into_rich)
     }
 
+    /// Format template with named inputs, selected by `input=NAME` sections.
+    ///
+    /// Works like [`Template::format_with_inputs`], where the inputs are
+    /// given in order together with a name. A section written as
+    /// `{input=NAME|...}` reads the input with that name, `{input=N|...}`
+    /// reads the input at position `N`, and other sections read the input at
+    /// their own position. `separators` belong to the inputs, in the same order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a section references a name or position that is
+    /// not among `inputs`, or if template section processing fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::Template;
+    ///
+    /// let template = Template::parse("{input=users|upper} -> {input=files}").unwrap();
+    /// let result = template
+    ///     .format_with_named_inputs(
+    ///         &[("files", &["a.txt", "b.txt"]), ("users", &["ann"])],
+    ///         &[",", " "],
+    ///     )
+    ///     .unwrap();
+    /// assert_eq!(result, "ANN -> a.txt,b.txt");
+    ///
+    /// assert!(template.format_with_named_inputs(&[("users", &["ann"])], &[]).is_err());
+    /// ```
+    pub fn format_with_named_inputs(
+        &self,
+        inputs: &[(&str, &[&str])],
+        separators: &[&str],
+    ) -> Result<String, String> {
+        let names: Vec<&str> = inputs.iter().map(|(name, _)| *name).collect();
+        let values: Vec<&[&str]> = inputs.iter().map(|(_, values)| *values).collect();
+        self.render_structured_inputs(&values, &names, separators, false)
+            .map(RenderBuffer::into_rendered)
+    }
+
     /// Get information about template sections for introspection.
     ///
     /// Returns a vector of tuples containing the position and operations for each
@@ -964,15 +3036,27 @@ impl Template {
                         template_position: None,
                         content: Some(text.clone()),
                         operations: None,
+                        input: None,
+                        variable: None,
+                        placeholder: None,
                     });
                 }
-                TemplateSection::Template { ops, .. } => {
+                TemplateSection::Template {
+                    ops,
+                    input,
+                    variable,
+                    placeholder,
+                    ..
+                } => {
                     result.push(SectionInfo {
                         section_type: SectionType::Template,
                         overall_position,
                         template_position: Some(template_position),
                         content: None,
                         operations: Some(ops.clone()),
+                        input: input.clone(),
+                        variable: variable.clone(),
+                        placeholder: *placeholder,
                     });
                     template_position += 1;
                 }
@@ -986,13 +3070,49 @@ impl Template {
     /*  internal helpers                                                   */
     /* ------------------------------------------------------------------ */
 
-    fn render_single_input(&self, input: &str, collect_rich: bool) -> Result<RenderBuffer, String> {
-        use std::time::Instant;
+    /// Renders `input` into `buffer`, caching section results in `entries`.
+    ///
+    /// `caches` are the regex and split caches of the format context, used
+    /// with [`CacheScope::Context`]. `values` provide the placeholders other
+    /// than the input.
+    fn render_single_input(
+        &self,
+        input: &str,
+        buffer: RenderBuffer,
+        entries: &mut HashMap<CacheKey, String>,
+        caches: Option<&mut LocalCaches>,
+        values: PlaceholderValues,
+    ) -> Result<RenderBuffer, String> {
+        cache::with_scope(self.cache_scope, caches, || {
+            self.with_format_settings(|| {
+                self.render_single_input_scoped(input, buffer, entries, values)
+            })
+        })
+    }
 
-        let mut cache = TemplateCache::new();
+    fn render_single_input_scoped(
+        &self,
+        input: &str,
+        buffer: RenderBuffer,
+        entries: &mut HashMap<CacheKey, String>,
+        values: PlaceholderValues,
+    ) -> Result<RenderBuffer, String> {
+        self.check_access()?;
+        let input = &*self.line_endings.normalize_input(input);
+        let values = PlaceholderValues {
+            input: Some(input),
+            ..values
+        };
+        let mut cache = TemplateCache {
+            operations: std::mem::take(entries),
+            guard: self.limits.as_ref().map(ExecGuard::start),
+        };
         let mut input_hash = None;
         let start_time = self.debug.then(Instant::now);
-        let tracer = self.debug.then(|| DebugTracer::new(true));
+        let tracer = self.debug.then(|| self.debug_tracer());
+        let _session_span = tracer
+            .as_ref()
+            .map(|t| t.session_span("MULTI-TEMPLATE", &self.raw));
 
         if let Some(tracer) = tracer.as_ref() {
             let info = format!(
@@ -1004,12 +3124,23 @@ impl Template {
             tracer.session_start("MULTI-TEMPLATE", &self.raw, input, Some(&info));
         }
 
-        let buffer = self.render_sections(
-            self.estimate_output_capacity(input),
-            collect_rich,
+        let result = self.render_sections(
+            buffer,
             tracer.as_ref(),
-            |_, ops, exec, cache_key, dbg| {
-                self.execute_template_section(
+            &values,
+            |_, ops, exec, cache_key, stored, dbg| match stored {
+                Some(value) => self.execute_template_section(
+                    value,
+                    ops,
+                    exec,
+                    cache_key,
+                    ExecutionContext {
+                        input_hash: &mut None,
+                        cache: &mut cache,
+                        dbg,
+                    },
+                ),
+                None => self.execute_template_section(
                     input,
                     ops,
                     exec,
@@ -1019,9 +3150,15 @@ impl Template {
                         cache: &mut cache,
                         dbg,
                     },
-                )
+                ),
             },
-        )?;
+        );
+        *entries = cache.operations;
+        let buffer = result?;
+
+        if let Some(guard) = &cache.guard {
+            guard.check_output(&buffer.rendered)?;
+        }
 
         if let (Some(tracer), Some(start_time)) = (tracer.as_ref(), start_time) {
             tracer.session_end("MULTI-TEMPLATE", &buffer.rendered, start_time.elapsed());
@@ -1030,45 +3167,120 @@ impl Template {
         Ok(buffer)
     }
 
+    /// Resolves the input position read by every template section.
+    ///
+    /// Sections without `input=` read the input at their own position.
+    fn input_slots(&self, input_count: usize, names: &[&str]) -> Result<Vec<usize>, String> {
+        self.sections
+            .iter()
+            .filter_map(|section| match section {
+                TemplateSection::Template { input, .. } => Some(input),
+                TemplateSection::Literal(_) => None,
+            })
+            .enumerate()
+            .map(|(position, input)| match input {
+                None => Ok(position),
+                Some(InputRef::Index(index)) if *index < input_count => Ok(*index),
+                Some(InputRef::Index(index)) => Err(format!(
+                    "Template section {position} references input {index}, but {input_count} input(s) were given"
+                )),
+                Some(InputRef::Name(name)) => names
+                    .iter()
+                    .position(|candidate| candidate == name)
+                    .ok_or_else(|| {
+                        if names.is_empty() {
+                            format!(
+                                "Template section {position} references input `{name}` by name; use format_with_named_inputs"
+                            )
+                        } else {
+                            format!(
+                                "Template section {position} references input `{name}`, which was not given"
+                            )
+                        }
+                    }),
+            })
+            .collect()
+    }
+
     fn render_structured_inputs(
         &self,
         inputs: &[&[&str]],
+        names: &[&str],
+        separators: &[&str],
+        collect_rich: bool,
+    ) -> Result<RenderBuffer, String> {
+        cache::with_scope(self.cache_scope, None, || {
+            self.with_format_settings(|| {
+                self.render_structured_inputs_scoped(inputs, names, separators, collect_rich)
+            })
+        })
+    }
+
+    fn render_structured_inputs_scoped(
+        &self,
+        inputs: &[&[&str]],
+        names: &[&str],
         separators: &[&str],
         collect_rich: bool,
     ) -> Result<RenderBuffer, String> {
-        let template_sections_count = self.template_section_count();
+        self.check_access()?;
+        let slots = self.input_slots(inputs.len(), names)?;
 
-        let adjusted_inputs: Vec<&[&str]> = (0..template_sections_count)
-            .map(|i| inputs.get(i).copied().unwrap_or(&[]))
+        let normalized: Vec<Vec<Cow<str>>> = inputs
+            .iter()
+            .map(|items| {
+                items
+                    .iter()
+                    .map(|item| self.line_endings.normalize_input(item))
+                    .collect()
+            })
             .collect();
-        let adjusted_separators: Vec<&str> = (0..template_sections_count)
-            .map(|i| separators.get(i).copied().unwrap_or(" "))
+        let normalized: Vec<Vec<&str>> = normalized
+            .iter()
+            .map(|items| items.iter().map(|item| &**item).collect())
             .collect();
+        let inputs: Vec<&[&str]> = normalized.iter().map(Vec::as_slice).collect();
 
-        let mut cache = TemplateCache::new();
+        let mut cache = TemplateCache::new(self.limits.as_ref());
 
-        self.render_sections(
-            self.literal_output_capacity(),
-            collect_rich,
+        let buffer = self.render_sections(
+            RenderBuffer::new(
+                self.literal_output_capacity(),
+                collect_rich.then_some(self.template_section_count()),
+            ),
             None,
-            |template_position, ops, exec, cache_key, _| {
+            &PlaceholderValues::default(),
+            |template_position, ops, exec, cache_key, stored, _| {
+                let slot = slots[template_position];
+                // A `get:` section reads the stored value as its only item
+                let stored = stored.as_slice();
                 self.execute_structured_template_section(
-                    adjusted_inputs[template_position],
-                    adjusted_separators[template_position],
+                    if stored.is_empty() {
+                        inputs.get(slot).copied().unwrap_or(&[])
+                    } else {
+                        stored
+                    },
+                    separators.get(slot).copied().unwrap_or(" "),
                     ops,
                     exec,
                     cache_key,
                     &mut cache,
                 )
             },
-        )
+        )?;
+
+        if let Some(guard) = &cache.guard {
+            guard.check_output(&buffer.rendered)?;
+        }
+
+        Ok(buffer)
     }
 
     fn render_sections<F>(
         &self,
-        rendered_capacity: usize,
-        collect_rich: bool,
+        mut buffer: RenderBuffer,
         tracer: Option<&DebugTracer>,
+        values: &PlaceholderValues,
         mut render_template_section: F,
     ) -> Result<RenderBuffer, String>
     where
@@ -1077,14 +3289,19 @@ impl Template {
             &[StringOp],
             &TemplateExecutionPlan,
             u64,
+            Option<&str>,
             Option<&DebugTracer>,
-        ) -> Result<String, String>,
+        ) -> Result<String, OpError>,
     {
-        let mut buffer = RenderBuffer::new(
-            rendered_capacity,
-            collect_rich.then_some(self.template_section_count()),
-        );
+        buffer.crlf = self.line_endings == LineEndings::Crlf;
         let mut template_position = 0;
+        // Values stored by `set:` sections, read by later `get:` sections
+        let mut variables: HashMap<&str, String> = HashMap::new();
+        let mut groups = self.groups.iter().peekable();
+        // Where the open conditional group starts in the buffer, and whether
+        // any of its template sections had output
+        let mut group_mark = None;
+        let mut group_has_output = false;
 
         for (overall_position, (section, plan)) in self
             .sections
@@ -1092,6 +3309,14 @@ impl Template {
             .zip(self.compiled_sections.iter())
             .enumerate()
         {
+            if groups
+                .peek()
+                .is_some_and(|group| group.start == overall_position)
+            {
+                group_mark = Some(buffer.mark());
+                group_has_output = false;
+            }
+
             match (section, plan) {
                 (TemplateSection::Literal(text), CompiledSectionPlan::Literal) => {
                     if let Some(tracer) = tracer {
@@ -1113,7 +3338,13 @@ impl Template {
                     }
                 }
                 (
-                    TemplateSection::Template { ops, .. },
+                    TemplateSection::Template {
+                        ops,
+                        spans,
+                        variable,
+                        placeholder,
+                        ..
+                    },
                     CompiledSectionPlan::Template { exec, cache_key },
                 ) => {
                     if let Some(tracer) = tracer {
@@ -1126,13 +3357,56 @@ impl Template {
                         );
                     }
 
-                    let output =
-                        render_template_section(template_position, ops, exec, *cache_key, tracer)?;
+                    let stored = match (variable, placeholder) {
+                        // Checked at parse time to follow the `set:` section
+                        (Some(Variable::Get(name)), _) => variables
+                            .get(name.as_str())
+                            .map(|value| Cow::Borrowed(value.as_str())),
+                        (_, Some(placeholder)) => {
+                            Some(values.resolve(*placeholder).inspect_err(|message| {
+                                if let Some(tracer) = tracer {
+                                    tracer.error(message);
+                                }
+                            })?)
+                        }
+                        _ => None,
+                    };
+                    let output = render_template_section(
+                        template_position,
+                        ops,
+                        exec,
+                        *cache_key,
+                        stored.as_deref(),
+                        tracer,
+                    )
+                    .map_err(|e| {
+                        let message = self.describe_op_error(ops, spans, e);
+                        if let Some(tracer) = tracer {
+                            tracer.error(&message);
+                        }
+                        message
+                    })?;
+                    if let Some(Variable::Set(name)) = variable {
+                        variables.insert(name, output.clone());
+                    }
+                    group_has_output |= !output.is_empty();
                     buffer.push_template_output(template_position, overall_position, output);
                     template_position += 1;
                 }
                 _ => unreachable!("compiled section plan must match template sections"),
             }
+
+            if groups
+                .next_if(|group| group.end == overall_position + 1)
+                .is_some()
+                && let Some(mark) = group_mark.take()
+                && !group_has_output
+            {
+                if let Some(tracer) = tracer {
+                    tracer.cache_operation("GROUP SKIPPED", "every template section was empty");
+                }
+                buffer.rewind(mark);
+            }
         }
 
         Ok(buffer)
@@ -1146,7 +3420,7 @@ impl Template {
         exec: &TemplateExecutionPlan,
         cache_key: u64,
         cache: &mut TemplateCache,
-    ) -> Result<String, String> {
+    ) -> Result<String, OpError> {
         match section_inputs.len() {
             0 => Ok(String::new()),
             1 => {
@@ -1192,13 +3466,18 @@ impl Template {
         exec: &TemplateExecutionPlan,
         section_key: u64,
         ctx: ExecutionContext<'_>,
-    ) -> Result<String, String> {
+    ) -> Result<String, OpError> {
+        let guard = ctx.cache.guard;
+        if let Some(guard) = &guard {
+            guard.check_deadline()?;
+        }
+
         match exec.cache_policy {
             CachePolicy::Never => {
                 if let Some(t) = ctx.dbg {
                     t.cache_operation("DIRECT EXEC", "cache disabled for unique section");
                 }
-                self.execute_template_section_inner(input, ops, &exec.kind, ctx.dbg)
+                self.execute_template_section_inner(input, ops, &exec.kind, ctx.dbg, guard)
             }
             CachePolicy::PerCall => {
                 let key = CacheKey {
@@ -1219,13 +3498,54 @@ impl Template {
                     t.cache_operation("CACHE MISS", "computing section");
                 }
 
-                let out = self.execute_template_section_inner(input, ops, &exec.kind, ctx.dbg)?;
+                let out =
+                    self.execute_template_section_inner(input, ops, &exec.kind, ctx.dbg, guard)?;
                 ctx.cache.operations.insert(key, out.clone());
                 Ok(out)
             }
         }
     }
 
+    /// Turns an operation error into the message reported to the caller,
+    /// naming the failing operation and its position in the template.
+    fn describe_op_error(&self, ops: &[StringOp], spans: &[OpSpan], error: OpError) -> String {
+        let Some((&first, rest)) = error.path.split_first() else {
+            return error.message;
+        };
+        let (Some(mut op), Some(mut span)) = (ops.get(first), spans.get(first)) else {
+            return error.message;
+        };
+        for &index in rest {
+            let (
+                StringOp::Map { operations }
+                | StringOp::MapFallible { operations }
+                | StringOp::Try { operations, .. }
+                | StringOp::SortBy { operations, .. }
+                | StringOp::UniqueBy { operations }
+                | StringOp::FilterBy { operations, .. },
+                Some(inner),
+            ) = (op, span.inner.get(index))
+            else {
+                return error.message;
+            };
+            let Some(inner_op) = operations.get(index) else {
+                return error.message;
+            };
+            op = inner_op;
+            span = inner;
+        }
+
+        // Positions count characters from 1, like editor columns
+        let column = |byte: usize| self.raw.get(..byte).map_or(0, |s| s.chars().count()) + 1;
+        format!(
+            "{} (in operation `{}` at chars {}..{})",
+            error.message,
+            op.keyword(),
+            column(span.span.start),
+            column(span.span.end)
+        )
+    }
+
     fn literal_preview(text: &str) -> String {
         if text.trim().is_empty() && text.len() <= 2 {
             "whitespace".to_string()
@@ -1242,7 +3562,15 @@ impl Template {
         ops: &[StringOp],
         kind: &TemplateExecutionKind,
         dbg: Option<&DebugTracer>,
-    ) -> Result<String, String> {
+        guard: Option<ExecGuard>,
+    ) -> Result<String, OpError> {
+        // Fast paths never build the intermediate values that split and
+        // length limits inspect, nor check range bounds.
+        let kind = match guard {
+            Some(guard) if guard.checks_values() => &TemplateExecutionKind::Generic,
+            _ if self.strict_ranges => &TemplateExecutionKind::Generic,
+            _ => kind,
+        };
         match kind {
             TemplateExecutionKind::Passthrough => {
                 if let Some(t) = dbg {
@@ -1254,7 +3582,10 @@ impl Template {
                 if let Some(t) = dbg {
                     t.cache_operation("FAST SPLIT", &format!("by '{sep}'"));
                 }
-                Ok(self.fast_split_index(input, sep, *idx))
+                let start = self.profiler.as_ref().map(|_| Instant::now());
+                let result = self.fast_split_index(input, sep, *idx);
+                self.record_fast_path("split", start);
+                Ok(result)
             }
             TemplateExecutionKind::SplitJoinRewrite {
                 split_sep,
@@ -1263,19 +3594,56 @@ impl Template {
                 if let Some(t) = dbg {
                     t.cache_operation("FAST SPLIT+JOIN", "direct separator rewrite");
                 }
-                Ok(self.fast_split_join(input, split_sep, join_sep))
+                let start = self.profiler.as_ref().map(|_| Instant::now());
+                let result = self.fast_split_join(input, split_sep, join_sep);
+                self.record_fast_path("split+join", start);
+                Ok(result)
+            }
+            TemplateExecutionKind::SplitRangeJoin {
+                split_sep,
+                range,
+                join_sep,
+                operation,
+            } => {
+                if let Some(t) = dbg {
+                    t.cache_operation("FAST SPLIT+JOIN", "single scan over selected items");
+                }
+                let start = self.profiler.as_ref().map(|_| Instant::now());
+                let result = Self::fast_split_range_join(input, split_sep, range, join_sep);
+                self.record_fast_path(operation, start);
+                Ok(result)
+            }
+            // Debug traces and profiles report every operation, so the fused
+            // chain only runs when neither is active.
+            TemplateExecutionKind::StringChain if !self.debug && self.profiler.is_none() => {
+                Ok(apply_string_chain(input, ops))
             }
-            TemplateExecutionKind::Generic => {
+            TemplateExecutionKind::StringChain | TemplateExecutionKind::Generic => {
                 let nested_dbg = if self.debug {
-                    Some(DebugTracer::new(true))
+                    Some(self.debug_tracer())
                 } else {
                     None
                 };
-                apply_ops_internal(input, ops, self.debug, nested_dbg)
+                apply_ops_internal(
+                    input,
+                    ops,
+                    self.output_separator.as_deref(),
+                    self.debug,
+                    nested_dbg,
+                    self.profiler.as_ref(),
+                    guard.as_ref(),
+                )
             }
         }
     }
 
+    /// Records the time spent in a fused fast path when profiling.
+    fn record_fast_path(&self, operation: &'static str, start: Option<Instant>) {
+        if let (Some(profiler), Some(start)) = (&self.profiler, start) {
+            profiler.record(operation, start.elapsed());
+        }
+    }
+
     fn compile_sections(sections: &[TemplateSection]) -> Vec<CompiledSectionPlan> {
         let mut repeated_keys = HashSet::with_capacity(sections.len());
         let mut seen_keys = HashSet::with_capacity(sections.len());
@@ -1291,7 +3659,7 @@ impl Template {
             .iter()
             .map(|section| match section {
                 TemplateSection::Literal(_) => CompiledSectionPlan::Literal,
-                TemplateSection::Template { ops, cache_key } => CompiledSectionPlan::Template {
+                TemplateSection::Template { ops, cache_key, .. } => CompiledSectionPlan::Template {
                     exec: TemplateExecutionPlan {
                         kind: Self::compile_template_execution_kind(ops),
                         cache_policy: if repeated_keys.contains(cache_key) {
@@ -1306,6 +3674,114 @@ impl Template {
             .collect()
     }
 
+    /// Finds the [`FastPath`] of a template made of literal text and at most
+    /// one template section that reads the input and needs no access checks.
+    fn compile_fast_path(sections: &[TemplateSection]) -> Option<FastPath> {
+        let literal_text = |sections: &[TemplateSection]| -> String {
+            sections
+                .iter()
+                .filter_map(|section| match section {
+                    TemplateSection::Literal(text) => Some(text.as_str()),
+                    TemplateSection::Template { .. } => None,
+                })
+                .collect()
+        };
+
+        let mut templates = sections
+            .iter()
+            .enumerate()
+            .filter(|(_, section)| matches!(section, TemplateSection::Template { .. }));
+        match (templates.next(), templates.next()) {
+            (None, _) => Some(FastPath::Literal(literal_text(sections))),
+            (
+                Some((
+                    index,
+                    TemplateSection::Template {
+                        ops,
+                        input: None,
+                        variable: None,
+                        placeholder: None,
+                        ..
+                    },
+                )),
+                None,
+            ) if !ops.iter().any(|op| op.reads_files() || op.runs_commands()) => {
+                Some(FastPath::Section {
+                    prefix: literal_text(&sections[..index]),
+                    index,
+                    suffix: literal_text(&sections[index + 1..]),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The fast path to use for a format call, if the template has one and
+    /// no setting needs the full rendering.
+    fn active_fast_path(&self) -> Option<&FastPath> {
+        self.fast_path.as_ref().filter(|_| {
+            !self.debug
+                && self.groups.is_empty()
+                && self.limits.is_none()
+                && !self.strict_ranges
+                && self.line_endings == LineEndings::Keep
+                && self.cache_scope == CacheScope::Global
+        })
+    }
+
+    /// Runs `f` with the settings that operations read while formatting.
+    fn with_format_settings<R>(&self, f: impl FnOnce() -> R) -> R {
+        style::with_color(self.color, || {
+            with_error_policy(self.error_policy, || {
+                with_strict_ranges(self.strict_ranges, f)
+            })
+        })
+    }
+
+    /// Renders `input` through `fast_path` into `out`, which is left empty
+    /// on error.
+    fn render_fast_path(
+        &self,
+        fast_path: &FastPath,
+        input: &str,
+        out: &mut String,
+    ) -> Result<(), String> {
+        out.clear();
+        let (prefix, index, suffix) = match fast_path {
+            FastPath::Literal(text) => {
+                out.push_str(text);
+                return Ok(());
+            }
+            FastPath::Section {
+                prefix,
+                index,
+                suffix,
+            } => (prefix, *index, suffix),
+        };
+        let (
+            TemplateSection::Template { ops, spans, .. },
+            CompiledSectionPlan::Template { exec, .. },
+        ) = (&self.sections[index], &self.compiled_sections[index])
+        else {
+            unreachable!("fast path must point at a template section");
+        };
+
+        let output = self
+            .with_format_settings(|| {
+                self.execute_template_section_inner(input, ops, &exec.kind, None, None)
+            })
+            .map_err(|e| self.describe_op_error(ops, spans, e))?;
+        if prefix.is_empty() && suffix.is_empty() {
+            *out = output;
+        } else {
+            out.reserve(prefix.len() + output.len() + suffix.len());
+            out.push_str(prefix);
+            out.push_str(&output);
+            out.push_str(suffix);
+        }
+        Ok(())
+    }
+
     fn compile_template_execution_kind(ops: &[StringOp]) -> TemplateExecutionKind {
         if ops.is_empty() {
             return TemplateExecutionKind::Passthrough;
@@ -1315,6 +3791,9 @@ impl Template {
             && let StringOp::Split {
                 sep,
                 range: RangeSpec::Index(idx),
+                max_splits: None,
+                keep_sep: false,
+                skip_empty: false,
             } = &ops[0]
         {
             return TemplateExecutionKind::SplitIndex {
@@ -1328,8 +3807,14 @@ impl Template {
                 StringOp::Split {
                     sep: split_sep,
                     range,
+                    max_splits: None,
+                    keep_sep: false,
+                    skip_empty: false,
+                },
+                StringOp::Join {
+                    sep: join_sep,
+                    last_sep: None,
                 },
-                StringOp::Join { sep: join_sep },
             ] = ops
             && Self::is_full_range(range)
         {
@@ -1339,9 +3824,82 @@ impl Template {
             };
         }
 
+        match ops {
+            [
+                StringOp::Split {
+                    sep: split_sep,
+                    range,
+                    max_splits: None,
+                    keep_sep: false,
+                    skip_empty: false,
+                },
+                StringOp::Join {
+                    sep: join_sep,
+                    last_sep: None,
+                },
+            ] if Self::is_contiguous_range(range) => {
+                return TemplateExecutionKind::SplitRangeJoin {
+                    split_sep: split_sep.clone(),
+                    range: range.clone(),
+                    join_sep: join_sep.clone(),
+                    operation: "split+join",
+                };
+            }
+            [
+                StringOp::Split {
+                    sep: split_sep,
+                    range: split_range,
+                    max_splits: None,
+                    keep_sep: false,
+                    skip_empty: false,
+                },
+                StringOp::Slice { range },
+                StringOp::Join {
+                    sep: join_sep,
+                    last_sep: None,
+                },
+            ] if Self::is_full_range(split_range) && Self::is_contiguous_range(range) => {
+                return TemplateExecutionKind::SplitRangeJoin {
+                    split_sep: split_sep.clone(),
+                    range: range.clone(),
+                    join_sep: join_sep.clone(),
+                    operation: "split+slice+join",
+                };
+            }
+            _ => {}
+        }
+
+        if is_fusable_string_chain(ops) {
+            return TemplateExecutionKind::StringChain;
+        }
+
         TemplateExecutionKind::Generic
     }
 
+    /// Whether every template section only strips ANSI codes, so the template
+    /// can be applied to bytes that are not valid UTF-8.
+    fn strips_ansi_only(&self) -> bool {
+        !self.debug
+            && self.groups.is_empty()
+            && self.limits.is_none()
+            && self.line_endings == LineEndings::Keep
+            && self.sections.iter().all(|section| match section {
+                TemplateSection::Literal(_) => true,
+                TemplateSection::Template {
+                    ops,
+                    input,
+                    variable,
+                    placeholder,
+                    ..
+                } => {
+                    input.is_none()
+                        && variable.is_none()
+                        && placeholder.is_none()
+                        && ops.iter().all(|op| matches!(op, StringOp::StripAnsi))
+                }
+            })
+    }
+
     fn estimate_output_capacity(&self, input: &str) -> usize {
         self.sections
             .iter()
@@ -1366,6 +3924,8 @@ impl Template {
     fn hash_input(input: &str) -> u64 {
         let mut hasher = DefaultHasher::new();
         input.hash(&mut hasher);
+        // `filter_valid_utf8` keeps U+FFFD unless the input was read lossily
+        lossy_input().hash(&mut hasher);
         hasher.finish()
     }
 
@@ -1375,67 +3935,98 @@ impl Template {
             return input.to_string();
         }
 
-        if split_sep.len() == 1 {
-            let split_byte = split_sep.as_bytes()[0];
-            let estimated_len = if join_sep.len() == 1 {
-                input.len()
-            } else {
-                let replacements = memchr_iter(split_byte, input.as_bytes()).count();
-                input.len() + replacements.saturating_mul(join_sep.len().saturating_sub(1))
-            };
+        let estimated_len = if join_sep.len() <= split_sep.len() {
+            input.len()
+        } else {
+            let replacements = separator_matches(input, split_sep).count();
+            input.len() + replacements.saturating_mul(join_sep.len() - split_sep.len())
+        };
+
+        let mut result = String::with_capacity(estimated_len);
+        let mut start = 0usize;
+        for idx in separator_matches(input, split_sep) {
+            result.push_str(&input[start..idx]);
+            result.push_str(join_sep);
+            start = idx + split_sep.len();
+        }
+        result.push_str(&input[start..]);
+        result
+    }
+
+    /// Splits, selects a range and joins in one scan, without building the
+    /// intermediate list.
+    fn fast_split_range_join(
+        input: &str,
+        split_sep: &str,
+        range: &RangeSpec,
+        join_sep: &str,
+    ) -> String {
+        if split_sep.is_empty() {
+            let parts_len = input.matches(split_sep).count() + 1;
+            let selected = range_indices(parts_len, range);
+            return Self::join_parts(
+                input
+                    .split(split_sep)
+                    .skip(selected.start)
+                    .take(selected.len()),
+                join_sep,
+            );
+        }
+
+        let mut input = input;
+
+        // Ranges counted from the end only need the last items, so skip
+        // straight to them instead of scanning the whole input.
+        if let [sep_byte] = split_sep.as_bytes()
+            && let RangeSpec::Range(Some(start), end, _) = *range
+            && start < 0
+            && end.is_none_or(|e| e < 0)
+            && let Some(idx) =
+                memrchr_iter(*sep_byte, input.as_bytes()).nth(start.unsigned_abs() - 1)
+        {
+            input = &input[idx + 1..];
+        }
 
-            let mut result = String::with_capacity(estimated_len);
-            let mut start = 0usize;
-            for idx in memchr_iter(split_byte, input.as_bytes()) {
-                result.push_str(&input[start..idx]);
+        let parts_len = separator_matches(input, split_sep).count() + 1;
+        let selected = range_indices(parts_len, range);
+        Self::join_parts(
+            split_ranges(input, split_sep)
+                .skip(selected.start)
+                .take(selected.len())
+                .map(|part| &input[part]),
+            join_sep,
+        )
+    }
+
+    #[inline]
+    fn join_parts<'a>(parts: impl Iterator<Item = &'a str>, join_sep: &str) -> String {
+        let mut result = String::new();
+        for (i, part) in parts.enumerate() {
+            if i > 0 {
                 result.push_str(join_sep);
-                start = idx + 1;
             }
-            result.push_str(&input[start..]);
-            result
-        } else {
-            input.replace(split_sep, join_sep)
+            result.push_str(part);
         }
+        result
     }
 
     #[inline]
     fn fast_split_index(&self, input: &str, sep: &str, idx: isize) -> String {
         if sep.is_empty() {
-            let parts = get_cached_split(input, sep);
+            let parts = get_cached_split(input, sep, SplitOptions::default());
             return apply_range(&parts, &RangeSpec::Index(idx))
                 .into_iter()
                 .next()
+                .map(String::from)
                 .unwrap_or_default();
         }
 
-        if sep.len() == 1 {
-            let sep_byte = sep.as_bytes()[0];
-            let parts_len = memchr_iter(sep_byte, input.as_bytes()).count() + 1;
-            let resolved = Self::resolve_split_index(idx, parts_len);
-            return Self::split_index_single_byte(input, sep_byte, resolved);
-        }
-
-        let parts_len = input.matches(sep).count() + 1;
+        let parts_len = separator_matches(input, sep).count() + 1;
         let resolved = Self::resolve_split_index(idx, parts_len);
-        input
-            .split(sep)
+        split_ranges(input, sep)
             .nth(resolved)
+            .map(|part| input[part].to_string())
             .unwrap_or_default()
-            .to_string()
-    }
-
-    #[inline]
-    fn split_index_single_byte(input: &str, sep_byte: u8, target_idx: usize) -> String {
-        let mut start = 0usize;
-
-        for (current_idx, idx) in memchr_iter(sep_byte, input.as_bytes()).enumerate() {
-            if current_idx == target_idx {
-                return input[start..idx].to_string();
-            }
-            start = idx + 1;
-        }
-
-        input[start..].to_string()
     }
 
     #[inline]
@@ -1450,49 +4041,40 @@ impl Template {
         matches!(range, RangeSpec::Range(None, None, false))
     }
 
+    /// Returns `true` if `range` selects adjacent items in input order.
+    #[inline]
+    fn is_contiguous_range(range: &RangeSpec) -> bool {
+        !matches!(range, RangeSpec::Stepped(..) | RangeSpec::Multi(_))
+            && matches!(ascending_range(range), Cow::Borrowed(_))
+    }
+
     fn format_operations_summary(ops: &[StringOp]) -> String {
         ops.iter()
             .map(|op| match op {
-                StringOp::Split { sep, range } => format!(
-                    "split('{sep}', {})",
-                    match range {
-                        RangeSpec::Index(i) => i.to_string(),
-                        RangeSpec::Range(s, e, inc) => match (s, e) {
-                            (None, None) => "..".into(),
-                            (Some(s), None) => format!("{s}.."),
-                            (None, Some(e)) => {
-                                if *inc {
-                                    format!("..={e}")
-                                } else {
-                                    format!("..{e}")
-                                }
-                            }
-                            (Some(s), Some(e)) => {
-                                let dots = if *inc { "..=" } else { ".." };
-                                format!("{s}{dots}{e}")
-                            }
-                        },
-                    }
-                ),
-                StringOp::Upper => "upper".into(),
-                StringOp::Lower => "lower".into(),
+                StringOp::Split { sep, range, .. } => {
+                    format!("split('{sep}', {})", canonical::format_range(range))
+                }
+                StringOp::Upper {
+                    locale: CaseLocale::Root,
+                } => "upper".into(),
+                StringOp::Lower {
+                    locale: CaseLocale::Root,
+                } => "lower".into(),
+                StringOp::Upper { .. } => "upper(turkic)".into(),
+                StringOp::Lower { .. } => "lower(turkic)".into(),
                 StringOp::Append { suffix } => format!("append('{suffix}')"),
                 StringOp::Prepend { prefix } => format!("prepend('{prefix}')"),
-                StringOp::Replace {
-                    pattern,
-                    replacement,
-                    ..
-                } => format!("replace('{pattern}' → '{replacement}')"),
+                StringOp::Replace { rules } => rules
+                    .iter()
+                    .map(|rule| format!("replace('{}' → '{}')", rule.pattern, rule.replacement))
+                    .collect::<Vec<_>>()
+                    .join(" | "),
                 _ => format!("{op:?}").to_lowercase(),
             })
             .collect::<Vec<_>>()
             .join(" | ")
     }
 
-    fn make_template_section(ops: Vec<StringOp>) -> TemplateSection {
-        TemplateSection::from_ops(ops)
-    }
-
     fn hash_ops(ops: &[StringOp]) -> u64 {
         let mut hasher = DefaultHasher::new();
         ops.hash(&mut hasher);
@@ -1504,18 +4086,25 @@ impl Template {
     /// Detects and parses templates that consist of exactly one `{ ... }` block
     /// with no surrounding literal text. Returns `Ok(Some(Self))` when
     /// the fast path can be applied, `Ok(None)` otherwise.
-    fn try_single_block(template: &str) -> Result<Option<Self>, String> {
+    fn try_single_block(template: &str, config: &ParserConfig) -> Result<Option<Self>, String> {
         // Must start with '{' and end with '}' to be a candidate.
-        if !(template.starts_with('{') && template.ends_with('}')) {
+        if config.custom_delimiters().is_some()
+            || !(template.starts_with('{') && template.ends_with('}'))
+        {
             return Ok(None);
         }
+        config.check_len(template)?;
 
         // Verify that the outer-most braces close at the very end and that the
         // brace nesting never returns to zero before the last char.
-        let mut depth = 0u32;
+        let mut depth = 0;
         for ch in template[1..template.len() - 1].chars() {
             match ch {
-                '{' => depth += 1,
+                '{' => {
+                    depth += 1;
+                    // The outer braces count as the first level
+                    config.check_depth(depth + 1)?;
+                }
                 '}' => {
                     if depth == 0 {
                         // Closed the top-level early → literal content exists.
@@ -1533,8 +4122,10 @@ impl Template {
         }
 
         // Safe to treat as single template block.
-        let (ops, dbg_flag) = parser::parse_template(template)?;
-        let sections = vec![Self::make_template_section(ops)];
+        let parsed = parser::parse_section(template, 0, config)?;
+        config.check_operations(&parsed.ops, &mut 0)?;
+        let dbg_flag = parsed.debug;
+        let sections = vec![TemplateSection::from_parsed(parsed)];
         Ok(Some(Self::new(template.to_string(), sections, dbg_flag)))
     }
 }
@@ -1560,6 +4151,49 @@ impl Display for Template {
     }
 }
 
+/// Compares templates by their parsed sections.
+///
+/// Two templates are equal when they parse to the same literal text and the
+/// same operations, however they were spelled: `{1}` equals `{split: :1}`
+/// and the verbose `{!# upper | trim }` equals `{upper|trim}`. Debug markers and runtime
+/// settings such as debug output, profiling, limits and file or command
+/// access are not compared. Equal templates also hash equally and have the
+/// same [`canonical_string`](Template::canonical_string).
+///
+/// # Examples
+///
+/// ```rust
+/// use string_pipeline::Template;
+///
+/// let a = Template::parse("{1}").unwrap();
+/// let b = Template::parse("{split: :1}").unwrap();
+/// assert_eq!(a, b);
+/// assert_ne!(a, Template::parse("{split:,:1}").unwrap());
+/// ```
+impl PartialEq for Template {
+    fn eq(&self, other: &Self) -> bool {
+        self.sections == other.sections && self.groups == other.groups
+    }
+}
+
+impl Eq for Template {}
+
+impl Hash for Template {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.sections.hash(state);
+        self.groups.hash(state);
+    }
+}
+
+/// Creates a single-section template, as [`Template::from_ops`] does.
+impl TryFrom<Vec<StringOp>> for Template {
+    type Error = String;
+
+    fn try_from(ops: Vec<StringOp>) -> Result<Self, Self::Error> {
+        Self::from_ops(ops)
+    }
+}
+
 /* ---------- backward compatibility alias --------------------------------- */
 
 /// Deprecated compatibility alias.
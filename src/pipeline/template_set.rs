@@ -0,0 +1,225 @@
+//! Named template collections loaded from template pack files.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use super::{ParserConfig, Template};
+
+/// A collection of named, compiled templates.
+///
+/// Template packs let a team keep the pipelines it relies on in one file
+/// and refer to them by name, from code with [`TemplateSet::get`] or from
+/// the command line with `--templates-file FILE --template-name NAME`.
+///
+/// A pack file holds one `NAME = TEMPLATE` entry per line. The name ends at
+/// the first `=`, and whitespace around the name and the template is
+/// ignored. Blank lines and lines starting with `#` are skipped. With the
+/// `toml` feature, files ending in `.toml` are read as TOML instead: string
+/// values are templates, and tables group them under dotted names.
+///
+/// Every template is parsed when the pack is loaded, so a broken entry is
+/// reported right away, with its name and line, rather than when it is
+/// first used.
+///
+/// # Examples
+///
+/// ```rust
+/// use string_pipeline::TemplateSet;
+///
+/// let set = TemplateSet::parse(
+///     "# Shared pipelines\n\
+///      shout = {upper|append:!}\n\
+///      first-field = {split:,:0|trim}\n",
+/// )
+/// .unwrap();
+///
+/// assert_eq!(set.names().collect::<Vec<_>>(), ["first-field", "shout"]);
+/// assert_eq!(set.get("shout").unwrap().format("hi").unwrap(), "HI!");
+/// assert_eq!(set.get("first-field").unwrap().format(" a , b").unwrap(), "a");
+/// assert!(set.get("missing").is_none());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TemplateSet {
+    templates: BTreeMap<String, Template>,
+}
+
+impl TemplateSet {
+    /// Load a template pack file with the default parser options.
+    ///
+    /// Files with a `.toml` extension are read as TOML, which needs the
+    /// `toml` feature, and other files in the line format described on
+    /// [`TemplateSet`].
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The template pack file to read
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(TemplateSet)` - The templates of the file
+    /// * `Err(String)` - If the file cannot be read, or an entry is malformed
+    ///   or fails to parse
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        Self::load_with_config(path, &ParserConfig::default())
+    }
+
+    /// Load a template pack file, parsing its templates with `config`.
+    ///
+    /// Like [`TemplateSet::load`], with the parser options of
+    /// [`Template::parse_with_config`] applied to every template, such as
+    /// custom delimiters or verbose syntax.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The template pack file to read
+    /// * `config` - The parser options to apply
+    pub fn load_with_config(path: impl AsRef<Path>, config: &ParserConfig) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read template file '{}': {e}", path.display()))?;
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            parse_toml_entries(&text, config)
+        } else {
+            parse_lines(&text, config)
+        }
+    }
+
+    /// Parse a template pack in the `NAME = TEMPLATE` line format.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The contents of the pack
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(TemplateSet)` - The templates of the pack
+    /// * `Err(String)` - If a line has no name, a name is defined twice, or
+    ///   a template fails to parse
+    pub fn parse(text: &str) -> Result<Self, String> {
+        parse_lines(text, &ParserConfig::default())
+    }
+
+    /// Parse a template pack written as TOML.
+    ///
+    /// Each string value is a template named by its key. Tables group
+    /// templates, which are then named by the dotted path of keys. Needs the
+    /// `toml` feature and fails without it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use string_pipeline::TemplateSet;
+    ///
+    /// let pack = "slug = '{lower|replace:s/ /-/g}'\n\n[csv]\nfirst = '{split:,:0}'\n";
+    /// # if cfg!(feature = "toml") {
+    /// let set = TemplateSet::parse_toml(pack).unwrap();
+    /// assert_eq!(set.names().collect::<Vec<_>>(), ["csv.first", "slug"]);
+    /// assert_eq!(set.get("csv.first").unwrap().format("a,b").unwrap(), "a");
+    /// # }
+    /// ```
+    pub fn parse_toml(text: &str) -> Result<Self, String> {
+        parse_toml_entries(text, &ParserConfig::default())
+    }
+
+    /// Get the template named `name`.
+    pub fn get(&self, name: &str) -> Option<&Template> {
+        self.templates.get(name)
+    }
+
+    /// Check whether the set has a template named `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.templates.contains_key(name)
+    }
+
+    /// Iterate over the template names in sorted order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.templates.keys().map(String::as_str)
+    }
+
+    /// Iterate over the names and templates in sorted order of names.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Template)> {
+        self.templates.iter().map(|(name, t)| (name.as_str(), t))
+    }
+
+    /// Get the number of templates in the set.
+    pub fn len(&self) -> usize {
+        self.templates.len()
+    }
+
+    /// Check whether the set has no templates.
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+}
+
+/// Parses the `NAME = TEMPLATE` line format.
+fn parse_lines(text: &str, config: &ParserConfig) -> Result<TemplateSet, String> {
+    let mut templates = BTreeMap::new();
+    let mut defined_on = BTreeMap::new();
+    for (index, line) in text.lines().enumerate() {
+        let line_no = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((name, template)) = line.split_once('=') else {
+            return Err(format!("Line {line_no}: expected NAME = TEMPLATE"));
+        };
+        let name = name.trim();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(format!("Line {line_no}: invalid template name '{name}'"));
+        }
+        if let Some(first) = defined_on.insert(name.to_string(), line_no) {
+            return Err(format!(
+                "Line {line_no}: template '{name}' is already defined on line {first}"
+            ));
+        }
+        let template = Template::parse_with_config(template.trim(), config)
+            .map_err(|e| format!("Line {line_no}: template '{name}': {e}"))?;
+        templates.insert(name.to_string(), template);
+    }
+    Ok(TemplateSet { templates })
+}
+
+/// Parses a TOML template pack.
+#[cfg(feature = "toml")]
+fn parse_toml_entries(text: &str, config: &ParserConfig) -> Result<TemplateSet, String> {
+    use toml_edit::{DocumentMut, Item, TableLike};
+
+    fn collect(
+        table: &dyn TableLike,
+        prefix: &str,
+        config: &ParserConfig,
+        templates: &mut BTreeMap<String, Template>,
+    ) -> Result<(), String> {
+        for (key, item) in table.iter() {
+            let name = format!("{prefix}{key}");
+            if let Some(template) = item.as_str() {
+                let template = Template::parse_with_config(template, config)
+                    .map_err(|e| format!("Template '{name}': {e}"))?;
+                templates.insert(name, template);
+            } else if let Some(inner) = item.as_table_like() {
+                collect(inner, &format!("{name}."), config, templates)?;
+            } else if !matches!(item, Item::None) {
+                return Err(format!("Template '{name}' is not a string"));
+            }
+        }
+        Ok(())
+    }
+
+    let doc: DocumentMut = text
+        .parse()
+        .map_err(|e: toml_edit::TomlError| format!("Invalid TOML: {}", e.message().trim()))?;
+    let mut templates = BTreeMap::new();
+    collect(doc.as_table(), "", config, &mut templates)?;
+    Ok(TemplateSet { templates })
+}
+
+#[cfg(not(feature = "toml"))]
+fn parse_toml_entries(_text: &str, _config: &ParserConfig) -> Result<TemplateSet, String> {
+    Err(super::config::disabled(
+        "Reading TOML template packs",
+        "toml",
+    ))
+}
@@ -0,0 +1,172 @@
+//! Static type inference for operation pipelines.
+//!
+//! Every pipeline starts from a string input, and each operation either
+//! requires a specific value type or accepts both. This module walks an
+//! operation list before any input is seen and rejects chains that can never
+//! succeed, such as `{upper|sort}`, reporting the offending operation.
+//!
+//! Sub-pipelines such as the one in `map:{...}` are checked against the
+//! item type they receive, a string, so `{split:,:..|map:{sort}}` is
+//! reported as needing a `split` inside the map rather than failing on the
+//! first input.
+//!
+//! Callers that build pipelines whose shape is only known at runtime can skip
+//! this pass with [`Template::parse_unchecked`](super::Template::parse_unchecked).
+
+use super::{RangeSpec, StringOp, ValueKind};
+
+/// Statically inferred type of a pipeline value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ValueType {
+    /// A single string.
+    Str,
+    /// A list of strings.
+    List,
+}
+
+impl ValueType {
+    fn describe(self) -> &'static str {
+        match self {
+            ValueType::Str => "a string",
+            ValueType::List => "a list",
+        }
+    }
+}
+
+/// Type-checks a top-level pipeline, which always receives a string input.
+///
+/// # Returns
+///
+/// * `Ok(ValueType)` - The inferred type of the pipeline result
+/// * `Err(String)` - Description of the first impossible operation
+pub(crate) fn check_pipeline(ops: &[StringOp]) -> Result<ValueType, String> {
+    check_ops(ops, ValueType::Str, "", None)
+}
+
+/// Checks `ops` in order, where `parent` is the keyword of the operation
+/// running them as a sub-pipeline, if any.
+fn check_ops(
+    ops: &[StringOp],
+    input: ValueType,
+    location: &str,
+    parent: Option<&str>,
+) -> Result<ValueType, String> {
+    let mut current = input;
+
+    for (i, op) in ops.iter().enumerate() {
+        let here = format!("{location}operation {} (`{}`)", i + 1, op.keyword());
+        current = infer_step(op, current, &here, parent)?;
+    }
+
+    Ok(current)
+}
+
+/// Infers the output type of a single operation, or `None` if it cannot
+/// accept `input`.
+pub(crate) fn step_type(op: &StringOp, input: ValueType) -> Option<ValueType> {
+    infer_step(op, input, "", None).ok()
+}
+
+/// Infers the output type of a single operation given its input type.
+///
+/// Apart from `split` and `split_any`, whose output depends on its range, and the operations
+/// that run a sub-pipeline, types come from the operation catalog.
+fn infer_step(
+    op: &StringOp,
+    input: ValueType,
+    location: &str,
+    parent: Option<&str>,
+) -> Result<ValueType, String> {
+    match op {
+        StringOp::Split { range, .. } | StringOp::SplitAny { range, .. } => Ok(match range {
+            RangeSpec::Index(_) => ValueType::Str,
+            RangeSpec::Range(..) | RangeSpec::Stepped(..) | RangeSpec::Multi(_) => ValueType::List,
+        }),
+        StringOp::Try { operations, .. } => {
+            expect_str(op, input, location, parent)?;
+            // The sub-pipeline's result, or the fallback, is a string again
+            check_ops(
+                operations,
+                ValueType::Str,
+                &format!("{location}, inner "),
+                Some(op.keyword()),
+            )?;
+            Ok(ValueType::Str)
+        }
+        StringOp::Map { operations }
+        | StringOp::MapFallible { operations }
+        | StringOp::SortBy { operations, .. }
+        | StringOp::UniqueBy { operations }
+        | StringOp::FilterBy { operations, .. } => {
+            expect_list(op, input, location, parent)?;
+            // Each item is a string; whatever the sub-pipeline yields is
+            // rendered back into a string: the new item for `map`, the key
+            // for `sort_by` and `unique_by`, the checked value for `filter_by`.
+            check_ops(
+                operations,
+                ValueType::Str,
+                &format!("{location}, inner "),
+                Some(op.keyword()),
+            )?;
+            Ok(ValueType::List)
+        }
+        _ => {
+            let info = op.info();
+            let input = match info.input {
+                ValueKind::String => expect_str(op, input, location, parent)?,
+                ValueKind::List => expect_list(op, input, location, parent)?,
+                ValueKind::Any => input,
+            };
+            Ok(match info.output {
+                ValueKind::String => ValueType::Str,
+                ValueKind::List => ValueType::List,
+                ValueKind::Any => input,
+            })
+        }
+    }
+}
+
+fn expect_list(
+    op: &StringOp,
+    input: ValueType,
+    location: &str,
+    parent: Option<&str>,
+) -> Result<ValueType, String> {
+    if input == ValueType::Str {
+        if let Some(parent) = parent {
+            let keyword = op.keyword();
+            return Err(format!(
+                "Type error in {location}: `{keyword}` inside {parent} requires split first, since each item is a string"
+            ));
+        }
+        return Err(format!(
+            "Type error in {location}: `{}` can only be applied to lists, but its input is {}",
+            op.keyword(),
+            input.describe()
+        ));
+    }
+    Ok(ValueType::List)
+}
+
+fn expect_str(
+    op: &StringOp,
+    input: ValueType,
+    location: &str,
+    parent: Option<&str>,
+) -> Result<ValueType, String> {
+    if input == ValueType::List {
+        let keyword = op.keyword();
+        if let Some(parent) = parent {
+            // Sub-pipelines cannot nest map, so the list has to be joined
+            return Err(format!(
+                "Type error in {location}: `{keyword}` inside {parent} requires join first, since its input is {}",
+                input.describe()
+            ));
+        }
+        return Err(format!(
+            "Type error in {location}: `{keyword}` can only be applied to strings, but its input is {}. Use map:{{{keyword}}} for lists.",
+            input.describe()
+        ));
+    }
+    Ok(ValueType::Str)
+}
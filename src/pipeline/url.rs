@@ -0,0 +1,135 @@
+//! A small URL parser for the `url_scheme`, `url_host`, `url_path` and
+//! `url_query` operations.
+//!
+//! URLs are split into their parts following the generic syntax of
+//! RFC 3986 without validating them, so anything can be parsed and missing
+//! parts are empty. Input without a scheme, such as the request path of an
+//! access log line, is read as a path with an optional query.
+
+use std::borrow::Cow;
+
+/// The parts of a URL, borrowed from the input.
+struct UrlParts<'a> {
+    scheme: &'a str,
+    authority: Option<&'a str>,
+    path: &'a str,
+    query: &'a str,
+}
+
+impl<'a> UrlParts<'a> {
+    fn parse(url: &'a str) -> Self {
+        let url = url.trim();
+        let url = url.split_once('#').map_or(url, |(before, _)| before);
+
+        let scheme_len = url
+            .find(':')
+            .filter(|&i| {
+                let mut chars = url[..i].chars();
+                chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+                    && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+            })
+            .unwrap_or(0);
+        let scheme = &url[..scheme_len];
+        let rest = if scheme_len > 0 {
+            &url[scheme_len + 1..]
+        } else {
+            url
+        };
+
+        let (authority, rest) = match rest.strip_prefix("//") {
+            Some(rest) => {
+                let end = rest.find(['/', '?']).unwrap_or(rest.len());
+                (Some(&rest[..end]), &rest[end..])
+            }
+            None => (None, rest),
+        };
+        let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+        UrlParts {
+            scheme,
+            authority,
+            path,
+            query,
+        }
+    }
+}
+
+fn lowercase(s: &str) -> Cow<'_, str> {
+    if s.bytes().any(|b| b.is_ascii_uppercase()) {
+        Cow::Owned(s.to_ascii_lowercase())
+    } else {
+        Cow::Borrowed(s)
+    }
+}
+
+/// Returns the scheme of `url` in lowercase, such as `https`.
+pub(crate) fn scheme(url: &str) -> Cow<'_, str> {
+    lowercase(UrlParts::parse(url).scheme)
+}
+
+/// Returns the host of `url` in lowercase, without user information or
+/// port. IPv6 addresses keep their brackets.
+pub(crate) fn host(url: &str) -> Cow<'_, str> {
+    let Some(authority) = UrlParts::parse(url).authority else {
+        return Cow::Borrowed("");
+    };
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+    let host = if host_port.starts_with('[') {
+        host_port.find(']').map_or(host_port, |i| &host_port[..=i])
+    } else {
+        host_port.split_once(':').map_or(host_port, |(h, _)| h)
+    };
+    lowercase(host)
+}
+
+/// Returns the path of `url`, without query or fragment. A URL with a
+/// host but no path has the path `/`.
+pub(crate) fn path(url: &str) -> &str {
+    let parts = UrlParts::parse(url);
+    if parts.path.is_empty() && parts.authority.is_some() {
+        "/"
+    } else {
+        parts.path
+    }
+}
+
+/// Returns the decoded value of the first query parameter named `key`, or
+/// an empty string if there is none.
+pub(crate) fn query_param<'a>(url: &'a str, key: &str) -> Cow<'a, str> {
+    UrlParts::parse(url)
+        .query
+        .split('&')
+        .map(|pair| pair.split_once('=').unwrap_or((pair, "")))
+        .find(|(name, _)| decode(name) == key)
+        .map_or(Cow::Borrowed(""), |(_, value)| decode(value))
+}
+
+/// Decodes `%XX` escapes and `+` as used in query strings. Malformed escapes
+/// are kept as they are.
+fn decode(s: &str) -> Cow<'_, str> {
+    if !s.contains(['%', '+']) {
+        return Cow::Borrowed(s);
+    }
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' => {
+                let hex = bytes
+                    .get(i + 1..i + 3)
+                    .filter(|h| h.iter().all(u8::is_ascii_hexdigit))
+                    .and_then(|h| u8::from_str_radix(std::str::from_utf8(h).ok()?, 16).ok());
+                if let Some(byte) = hex {
+                    out.push(byte);
+                    i += 2;
+                } else {
+                    out.push(b'%');
+                }
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    Cow::Owned(String::from_utf8_lossy(&out).into_owned())
+}
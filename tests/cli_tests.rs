@@ -1,3 +1,4 @@
+use std::fs;
 use std::io::Write;
 use std::process::Command;
 use tempfile::NamedTempFile;
@@ -169,6 +170,48 @@ fn test_template_file_template() {
     );
 }
 
+#[test]
+fn test_template_file_verbose_syntax() {
+    let annotated = "{!#\n  split:,:..       # one field per item\n  | map:{trim|upper}\n  | join:\\ -\\   # spaces are escaped\n}\n";
+    let template_file = create_temp_file(annotated);
+    let output = run_cli_with_stdin(
+        &["--template-file", template_file.path().to_str().unwrap()],
+        " a, b ,c",
+    );
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "A - B - C");
+
+    let output = run_cli(&["{ split:,:.. # items\n | join:+ }", "a,b"]);
+    assert!(!output.status.success());
+
+    let output = run_cli(&[
+        "{ split:,:.. # items\n | join:+ }",
+        "a,b",
+        "--verbose-syntax",
+    ]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "a+b");
+}
+
+#[test]
+fn test_delim_option() {
+    let output = run_cli(&[
+        "--delim",
+        "<< >>",
+        "fn <<lower>>() { return <<split:,:..|map:{upper}|join:;>>; }",
+        "A,b",
+    ]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "fn a,b() { return A;B; }"
+    );
+
+    let output = run_cli(&["--delim", "<<", "{upper}", "a"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--delim expects"));
+}
+
 #[test]
 fn test_input_file_option() {
     let input_file = create_temp_file("hello world");
@@ -184,6 +227,188 @@ fn test_input_file_option() {
     );
 }
 
+#[test]
+fn test_read_file_requires_allow_fs() {
+    let file = create_temp_file("file contents");
+    let path = file.path().to_str().unwrap();
+
+    let output = run_cli(&["{read_file|upper}", path]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--allow-fs"));
+
+    let output = run_cli(&["{read_file|upper}", path, "--allow-fs"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "FILE CONTENTS");
+}
+
+#[test]
+fn test_strict_ranges_flag() {
+    let output = run_cli(&["{substring:5}", "hi"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "i");
+
+    let output = run_cli(&["--strict-ranges", "{substring:5}", "hi"]);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("Index 5 is out of bounds for 2 characters")
+    );
+
+    let output = run_cli(&[
+        "--strict-ranges",
+        "{split:,:..|clamp:1..10|join:-}",
+        "a,b,c",
+    ]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "b-c");
+}
+
+#[test]
+fn test_templates_file_with_template_name() {
+    let pack = create_temp_file("# Team pipelines\nshout = {upper}\nfirst = {split:,:0}\n");
+    let pack_path = pack.path().to_str().unwrap();
+
+    let output = run_cli(&[
+        "--templates-file",
+        pack_path,
+        "--template-name",
+        "first",
+        "--input-file",
+        create_temp_file("a,b").path().to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "a");
+
+    // The only positional argument is the input
+    let output = run_cli(&[
+        "--templates-file",
+        pack_path,
+        "--template-name",
+        "shout",
+        "hello",
+    ]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "HELLO");
+
+    let output = run_cli_with_stdin(
+        &["--templates-file", pack_path, "--template-name", "shout"],
+        "hello",
+    );
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "HELLO");
+
+    let output = run_cli(&[
+        "--templates-file",
+        pack_path,
+        "--template-name",
+        "missing",
+        "--validate",
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No template named 'missing' in"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("(available: first, shout)"));
+
+    // A name needs a pack, and a pack needs a name
+    let output = run_cli(&["--template-name", "shout", "--validate"]);
+    assert!(!output.status.success());
+    let output = run_cli(&["--templates-file", pack_path, "--validate"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_templates_file_reports_broken_entries() {
+    let pack = create_temp_file("ok = {upper}\nbroken = {upper|sort}\n");
+    let output = run_cli(&[
+        "--templates-file",
+        pack.path().to_str().unwrap(),
+        "--template-name",
+        "ok",
+        "--validate",
+    ]);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("Error reading templates file: Line 2: template 'broken'")
+    );
+}
+
+#[test]
+fn test_watch_reruns_on_changes() {
+    use std::io::Read;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    let template_file = create_temp_file("{upper}");
+    let input_file = create_temp_file("abc");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_string-pipeline"))
+        .args([
+            "--watch",
+            "-t",
+            template_file.path().to_str().unwrap(),
+            "-f",
+            input_file.path().to_str().unwrap(),
+        ])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("Failed to spawn command");
+
+    let stdout = Arc::new(Mutex::new(String::new()));
+    let mut pipe = child.stdout.take().unwrap();
+    let reader = {
+        let stdout = Arc::clone(&stdout);
+        std::thread::spawn(move || {
+            let mut buf = [0; 256];
+            while let Ok(n @ 1..) = pipe.read(&mut buf) {
+                stdout
+                    .lock()
+                    .unwrap()
+                    .push_str(&String::from_utf8_lossy(&buf[..n]));
+            }
+        })
+    };
+    let wait_for = |expected: &str| {
+        let start = Instant::now();
+        while !stdout.lock().unwrap().ends_with(expected) {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "no output '{expected}', got {:?}",
+                stdout.lock().unwrap()
+            );
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    };
+
+    wait_for("ABC");
+    fs::write(template_file.path(), "{upper|append:!}").unwrap();
+    wait_for("ABC!");
+    fs::write(input_file.path(), "xyz").unwrap();
+    wait_for("XYZ!");
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+    reader.join().unwrap();
+}
+
+#[test]
+fn test_watch_requires_template_file() {
+    let output = run_cli(&["--watch", "{upper}", "abc"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--template-file"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_exec_requires_allow_exec() {
+    let output = run_cli(&["{exec:tr a-z A-Z}", "hello"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--allow-exec"));
+
+    let output = run_cli(&["{exec:tr a-z A-Z}", "hello", "--allow-exec"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "HELLO");
+}
+
 #[test]
 fn test_both_template_and_input_files() {
     let template_file = create_temp_file("{upper}");
@@ -237,6 +462,72 @@ fn test_debug_flag_with_template() {
     );
 }
 
+#[test]
+fn test_debug_format_json() {
+    let output = run_cli(&[
+        "--debug",
+        "--debug-format",
+        "json",
+        "{split:,:..|map:{upper}|join:-}",
+        "a,\"b\"",
+    ]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "A-\"B\"");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("DEBUG:"));
+    // Skip cargo's own build output
+    let lines: Vec<&str> = stderr.lines().filter(|l| l.starts_with('{')).collect();
+    assert!(!lines.is_empty());
+    for line in &lines {
+        assert!(line.starts_with("{\"event\":"), "not a JSON event: {line}");
+        assert!(line.ends_with('}'), "not a JSON event: {line}");
+    }
+    assert!(lines[0].contains("\"event\":\"session_start\""));
+    assert!(lines.last().unwrap().contains("\"event\":\"session_end\""));
+    assert!(stderr.contains("\"operation\":\"split\""));
+    assert!(stderr.contains("\"duration_ns\":"));
+    assert!(stderr.contains("\"event\":\"map_item_start\""));
+    assert!(!stderr.contains("processing..."));
+    // Quotes in values are escaped
+    assert!(stderr.contains(r#""output":"\"B\"""#));
+}
+
+#[test]
+fn test_debug_level_and_preview() {
+    let output = run_cli(&[
+        "--debug",
+        "--debug-level",
+        "ops",
+        "--debug-preview",
+        "3",
+        "{split:,:..|map:{upper}}",
+        "hello,world",
+    ]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "HELLO,WORLD"
+    );
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    // Skip cargo's own build output
+    let trace: Vec<&str> = stderr.lines().filter(|l| l.starts_with("DEBUG:")).collect();
+    assert!(trace.iter().any(|l| l.contains("Step 2: Map")));
+    assert!(trace.iter().any(|l| l.ends_with("Input: \"hel...\"")));
+    assert!(!trace.iter().any(|l| l.contains("Item 1/2")));
+    assert!(!trace.iter().any(|l| l.contains("hello")));
+
+    let output = run_cli(&["--debug-level", "all", "{upper}", "hello"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_debug_format_invalid_value() {
+    let output = run_cli(&["--debug-format", "xml", "{upper}", "hello"]);
+    assert!(!output.status.success());
+}
+
 #[test]
 fn test_quiet_suppresses_debug() {
     let output = run_cli(&["--quiet", "--debug", "{split:,:..|map:{upper}}", "a,b"]);
@@ -279,6 +570,301 @@ fn test_quiet_suppresses_inline_debug_stderr() {
     assert!(!stderr.contains("DEBUG:"));
 }
 
+#[test]
+fn test_cache_stats_flag() {
+    let output = run_cli(&["--cache-stats", "{split:,:..|filter:a|join:-}", "a,b,a"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(stdout.trim(), "a-a");
+    assert!(stderr.contains("regex cache: 1 entries, 0 hits, 1 misses"));
+    assert!(stderr.contains("split cache: 1 entries"));
+    assert!(stderr.contains("cached text: "));
+}
+
+#[test]
+fn test_quiet_suppresses_cache_stats() {
+    let output = run_cli(&["--quiet", "--cache-stats", "{upper}", "a"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "A");
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("regex cache:"));
+}
+
+#[test]
+fn test_diff_identical_outputs() {
+    let output = run_cli(&[
+        "{split:,:..|map:{upper}|join:,}",
+        "--diff",
+        "{upper}",
+        "a,b",
+    ]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_diff_different_outputs() {
+    let output = run_cli(&[
+        "{split:,:..|join:\\n}",
+        "--diff",
+        "{split:,:..|filter_not:^c$|join:\\n}",
+        "a,b,c,d",
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "--- {split:,:..|join:\\n}\n\
+         +++ {split:,:..|filter_not:^c$|join:\\n}\n \
+         a\n b\n-c\n d\n"
+    );
+}
+
+#[test]
+fn test_diff_collapses_distant_unchanged_lines() {
+    let input = (1..=20)
+        .map(|n| n.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    let output = run_cli(&[
+        "{split:,:..|join:\\n}",
+        "--diff",
+        "{split:,:..|map:{replace:s/^10$/ten/}|join:\\n}",
+        &input,
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().skip(2).collect();
+    assert_eq!(
+        lines,
+        [
+            "@@ 6 unchanged lines @@",
+            " 7",
+            " 8",
+            " 9",
+            "-10",
+            "+ten",
+            " 11",
+            " 12",
+            " 13",
+            "@@ 7 unchanged lines @@"
+        ]
+    );
+}
+
+#[test]
+fn test_diff_falls_back_to_first_difference_for_unrelated_outputs() {
+    let input = (1..=10_000)
+        .map(|n| n.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    let output = run_cli(&[
+        "{split:,:..|join:\\n}",
+        "--diff",
+        "{split:,:..|map:{append:x}|join:\\n}",
+        &input,
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "--- {split:,:..|join:\\n}\n\
+         +++ {split:,:..|map:{append:x}|join:\\n}\n\
+         @@ outputs differ from line 1 on, too much for a diff @@\n\
+         -1\n+1x\n"
+    );
+}
+
+#[test]
+fn test_diff_checks_second_template() {
+    let output = run_cli(&["{upper}", "--diff", "{upper", "a"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Error parsing template"));
+
+    let output = run_cli(&["{upper}", "--diff", "{read_file}", "a"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--allow-fs"));
+}
+
+#[test]
+fn test_assert_matching_output() {
+    let output = run_cli(&["{split:,:..|map:{upper}|join:-}", "--assert", "A-B", "a,b"]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_assert_mismatch_shows_diff() {
+    let output = run_cli(&["{upper}", "--assert", "abc", "abc"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "--- expected\n+++ actual\n-abc\n+ABC\n"
+    );
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("Assertion failed: output differs from expected at line 1")
+    );
+}
+
+#[test]
+fn test_assert_mismatch_on_unrelated_large_output() {
+    let input = (1..=10_000)
+        .map(|n| n.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    let expected = input.replace(',', "\n");
+    let output = run_cli(&[
+        "{split:,:..|map:{append:x}|join:\\n}",
+        "--assert",
+        &expected,
+        &input,
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("Assertion failed: output differs from expected at line 1")
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "--- expected\n+++ actual\n\
+         @@ outputs differ from line 1 on, too much for a diff @@\n\
+         -1\n+1x\n"
+    );
+}
+
+#[test]
+fn test_assert_file() {
+    let golden = create_temp_file("A\nB\n");
+    let path = golden.path().to_str().unwrap();
+
+    let output = run_cli(&[
+        "{split:,:..|map:{upper}|join:\\n}",
+        "--assert-file",
+        path,
+        "a,b",
+    ]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let output = run_cli(&["{split:,:..|join:\\n}", "--assert-file", path, "a,b"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with(&format!("--- {path}\n+++ actual\n")));
+    assert!(stdout.ends_with("-A\n-B\n+a\n+b\n"));
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains(&format!("output differs from {path} at line 1"))
+    );
+}
+
+#[test]
+fn test_assert_conflicts_with_diff() {
+    let output = run_cli(&["{upper}", "--assert", "A", "--diff", "{lower}", "a"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+}
+
+#[test]
+fn test_crlf_flags() {
+    let file = create_temp_file("ba\r\nca\r\nx\r\n");
+    let path = file.path().to_str().unwrap();
+    let template = "{split:\\n:..|filter:a$|join:\\n}";
+
+    let output = run_cli(&["-f", path, template]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+
+    let output = run_cli(&["--crlf", "-f", path, template]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ba\nca");
+
+    let output = run_cli(&["--crlf-output", "-f", path, template]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ba\r\nca");
+}
+
+#[test]
+fn test_ors_flag() {
+    let template = "{split:,:..|map:{split: :..|join:-}}";
+    let output = run_cli(&[template, "a b,c d"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "a-b,c-d");
+
+    let output = run_cli(&["--ors", "\\n", template, "a b,c d"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "a-b\nc-d");
+
+    let output = run_cli(&["--join", ", ", "{split:,:..|sort}", "b,a"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "a, b");
+}
+
+#[test]
+fn test_per_line_flag() {
+    let file = create_temp_file("buy milk\r\ncall bob\n\nlast");
+    let path = file.path().to_str().unwrap();
+    let output = run_cli(&["--per-line", "--crlf", "{line_no}: {upper}", "-f", path]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "1: BUY MILK\n2: CALL BOB\n3: \n4: LAST\n"
+    );
+
+    let output = run_cli(&["--per-line", "{file_name}", "-f", path]);
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with(path));
+
+    let output = run_cli_with_stdin(&["--per-line", "{split:,:1}"], "a,b\nc,d\n");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "b\nd\n");
+}
+
+#[test]
+fn test_per_line_errors() {
+    let output = run_cli_with_stdin(&["--per-line", "{roman}"], "4\nfour\n");
+    assert!(!output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "IV\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Error formatting line 2"));
+
+    let output = run_cli_with_stdin(&["--per-line", "{file_name}"], "a\n");
+    assert!(!output.status.success());
+
+    let output = run_cli(&["--per-line", "--diff", "{lower}", "{upper}", "a"]);
+    assert!(!output.status.success());
+    let output = run_cli(&["--progress", "{upper}", "a"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_no_color() {
+    let run = |args: &[&str], no_color: Option<&str>| {
+        let mut cmd = Command::new("cargo");
+        cmd.args(["run", "--bin", BINARY_NAME, "--"]).args(args);
+        match no_color {
+            Some(value) => cmd.env("NO_COLOR", value),
+            None => cmd.env_remove("NO_COLOR"),
+        };
+        let output = cmd.output().expect("Failed to execute command");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    assert_eq!(run(&["{color:red}", "a"], None), "\x1b[31ma\x1b[39m");
+    assert_eq!(run(&["--no-color", "{color:red|bold}", "a"], None), "a");
+    assert_eq!(run(&["{bg:blue|underline}", "a"], Some("1")), "a");
+    assert_eq!(run(&["{bold}", "a"], Some("")), "\x1b[1ma\x1b[22m");
+}
+
+#[test]
+fn test_per_line_progress() {
+    let file = create_temp_file("a\nb\nc\n");
+    let path = file.path().to_str().unwrap();
+    let output = run_cli(&["--per-line", "--progress", "{upper}", "-f", path]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "A\nB\nC\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let report = stderr.lines().last().unwrap();
+    assert!(report.starts_with("6 B, 3 lines, "), "{report}");
+    assert!(report.contains(" lines/s, ") && report.contains(", done in "));
+
+    let output = run_cli(&["--per-line", "--progress", "-q", "{upper}", "-f", path]);
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("lines/s"));
+}
+
 #[test]
 fn test_debug_without_quiet_shows_stderr() {
     let output = run_cli(&["--debug", "{split:,:..|map:{upper}}", "a,b"]);
@@ -378,6 +964,29 @@ fn test_validate_invalid_template() {
     assert!(stderr.contains("Error parsing template"));
 }
 
+#[test]
+fn test_validate_type_error() {
+    let output = run_cli(&["--validate", "{split:,:..|upper}"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Error parsing template"));
+    assert!(stderr.contains("Type error in operation 2 (`upper`)"));
+}
+
+#[test]
+fn test_validate_prints_lint_warnings() {
+    let output = run_cli(&["--validate", "{split:,:..|sort|shuffle|join:,}"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "Template syntax is valid"
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(
+        "Warning: template section 1, operation 2 (`sort`): the following `shuffle` discards this order"
+    ));
+}
+
 #[test]
 fn test_validate_complex_template() {
     let output = run_cli(&["--validate", "{split:,:..|map:{upper|append:!}|join:-}"]);
@@ -430,6 +1039,19 @@ fn test_list_operations_flag() {
     assert!(stdout.contains("split:"));
     assert!(stdout.contains("upper"));
     assert!(stdout.contains("lower"));
+    assert!(stdout.contains("  quote:CHARS[:SUFFIX]     - Same as surround\n"));
+    assert!(stdout.contains(
+        "  s:SEP:RANGE[:MAX][:keep_sep][:skip_empty]\n                           - Same as split\n"
+    ));
+}
+
+#[test]
+fn test_list_operations_json() {
+    let output = run_cli(&["--list-operations=json"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("[\n{\"name\":\"split\""));
+    assert!(stdout.contains(r#""name":"map","aliases":[],"syntax":"map:{OPS}","input":"list""#));
 }
 
 #[test]
@@ -690,3 +1312,55 @@ fn test_template_file_with_template_and_validation() {
         "Template syntax is valid"
     );
 }
+
+#[test]
+fn test_lossy_flag() {
+    let mut file = NamedTempFile::new().expect("Failed to create temp file");
+    file.write_all(b"logs/app\xff.log\n").unwrap();
+    let path = file.path().to_str().unwrap();
+
+    let output = run_cli(&["-f", path, "{split:/:-1}"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--lossy"));
+
+    let output = run_cli(&["--lossy", "-f", path, "{split:/:-1}"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "app\u{FFFD}.log");
+
+    let mut file = NamedTempFile::new().expect("Failed to create temp file");
+    file.write_all(b"\x1b[32mok\x1b[0m \xfe\n").unwrap();
+    let path = file.path().to_str().unwrap();
+    let output = run_cli(&["--lossy", "-f", path, "{strip_ansi}"]);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"ok \xfe");
+}
+
+#[test]
+fn test_lossy_input_trims_unicode_whitespace() {
+    let mut file = NamedTempFile::new().expect("Failed to create temp file");
+    file.write_all(b"a\xff b\xc2\xa0\xe3\x80\x80\n").unwrap();
+    let path = file.path().to_str().unwrap();
+
+    let output = run_cli(&["--lossy", "-f", path, "<{upper}>"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "<A\u{FFFD} B>");
+
+    let output = run_cli(&["--lossy", "-f", path, "<{strip_ansi}>"]);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"<a\xff b>");
+}
+
+#[test]
+fn test_lossy_per_line_filter_valid_utf8() {
+    let mut file = NamedTempFile::new().expect("Failed to create temp file");
+    file.write_all("ok \u{FFFD}\n".as_bytes()).unwrap();
+    file.write_all(b"bad \xff\n").unwrap();
+    let path = file.path().to_str().unwrap();
+
+    let output = run_cli(&["--lossy", "--per-line", "-f", path, "[{filter_valid_utf8}]"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "[ok \u{FFFD}]\n[]\n"
+    );
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "tokio")]
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use string_pipeline::Template;
+use tokio_stream::StreamExt;
+
+fn lines(items: &[&str]) -> impl tokio_stream::Stream<Item = String> + Send + 'static {
+    let items: Vec<String> = items.iter().map(|s| s.to_string()).collect();
+    tokio_stream::iter(items)
+}
+
+#[tokio::test]
+async fn test_format_stream_keeps_order() {
+    let template = Template::parse("{split:,:1|upper}").unwrap();
+    let results: Vec<_> = template
+        .format_stream(lines(&["a,b", "c,d", "e,f"]), 1)
+        .collect()
+        .await;
+    assert_eq!(
+        results,
+        [
+            Ok("B".to_string()),
+            Ok("D".to_string()),
+            Ok("F".to_string())
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_format_stream_continues_after_errors() {
+    let template = Template::parse("{roman}").unwrap();
+    let results: Vec<_> = template
+        .format_stream(lines(&["4", "four", "9"]), 8)
+        .collect()
+        .await;
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0], Ok("IV".to_string()));
+    assert!(results[1].as_ref().unwrap_err().contains("four"));
+    assert_eq!(results[2], Ok("IX".to_string()));
+}
+
+#[tokio::test]
+async fn test_format_stream_empty_input() {
+    let template = Template::parse("{upper}").unwrap();
+    let results: Vec<_> = template.format_stream(lines(&[]), 0).collect().await;
+    assert!(results.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_format_stream_bounds_buffering() {
+    let polled = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&polled);
+    let input = tokio_stream::iter(0..).map(move |i: u64| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        i.to_string()
+    });
+
+    let template = Template::parse("{append:!}").unwrap();
+    let mut results = Box::pin(template.format_stream(input, 4));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Both channels are full, plus one item held by each task
+    let before = polled.load(Ordering::SeqCst);
+    assert!(before <= 4 + 4 + 2, "polled {before} items");
+
+    assert_eq!(results.next().await, Some(Ok("0!".to_string())));
+    assert_eq!(results.next().await, Some(Ok("1!".to_string())));
+    drop(results);
+}
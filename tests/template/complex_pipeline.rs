@@ -1,4 +1,5 @@
 use super::process;
+use string_pipeline::{DebugSink, Template};
 
 // Split + Join combinations
 #[test]
@@ -739,3 +740,152 @@ fn test_map_normalize_whitespace_extract_first_field() {
     .unwrap();
     assert_eq!(result, "alice,bob,charlie");
 }
+
+// Borrowed input vs. strings produced by earlier operations
+#[test]
+fn test_selecting_operations_on_borrowed_and_owned_strings() {
+    // Directly on the input
+    assert_eq!(process("  héllo  ", "{trim}").unwrap(), "héllo");
+    assert_eq!(process("héllo wörld", "{substring:1..4}").unwrap(), "éll");
+    assert_eq!(process("héllo wörld", "{substring:-5..}").unwrap(), "wörld");
+    assert_eq!(process("abc123", r"{regex_extract:\d+}").unwrap(), "123");
+    assert_eq!(process("abc", "{replace:s/x/y/}").unwrap(), "abc");
+    assert_eq!(process("plain", "{strip_ansi}").unwrap(), "plain");
+
+    // On strings created by a previous operation
+    assert_eq!(process("  héllo  ", "{append:!|trim}").unwrap(), "héllo  !");
+    assert_eq!(process("xxhixx", "{upper|trim:X}").unwrap(), "HI");
+    assert_eq!(
+        process("héllo wörld", "{upper|substring:1..4}").unwrap(),
+        "ÉLL"
+    );
+    assert_eq!(
+        process("abc123", r"{upper|regex_extract:(\w)\d}").unwrap(),
+        "C1"
+    );
+    assert_eq!(
+        process("abc123", r"{upper|regex_extract:([A-Z]+)(\d+):2}").unwrap(),
+        "123"
+    );
+    assert_eq!(process("abc", "{upper|replace:s/x/y/}").unwrap(), "ABC");
+    assert_eq!(
+        process("\x1b[31mred\x1b[0m", "{append:!|strip_ansi}").unwrap(),
+        "red!"
+    );
+    assert_eq!(process("plain", "{upper|strip_ansi}").unwrap(), "PLAIN");
+}
+
+#[test]
+fn test_list_operations_after_filtering_and_slicing() {
+    assert_eq!(
+        process(
+            "c,a,b,a,d",
+            "{split:,:..|filter_not:^d$|sort|unique|join:,}"
+        )
+        .unwrap(),
+        "a,b,c"
+    );
+    assert_eq!(
+        process("a,b,c,d,e", "{split:,:1..|slice:-2..|reverse|join:,}").unwrap(),
+        "e,d"
+    );
+    assert_eq!(process("a,b,c", "{split:,:5}").unwrap(), "c");
+    assert_eq!(process("", "{split:,:0}").unwrap(), "");
+}
+
+#[test]
+fn test_in_place_list_operations_do_not_modify_cached_splits() {
+    let input = "delta,alpha,charlie,bravo";
+
+    // Warm the split cache, then mutate lists produced from the cached entry
+    assert_eq!(
+        process(input, "{split:,:..|join:,}").unwrap(),
+        "delta,alpha,charlie,bravo"
+    );
+    assert_eq!(
+        process(input, "{split:,:..|sort|join:,}").unwrap(),
+        "alpha,bravo,charlie,delta"
+    );
+    assert_eq!(
+        process(input, "{split:,:..|reverse|join:,}").unwrap(),
+        "bravo,charlie,alpha,delta"
+    );
+    assert_eq!(
+        process(input, "{split:,:..|filter:^[cd]|join:,}").unwrap(),
+        "delta,charlie"
+    );
+
+    // The cached order is untouched
+    assert_eq!(
+        process(input, "{split:,:..|slice:0..2|join:,}").unwrap(),
+        "delta,alpha"
+    );
+}
+
+#[test]
+fn test_in_place_list_operations_do_not_modify_cached_splits_unfused() {
+    let input = "delta,alpha,charlie,bravo";
+
+    // `split|slice|join` runs as one fused plan, so read the cached list
+    // through a `map` that keeps the pipeline unfused
+    assert_eq!(
+        process(input, "{split:,:..|sort|join:,}").unwrap(),
+        "alpha,bravo,charlie,delta"
+    );
+    assert_eq!(
+        process(input, "{split:,:..|slice:0..2|map:{trim}|join:,}").unwrap(),
+        "delta,alpha"
+    );
+}
+
+#[test]
+fn test_fused_string_chains_match_step_by_step_execution() {
+    let chains = [
+        "trim|upper|append:!",
+        "append: x |trim|lower",
+        "prepend:ΣΑΣ |lower|substring:1..",
+        "surround:_|substring:-4..|upper",
+        "trim|substring:20..|append:z",
+    ];
+
+    for input in ["", "  Hello World  ", " ÉCOLE ", "abc"] {
+        for chain in chains {
+            let fused = process(input, &format!("{{{chain}}}")).unwrap();
+            // Debug mode traces every operation, so it runs them unfused
+            let stepped = Template::parse(&format!("{{!{chain}}}"))
+                .unwrap()
+                .with_debug_sink(DebugSink::from_fn(|_| {}))
+                .format(input)
+                .unwrap();
+            assert_eq!(fused, stepped, "chain {chain} on {input:?}");
+        }
+    }
+
+    assert_eq!(
+        process("  Hello  ", "{trim|lower|append:!}").unwrap(),
+        "hello!"
+    );
+    assert_eq!(
+        process(" a , b ", "{split:,:..|map:{trim|upper|surround:'}|join:,}").unwrap(),
+        "'A','B'"
+    );
+}
+
+#[test]
+fn test_split_range_join_without_intermediate_list() {
+    let input = "a,b,c,d,e";
+    assert_eq!(process(input, "{split:,:1..3|join:-}").unwrap(), "b-c");
+    assert_eq!(process(input, "{split:,:-2..|join:-}").unwrap(), "d-e");
+    assert_eq!(process(input, "{split:,:-3..-1|join:-}").unwrap(), "c-d");
+    assert_eq!(process("a,b", "{split:,:-9..|join:-}").unwrap(), "a-b");
+    assert_eq!(process(input, "{split:,:9|join:-}").unwrap(), "e");
+    assert_eq!(process(input, "{split:,:3..1|join:-}").unwrap(), "d-c");
+    assert_eq!(process(input, "{split:,:1..-1|join:-}").unwrap(), "b-c-d");
+    assert_eq!(process("a", "{split:,:3..-1|join:-}").unwrap(), "");
+    assert_eq!(
+        process(input, "{split:,:..|slice:..=1|join:+}").unwrap(),
+        "a+b"
+    );
+    assert_eq!(process("a->b->c", "{split:->:1..|join:+}").unwrap(), "b+c");
+    assert_eq!(process("", "{split:,:1..|join:-}").unwrap(), "");
+}
@@ -761,3 +761,75 @@ pub mod comprehensive_scenarios {
         );
     }
 }
+
+pub mod error_tolerance {
+    use super::process;
+
+    #[test]
+    fn test_try_keeps_input_on_error() {
+        assert_eq!(
+            process("1,x,3", "{split:,:..|map:{try:{ordinal}}|join:,}").unwrap(),
+            "1st,x,3rd"
+        );
+    }
+
+    #[test]
+    fn test_try_uses_fallback_on_error() {
+        assert_eq!(
+            process("1,x,3", "{split:,:..|map:{try:{ordinal}:?}|join:,}").unwrap(),
+            "1st,?,3rd"
+        );
+        assert_eq!(
+            process("1,x,3", "{split:,:..|map:{try:{ordinal}:}|join:,}").unwrap(),
+            "1st,,3rd"
+        );
+    }
+
+    #[test]
+    fn test_try_on_string() {
+        assert_eq!(process("x", "{try:{roman}}").unwrap(), "x");
+        assert_eq!(process("x", "{try:{roman}:none}").unwrap(), "none");
+        assert_eq!(process("4", "{try:{roman|lower}:none}").unwrap(), "iv");
+    }
+
+    #[test]
+    fn test_try_fails_as_a_whole() {
+        // `upper` succeeded, but the failing `ordinal` discards its result too
+        assert_eq!(process("a", "{try:{upper|ordinal}}").unwrap(), "a");
+    }
+
+    #[test]
+    fn test_try_nested() {
+        assert_eq!(
+            process("4,x", "{split:,:..|map:{try:{try:{roman}:0|ordinal}:z}}").unwrap(),
+            "z,0th"
+        );
+    }
+
+    #[test]
+    fn test_try_rejects_lists() {
+        let err = process("a,b", "{split:,:..|try:{upper}}").unwrap_err();
+        assert!(err.contains("can only be applied to strings"), "{err}");
+    }
+
+    #[test]
+    fn test_map_fallible_drops_failing_items() {
+        assert_eq!(
+            process("4,x,9,0", "{split:,:..|map_fallible:{roman}|join:,}").unwrap(),
+            "IV,IX"
+        );
+    }
+
+    #[test]
+    fn test_map_fallible_all_failing() {
+        assert_eq!(
+            process("x,y", "{split:,:..|map_fallible:{roman}|join:,}").unwrap(),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_map_fallible_cannot_nest_in_map() {
+        assert!(process("a", "{split:,:..|map:{map_fallible:{upper}}}").is_err());
+    }
+}
@@ -1,10 +1,26 @@
+use std::io::Write;
 use string_pipeline::Template;
+use tempfile::NamedTempFile;
 
 pub fn process(input: &str, template: &str) -> Result<String, String> {
     let tmpl = Template::parse(template)?;
     tmpl.format(input)
 }
 
+pub fn process_with_fs(input: &str, template: &str) -> Result<String, String> {
+    let tmpl = Template::parse(template)?.with_fs_access(true);
+    tmpl.format(input)
+}
+
+/// Writes `content` to a temporary file and returns it with its path.
+pub fn temp_file(content: &str) -> (NamedTempFile, String) {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(content.as_bytes()).unwrap();
+    let path = file.path().display().to_string();
+    (file, path)
+}
+
 pub mod complex_pipeline;
 pub mod map_operations;
 pub mod simple_pipeline;
+pub mod type_checking;
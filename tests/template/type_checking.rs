@@ -0,0 +1,169 @@
+use string_pipeline::Template;
+
+#[test]
+fn test_list_operation_on_string_rejected_at_parse() {
+    let err = Template::parse("{upper|sort}").unwrap_err();
+    assert!(err.contains("operation 2 (`sort`)"));
+    assert!(err.contains("can only be applied to lists"));
+}
+
+#[test]
+fn test_string_operation_on_list_rejected_at_parse() {
+    let err = Template::parse("{split:,:..|upper}").unwrap_err();
+    assert!(err.contains("operation 2 (`upper`)"));
+    assert!(err.contains("Use map:{upper} for lists."));
+}
+
+#[test]
+fn test_map_on_string_rejected_at_parse() {
+    let err = Template::parse("{trim|map:{upper}}").unwrap_err();
+    assert!(err.contains("operation 2 (`map`)"));
+}
+
+#[test]
+fn test_map_inner_operation_rejected_at_parse() {
+    let err = Template::parse("{split:,:..|map:{sort}}").unwrap_err();
+    assert!(err.contains("operation 2 (`map`), inner operation 1 (`sort`)"));
+}
+
+#[test]
+fn test_map_inner_list_operations_require_split() {
+    let list_ops = [
+        ("sort", "sort"),
+        ("unique", "unique"),
+        ("slice:0..1", "slice"),
+        ("duplicates", "duplicates"),
+        ("remove_empty", "remove_empty"),
+        ("intersect:a", "intersect"),
+        ("diff:a", "diff"),
+        ("union:a", "union"),
+        ("shuffle", "shuffle"),
+        ("sample:1", "sample"),
+        ("table", "table"),
+    ];
+
+    for (op, keyword) in list_ops {
+        let err = Template::parse(&format!("{{split:,:..|map:{{{op}}}}}")).unwrap_err();
+        assert!(
+            err.contains(&format!(
+                "inner operation 1 (`{keyword}`): `{keyword}` inside map requires split first"
+            )),
+            "{op}: {err}"
+        );
+
+        let template = format!("{{split:,:..|map:{{split:-:..|{op}|join:-}}}}");
+        assert!(Template::parse(&template).is_ok(), "{template}");
+    }
+}
+
+#[test]
+fn test_map_inner_string_operations_accepted() {
+    let string_ops = [
+        "strip_ansi",
+        "deaccent",
+        "ascii",
+        "slugify",
+        "normalize:nfc",
+        "read_file",
+        "exec:cat",
+        "substring:0..1",
+        "replace_literal:a:b",
+        "replace:s/a/b/",
+        "append:x",
+        "prepend:x",
+        "surround:\"",
+        "upper",
+        "lower",
+        "trim_prefix:a",
+        "trim_suffix:a",
+        "trim_regex:a",
+        "trim",
+        "squeeze",
+        "pad:3",
+        "center:3",
+        "reverse",
+        "len",
+        "split:-:0",
+        "join:-",
+        "output_sep:-",
+        "filter:a",
+        "filter_not:a",
+        "regex_extract:a",
+        "kv:a",
+    ];
+
+    for op in string_ops {
+        let template = format!("{{split:,:..|map:{{{op}}}}}");
+        assert!(Template::parse(&template).is_ok(), "{template}");
+    }
+}
+
+#[test]
+fn test_map_inner_string_operation_after_list_requires_join() {
+    for list_op in [
+        "split:-:..",
+        "partition:-",
+        "rpartition:-",
+        "kv_keys",
+        "kv_values",
+    ] {
+        let err = Template::parse(&format!("{{split:,:..|map:{{{list_op}|upper}}}}")).unwrap_err();
+        assert!(
+            err.contains("inner operation 2 (`upper`): `upper` inside map requires join first"),
+            "{list_op}: {err}"
+        );
+
+        let template = format!("{{split:,:..|map:{{{list_op}|join:-|upper}}}}");
+        assert!(Template::parse(&template).is_ok(), "{template}");
+    }
+}
+
+#[test]
+fn test_key_pipeline_errors_name_parent_operation() {
+    let err = Template::parse("{split:,:..|sort_by:{sort}}").unwrap_err();
+    assert!(err.contains("`sort` inside sort_by requires split first"));
+
+    let err = Template::parse("{split:,:..|unique_by:{unique}}").unwrap_err();
+    assert!(err.contains("`unique` inside unique_by requires split first"));
+
+    let err = Template::parse("{split:,:..|filter_by:{split:-:..|lower}:a}").unwrap_err();
+    assert!(err.contains("`lower` inside filter_by requires join first"));
+}
+
+#[test]
+fn test_map_inner_list_pipeline_accepted() {
+    let template = Template::parse("{split:,:..|map:{split: :..|sort|join: }}").unwrap();
+    assert_eq!(template.format("b a,d c").unwrap(), "a b,c d");
+}
+
+#[test]
+fn test_split_index_yields_string() {
+    assert!(Template::parse("{split:,:0|upper}").is_ok());
+    assert!(Template::parse("{split:,:0|sort}").is_err());
+}
+
+#[test]
+fn test_type_preserving_operations_keep_input_type() {
+    assert!(Template::parse("{split:,:..|filter:a|reverse|sort}").is_ok());
+    assert!(Template::parse("{filter:a|reverse|sort}").is_err());
+    assert!(Template::parse("{join:-|upper}").is_ok());
+}
+
+#[test]
+fn test_error_names_template_section() {
+    let err = Template::parse("A: {upper} B: {unique}").unwrap_err();
+    assert!(err.contains("operation 1 (`unique`)"));
+    assert!(err.contains("template section 2"));
+}
+
+#[test]
+fn test_parse_with_debug_type_checks() {
+    assert!(Template::parse_with_debug("{split:,:..|lower}", Some(false)).is_err());
+}
+
+#[test]
+fn test_parse_unchecked_defers_type_errors() {
+    let template = Template::parse_unchecked("{upper|sort}").unwrap();
+    assert!(template.check_types().is_err());
+    assert!(template.format("hello").is_err());
+}
@@ -1,4 +1,14 @@
-use string_pipeline::{SectionType, Template};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use string_pipeline::{
+    CacheScope, CaseLocale, ChecksumAlgorithm, ColumnAlign, DebugFormat, DebugLevel, DebugSink,
+    ErrorPolicy, FormatContext, HighlightStyle, LengthUnit, Limits, LineEndings, LintKind,
+    PadDirection, ParserConfig, PathStyle, Pipeline, ProfilingReport, Range, RangeSpec,
+    SafetyProfile, Section, SectionType, SortDirection, StringOp, Template, TemplateBuilder,
+    TemplateSet, TermColor, TokenKind, TrimDirection, ValueKind, cache_stats, operation,
+    operations, operations_json, register_alias, tokenize,
+};
+use string_pipeline::{InputRef, Placeholder, Variable};
 
 #[test]
 fn test_template_literal_text_only() {
@@ -86,6 +96,572 @@ fn test_template_debug_mode() {
     assert_eq!(result, "Debug: TEST Normal: test");
 }
 
+fn capture_sink() -> (DebugSink, Arc<Mutex<Vec<String>>>) {
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let captured = Arc::clone(&lines);
+    let sink = DebugSink::from_fn(move |line| captured.lock().unwrap().push(line.to_string()));
+    (sink, lines)
+}
+
+#[test]
+fn test_template_debug_sink_text() {
+    let (sink, lines) = capture_sink();
+    let template = Template::parse("{split:,:..|map:{upper}|join:-}")
+        .unwrap()
+        .with_debug(true)
+        .with_debug_sink(sink);
+
+    assert_eq!(template.format("a,b").unwrap(), "A-B");
+
+    let lines = lines.lock().unwrap();
+    assert!(!lines.is_empty());
+    assert!(lines.iter().all(|l| l.starts_with("DEBUG: ")));
+    // Sub-pipeline lines from map go to the same sink
+    assert!(lines.iter().any(|l| l.contains("SUB-PIPELINE")));
+}
+
+#[test]
+fn test_template_debug_sink_json() {
+    let (sink, lines) = capture_sink();
+    let template = Template::parse("Value: {upper}")
+        .unwrap()
+        .with_debug(true)
+        .with_debug_format(DebugFormat::Json)
+        .with_debug_sink(sink);
+
+    assert_eq!(template.format("hi").unwrap(), "Value: HI");
+
+    let lines = lines.lock().unwrap();
+    assert!(lines.iter().all(|l| l.starts_with("{\"event\":")));
+    assert!(
+        lines
+            .iter()
+            .any(|l| l.contains("\"event\":\"operation\"") && l.contains("\"operation\":\"upper\""))
+    );
+}
+
+#[test]
+fn test_template_debug_reports_render_separator() {
+    let (sink, lines) = capture_sink();
+    let template = Template::parse("{split:,:..|sort|output_sep:+}")
+        .unwrap()
+        .with_debug(true)
+        .with_debug_sink(sink);
+
+    assert_eq!(template.format("b,a").unwrap(), "a+b");
+    let lines = lines.lock().unwrap();
+    assert!(
+        lines
+            .iter()
+            .any(|l| l.contains("Rendered with separator: \"+\""))
+    );
+
+    let (sink, lines) = capture_sink();
+    let template = Template::parse("{split:,:..}")
+        .unwrap()
+        .with_debug(true)
+        .with_debug_format(DebugFormat::Json)
+        .with_debug_sink(sink);
+
+    assert_eq!(template.format("a,b").unwrap(), "a,b");
+    let lines = lines.lock().unwrap();
+    assert!(
+        lines
+            .iter()
+            .any(|l| l.contains("\"event\":\"pipeline_end\"") && l.contains("\"join_sep\":\",\""))
+    );
+}
+
+#[test]
+fn test_template_debug_sink_requires_debug() {
+    let (sink, lines) = capture_sink();
+    let template = Template::parse("{upper}").unwrap().with_debug_sink(sink);
+
+    assert_eq!(template.format("hi").unwrap(), "HI");
+    assert!(lines.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_template_debug_sink_writer() {
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let buf = SharedBuf::default();
+    let mut template = Template::parse("{lower}").unwrap().with_debug(true);
+    template.set_debug_sink(Some(DebugSink::from_writer(buf.clone())));
+
+    assert_eq!(template.format("HI").unwrap(), "hi");
+
+    let written = String::from_utf8(buf.0.lock().unwrap().clone()).unwrap();
+    assert!(written.ends_with('\n'));
+    assert!(written.lines().all(|l| l.starts_with("DEBUG: ")));
+}
+
+#[test]
+fn test_template_debug_levels() {
+    let trace = |level: DebugLevel| {
+        let (sink, lines) = capture_sink();
+        let template = Template::parse("{split:,:..|map:{upper}}")
+            .unwrap()
+            .with_debug(true)
+            .with_debug_level(level)
+            .with_debug_sink(sink);
+        assert_eq!(template.format("a,b").unwrap(), "A,B");
+        let lines = lines.lock().unwrap().clone();
+        move |needle: &str| lines.iter().any(|l| l.contains(needle))
+    };
+
+    let summary = trace(DebugLevel::Summary);
+    assert!(summary("PIPELINE START: 2 operations"));
+    assert!(summary("Final result"));
+    assert!(!summary("Step 1"));
+    assert!(!summary("Input: List"));
+    assert!(!summary("Result: List"));
+    assert!(!summary("Item 1/2"));
+
+    let ops = trace(DebugLevel::Ops);
+    assert!(ops("Step 1: Split"));
+    assert!(ops("Step 2: Map"));
+    assert!(ops("MAP COMPLETED: 2 → 2 items"));
+    assert!(!ops("Item 1/2"));
+    assert!(!ops("SUB-PIPELINE"));
+
+    let full = trace(DebugLevel::Full);
+    assert!(full("Item 1/2"));
+    assert!(full("SUB-PIPELINE"));
+    assert!(full("Step 1: Upper"));
+}
+
+#[test]
+fn test_template_debug_levels_json() {
+    let (sink, lines) = capture_sink();
+    let template = Template::parse("{split:,:..|map:{upper}}")
+        .unwrap()
+        .with_debug(true)
+        .with_debug_format(DebugFormat::Json)
+        .with_debug_level(DebugLevel::Summary)
+        .with_debug_sink(sink);
+
+    assert_eq!(template.format("a,b").unwrap(), "A,B");
+    let lines = lines.lock().unwrap();
+    assert!(
+        lines
+            .iter()
+            .any(|l| l.contains("\"event\":\"pipeline_end\""))
+    );
+    assert!(!lines.iter().any(|l| l.contains("\"event\":\"operation\"")));
+    assert!(
+        !lines
+            .iter()
+            .any(|l| l.contains("\"event\":\"map_item_start\""))
+    );
+    assert!(!lines.iter().any(|l| l.contains("\"preview\"")));
+}
+
+#[test]
+fn test_template_debug_preview_chars() {
+    let (sink, lines) = capture_sink();
+    let template = Template::parse("{upper}")
+        .unwrap()
+        .with_debug(true)
+        .with_debug_preview_chars(5)
+        .with_debug_sink(sink);
+
+    assert_eq!(template.format("abcdefgh").unwrap(), "ABCDEFGH");
+    let lines = lines.lock().unwrap();
+    assert!(lines.iter().any(|l| l.ends_with("Input: \"abcde...\"")));
+    assert!(lines.iter().any(|l| l.ends_with("Result: String(ABCDE..)")));
+    assert!(!lines.iter().any(|l| l.contains("abcdef")));
+
+    // Previews are cut on character boundaries
+    let (sink, lines) = capture_sink();
+    let template = Template::parse("{split:,:..}")
+        .unwrap()
+        .with_debug(true)
+        .with_debug_format(DebugFormat::Json)
+        .with_debug_preview_chars(2)
+        .with_debug_sink(sink);
+
+    assert_eq!(template.format("€€€,ü").unwrap(), "€€€,ü");
+    let lines = lines.lock().unwrap();
+    assert!(
+        lines
+            .iter()
+            .any(|l| l.contains("\"preview\":[\"€€...\",\"ü\"]"))
+    );
+}
+
+#[test]
+fn test_template_profiling_counts_operations() {
+    let template = Template::parse("{split:,:..|map:{trim|upper}|join:-}")
+        .unwrap()
+        .with_profiling(true);
+
+    assert_eq!(template.format("a, b ,c").unwrap(), "A-B-C");
+    assert_eq!(template.format("x,y").unwrap(), "X-Y");
+
+    let report = template.profiling_report().unwrap();
+    assert_eq!(report.get("split").unwrap().invocations, 2);
+    assert_eq!(report.get("map").unwrap().invocations, 2);
+    assert_eq!(report.get("trim").unwrap().invocations, 5);
+    assert_eq!(report.get("upper").unwrap().invocations, 5);
+    assert_eq!(report.get("join").unwrap().invocations, 2);
+    assert!(report.get("sort").is_none());
+
+    // Slowest operations come first
+    let totals: Vec<_> = report.operations().iter().map(|p| p.total_time).collect();
+    assert!(totals.windows(2).all(|w| w[0] >= w[1]));
+}
+
+#[test]
+fn test_template_profiling_fast_paths() {
+    let template = Template::parse("{split:,:1} {split:,:..|join:-}")
+        .unwrap()
+        .with_profiling(true);
+
+    assert_eq!(template.format("a,b").unwrap(), "b a-b");
+
+    let report = template.profiling_report().unwrap();
+    assert_eq!(report.get("split").unwrap().invocations, 1);
+    assert_eq!(report.get("split+join").unwrap().invocations, 1);
+}
+
+#[test]
+fn test_template_profiling_fused_plans() {
+    let template = Template::parse("{split:,:..|slice:1..|join:-} {trim|upper|append:!}")
+        .unwrap()
+        .with_profiling(true);
+
+    assert_eq!(template.format(" a,b,c ").unwrap(), "b-c  A,B,C!");
+
+    let report = template.profiling_report().unwrap();
+    assert_eq!(report.get("split+slice+join").unwrap().invocations, 1);
+    // String chains are reported operation by operation while profiling
+    assert_eq!(report.get("trim").unwrap().invocations, 1);
+    assert_eq!(report.get("upper").unwrap().invocations, 1);
+    assert_eq!(report.get("append").unwrap().invocations, 1);
+}
+
+#[test]
+fn test_template_profiling_disabled_and_reset() {
+    let template = Template::parse("{upper}").unwrap();
+    assert!(!template.is_profiling());
+    assert!(template.profiling_report().is_none());
+
+    let template = template.with_profiling(true);
+    template.format("a").unwrap();
+    assert!(!template.profiling_report().unwrap().is_empty());
+
+    template.reset_profiling();
+    assert!(template.profiling_report().unwrap().is_empty());
+
+    let template = template.with_profiling(false);
+    template.format("a").unwrap();
+    assert!(template.profiling_report().is_none());
+}
+
+#[test]
+fn test_profiling_report_merge() {
+    let first = Template::parse("{upper}").unwrap().with_profiling(true);
+    let second = Template::parse("{upper|lower}")
+        .unwrap()
+        .with_profiling(true);
+    first.format("a").unwrap();
+    second.format("a").unwrap();
+
+    let mut report = ProfilingReport::default();
+    report.merge(&first.profiling_report().unwrap());
+    report.merge(&second.profiling_report().unwrap());
+
+    assert_eq!(report.get("upper").unwrap().invocations, 2);
+    assert_eq!(report.get("lower").unwrap().invocations, 1);
+    assert_eq!(report.operations().len(), 2);
+}
+
+#[test]
+fn test_template_limits_regex_size() {
+    let pattern_template = "{replace:s/\\w{30}z/x/}";
+    let input = "hello";
+
+    // Compiling the pattern without limits first must not let the limited
+    // template reuse the unlimited compilation
+    assert_eq!(
+        Template::parse(pattern_template)
+            .unwrap()
+            .format(input)
+            .unwrap(),
+        "hello"
+    );
+
+    let limited = Template::parse(pattern_template)
+        .unwrap()
+        .with_limits(Limits {
+            max_regex_size: Some(1024),
+            ..Limits::default()
+        });
+    let err = limited.format(input).unwrap_err();
+    assert!(err.starts_with("Limit exceeded:"), "{err}");
+
+    let roomy = Template::parse(pattern_template)
+        .unwrap()
+        .with_limits(Limits {
+            max_regex_size: Some(10 << 20),
+            max_dfa_size: Some(1 << 20),
+            ..Limits::default()
+        });
+    assert_eq!(roomy.format(input).unwrap(), "hello");
+}
+
+#[test]
+fn test_template_limits_invalid_regex_is_not_a_limit_error() {
+    let template = Template::parse("{split:,:..|filter:[a-}")
+        .unwrap()
+        .with_limits(Limits {
+            max_regex_size: Some(1024),
+            ..Limits::default()
+        });
+    let err = template.format("a,b").unwrap_err();
+    assert!(err.starts_with("Invalid regex"), "{err}");
+}
+
+#[test]
+fn test_template_limits_are_not_caught_by_try() {
+    let limits = Limits {
+        max_output_len: Some(4),
+        ..Limits::default()
+    };
+    for template in [
+        "{try:{append:xxxxxx}:ok}",
+        "{split:,:..|map:{try:{append:xxxxxx}}}",
+        "{split:,:..|map_fallible:{append:xxxxxx}}",
+    ] {
+        let template = Template::parse(template).unwrap().with_limits(limits);
+        let err = template.format("a").unwrap_err();
+        assert!(err.starts_with("Limit exceeded:"), "{err}");
+    }
+}
+
+#[test]
+fn test_template_error_policy_collect_all() {
+    let template = Template::parse("{split:,:..|map:{trim|ordinal}|join:,}").unwrap();
+    assert_eq!(template.error_policy(), ErrorPolicy::FailFast);
+    let err = template.format("1,x,3,y").unwrap_err();
+    assert!(err.starts_with("Invalid integer: 'x'"), "{err}");
+
+    let template = template.with_error_policy(ErrorPolicy::CollectAll);
+    assert_eq!(template.format("1,2").unwrap(), "1st,2nd");
+    let err = template.format("1, x,3,y").unwrap_err();
+    assert!(
+        err.starts_with(
+            "2 of 4 items failed:\n  item 2 (`ordinal`): Invalid integer: 'x'\n  item 4 (`ordinal`): Invalid integer: 'y'"
+        ),
+        "{err}"
+    );
+    assert!(err.contains("in operation `map`"), "{err}");
+
+    let template = Template::parse("{split:,:..|sort_by:{ordinal}}")
+        .unwrap()
+        .with_error_policy(ErrorPolicy::CollectAll);
+    let err = template.format("a,1,b").unwrap_err();
+    assert!(
+        err.starts_with("2 of 3 items failed:\n  item 1 (`ordinal`)"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_template_error_policy_truncates_long_reports() {
+    let template = Template::parse("{split:,:..|map:{ordinal}}")
+        .unwrap()
+        .with_error_policy(ErrorPolicy::CollectAll);
+    let input = vec!["x"; 13].join(",");
+    let err = template.format(&input).unwrap_err();
+    assert!(err.starts_with("13 of 13 items failed:"), "{err}");
+    assert!(err.contains("item 10 (`ordinal`)"), "{err}");
+    assert!(!err.contains("item 11"), "{err}");
+    assert!(err.contains("\n  ... and 3 more"), "{err}");
+}
+
+#[test]
+fn test_template_error_policy_keeps_limit_errors() {
+    let limits = Limits {
+        max_output_len: Some(4),
+        ..Limits::default()
+    };
+    let template = Template::parse("{split:,:..|map:{append:xxxxxx}}")
+        .unwrap()
+        .with_limits(limits)
+        .with_error_policy(ErrorPolicy::CollectAll);
+    let err = template.format("a,b").unwrap_err();
+    assert!(err.starts_with("Limit exceeded:"), "{err}");
+}
+
+#[test]
+fn test_template_limits_timeout() {
+    let expired = Limits {
+        step_timeout: Some(Duration::ZERO),
+        ..Limits::default()
+    };
+
+    for template in [
+        "{upper}",
+        "{split:,:..|map:{trim|upper}|join:,}",
+        "a {split:,:0} b",
+    ] {
+        let template = Template::parse(template).unwrap().with_limits(expired);
+        let err = template.format("a,b").unwrap_err();
+        assert!(err.starts_with("Limit exceeded:"), "{err}");
+    }
+
+    let template = Template::parse("{upper}").unwrap().with_limits(expired);
+    assert!(
+        template
+            .format_with_inputs(&[&["a"]], &[" "])
+            .unwrap_err()
+            .starts_with("Limit exceeded:")
+    );
+
+    // The timeout applies per format call, not to the template's lifetime
+    let template = Template::parse("{split:,:..|map:{upper}|join:,}")
+        .unwrap()
+        .with_limits(Limits {
+            step_timeout: Some(Duration::from_secs(60)),
+            ..Limits::default()
+        });
+    assert_eq!(template.format("a,b").unwrap(), "A,B");
+    assert_eq!(template.format("c,d").unwrap(), "C,D");
+}
+
+#[test]
+fn test_template_limits_accessors() {
+    let mut template = Template::parse("{upper}").unwrap();
+    assert!(template.limits().is_none());
+
+    let limits = Limits {
+        max_regex_size: Some(4096),
+        ..Limits::default()
+    };
+    template.set_limits(Some(limits));
+    assert_eq!(template.limits(), Some(&limits));
+    assert_eq!(template.clone().limits(), Some(&limits));
+
+    template.set_limits(None);
+    assert!(template.limits().is_none());
+}
+
+#[test]
+fn test_template_limits_split_items_and_output_len() {
+    let limits = Limits {
+        max_split_items: Some(3),
+        max_output_len: Some(8),
+        ..Limits::default()
+    };
+
+    // Split+join would normally take a fused fast path that skips the checks.
+    let template = Template::parse("{split:,:..|join:-}")
+        .unwrap()
+        .with_limits(limits);
+    assert_eq!(template.format("a,b,c").unwrap(), "a-b-c");
+    let err = template.format("a,b,c,d").unwrap_err();
+    assert!(err.starts_with("Limit exceeded:"), "{err}");
+    assert!(err.contains("4 items"), "{err}");
+
+    let template = Template::parse("{pad:20}").unwrap().with_limits(limits);
+    let err = template.format("abc").unwrap_err();
+    assert!(err.starts_with("Limit exceeded:"), "{err}");
+
+    // Each section fits, but the rendered output does not.
+    let template = Template::parse("{upper}-{lower}")
+        .unwrap()
+        .with_limits(limits);
+    assert_eq!(template.format("abc").unwrap(), "ABC-abc");
+    let err = template.format("abcde").unwrap_err();
+    assert!(err.contains("output of 11 bytes"), "{err}");
+
+    let err = template
+        .format_with_inputs(&[&["abcde"], &["abcde"]], &[" ", " "])
+        .unwrap_err();
+    assert!(err.starts_with("Limit exceeded:"), "{err}");
+}
+
+#[test]
+fn test_template_parse_untrusted_static_caps() {
+    let profile = SafetyProfile {
+        max_operations: 4,
+        max_map_depth: 0,
+        max_output_len: 16,
+        max_regex_size: 32 * 1024,
+        ..SafetyProfile::default()
+    };
+
+    let err = Template::parse_untrusted("{trim} {upper|lower} {append:!|prepend:>}", &profile)
+        .unwrap_err();
+    assert!(err.contains("more than 4 operations"), "{err}");
+
+    let err = Template::parse_untrusted("{split:,:..|map:{upper}|join:,}", &profile).unwrap_err();
+    assert!(err.contains("`map` nested deeper than 0"), "{err}");
+
+    let err = Template::parse_untrusted("{pad:100}", &profile).unwrap_err();
+    assert!(err.contains("pad width 100"), "{err}");
+
+    for template in [
+        "{replace:s/\\w{30}z/x/}",
+        "{split:,:..|filter:\\w{30}z|join:,}",
+        "{regex_extract:\\w{30}z}",
+        "{split:,:..|sort_by:{regex_extract:\\w{30}z}|join:,}",
+        "{split:,:..|unique_by:{regex_extract:\\w{30}z}|join:,}",
+        "{split:,:..|filter_by:{upper}:\\w{30}z|join:,}",
+    ] {
+        let err = Template::parse_untrusted(template, &profile).unwrap_err();
+        assert!(
+            err.starts_with("Limit exceeded: regex"),
+            "{template}: {err}"
+        );
+    }
+
+    let err = Template::parse_untrusted("{split:,:..|diff:@/etc/passwd}", &profile).unwrap_err();
+    assert!(err.contains("reading files is not allowed"), "{err}");
+    let err = Template::parse_untrusted("{split:,:..|map:{read_file}}", &profile).unwrap_err();
+    assert!(err.contains("reading files is not allowed"), "{err}");
+    let err = Template::parse_untrusted("{exec:cat}", &profile).unwrap_err();
+    assert!(err.contains("running commands is not allowed"), "{err}");
+    assert!(Template::parse_untrusted("{split:,:..|diff:a,b|join:,}", &profile).is_ok());
+
+    // Ordinary parse and type errors are reported unchanged.
+    assert!(
+        !Template::parse_untrusted("{upper|sort}", &profile)
+            .unwrap_err()
+            .starts_with("Limit exceeded:")
+    );
+}
+
+#[test]
+fn test_template_parse_untrusted_enforces_limits() {
+    let profile = SafetyProfile {
+        max_split_items: 2,
+        ..SafetyProfile::default()
+    };
+
+    let template = Template::parse_untrusted("{split:,:..|map:{upper}|join:,}", &profile).unwrap();
+    assert_eq!(template.limits(), Some(&profile.limits()));
+    assert_eq!(template.format("a,b").unwrap(), "A,B");
+    let err = template.format("a,b,c").unwrap_err();
+    assert!(err.starts_with("Limit exceeded:"), "{err}");
+
+    let defaults = SafetyProfile::default();
+    let template = Template::parse_untrusted("{split:,:..|map:{trim}|join:,}", &defaults).unwrap();
+    assert_eq!(template.format(" a , b ").unwrap(), "a,b");
+}
+
 #[test]
 fn test_template_display_trait() {
     // Test Display implementation shows original template string
@@ -109,6 +685,127 @@ fn test_template_section_counts() {
     assert_eq!(template.template_section_count(), 2); // upper and lower operations
 }
 
+#[test]
+fn test_template_complexity_metrics() {
+    let template = Template::parse("A {upper} B").unwrap();
+    assert_eq!(template.operation_count(), 1);
+    assert_eq!(template.max_map_depth(), 0);
+    assert!(!template.uses_regex());
+
+    let template = Template::parse(
+        "{split:,:..|sort_by:{trim|lower}|filter_by:{upper}:^A|map:{trim}} {lower}",
+    )
+    .unwrap();
+    assert_eq!(template.operation_count(), 9);
+    assert_eq!(template.max_map_depth(), 1);
+    assert!(template.uses_regex());
+    assert!(
+        !Template::parse("{split:,:..|filter_by:{upper}}")
+            .unwrap()
+            .uses_regex()
+    );
+
+    let template = Template::parse("{each:{split:,:..}:{try:{regex_extract:\\d+}}}").unwrap();
+    assert_eq!(template.operation_count(), 4);
+    assert_eq!(template.max_map_depth(), 1);
+    assert!(template.uses_regex());
+
+    // The metrics match what a safety profile checks
+    let profile = SafetyProfile {
+        max_operations: template.operation_count(),
+        max_map_depth: template.max_map_depth(),
+        ..SafetyProfile::default()
+    };
+    assert!(Template::parse_untrusted(template.template_string(), &profile).is_ok());
+}
+
+#[test]
+fn test_template_set_parse() {
+    let set = TemplateSet::parse(
+        "# Shared pipelines\n\
+         \n\
+         shout = {upper|append:!}\n\
+           csv.first={split:,:0}  \n\
+         pairs = {split:,:..|map:{kv:a:;:=}|join:-}\n",
+    )
+    .unwrap();
+    assert_eq!(set.len(), 3);
+    assert_eq!(
+        set.names().collect::<Vec<_>>(),
+        ["csv.first", "pairs", "shout"]
+    );
+    assert!(set.contains("shout"));
+    assert_eq!(set.get("shout").unwrap().format("hi").unwrap(), "HI!");
+    assert_eq!(set.get("csv.first").unwrap().format("a,b").unwrap(), "a");
+    assert_eq!(set.get("pairs").unwrap().format("a=1,a=2").unwrap(), "1-2");
+    assert_eq!(
+        set.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+        ["csv.first", "pairs", "shout"]
+    );
+    assert!(TemplateSet::parse("# nothing here\n").unwrap().is_empty());
+}
+
+#[test]
+fn test_template_set_parse_errors() {
+    let err = TemplateSet::parse("ok = {upper}\nbroken = {upper|sort}").unwrap_err();
+    assert!(err.starts_with("Line 2: template 'broken': "), "{err}");
+
+    let err = TemplateSet::parse("a = {upper}\n\na = {lower}").unwrap_err();
+    assert_eq!(err, "Line 3: template 'a' is already defined on line 1");
+
+    let err = TemplateSet::parse("{upper}").unwrap_err();
+    assert_eq!(err, "Line 1: expected NAME = TEMPLATE");
+
+    let err = TemplateSet::parse("two words = {upper}").unwrap_err();
+    assert_eq!(err, "Line 1: invalid template name 'two words'");
+}
+
+#[test]
+fn test_template_set_load() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    std::io::Write::write_all(&mut file, b"wrap = <<upper>> {lower}\n").unwrap();
+
+    let set = TemplateSet::load(file.path()).unwrap();
+    assert_eq!(set.get("wrap").unwrap().format("A").unwrap(), "<<upper>> a");
+
+    let config = ParserConfig {
+        delimiters: Some(("<<".to_string(), ">>".to_string())),
+        ..ParserConfig::default()
+    };
+    let set = TemplateSet::load_with_config(file.path(), &config).unwrap();
+    assert_eq!(set.get("wrap").unwrap().format("a").unwrap(), "A {lower}");
+
+    let err = TemplateSet::load("/nonexistent/pack.templates").unwrap_err();
+    assert!(err.starts_with("Failed to read template file"), "{err}");
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn test_template_set_toml() {
+    let set = TemplateSet::parse_toml(
+        "shout = \"{upper}\"\n\n[csv]\nfirst = '{split:,:0}'\nlast = { value = '{split:,:-1}' }\n",
+    )
+    .unwrap();
+    assert_eq!(
+        set.names().collect::<Vec<_>>(),
+        ["csv.first", "csv.last.value", "shout"]
+    );
+    assert_eq!(
+        set.get("csv.last.value").unwrap().format("a,b").unwrap(),
+        "b"
+    );
+
+    let mut file = tempfile::Builder::new().suffix(".toml").tempfile().unwrap();
+    std::io::Write::write_all(&mut file, b"name = '{trim}'\n").unwrap();
+    let set = TemplateSet::load(file.path()).unwrap();
+    assert_eq!(set.get("name").unwrap().format(" a ").unwrap(), "a");
+
+    let err = TemplateSet::parse_toml("count = 3").unwrap_err();
+    assert_eq!(err, "Template 'count' is not a string");
+    let err = TemplateSet::parse_toml("[a]\nb = '{upper|sort}'").unwrap_err();
+    assert!(err.starts_with("Template 'a.b': "), "{err}");
+}
+
 #[test]
 fn test_template_special_characters() {
     // Test templates with special characters in literal text
@@ -767,6 +1464,83 @@ fn test_format_rich_literal_only_template() {
     assert!(result.template_outputs.is_empty());
 }
 
+fn section_parts(template: &str, input: &str) -> Vec<(Option<usize>, String)> {
+    Template::parse(template)
+        .unwrap()
+        .format_sections(input)
+        .unwrap()
+        .into_iter()
+        .map(|section| {
+            assert_eq!(section.is_template(), section.template_position.is_some());
+            (section.template_position, section.text)
+        })
+        .collect()
+}
+
+#[test]
+fn test_format_sections_keeps_section_kinds() {
+    let sections = Template::parse("Name: {split: :0|upper}!")
+        .unwrap()
+        .format_sections("ada lovelace")
+        .unwrap();
+    assert_eq!(sections.len(), 3);
+    assert_eq!(sections[0].section_type, SectionType::Literal);
+    assert_eq!(sections[0].text, "Name: ");
+    assert_eq!(sections[1].section_type, SectionType::Template);
+    assert_eq!(sections[1].text, "ADA");
+    assert_eq!(sections[2].text, "!");
+
+    assert_eq!(
+        section_parts("{split:,:0}{split:,:1}-{trim}", "a,b"),
+        [
+            (Some(0), "a".to_string()),
+            (Some(1), "b".to_string()),
+            (None, "-".to_string()),
+            (Some(2), "a,b".to_string()),
+        ]
+    );
+    assert_eq!(
+        section_parts("only text", "x"),
+        [(None, "only text".to_string())]
+    );
+    assert!(section_parts("", "x").is_empty());
+}
+
+#[test]
+fn test_format_sections_with_groups_and_empty_outputs() {
+    assert_eq!(
+        section_parts("x[? <{trim}>]y", "  "),
+        [
+            (None, "x".to_string()),
+            (Some(0), String::new()),
+            (None, "y".to_string()),
+        ]
+    );
+    assert_eq!(
+        section_parts("x[? <{trim}>]y", " a "),
+        [
+            (None, "x <".to_string()),
+            (Some(0), "a".to_string()),
+            (None, ">y".to_string()),
+        ]
+    );
+
+    let template = Template::parse("{upper} {lower}").unwrap();
+    let joined: String = template
+        .format_sections("Mix")
+        .unwrap()
+        .into_iter()
+        .map(|section| section.text)
+        .collect();
+    assert_eq!(joined, template.format("Mix").unwrap());
+    assert!(
+        Template::parse("{roman}")
+            .unwrap()
+            .format_sections("x")
+            .is_err()
+    );
+}
+
 #[test]
 fn test_format_rich_empty_template_section() {
     let template = Template::parse("keep {} here").unwrap();
@@ -889,6 +1663,81 @@ fn test_format_with_inputs_rich_error_propagation() {
     assert_eq!(rich_error, regular_error);
 }
 
+#[test]
+fn test_format_with_inputs_by_index() {
+    let template = Template::parse("cp {input=1} {input=0|upper}").unwrap();
+    let result = template
+        .format_with_inputs(&[&["dst"], &["src"]], &[" ", " "])
+        .unwrap();
+    assert_eq!(result, "cp src DST");
+
+    // Sections without a reference keep their position
+    let template = Template::parse("{input=0|lower} {}").unwrap();
+    let result = template
+        .format_with_inputs(&[&["A", "B"], &["x"]], &["+", " "])
+        .unwrap();
+    assert_eq!(result, "a+b x");
+}
+
+#[test]
+fn test_format_with_inputs_missing_reference() {
+    let template = Template::parse("{input=2|upper}").unwrap();
+    let error = template
+        .format_with_inputs(&[&["a"], &["b"]], &[" ", " "])
+        .unwrap_err();
+    assert_eq!(
+        error,
+        "Template section 0 references input 2, but 2 input(s) were given"
+    );
+
+    let template = Template::parse("{input=users}").unwrap();
+    let error = template.format_with_inputs(&[&["a"]], &[" "]).unwrap_err();
+    assert!(error.contains("use format_with_named_inputs"));
+}
+
+#[test]
+fn test_format_with_named_inputs() {
+    let template = Template::parse("{input=users|upper} -> {input=files} ({})").unwrap();
+    let result = template
+        .format_with_named_inputs(
+            &[("files", &["a.txt", "b.txt"]), ("users", &["ann", "bob"])],
+            &[",", " & "],
+        )
+        .unwrap();
+    // The last section reads the missing third input
+    assert_eq!(result, "ANN & BOB -> a.txt,b.txt ()");
+
+    let error = template
+        .format_with_named_inputs(&[("users", &["ann"])], &[])
+        .unwrap_err();
+    assert_eq!(
+        error,
+        "Template section 1 references input `files`, which was not given"
+    );
+}
+
+#[test]
+fn test_input_reference_ignored_by_format() {
+    let template = Template::parse("{input=users|upper}-{input=1}").unwrap();
+    assert_eq!(template.format("ann").unwrap(), "ANN-ann");
+}
+
+#[test]
+fn test_input_reference_parsing() {
+    let info = Template::parse("{!input=my_list-2|upper}")
+        .unwrap()
+        .get_section_info();
+    assert_eq!(info[0].input, Some(InputRef::Name("my_list-2".to_string())));
+
+    let info = Template::parse("{!# input = 3 | upper }")
+        .unwrap()
+        .get_section_info();
+    assert_eq!(info[0].input, Some(InputRef::Index(3)));
+
+    assert!(Template::parse("{input=|upper}").is_err());
+    assert!(Template::parse("{input=a b}").is_err());
+}
+
 // Tests for shell variable support (${...} patterns)
 
 #[test]
@@ -1018,3 +1867,2365 @@ fn test_template_shell_variable_complex_nesting() {
         "${DIR:-${HOME}/default} contains file1.txt and file2.txt"
     );
 }
+
+fn lint_kinds(template: &str) -> Vec<LintKind> {
+    Template::parse(template)
+        .unwrap()
+        .lint()
+        .into_iter()
+        .map(|warning| warning.kind)
+        .collect()
+}
+
+#[test]
+fn test_template_lint_clean_templates() {
+    for template in [
+        "{upper}",
+        "{split:,:..|map:{trim|upper}|sort|unique|join:,}",
+        "{split:,:..|sort|reverse|join:,}",
+        "{split:,:..|sort:desc|filter:a|join:,}",
+        "{split:,:..|join:,|upper}",
+        "Hello {split: :0} {lower}",
+    ] {
+        assert_eq!(lint_kinds(template), [], "{template}");
+    }
+}
+
+#[test]
+fn test_template_lint_redundant_operations() {
+    assert_eq!(lint_kinds("{upper|upper}"), [LintKind::Redundant]);
+    assert_eq!(
+        lint_kinds("{split:,:..|unique|unique}"),
+        [LintKind::Redundant]
+    );
+    assert_eq!(
+        lint_kinds("{split:,:..|sort|unique|filter:a|sort|join:,}"),
+        [LintKind::Redundant]
+    );
+    // A different direction overrides the first sort instead.
+    assert_eq!(
+        lint_kinds("{split:,:..|sort|sort:desc}"),
+        [LintKind::Overridden]
+    );
+    // Changing the items in between makes the second sort useful.
+    assert_eq!(lint_kinds("{split:,:..|sort|map:{upper}|sort}"), []);
+    // Repeating an operation with other arguments is not redundant.
+    assert_eq!(lint_kinds("{capitalize|capitalize:lower}"), []);
+    assert_eq!(lint_kinds("{with_ext:a|with_ext:b}"), []);
+    // An empty extension strips one more extension each time.
+    assert_eq!(lint_kinds("{with_ext:|with_ext:}"), []);
+}
+
+#[test]
+fn test_template_lint_overridden_and_cancelling() {
+    let warnings = Template::parse("{lower|upper}").unwrap().lint();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].kind, LintKind::Overridden);
+    assert_eq!(warnings[0].operation, "lower");
+    assert_eq!(warnings[0].operation_path, [0]);
+
+    assert_eq!(
+        lint_kinds("{split:,:..|shuffle|sort|join:,}"),
+        [LintKind::Overridden]
+    );
+    assert_eq!(
+        lint_kinds("{split:,:..|reverse|reverse}"),
+        [LintKind::Cancelling]
+    );
+}
+
+#[test]
+fn test_template_lint_no_ops() {
+    assert_eq!(
+        lint_kinds("{append:|replace_literal:a:a}"),
+        [LintKind::NoOp, LintKind::NoOp]
+    );
+    assert_eq!(lint_kinds("{split:,:..|slice:..}"), [LintKind::NoOp]);
+    assert_eq!(
+        lint_kinds("{split:,:..|map:{prepend:|substring:..}|join:,}"),
+        [LintKind::NoOpMap]
+    );
+}
+
+#[test]
+fn test_template_lint_join_and_filter() {
+    assert_eq!(lint_kinds("{split:,:1|join:-}"), [LintKind::UselessJoin]);
+    assert_eq!(
+        lint_kinds("{split:,:..|join:,|trim|filter:x}"),
+        [LintKind::FilterAfterJoin]
+    );
+    assert_eq!(
+        lint_kinds("{split:,:..|join:,|filter_prefix:x}"),
+        [LintKind::FilterAfterJoin]
+    );
+    // `filter` on a string that was not joined is deliberate.
+    assert_eq!(lint_kinds("{filter:x}"), []);
+}
+
+#[test]
+fn test_template_lint_positions() {
+    let warnings = Template::parse("A {upper} B {split:,:..|map:{trim|lower|lower}|join:,}")
+        .unwrap()
+        .lint();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].template_position, 1);
+    assert_eq!(warnings[0].operation_path, [1, 2]);
+    assert_eq!(
+        warnings[0].to_string(),
+        "template section 2, operation 2.3 (`lower`): repeating `lower` has no effect"
+    );
+}
+
+fn token_texts(template: &str) -> Vec<(TokenKind, &str)> {
+    tokenize(template)
+        .into_iter()
+        .map(|token| (token.kind, &template[token.span]))
+        .collect()
+}
+
+#[test]
+fn test_tokenize_map_and_debug_flag() {
+    use TokenKind::*;
+    assert_eq!(
+        token_texts("{!split:,:..|map:{trim|append:!}|join:-}"),
+        [
+            (Brace, "{"),
+            (DebugFlag, "!"),
+            (OperationName, "split"),
+            (Separator, ":"),
+            (Argument, ","),
+            (Separator, ":"),
+            (Argument, ".."),
+            (Separator, "|"),
+            (OperationName, "map"),
+            (Separator, ":"),
+            (Brace, "{"),
+            (OperationName, "trim"),
+            (Separator, "|"),
+            (OperationName, "append"),
+            (Separator, ":"),
+            (Argument, "!"),
+            (Brace, "}"),
+            (Separator, "|"),
+            (OperationName, "join"),
+            (Separator, ":"),
+            (Argument, "-"),
+            (Brace, "}"),
+        ]
+    );
+}
+
+#[test]
+fn test_tokenize_input_reference() {
+    use TokenKind::*;
+    assert_eq!(
+        token_texts("{input=users|upper}"),
+        [
+            (Brace, "{"),
+            (InputRef, "input=users"),
+            (Separator, "|"),
+            (OperationName, "upper"),
+            (Brace, "}"),
+        ]
+    );
+}
+
+#[test]
+fn test_tokenize_variables() {
+    use TokenKind::*;
+    assert_eq!(
+        token_texts("{set:id:upper} {get:id|lower}{get:id}"),
+        [
+            (Brace, "{"),
+            (Variable, "set:id"),
+            (Separator, ":"),
+            (OperationName, "upper"),
+            (Brace, "}"),
+            (Literal, " "),
+            (Brace, "{"),
+            (Variable, "get:id"),
+            (Separator, "|"),
+            (OperationName, "lower"),
+            (Brace, "}"),
+            (Brace, "{"),
+            (Variable, "get:id"),
+            (Brace, "}"),
+        ]
+    );
+}
+
+#[test]
+fn test_tokenize_conditional_groups() {
+    use TokenKind::*;
+    assert_eq!(
+        token_texts("a] [?b: {upper}] c]"),
+        [
+            (Literal, "a] "),
+            (Group, "[?"),
+            (Literal, "b: "),
+            (Brace, "{"),
+            (OperationName, "upper"),
+            (Brace, "}"),
+            (Group, "]"),
+            (Literal, " c]"),
+        ]
+    );
+    assert_eq!(
+        token_texts("\\[?{upper}"),
+        [
+            (Literal, "\\[?"),
+            (Brace, "{"),
+            (OperationName, "upper"),
+            (Brace, "}"),
+        ]
+    );
+}
+
+#[test]
+fn test_tokenize_each_section() {
+    use TokenKind::*;
+    assert_eq!(
+        token_texts("{each:{split:,:..}:- {upper};}"),
+        [
+            (Brace, "{"),
+            (OperationName, "each"),
+            (Separator, ":"),
+            (Brace, "{"),
+            (OperationName, "split"),
+            (Separator, ":"),
+            (Argument, ","),
+            (Separator, ":"),
+            (Argument, ".."),
+            (Brace, "}"),
+            (Separator, ":"),
+            (Literal, "- "),
+            (Brace, "{"),
+            (OperationName, "upper"),
+            (Brace, "}"),
+            (Literal, ";"),
+            (Brace, "}"),
+        ]
+    );
+}
+
+#[test]
+fn test_tokenize_placeholders() {
+    use TokenKind::*;
+    assert_eq!(
+        token_texts("{input_len}{file_name|upper}"),
+        [
+            (Brace, "{"),
+            (Placeholder, "input_len"),
+            (Brace, "}"),
+            (Brace, "{"),
+            (Placeholder, "file_name"),
+            (Separator, "|"),
+            (OperationName, "upper"),
+            (Brace, "}"),
+        ]
+    );
+}
+
+#[test]
+fn test_tokenize_arguments() {
+    use TokenKind::*;
+    assert_eq!(
+        token_texts("{1..3|replace:s/a|b/c/g|filter:x\\|y|append:}"),
+        [
+            (Brace, "{"),
+            (Argument, "1..3"),
+            (Separator, "|"),
+            (OperationName, "replace"),
+            (Separator, ":"),
+            (Argument, "s/a|b/c/g"),
+            (Separator, "|"),
+            (OperationName, "filter"),
+            (Separator, ":"),
+            (Argument, "x\\|y"),
+            (Separator, "|"),
+            (OperationName, "append"),
+            (Separator, ":"),
+            (Brace, "}"),
+        ]
+    );
+    assert_eq!(
+        token_texts("{sort_by:{len}:num:desc}"),
+        [
+            (Brace, "{"),
+            (OperationName, "sort_by"),
+            (Separator, ":"),
+            (Brace, "{"),
+            (OperationName, "len"),
+            (Brace, "}"),
+            (Separator, ":"),
+            (Argument, "num"),
+            (Separator, ":"),
+            (Argument, "desc"),
+            (Brace, "}"),
+        ]
+    );
+}
+
+#[test]
+fn test_tokenize_literals_and_errors() {
+    use TokenKind::*;
+    assert_eq!(
+        token_texts("é ${HOME} {upper} {nope:1} end"),
+        [
+            (Literal, "é ${HOME} "),
+            (Brace, "{"),
+            (OperationName, "upper"),
+            (Brace, "}"),
+            (Literal, " "),
+            (Brace, "{"),
+            (Error, "nope:1"),
+            (Brace, "}"),
+            (Literal, " end"),
+        ]
+    );
+    assert_eq!(
+        token_texts("a {upper|lower"),
+        [(Literal, "a "), (Brace, "{"), (Error, "upper|lower")]
+    );
+    assert_eq!(token_texts(""), []);
+    assert_eq!(token_texts("{}"), [(Brace, "{"), (Brace, "}")]);
+}
+
+#[test]
+fn test_tokenize_covers_template() {
+    for template in [
+        "Name: {split: :0} Age: {split: :1|pad:3:0:left}",
+        "{split:,:..|filter_by:{split:=:1}:^[0-9]+$|sort|join:\\n}",
+        "{regex_extract:(\\w+)@(\\w+):1|surround:<:>}",
+        "{split:\\n:0..10;2,-1|map:{substring:0..3|upper}}",
+    ] {
+        let tokens = tokenize(template);
+        assert!(
+            tokens.iter().all(|token| token.kind != TokenKind::Error),
+            "{template}"
+        );
+        for pair in tokens.windows(2) {
+            assert_eq!(pair[0].span.end, pair[1].span.start, "{template}");
+        }
+        assert_eq!(tokens.first().unwrap().span.start, 0);
+        assert_eq!(tokens.last().unwrap().span.end, template.len());
+    }
+}
+
+#[test]
+fn test_runtime_error_names_operation() {
+    let template = Template::parse("{split:,:..|map:{regex_extract:[}}").unwrap();
+    let err = template.format("a,b").unwrap_err();
+    assert!(err.starts_with("Invalid regex"), "{err}");
+    assert!(
+        err.ends_with("(in operation `regex_extract` at chars 18..33)"),
+        "{err}"
+    );
+
+    let template = Template::parse("{trim|regex_extract:x[}").unwrap();
+    let err = template.format("a").unwrap_err();
+    assert!(
+        err.ends_with("(in operation `regex_extract` at chars 7..23)"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_chained_replace_is_one_operation() {
+    let chained = Template::parse("{replace:s/a/b/g;s|/|-|;s/c/d/2}").unwrap();
+    let separate = Template::parse("{replace:s/a/b/g|replace:s/\\//-/|replace:s/c/d/2}").unwrap();
+    for input in ["a/c c", "aa//cc", "ccc/a"] {
+        assert_eq!(chained.format(input), separate.format(input), "{input}");
+    }
+
+    // The rules remain one operation
+    assert_eq!(chained.operation_count(), 1);
+    assert_eq!(
+        chained.canonical_string(),
+        "{replace:s/a/b/g;s/\\//-/;s/c/d/2}"
+    );
+    let err = Template::parse("{replace:s/a/b/;s/c/d/|sort}").unwrap_err();
+    assert!(err.contains("operation 2 (`sort`)"), "{err}");
+    let config = ParserConfig {
+        max_operations: 2,
+        ..ParserConfig::default()
+    };
+    assert!(Template::parse_with_config("{replace:s/a/b/;s/c/d/;s/e/f/|upper}", &config).is_ok());
+
+    // An invalid rule points at the whole operation
+    let template = Template::parse("{upper|replace:s/a/b/;s/(/x/}").unwrap();
+    let err = template.format("a").unwrap_err();
+    assert!(
+        err.ends_with("(in operation `replace` at chars 8..29)"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_runtime_error_spans_in_later_sections() {
+    let template = Template::parse("x{upper}y{split:,:..|map:{trim|regex_extract:[}}").unwrap();
+    let err = template.format("a,b").unwrap_err();
+    assert!(
+        err.ends_with("(in operation `regex_extract` at chars 32..47)"),
+        "{err}"
+    );
+
+    // Positions count characters, not bytes
+    let template = Template::parse("é→{split:,:..|sort_by:{regex_extract:[}}").unwrap();
+    let err = template.format("a,b").unwrap_err();
+    assert!(
+        err.ends_with("(in operation `regex_extract` at chars 24..39)"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_runtime_error_spans_with_inputs() {
+    let template = Template::parse("{upper} {regex_extract:[}").unwrap();
+    let err = template
+        .format_with_inputs(&[&["a"], &["b"]], &[" ", " "])
+        .unwrap_err();
+    assert!(
+        err.ends_with("(in operation `regex_extract` at chars 10..25)"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_runtime_error_in_debug_trace() {
+    let (sink, lines) = capture_sink();
+    let template = Template::parse("{split:,:..|map:{regex_extract:[}}")
+        .unwrap()
+        .with_debug(true)
+        .with_debug_sink(sink);
+    assert!(template.format("a,b").is_err());
+    let lines = lines.lock().unwrap();
+    assert!(
+        lines.iter().any(|l| l.contains("❌ ERROR:")
+            && l.contains("in operation `regex_extract` at chars 18..33"))
+    );
+
+    let (sink, lines) = capture_sink();
+    let template = Template::parse("{regex_extract:[}")
+        .unwrap()
+        .with_debug(true)
+        .with_debug_format(DebugFormat::Json)
+        .with_debug_sink(sink);
+    assert!(template.format("a").is_err());
+    let lines = lines.lock().unwrap();
+    assert!(
+        lines
+            .iter()
+            .any(|l| l.starts_with("{\"event\":\"error\"") && l.contains("at chars 2..17"))
+    );
+}
+
+#[test]
+fn test_parser_config_template_length() {
+    let config = ParserConfig {
+        max_template_len: 16,
+        ..ParserConfig::default()
+    };
+    assert!(Template::parse_with_config("Hi {upper}!", &config).is_ok());
+    for template in ["{split:,:..|map:{upper}}", "Hello there, {upper}!"] {
+        let err = Template::parse_with_config(template, &config).unwrap_err();
+        assert_eq!(err, "Limit exceeded: template is longer than 16 bytes");
+    }
+
+    // The default bounds still reject oversized arguments
+    let template = format!("{{append:{}}}", "a".repeat(2 << 20));
+    let err = Template::parse(&template).unwrap_err();
+    assert!(err.starts_with("Limit exceeded:"), "{err}");
+}
+
+#[test]
+fn test_parser_config_nesting_depth() {
+    let config = ParserConfig {
+        max_nesting_depth: 2,
+        ..ParserConfig::default()
+    };
+    assert!(Template::parse_with_config("{split:,:..|map:{upper}}", &config).is_ok());
+    assert!(Template::parse_with_config("a {split:,:..|map:{upper}} ${HOME}", &config).is_ok());
+    for template in [
+        "{split:,:..|map:{regex_extract:a{2}}}",
+        "x {split:,:..|map:{regex_extract:a{2}}}",
+        "x ${a{b{c}}}",
+    ] {
+        let err = Template::parse_with_config(template, &config).unwrap_err();
+        assert_eq!(
+            err, "Limit exceeded: template braces nest deeper than 2 level(s)",
+            "{template}"
+        );
+    }
+
+    // Deeply nested braces fail cleanly instead of exhausting the stack
+    let deep = format!("{}{}", "{".repeat(100_000), "}".repeat(100_000));
+    for template in [deep.clone(), format!("x{deep}"), "{".repeat(100_000)] {
+        let err = Template::parse(&template).unwrap_err();
+        assert!(err.starts_with("Limit exceeded:"), "{err}");
+    }
+}
+
+#[test]
+fn test_parser_config_operations() {
+    let config = ParserConfig {
+        max_operations: 4,
+        ..ParserConfig::default()
+    };
+    assert!(Template::parse_with_config("{split:,:..|map:{upper}|join:-}", &config).is_ok());
+    for template in [
+        "{split:,:..|map:{trim|upper}|join:-}",
+        "{upper} {lower} {trim} {upper} {lower}",
+        "{split:,:..|sort_by:{lower|trim}|join:-}",
+    ] {
+        let err = Template::parse_with_config(template, &config).unwrap_err();
+        assert_eq!(
+            err, "Limit exceeded: template has more than 4 operations",
+            "{template}"
+        );
+    }
+
+    let template = format!("{{{}}}", vec!["upper"; 2000].join("|"));
+    assert!(Template::parse(&template).is_err());
+    assert!(
+        Template::parse_with_config(
+            &template,
+            &ParserConfig {
+                max_operations: 2000,
+                ..ParserConfig::default()
+            }
+        )
+        .is_ok()
+    );
+}
+
+#[test]
+fn test_verbose_syntax_marker() {
+    let template = Template::parse(
+        "{!#
+            split:,:..          # one item per field
+            | map:{
+                trim            # drop padding
+                | upper
+            }
+            | join:\\ -\\       # escaped spaces are kept
+        }",
+    )
+    .unwrap();
+    assert_eq!(template.format(" a, b ,c").unwrap(), "A - B - C");
+    assert!(!template.is_debug());
+
+    // Only the marked section is verbose
+    let template = Template::parse("Items: {!# split:,:.. | join:+ } | {split:,:0}").unwrap();
+    assert_eq!(template.format("a,b").unwrap(), "Items: a+b | a");
+
+    // A debug flag can follow the marker
+    let template = Template::parse_with_debug("{!#  !upper  # shout\n}", None).unwrap();
+    assert!(template.is_debug());
+    assert_eq!(template.format("hi").unwrap(), "HI");
+}
+
+#[test]
+fn test_verbose_syntax_config() {
+    let config = ParserConfig {
+        verbose_syntax: true,
+        ..ParserConfig::default()
+    };
+    let template = Template::parse_with_config(
+        "[{ split:,:..  # fields\n | sort:desc }] {split:\\#:1 | append:\\ \\#}",
+        &config,
+    )
+    .unwrap();
+    assert_eq!(template.format("b,a#c").unwrap(), "[b,a#c] c #");
+
+    // Quoted arguments keep their whitespace
+    let template = Template::parse("{!# split:': ':..   # fields\n | join:\" # \" }").unwrap();
+    assert_eq!(template.format("a: b").unwrap(), "a # b");
+
+    // Without the option whitespace is significant
+    assert!(Template::parse("{ split:,:.. | sort:desc }").is_err());
+    assert!(Template::parse("{!split:,:..|join:-}").is_ok());
+}
+
+#[test]
+fn test_verbose_syntax_error_spans() {
+    let template = Template::parse("x {!#\n  split:,:..\n  | map:{ regex_extract:[ }\n}").unwrap();
+    let err = template.format("a,b").unwrap_err();
+    assert!(
+        err.ends_with("(in operation `regex_extract` at chars 30..45)"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_custom_delimiters() {
+    let template = Template::parse_with_delimiters(
+        "fn <<lower>>() { return <<split:,:..|map:{upper}|join:;>>; }",
+        "<<",
+        ">>",
+    )
+    .unwrap();
+    assert_eq!(template.format("A,b").unwrap(), "fn a,b() { return A;B; }");
+    assert_eq!(template.template_section_count(), 2);
+    assert_eq!(template.section_count(), 5);
+
+    // Sections may close right after a sub-pipeline brace
+    let template =
+        Template::parse_with_delimiters("{ {{split:,:..|map:{upper}}} }", "{{", "}}").unwrap();
+    assert_eq!(template.format("a,b").unwrap(), "{ A,B }");
+
+    // Escaped characters never close a section
+    let template = Template::parse_with_delimiters("[<<append:\\>>>]", "<<", ">>").unwrap();
+    assert_eq!(template.format("a").unwrap(), "[a>]");
+
+    // Same-string delimiters and verbose sections
+    let template =
+        Template::parse_with_delimiters("%%!# trim # tidy\n | upper%% x", "%%", "%%").unwrap();
+    assert_eq!(template.format(" a ").unwrap(), "A x");
+
+    // Braces are ordinary delimiters too
+    let template = Template::parse_with_delimiters("a {upper}", "{", "}").unwrap();
+    assert_eq!(template.format("b").unwrap(), "a B");
+}
+
+#[test]
+fn test_custom_delimiters_errors() {
+    let err = Template::parse_with_delimiters("a <<upper", "<<", ">>").unwrap_err();
+    assert_eq!(err, "Unclosed template delimiter `<<`");
+
+    let err = Template::parse_with_delimiters("{upper}", "", ">>").unwrap_err();
+    assert_eq!(err, "Template delimiters cannot be empty");
+
+    let config = ParserConfig {
+        delimiters: Some(("<<".to_string(), ">>".to_string())),
+        max_nesting_depth: 1,
+        ..ParserConfig::default()
+    };
+    assert!(Template::parse_with_config("<<upper>>", &config).is_ok());
+    let err = Template::parse_with_config("<<split:,:..|map:{upper}>>", &config).unwrap_err();
+    assert!(err.starts_with("Limit exceeded:"), "{err}");
+
+    // Error positions point into the template as written
+    let template =
+        Template::parse_with_delimiters("é <<<regex_extract:[>>>", "<<<", ">>>").unwrap();
+    let err = template.format("a").unwrap_err();
+    assert!(
+        err.ends_with("(in operation `regex_extract` at chars 6..21)"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_template_equality_ignores_spelling() {
+    let same = [
+        ("{1}", "{split: :1}"),
+        ("{upper|trim:both}", "{!# upper | trim # tidy\n}"),
+        ("{quote:\"}", "{surround:\\\"}"),
+        ("{surround:*:*}", "{surround:*}"),
+        ("{split:,:..|sort:asc}", "{split:,:..|sort}"),
+        ("{slugify:-}", "{slugify}"),
+        ("{pad:5: :right}", "{pad:5}"),
+        ("{pad:5:*:both}", "{center:5:*}"),
+        ("{upper:az}", "{upper:tr}"),
+        ("{split:,:..|join:'|'}", "{split:\\,:..|join:\\|}"),
+        ("a {input=0|lower}", "a {input=0|lower}"),
+        ("{split:,:..=;-1}", "{split:,:..;-1}"),
+    ];
+    for (a, b) in same {
+        let parse = |t: &str| Template::parse(t).unwrap_or_else(|err| panic!("{t}: {err}"));
+        let (a, b) = (parse(a), parse(b));
+        assert_eq!(a, b, "{a} vs {b}");
+        assert_eq!(hash_of(&a), hash_of(&b), "{a} vs {b}");
+        assert_eq!(a.canonical_string(), b.canonical_string());
+    }
+
+    // Runtime settings are not part of the template
+    let plain = Template::parse("{upper}").unwrap();
+    assert_eq!(plain, plain.clone().with_debug(true).with_profiling(true));
+
+    let different = [
+        ("{split:,:1}", "{split: :1}"),
+        ("{upper}", "{lower}"),
+        ("{upper:tr}", "{upper}"),
+        ("{trim:left}", "{trim}"),
+        ("a {upper}", "b {upper}"),
+        ("{input=0|upper}", "{upper}"),
+        ("{split:,:..|sort}", "{split:,:..|sort:desc}"),
+    ];
+    for (a, b) in different {
+        assert_ne!(Template::parse(a).unwrap(), Template::parse(b).unwrap());
+    }
+}
+
+#[test]
+fn test_template_canonical_string() {
+    let cases = [
+        ("{1}", "{split: :1}"),
+        ("{!upper}", "{upper}"),
+        ("{-2..}", "{split: :-2..}"),
+        ("{trim:both|quote:'}", "{trim|surround:\\'}"),
+        ("{split:,:..|sort:asc|join:, }", "{split:,:..|sort|join:, }"),
+        ("{pad:3:0:left:truncate}", "{pad:3:0:left:truncate}"),
+        ("{center:7}", "{center:7}"),
+        ("{!# slugify:- | normalize:nfc }", "{slugify|normalize:nfc}"),
+        ("{input=users}", "{input=users}"),
+        ("x ${HOME} {lower:az}", "x ${HOME} {lower:tr}"),
+    ];
+    for (template, expected) in cases {
+        let canonical = Template::parse(template).unwrap().canonical_string();
+        assert_eq!(canonical, expected, "{template}");
+    }
+}
+
+#[test]
+fn test_template_canonical_string_round_trips() {
+    let templates = [
+        "{split:,:0..=2;2|map:{trim:'x:'|upper|pad:4:truncate}|join:\\n:' and '}",
+        "{trim:\\:\\|:left|append:'{é}'|prepend:\\||squeeze:'-_'}",
+        "{trim:left:right|trim:\"it's: \"|trim:'  '}",
+        "{replace:s/(\\w+) (\\w+)/$2\\/$1/g2|replace_literal:a\\:b:é\\|:3}",
+        "{split:,:..|filter:^a{2}|filter_not:\\d+$|regex_extract:(\\w+)-(\\d+):2}",
+        "{split:,:..|filter_prefix:'[x] '|filter_suffix:\\:|filter_contains:a\\|b}",
+        "{split:,:..|sort_by:{split:=:1}:num:desc|unique_by:{lower}|filter_by:{len}:^[0-9]$}",
+        "{split:,:0,2..4,-1|intersect:\\@a,b\\,c,\"q\"|diff:@/tmp/list|union:}",
+        "{split:,:..|shuffle:7|sample:2:9|remove_empty|output_sep:;|slice:1..}",
+        "{trim_regex:\\s+:right|len:chars|substring:..=-2|rpartition:=|partition:\\:}",
+        "{pad:6:left|pad:6: :left:truncate|pad:2:truncate|center:9:*:truncate}",
+        "{split:\t:..|join:\\t}",
+        "{capitalize|capitalize:lower|capitalize:tr|capitalize:lower:tr|swapcase|swapcase:tr}",
+        "{ordinal|ordinal:parse|to_words|to_words:parse|roman|roman:parse}",
+        "{normalize_path|normalize_path:windows|dirname|basename:windows|extname|strip_ext}",
+        "{with_ext:md|with_ext:|with_ext:.t\\:z:windows|strip_ext:windows|extname:windows}",
+        "{url_scheme|url_host|url_path|url_query:q|url_query:a\\|b}",
+        "{color:red|bg:bright_blue|color:208|bg:#00ff7f|bold|underline|style_reset}",
+        "{highlight:a|b|highlight:\\d+\\:x:208|highlight:x:mark:\\:|highlight:y:mark:<:>}",
+        "{split:,:..|map:{highlight:x{2}:green|highlight:b:mark:[:]}}",
+        "{extract_emails|join:,|extract_urls|join:,|extract_uuids|map:{upper}}",
+        "{split:\\n:..|table|table:,|table:\\::rlc:header|table::c|table:header}",
+        "{split:\\n:..|md_table|md_table:,|md_table:\\::rlc:header|html_table::c|html_table:header}",
+        "{split:,:..|json_array}{split:,:..|json_object:a,b\\,c}{json_object:}",
+        "{split:,:..|filter_valid_utf8|filter_luhn|filter_checksum:adler32:091e01de|map:{filter_luhn}}",
+        "{try:{ordinal}}{try:{roman|lower}:}{try:{upper}:n/a\\:}{split:,:..|map:{try:{try:{roman}:x}}}",
+        "{split:,:..|map_fallible:{ordinal|upper}|join:,}",
+        "{split:,:..|clamp:1..10|clamp:-2,0..=1;2|map:{clamp:..}}",
+        "{split_any:,;\\|:..|map:{split_any: \\t\\::1}}{split_any:\\:-:-1..=-3}",
+        "{extract_between:(:)|extract_between:\\::\\::-2}{split:,:..|map:{extract_between:<<:>>:1}}",
+        "{kv:a|kv:a:&|kv:a::\\:|kv_keys|join:,|kv_values:;|join:,|kv_keys::->}",
+        "{input=users|set:first-1:split:,:0}{get:first-1|upper} {set:all:}{get:all}",
+        "] {upper}[?, Email: {lower}]{trim}[?{upper} and {lower}] x]",
+        "{each:{split:,:..|sort}:- {upper}[? ({set:n:len}{get:n})]\n}{each:{}:{}}",
+        "{each:{split:;:..}:{each:{split:,:..}:<{}>}/}",
+        "{input} {input_len|pad:3} {!line_no}:{file_name|upper}{input=input_len}",
+        "{split:,:..:keep_sep|map:{split:;:1..:2:keep_sep:skip_empty}|split::..:skip_empty}",
+        "{sentences|map:{words|count}|join:,}{words|slice:..2|join: }",
+        "{split:,:..|histogram|join:;}{split:,:..|count_by:3|map:{upper}}",
+        r"{replace:s|/usr/bin|/opt|g|replace:s#\##/#|replace:s/a\\b\//x\\/2gl}",
+        r"{replace:s|a\|b|\/|}{replace:s/x\/y/$$1/}",
+        "{replace:s/a/b/g;s|/|-|;s/c/d/2|map:{replace:s/x/y/;s/y/z/gl}}",
+    ];
+    for text in templates {
+        let template =
+            Template::parse_unchecked(text).unwrap_or_else(|err| panic!("{text}: {err}"));
+        let canonical = template.canonical_string();
+        let reparsed = Template::parse_unchecked(&canonical)
+            .unwrap_or_else(|err| panic!("{text} -> {canonical}: {err}"));
+        assert_eq!(reparsed, template, "{text} -> {canonical}");
+        assert_eq!(reparsed.canonical_string(), canonical);
+    }
+}
+
+#[test]
+fn test_template_canonical_string_round_trips_ranges() {
+    let ranges = [
+        "2",
+        "-1",
+        "..",
+        "..=",
+        "1..",
+        "1..=",
+        "-2..=",
+        "..3",
+        "..=3",
+        "..=-1",
+        "1..3",
+        "1..=3",
+        "3..1",
+        "-1..=-3",
+        "..;2",
+        "..=;-1",
+        "1..=;2",
+        "..=4;-2",
+        "0..=5;3",
+        "0,2..,..=-1;-1",
+    ];
+    for range in ranges {
+        let text = format!("{{split:,:{range}}}");
+        let template = Template::parse(&text).unwrap_or_else(|err| panic!("{text}: {err}"));
+        let canonical = template.canonical_string();
+        assert_eq!(
+            Template::parse(&canonical).unwrap(),
+            template,
+            "{text} -> {canonical}"
+        );
+    }
+}
+
+fn hash_of(template: &Template) -> u64 {
+    use std::hash::{DefaultHasher, Hash, Hasher};
+    let mut hasher = DefaultHasher::new();
+    template.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn test_template_builder_matches_parsed_template() {
+    let built = TemplateBuilder::new()
+        .literal("Tags: ")
+        .split(",", Range::all())
+        .map(|tag| tag.trim().upper())
+        .join("-")
+        .literal(" (")
+        .split(",", Range::index(0))
+        .lower()
+        .literal(")")
+        .build()
+        .unwrap();
+    let syntax = "Tags: {split:,:..|map:{trim|upper}|join:-} ({split:,:0|lower})";
+    assert_eq!(built.template_string(), syntax);
+    assert_eq!(built, Template::parse(syntax).unwrap());
+    assert_eq!(built.format("a, b ,C").unwrap(), "Tags: A-B-C (a)");
+}
+
+#[test]
+fn test_template_builder_kv() {
+    let built = TemplateBuilder::new().kv("port", ";", ":").build().unwrap();
+    assert_eq!(built.template_string(), "{kv:port:;:\\:}");
+    assert_eq!(built.format("host: a.org; port: 8080").unwrap(), "8080");
+
+    let keys = TemplateBuilder::new()
+        .kv_keys("", "=")
+        .join(",")
+        .build()
+        .unwrap();
+    assert_eq!(keys.template_string(), "{kv_keys|join:,}");
+    assert_eq!(keys.format("a=1 b='x y'").unwrap(), "a,b");
+}
+
+#[test]
+fn test_template_builder_capitalize_swapcase() {
+    let built = TemplateBuilder::new()
+        .split(" ", Range::all())
+        .map(|m| m.capitalize(true))
+        .join(" ")
+        .build()
+        .unwrap();
+    assert_eq!(
+        built.template_string(),
+        "{split: :..|map:{capitalize:lower}|join: }"
+    );
+    assert_eq!(built.format("jOHN mcDONALD").unwrap(), "John Mcdonald");
+
+    let swapped = TemplateBuilder::new()
+        .swapcase_locale(CaseLocale::Turkic)
+        .build()
+        .unwrap();
+    assert_eq!(swapped.template_string(), "{swapcase:tr}");
+    assert_eq!(swapped.format("Iİi").unwrap(), "ıiİ");
+}
+
+#[test]
+fn test_template_builder_numerals() {
+    let built = TemplateBuilder::new()
+        .split(",", Range::all())
+        .map(|m| m.roman(false).to_words(false))
+        .join(", ")
+        .build()
+        .unwrap();
+    assert_eq!(
+        built.template_string(),
+        "{split:,:..|map:{roman|to_words}|join:, }"
+    );
+
+    let parsed = TemplateBuilder::new().ordinal(true).build().unwrap();
+    assert_eq!(parsed.template_string(), "{ordinal:parse}");
+    assert_eq!(parsed.format("42nd").unwrap(), "42");
+}
+
+#[test]
+fn test_template_builder_paths() {
+    let built = TemplateBuilder::new()
+        .normalize_path(PathStyle::Posix)
+        .with_ext("md", PathStyle::Posix)
+        .basename(PathStyle::Windows)
+        .build()
+        .unwrap();
+    assert_eq!(
+        built.template_string(),
+        "{normalize_path|with_ext:md|basename:windows}"
+    );
+    assert_eq!(
+        built.format("docs/./guide/../intro.txt").unwrap(),
+        "intro.md"
+    );
+}
+
+#[test]
+fn test_template_builder_urls() {
+    let built = TemplateBuilder::new()
+        .url_host()
+        .append(" ")
+        .build()
+        .unwrap();
+    assert_eq!(built.template_string(), "{url_host|append: }");
+
+    let query = TemplateBuilder::new().url_query("a:b").build().unwrap();
+    assert_eq!(query.template_string(), "{url_query:a\\:b}");
+    assert_eq!(query.format("/?a%3Ab=1").unwrap(), "1");
+}
+
+#[test]
+fn test_template_builder_styles() {
+    let built = TemplateBuilder::new()
+        .color(TermColor::BrightRed)
+        .bg(TermColor::Rgb(0, 0, 0x80))
+        .bold()
+        .underline()
+        .style_reset()
+        .build()
+        .unwrap();
+    assert_eq!(
+        built.template_string(),
+        "{color:bright_red|bg:#000080|bold|underline|style_reset}"
+    );
+
+    let built = TemplateBuilder::new()
+        .color(TermColor::Indexed(42))
+        .build()
+        .unwrap()
+        .with_color(false);
+    assert_eq!(built.template_string(), "{color:42}");
+    assert_eq!(built.format("a").unwrap(), "a");
+}
+
+#[test]
+fn test_template_builder_highlight() {
+    let built = TemplateBuilder::new()
+        .highlight("a:b", HighlightStyle::default())
+        .highlight(
+            "c",
+            HighlightStyle::Marker {
+                open: "[".to_string(),
+                close: "]".to_string(),
+            },
+        )
+        .build()
+        .unwrap();
+    assert_eq!(
+        built.template_string(),
+        "{highlight:a:b|highlight:c:mark:[:]}"
+    );
+    assert_eq!(built.format("a:bc").unwrap(), "\x1b[31ma:b\x1b[39m[c]");
+}
+
+#[test]
+fn test_template_builder_markup_tables() {
+    let built = TemplateBuilder::new()
+        .split("\n", Range::all())
+        .md_table(",", &[ColumnAlign::Right], true)
+        .build()
+        .unwrap();
+    assert_eq!(
+        built.template_string(),
+        "{split:\\n:..|md_table:,:r:header}"
+    );
+    assert_eq!(
+        built.format("n,x\n10,a").unwrap(),
+        "|   n | x   |\n| --: | --- |\n|  10 | a   |"
+    );
+
+    let built = TemplateBuilder::new()
+        .split(",", Range::all())
+        .html_table("", &[], false)
+        .join("")
+        .build()
+        .unwrap();
+    assert_eq!(built.template_string(), "{split:,:..|html_table|join:}");
+    assert_eq!(
+        built.format("a b").unwrap(),
+        "<table>  <tbody>    <tr><td>a</td><td>b</td></tr>  </tbody></table>"
+    );
+}
+
+#[test]
+fn test_template_builder_json() {
+    let built = TemplateBuilder::new()
+        .split(",", Range::all())
+        .json_object(&["name", "a,b"])
+        .build()
+        .unwrap();
+    assert_eq!(
+        built.template_string(),
+        "{split:,:..|json_object:name,a\\,b}"
+    );
+    assert_eq!(built.format("x,y,z").unwrap(), r#"{"name":"x","a,b":"y"}"#);
+
+    let built = TemplateBuilder::new()
+        .split(",", Range::all())
+        .json_array()
+        .build()
+        .unwrap();
+    assert_eq!(built.format("a,\\").unwrap(), r#"["a","\\"]"#);
+
+    assert!(
+        TemplateBuilder::new()
+            .json_object(&["a", "a"])
+            .build()
+            .is_err()
+    );
+}
+
+#[cfg(all(feature = "yaml", feature = "toml"))]
+#[test]
+fn test_template_builder_config_get() {
+    let built = TemplateBuilder::new()
+        .yaml_get(&["server", "a.b"])
+        .build()
+        .unwrap();
+    assert_eq!(built.template_string(), "{yaml_get:server.a\\.b}");
+    assert_eq!(built.format("server:\n  a.b: 1").unwrap(), "1");
+
+    let built = TemplateBuilder::new()
+        .toml_get(&["package", "version"])
+        .build()
+        .unwrap();
+    assert_eq!(built.template_string(), "{toml_get:package.version}");
+    assert_eq!(built.format("[package]\nversion = \"2.0\"").unwrap(), "2.0");
+
+    assert!(TemplateBuilder::new().yaml_get(&[]).build().is_err());
+    assert!(TemplateBuilder::new().toml_get(&["a", ""]).build().is_err());
+}
+
+#[test]
+fn test_template_builder_validation_filters() {
+    let built = TemplateBuilder::new()
+        .split(",", Range::all())
+        .filter_valid_utf8()
+        .filter_luhn()
+        .filter_checksum(ChecksumAlgorithm::Crc32, 0x9567_15df)
+        .build()
+        .unwrap();
+    assert_eq!(
+        built.template_string(),
+        "{split:,:..|filter_valid_utf8|filter_luhn|filter_checksum:crc32:956715df}"
+    );
+    assert_eq!(
+        built.format("79927398713,79927398710").unwrap(),
+        "79927398713"
+    );
+}
+
+#[test]
+fn test_template_builder_extract_between() {
+    let built = TemplateBuilder::new()
+        .extract_between("(", ")", 0)
+        .extract_between("\"", "\"", -1)
+        .build()
+        .unwrap();
+    assert_eq!(
+        built.template_string(),
+        r#"{extract_between:(:)|extract_between:\":\":-1}"#
+    );
+    assert_eq!(built.format(r#"f("a", g("b"))"#).unwrap(), "b");
+
+    let err = TemplateBuilder::new()
+        .extract_between("(", "", 0)
+        .build()
+        .unwrap_err();
+    assert!(err.contains("delimiters cannot be empty"), "{err}");
+}
+
+#[test]
+fn test_template_builder_split_any() {
+    let built = TemplateBuilder::new()
+        .split_any(",;", Range::from(1))
+        .join("-")
+        .build()
+        .unwrap();
+    assert_eq!(built.template_string(), "{split_any:,;:1..|join:-}");
+    assert_eq!(built.format("a,b;c").unwrap(), "b-c");
+
+    let err = TemplateBuilder::new()
+        .split_any("", Range::all())
+        .build()
+        .unwrap_err();
+    assert!(err.contains("at least one separator character"), "{err}");
+}
+
+#[test]
+fn test_template_builder_clamp() {
+    let built = TemplateBuilder::new()
+        .split(",", Range::all())
+        .clamp(Range::between(1, 10))
+        .join("-")
+        .build()
+        .unwrap()
+        .with_strict_ranges(true);
+    assert_eq!(built.template_string(), "{split:,:..|clamp:1..10|join:-}");
+    assert_eq!(built.format("a,b,c").unwrap(), "b-c");
+}
+
+#[test]
+fn test_template_builder_error_tolerance() {
+    let built = TemplateBuilder::new()
+        .split(",", Range::all())
+        .map(|p| p.try_ops(|p| p.ordinal(false), Some("?")))
+        .join(",")
+        .build()
+        .unwrap();
+    assert_eq!(
+        built.template_string(),
+        "{split:,:..|map:{try:{ordinal}:?}|join:,}"
+    );
+    assert_eq!(built.format("1,x").unwrap(), "1st,?");
+
+    let built = TemplateBuilder::new()
+        .split(",", Range::all())
+        .map_fallible(|p| p.roman(false))
+        .join(",")
+        .build()
+        .unwrap();
+    assert_eq!(
+        built.template_string(),
+        "{split:,:..|map_fallible:{roman}|join:,}"
+    );
+    assert_eq!(built.format("4,x").unwrap(), "IV");
+
+    assert!(TemplateBuilder::new().try_ops(|p| p, None).build().is_err());
+    assert!(
+        TemplateBuilder::new()
+            .try_ops(|p| p.map(|p| p.upper()), None)
+            .build()
+            .is_err()
+    );
+}
+
+#[test]
+fn test_template_builder_extract() {
+    let built = TemplateBuilder::new()
+        .extract_emails()
+        .join(";")
+        .build()
+        .unwrap();
+    assert_eq!(built.template_string(), "{extract_emails|join:;}");
+    assert_eq!(built.format("to: a@b.io, c@d.io").unwrap(), "a@b.io;c@d.io");
+}
+
+#[test]
+fn test_template_numeral_error_span() {
+    let template = Template::parse("{trim|roman}").unwrap();
+    let err = template.format("5000").unwrap_err();
+    assert!(err.contains("only cover 1 to 3999"), "{err}");
+    assert!(
+        err.contains("(in operation `roman` at chars 7..12)"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_template_builder_split_options() {
+    let built = TemplateBuilder::new()
+        .split_skip_empty(",", Range::all())
+        .join("-")
+        .build()
+        .unwrap();
+    assert_eq!(built.template_string(), "{split:,:..:skip_empty|join:-}");
+    assert_eq!(built.format(",a,,b").unwrap(), "a-b");
+
+    let built = TemplateBuilder::new()
+        .split_keep_sep(";", Range::all())
+        .map(|ops| ops.upper())
+        .join("")
+        .build()
+        .unwrap();
+    assert_eq!(
+        built.template_string(),
+        "{split:;:..:keep_sep|map:{upper}|join:}"
+    );
+    assert_eq!(built.format("a;b").unwrap(), "A;B");
+}
+
+#[test]
+fn test_template_builder_words() {
+    let built = TemplateBuilder::new()
+        .sentences()
+        .map(|ops| ops.words().len(LengthUnit::Graphemes))
+        .join(",")
+        .build()
+        .unwrap();
+    assert_eq!(
+        built.template_string(),
+        "{sentences|map:{words|len}|join:,}"
+    );
+    assert_eq!(built.format("One two. Three!").unwrap(), "2,1");
+}
+
+#[test]
+fn test_template_builder_replace_literal_flag() {
+    let built = TemplateBuilder::new()
+        .replace("a/b.c", "$x/", "gl")
+        .build()
+        .unwrap();
+    assert_eq!(built.template_string(), r"{replace:s/a\/b.c/$x\//gl}");
+    assert_eq!(built, Template::parse(built.template_string()).unwrap());
+    assert_eq!(built.format("a/b.c a/bxc").unwrap(), "$x/ a/bxc");
+}
+
+#[test]
+fn test_template_builder_table() {
+    let built = TemplateBuilder::new()
+        .split("\n", Range::all())
+        .table(",", &[ColumnAlign::Left, ColumnAlign::Right], true)
+        .build()
+        .unwrap();
+    assert_eq!(built.template_string(), "{split:\\n:..|table:,:lr:header}");
+    assert_eq!(
+        built.format("name,size\nsrc,12").unwrap(),
+        "name  size\n----  ----\nsrc     12"
+    );
+}
+
+#[test]
+fn test_template_builder_escapes_arguments() {
+    let builder = TemplateBuilder::new()
+        .split(":", Range::from(1))
+        .join_last(", ", " | ")
+        .append("{x}")
+        .replace("a/b", "c", "g")
+        .trim_chars("'", TrimDirection::Left)
+        .pad(6, "*", PadDirection::Left);
+    let syntax = builder.to_template_string();
+    assert_eq!(
+        syntax,
+        r#"{split:\::1..|join:, : \| |append:\{x\}|replace:s/a\/b/c/g|trim:"'":left|pad:6:*:left}"#
+    );
+    let built = builder.build().unwrap();
+    assert_eq!(built, Template::parse(&syntax).unwrap());
+    assert_eq!(built.format("x:a/b:y:z").unwrap(), "c, y | z{x}");
+}
+
+#[test]
+fn test_template_builder_sections_and_inputs() {
+    let template = TemplateBuilder::new()
+        .input(InputRef::Name("users".to_string()))
+        .upper()
+        .section()
+        .literal(" ")
+        .section()
+        .section()
+        .pipeline(Pipeline::new().append("!"))
+        .build()
+        .unwrap();
+    assert_eq!(
+        template.template_string(),
+        "{input=users|upper}{} {}{append:!}"
+    );
+    assert_eq!(template.template_section_count(), 4);
+    assert_eq!(
+        template,
+        Template::parse("{input=users|upper}{} {}{append:!}").unwrap()
+    );
+
+    // Sub-pipelines reuse prebuilt pipelines
+    let tidy = Pipeline::new().trim().lower();
+    let template = TemplateBuilder::new()
+        .split(",", Range::all())
+        .sort_by(|key| key.pipeline(tidy.clone()), false, SortDirection::Desc)
+        .unique_by(|key| key.pipeline(tidy))
+        .join(",")
+        .build()
+        .unwrap();
+    assert_eq!(
+        template.template_string(),
+        "{split:,:..|sort_by:{trim|lower}:desc|unique_by:{trim|lower}|join:,}"
+    );
+    assert_eq!(template.format("b, A,a ,c").unwrap(), "c,b, A");
+}
+
+#[test]
+fn test_template_builder_errors() {
+    let err = TemplateBuilder::new()
+        .upper()
+        .sort(SortDirection::Asc)
+        .build();
+    assert!(err.unwrap_err().contains("can only be applied to lists"));
+
+    let err = TemplateBuilder::new().partition("").build().unwrap_err();
+    assert_eq!(err, "Empty separator in partition");
+
+    let err = TemplateBuilder::new()
+        .split(",", Range::all())
+        .map(|item| item.map(|inner| inner.upper()))
+        .build()
+        .unwrap_err();
+    assert_eq!(err, "`map` cannot be used inside `map`");
+
+    let err = TemplateBuilder::new()
+        .split(",", Range::all())
+        .map(|item| item)
+        .build()
+        .unwrap_err();
+    assert_eq!(err, "`map` needs at least one operation");
+
+    // Formatting errors point into the written template string
+    let template = TemplateBuilder::new()
+        .literal("é ")
+        .split(",", Range::all())
+        .map(|item| item.regex_extract("[", None))
+        .build()
+        .unwrap();
+    let err = template.format("a").unwrap_err();
+    assert!(
+        err.ends_with("(in operation `regex_extract` at chars 20..35)"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_template_from_ops() {
+    let ops = vec![
+        StringOp::Split {
+            sep: ",".to_string(),
+            range: RangeSpec::Range(Some(1), None, false),
+            max_splits: None,
+            keep_sep: false,
+            skip_empty: false,
+        },
+        StringOp::Join {
+            sep: "|".to_string(),
+            last_sep: None,
+        },
+    ];
+    let template = Template::from_ops(ops.clone()).unwrap();
+    assert_eq!(template.template_string(), "{split:,:1..|join:\\|}");
+    assert_eq!(template.format("a,b,c").unwrap(), "b|c");
+    assert_eq!(template, Template::try_from(ops.clone()).unwrap());
+
+    // The operations of parsed templates build equal templates
+    let parsed = Template::parse("{split:,:..|map:{trim|upper}|sort:desc}").unwrap();
+    let parsed_ops = parsed.get_template_sections()[0].1.clone();
+    assert_eq!(Template::from_ops(parsed_ops).unwrap(), parsed);
+
+    let err = Template::from_ops(vec![StringOp::Sort {
+        direction: SortDirection::Asc,
+    }])
+    .unwrap_err();
+    assert!(err.contains("can only be applied to lists"), "{err}");
+
+    let err = Template::from_ops(vec![StringOp::Slice {
+        range: RangeSpec::Stepped(None, None, false, 0),
+    }])
+    .unwrap_err();
+    assert_eq!(err, "Range step cannot be 0");
+}
+
+#[test]
+fn test_template_from_sections() {
+    let template = Template::from_sections(vec![
+        Section::Literal("[".to_string()),
+        Section::Literal("".to_string()),
+        Section::Template {
+            ops: Pipeline::new().upper().into_ops(),
+            input: Some(InputRef::Index(1)),
+            variable: None,
+            placeholder: None,
+        },
+        Section::Literal("] [".to_string()),
+        Section::Template {
+            ops: Vec::new(),
+            input: Some(InputRef::Index(0)),
+            variable: None,
+            placeholder: None,
+        },
+        Section::Literal("]".to_string()),
+    ])
+    .unwrap();
+    assert_eq!(template.template_string(), "[{input=1|upper}] [{input=0}]");
+    assert_eq!(template.section_count(), 5);
+    assert_eq!(
+        template
+            .format_with_inputs(&[&["a"], &["b"]], &[",", ","])
+            .unwrap(),
+        "[B] [a]"
+    );
+    assert_eq!(
+        template,
+        Template::parse("[{input=1|upper}] [{input=0}]").unwrap()
+    );
+
+    assert_eq!(
+        Template::from_sections(Vec::new())
+            .unwrap()
+            .format("x")
+            .unwrap(),
+        ""
+    );
+}
+
+#[test]
+fn test_cache_stats_count_lookups() {
+    // Counters are shared by every test in the process, so only check growth
+    let template = Template::parse("{split:;:..|filter:^cache-stats-|join:-}").unwrap();
+    let before = cache_stats();
+    template.format("cache-stats-a;b;cache-stats-c").unwrap();
+    template.format("cache-stats-a;b;cache-stats-c").unwrap();
+    let after = cache_stats();
+
+    assert!(after.split_hits + after.split_misses >= before.split_hits + before.split_misses + 2);
+    assert!(after.split_hits > before.split_hits);
+    assert!(after.regex_hits + after.regex_misses > before.regex_hits + before.regex_misses);
+    assert!(after.split_entries >= 1);
+    assert!(after.regex_entries >= 1);
+    assert!(after.bytes > 0);
+
+    let text = after.to_string();
+    assert!(text.starts_with("regex cache: "));
+    assert!(text.contains("\nsplit cache: "));
+    assert!(text.ends_with(" bytes"));
+}
+
+#[test]
+fn test_format_into_matches_format() {
+    let template =
+        Template::parse("{split:,:0} [{split:,:..|map:{upper}|join:-}] {split:,:0}").unwrap();
+    let mut ctx = template.new_context();
+    let mut out = String::from("stale contents");
+
+    for input in ["a,b,c", "x,y", "a,b,c", "", "single"] {
+        template.format_into(input, &mut ctx, &mut out).unwrap();
+        assert_eq!(out, template.format(input).unwrap());
+    }
+
+    // A context can be shared between templates
+    let other = Template::parse("{split:,:0}|{split:,:0}").unwrap();
+    other.format_into("a,b,c", &mut ctx, &mut out).unwrap();
+    assert_eq!(out, "a|a");
+
+    ctx.clear();
+    template.format_into("q,r", &mut ctx, &mut out).unwrap();
+    assert_eq!(out, "q [Q-R] q");
+}
+
+#[test]
+fn test_format_into_many_inputs() {
+    let template = Template::parse("{upper}{upper}").unwrap();
+    let mut ctx = FormatContext::new();
+    let mut out = String::new();
+    for i in 0..3000 {
+        let input = format!("item{i}");
+        template.format_into(&input, &mut ctx, &mut out).unwrap();
+        assert_eq!(out, format!("ITEM{i}ITEM{i}"));
+    }
+}
+
+#[test]
+fn test_cache_scopes_format_like_global() {
+    let text = "{split:,:0} {split:,:..|filter:^[ab]|map:{replace:s/a/A/}|join:-} {each:{split:,:..}:<{regex_extract:\\w}>}";
+    let global = Template::parse(text).unwrap();
+    for scope in [CacheScope::Thread, CacheScope::Context] {
+        let template = Template::parse(text).unwrap().with_cache_scope(scope);
+        assert_eq!(template.cache_scope(), scope);
+
+        let mut ctx = template.new_context();
+        let mut out = String::new();
+        for input in ["a,b,c", "ba,ab", "a,b,c", ""] {
+            let expected = global.format(input).unwrap();
+            assert_eq!(template.format(input).unwrap(), expected);
+            template.format_into(input, &mut ctx, &mut out).unwrap();
+            assert_eq!(out, expected);
+        }
+        ctx.clear();
+        template.format_into("b,a", &mut ctx, &mut out).unwrap();
+        assert_eq!(out, "b b-A <b><a>");
+
+        assert_eq!(
+            template
+                .format_with_inputs(&[&["a,b"], &["b"], &["x"]], &[" ", " ", " "])
+                .unwrap(),
+            "a b <x>"
+        );
+
+        // Regexes compiled under limits are cached apart
+        let limited = template.clone().with_limits(Limits {
+            max_regex_size: Some(64),
+            ..Limits::default()
+        });
+        assert!(limited.format("a,b").is_err());
+    }
+}
+
+#[test]
+fn test_thread_cache_scope_across_threads() {
+    let template = Template::parse("{split:,:..|filter:^t|map:{upper}|join:-}")
+        .unwrap()
+        .with_cache_scope(CacheScope::Thread);
+    std::thread::scope(|scope| {
+        for thread in 0..16 {
+            let template = &template;
+            scope.spawn(move || {
+                for i in 0..50 {
+                    let input = format!("t{thread},x,t{i}");
+                    assert_eq!(template.format(&input).unwrap(), format!("T{thread}-T{i}"));
+                }
+            });
+        }
+    });
+}
+
+#[test]
+fn test_format_into_error_clears_output() {
+    let template = Template::parse("{read_file}").unwrap();
+    let mut ctx = template.new_context();
+    let mut out = String::from("previous");
+    assert!(template.format_into("x", &mut ctx, &mut out).is_err());
+    assert!(out.is_empty());
+
+    let template = Template::parse("{upper}").unwrap().with_limits(Limits {
+        max_output_len: Some(3),
+        ..Limits::default()
+    });
+    let mut out = String::from("previous");
+    assert!(
+        template
+            .format_into("long input", &mut ctx, &mut out)
+            .is_err()
+    );
+    assert!(out.is_empty());
+    template.format_into("ok", &mut ctx, &mut out).unwrap();
+    assert_eq!(out, "OK");
+}
+
+#[test]
+fn test_format_to_appends() {
+    let template = Template::parse("{split:,:0}={split:,:1|upper};").unwrap();
+    let mut ctx = template.new_context();
+    let mut out = String::from("> ");
+    for input in ["a,b", "c,d", "a,b"] {
+        template.format_to(input, &mut ctx, &mut out).unwrap();
+    }
+    assert_eq!(out, "> a=B;c=D;a=B;");
+
+    // Errors write nothing
+    let template = Template::parse("[{roman}]").unwrap();
+    assert!(template.format_to("x", &mut ctx, &mut out).is_err());
+    assert_eq!(out, "> a=B;c=D;a=B;");
+}
+
+#[test]
+fn test_format_to_any_writer() {
+    struct Failing;
+    impl std::fmt::Write for Failing {
+        fn write_str(&mut self, _: &str) -> std::fmt::Result {
+            Err(std::fmt::Error)
+        }
+    }
+
+    let template = Template::parse("{upper}").unwrap();
+    let mut ctx = template.new_context();
+    let error = template.format_to("a", &mut ctx, &mut Failing).unwrap_err();
+    assert_eq!(error, "Failed to write the formatted output");
+}
+
+/// Returns `false` for operations whose cargo feature is not enabled, which
+/// fail to parse.
+fn is_compiled_in(name: &str) -> bool {
+    (name != "yaml_get" || cfg!(feature = "yaml")) && (name != "toml_get" || cfg!(feature = "toml"))
+}
+
+#[test]
+fn test_operation_catalog_examples_parse() {
+    for op in operations().iter().filter(|op| is_compiled_in(op.name)) {
+        let template = Template::parse(op.example)
+            .unwrap_or_else(|err| panic!("{}: {}: {err}", op.name, op.example));
+        let canonical = template.canonical_string();
+        assert_eq!(
+            Template::parse(&canonical).unwrap().canonical_string(),
+            canonical
+        );
+        assert!(op.syntax.starts_with(op.name), "{}", op.name);
+    }
+}
+
+#[test]
+fn test_operation_catalog_lookup() {
+    assert_eq!(operation("quote").unwrap().name, "surround");
+    assert!(operation("nope").is_none());
+
+    let split = operation("split").unwrap();
+    assert_eq!(
+        (split.input, split.output),
+        (ValueKind::Any, ValueKind::Any)
+    );
+    let sort = operation("sort").unwrap();
+    assert_eq!(
+        (sort.input, sort.output),
+        (ValueKind::List, ValueKind::List)
+    );
+    assert!(operation("upper").unwrap().idempotent);
+    assert!(!operation("append").unwrap().idempotent);
+
+    let mut names: Vec<&str> = operations()
+        .iter()
+        .flat_map(|op| std::iter::once(op.name).chain(op.aliases.iter().copied()))
+        .collect();
+    let count = names.len();
+    names.sort_unstable();
+    names.dedup();
+    assert_eq!(names.len(), count, "duplicate operation names");
+}
+
+#[test]
+fn test_operation_catalog_types_drive_type_checking() {
+    for op in operations()
+        .iter()
+        .filter(|op| op.input == ValueKind::String && is_compiled_in(op.name))
+    {
+        // `{split:,:..|NAME...}` feeds a list to an operation that needs a string
+        let example = op.example.trim_start_matches('{');
+        let template = format!("{{split:,:..|{example}");
+        let err = Template::parse(&template).unwrap_err();
+        assert!(
+            err.contains("can only be applied to strings"),
+            "{template}: {err}"
+        );
+    }
+}
+
+#[test]
+fn test_operations_json() {
+    let json = operations_json();
+    assert!(json.starts_with("[\n{\"name\":\"split\""));
+    assert!(json.ends_with("}\n]"));
+    assert_eq!(json.lines().count(), operations().len() + 2);
+    assert!(json.contains(r#""name":"surround","aliases":["quote"]"#));
+    assert!(json.contains(r#""example":"{surround:\"}""#));
+}
+
+#[test]
+fn test_parse_error_suggests_operation() {
+    let err = Template::parse("{split:,:..|mapp:{uppr}}").unwrap_err();
+    assert!(
+        err.contains("unknown operation `mapp`, did you mean `map`?"),
+        "{err}"
+    );
+
+    let err = Template::parse("{split:,:..|map:{uppr}}").unwrap_err();
+    assert!(
+        err.contains("unknown operation `uppr`, did you mean `upper`?"),
+        "{err}"
+    );
+
+    let err = Template::parse("{frobnicate}").unwrap_err();
+    assert!(err.contains("unknown operation `frobnicate`"), "{err}");
+    assert!(!err.contains("did you mean"), "{err}");
+
+    // Bad arguments of a known operation get no hint
+    let err = Template::parse("{split:,:..|sort:bad}").unwrap_err();
+    assert!(!err.contains("unknown operation"), "{err}");
+}
+
+#[test]
+fn test_builtin_operation_aliases() {
+    let template = Template::parse("{s:,:..|map:{uppercase|rm_ansi}|j:-}").unwrap();
+    assert_eq!(template.format("a,\x1b[1mb\x1b[0m").unwrap(), "A-B");
+    assert_eq!(
+        template.canonical_string(),
+        "{split:,:..|map:{upper|strip_ansi}|join:-}"
+    );
+    assert_eq!(
+        Template::parse("{!lowercase}")
+            .unwrap()
+            .format("AB")
+            .unwrap(),
+        "ab"
+    );
+    assert_eq!(operation("s").unwrap().name, "split");
+}
+
+#[test]
+fn test_alias_spans_point_at_original_text() {
+    let template = Template::parse_unchecked("x {j:-|sort}").unwrap();
+    let err = template.format("a").unwrap_err();
+    assert!(
+        err.contains("(in operation `sort` at chars 8..12)"),
+        "{err}"
+    );
+
+    use TokenKind::*;
+    assert_eq!(
+        token_texts("{s:,:..|uppercase}"),
+        [
+            (Brace, "{"),
+            (OperationName, "s"),
+            (Separator, ":"),
+            (Argument, ","),
+            (Separator, ":"),
+            (Argument, ".."),
+            (Separator, "|"),
+            (OperationName, "uppercase"),
+            (Brace, "}"),
+        ]
+    );
+}
+
+#[test]
+fn test_register_alias() {
+    register_alias("shout", "upper").unwrap();
+    // Registering the same alias again is fine
+    register_alias("shout", "upper").unwrap();
+    assert_eq!(operation("shout").unwrap().name, "upper");
+
+    let template = Template::parse("{split:,:..|map:{shout}|join:,}").unwrap();
+    assert_eq!(template.format("a,b").unwrap(), "A,B");
+    assert_eq!(
+        template.canonical_string(),
+        "{split:,:..|map:{upper}|join:,}"
+    );
+
+    assert!(
+        register_alias("shout", "lower")
+            .unwrap_err()
+            .contains("`upper`")
+    );
+    assert!(register_alias("upper", "lower").is_err());
+    assert!(register_alias("s", "lower").is_err());
+    assert!(register_alias("Loud", "upper").is_err());
+    assert!(register_alias("loud-er", "upper").is_err());
+    assert_eq!(
+        register_alias("loud", "yell").unwrap_err(),
+        "Unknown operation `yell`"
+    );
+}
+
+#[test]
+fn test_line_endings_keep_by_default() {
+    let template = Template::parse("{split:\\n:..|join:,}").unwrap();
+    assert_eq!(template.line_endings(), LineEndings::Keep);
+    assert_eq!(template.format("a\r\nb").unwrap(), "a\r,b");
+}
+
+#[test]
+fn test_line_endings_lf_normalizes_input() {
+    let template = Template::parse("{split:\\n:..|filter:a$|join:,}")
+        .unwrap()
+        .with_line_endings(LineEndings::Lf);
+    assert_eq!(template.format("ba\r\nca\r\nx").unwrap(), "ba,ca");
+    // A lone \r is not a line ending
+    assert_eq!(template.format("a\rb").unwrap(), "");
+
+    let template = Template::parse("{split:,:..|join:\\n}")
+        .unwrap()
+        .with_line_endings(LineEndings::Lf);
+    assert_eq!(template.format("a,b").unwrap(), "a\nb");
+}
+
+#[test]
+fn test_line_endings_crlf_output() {
+    let template = Template::parse("x\n{split:\\n:..|join:\\n}\ny")
+        .unwrap()
+        .with_line_endings(LineEndings::Crlf);
+    assert_eq!(template.format("a\r\nb").unwrap(), "x\r\na\r\nb\r\ny");
+
+    // Existing \r\n in literal text is not doubled
+    let template = Template::parse("x\r\n{upper}")
+        .unwrap()
+        .with_line_endings(LineEndings::Crlf);
+    assert_eq!(template.format("a").unwrap(), "x\r\nA");
+
+    let rich = Template::parse("{upper}\n{lower}")
+        .unwrap()
+        .with_line_endings(LineEndings::Crlf)
+        .format_rich("a\nb")
+        .unwrap();
+    assert_eq!(rich.rendered(), "A\r\nB\r\na\r\nb");
+    assert_eq!(rich.template_output(0), Some("A\r\nB"));
+    assert_eq!(rich.template_output(1), Some("a\r\nb"));
+
+    let mut ctx = FormatContext::new();
+    let mut out = String::new();
+    Template::parse("{split:,:..|join:\\n}")
+        .unwrap()
+        .with_line_endings(LineEndings::Crlf)
+        .format_into("a,b", &mut ctx, &mut out)
+        .unwrap();
+    assert_eq!(out, "a\r\nb");
+}
+
+#[test]
+fn test_line_endings_structured_inputs() {
+    let template = Template::parse("{split:\\n:..|join:,} {upper}")
+        .unwrap()
+        .with_line_endings(LineEndings::Lf);
+    assert_eq!(
+        template
+            .format_with_inputs(&[&["a\r\nb"], &["c\r\n"]], &[" ", " "])
+            .unwrap(),
+        "a,b C\n"
+    );
+}
+
+#[test]
+fn test_output_separator_replaces_implicit_join() {
+    let template = Template::parse("{split:,:..|map:{split: :..|join:-}}").unwrap();
+    assert_eq!(template.output_separator(), None);
+    assert_eq!(template.format("a b,c d").unwrap(), "a-b,c-d");
+
+    let template = template.with_output_separator("\n");
+    assert_eq!(template.output_separator(), Some("\n"));
+    assert_eq!(template.format("a b,c d").unwrap(), "a-b\nc-d");
+}
+
+#[test]
+fn test_output_separator_keeps_explicit_separators() {
+    let template = Template::parse("{split:,:..|join:+} {split:,:..|output_sep:/} {split:,:..}")
+        .unwrap()
+        .with_output_separator(";");
+    assert_eq!(template.format("a,b").unwrap(), "a+b a/b a;b");
+    assert_eq!(template.format("a").unwrap(), "a a a");
+}
+
+#[test]
+fn test_output_separator_not_used_inside_map() {
+    let mut template = Template::parse("{split:,:..|map:{split: :..}}").unwrap();
+    template.set_output_separator(Some("|"));
+    assert_eq!(template.format("a b,c d").unwrap(), "a b|c d");
+
+    template.set_output_separator(None);
+    assert_eq!(template.format("a b,c d").unwrap(), "a b,c d");
+}
+
+#[test]
+fn test_output_separator_in_debug_trace() {
+    let (sink, lines) = capture_sink();
+    let template = Template::parse("{split:,:..|sort}")
+        .unwrap()
+        .with_output_separator("~")
+        .with_debug(true)
+        .with_debug_format(DebugFormat::Json)
+        .with_debug_sink(sink);
+    assert_eq!(template.format("b,a").unwrap(), "a~b");
+
+    let lines = lines.lock().unwrap();
+    assert!(lines.iter().any(|l| l.contains(r#""join_sep":"~""#)));
+}
+
+#[test]
+fn test_memo_evicts_least_recently_used() {
+    let template = Template::parse("{split:,:..|map:{upper}|join:-}")
+        .unwrap()
+        .with_memo(2);
+    let stats = template.memo_stats().unwrap();
+    assert_eq!((stats.entries, stats.capacity, stats.misses), (0, 2, 0));
+
+    for (input, expected) in [("a,b", "A-B"), ("c", "C"), ("a,b", "A-B"), ("d", "D")] {
+        assert_eq!(template.format(input).unwrap(), expected);
+    }
+    // "c" was used least recently, so "d" replaced it
+    assert_eq!(template.format("a,b").unwrap(), "A-B");
+    assert_eq!(template.format("c").unwrap(), "C");
+
+    let stats = template.memo_stats().unwrap();
+    assert_eq!((stats.entries, stats.capacity), (2, 2));
+    assert_eq!((stats.hits, stats.misses, stats.evictions), (2, 4, 2));
+    assert_eq!(
+        stats.to_string(),
+        "memo: 2/2 entries, 2 hits, 4 misses, 2 evictions"
+    );
+
+    template.clear_memo();
+    assert_eq!(template.memo_stats().unwrap().entries, 0);
+    assert_eq!(template.memo_stats().unwrap().hits, 0);
+}
+
+#[test]
+fn test_memo_shared_by_clones_and_format_into() {
+    let template = Template::parse("{split:/:-1}").unwrap().with_memo(8);
+    let clone = template.clone();
+    template.format("a/b").unwrap();
+
+    let mut ctx = FormatContext::new();
+    let mut out = String::from("stale");
+    clone.format_into("a/b", &mut ctx, &mut out).unwrap();
+    assert_eq!(out, "b");
+    assert_eq!(template.memo_stats().unwrap().hits, 1);
+}
+
+#[test]
+fn test_memo_skips_errors_and_debug() {
+    let template = Template::parse("{roman}").unwrap().with_memo(8);
+    assert!(template.format("x").is_err());
+    assert!(template.format("x").is_err());
+    assert_eq!(template.memo_stats().unwrap().entries, 0);
+
+    let (sink, lines) = capture_sink();
+    let template = Template::parse("{upper}")
+        .unwrap()
+        .with_memo(8)
+        .with_debug(true)
+        .with_debug_sink(sink);
+    template.format("a").unwrap();
+    template.format("a").unwrap();
+    assert_eq!(template.memo_stats().unwrap().hits, 0);
+    assert!(!lines.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_memo_only_for_repeatable_templates() {
+    for text in [
+        "{split:,:..|shuffle}",
+        "{split:,:..|sample:2}",
+        "{line_no}: {upper}",
+        "{read_file}",
+        "{each:{split:,:..}:{file_name}}",
+    ] {
+        let template = Template::parse(text).unwrap().with_memo(8);
+        assert_eq!(template.memo_stats(), None, "{text}");
+    }
+    let template = Template::parse("{split:,:..|shuffle:42}")
+        .unwrap()
+        .with_memo(8);
+    assert!(template.memo_stats().is_some());
+    assert_eq!(
+        Template::parse("{upper}")
+            .unwrap()
+            .with_memo(0)
+            .memo_stats(),
+        None
+    );
+}
+
+#[test]
+fn test_memo_not_shared_after_output_settings_change() {
+    let template = Template::parse("{split:,:..}").unwrap().with_memo(8);
+    assert_eq!(template.format("a,b").unwrap(), "a,b");
+
+    let mut clone = template.clone();
+    clone.set_output_separator(Some(" "));
+    assert_eq!(clone.format("a,b").unwrap(), "a b");
+    let clone = clone.with_line_endings(LineEndings::Crlf);
+    assert_eq!(clone.format("a,b").unwrap(), "a b");
+    assert_eq!(template.format("a,b").unwrap(), "a,b");
+    assert_eq!(template.memo_stats().unwrap().hits, 1);
+}
+
+#[test]
+fn test_small_templates_match_full_rendering() {
+    let cases = [
+        ("Hello, world!", "ignored"),
+        ("", "ignored"),
+        ("Hello {upper}!", "bob"),
+        ("{split:,:..|sort}", "b,a"),
+        ("{}", "as is"),
+        ("[{split:/:-1}]", "a/b.txt"),
+        ("{input} and {input}", "x"),
+    ];
+    for (text, input) in cases {
+        let template = Template::parse(text).unwrap();
+        let expected = template.format_rich(input).unwrap().rendered().to_string();
+        assert_eq!(template.format(input).unwrap(), expected, "{text}");
+
+        let mut ctx = FormatContext::new();
+        let mut out = String::from("stale");
+        template.format_into(input, &mut ctx, &mut out).unwrap();
+        assert_eq!(out, expected, "{text}");
+    }
+
+    let template = Template::parse("n={roman}").unwrap();
+    let error = template.format("x").unwrap_err();
+    assert_eq!(error, template.format_rich("x").unwrap_err());
+    assert!(
+        error.ends_with("(in operation `roman` at chars 4..9)"),
+        "{error}"
+    );
+    let mut out = String::from("stale");
+    assert!(
+        template
+            .format_into("x", &mut FormatContext::new(), &mut out)
+            .is_err()
+    );
+    assert!(out.is_empty());
+}
+
+#[test]
+fn test_small_templates_honor_settings() {
+    let template = Template::parse("{split:,:..}!")
+        .unwrap()
+        .with_output_separator("+");
+    assert_eq!(template.format("a,b").unwrap(), "a+b!");
+
+    let template = Template::parse("{split:\\n:..|sort}")
+        .unwrap()
+        .with_line_endings(LineEndings::Crlf);
+    assert_eq!(template.format("b\r\na").unwrap(), "a\r\nb");
+
+    let template = Template::parse("{upper}").unwrap().with_limits(Limits {
+        max_output_len: Some(2),
+        ..Limits::default()
+    });
+    assert!(template.format("abc").is_err());
+
+    let template = Template::parse("{read_file}").unwrap();
+    assert!(template.format("Cargo.toml").is_err());
+}
+
+#[test]
+fn test_format_lossy_replaces_invalid_utf8() {
+    let template = Template::parse("{split:/:-1|upper}").unwrap();
+    assert_eq!(template.format_lossy(b"dir/file").unwrap(), "FILE");
+    assert_eq!(
+        template.format_lossy(b"dir/f\xffile").unwrap(),
+        "F\u{FFFD}ILE"
+    );
+}
+
+#[test]
+fn test_format_bytes_passes_bytes_through_strip_ansi() {
+    let template = Template::parse("> {strip_ansi}|{}").unwrap();
+    assert_eq!(
+        template.format_bytes(b"\x1b[31mred\xfe\x1b[0m").unwrap(),
+        b"> red\xfe|\x1b[31mred\xfe\x1b[0m"
+    );
+
+    // Valid UTF-8 is formatted normally
+    assert_eq!(
+        template
+            .format_bytes("\x1b[1mbold\x1b[0m é".as_bytes())
+            .unwrap(),
+        "> bold é|\x1b[1mbold\x1b[0m é".as_bytes()
+    );
+
+    // Other templates fall back to lossy formatting
+    let template = Template::parse("{strip_ansi|upper}").unwrap();
+    assert_eq!(
+        template.format_bytes(b"\x1b[31mred\xfe").unwrap(),
+        "RED\u{FFFD}".as_bytes()
+    );
+}
+
+#[test]
+fn test_variables_share_values_between_sections() {
+    let template = Template::parse(
+        r"Name: {set:name:regex_extract:^(\w+)} Id: {regex_extract:(\d+)$} Again: {get:name|upper}",
+    )
+    .unwrap();
+    assert_eq!(
+        template.format("bob 42").unwrap(),
+        "Name: bob Id: 42 Again: BOB"
+    );
+    // Values do not leak between format calls
+    assert_eq!(
+        template.format("amy 7").unwrap(),
+        "Name: amy Id: 7 Again: AMY"
+    );
+
+    // Lists are stored as rendered
+    let template =
+        Template::parse("{set:items:split:,:..|sort|join:-}|{get:items|split:-:-1}").unwrap();
+    assert_eq!(template.format("c,a,b").unwrap(), "a-b-c|c");
+
+    let rich = template.format_rich("b,a").unwrap();
+    assert_eq!(rich.template_output(0), Some("a-b"));
+    assert_eq!(rich.template_output(1), Some("b"));
+
+    let info = template.get_section_info();
+    assert_eq!(info[0].variable, Some(Variable::Set("items".to_string())));
+    assert_eq!(info[1].variable, None);
+    assert_eq!(info[2].variable, Some(Variable::Get("items".to_string())));
+}
+
+#[test]
+fn test_variables_in_structured_formatting() {
+    let template = Template::parse("{input=names|set:first:split:,:0} {get:first|upper}").unwrap();
+    assert_eq!(
+        template
+            .format_with_named_inputs(&[("names", &["ann,bo"])], &[" "])
+            .unwrap(),
+        "ann ANN"
+    );
+}
+
+#[test]
+fn test_variables_checked_at_parse() {
+    let err = Template::parse("{get:x} {set:x:upper}").unwrap_err();
+    assert_eq!(
+        err,
+        "Variable `x` is read in template section 1 before any section sets it"
+    );
+    let err = Template::parse("{get:x|upper}").unwrap_err();
+    assert!(err.contains("`x` is read in template section 1"), "{err}");
+
+    let err = Template::parse("{set:x:upper} {set:x:lower}").unwrap_err();
+    assert_eq!(err, "Variable `x` is set again in template section 2");
+
+    assert!(Template::parse("{input=0|get:x}").is_err());
+    assert!(Template::parse("{set:x}").is_err());
+
+    let err = TemplateBuilder::new().get("x").build().unwrap_err();
+    assert!(err.contains("Variable `x` is read"), "{err}");
+}
+
+#[test]
+fn test_template_builder_variables() {
+    let template = TemplateBuilder::new()
+        .set("user")
+        .split("@", Range::index(0))
+        .literal(" ")
+        .get("user")
+        .upper()
+        .build()
+        .unwrap();
+    assert_eq!(
+        template.template_string(),
+        "{set:user:split:@:0} {get:user|upper}"
+    );
+    assert_eq!(
+        template,
+        Template::parse("{set:user:split:@:0} {get:user|upper}").unwrap()
+    );
+    assert_eq!(template.format("ada@example.com").unwrap(), "ada ADA");
+}
+
+#[test]
+fn test_conditional_group_omitted_when_empty() {
+    let template =
+        Template::parse("Name: {split:,:0}[?, Email: {split:,:1|filter:@}] (end)").unwrap();
+    assert_eq!(
+        template.format("bob,b@x.io").unwrap(),
+        "Name: bob, Email: b@x.io (end)"
+    );
+    assert_eq!(template.format("bob,none").unwrap(), "Name: bob (end)");
+
+    // The group is kept when any of its sections has output
+    let template = Template::parse("[?<{split:,:0}|{split:,:1}>]").unwrap();
+    assert_eq!(template.format(",b").unwrap(), "<|b>");
+    assert_eq!(template.format(",").unwrap(), "");
+
+    // `]` outside of a group is literal text
+    let template = Template::parse("[{upper}]").unwrap();
+    assert_eq!(template.format("a").unwrap(), "[A]");
+
+    let template = Template::parse_with_delimiters("x[?:<<upper>>]", "<<", ">>").unwrap();
+    assert_eq!(template.format("").unwrap(), "x");
+    assert_eq!(template.format("a").unwrap(), "x:A");
+}
+
+#[test]
+fn test_conditional_group_rich_outputs() {
+    let template = Template::parse("{upper}[? - {lower}]!").unwrap();
+    let rich = template.format_rich("").unwrap();
+    assert_eq!(rich.rendered(), "!");
+    assert_eq!(rich.template_output(1), Some(""));
+    assert_eq!(rich.template_outputs()[1].rendered_range(), 0..0);
+
+    let rich = template.format_rich("Ab").unwrap();
+    assert_eq!(rich.rendered(), "AB - ab!");
+    assert_eq!(rich.template_output(1), Some("ab"));
+}
+
+#[test]
+fn test_each_section_formats_template_per_item() {
+    let template = Template::parse("Report:\n{each:{split:,:..}:- {upper}\n}").unwrap();
+    assert_eq!(template.format("a,b").unwrap(), "Report:\n- A\n- B\n");
+    assert_eq!(template.format("").unwrap(), "Report:\n- \n");
+
+    // The body is a full template, with groups and nested `each`
+    let template =
+        Template::parse("{each:{split:,:..|sort}:{split:=:0}[?={regex_extract:=(.*):1}];}")
+            .unwrap();
+    assert_eq!(template.format("b=2,a").unwrap(), "a;b=2;");
+    let template = Template::parse("{each:{split:;:..}:{each:{split:,:..}:<{}>}/}").unwrap();
+    assert_eq!(template.format("a,b;c").unwrap(), "<a><b>/<c>/");
+
+    // A string result is formatted once
+    let template = Template::parse("{each:{upper}:[{}]}").unwrap();
+    assert_eq!(template.format("ab").unwrap(), "[AB]");
+
+    let template = Template::parse_with_delimiters("<<each:{split:,:..}:(<<>>)>>", "<<", ">>");
+    assert_eq!(template.unwrap().format("a,b").unwrap(), "(a)(b)");
+}
+
+#[test]
+fn test_each_section_errors() {
+    let err = Template::parse("{upper|each:{}:x}").unwrap_err();
+    assert!(
+        err.contains("`each` must start a section: {each:{OPS}:TEMPLATE}"),
+        "{err}"
+    );
+    assert_eq!(
+        Template::parse("{each:{split:,:..}}").unwrap_err(),
+        "Expected `:` and a template after `each:{...}`"
+    );
+    assert_eq!(
+        Template::parse("{each:{split:,:..}:{sort}}").unwrap_err(),
+        "Error in `each` template: Type error in operation 1 (`sort`): `sort` can only be applied to lists, but its input is a string"
+    );
+    assert_eq!(
+        Template::parse("{each:{get:x}:{}}").unwrap_err(),
+        "The list of `each:{...}` cannot use `input=`, `set:`, `get:` or a placeholder"
+    );
+
+    // Access and limits cover the body
+    let template = Template::parse("{each:{split:,:..}:{read_file}}").unwrap();
+    assert!(template.reads_files());
+    assert!(template.format("Cargo.toml").is_err());
+    let template = template.with_fs_access(true);
+    assert!(template.format("Cargo.toml").unwrap().contains("[package]"));
+
+    let profile = SafetyProfile {
+        max_map_depth: 1,
+        ..SafetyProfile::default()
+    };
+    assert!(Template::parse_untrusted("{each:{split:,:..}:{upper}}", &profile).is_ok());
+    let err = Template::parse_untrusted("{each:{split:,:..}:{each:{}:{}}}", &profile).unwrap_err();
+    assert!(err.contains("`each` nested deeper than 1"), "{err}");
+}
+
+#[test]
+fn test_builder_each() {
+    let body = Template::parse("- {upper};").unwrap();
+    let template = TemplateBuilder::new()
+        .literal("Items: ")
+        .split(",", Range::all())
+        .each(body)
+        .literal("!")
+        .build()
+        .unwrap();
+    assert_eq!(
+        template.template_string(),
+        "Items: {each:{split:,:..}:- {upper};}!"
+    );
+    assert_eq!(template.format("a,b").unwrap(), "Items: - A;- B;!");
+}
+
+#[test]
+fn test_placeholders_read_input_and_context() {
+    let template = Template::parse("{split:,:0} {input_len} [{input|upper}]").unwrap();
+    assert_eq!(template.format("né,b").unwrap(), "né 4 [NÉ,B]");
+    assert_eq!(
+        template.format_rich("a").unwrap().template_output(1),
+        Some("1")
+    );
+
+    let template = Template::parse("{file_name}:{line_no}: {input}").unwrap();
+    let mut ctx = FormatContext::new();
+    ctx.set_file_name("list.txt");
+    let mut out = String::new();
+    for (index, line) in ["first", "second"].into_iter().enumerate() {
+        ctx.set_line_no(index + 1);
+        template.format_into(line, &mut ctx, &mut out).unwrap();
+        assert_eq!(out, format!("list.txt:{}: {line}", index + 1));
+    }
+
+    // `input=` is still an input reference
+    let template = Template::parse("{input=input_len|upper}").unwrap();
+    assert_eq!(
+        template
+            .format_with_named_inputs(&[("input_len", &["a"])], &[" "])
+            .unwrap(),
+        "A"
+    );
+
+    let info = Template::parse("{line_no|pad:3}")
+        .unwrap()
+        .get_section_info();
+    assert_eq!(info[0].placeholder, Some(Placeholder::LineNo));
+    assert_eq!(info[0].operations.as_ref().unwrap().len(), 1);
+}
+
+#[test]
+fn test_placeholder_errors() {
+    let template = Template::parse("{line_no}").unwrap();
+    assert_eq!(
+        template.format("a").unwrap_err(),
+        "`{line_no}` needs a line number; set one with FormatContext::set_line_no"
+    );
+    let template = Template::parse("[{file_name}]").unwrap();
+    assert_eq!(
+        template
+            .format_into("a", &mut FormatContext::new(), &mut String::new())
+            .unwrap_err(),
+        "`{file_name}` needs a file name; set one with FormatContext::set_file_name"
+    );
+    let template = Template::parse("{input_len}").unwrap();
+    assert_eq!(
+        template.format_with_inputs(&[&["a"]], &[" "]).unwrap_err(),
+        "`{input_len}` is not available in structured formatting"
+    );
+
+    assert!(Template::parse("{set:x:input}").is_err());
+    assert!(Template::parse("{upper|input}").is_err());
+    assert_eq!(
+        Template::from_sections(vec![Section::Template {
+            ops: Vec::new(),
+            input: Some(InputRef::Index(0)),
+            variable: None,
+            placeholder: Some(Placeholder::Input),
+        }])
+        .unwrap_err(),
+        "A placeholder section cannot use `input=`, `set:` or `get:`"
+    );
+}
+
+#[test]
+fn test_builder_placeholder() {
+    let template = TemplateBuilder::new()
+        .placeholder(Placeholder::InputLen)
+        .literal(" ")
+        .placeholder(Placeholder::Input)
+        .upper()
+        .build()
+        .unwrap();
+    assert_eq!(template.template_string(), "{input_len} {input|upper}");
+    assert_eq!(template.format("ab").unwrap(), "2 AB");
+}
+
+#[test]
+fn test_conditional_group_escaped_marker() {
+    let template = Template::parse("arr\\[?]{upper}").unwrap();
+    assert_eq!(template.format("hi").unwrap(), "arr[?]HI");
+    assert_eq!(template.canonical_string(), "arr\\[?]{upper}");
+    assert_eq!(
+        Template::parse(&template.canonical_string()).unwrap(),
+        template
+    );
+
+    // Inside a group the escaped marker is literal text too
+    let template = Template::parse("[?\\[?{upper}]").unwrap();
+    assert_eq!(template.format("a").unwrap(), "[?A");
+    assert_eq!(template.format("").unwrap(), "");
+
+    // Other backslashes in literal text are kept
+    let template = Template::parse("a\\b[?{upper}]").unwrap();
+    assert_eq!(template.format("c").unwrap(), "a\\bC");
+}
+
+#[test]
+fn test_conditional_group_errors() {
+    assert_eq!(
+        Template::parse("a [?b {upper}").unwrap_err(),
+        "Unclosed conditional group `[?`"
+    );
+    assert_eq!(
+        Template::parse("{upper}[?b]").unwrap_err(),
+        "Conditional group `[?...]` needs a template section"
+    );
+    assert_eq!(
+        Template::parse("[?{upper}[?{lower}]]").unwrap_err(),
+        "Conditional groups `[?...]` cannot be nested"
+    );
+
+    // Groups are part of template equality
+    assert_ne!(
+        Template::parse("a{upper}").unwrap(),
+        Template::parse("[?a{upper}]").unwrap()
+    );
+}
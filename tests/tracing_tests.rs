@@ -0,0 +1,175 @@
+#![cfg(feature = "tracing")]
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use string_pipeline::{DebugFormat, DebugSink, Template};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Metadata, Subscriber, span};
+
+/// An event with the names of the spans it was recorded in.
+#[derive(Debug)]
+struct RecordedEvent {
+    path: String,
+    fields: Vec<(String, String)>,
+}
+
+impl RecordedEvent {
+    fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Default)]
+struct Recorded {
+    span_names: Vec<String>,
+    stack: Vec<usize>,
+    events: Vec<RecordedEvent>,
+}
+
+/// A minimal subscriber that records span names and events.
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Recorded>>);
+
+#[derive(Default)]
+struct Fields(Vec<(String, String)>);
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.push((field.name().to_string(), value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .push((field.name().to_string(), format!("{value:?}")));
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, attrs: &span::Attributes<'_>) -> span::Id {
+        let mut recorded = self.0.lock().unwrap();
+        recorded
+            .span_names
+            .push(attrs.metadata().name().to_string());
+        span::Id::from_u64(recorded.span_names.len() as u64)
+    }
+
+    fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let mut recorded = self.0.lock().unwrap();
+        let path = recorded
+            .stack
+            .iter()
+            .map(|&idx| recorded.span_names[idx].as_str())
+            .collect::<Vec<_>>()
+            .join("/");
+        recorded.events.push(RecordedEvent {
+            path,
+            fields: fields.0,
+        });
+    }
+
+    fn enter(&self, id: &span::Id) {
+        self.0
+            .lock()
+            .unwrap()
+            .stack
+            .push(id.into_u64() as usize - 1);
+    }
+
+    fn exit(&self, _: &span::Id) {
+        self.0.lock().unwrap().stack.pop();
+    }
+}
+
+fn record(template: &Template, input: &str) -> (String, Recorded) {
+    let recorder = Recorder::default();
+    let output =
+        tracing::subscriber::with_default(recorder.clone(), || template.format(input).unwrap());
+    let recorded = std::mem::take(&mut *recorder.0.lock().unwrap());
+    (output, recorded)
+}
+
+#[test]
+fn test_tracing_spans_nest_operations() {
+    let template = Template::parse("{split:,:..|map:{upper}}")
+        .unwrap()
+        .with_debug(true)
+        .with_debug_format(DebugFormat::Tracing);
+
+    let (output, recorded) = record(&template, "a,b");
+    assert_eq!(output, "A,B");
+
+    for name in ["session", "pipeline", "operation", "map_item"] {
+        assert!(recorded.span_names.iter().any(|n| n == name), "{name}");
+    }
+
+    let operation = |keyword: &str| {
+        recorded
+            .events
+            .iter()
+            .filter(|e| e.field("message") == Some("operation"))
+            .find(|e| e.field("operation") == Some(keyword))
+            .unwrap_or_else(|| panic!("no operation event for {keyword}"))
+    };
+    assert_eq!(operation("map").path, "session/pipeline/operation");
+    assert_eq!(operation("map").field("output"), Some("List[\"A\", \"B\"]"));
+    assert_eq!(
+        operation("upper").path,
+        "session/pipeline/operation/map_item/pipeline/operation"
+    );
+
+    let items: Vec<_> = recorded
+        .events
+        .iter()
+        .filter(|e| e.field("message") == Some("map item end"))
+        .collect();
+    assert_eq!(items.len(), 2);
+    assert!(items.iter().all(|e| e.path.ends_with("operation/map_item")));
+    assert_eq!(items[1].field("output"), Some("B"));
+
+    assert!(recorded.events.iter().all(|e| !e.path.is_empty()));
+    assert!(recorded.stack.is_empty());
+}
+
+#[test]
+fn test_tracing_format_writes_no_lines() {
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let captured = Arc::clone(&lines);
+    let template = Template::parse("{split:,:0|upper}")
+        .unwrap()
+        .with_debug(true)
+        .with_debug_format(DebugFormat::Tracing)
+        .with_debug_sink(DebugSink::from_fn(move |line| {
+            captured.lock().unwrap().push(line.to_string());
+        }));
+
+    let (output, recorded) = record(&template, "x,y");
+    assert_eq!(output, "X");
+    assert!(!recorded.events.is_empty());
+    assert!(lines.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_tracing_requires_debug() {
+    let template = Template::parse("{split:,:..|map:{upper}}")
+        .unwrap()
+        .with_debug_format(DebugFormat::Tracing);
+
+    let (output, recorded) = record(&template, "a,b");
+    assert_eq!(output, "A,B");
+    assert!(recorded.span_names.is_empty());
+    assert!(recorded.events.is_empty());
+}